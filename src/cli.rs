@@ -0,0 +1,781 @@
+//
+// Subcommands to script lvm-tui, e.g. in provisioning: reports and a tree of
+// what is there, creating lvs. Same backends as the tui, nothing interactive.
+//
+// Table output is for people, sizes are rounded. JSON and CSV are for
+// scripts, sizes are in bytes.
+//
+
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand, ValueEnum};
+use serde_json::{Map, Value, json};
+
+use crate::lvm::capture;
+use crate::lvm::topology::{self, ExportFormat};
+use crate::lvm::{self, LvmBackend, LvmError, LvmExtraArg, LvmLvData, fmt_size};
+use crate::spec::{Plan, Spec};
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum ReportKind {
+    Vgs,
+    Pvs,
+    Lvs,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Browse and change lvm interactively, the default
+    Tui,
+    /// List vgs, pvs or lvs
+    Report {
+        kind: ReportKind,
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+        output: OutputFormat,
+        /// Only the ones in this vg
+        #[arg(long)]
+        vg: Option<String>,
+    },
+    /// Vgs with their pvs and lvs, sub lvs under the lv they are part of
+    Tree {
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+        output: OutputFormat,
+        /// Only this vg
+        #[arg(long)]
+        vg: Option<String>,
+    },
+    /// Create an lv, as the new lv form (F7) does
+    CreateLv(CreateLvArgs),
+    /// All vgs, pvs and lvs as one document, for inventory tools
+    Export {
+        /// json or yaml, from the path's extension if not given
+        #[arg(short, long, value_enum)]
+        format: Option<ExportFormat>,
+        /// Written to stdout if none
+        path: Option<PathBuf>,
+    },
+    /// Bundle reports, vg metadata, dm tables, lsblk and lvm.conf settings of
+    /// this host into a tar for support, browsable with --from-dir
+    Capture { path: PathBuf },
+    /// Print the lvm commands that bring vgs and lvs to a YAML or TOML spec
+    Plan { path: PathBuf },
+    /// Run the commands of plan, after asking
+    Apply {
+        path: PathBuf,
+        /// Do not ask
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Args)]
+pub struct CreateLvArgs {
+    vg: String,
+    lv: String,
+    /// Size with its unit, M, G, T, %FREE or %VG, e.g. 10G
+    #[arg(short = 'L', long)]
+    size: String,
+    /// linear, raid0, raid1, raid5, raid6, raid10, thin-pool or thin
+    #[arg(long = "type", default_value = "linear")]
+    segtype: String,
+    #[arg(long)]
+    stripes: Option<u32>,
+    /// e.g. 64k
+    #[arg(long)]
+    stripesize: Option<String>,
+    #[arg(long)]
+    mirrors: Option<u32>,
+    /// Pool of a thin lv
+    #[arg(long)]
+    thinpool: Option<String>,
+    /// Pvs to allocate from, any in the vg if none
+    #[arg(long = "pv")]
+    pvs: Vec<String>,
+    /// Print the lvcreate command, do not run it
+    #[arg(long)]
+    dry_run: bool,
+}
+
+//
+// Exit code for an error, lvm's own when it has one, else as in sysexits.h.
+//
+pub fn exit_code(e: &LvmError) -> u8 {
+    match e {
+        LvmError::CommandFailed {
+            code: Some(code), ..
+        } => u8::try_from(*code).ok().filter(|c| *c != 0).unwrap_or(1),
+        LvmError::CommandFailed { code: None, .. } => 1,
+        LvmError::BinaryNotFound(_) => 127,
+        LvmError::Usage(_) => 64,
+        LvmError::Parse { .. } => 65,
+        LvmError::Unsupported(_) | LvmError::Dbus(_) => 69,
+        LvmError::Io(_) => 74,
+        LvmError::LockContention(_) => 75,
+        LvmError::PermissionDenied(_) => 77,
+    }
+}
+
+//
+// Run a subcommand other than tui, output goes to 'out', answers to
+// questions come from 'input'.
+//
+pub fn run(
+    cmd: &Command,
+    lvm: &dyn LvmBackend,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> Result<(), LvmError> {
+    let text = match cmd {
+        Command::Tui => {
+            return Err(LvmError::Unsupported(
+                "tui is not a cli command".to_string(),
+            ));
+        }
+        Command::Report { kind, output, vg } => report(lvm, *kind, vg.as_deref())?.format(*output),
+        Command::Tree { output, vg } => tree(lvm, *output, vg.as_deref())?,
+        Command::CreateLv(args) => create_lv(lvm, args)?,
+        Command::Export { format, path } => {
+            let format = match (format, path) {
+                (Some(format), _) => *format,
+                (None, Some(path)) => ExportFormat::from_path(path),
+                (None, None) => ExportFormat::Json,
+            };
+            let text = topology::to_string(&topology::topology(lvm)?, format)?;
+            match path {
+                Some(path) => {
+                    std::fs::write(path, text).map_err(LvmError::Io)?;
+                    String::new()
+                }
+                None => text,
+            }
+        }
+        Command::Capture { path } => {
+            let manifest = capture::capture(path)?;
+            let mut text = format!(
+                "Captured {} of {} to {}\n",
+                manifest.entries.len() - manifest.failed().count(),
+                manifest.entries.len(),
+                path.display()
+            );
+            for entry in manifest.failed() {
+                let error = entry.error.as_deref().unwrap_or_default();
+                text += &format!("  left out {}: {}\n", entry.name, error.trim());
+            }
+            text
+        }
+        Command::Plan { path } => Plan::new(&Spec::load(path)?, lvm)?.text(),
+        Command::Apply { path, yes } => {
+            return apply(&Plan::new(&Spec::load(path)?, lvm)?, lvm, *yes, input, out);
+        }
+    };
+    out.write_all(text.as_bytes()).map_err(LvmError::Io)
+}
+
+//
+// Each step is told about as it is done, the first to fail stops the rest.
+//
+fn apply(
+    plan: &Plan,
+    lvm: &dyn LvmBackend,
+    yes: bool,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> Result<(), LvmError> {
+    out.write_all(plan.text().as_bytes())
+        .map_err(LvmError::Io)?;
+    if plan.steps.is_empty() {
+        return Ok(());
+    }
+    if !yes {
+        write!(out, "Apply {} changes? [y/N] ", plan.steps.len()).map_err(LvmError::Io)?;
+        out.flush().map_err(LvmError::Io)?;
+        let mut answer = String::new();
+        input.read_line(&mut answer).map_err(LvmError::Io)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            return writeln!(out, "Nothing changed.").map_err(LvmError::Io);
+        }
+    }
+    for step in &plan.steps {
+        step.apply(lvm)?;
+        writeln!(out, "Done: {}", step.command()).map_err(LvmError::Io)?;
+    }
+    Ok(())
+}
+
+enum Field {
+    Text(String),
+    // Bytes, rounded in tables.
+    Size(u64),
+    Num(u64),
+    // None if not applicable, e.g. sync% of a linear lv.
+    Percent(Option<f64>),
+}
+
+impl Field {
+    fn text(&self) -> String {
+        match self {
+            Field::Text(s) => s.clone(),
+            Field::Size(bytes) => fmt_size(*bytes),
+            Field::Num(n) => n.to_string(),
+            Field::Percent(p) => p.map(|p| format!("{p:.2}")).unwrap_or_default(),
+        }
+    }
+
+    fn raw(&self) -> String {
+        match self {
+            Field::Size(bytes) => bytes.to_string(),
+            _ => self.text(),
+        }
+    }
+
+    fn json(&self) -> Value {
+        match self {
+            Field::Text(s) => json!(s),
+            Field::Size(n) | Field::Num(n) => json!(n),
+            Field::Percent(p) => json!(p),
+        }
+    }
+
+    fn right_aligned(&self) -> bool {
+        !matches!(self, Field::Text(_))
+    }
+}
+
+struct Report {
+    header: Vec<&'static str>,
+    rows: Vec<Vec<Field>>,
+}
+
+impl Report {
+    fn format(&self, output: OutputFormat) -> String {
+        match output {
+            OutputFormat::Table => {
+                let texts: Vec<Vec<String>> = self
+                    .rows
+                    .iter()
+                    .map(|row| row.iter().map(Field::text).collect())
+                    .collect();
+                let widths: Vec<usize> = self
+                    .header
+                    .iter()
+                    .enumerate()
+                    .map(|(i, h)| {
+                        texts
+                            .iter()
+                            .map(|row| row[i].len())
+                            .chain([h.len()])
+                            .max()
+                            .unwrap_or(0)
+                    })
+                    .collect();
+                let line = |cells: Vec<String>, right: &dyn Fn(usize) -> bool| {
+                    let cells: Vec<String> = cells
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, c)| match right(i) {
+                            true => format!("{c:>w$}", w = widths[i]),
+                            false => format!("{c:<w$}", w = widths[i]),
+                        })
+                        .collect();
+                    cells.join("  ").trim_end().to_string() + "\n"
+                };
+                let right = |i: usize| self.rows.first().is_some_and(|row| row[i].right_aligned());
+                let header = self.header.iter().map(|h| h.to_string()).collect();
+                let mut text = line(header, &right);
+                for row in texts {
+                    text += &line(row, &right);
+                }
+                text
+            }
+            OutputFormat::Json => {
+                let rows: Vec<Value> = self
+                    .rows
+                    .iter()
+                    .map(|row| {
+                        let obj: Map<String, Value> = self
+                            .header
+                            .iter()
+                            .zip(row)
+                            .map(|(h, f)| (h.to_string(), f.json()))
+                            .collect();
+                        Value::Object(obj)
+                    })
+                    .collect();
+                serde_json::to_string_pretty(&rows).unwrap_or_default() + "\n"
+            }
+            OutputFormat::Csv => {
+                let mut text = self.header.join(",") + "\n";
+                for row in &self.rows {
+                    let cells: Vec<String> = row.iter().map(|f| csv_field(&f.raw())).collect();
+                    text += &(cells.join(",") + "\n");
+                }
+                text
+            }
+        }
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn in_vg(vg: Option<&str>, vg_name: &str) -> bool {
+    vg.is_none_or(|vg| vg == vg_name)
+}
+
+fn report(lvm: &dyn LvmBackend, kind: ReportKind, vg: Option<&str>) -> Result<Report, LvmError> {
+    let report = match kind {
+        ReportKind::Vgs => {
+            let lvs = lvm.get_lvs()?;
+            let mut rows = Vec::new();
+            for vg_name in lvm.get_vgs()?.iter().filter(|name| in_vg(vg, name)) {
+                let info = lvm.get_vg_info(vg_name)?;
+                let lv_count = lvs
+                    .iter()
+                    .filter(|lv| lv.vg_name == info.name && !lv.path.is_empty())
+                    .count();
+                rows.push(vec![
+                    Field::Text(info.name),
+                    Field::Size(info.size),
+                    Field::Size(info.free),
+                    Field::Num(info.pv_count),
+                    Field::Num(lv_count as u64),
+                    Field::Text(info.attr),
+                    Field::Text(info.uuid),
+                ]);
+            }
+            Report {
+                header: vec!["vg", "size", "free", "pvs", "lvs", "attr", "uuid"],
+                rows,
+            }
+        }
+        ReportKind::Pvs => Report {
+            header: vec!["pv", "vg", "size", "free", "attr", "uuid"],
+            rows: lvm
+                .get_pvs()?
+                .into_iter()
+                .filter(|pv| in_vg(vg, &pv.vg_name))
+                .map(|pv| {
+                    vec![
+                        Field::Text(pv.pv_name),
+                        Field::Text(pv.vg_name),
+                        Field::Size(pv.pv_size),
+                        Field::Size(pv.pv_free),
+                        Field::Text(pv.pv_attr),
+                        Field::Text(pv.pv_uuid),
+                    ]
+                })
+                .collect(),
+        },
+        ReportKind::Lvs => Report {
+            header: vec![
+                "lv", "vg", "size", "attr", "segtype", "pool", "origin", "data%", "sync%", "path",
+            ],
+            rows: lvm
+                .get_lvs()?
+                .into_iter()
+                .filter(|lv| in_vg(vg, &lv.vg_name))
+                .map(|lv| {
+                    vec![
+                        Field::Text(lv.lv_name),
+                        Field::Text(lv.vg_name),
+                        Field::Size(lv.size),
+                        Field::Text(lv.attr),
+                        Field::Text(lv.segtype),
+                        Field::Text(lv.pool_lv),
+                        Field::Text(lv.origin),
+                        Field::Percent(lv.data_percent),
+                        Field::Percent(lv.sync_percent),
+                        Field::Text(lv.path),
+                    ]
+                })
+                .collect(),
+        },
+    };
+    Ok(report)
+}
+
+struct Node {
+    kind: &'static str,
+    name: String,
+    size: u64,
+    // segtype of lvs, "" for vgs and pvs.
+    segtype: String,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn lv(lv: &LvmLvData, lvs: &[LvmLvData]) -> Self {
+        Self {
+            kind: "lv",
+            name: lv.lv_name.clone(),
+            size: lv.size,
+            segtype: lv.segtype.clone(),
+            children: sub_lvs(&lv.lv_name, lvs),
+        }
+    }
+
+    fn text(&self, prefix: &str, last: bool, top: bool, text: &mut String) {
+        let (branch, more) = match (top, last) {
+            (true, _) => ("", ""),
+            (false, true) => ("└─ ", "   "),
+            (false, false) => ("├─ ", "│  "),
+        };
+        *text += &format!("{prefix}{branch}{} {}", self.name, fmt_size(self.size));
+        if !self.segtype.is_empty() {
+            *text += &format!(" {}", self.segtype);
+        }
+        *text += "\n";
+        let prefix = format!("{prefix}{more}");
+        for (i, child) in self.children.iter().enumerate() {
+            child.text(&prefix, i + 1 == self.children.len(), false, text);
+        }
+    }
+
+    fn json(&self) -> Value {
+        let mut obj = Map::new();
+        obj.insert("type".to_string(), json!(self.kind));
+        obj.insert("name".to_string(), json!(self.name));
+        obj.insert("size".to_string(), json!(self.size));
+        if !self.segtype.is_empty() {
+            obj.insert("segtype".to_string(), json!(self.segtype));
+        }
+        if !self.children.is_empty() {
+            let children: Vec<Value> = self.children.iter().map(Node::json).collect();
+            obj.insert("children".to_string(), Value::Array(children));
+        }
+        Value::Object(obj)
+    }
+
+    // vg,type,name,parent,size,segtype
+    fn csv(&self, vg: &str, parent: &str, text: &mut String) {
+        let cells = [
+            vg,
+            self.kind,
+            &self.name,
+            parent,
+            &self.size.to_string(),
+            &self.segtype,
+        ];
+        let cells: Vec<String> = cells.iter().map(|c| csv_field(c)).collect();
+        *text += &(cells.join(",") + "\n");
+        for child in &self.children {
+            child.csv(vg, &self.name, text);
+        }
+    }
+}
+
+// Hidden lvs making up 'parent', rimages, cache pools and so on.
+fn sub_lvs(parent: &str, lvs: &[LvmLvData]) -> Vec<Node> {
+    lvs.iter()
+        .filter(|lv| lv.parent_lv == parent && lv.lv_name != parent)
+        .map(|lv| Node::lv(lv, lvs))
+        .collect()
+}
+
+fn tree(lvm: &dyn LvmBackend, output: OutputFormat, vg: Option<&str>) -> Result<String, LvmError> {
+    let pvs = lvm.get_pvs()?;
+    let all_lvs = lvm.get_lvs()?;
+    let mut vgs = Vec::new();
+    for vg_name in lvm.get_vgs()?.iter().filter(|name| in_vg(vg, name)) {
+        let info = lvm.get_vg_info(vg_name)?;
+        let lvs: Vec<LvmLvData> = all_lvs
+            .iter()
+            .filter(|lv| lv.vg_name == *vg_name)
+            .cloned()
+            .collect();
+        let mut children: Vec<Node> = pvs
+            .iter()
+            .filter(|pv| pv.vg_name == *vg_name)
+            .map(|pv| Node {
+                kind: "pv",
+                name: pv.pv_name.clone(),
+                size: pv.pv_size,
+                segtype: String::new(),
+                children: Vec::new(),
+            })
+            .collect();
+        children.extend(
+            lvs.iter()
+                .filter(|lv| lv.parent_lv.is_empty())
+                .map(|lv| Node::lv(lv, &lvs)),
+        );
+        vgs.push(Node {
+            kind: "vg",
+            name: info.name,
+            size: info.size,
+            segtype: String::new(),
+            children,
+        });
+    }
+
+    let text = match output {
+        OutputFormat::Table => {
+            let mut text = String::new();
+            for vg in &vgs {
+                vg.text("", true, true, &mut text);
+            }
+            text
+        }
+        OutputFormat::Json => {
+            let vgs: Vec<Value> = vgs.iter().map(Node::json).collect();
+            serde_json::to_string_pretty(&vgs).unwrap_or_default() + "\n"
+        }
+        OutputFormat::Csv => {
+            let mut text = "vg,type,name,parent,size,segtype\n".to_string();
+            for vg in &vgs {
+                vg.csv(&vg.name, "", &mut text);
+            }
+            text
+        }
+    };
+    Ok(text)
+}
+
+fn usage(msg: String) -> LvmError {
+    LvmError::Usage(msg)
+}
+
+fn split_size(size: &str) -> Result<(u64, String), LvmError> {
+    lvm::split_size_arg(size).ok_or_else(|| {
+        usage(format!(
+            "Size is a number and M, G, T, %FREE or %VG, not '{size}'"
+        ))
+    })
+}
+
+fn create_lv(lvm: &dyn LvmBackend, args: &CreateLvArgs) -> Result<String, LvmError> {
+    lvm::check_lv_name(&args.lv).map_err(usage)?;
+    let (size, unit) = split_size(&args.size)?;
+    if args.segtype == "thin" && unit.starts_with('%') {
+        return Err(usage(
+            "The virtual size of a thin lv can not be a percentage".to_string(),
+        ));
+    }
+    let mut extra = Vec::new();
+    let mut push = |opt: &str, value: Option<String>| {
+        if let Some(value) = value {
+            extra.push(LvmExtraArg {
+                opt: opt.to_string(),
+                value,
+            });
+        }
+    };
+    push("--stripes", args.stripes.map(|n| n.to_string()));
+    push("--stripesize", args.stripesize.clone());
+    push("--mirrors", args.mirrors.map(|n| n.to_string()));
+    push("--thinpool", args.thinpool.clone());
+
+    if args.dry_run {
+        let cmd = lvm::create_lv_args(
+            &args.lv,
+            &args.vg,
+            size,
+            &unit,
+            &args.segtype,
+            &args.pvs,
+            &extra,
+        );
+        return Ok(format!("lvcreate {}\n", cmd.join(" ")));
+    }
+    let msg = lvm.create_lv(
+        &args.lv,
+        &args.vg,
+        size,
+        &unit,
+        &args.segtype,
+        &args.pvs,
+        &extra,
+    )?;
+    Ok(format!("{}\n", msg.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use crate::cli::{Command, exit_code, run};
+    use crate::lvm::{LvmBackend, LvmError, fake::FakeBackend};
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(subcommand)]
+        command: Command,
+    }
+
+    fn cli(lvm: &dyn LvmBackend, args: &[&str]) -> Result<String, LvmError> {
+        cli_answering(lvm, args, "")
+    }
+
+    fn cli_answering(
+        lvm: &dyn LvmBackend,
+        args: &[&str],
+        answer: &str,
+    ) -> Result<String, LvmError> {
+        let cli = Cli::try_parse_from([&["lvm-tui"], args].concat()).unwrap();
+        let mut out = Vec::new();
+        run(&cli.command, lvm, &mut answer.as_bytes(), &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_cli() {
+        let gb = 1024 * 1024 * 1024;
+        let fake = FakeBackend::new();
+        fake.add_vg("vg01", &[("/dev/sda1", 10 * gb), ("/dev/sdb1", 10 * gb)]);
+        fake.add_vg("vg02", &[("/dev/sdc1", 10 * gb)]);
+
+        let out = cli(
+            &fake,
+            &["create-lv", "vg01", "lvr", "-L", "2G", "--type", "raid1"],
+        );
+        assert!(out.is_ok());
+
+        // Dry run changes nothing
+        let out = cli(
+            &fake,
+            &[
+                "create-lv",
+                "vg01",
+                "lvd",
+                "-L",
+                "1g",
+                "--pv",
+                "/dev/sdb1",
+                "--dry-run",
+            ],
+        )
+        .unwrap();
+        assert_eq!(out, "lvcreate --type linear -L 1G -n lvd vg01 /dev/sdb1\n");
+        assert!(fake.get_lvs().unwrap().iter().all(|lv| lv.lv_name != "lvd"));
+        let e = cli(&fake, &["create-lv", "vg01", "lvd", "-L", "1Q"]).unwrap_err();
+        assert_eq!(exit_code(&e), 64);
+        let e = cli(&fake, &["create-lv", "vg01", "_rimage", "-L", "1G"]).unwrap_err();
+        assert_eq!(exit_code(&e), 64);
+        let thin = [
+            "create-lv",
+            "vg01",
+            "thin1",
+            "-L",
+            "100%FREE",
+            "--type",
+            "thin",
+        ];
+        let e = cli(
+            &fake,
+            &[&thin[..], &["--thinpool", "pool0", "--dry-run"]].concat(),
+        )
+        .unwrap_err();
+        assert_eq!(exit_code(&e), 64);
+
+        let table = cli(&fake, &["report", "vgs"]).unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "vg      size    free  pvs  lvs  attr    uuid");
+        assert!(lines[1].starts_with("vg01  20.00G  15.99G    2    1  wz--n-"));
+        assert_eq!(lines.len(), 3);
+
+        let json = cli(&fake, &["report", "lvs", "-o", "json", "--vg", "vg01"]).unwrap();
+        let lvs: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let lvr = lvs
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|lv| lv["lv"] == "lvr")
+            .unwrap();
+        assert_eq!(lvr["size"], 2 * gb);
+        assert_eq!(lvr["segtype"], "raid1");
+
+        let csv = cli(&fake, &["report", "pvs", "--output", "csv"]).unwrap();
+        assert_eq!(csv.lines().next().unwrap(), "pv,vg,size,free,attr,uuid");
+        assert!(csv.contains(&format!("/dev/sdc1,vg02,{},{},", 10 * gb, 10 * gb)));
+
+        // Images under the raid
+        let tree = cli(&fake, &["tree", "--vg", "vg01"]).unwrap();
+        assert!(tree.starts_with("vg01 20.00G\n├─ /dev/sda1 10.00G\n"));
+        assert!(tree.contains("└─ lvr 2.00G raid1\n   ├─ [lvr_rmeta_0] "));
+        let csv = cli(&fake, &["tree", "-o", "csv"]).unwrap();
+        assert!(csv.contains("vg01,lv,[lvr_rimage_1],lvr,"));
+        assert!(csv.contains("vg02,pv,/dev/sdc1,vg02,"));
+
+        let yaml = cli(&fake, &["export", "--format", "yaml"]).unwrap();
+        assert!(yaml.starts_with("vgs:\n- name: vg01\n"));
+        assert!(yaml.contains("    parent: lvr\n"));
+        let json = cli(&fake, &["export"]).unwrap();
+        let doc: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(doc["vgs"][1]["pvs"][0]["name"], "/dev/sdc1");
+
+        // lvm's exit code is passed on
+        let e = cli(&fake, &["create-lv", "vgnone", "lvd", "-L", "1G"]).unwrap_err();
+        assert_eq!(exit_code(&e), 5);
+    }
+
+    #[test]
+    fn test_plan_apply() {
+        let gb = 1024 * 1024 * 1024;
+        let fake = FakeBackend::new();
+        fake.add_vg("vg01", &[("/dev/sda1", 10 * gb)]);
+        fake.add_pv("/dev/sdb1", 10 * gb);
+        let dir = std::env::temp_dir().join(format!("lvm-tui-spec-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let spec = dir.join("spec.toml");
+        std::fs::write(
+            &spec,
+            "[[vgs]]\nname = \"vg01\"\npvs = [\"/dev/sda1\", \"/dev/sdb1\"]\n\
+             [[vgs.lvs]]\nname = \"lvdata\"\nsize = \"12G\"\n",
+        )
+        .unwrap();
+        let path = spec.to_str().unwrap();
+
+        let plan = "vgextend vg01 /dev/sdb1\nlvcreate --type linear -L 12G -n lvdata vg01\n";
+        assert_eq!(cli(&fake, &["plan", path]).unwrap(), plan);
+
+        // Not without a yes
+        let out = cli_answering(&fake, &["apply", path], "n\n").unwrap();
+        assert_eq!(
+            out,
+            format!("{plan}Apply 2 changes? [y/N] Nothing changed.\n")
+        );
+        assert_eq!(fake.get_lvs().unwrap().len(), 0);
+
+        let out = cli_answering(&fake, &["apply", path], "y\n").unwrap();
+        assert!(out.ends_with("Done: lvcreate --type linear -L 12G -n lvdata vg01\n"));
+        assert_eq!(fake.get_lvs().unwrap()[0].size, 12 * gb);
+        assert_eq!(
+            cli(&fake, &["apply", "--yes", path]).unwrap(),
+            "# Nothing to do.\n"
+        );
+
+        // Not in the spec's format
+        std::fs::write(&spec, "vgs: []\n").unwrap();
+        let e = cli(&fake, &["plan", path]).unwrap_err();
+        assert_eq!(exit_code(&e), 65);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let failed = |code| LvmError::CommandFailed {
+            cmd: "lvcreate".to_string(),
+            code,
+            stderr: String::new(),
+        };
+        assert_eq!(exit_code(&failed(Some(3))), 3);
+        assert_eq!(exit_code(&failed(Some(0))), 1);
+        assert_eq!(exit_code(&failed(Some(-9))), 1);
+        assert_eq!(exit_code(&failed(None)), 1);
+        assert_eq!(exit_code(&LvmError::BinaryNotFound("lvs".to_string())), 127);
+        assert_eq!(exit_code(&LvmError::PermissionDenied(String::new())), 77);
+    }
+}
@@ -9,439 +9,2300 @@
 // Started with libblockdev (clib), but it turns out it spawns lvm cmds anyway.
 // Hence 'Command' in rust dropping deps to c-libs, bindgen. Commands used are lvs, pvs and vgs.
 //
+// Reports are requested with '--reportformat json_std' and deserialized into the
+// report model below, so fields containing ',' (seg_le_ranges, tags) are safe.
+//
+// The dbus option is in dbus.rs (lvmdbusd), selected with '--backend dbus'.
+//
+
+pub mod capture;
+pub mod dbus;
+pub mod devinfo;
+pub mod fake;
+pub mod replay;
+pub mod topology;
+pub mod watch;
+
+use serde::{Deserialize, Deserializer};
 
-use std::collections::HashMap;
+use crate::lvm::devinfo::BlockDev;
 
 const VGDISPLAY_BIN: &str = "/usr/sbin/vgs";
 const PVS_BIN: &str = "/usr/sbin/pvs";
 const LVS_BIN: &str = "/usr/sbin/lvs";
 const LVCREATE_BIN: &str = "/usr/sbin/lvcreate";
+const LVREMOVE_BIN: &str = "/usr/sbin/lvremove";
+const LVRESIZE_BIN: &str = "/usr/sbin/lvresize";
+const LVRENAME_BIN: &str = "/usr/sbin/lvrename";
+const LVCONVERT_BIN: &str = "/usr/sbin/lvconvert";
+const LVCHANGE_BIN: &str = "/usr/sbin/lvchange";
+const VGRENAME_BIN: &str = "/usr/sbin/vgrename";
+const PVCREATE_BIN: &str = "/usr/sbin/pvcreate";
+const VGCREATE_BIN: &str = "/usr/sbin/vgcreate";
+const VGEXTEND_BIN: &str = "/usr/sbin/vgextend";
+const VGREDUCE_BIN: &str = "/usr/sbin/vgreduce";
+const PVMOVE_BIN: &str = "/usr/sbin/pvmove";
+const VGCFGBACKUP_BIN: &str = "/usr/sbin/vgcfgbackup";
+const LVMCONFIG_BIN: &str = "/usr/sbin/lvmconfig";
+const LVM_BIN: &str = "/usr/sbin/lvm";
+const DMSETUP_BIN: &str = "/usr/sbin/dmsetup";
+const LSBLK_BIN: &str = "/usr/bin/lsblk";
 //const LVCREATE_BIN: &str = "/tmp/foo.sh";
 
+// Longest vg or lv name lvm accepts.
+const NAME_LEN_MAX: usize = 127;
+// lvm names its sub lvs <lv>_<suffix>_<n>, an lv can not be named like them.
+const LV_RESERVED_SUFFIXES: [&str; 16] = [
+    "_cdata", "_cmeta", "_corig", "_cpool", "_cvol", "_iorig", "_mimage", "_mlog", "_pmspare",
+    "_rimage", "_rmeta", "_tdata", "_tmeta", "_vdata", "_vorigin", "_wcorig",
+];
+const LV_RESERVED_PREFIXES: [&str; 2] = ["snapshot", "pvmove"];
+
+// Common args for all reports, sizes in bytes without unit suffix.
+const REPORT_ARGS: [&str; 6] = [
+    "--reportformat",
+    "json_std",
+    "--units",
+    "b",
+    "--nosuffix",
+    "-a",
+];
+// Fields read from each report, also what a capture has to contain.
+pub const VGS_FIELDS: &str = "vg_name,vg_size,vg_free,pv_count,vg_attr,vg_uuid";
+pub const PVS_FIELDS: &str = "pv_name,vg_name,pv_size,pv_free,pe_start,vg_extent_size,pv_pe_count,\
+     pv_pe_alloc_count,pv_attr,pv_uuid,pv_major,pv_minor";
+pub const LVS_FIELDS: &str = "lv_name,vg_name,lv_size,lv_attr,segtype,lv_uuid,stripes,data_stripes,lv_parent,\
+     seg_le_ranges,lv_path,lv_dm_path,lv_kernel_major,lv_kernel_minor,sync_percent,\
+     lv_health_status,raid_sync_action,raid_mismatch_count,lv_tags,lv_time,lv_host,origin,snap_percent,pool_lv,data_percent,\
+     metadata_percent,cache_mode,cache_policy,cache_total_blocks,cache_used_blocks,\
+     cache_dirty_blocks,cache_read_hits,cache_read_misses,cache_write_hits,\
+     cache_write_misses,writecache_total_blocks,writecache_free_blocks,\
+     writecache_writeback_blocks,move_pv,copy_percent";
+
 pub struct LvmExtraArg {
     pub opt: String,
     pub value: String,
 }
 
-pub struct LvmPVData {
-    pub pv_name: String,
-    pub vg_name: String,
+//
+// A long operation the backend waits for, e.g. an lvmdbusd job, by its path.
+//
+#[derive(Clone)]
+pub struct LvmBackendJob {
+    pub path: String,
+    pub percent: f64,
+    pub complete: bool,
+}
+
+//
+// Size for lvresize, absolute ('20G') or relative to the current size ('+10G',
+// '-50%FREE'). size_unit as for create_lv.
+//
+#[derive(Clone, Debug, PartialEq)]
+pub struct LvmResize {
+    pub sign: Option<char>,
+    pub size: u64,
+    pub size_unit: String,
+    pub resizefs: bool,
+}
+
+//
+// COW snapshot for lvcreate -s, size_unit as for create_lv or a percentage
+// ('%ORIGIN', '%FREE', '%VG').
+//
+#[derive(Clone, Debug, PartialEq)]
+pub struct LvmSnapshot {
+    pub name: String,
+    pub size: u64,
+    pub size_unit: String,
+}
+
+//
+// Fast lv to cache an lv with, see lvmcache(7). It is created on 'fast_pv' and
+// then attached with lvconvert. Mode and policy are for dm-cache only, "" for
+// lvm's default.
+//
+#[derive(Clone, Debug, PartialEq)]
+pub struct LvmCache {
+    pub name: String,
+    pub cache_type: LvmCacheType,
+    pub fast_pv: String,
+    pub size: u64,
+    pub size_unit: String,
+    pub cache_mode: String,
+    pub cache_policy: String,
+}
+
+//
+// dm-cache takes a cache pool (separate data and metadata) or a cachevol,
+// dm-writecache only a cachevol.
+//
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LvmCacheType {
+    CachePool,
+    CacheVol,
+    Writecache,
+}
+
+impl LvmCacheType {
+    pub const ALL: [LvmCacheType; 3] = [
+        LvmCacheType::CachePool,
+        LvmCacheType::CacheVol,
+        LvmCacheType::Writecache,
+    ];
+
+    pub fn text(&self) -> &'static str {
+        match self {
+            LvmCacheType::CachePool => "cache pool",
+            LvmCacheType::CacheVol => "cachevol",
+            LvmCacheType::Writecache => "writecache",
+        }
+    }
+
+    // Segtype of the cached lv.
+    pub fn segtype(&self) -> &'static str {
+        match self {
+            LvmCacheType::Writecache => "writecache",
+            _ => "cache",
+        }
+    }
+}
+
+//
+// Scrubbing of a raid lv. Check counts mismatches between images, repair also
+// rewrites them.
+//
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LvmSyncAction {
+    Check,
+    Repair,
+}
+
+impl LvmSyncAction {
+    // As in raid_sync_action and 'lvchange --syncaction'.
+    pub fn text(&self) -> &'static str {
+        match self {
+            LvmSyncAction::Check => "check",
+            LvmSyncAction::Repair => "repair",
+        }
+    }
+}
+
+//
+// Segtype to convert an lv to with lvconvert, see lvmraid(7). Mirrors for
+// raid1, mirror and raid10, stripes for the striped segtypes, None for lvm's
+// default.
+//
+#[derive(Clone, Debug, PartialEq)]
+pub struct LvmConvert {
+    pub segtype: String,
+    pub mirrors: Option<u64>,
+    pub stripes: Option<u64>,
+}
+
+//
+// pvcreate options, None for lvm's default. A pv with 0 metadata copies
+// relies on the other pvs of its vg for the metadata. Data alignment in bytes.
+//
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LvmPvCreate {
+    pub metadata_copies: Option<u64>,
+    pub data_alignment: Option<u64>,
+}
+
+//
+// vgcreate options, extent size in bytes. None and AllocPolicy::Unset for
+// lvm's default (4MiB, normal).
+//
+#[derive(Clone, Debug, PartialEq)]
+pub struct LvmVgCreate {
+    pub extent_size: Option<u64>,
+    pub alloc: AllocPolicy,
+}
+
+//
+// pvmove of src_pv, or of the extents first..=last on it. Only those of lv_name
+// if not "". None dest_pvs lets lvm pick from the other pvs of the vg.
+//
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LvmPvMove {
+    pub src_pv: String,
+    pub pe_range: Option<(u64, u64)>,
+    pub lv_name: String,
+    pub dest_pvs: Vec<String>,
+}
+
+//
+// How a segtype lays out an lv: number of images (sub lvs, 0 for linear and
+// striped), stripes the data is spread on and if each image has a metadata
+// sub lv. Each image holds size / data_stripes.
+//
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LvmLayout {
+    pub images: u64,
+    pub data_stripes: u64,
+    pub meta: bool,
+}
+
+impl LvmLayout {
+    // Stripes and mirrors default as for lvcreate.
+    pub fn new(segtype: &str, stripes: Option<u64>, mirrors: Option<u64>) -> Option<Self> {
+        let stripes = stripes.unwrap_or(if segtype == "raid6" { 3 } else { 2 });
+        let mirrors = mirrors.unwrap_or(1);
+        let (images, data_stripes, meta) = match segtype {
+            "linear" => (0, 1, false),
+            "striped" => (0, stripes, false),
+            "mirror" => (mirrors + 1, 1, false),
+            "raid0" => (stripes, stripes, false),
+            "raid1" => (mirrors + 1, 1, true),
+            "raid10" => (stripes * (mirrors + 1), stripes, true),
+            "raid4" | "raid5" => (stripes + 1, stripes, true),
+            "raid6" => (stripes + 2, stripes, true),
+            _ => return None,
+        };
+        Some(Self {
+            images,
+            data_stripes,
+            meta,
+        })
+    }
+
+    // Of an existing lv, lvs counts the images of raid and mirror in stripes.
+    pub fn of_lv(lv: &LvmLvData) -> Self {
+        let mirrored = lv.segtype.starts_with("raid") || lv.segtype == "mirror";
+        Self {
+            images: if mirrored { u64::from(lv.stripes) } else { 0 },
+            data_stripes: u64::from(lv.data_stripes.max(1)),
+            meta: lv.segtype.starts_with("raid") && lv.segtype != "raid0",
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct LvmPVData {
+    pub pv_name: String,
+    pub vg_name: String,
+    pub pv_size: u64,  // bytes
+    pub pv_free: u64,  // bytes
+    pub pe_start: u64, // bytes, offset of first extent
+    pub pe_size: u64,  // bytes, extent size of vg, 0 if not in vg
+    pub pe_count: u64,
+    pub pe_alloc_count: u64,
+    pub pv_attr: String,
+    pub pv_uuid: String,
+    // Device numbers, None if device is missing.
+    pub major: Option<u32>,
+    pub minor: Option<u32>,
+}
+
+#[derive(Clone, Default)]
+pub struct LvmLvData {
+    pub lv_name: String,
+    pub vg_name: String,
+    pub size: u64, // bytes
+    pub attr: String,
+    pub segtype: String,
+    pub uuid: String,
+    pub lv_segs: Vec<LvmlvSegData>,
+    pub stripes: u16,
+    pub data_stripes: u16,
+    pub parent_lv: String,
+    pub path: String,    // /dev/vg/lv, "" for hidden lvs
+    pub dm_path: String, // /dev/mapper/vg-lv
+    // Kernel device numbers, None if not active.
+    pub kernel_major: Option<u32>,
+    pub kernel_minor: Option<u32>,
+    // raid/mirror sync, None if not applicable.
+    pub sync_percent: Option<f64>,
+    pub health_status: String,
+    // raid scrubbing, "idle", "check", "repair", "recover", ... "" if not raid.
+    // Mismatches are counted by the last check, None if not raid.
+    pub raid_sync_action: String,
+    pub raid_mismatch_count: Option<u64>,
+    pub tags: Vec<String>,
+    pub time: String,   // creation time
+    pub host: String,   // created on host
+    pub origin: String, // origin lv of a snapshot, "" if not a snapshot
+    // Used part of a snapshot's cow space, None if not a snapshot.
+    pub snap_percent: Option<f64>,
+    pub pool_lv: String, // thin pool of a thin lv, "" if not thin
+    // Used part of a thin pool's data and metadata, or of a thin lv's virtual size.
+    // None if not thin.
+    pub data_percent: Option<f64>,
+    pub metadata_percent: Option<f64>,
+    // dm-cache of a cached lv, "" and None if not cached. Blocks are cache
+    // chunks, hits and misses are counted since activation.
+    pub cache_mode: String,
+    pub cache_policy: String,
+    pub cache_total_blocks: Option<u64>,
+    pub cache_used_blocks: Option<u64>,
+    pub cache_dirty_blocks: Option<u64>,
+    pub cache_read_hits: Option<u64>,
+    pub cache_read_misses: Option<u64>,
+    pub cache_write_hits: Option<u64>,
+    pub cache_write_misses: Option<u64>,
+    // dm-writecache, None if not a writecache.
+    pub writecache_total_blocks: Option<u64>,
+    pub writecache_free_blocks: Option<u64>,
+    pub writecache_writeback_blocks: Option<u64>,
+    // Of a pvmove lv, e.g. [pvmove0]: the pv moved from and how much of it is
+    // copied. "" and None if not a pvmove.
+    pub move_pv: String,
+    pub copy_percent: Option<f64>,
+}
+
+#[derive(Clone)]
+pub struct LvmlvSegData {
+    pub pvdev: String,
+    pub pv_start_pe: u64,
+    pub size_pe: u64,
+}
+
+#[derive(Clone, Default)]
+pub struct LvmVgData {
+    pub name: String,
+    pub free: u64, // bytes
+    pub size: u64, // bytes
+    pub pv_count: u64,
+    pub attr: String,
+    pub uuid: String,
+}
+
+//
+// Decoded lv_attr/vg_attr/pv_attr, see lvs(8), vgs(8) and pvs(8). Each position
+// is a char, '-' if not set. Unknown chars are kept as Other so nothing is lost.
+//
+
+// How much attention a flag needs in the ui.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttrLevel {
+    Normal,
+    Info,
+    Warning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttrFlag {
+    pub text: &'static str,
+    pub level: AttrLevel,
+}
+
+//
+// Enum for one attr position, Unset for '-'. Variants get a text and a level.
+//
+macro_rules! attr_enum {
+    ($name:ident { $($variant:ident = $c:literal, $text:literal, $level:ident;)* }) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum $name {
+            $($variant,)*
+            Unset,
+            Other(char),
+        }
+
+        impl $name {
+            pub fn from_char(c: char) -> Self {
+                match c {
+                    $($c => $name::$variant,)*
+                    '-' => $name::Unset,
+                    c => $name::Other(c),
+                }
+            }
+
+            pub fn to_char(&self) -> char {
+                match self {
+                    $($name::$variant => $c,)*
+                    $name::Unset => '-',
+                    $name::Other(c) => *c,
+                }
+            }
+
+            pub fn text(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $text,)*
+                    $name::Unset => "-",
+                    $name::Other(_) => "unknown",
+                }
+            }
+
+            pub fn level(&self) -> AttrLevel {
+                match self {
+                    $($name::$variant => AttrLevel::$level,)*
+                    $name::Unset => AttrLevel::Normal,
+                    $name::Other(_) => AttrLevel::Warning,
+                }
+            }
+
+            pub fn flag(&self) -> Option<AttrFlag> {
+                match self {
+                    $name::Unset => None,
+                    _ => Some(AttrFlag {
+                        text: self.text(),
+                        level: self.level(),
+                    }),
+                }
+            }
+        }
+    };
+}
+
+attr_enum!(LvVolumeType {
+    Cache = 'C', "cache", Normal;
+    Mirrored = 'm', "mirrored", Normal;
+    MirroredNoSync = 'M', "mirrored, no initial sync", Info;
+    Origin = 'o', "origin", Normal;
+    OriginMerging = 'O', "origin, merging snapshot", Info;
+    Raid = 'r', "raid", Normal;
+    RaidNoSync = 'R', "raid, no initial sync", Info;
+    Snapshot = 's', "snapshot", Normal;
+    MergingSnapshot = 'S', "merging snapshot", Info;
+    PvMove = 'p', "pvmove", Info;
+    Virtual = 'v', "virtual", Normal;
+    // also (i)ntegrity, lvm uses the same char
+    Image = 'i', "image", Normal;
+    ImageOutOfSync = 'I', "image out of sync", Warning;
+    MirrorLog = 'l', "mirror log", Normal;
+    UnderConversion = 'c', "under conversion", Info;
+    Thin = 'V', "thin", Normal;
+    ThinPool = 't', "thin pool", Normal;
+    ThinPoolData = 'T', "thin pool data", Normal;
+    VdoPool = 'd', "vdo pool", Normal;
+    VdoPoolData = 'D', "vdo pool data", Normal;
+    Metadata = 'e', "metadata", Normal;
+});
+
+attr_enum!(LvPermissions {
+    Writeable = 'w', "writeable", Normal;
+    ReadOnly = 'r', "read-only", Info;
+    ReadOnlyActivation = 'R', "read-only activation", Info;
+});
+
+// Upper case in lv_attr means locked.
+attr_enum!(AllocPolicy {
+    Anywhere = 'a', "anywhere", Normal;
+    Contiguous = 'c', "contiguous", Normal;
+    Inherited = 'i', "inherited", Normal;
+    Cling = 'l', "cling", Normal;
+    Normal = 'n', "normal", Normal;
+});
+
+impl AllocPolicy {
+    // Policies a vg can have, inherited is for lvs only.
+    pub const VG: [AllocPolicy; 4] = [
+        AllocPolicy::Normal,
+        AllocPolicy::Contiguous,
+        AllocPolicy::Cling,
+        AllocPolicy::Anywhere,
+    ];
+}
+
+attr_enum!(LvState {
+    Active = 'a', "active", Normal;
+    Historical = 'h', "historical", Info;
+    Suspended = 's', "suspended", Warning;
+    InvalidSnapshot = 'I', "invalid snapshot", Warning;
+    InvalidSuspendedSnapshot = 'S', "invalid suspended snapshot", Warning;
+    MergeFailed = 'm', "snapshot merge failed", Warning;
+    SuspendedMergeFailed = 'M', "suspended snapshot merge failed", Warning;
+    NoTable = 'd', "device present without tables", Warning;
+    InactiveTable = 'i', "inactive table", Warning;
+    CheckNeeded = 'c', "thin pool check needed", Warning;
+    SuspendedCheckNeeded = 'C', "suspended thin pool check needed", Warning;
+    Unknown = 'X', "state unknown", Warning;
+});
+
+attr_enum!(LvTargetType {
+    Cache = 'C', "cache target", Normal;
+    Mirror = 'm', "mirror target", Normal;
+    Raid = 'r', "raid target", Normal;
+    Snapshot = 's', "snapshot target", Normal;
+    Thin = 't', "thin target", Normal;
+    Unknown = 'u', "unknown target", Warning;
+    Virtual = 'v', "virtual target", Normal;
+});
+
+attr_enum!(LvHealth {
+    Partial = 'p', "partial", Warning;
+    Unknown = 'X', "health unknown", Warning;
+    RefreshNeeded = 'r', "refresh needed", Warning;
+    Mismatches = 'm', "mismatches exist", Warning;
+    WriteMostly = 'w', "writemostly", Info;
+    Failed = 'F', "failed", Warning;
+    OutOfData = 'D', "out of data space", Warning;
+    MetadataReadOnly = 'M', "metadata read only", Warning;
+    Error = 'E', "error", Warning;
+});
+
+//
+// lv_attr, e.g. 'rwi-aor---'
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LvAttr {
+    pub volume_type: LvVolumeType,
+    pub permissions: LvPermissions,
+    pub allocation: AllocPolicy,
+    pub allocation_locked: bool,
+    pub fixed_minor: bool,
+    pub state: LvState,
+    // None if unknown ('X').
+    pub open: Option<bool>,
+    pub target_type: LvTargetType,
+    pub zero: bool,
+    pub health: LvHealth,
+    pub skip_activation: bool,
+}
+
+// Char n of attr, '-' if attr is short (older lvm prints fewer chars).
+fn attr_char(attr: &str, n: usize) -> char {
+    attr.chars().nth(n).unwrap_or('-')
+}
+
+impl LvAttr {
+    pub fn decode(attr: &str) -> Self {
+        let alloc = attr_char(attr, 2);
+        let open = match attr_char(attr, 5) {
+            'o' => Some(true),
+            'X' => None,
+            _ => Some(false),
+        };
+        Self {
+            volume_type: LvVolumeType::from_char(attr_char(attr, 0)),
+            permissions: LvPermissions::from_char(attr_char(attr, 1)),
+            allocation: AllocPolicy::from_char(alloc.to_ascii_lowercase()),
+            allocation_locked: alloc.is_ascii_uppercase(),
+            fixed_minor: attr_char(attr, 3) == 'm',
+            state: LvState::from_char(attr_char(attr, 4)),
+            open,
+            target_type: LvTargetType::from_char(attr_char(attr, 6)),
+            zero: attr_char(attr, 7) == 'z',
+            health: LvHealth::from_char(attr_char(attr, 8)),
+            skip_activation: attr_char(attr, 9) == 'k',
+        }
+    }
+
+    //
+    // Level of each char in attr, for coloring the raw string.
+    //
+    pub fn levels(&self) -> [AttrLevel; 10] {
+        let level = |set: bool, level| if set { level } else { AttrLevel::Normal };
+        [
+            self.volume_type.level(),
+            self.permissions.level(),
+            level(self.allocation_locked, AttrLevel::Info),
+            AttrLevel::Normal,
+            self.state.level(),
+            level(self.open.is_none(), AttrLevel::Warning),
+            self.target_type.level(),
+            AttrLevel::Normal,
+            self.health.level(),
+            level(self.skip_activation, AttrLevel::Info),
+        ]
+    }
+
+    pub fn is_active(&self) -> bool {
+        !matches!(self.state, LvState::Unset | LvState::Historical)
+    }
+
+    //
+    // Set flags in attr order, inactive and closed are shown too.
+    //
+    pub fn flags(&self) -> Vec<AttrFlag> {
+        let normal = |text| AttrFlag {
+            text,
+            level: AttrLevel::Normal,
+        };
+        let mut flags: Vec<AttrFlag> = [
+            self.volume_type.flag(),
+            self.permissions.flag(),
+            self.allocation.flag(),
+            self.allocation_locked.then_some(AttrFlag {
+                text: "allocation locked",
+                level: AttrLevel::Info,
+            }),
+            self.fixed_minor.then_some(normal("fixed minor")),
+            Some(self.state.flag().unwrap_or(normal("inactive"))),
+            match self.open {
+                Some(true) => Some(normal("open")),
+                Some(false) => None,
+                None => Some(AttrFlag {
+                    text: "open unknown",
+                    level: AttrLevel::Warning,
+                }),
+            },
+            self.target_type.flag(),
+            self.zero.then_some(normal("zero")),
+            self.health.flag(),
+            self.skip_activation.then_some(AttrFlag {
+                text: "skip activation",
+                level: AttrLevel::Info,
+            }),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        flags.dedup();
+        flags
+    }
+}
+
+//
+// vg_attr, e.g. 'wz--n-'
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VgAttr {
+    pub writeable: bool,
+    pub resizeable: bool,
+    pub exported: bool,
+    pub partial: bool,
+    pub allocation: AllocPolicy,
+    pub clustered: bool,
+    pub shared: bool,
+}
+
+impl VgAttr {
+    pub fn decode(attr: &str) -> Self {
+        Self {
+            writeable: attr_char(attr, 0) == 'w',
+            resizeable: attr_char(attr, 1) == 'z',
+            exported: attr_char(attr, 2) == 'x',
+            partial: attr_char(attr, 3) == 'p',
+            allocation: AllocPolicy::from_char(attr_char(attr, 4)),
+            clustered: attr_char(attr, 5) == 'c',
+            shared: attr_char(attr, 5) == 's',
+        }
+    }
+
+    pub fn flags(&self) -> Vec<AttrFlag> {
+        let flag = |text, level| AttrFlag { text, level };
+        [
+            Some(if self.writeable {
+                flag("writeable", AttrLevel::Normal)
+            } else {
+                flag("read-only", AttrLevel::Info)
+            }),
+            self.resizeable
+                .then_some(flag("resizeable", AttrLevel::Normal)),
+            self.exported.then_some(flag("exported", AttrLevel::Info)),
+            self.partial.then_some(flag("partial", AttrLevel::Warning)),
+            self.allocation.flag(),
+            self.clustered
+                .then_some(flag("clustered", AttrLevel::Normal)),
+            self.shared.then_some(flag("shared", AttrLevel::Normal)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+//
+// pv_attr, e.g. 'a--'
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PvAttr {
+    pub allocatable: bool,
+    pub duplicate: bool,
+    pub used: bool,
+    pub exported: bool,
+    pub missing: bool,
+}
+
+impl PvAttr {
+    pub fn decode(attr: &str) -> Self {
+        let first = attr_char(attr, 0);
+        Self {
+            allocatable: first == 'a',
+            duplicate: first == 'd',
+            used: first == 'u',
+            exported: attr_char(attr, 1) == 'x',
+            missing: attr_char(attr, 2) == 'm',
+        }
+    }
+
+    pub fn flags(&self) -> Vec<AttrFlag> {
+        let flag = |text, level| AttrFlag { text, level };
+        [
+            Some(if self.allocatable {
+                flag("allocatable", AttrLevel::Normal)
+            } else {
+                flag("not allocatable", AttrLevel::Info)
+            }),
+            self.duplicate
+                .then_some(flag("duplicate", AttrLevel::Warning)),
+            self.used.then_some(flag("used", AttrLevel::Info)),
+            self.exported.then_some(flag("exported", AttrLevel::Info)),
+            self.missing.then_some(flag("missing", AttrLevel::Warning)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+//
+// Errors from running/parsing lvm commands. Returned by all public functions so
+// the ui can show them instead of tearing down the terminal.
+//
+#[derive(Debug)]
+pub enum LvmError {
+    // Binary (path) does not exist.
+    BinaryNotFound(String),
+    // Command ran but exited with non-zero, stderr is kept as is.
+    CommandFailed {
+        cmd: String,
+        code: Option<i32>,
+        stderr: String,
+    },
+    // Output could not be parsed, 'line' is the offending line of output.
+    Parse {
+        msg: String,
+        line: String,
+    },
+    PermissionDenied(String),
+    // Another lvm command holds the vg/global lock.
+    LockContention(String),
+    Io(std::io::Error),
+    // Operation not available in this backend.
+    Unsupported(String),
+    // Talking to lvmdbusd failed, e.g. not running.
+    Dbus(String),
+    // What was asked for makes no sense, e.g. a size without unit. Nothing ran.
+    Usage(String),
+}
+
+impl std::fmt::Display for LvmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LvmError::BinaryNotFound(bin) => write!(f, "lvm binary not found: {bin}"),
+            LvmError::CommandFailed { cmd, code, stderr } => match code {
+                Some(code) => write!(f, "{cmd} failed ({code}): {}", stderr.trim()),
+                None => write!(f, "{cmd} failed: {}", stderr.trim()),
+            },
+            LvmError::Parse { msg, line } => write!(f, "{msg}, at: '{}'", line.trim()),
+            LvmError::PermissionDenied(msg) => write!(f, "permission denied: {}", msg.trim()),
+            LvmError::LockContention(msg) => write!(f, "lvm lock busy: {}", msg.trim()),
+            LvmError::Io(e) => write!(f, "{e}"),
+            LvmError::Unsupported(msg) => write!(f, "not supported: {msg}"),
+            LvmError::Dbus(msg) => write!(f, "lvmdbusd: {msg}"),
+            LvmError::Usage(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for LvmError {}
+
+impl LvmError {
+    fn parse<M: Into<String>>(msg: M, line: &str) -> Self {
+        LvmError::Parse {
+            msg: msg.into(),
+            line: line.to_string(),
+        }
+    }
+
+    //
+    // Classify a non-zero exit from its stderr, lvm uses exit code 5 for everything.
+    //
+    pub(crate) fn from_failed_cmd(cmd: &str, code: Option<i32>, stderr: String) -> Self {
+        let lower = stderr.to_lowercase();
+        if lower.contains("permission denied") || lower.contains("must be root") {
+            LvmError::PermissionDenied(stderr)
+        } else if lower.contains("lock")
+            && (lower.contains("failed") || lower.contains("can't get"))
+        {
+            LvmError::LockContention(stderr)
+        } else {
+            LvmError::CommandFailed {
+                cmd: cmd.to_string(),
+                code,
+                stderr,
+            }
+        }
+    }
+}
+
+//
+// Everything the ui needs from lvm, queries and mutations. CmdBackend runs the
+// lvm binaries, fake::FakeBackend keeps an in-memory lvm for tests and demo,
+// replay::ReplayBackend reads captured reports.
+//
+pub trait LvmBackend: Send + Sync {
+    fn get_vgs(&self) -> Result<Vec<String>, LvmError>;
+    fn get_vg_info(&self, vg_name: &str) -> Result<LvmVgData, LvmError>;
+    fn get_pvs(&self) -> Result<Vec<LvmPVData>, LvmError>;
+    fn get_lvs(&self) -> Result<Vec<LvmLvData>, LvmError>;
+    #[allow(clippy::too_many_arguments)]
+    fn create_lv(
+        &self,
+        lv: &str,
+        vg: &str,
+        size: u64,
+        size_unit: &str,
+        segtype: &str,
+        pvl: &[String],
+        extra: &[LvmExtraArg],
+    ) -> Result<String, LvmError>;
+    // Removes the lv with its sub lvs, and snapshots if it is an origin.
+    fn remove_lv(&self, vg: &str, lv: &str) -> Result<String, LvmError>;
+    fn resize_lv(&self, vg: &str, lv: &str, resize: &LvmResize) -> Result<String, LvmError>;
+    fn rename_lv(&self, vg: &str, lv: &str, new_name: &str) -> Result<String, LvmError>;
+    fn rename_vg(&self, vg: &str, new_name: &str) -> Result<String, LvmError>;
+    fn create_snapshot(
+        &self,
+        vg: &str,
+        origin: &str,
+        snap: &LvmSnapshot,
+    ) -> Result<String, LvmError>;
+    // Merge is delayed until the origin is next activated if it is open.
+    fn merge_snapshot(&self, vg: &str, snap: &str) -> Result<String, LvmError>;
+    // Creates the fast lv and attaches it, the fast lv is removed again if
+    // attaching fails.
+    fn attach_cache(&self, vg: &str, lv: &str, cache: &LvmCache) -> Result<String, LvmError>;
+    // Dirty blocks are written back first. The fast lv is kept (split) or
+    // removed (uncache).
+    fn detach_cache(&self, vg: &str, lv: &str, keep: bool) -> Result<String, LvmError>;
+    // Starts a scrub, progress is in raid_sync_action and sync_percent.
+    fn raid_sync_action(
+        &self,
+        vg: &str,
+        lv: &str,
+        action: LvmSyncAction,
+    ) -> Result<String, LvmError>;
+    // Replaces the images on missing pvs of a degraded raid lv, allocating on
+    // pvl, or anywhere in the vg if empty.
+    fn repair_raid(&self, vg: &str, lv: &str, pvl: &[String]) -> Result<String, LvmError>;
+    // Moves the image on old_pv to pvl, or anywhere in the vg if empty.
+    fn replace_raid_pv(
+        &self,
+        vg: &str,
+        lv: &str,
+        old_pv: &str,
+        pvl: &[String],
+    ) -> Result<String, LvmError>;
+    // Takes the lv over to another segtype, new images are allocated on pvl,
+    // or anywhere in the vg if empty, and then synced.
+    fn convert_lv(
+        &self,
+        vg: &str,
+        lv: &str,
+        convert: &LvmConvert,
+        pvl: &[String],
+    ) -> Result<String, LvmError>;
+    // Block devices of the host with what keeps them from becoming a pv, read
+    // from sysfs. Pvs are listed too, as used by lvm.
+    fn get_block_devs(&self) -> Result<Vec<BlockDev>, LvmError>;
+    // Initializes dev as a pv not in any vg.
+    fn create_pv(&self, dev: &str, pv: &LvmPvCreate) -> Result<String, LvmError>;
+    // pvl have to be pvs not in a vg yet.
+    fn create_vg(&self, vg: &str, pvl: &[String], opts: &LvmVgCreate) -> Result<String, LvmError>;
+    fn extend_vg(&self, vg: &str, pvl: &[String]) -> Result<String, LvmError>;
+    // Only pvs without allocated extents can be taken out, they stay pvs.
+    fn reduce_vg(&self, vg: &str, pvl: &[String]) -> Result<String, LvmError>;
+    // Starts the move in the background, progress is in the copy_percent of
+    // the pvmove lv.
+    fn move_pv(&self, vg: &str, mv: &LvmPvMove) -> Result<String, LvmError>;
+    // Stops all pvmoves, extents not copied yet stay where they were.
+    fn abort_pvmove(&self) -> Result<String, LvmError>;
+    // Changes are refused, e.g. when browsing captured output. The ui does
+    // not offer them then.
+    fn read_only(&self) -> bool {
+        false
+    }
+    // Operations the backend itself is waiting for, e.g. lvmdbusd jobs,
+    // shown in the job list with the job that started them.
+    fn pending_jobs(&self) -> Vec<LvmBackendJob> {
+        Vec::new()
+    }
+}
+
+//
+// Backend exec'ing lvs, pvs, vgs, lvcreate etc.
+//
+pub struct CmdBackend;
+
+impl LvmBackend for CmdBackend {
+    fn get_vgs(&self) -> Result<Vec<String>, LvmError> {
+        get_vgs()
+    }
+
+    fn get_vg_info(&self, vg_name: &str) -> Result<LvmVgData, LvmError> {
+        get_vg_info(vg_name)
+    }
+
+    fn get_pvs(&self) -> Result<Vec<LvmPVData>, LvmError> {
+        get_pvs()
+    }
+
+    fn get_lvs(&self) -> Result<Vec<LvmLvData>, LvmError> {
+        get_lvs()
+    }
+
+    fn create_lv(
+        &self,
+        lv: &str,
+        vg: &str,
+        size: u64,
+        size_unit: &str,
+        segtype: &str,
+        pvl: &[String],
+        extra: &[LvmExtraArg],
+    ) -> Result<String, LvmError> {
+        create_lv(lv, vg, size, size_unit, segtype, pvl, extra)
+    }
+
+    fn remove_lv(&self, vg: &str, lv: &str) -> Result<String, LvmError> {
+        remove_lv(vg, lv)
+    }
+
+    fn resize_lv(&self, vg: &str, lv: &str, resize: &LvmResize) -> Result<String, LvmError> {
+        resize_lv(vg, lv, resize)
+    }
+
+    fn rename_lv(&self, vg: &str, lv: &str, new_name: &str) -> Result<String, LvmError> {
+        rename_lv(vg, lv, new_name)
+    }
+
+    fn rename_vg(&self, vg: &str, new_name: &str) -> Result<String, LvmError> {
+        rename_vg(vg, new_name)
+    }
+
+    fn create_snapshot(
+        &self,
+        vg: &str,
+        origin: &str,
+        snap: &LvmSnapshot,
+    ) -> Result<String, LvmError> {
+        create_snapshot(vg, origin, snap)
+    }
+
+    fn merge_snapshot(&self, vg: &str, snap: &str) -> Result<String, LvmError> {
+        merge_snapshot(vg, snap)
+    }
+
+    fn attach_cache(&self, vg: &str, lv: &str, cache: &LvmCache) -> Result<String, LvmError> {
+        attach_cache(vg, lv, cache)
+    }
+
+    fn detach_cache(&self, vg: &str, lv: &str, keep: bool) -> Result<String, LvmError> {
+        detach_cache(vg, lv, keep)
+    }
+
+    fn raid_sync_action(
+        &self,
+        vg: &str,
+        lv: &str,
+        action: LvmSyncAction,
+    ) -> Result<String, LvmError> {
+        raid_sync_action(vg, lv, action)
+    }
+
+    fn repair_raid(&self, vg: &str, lv: &str, pvl: &[String]) -> Result<String, LvmError> {
+        repair_raid(vg, lv, pvl)
+    }
+
+    fn replace_raid_pv(
+        &self,
+        vg: &str,
+        lv: &str,
+        old_pv: &str,
+        pvl: &[String],
+    ) -> Result<String, LvmError> {
+        replace_raid_pv(vg, lv, old_pv, pvl)
+    }
+
+    fn convert_lv(
+        &self,
+        vg: &str,
+        lv: &str,
+        convert: &LvmConvert,
+        pvl: &[String],
+    ) -> Result<String, LvmError> {
+        convert_lv(vg, lv, convert, pvl)
+    }
+
+    fn get_block_devs(&self) -> Result<Vec<BlockDev>, LvmError> {
+        Ok(devinfo::block_devs())
+    }
+
+    fn create_pv(&self, dev: &str, pv: &LvmPvCreate) -> Result<String, LvmError> {
+        create_pv(dev, pv)
+    }
+
+    fn create_vg(&self, vg: &str, pvl: &[String], opts: &LvmVgCreate) -> Result<String, LvmError> {
+        create_vg(vg, pvl, opts)
+    }
+
+    fn extend_vg(&self, vg: &str, pvl: &[String]) -> Result<String, LvmError> {
+        extend_vg(vg, pvl)
+    }
+
+    fn reduce_vg(&self, vg: &str, pvl: &[String]) -> Result<String, LvmError> {
+        reduce_vg(vg, pvl)
+    }
+
+    fn move_pv(&self, _vg: &str, mv: &LvmPvMove) -> Result<String, LvmError> {
+        move_pv(mv)
+    }
+
+    fn abort_pvmove(&self) -> Result<String, LvmError> {
+        abort_pvmove()
+    }
+}
+// Fields of plain reports, as read before json_std: sizes in bytes, lists
+// ',' separated in the last field.
+pub const BASIC_VGS_FIELDS: &str = "vg_name,vg_size,vg_free,pv_count,vg_attr,vg_uuid";
+pub const BASIC_PVS_FIELDS: &str = "pv_name,vg_name";
+pub const BASIC_LVS_FIELDS: &str =
+    "lv_name,vg_name,lv_size,lv_attr,segtype,lv_uuid,stripes,data_stripes,lv_parent,seg_le_ranges";
+
+//
+// Report model, as printed by e.g. 'lvs --reportformat json_std'
+//
+// {
+//   "report": [
+//     { "lv": [ {"lv_name":"lvpub", "vg_name":"vg04_1tbdisks", ...}, ... ] }
+//   ]
+// }
+//
+#[derive(Deserialize)]
+struct LvmReport {
+    report: Vec<LvmReportSection>,
+}
+
+#[derive(Deserialize, Default)]
+struct LvmReportSection {
+    #[serde(default)]
+    vg: Vec<VgRow>,
+    #[serde(default)]
+    pv: Vec<PvRow>,
+    // lvs reports containing seg_* fields are named "seg".
+    #[serde(default, alias = "seg")]
+    lv: Vec<LvRow>,
+}
+
+#[derive(Deserialize)]
+struct VgRow {
+    vg_name: String,
+    #[serde(default, deserialize_with = "de_u64")]
+    vg_size: u64,
+    #[serde(default, deserialize_with = "de_u64")]
+    vg_free: u64,
+    #[serde(default, deserialize_with = "de_u64")]
+    pv_count: u64,
+    #[serde(default)]
+    vg_attr: String,
+    #[serde(default)]
+    vg_uuid: String,
+}
+
+#[derive(Deserialize)]
+struct PvRow {
+    pv_name: String,
+    #[serde(default, deserialize_with = "de_string")]
+    vg_name: String,
+    #[serde(default, deserialize_with = "de_u64")]
+    pv_size: u64,
+    #[serde(default, deserialize_with = "de_u64")]
+    pv_free: u64,
+    #[serde(default, deserialize_with = "de_u64")]
+    pe_start: u64,
+    #[serde(default, deserialize_with = "de_u64")]
+    vg_extent_size: u64,
+    #[serde(default, deserialize_with = "de_u64")]
+    pv_pe_count: u64,
+    #[serde(default, deserialize_with = "de_u64")]
+    pv_pe_alloc_count: u64,
+    #[serde(default, deserialize_with = "de_string")]
+    pv_attr: String,
+    #[serde(default, deserialize_with = "de_string")]
+    pv_uuid: String,
+    #[serde(default, deserialize_with = "de_devnum")]
+    pv_major: Option<u32>,
+    #[serde(default, deserialize_with = "de_devnum")]
+    pv_minor: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct LvRow {
+    lv_name: String,
+    vg_name: String,
+    #[serde(default, deserialize_with = "de_u64")]
+    lv_size: u64,
+    #[serde(default)]
+    lv_attr: String,
+    #[serde(default)]
+    segtype: String,
+    #[serde(default)]
+    lv_uuid: String,
+    #[serde(default, deserialize_with = "de_u64")]
+    stripes: u64,
+    #[serde(default, deserialize_with = "de_u64")]
+    data_stripes: u64,
+    #[serde(default, deserialize_with = "de_string")]
+    lv_parent: String,
+    #[serde(default, deserialize_with = "de_strlist")]
+    seg_le_ranges: Vec<String>,
+    #[serde(default, deserialize_with = "de_string")]
+    lv_path: String,
+    #[serde(default, deserialize_with = "de_string")]
+    lv_dm_path: String,
+    #[serde(default, deserialize_with = "de_devnum")]
+    lv_kernel_major: Option<u32>,
+    #[serde(default, deserialize_with = "de_devnum")]
+    lv_kernel_minor: Option<u32>,
+    #[serde(default, deserialize_with = "de_percent")]
+    sync_percent: Option<f64>,
+    #[serde(default, deserialize_with = "de_string")]
+    lv_health_status: String,
+    #[serde(default, deserialize_with = "de_string")]
+    raid_sync_action: String,
+    #[serde(default, deserialize_with = "de_count")]
+    raid_mismatch_count: Option<u64>,
+    #[serde(default, deserialize_with = "de_strlist")]
+    lv_tags: Vec<String>,
+    #[serde(default, deserialize_with = "de_string")]
+    lv_time: String,
+    #[serde(default, deserialize_with = "de_string")]
+    lv_host: String,
+    #[serde(default, deserialize_with = "de_string")]
+    origin: String,
+    #[serde(default, deserialize_with = "de_percent")]
+    snap_percent: Option<f64>,
+    #[serde(default, deserialize_with = "de_string")]
+    pool_lv: String,
+    #[serde(default, deserialize_with = "de_percent")]
+    data_percent: Option<f64>,
+    #[serde(default, deserialize_with = "de_percent")]
+    metadata_percent: Option<f64>,
+    #[serde(default, deserialize_with = "de_string")]
+    cache_mode: String,
+    #[serde(default, deserialize_with = "de_string")]
+    cache_policy: String,
+    #[serde(default, deserialize_with = "de_count")]
+    cache_total_blocks: Option<u64>,
+    #[serde(default, deserialize_with = "de_count")]
+    cache_used_blocks: Option<u64>,
+    #[serde(default, deserialize_with = "de_count")]
+    cache_dirty_blocks: Option<u64>,
+    #[serde(default, deserialize_with = "de_count")]
+    cache_read_hits: Option<u64>,
+    #[serde(default, deserialize_with = "de_count")]
+    cache_read_misses: Option<u64>,
+    #[serde(default, deserialize_with = "de_count")]
+    cache_write_hits: Option<u64>,
+    #[serde(default, deserialize_with = "de_count")]
+    cache_write_misses: Option<u64>,
+    #[serde(default, deserialize_with = "de_count")]
+    writecache_total_blocks: Option<u64>,
+    #[serde(default, deserialize_with = "de_count")]
+    writecache_free_blocks: Option<u64>,
+    #[serde(default, deserialize_with = "de_count")]
+    writecache_writeback_blocks: Option<u64>,
+    #[serde(default, deserialize_with = "de_string")]
+    move_pv: String,
+    #[serde(default, deserialize_with = "de_percent")]
+    copy_percent: Option<f64>,
+}
+
+//
+// Bytes per size unit as used by lvcreate -L (binary units), None if not a size unit
+// e.g. %FREE.
+//
+pub fn size_unit_bytes(unit: &str) -> Option<u64> {
+    match unit {
+        "B" => Some(1),
+        "K" => Some(1024),
+        "M" => Some(1024 * 1024),
+        "G" => Some(1024 * 1024 * 1024),
+        "T" => Some(1024 * 1024 * 1024 * 1024),
+        _ => None,
+    }
+}
+
+//
+// Size option value in bytes, e.g. '64M' or '512k' as --poolmetadatasize and
+// --chunksize take them, 'default_unit' if there is no unit.
+//
+pub fn parse_size_arg(value: &str, default_unit: &str) -> Option<u64> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (num, unit) = value.split_at(split);
+    let unit = if unit.is_empty() { default_unit } else { unit };
+    let num = num.parse::<u64>().ok()?;
+    num.checked_mul(size_unit_bytes(&unit.to_ascii_uppercase())?)
+}
+
+//
+// '10G' to (10, "G"), '50%FREE' to (50, "%FREE"), units as the new lv form
+// has them. None if not one of those.
+//
+pub fn split_size_arg(size: &str) -> Option<(u64, String)> {
+    let split = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (num, unit) = size.split_at(split);
+    let unit = unit.to_ascii_uppercase();
+    match (num.parse::<u64>(), unit.as_str()) {
+        (Ok(num), "M" | "G" | "T" | "%FREE" | "%VG") if num > 0 => Some((num, unit)),
+        _ => None,
+    }
+}
+
+impl LvmResize {
+    // As given to lvresize -L/-l, e.g. '+10G'.
+    pub fn size_arg(&self) -> String {
+        let sign = self.sign.map(String::from).unwrap_or_default();
+        format!("{sign}{}{}", self.size, self.size_unit)
+    }
+
+    //
+    // New lv size in bytes, rounded up to whole extents like lvm does. Percentages
+    // are of the vg, so '+50%FREE' adds half of the free space.
+    //
+    pub fn new_size(
+        &self,
+        lv_size: u64,
+        vg: &LvmVgData,
+        extent_size: u64,
+    ) -> Result<u64, LvmError> {
+        let invalid = LvmError::Usage;
+        let too_large = || invalid(format!("Size {} is too large.", self.size_arg()));
+        let amount = match self.size_unit.as_str() {
+            "%FREE" => (vg.free / 100).checked_mul(self.size),
+            "%VG" => (vg.size / 100).checked_mul(self.size),
+            unit => self.size.checked_mul(
+                size_unit_bytes(unit)
+                    .ok_or_else(|| invalid(format!("Invalid size unit {unit}.")))?,
+            ),
+        }
+        .ok_or_else(too_large)?;
+        let size = match self.sign {
+            None => amount,
+            Some('+') => lv_size.checked_add(amount).ok_or_else(too_large)?,
+            Some('-') => lv_size
+                .checked_sub(amount)
+                .ok_or_else(|| invalid(format!("Can't reduce by {} below 0.", self.size_arg())))?,
+            Some(c) => return Err(invalid(format!("Invalid sign {c}."))),
+        };
+        let size = if extent_size > 0 {
+            size.div_ceil(extent_size)
+                .checked_mul(extent_size)
+                .ok_or_else(too_large)?
+        } else {
+            size
+        };
+        if size == 0 {
+            return Err(invalid("Size must be larger than 0.".to_string()));
+        }
+        Ok(size)
+    }
+}
+
+impl LvmSnapshot {
+    // As given to lvcreate -L/-l, e.g. '20%ORIGIN'.
+    pub fn size_arg(&self) -> String {
+        format!("{}{}", self.size, self.size_unit)
+    }
+
+    //
+    // Cow size in bytes, rounded up to whole extents.
+    //
+    pub fn cow_size(
+        &self,
+        origin_size: u64,
+        vg: &LvmVgData,
+        extent_size: u64,
+    ) -> Result<u64, LvmError> {
+        let invalid = LvmError::Usage;
+        let too_large = || {
+            invalid(format!(
+                "Size {}{} is too large.",
+                self.size, self.size_unit
+            ))
+        };
+        let size = match self.size_unit.as_str() {
+            "%ORIGIN" => (origin_size / 100).checked_mul(self.size),
+            "%FREE" => (vg.free / 100).checked_mul(self.size),
+            "%VG" => (vg.size / 100).checked_mul(self.size),
+            unit => self.size.checked_mul(
+                size_unit_bytes(unit)
+                    .ok_or_else(|| invalid(format!("Invalid size unit {unit}.")))?,
+            ),
+        }
+        .ok_or_else(too_large)?;
+        let size = if extent_size > 0 {
+            size.div_ceil(extent_size)
+                .checked_mul(extent_size)
+                .ok_or_else(too_large)?
+        } else {
+            size
+        };
+        if size == 0 {
+            return Err(invalid("Size must be larger than 0.".to_string()));
+        }
+        Ok(size)
+    }
+}
+
+//
+// json_std prints numbers as numbers and undefined values as null, plain json
+// prints everything as strings, possibly with unit suffix ('123B'). Accept all,
+// also sizes reported without --units b ('<99.00g'), as in captured output.
+//
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumOrStr {
+    Num(u64),
+    Float(f64),
+    Str(String),
+}
+
+fn de_u64<'de, D: Deserializer<'de>>(d: D) -> Result<u64, D::Error> {
+    match Option::<NumOrStr>::deserialize(d)? {
+        None => Ok(0),
+        Some(NumOrStr::Num(n)) => Ok(n),
+        Some(NumOrStr::Float(f)) => Ok(f as u64),
+        Some(NumOrStr::Str(s)) => parse_report_size(&s).map_err(serde::de::Error::custom),
+    }
+}
+
+//
+// Size as lvm reports it with --units, rounded ('<', '>') and lower case units
+// are 1024 based, upper case ones 1000 based, 's' is sectors.
+//
+fn parse_report_size(s: &str) -> Result<u64, String> {
+    let s = s.trim().trim_start_matches(['<', '>']);
+    let Some(unit) = s.chars().last().filter(|c| c.is_ascii_alphabetic()) else {
+        return if s.is_empty() {
+            Ok(0)
+        } else {
+            s.parse::<u64>().map_err(|e| format!("{e}: '{s}'"))
+        };
+    };
+    let num = &s[..s.len() - 1];
+    let exp = match unit.to_ascii_lowercase() {
+        'b' => return num.parse::<u64>().map_err(|e| format!("{e}: '{s}'")),
+        's' => return Ok(num.parse::<u64>().map_err(|e| format!("{e}: '{s}'"))? * 512),
+        'k' => 1,
+        'm' => 2,
+        'g' => 3,
+        't' => 4,
+        'p' => 5,
+        'e' => 6,
+        _ => return Err(format!("unknown unit: '{s}'")),
+    };
+    let base: f64 = if unit.is_ascii_lowercase() {
+        1024.0
+    } else {
+        1000.0
+    };
+    let num = num.parse::<f64>().map_err(|e| format!("{e}: '{s}'"))?;
+    Ok((num * base.powi(exp)).round() as u64)
+}
+
+// major/minor, -1 (or "") when the device is missing.
+fn de_devnum<'de, D: Deserializer<'de>>(d: D) -> Result<Option<u32>, D::Error> {
+    Ok(match Option::<NumOrStr>::deserialize(d)? {
+        Some(NumOrStr::Num(n)) => u32::try_from(n).ok(),
+        Some(NumOrStr::Str(s)) => s.trim().parse::<u32>().ok(),
+        _ => None,
+    })
+}
+
+// Percent fields, null or "" if not applicable for the lv.
+fn de_percent<'de, D: Deserializer<'de>>(d: D) -> Result<Option<f64>, D::Error> {
+    match Option::<NumOrStr>::deserialize(d)? {
+        None => Ok(None),
+        Some(NumOrStr::Num(n)) => Ok(Some(n as f64)),
+        Some(NumOrStr::Float(f)) => Ok(Some(f)),
+        Some(NumOrStr::Str(s)) => {
+            let s = s.trim();
+            if s.is_empty() {
+                return Ok(None);
+            }
+            s.parse::<f64>().map(Some).map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+// Cache counters, null, "" or -1 if not cached or not active.
+fn de_count<'de, D: Deserializer<'de>>(d: D) -> Result<Option<u64>, D::Error> {
+    Ok(match Option::<NumOrStr>::deserialize(d)? {
+        Some(NumOrStr::Num(n)) => Some(n),
+        Some(NumOrStr::Str(s)) => s.trim().parse::<u64>().ok(),
+        _ => None,
+    })
+}
+
+fn de_string<'de, D: Deserializer<'de>>(d: D) -> Result<String, D::Error> {
+    Ok(Option::<String>::deserialize(d)?.unwrap_or_default())
+}
+
+//
+// String lists are json arrays in json_std, ',' separated strings in json.
+//
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StrList {
+    List(Vec<String>),
+    Str(String),
+}
+
+fn de_strlist<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<String>, D::Error> {
+    let list = match Option::<StrList>::deserialize(d)? {
+        None => Vec::new(),
+        Some(StrList::List(l)) => l,
+        Some(StrList::Str(s)) => s.split(',').map(|s| s.to_string()).collect(),
+    };
+
+    Ok(list
+        .into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect())
+}
+
+fn parse_report(s: &str) -> Result<Vec<LvmReportSection>, LvmError> {
+    if s.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let report: LvmReport = serde_json::from_str(s).map_err(|e| {
+        let line = s
+            .lines()
+            .nth(e.line().saturating_sub(1))
+            .unwrap_or_default();
+        LvmError::parse(format!("failed to parse lvm report: {e}"), line)
+    })?;
+
+    Ok(report.report)
+}
+
+//
+// A plain report as json_std, to be read like one. As printed by e.g.
+//
+//   lvs --reportformat basic --headings none --separator , --units b -o <BASIC_LVS_FIELDS>
+//
+// vgssd_virt,120028397568B,54530146304B,1,wz--n-,ZFcFCx-fW2F-sWq6-PVy1-8PN2-2CVt-epVMVt
+//
+// Missing trailing fields are empty, lvm leaves them off, e.g. '  /dev/sdb2'
+// for a pv in no vg. The last field takes the rest of the line. Names and
+// paths have no blanks, a first field with some is a report in columns.
+//
+fn basic_report_json(s: &str, section: &str, fields: &str) -> Result<String, LvmError> {
+    let fields: Vec<&str> = fields.split(',').collect();
+    let mut rows = Vec::new();
+    for line in s.lines().filter(|line| !line.trim().is_empty()) {
+        let mut values = line.trim().splitn(fields.len(), ',');
+        if values
+            .clone()
+            .next()
+            .is_some_and(|v| v.contains(char::is_whitespace))
+        {
+            return Err(LvmError::parse("expected ',' separated fields", line));
+        }
+        let row: serde_json::Map<String, serde_json::Value> = fields
+            .iter()
+            .map(|field| {
+                let value = values.next().unwrap_or_default().trim();
+                (field.to_string(), value.into())
+            })
+            .collect();
+        rows.push(serde_json::Value::Object(row));
+    }
+    Ok(serde_json::json!({ "report": [{ section: rows }] }).to_string())
+}
+
+//
+// Run cmd, return stdout and stderr, what lvm printed, if exit is 0.
+//
+fn run_cmd(cmd: &str, args: &[&str]) -> Result<String, LvmError> {
+    let o = exec_cmd(cmd, args)?;
+    let mut out = String::from_utf8_lossy(&o.stdout).into_owned();
+    out += &String::from_utf8_lossy(&o.stderr);
+    Ok(out)
+}
+
+//
+// Run a report cmd, return stdout only as warnings on stderr are no json.
+//
+pub(crate) fn run_report(cmd: &str, args: &[&str]) -> Result<String, LvmError> {
+    let o = exec_cmd(cmd, args)?;
+    Ok(String::from_utf8_lossy(&o.stdout).into_owned())
+}
+
+fn exec_cmd(cmd: &str, args: &[&str]) -> Result<std::process::Output, LvmError> {
+    let mut command: std::process::Command = std::process::Command::new(cmd);
+    let o = command.args(args).output().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => LvmError::BinaryNotFound(cmd.to_string()),
+        std::io::ErrorKind::PermissionDenied => LvmError::PermissionDenied(cmd.to_string()),
+        _ => LvmError::Io(e),
+    })?;
+
+    if !o.status.success() {
+        let stderr = String::from_utf8_lossy(&o.stderr).into_owned();
+        return Err(LvmError::from_failed_cmd(cmd, o.status.code(), stderr));
+    }
+    Ok(o)
+}
+
+// What lvm printed, or done when it printed nothing.
+fn cmd_output(out: String, done: &str) -> String {
+    if out.trim().is_empty() {
+        done.to_string()
+    } else {
+        out
+    }
+}
+
+fn report_args<'a>(fields: &'a str, extra: &[&'a str]) -> Vec<&'a str> {
+    let mut args: Vec<&str> = extra.to_vec();
+    args.extend_from_slice(&REPORT_ARGS);
+    args.push("-o");
+    args.push(fields);
+    args
+}
+
+pub fn get_vg_info(vg_name: &str) -> Result<LvmVgData, LvmError> {
+    let args = report_args(VGS_FIELDS, &[vg_name]);
+
+    let s = run_report(VGDISPLAY_BIN, &args)?;
+    parse_vgdo(&s)
+}
+
+//
+// {"report": [{"vg": [{"vg_name":"vgssd_virt", "vg_size":120028397568, "vg_free":54530146304,
+//   "pv_count":1, "vg_attr":"wz--n-", "vg_uuid":"ZFcFCx-fW2F-sWq6-PVy1-8PN2-2CVt-epVMVt"}]}]}
+//
+fn parse_vgdo(s: &str) -> Result<LvmVgData, LvmError> {
+    parse_vgso_full(s)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| LvmError::parse("failed to parse vgs output, no vg in report", s))
+}
+
+//
+// All vgs of a report with their details, as in parse_vgdo.
+//
+fn parse_vgso_full(s: &str) -> Result<Vec<LvmVgData>, LvmError> {
+    Ok(parse_report(s)?
+        .into_iter()
+        .flat_map(|section| section.vg)
+        .map(|vg| LvmVgData {
+            name: vg.vg_name,
+            size: vg.vg_size,
+            free: vg.vg_free,
+            pv_count: vg.pv_count,
+            attr: vg.vg_attr,
+            uuid: vg.vg_uuid,
+        })
+        .collect())
+}
+
+//
+// {"report": [{"vg": [{"vg_name":"vgssd_virt"}, {"vg_name":"vg2"}]}]}
+//
+fn parse_vgso(s: &str) -> Result<Vec<String>, LvmError> {
+    Ok(parse_report(s)?
+        .into_iter()
+        .flat_map(|section| section.vg)
+        .map(|vg| vg.vg_name)
+        .collect())
+}
+
+//
+// Return all volumne groups found.
+//
+pub fn get_vgs() -> Result<Vec<String>, LvmError> {
+    let args = report_args("vg_name", &[]);
+
+    let s = run_report(VGDISPLAY_BIN, &args)?;
+    parse_vgso(&s)
 }
 
-#[derive(Clone, Default)]
-pub struct LvmLvData {
-    pub lv_name: String,
-    pub vg_name: String,
-    pub size: u64, // bytes
-    pub attr: String,
-    pub segtype: String,
-    pub uuid: String,
-    pub lv_segs: Vec<LvmlvSegData>,
-    pub stripes: u16,
-    pub data_stripes: u16,
-    pub parent_lv: String,
+//
+// {"report": [{"pv": [{"pv_name":"/dev/sda1", "vg_name":"vg01"}, {"pv_name":"/dev/sdx", "vg_name":""}]}]}
+//
+fn parse_pvso(s: &str) -> Result<Vec<LvmPVData>, LvmError> {
+    Ok(parse_report(s)?
+        .into_iter()
+        .flat_map(|section| section.pv)
+        .map(|pv| LvmPVData {
+            pv_name: pv.pv_name,
+            vg_name: pv.vg_name, // ok, pv may not have vg
+            pv_size: pv.pv_size,
+            pv_free: pv.pv_free,
+            pe_start: pv.pe_start,
+            pe_size: pv.vg_extent_size,
+            pe_count: pv.pv_pe_count,
+            pe_alloc_count: pv.pv_pe_alloc_count,
+            pv_attr: pv.pv_attr,
+            pv_uuid: pv.pv_uuid,
+            major: pv.pv_major,
+            minor: pv.pv_minor,
+        })
+        .collect())
 }
 
-#[derive(Clone)]
-pub struct LvmlvSegData {
-    pub pvdev: String,
-    pub pv_start_pe: u64,
-    pub size_pe: u64,
+pub fn get_pvs() -> Result<Vec<LvmPVData>, LvmError> {
+    let args = report_args(PVS_FIELDS, &[]);
+
+    let s = run_report(PVS_BIN, &args)?;
+    parse_pvso(&s)
 }
 
-#[derive(Clone, Default)]
-pub struct LvmVgData {
-    pub name: String,
-    pub free: u64, // bytes
-    pub size: u64, // bytes
-    pub pv_count: u64,
-    pub attr: String,
-    pub uuid: String,
+//
+// One row per segment, a LV with several segments (e.g. extended linear LV) is
+// reported several times and the rows are merged into one LvmLvData, keyed by uuid.
+//
+// {"report": [{"lv": [
+//   {"lv_name":"lvpub", "vg_name":"vg04_1tbdisks", "lv_size":536875106304, "lv_attr":"rwi-aor---",
+//    "segtype":"raid5", "lv_uuid":"0iPPdB-...", "stripes":4, "data_stripes":3, "lv_parent":"",
+//    "seg_le_ranges":["[lvpub_rimage_0]:0-42666", "[lvpub_rimage_1]:0-42666", ...]},
+//   {"lv_name":"[lvpub_rmeta_3]", ..., "lv_parent":"lvpub", "seg_le_ranges":["/dev/sda1:7424-7424"]}
+// ]}]}
+//
+fn parse_lvso(s: &str) -> Result<Vec<LvmLvData>, LvmError> {
+    let mut lvs = Vec::<LvmLvData>::new();
+
+    for row in parse_report(s)?.into_iter().flat_map(|section| section.lv) {
+        let lv_segs = parse_lvso_segs(&row.seg_le_ranges)?;
+        let existing = lvs.iter_mut().find(|lv| {
+            lv.vg_name == row.vg_name
+                && if row.lv_uuid.is_empty() {
+                    lv.lv_name == row.lv_name
+                } else {
+                    lv.uuid == row.lv_uuid
+                }
+        });
+
+        if let Some(lv) = existing {
+            lv.lv_segs.extend(lv_segs);
+            continue;
+        }
+
+        let stripes =
+            u16::try_from(row.stripes).map_err(|e| LvmError::parse(e.to_string(), &row.lv_name))?;
+        let data_stripes = u16::try_from(row.data_stripes)
+            .map_err(|e| LvmError::parse(e.to_string(), &row.lv_name))?;
+        lvs.push(LvmLvData {
+            lv_name: row.lv_name,
+            vg_name: row.vg_name,
+            size: row.lv_size,
+            attr: row.lv_attr,
+            segtype: row.segtype,
+            uuid: row.lv_uuid,
+            lv_segs,
+            stripes,
+            data_stripes,
+            parent_lv: row.lv_parent,
+            path: row.lv_path,
+            dm_path: row.lv_dm_path,
+            kernel_major: row.lv_kernel_major,
+            kernel_minor: row.lv_kernel_minor,
+            sync_percent: row.sync_percent,
+            health_status: row.lv_health_status,
+            raid_sync_action: row.raid_sync_action,
+            raid_mismatch_count: row.raid_mismatch_count,
+            tags: row.lv_tags,
+            time: row.lv_time,
+            host: row.lv_host,
+            origin: row.origin,
+            snap_percent: row.snap_percent,
+            pool_lv: row.pool_lv,
+            data_percent: row.data_percent,
+            metadata_percent: row.metadata_percent,
+            cache_mode: row.cache_mode,
+            cache_policy: row.cache_policy,
+            cache_total_blocks: row.cache_total_blocks,
+            cache_used_blocks: row.cache_used_blocks,
+            cache_dirty_blocks: row.cache_dirty_blocks,
+            cache_read_hits: row.cache_read_hits,
+            cache_read_misses: row.cache_read_misses,
+            cache_write_hits: row.cache_write_hits,
+            cache_write_misses: row.cache_write_misses,
+            writecache_total_blocks: row.writecache_total_blocks,
+            writecache_free_blocks: row.writecache_free_blocks,
+            writecache_writeback_blocks: row.writecache_writeback_blocks,
+            move_pv: row.move_pv,
+            copy_percent: row.copy_percent,
+        });
+    }
+
+    Ok(lvs)
 }
 
-fn run_cmd(cmd: &str, args: &[&str]) -> Result<std::process::Output, std::io::Error> {
-    let mut command: std::process::Command = std::process::Command::new(cmd);
-    command.args(args).output()
-}
-
-pub fn get_vg_info(vg_name: &String) -> LvmVgData {
-    let args: [&str; 12] = [
-        vg_name,
-        "--headings",
-        "none",
-        "--separator",
-        ",",
-        "--reportformat",
-        "basic",
-        "-a",
-        "--units",
-        "B",
-        "-o",
-        "vg_name,vg_size,vg_free,pv_count,vg_attr,vg_uuid",
-    ];
+//
+// Parse seg_le_ranges items, '/dev/sda1:7424-9983' or '[lvpub_rimage_0]:0-42666'.
+// Range is inclusive, i.e. '0-0' is one extent.
+//
+fn parse_lvso_segs(ranges: &[String]) -> Result<Vec<LvmlvSegData>, LvmError> {
+    let mut v_lv_segs: Vec<LvmlvSegData> = Vec::<LvmlvSegData>::new();
 
-    match run_cmd(VGDISPLAY_BIN, &args) {
-        Ok(o) => {
-            let s: std::borrow::Cow<'_, str> = String::from_utf8_lossy(&o.stdout);
-            match parse_vgdo(&s) {
-                Ok(s) => s,
-                Err(e) => panic!("{e}"),
+    for dev_data in ranges {
+        let pos = dev_data
+            .rfind(':')
+            .ok_or_else(|| LvmError::parse("failed to parse extents for pvdev", dev_data))?;
+        let pvdev: &str = &dev_data[0..pos];
+        let remainder: &str = &dev_data[(pos + 1)..];
+        let (ext_start, ext_end) = match remainder.split_once('-') {
+            Some((start, end)) => (
+                start.parse::<u64>().unwrap_or(0),
+                end.parse::<u64>().unwrap_or(0),
+            ),
+            None => {
+                let start = remainder.parse::<u64>().unwrap_or(0);
+                (start, start)
             }
-        }
-        Err(e) => panic!("{e}"),
+        };
+
+        v_lv_segs.push(LvmlvSegData {
+            pvdev: pvdev.to_string(),
+            pv_start_pe: ext_start,
+            size_pe: ext_end.saturating_sub(ext_start) + 1,
+        });
     }
+
+    Ok(v_lv_segs)
 }
 
 //
-// VG,VSize,VFree,#PV,Attr,VG UUID
-// vgssd_virt,120028397568B,54530146304B,1,wz--n-,ZFcFCx-fW2F-sWq6-PVy1-8PN2-2CVt-epVMVt
+// lvs --reportformat json_std --units b --nosuffix -a -o lv_name,vg_name,lv_size,...
 //
-fn parse_vgdo(s: &std::borrow::Cow<'_, str>) -> Result<LvmVgData, &'static str> {
-    let err = "failed to parse/split vgdisplay output";
-
-    if s.len() < 1 {
-        return Err(err);
-    }
-    let v: Vec<&str> = s.split(",").collect();
-    if v.len() < 6 {
-        return Err(err);
-    }
-
-    let lvmvgdata: LvmVgData = LvmVgData {
-        name: v.get(0).ok_or(err)?.trim().to_string(),
-        size: parseu64_ds(v.get(1).ok_or_else(|| err)?.trim())?,
-        free: parseu64_ds(v.get(2).ok_or_else(|| err)?.trim())?,
-        pv_count: v
-            .get(3)
-            .ok_or(err)?
-            .trim()
-            .to_string()
-            .parse::<u64>()
-            .unwrap_or(0),
-        attr: v.get(4).ok_or_else(|| err)?.trim().to_string(),
-        uuid: v.get(5).ok_or_else(|| err)?.trim().to_string(),
-    };
+pub fn get_lvs() -> Result<Vec<LvmLvData>, LvmError> {
+    let args = report_args(LVS_FIELDS, &[]);
+
+    let s = run_report(LVS_BIN, &args)?;
+    parse_lvso(&s)
+}
 
-    return Ok(lvmvgdata);
+// Binary units as in the size inputs.
+pub fn fmt_size(bytes: u64) -> String {
+    let gib = 1024.0 * 1024.0 * 1024.0;
+    format!("{:.2}G", bytes as f64 / gib)
 }
 
-fn parseu64_ds(s: &str) -> Result<u64, &'static str> {
-    let num = &s[0..(s.len() - 1)]; //  drop last char, eg. '123321B'
-    let num = num.to_string().parse::<u64>();
-    match num {
-        Ok(num) => Ok(num),
-        Err(_) => Err("Failed to convert string to int"),
+//
+// lvcreate arguments, e.g.
+// --type raid1 -m 1 -L 10G -n lvvirt_archjol vg04_1tbdisks /dev/sdd1 /dev/sde1
+// Thin lvs take their virtual size with -V, the pool is an extra --thinpool.
+// Other sizes relative to the vg ('50%FREE') are in extents, -l. A thin lv
+// has no extents of its own, callers refuse % sizes for it.
+//
+pub fn create_lv_args(
+    lv: &str,
+    vg: &str,
+    size: u64,
+    size_unit: &str,
+    segtype: &str,
+    pvl: &[String],
+    extra: &[LvmExtraArg],
+) -> Vec<String> {
+    let mut args = vec!["--type".to_string(), segtype.to_string()];
+    for e in extra {
+        args.push(e.opt.clone());
+        args.push(e.value.clone());
     }
+    let size_opt = if segtype == "thin" {
+        "-V"
+    } else if size_unit.starts_with('%') {
+        "-l"
+    } else {
+        "-L"
+    };
+    args.push(size_opt.to_string());
+    args.push(format!("{}{}", size, size_unit));
+    args.push("-n".to_string());
+    args.push(lv.to_string());
+    args.push(vg.to_string());
+    args.extend(pvl.iter().cloned());
+    args
+}
+
+//
+// Create logical volumne
+//
+pub fn create_lv(
+    lv: &str,
+    vg: &str,
+    size: u64,
+    size_unit: &str,
+    segtype: &str,
+    pvl: &[String],
+    extra: &[LvmExtraArg],
+) -> Result<String, LvmError> {
+    let args = create_lv_args(lv, vg, size, size_unit, segtype, pvl, extra);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let out = run_cmd(LVCREATE_BIN, &args)?;
+    Ok(cmd_output(out, "Created lv"))
 }
 
-fn parseu16(s: &str) -> Result<u16, &'static str> {
-    let num = &s[0..(s.len())]; //  
-    let num = num.to_string().parse::<u16>();
-    match num {
-        Ok(num) => Ok(num),
-        Err(_) => Err("Failed to convert string to u16"),
+//
+// Virtual size of the thin lvs in a pool over the pool's size, above 1 the
+// pool is over-provisioned. None if the pool has no size.
+//
+pub fn thin_provisioning(pool: &LvmLvData, lvs: &[LvmLvData]) -> Option<f64> {
+    if pool.size == 0 {
+        return None;
     }
+    let virtual_size: u64 = lvs
+        .iter()
+        .filter(|lv| lv.vg_name == pool.vg_name && lv.pool_lv == pool.lv_name)
+        .map(|lv| lv.size)
+        .sum();
+    Some(virtual_size as f64 / pool.size as f64)
+}
+
+//
+// Remove logical volume, -y answers the 'really remove active lv' question. Open
+// lvs are refused by lvremove itself.
+//
+pub fn remove_lv(vg: &str, lv: &str) -> Result<String, LvmError> {
+    let lv_path = format!("{vg}/{lv}");
+    let out = run_cmd(LVREMOVE_BIN, &["-y", &lv_path])?;
+    Ok(cmd_output(out, "Removed lv"))
 }
 
 //
-// VG
-// vgssd_virt
-// vg2
+// lvresize -y [--resizefs] -L|-l [+|-]size vg/lv, -l for percentages.
+// -y answers the 'reduce active lv' question, the ui has asked already.
 //
-fn parse_vgso(s: &std::borrow::Cow<'_, str>) -> Result<Vec<String>, &'static str> {
-    if s.len() < 1 {
-        return Ok(Vec::<String>::new());
+pub fn resize_lv_args(vg: &str, lv: &str, resize: &LvmResize) -> Vec<String> {
+    let mut args = vec!["-y".to_string()];
+    if resize.resizefs {
+        args.push("--resizefs".to_string());
+    }
+    if resize.size_unit.starts_with('%') {
+        args.push("-l".to_string());
+    } else {
+        args.push("-L".to_string());
     }
+    args.push(resize.size_arg());
+    args.push(format!("{vg}/{lv}"));
+    args
+}
+
+pub fn resize_lv(vg: &str, lv: &str, resize: &LvmResize) -> Result<String, LvmError> {
+    let args = resize_lv_args(vg, lv, resize);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let out = run_cmd(LVRESIZE_BIN, &args)?;
+    Ok(cmd_output(out, "Resized lv"))
+}
 
-    let v_vgs = s.lines().map(|line| line.trim().to_string()).collect();
+pub fn rename_lv(vg: &str, lv: &str, new_name: &str) -> Result<String, LvmError> {
+    let out = run_cmd(LVRENAME_BIN, &[vg, lv, new_name])?;
+    Ok(cmd_output(out, "Renamed lv"))
+}
 
-    return Ok(v_vgs);
+pub fn rename_vg(vg: &str, new_name: &str) -> Result<String, LvmError> {
+    let out = run_cmd(VGRENAME_BIN, &[vg, new_name])?;
+    Ok(cmd_output(out, "Renamed vg"))
 }
 
 //
-// Return all volumne groups found.
+// lvcreate -s -L|-l size -n name vg/origin, -l for percentages.
 //
-pub fn get_vgs() -> Vec<String> {
-    let args: [&str; 11] = [
-        "--headings",
-        "none",
-        "--separator",
-        ",",
-        "--reportformat",
-        "basic",
-        "-a",
-        "--units",
-        "B",
-        "-o",
-        "vg_name",
-    ];
+pub fn snapshot_args(vg: &str, origin: &str, snap: &LvmSnapshot) -> Vec<String> {
+    let size_opt = if snap.size_unit.starts_with('%') {
+        "-l"
+    } else {
+        "-L"
+    };
+    vec![
+        "-s".to_string(),
+        size_opt.to_string(),
+        snap.size_arg(),
+        "-n".to_string(),
+        snap.name.clone(),
+        format!("{vg}/{origin}"),
+    ]
+}
+
+pub fn create_snapshot(vg: &str, origin: &str, snap: &LvmSnapshot) -> Result<String, LvmError> {
+    let args = snapshot_args(vg, origin, snap);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let out = run_cmd(LVCREATE_BIN, &args)?;
+    Ok(cmd_output(out, "Created snapshot"))
+}
+
+pub fn merge_snapshot(vg: &str, snap: &str) -> Result<String, LvmError> {
+    let snap_path = format!("{vg}/{snap}");
+    let out = run_cmd(LVCONVERT_BIN, &["--merge", &snap_path])?;
+    // 'Merging of volume vg/snap started.', or when it is delayed as the
+    // origin is open, when it will happen.
+    Ok(cmd_output(out, "Merged snapshot"))
+}
+
+//
+// lvcreate [--type cache-pool] -L size -n name vg fast_pv, the fast lv.
+//
+pub fn cache_create_args(vg: &str, cache: &LvmCache) -> Vec<String> {
+    let mut args = Vec::new();
+    if cache.cache_type == LvmCacheType::CachePool {
+        args.push("--type".to_string());
+        args.push("cache-pool".to_string());
+    }
+    args.extend([
+        "-L".to_string(),
+        format!("{}{}", cache.size, cache.size_unit),
+        "-n".to_string(),
+        cache.name.clone(),
+        vg.to_string(),
+        cache.fast_pv.clone(),
+    ]);
+    args
+}
 
-    match run_cmd(VGDISPLAY_BIN, &args) {
-        Ok(o) => {
-            let s: std::borrow::Cow<'_, str> = String::from_utf8_lossy(&o.stdout);
-            match parse_vgso(&s) {
-                Ok(s) => s,
-                Err(e) => panic!("{e}"),
+//
+// lvconvert -y --type cache|writecache --cachepool|--cachevol vg/fast
+// [--cachemode mode] [--cachepolicy policy] vg/lv
+//
+pub fn cache_attach_args(vg: &str, lv: &str, cache: &LvmCache) -> Vec<String> {
+    let fast_opt = match cache.cache_type {
+        LvmCacheType::CachePool => "--cachepool",
+        _ => "--cachevol",
+    };
+    let mut args = vec![
+        "-y".to_string(),
+        "--type".to_string(),
+        cache.cache_type.segtype().to_string(),
+        fast_opt.to_string(),
+        format!("{vg}/{}", cache.name),
+    ];
+    if cache.cache_type != LvmCacheType::Writecache {
+        for (opt, value) in [
+            ("--cachemode", &cache.cache_mode),
+            ("--cachepolicy", &cache.cache_policy),
+        ] {
+            if !value.is_empty() {
+                args.push(opt.to_string());
+                args.push(value.clone());
             }
         }
-        Err(e) => panic!("{e}"),
     }
+    args.push(format!("{vg}/{lv}"));
+    args
 }
 
 //
-// PV, VG
-// /dev/sda1,vg01
-// /dev/sdx,
-// /dev/sd1
+// lvconvert -y --splitcache|--uncache vg/lv
 //
-fn parse_pvso(s: &std::borrow::Cow<'_, str>) -> Result<Vec<LvmPVData>, &'static str> {
-    if s.len() < 1 {
-        return Ok(Vec::<LvmPVData>::new());
+pub fn cache_detach_args(vg: &str, lv: &str, keep: bool) -> Vec<String> {
+    let opt = if keep { "--splitcache" } else { "--uncache" };
+    vec!["-y".to_string(), opt.to_string(), format!("{vg}/{lv}")]
+}
+
+pub fn attach_cache(vg: &str, lv: &str, cache: &LvmCache) -> Result<String, LvmError> {
+    let args = cache_create_args(vg, cache);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let mut out = run_cmd(LVCREATE_BIN, &args)?;
+
+    let args = cache_attach_args(vg, lv, cache);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match run_cmd(LVCONVERT_BIN, &args) {
+        Ok(attached) => out += &attached,
+        Err(e) => {
+            // Don't leave the fast lv behind, the attach error is what matters.
+            let _ = remove_lv(vg, &cache.name);
+            return Err(e);
+        }
     }
+    Ok(cmd_output(out, "Attached cache"))
+}
 
-    let res: Result<Vec<LvmPVData>, &'static str> = s
-        .lines()
-        .filter(|&line| !line.trim().is_empty())
-        .map(|line| {
-            let data: Vec<&str> = line.trim().split(",").collect();
-            let err = "Could not parse 'pv_name' from lines, unexpected";
-            let pv_name = data.get(0).ok_or(err)?.trim().to_string();
-            let vg_name = data.get(1).unwrap_or(&"").trim().to_string(); // ok, pv may not have vg
-            let lvm_pv_data: LvmPVData = {
-                LvmPVData {
-                    pv_name: pv_name,
-                    vg_name: vg_name,
-                }
-            };
-            Ok::<LvmPVData, &'static str>(lvm_pv_data)
-        })
-        .collect();
+pub fn detach_cache(vg: &str, lv: &str, keep: bool) -> Result<String, LvmError> {
+    let args = cache_detach_args(vg, lv, keep);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let out = run_cmd(LVCONVERT_BIN, &args)?;
+    Ok(cmd_output(
+        out,
+        if keep { "Split cache" } else { "Removed cache" },
+    ))
+}
+
+//
+// lvchange --syncaction check|repair vg/lv
+//
+pub fn raid_syncaction_args(vg: &str, lv: &str, action: LvmSyncAction) -> Vec<String> {
+    vec![
+        "--syncaction".to_string(),
+        action.text().to_string(),
+        format!("{vg}/{lv}"),
+    ]
+}
 
-    return res;
-}
-
-pub fn get_pvs() -> Vec<LvmPVData> {
-    let args: [&str; 11] = [
-        "--headings",
-        "none",
-        "--separator",
-        ",",
-        "--reportformat",
-        "basic",
-        "-a",
-        "--units",
-        "B",
-        "-o",
-        "pv_name,vg_name",
+//
+// lvconvert -y --repair vg/lv [pv ...]
+//
+pub fn raid_repair_args(vg: &str, lv: &str, pvl: &[String]) -> Vec<String> {
+    let mut args = vec![
+        "-y".to_string(),
+        "--repair".to_string(),
+        format!("{vg}/{lv}"),
     ];
+    args.extend(pvl.iter().cloned());
+    args
+}
 
-    match run_cmd(PVS_BIN, &args) {
-        Ok(o) => {
-            let s: std::borrow::Cow<'_, str> = String::from_utf8_lossy(&o.stdout);
-            let result: Result<Vec<LvmPVData>, &'static str> = parse_pvso(&s);
-            match result {
-                Ok(pvs) => {
-                    return pvs;
-                }
-                Err(e) => panic!("{e}"),
-            }
-        }
-        Err(e) => panic!("{e}"),
-    }
-}
-
-// output ex:
-// LV,VG,LSize,Attr,Type,LV UUID,#Str,#DStr
-// [lvpub_rmeta_3],vg04_1tbdisks,4194304B,ewi-aor---,linear,qhuhv2-Kdro-dySw-L8d4-uSLJ-8ReD-rgYbD9,1,1,parentlv,/dev/sda1:7424-9983
-// lvpub,vg04_1tbdisks,536875106304B,rwi-aor---,raid5,0iPPdB-17pl-7SKc-3rwU-EiBd-10fZ-WheGSZ,4,3,,[lvpub_rimage_0]:0-42666,[lvpub_rimage_1]:0-42666,[lvpub_rimage_2]:0-42666,[lvpub_rimage_3]:0-42666
-//
-fn parse_lvso(s: &std::borrow::Cow<'_, str>) -> Result<Vec<LvmLvData>, &'static str> {
-    if s.len() < 1 {
-        return Ok(Vec::<LvmLvData>::new());
-    }
-
-    let mut lvdata_set = HashMap::<String, LvmLvData>::new();
-
-    for line in s.lines().filter(|&line| !line.trim().is_empty()) {
-        let err = "failed to parse Logical Volumne data";
-        let mut data: Vec<&str> = line.trim().split(",").collect();
-        let mut d_iter: std::slice::IterMut<'_, &str> = data.iter_mut();
-        let lv_name = d_iter.next().ok_or_else(|| err)?.to_string();
-        let vg_name = d_iter.next().ok_or_else(|| err)?.to_string();
-        let size = parseu64_ds(d_iter.next().ok_or_else(|| err)?)?;
-        let attr = d_iter.next().ok_or_else(|| err)?.to_string();
-        let segtype = d_iter.next().ok_or_else(|| err)?.to_string();
-        let uuid = d_iter.next().ok_or_else(|| err)?.to_string();
-        let stripes = parseu16(
-            d_iter
-                .next()
-                .ok_or_else(|| "failed to parse 'stripes'")?
-                .trim(),
-        )?;
-        let data_stripes = parseu16(
-            d_iter
-                .next()
-                .ok_or_else(|| "failed to parse 'data_stripes")?
-                .trim(),
-        )?;
-        let parent_lv = d_iter.next().ok_or_else(|| err)?.to_string();
-        let lv_data = LvmLvData {
-            lv_name: lv_name.clone(),
-            vg_name: vg_name,
-            size: size,
-            attr: attr,
-            segtype: segtype,
-            uuid: uuid,
-            lv_segs: Vec::<LvmlvSegData>::new(),
-            stripes: stripes,
-            data_stripes: data_stripes,
-            parent_lv: parent_lv,
-        };
+//
+// lvconvert -y --replace old_pv vg/lv [pv ...]
+//
+pub fn raid_replace_args(vg: &str, lv: &str, old_pv: &str, pvl: &[String]) -> Vec<String> {
+    let mut args = vec![
+        "-y".to_string(),
+        "--replace".to_string(),
+        old_pv.to_string(),
+        format!("{vg}/{lv}"),
+    ];
+    args.extend(pvl.iter().cloned());
+    args
+}
 
-        // add lv to map if it does not exist before.
-        if !lvdata_set.contains_key(&lv_name) {
-            lvdata_set.insert(lv_name.clone(), lv_data);
-        }
+pub fn raid_sync_action(vg: &str, lv: &str, action: LvmSyncAction) -> Result<String, LvmError> {
+    let args = raid_syncaction_args(vg, lv, action);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let out = run_cmd(LVCHANGE_BIN, &args)?;
+    Ok(cmd_output(out, &format!("Started {}", action.text())))
+}
 
-        // now remaining are devices
-        let v_lv_segs: Vec<LvmlvSegData> = parse_lvso_segs(d_iter)?;
+pub fn repair_raid(vg: &str, lv: &str, pvl: &[String]) -> Result<String, LvmError> {
+    let args = raid_repair_args(vg, lv, pvl);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let out = run_cmd(LVCONVERT_BIN, &args)?;
+    Ok(cmd_output(out, "Repaired raid, resyncing"))
+}
+
+pub fn replace_raid_pv(
+    vg: &str,
+    lv: &str,
+    old_pv: &str,
+    pvl: &[String],
+) -> Result<String, LvmError> {
+    let args = raid_replace_args(vg, lv, old_pv, pvl);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let out = run_cmd(LVCONVERT_BIN, &args)?;
+    Ok(cmd_output(out, &format!("Replaced {old_pv}, resyncing")))
+}
 
-        // add segs to lvdata map.
-        let mut lv_data = lvdata_set.get(&lv_name).unwrap().clone(); // just unwrap here, unexpected to fail here.
-        for lv_segs in v_lv_segs {
-            lv_data.lv_segs.push(lv_segs);
+//
+// Segtypes lvconvert takes an lv over to, by lvmraid(7). Linear and mirror
+// go through raid1, the striped ones add or drop parity and mirrors. Their own
+// segtype changes the stripes (reshape). A two image raid1 and raid5 are alike
+// on disk and convert into each other.
+//
+pub fn convert_targets(lv: &LvmLvData) -> Vec<&'static str> {
+    let mut targets = match lv.segtype.as_str() {
+        "linear" => vec!["raid1", "mirror"],
+        "mirror" => vec!["linear", "raid1"],
+        "raid1" => vec!["linear", "raid1", "mirror"],
+        "striped" => vec!["raid0", "raid5", "raid6", "raid10"],
+        "raid0" => vec!["striped", "raid5", "raid6", "raid10"],
+        "raid4" => vec!["raid4", "raid5", "raid6", "raid0", "striped"],
+        "raid5" => vec!["raid5", "raid6", "raid0", "striped"],
+        "raid6" => vec!["raid6", "raid5", "raid0", "striped"],
+        "raid10" => vec!["raid10", "raid0", "striped"],
+        _ => vec![],
+    };
+    if lv.stripes == 2 {
+        match lv.segtype.as_str() {
+            "raid1" => targets.push("raid5"),
+            "raid4" | "raid5" => targets.push("raid1"),
+            _ => {}
         }
-        lvdata_set.insert(lv_name.clone(), lv_data);
     }
+    targets
+}
+
+//
+// lvconvert -y --type segtype [-m mirrors] [--stripes stripes] vg/lv [pv ...]
+//
+pub fn convert_lv_args(vg: &str, lv: &str, convert: &LvmConvert, pvl: &[String]) -> Vec<String> {
+    let mut args = vec![
+        "-y".to_string(),
+        "--type".to_string(),
+        convert.segtype.clone(),
+    ];
+    if let Some(mirrors) = convert.mirrors {
+        args.push("-m".to_string());
+        args.push(mirrors.to_string());
+    }
+    if let Some(stripes) = convert.stripes {
+        args.push("--stripes".to_string());
+        args.push(stripes.to_string());
+    }
+    args.push(format!("{vg}/{lv}"));
+    args.extend(pvl.iter().cloned());
+    args
+}
 
-    let res: Vec<LvmLvData> = lvdata_set.values().cloned().collect::<Vec<LvmLvData>>();
+pub fn convert_lv(
+    vg: &str,
+    lv: &str,
+    convert: &LvmConvert,
+    pvl: &[String],
+) -> Result<String, LvmError> {
+    let args = convert_lv_args(vg, lv, convert, pvl);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let out = run_cmd(LVCONVERT_BIN, &args)?;
+    Ok(cmd_output(
+        out,
+        &format!("Converted to {}", convert.segtype),
+    ))
+}
 
-    return Ok(res);
+//
+// Size option value with the largest unit it is a whole number of, e.g.
+// 4194304 is '4M'.
+//
+pub fn exact_size_arg(bytes: u64) -> String {
+    ["T", "G", "M", "K"]
+        .into_iter()
+        .filter_map(|unit| Some((unit, size_unit_bytes(unit)?)))
+        .find(|(_, n)| bytes >= *n && bytes.is_multiple_of(*n))
+        .map(|(unit, n)| format!("{}{unit}", bytes / n))
+        .unwrap_or_else(|| format!("{bytes}B"))
 }
 
 //
-// Get '/dev/sda1:7424-9983' part from
-// [lvpub_rmeta_3],vg04_1tbdisks,4194304B,ewi-aor---,linear,qhuhv2-Kdro-dySw-L8d4-uSLJ-8ReD-rgYbD9,1,1,parentlv,/dev/sda1:7424-9983
-// or [lvpub_rimage_0]:0-42666,[lvpub_rimage_1]:0-42666 ...
+// pvcreate [--pvmetadatacopies n] [--dataalignment size] dev. No -y, pvcreate
+// refuses devices with a signature on them instead of wiping it.
 //
-fn parse_lvso_segs(
-    d_iter: std::slice::IterMut<'_, &str>,
-) -> Result<Vec<LvmlvSegData>, &'static str> {
-    let mut v_lv_segs: Vec<LvmlvSegData> = Vec::<LvmlvSegData>::new();
+pub fn pvcreate_args(dev: &str, pv: &LvmPvCreate) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(copies) = pv.metadata_copies {
+        args.push("--pvmetadatacopies".to_string());
+        args.push(copies.to_string());
+    }
+    if let Some(alignment) = pv.data_alignment {
+        args.push("--dataalignment".to_string());
+        args.push(exact_size_arg(alignment));
+    }
+    args.push(dev.to_string());
+    args
+}
 
-    for seg in d_iter {
-        let seg_data: Vec<&str> = seg.trim().split(",").collect();
-
-        for dev_data in seg_data {
-            let pos = dev_data
-                .find(":")
-                .ok_or_else(|| "failed to extents for pvdev")?;
-            let pvdev: &str = &dev_data[0..pos];
-            let remainder: &str = &dev_data[(pos + 1)..dev_data.len()];
-            let extent_pos = remainder.find("-");
-            let mut ext_start: u64 = 0;
-            let mut ext_end: u64 = 0;
-            match extent_pos {
-                Some(extent_pos) => {
-                    ext_start = remainder[0..(extent_pos)].parse::<u64>().unwrap_or(0);
-                    ext_end = remainder[(extent_pos + 1)..remainder.len()]
-                        .parse::<u64>()
-                        .unwrap_or(0);
-                }
-                None => (), // noop, init to 0 already
-            }
+//
+// vgcreate [-s extent_size] [--alloc policy] vg pv ...
+//
+pub fn vgcreate_args(vg: &str, pvl: &[String], opts: &LvmVgCreate) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(extent_size) = opts.extent_size {
+        args.push("-s".to_string());
+        args.push(exact_size_arg(extent_size));
+    }
+    if opts.alloc != AllocPolicy::Unset {
+        args.push("--alloc".to_string());
+        args.push(opts.alloc.text().to_string());
+    }
+    args.push(vg.to_string());
+    args.extend(pvl.iter().cloned());
+    args
+}
 
-            v_lv_segs.push(LvmlvSegData {
-                pvdev: pvdev.to_string(),
-                pv_start_pe: ext_start,
-                size_pe: ext_end,
-            });
-        }
+pub fn create_pv(dev: &str, pv: &LvmPvCreate) -> Result<String, LvmError> {
+    let args = pvcreate_args(dev, pv);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let out = run_cmd(PVCREATE_BIN, &args)?;
+    Ok(cmd_output(out, "Created pv"))
+}
+
+pub fn create_vg(vg: &str, pvl: &[String], opts: &LvmVgCreate) -> Result<String, LvmError> {
+    let args = vgcreate_args(vg, pvl, opts);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let out = run_cmd(VGCREATE_BIN, &args)?;
+    Ok(cmd_output(out, "Created vg"))
+}
+
+//
+// vgextend vg pv ..., vgreduce likewise.
+//
+pub fn extend_vg(vg: &str, pvl: &[String]) -> Result<String, LvmError> {
+    let mut args = vec![vg];
+    args.extend(pvl.iter().map(String::as_str));
+    let out = run_cmd(VGEXTEND_BIN, &args)?;
+    Ok(cmd_output(out, "Extended vg"))
+}
+
+pub fn reduce_vg(vg: &str, pvl: &[String]) -> Result<String, LvmError> {
+    let mut args = vec![vg];
+    args.extend(pvl.iter().map(String::as_str));
+    let out = run_cmd(VGREDUCE_BIN, &args)?;
+    Ok(cmd_output(out, "Reduced vg"))
+}
+
+//
+// pvmove arguments, e.g. -b -n lvdata /dev/sdb1:0-2559 /dev/sdc1
+// -b returns once the move is set up, lvmpolld copies the extents.
+//
+pub fn pvmove_args(mv: &LvmPvMove) -> Vec<String> {
+    let mut args = vec!["-b".to_string()];
+    if !mv.lv_name.is_empty() {
+        args.push("-n".to_string());
+        args.push(mv.lv_name.clone());
+    }
+    match mv.pe_range {
+        Some((first, last)) => args.push(format!("{}:{first}-{last}", mv.src_pv)),
+        None => args.push(mv.src_pv.clone()),
     }
+    args.extend(mv.dest_pvs.iter().cloned());
+    args
+}
 
-    Ok(v_lv_segs)
+pub fn move_pv(mv: &LvmPvMove) -> Result<String, LvmError> {
+    let args = pvmove_args(mv);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let out = run_cmd(PVMOVE_BIN, &args)?;
+    Ok(cmd_output(out, "Started pvmove"))
+}
+
+pub fn abort_pvmove() -> Result<String, LvmError> {
+    let out = run_cmd(PVMOVE_BIN, &["--abort"])?;
+    Ok(cmd_output(out, "Aborted pvmove"))
 }
 
 //
-// lvs --headings none --separator ',' -a --units B -o lv_name,vg_name,size,attr,segtype,uuid,stripes,data_stripes
+// pvmove lvs of the vg, the moves in progress.
+//
+pub fn pvmoves<'a>(vg: &str, lvs: &'a [LvmLvData]) -> Vec<&'a LvmLvData> {
+    lvs.iter()
+        .filter(|lv| lv.vg_name == vg && !lv.move_pv.is_empty())
+        .collect()
+}
+
 //
-// ex:
-// [lvdata_mpriv_rmeta_3],vgdata01,4194304B,ewi-aor---,linear,sDjQnR-1sCa-zHKG-mWQC-rO5C-pb7a-OrEjIE,1,1,lvdata_mpriv
+// Characters allowed when typing a vg or lv name. lvm also takes '.' and '+',
+// the forms stick to these.
 //
-pub fn get_lvs() -> Vec<LvmLvData> {
-    let args: [&str; 11] = [
-        "--headings",
-        "none",
-        "--separator",
-        ",",
-        "--reportformat",
-        "basic",
-        "-a",
-        "--units",
-        "B",
-        "-o",
-        "lv_name,vg_name,size,attr,segtype,uuid,stripes,data_stripes,lv_parent,seg_le_ranges",
-    ];
+pub fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
 
-    match run_cmd(LVS_BIN, &args) {
-        Ok(o) => {
-            let s: std::borrow::Cow<'_, str> = String::from_utf8_lossy(&o.stdout);
-            let result: Result<Vec<LvmLvData>, &'static str> = parse_lvso(&s);
-            match result {
-                Ok(pvs) => {
-                    return pvs;
-                }
-                Err(e) => panic!("{e}"),
-            }
-        }
-        Err(e) => panic!("{e}"),
+pub fn check_vg_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Name is empty.".to_string());
+    }
+    if name.len() > NAME_LEN_MAX {
+        return Err(format!("Name is longer than {NAME_LEN_MAX} characters."));
+    }
+    if name.starts_with('-') {
+        return Err("Name can not start with '-'.".to_string());
     }
+    if let Some(c) = name.chars().find(|c| !is_name_char(*c)) {
+        return Err(format!("'{c}' is not allowed in a name."));
+    }
+    Ok(())
 }
 
 //
-// Create logical volumne
+// As for vgs, and not like the names lvm gives to its own lvs.
 //
-pub fn create_lv(
-    lv: &String,
-    vg: &String,
-    size: u64,
-    size_unit: &String,
-    segtype: &String,
-    pvl: &Vec<String>,
-    extra: &Vec<LvmExtraArg>,
-) -> Result<String, String> {
-    let mut args = Vec::<&str>::new();
-    // lvcreate --type raid1 -m 1 -L 10G -n lvvirt_archjol vg04_1tbdisks /dev/sdd1 /dev/sde1
-    args.push("--type");
-    args.push(segtype);
-    for e in extra {
-        args.push(&e.opt);
-        args.push(&e.value);
-    }
-    args.push("-L");
-    let size_str = format!("{}{}", size, size_unit);
-    args.push(&size_str);
-    args.push("-n");
-    args.push(lv);
-    args.push(vg);
-    let mut pvs = String::new();
-    for pvdev in pvl {
-        pvs = pvdev.to_owned() + &" ".to_string();
-    }
-    if pvs.len() > 0 {
-        args.push(pvs.trim());
-    }
-
-    match run_cmd(LVCREATE_BIN, &args) {
-        Ok(o) => {
-            if !o.status.success() {
-                let es: std::borrow::Cow<'_, str> = String::from_utf8_lossy(&o.stderr);
-                return Err(es.into_owned());
-            }
-            Ok("Created lv".to_string())
-        }
-        Err(e) => panic!("{e}"),
+pub fn check_lv_name(name: &str) -> Result<(), String> {
+    check_vg_name(name)?;
+    if let Some(prefix) = LV_RESERVED_PREFIXES.iter().find(|p| name.starts_with(*p)) {
+        return Err(format!("Names starting with '{prefix}' are reserved."));
+    }
+    if let Some(suffix) = LV_RESERVED_SUFFIXES.iter().find(|s| name.contains(*s)) {
+        return Err(format!("'{suffix}' is reserved for lvm internal lvs."));
     }
+    Ok(())
 }
 
 // Convinient functions
@@ -454,7 +2315,7 @@ pub fn find_pvs_by_vg(vg_name: &String, pv_list: &Vec<LvmPVData>) -> Vec<String>
         }
     }
 
-    return pvs_in_vg_list;
+    pvs_in_vg_list
 }
 
 pub fn find_lvs_by_vg(vg_name: &String, lv_list: &Vec<LvmLvData>) -> Vec<String> {
@@ -466,7 +2327,7 @@ pub fn find_lvs_by_vg(vg_name: &String, lv_list: &Vec<LvmLvData>) -> Vec<String>
         }
     }
 
-    return lvs_in_vg_list;
+    lvs_in_vg_list
 }
 
 pub fn get_lvinfo_by_vg(vg_name: &String, lv_list: &Vec<LvmLvData>) -> Vec<LvmLvData> {
@@ -478,22 +2339,37 @@ pub fn get_lvinfo_by_vg(vg_name: &String, lv_list: &Vec<LvmLvData>) -> Vec<LvmLv
         }
     }
 
-    return lvs_in_vg_list;
+    lvs_in_vg_list
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::lvm::{LvmVgData, LvmlvSegData, parse_lvso, parse_pvso, parse_vgdo, parse_vgso};
+    use crate::lvm::{
+        AllocPolicy, AttrLevel, LvAttr, LvHealth, LvPermissions, LvState, LvTargetType,
+        LvVolumeType, LvmCache, LvmCacheType, LvmConvert, LvmError, LvmExtraArg, LvmLayout,
+        LvmLvData, LvmPvCreate, LvmPvMove, LvmResize, LvmSnapshot, LvmSyncAction, LvmVgCreate,
+        LvmVgData, PvAttr, VgAttr, cache_attach_args, cache_create_args, cache_detach_args,
+        check_lv_name, check_vg_name, cmd_output, convert_lv_args, convert_targets, create_lv_args,
+        exact_size_arg, parse_lvso, parse_pvso, parse_size_arg, parse_vgdo, parse_vgso,
+        pvcreate_args, pvmove_args, pvmoves, raid_repair_args, raid_replace_args,
+        raid_syncaction_args, resize_lv_args, run_cmd, run_report, snapshot_args,
+        thin_provisioning, vgcreate_args,
+    };
 
     #[test]
     fn test_parse_vgdo() {
-        // VG,VSize,VFree,#PV,Attr,VG UUID
-        let s =
-            "vgssd_virt,120028397568B,54530146304B,1,wz--n-,ZFcFCx-fW2F-sWq6-PVy1-8PN2-2CVt-epVMVt";
-        let s: std::borrow::Cow<'_, str> = std::borrow::Cow::Borrowed(s);
-
-        let lvm_vg_data: LvmVgData = parse_vgdo(&s).expect("error");
+        let s = r#"{
+      "report": [
+          {
+              "vg": [
+                  {"vg_name":"vgssd_virt", "vg_size":120028397568, "vg_free":54530146304, "pv_count":1, "vg_attr":"wz--n-", "vg_uuid":"ZFcFCx-fW2F-sWq6-PVy1-8PN2-2CVt-epVMVt"}
+              ]
+          }
+      ]
+  }"#;
+
+        let lvm_vg_data: LvmVgData = parse_vgdo(s).expect("error");
         assert_eq!("vgssd_virt", lvm_vg_data.name);
         assert_eq!(120028397568, lvm_vg_data.size);
         assert_eq!(54530146304, lvm_vg_data.free);
@@ -501,94 +2377,757 @@ mod tests {
         assert_eq!("wz--n-", lvm_vg_data.attr);
         assert_eq!("ZFcFCx-fW2F-sWq6-PVy1-8PN2-2CVt-epVMVt", lvm_vg_data.uuid);
 
-        // To few options in result
-        let s =
-            "vgssd_virt,120028397568B,54530146304B,wz--n-,ZFcFCx-fW2F-sWq6-PVy1-8PN2-2CVt-epVMVt";
-        let s: std::borrow::Cow<'_, str> = std::borrow::Cow::Borrowed(s);
+        // Plain json format, values as strings with unit suffix
+        let s = r#"{"report": [{"vg": [{"vg_name":"vgssd_virt", "vg_size":"120028397568B", "vg_free":"54530146304B", "pv_count":"1", "vg_attr":"wz--n-", "vg_uuid":"ZFcFCx-fW2F-sWq6-PVy1-8PN2-2CVt-epVMVt"}]}]}"#;
+        let lvm_vg_data: LvmVgData = parse_vgdo(s).expect("error");
+        assert_eq!(120028397568, lvm_vg_data.size);
+        assert_eq!(1, lvm_vg_data.pv_count);
 
-        let result = parse_vgdo(&s);
+        // Sizes in other units, as captured without --units b
+        let s = r#"{"report": [{"vg": [{"vg_name":"vg01", "vg_size":"<1.50t", "vg_free":"2048s", "pv_count":"1"}]}]}"#;
+        let lvm_vg_data: LvmVgData = parse_vgdo(s).expect("error");
+        assert_eq!(1536 * 1024 * 1024 * 1024, lvm_vg_data.size);
+        assert_eq!(1024 * 1024, lvm_vg_data.free);
+        let s = r#"{"report": [{"vg": [{"vg_name":"vg01", "vg_size":"1.5G", "vg_free":"1.5x"}]}]}"#;
+        assert!(parse_vgdo(s).is_err());
+
+        // No vg in report
+        let s = r#"{"report": [{"vg": []}]}"#;
+        let result = parse_vgdo(s);
         assert!(result.is_err());
 
         // bad data in size
-        // VG,VSize,VFree,#PV,Attr,VG UUID
-        let s = "vgssd_virt,120028a397568B,54530146304B,1,wz--n-,ZFcFCx-fW2F-sWq6-PVy1-8PN2-2CVt-epVMVt";
-        let s: std::borrow::Cow<'_, str> = std::borrow::Cow::Borrowed(s);
-        let result = parse_vgdo(&s);
+        let s = r#"{"report": [{"vg": [{"vg_name":"vgssd_virt", "vg_size":"120028a397568B", "vg_free":54530146304, "pv_count":1, "vg_attr":"wz--n-", "vg_uuid":"ZFcFCx-fW2F-sWq6-PVy1-8PN2-2CVt-epVMVt"}]}]}"#;
+        let result = parse_vgdo(s);
+        assert!(result.is_err());
+
+        // Not json at all, e.g. old basic format
+        let s =
+            "vgssd_virt,120028397568B,54530146304B,1,wz--n-,ZFcFCx-fW2F-sWq6-PVy1-8PN2-2CVt-epVMVt";
+        let result = parse_vgdo(s);
         assert!(result.is_err());
     }
 
     #[test]
     fn test_parse_vgso() {
-        let s = "  vg03_backups\n  vg04_1tbdisks\n  vgdata01\n  vgroot";
-        let s: std::borrow::Cow<'_, str> = std::borrow::Cow::Borrowed(s);
-
-        let lvm_vgs = parse_vgso(&s).expect("error");
-        assert_eq!(lvm_vgs.get(0).unwrap(), "vg03_backups");
+        let s = r#"{
+      "report": [
+          {
+              "vg": [
+                  {"vg_name":"vg03_backups"},
+                  {"vg_name":"vg04_1tbdisks"},
+                  {"vg_name":"vgdata01"},
+                  {"vg_name":"vgroot"}
+              ]
+          }
+      ]
+  }"#;
+
+        let lvm_vgs = parse_vgso(s).expect("error");
+        assert_eq!(lvm_vgs.first().unwrap(), "vg03_backups");
         assert_eq!(lvm_vgs.get(1).unwrap(), "vg04_1tbdisks");
         assert_eq!(lvm_vgs.get(2).unwrap(), "vgdata01");
         assert_eq!(lvm_vgs.get(3).unwrap(), "vgroot");
         assert_eq!(lvm_vgs.len(), 4);
 
         let s = "";
-        let s: std::borrow::Cow<'_, str> = std::borrow::Cow::Borrowed(s);
-        let lvm_vgs = parse_vgso(&s).expect("error");
+        let lvm_vgs = parse_vgso(s).expect("error");
+        assert_eq!(lvm_vgs.len(), 0);
+
+        let s = r#"{"report": [{"vg": []}]}"#;
+        let lvm_vgs = parse_vgso(s).expect("error");
         assert_eq!(lvm_vgs.len(), 0);
     }
 
     #[test]
     fn test_parse_pvso() {
-        ////
-        // PV, VG
-        // /dev/sda1,vg01
-        // /dev/sdx,
-        // /dev/sd1
-
-        let s = "  /dev/sda1,vg01\n  /dev/sdx,\n  /dev/sdb2";
-        let s: std::borrow::Cow<'_, str> = std::borrow::Cow::Borrowed(s);
-
-        let lvm_pvs = parse_pvso(&s).expect("error");
-        assert_eq!(lvm_pvs.get(0).unwrap().pv_name, "/dev/sda1");
-        assert_eq!(lvm_pvs.get(0).unwrap().vg_name, "vg01");
+        let s = r#"{
+      "report": [
+          {
+              "pv": [
+                  {"pv_name":"/dev/sda1", "vg_name":"vg01"},
+                  {"pv_name":"/dev/sdx", "vg_name":""},
+                  {"pv_name":"/dev/sdb2", "vg_name":null}
+              ]
+          }
+      ]
+  }"#;
+
+        let lvm_pvs = parse_pvso(s).expect("error");
+        assert_eq!(lvm_pvs.first().unwrap().pv_name, "/dev/sda1");
+        assert_eq!(lvm_pvs.first().unwrap().vg_name, "vg01");
         assert_eq!(lvm_pvs.get(1).unwrap().pv_name, "/dev/sdx");
+        assert_eq!(lvm_pvs.get(1).unwrap().vg_name, "");
         assert_eq!(lvm_pvs.get(2).unwrap().pv_name, "/dev/sdb2");
+        assert_eq!(lvm_pvs.get(2).unwrap().vg_name, "");
         assert_eq!(lvm_pvs.len(), 3);
 
-        // Ngegative test
-        let s = "  /dev/sda1,vg01\n  \n  /dev/sdb2";
-        let s: std::borrow::Cow<'_, str> = std::borrow::Cow::Borrowed(s);
-
-        let lvm_pvs = parse_pvso(&s).expect("error");
-        assert_eq!(lvm_pvs.len(), 2);
-    }
-
-    // ex:
-    // [lvpub_rmeta_3],vg04_1tbdisks,4194304B,ewi-aor---,linear,qhuhv2-Kdro-dySw-L8d4-uSLJ-8ReD-rgYbD9,1,1
-    // lvpub,vg04_1tbdisks,536875106304B,rwi-aor---,raid5,0iPPdB-17pl-7SKc-3rwU-EiBd-10fZ-WheGSZ,4,3
-    // [lvpub_rimage_0],vg04_1tbdisks,178958368768B,iwi-aor---,linear,pIfgYg-TSAx-zinr-EyUh-AO8D-VezQ-FUDRGR,1,1
-    // [lvpub_rimage_1],vg04_1tbdisks,178958368768B,iwi-aor---,linear,CfoQsY-v1Py-SaN4-KoGF-JmDk-1aNe-Q82Np9,1,1
-    // [lvpub_rimage_2],vg04_1tbdisks,178958368768B,iwi-aor---,linear,Z4fbfS-DEJy-SBaU-qo89-XIH0-oRFW-kpOgBj,1,1
-    // [lvpub_rimage_3],vg04_1tbdisks,178958368768B,iwi-aor---,linear,XFDDI5-0pL2-SO6Y-NS8P-TJJv-jSGk-KRv1gz,1,1
-    // [lvpub_rmeta_0],vg04_1tbdisks,4194304B,ewi-aor---,linear,Rv8iwp-YEGJ-b9V4-ekfe-blD0-5FdB-7pIrhZ,1,1
-    // [lvpub_rmeta_1],vg04_1tbdisks,4194304B,ewi-aor---,linear,wgwTeO-cW8E-xHuL-Zt0j-xwAa-F5tj-WnJqm9,1,1
-    // [lvpub_rmeta_2],vg04_1tbdisks,4194304B,ewi-aor---,linear,WGltv5-UiaK-n0IT-tLeO-HDyj-jZnx-w0TIrM,1,1
-    // [lvpub_rmeta_3],vg04_1tbdisks,4194304B,ewi-aor---,linear,qhuhv2-Kdro-dySw-L8d4-uSLJ-8ReD-rgYbD9,1,1
+        // Negative test, pv_name is mandatory
+        let s = r#"{"report": [{"pv": [{"vg_name":"vg01"}]}]}"#;
+        assert!(parse_pvso(s).is_err());
+    }
+
+    #[test]
+    fn test_parse_pvso_details() {
+        let s = r#"{
+      "report": [
+          {
+              "pv": [
+                  {"pv_name":"/dev/sdc1", "vg_name":"vg04_1tbdisks", "pv_size":1000203091968, "pv_free":821220040704, "pe_start":1048576, "vg_extent_size":4194304, "pv_pe_count":238467, "pv_pe_alloc_count":42667, "pv_attr":"a--", "pv_uuid":"dRpB3c-1D8M-1mLl-z1lV-vJcT-o3qk-aQ1Pvn", "pv_major":8, "pv_minor":33},
+                  {"pv_name":"[unknown]", "vg_name":"vg04_1tbdisks", "pv_size":1000203091968, "pv_free":0, "pe_start":1048576, "vg_extent_size":4194304, "pv_pe_count":238467, "pv_pe_alloc_count":238467, "pv_attr":"a-m", "pv_uuid":"x", "pv_major":-1, "pv_minor":-1},
+                  {"pv_name":"/dev/sdg", "vg_name":"", "pv_size":"240057409536B", "pv_free":"240057409536B", "pe_start":"0B", "vg_extent_size":"0B", "pv_pe_count":"0", "pv_pe_alloc_count":"0", "pv_attr":"---", "pv_major":"8", "pv_minor":"96"}
+              ]
+          }
+      ]
+  }"#;
+
+        let lvm_pvs = parse_pvso(s).expect("error");
+        let pv = &lvm_pvs[0];
+        assert_eq!(pv.pv_size, 1000203091968);
+        assert_eq!(pv.pv_free, 821220040704);
+        assert_eq!(pv.pe_start, 1048576);
+        assert_eq!(pv.pe_size, 4194304);
+        assert_eq!(pv.pe_count, 238467);
+        assert_eq!(pv.pe_alloc_count, 42667);
+        assert_eq!(pv.pv_attr, "a--");
+        assert_eq!((pv.major, pv.minor), (Some(8), Some(33)));
+
+        // missing device
+        assert_eq!((lvm_pvs[1].major, lvm_pvs[1].minor), (None, None));
+
+        // plain json, orphan pv
+        let pv = &lvm_pvs[2];
+        assert_eq!(pv.pv_size, 240057409536);
+        assert_eq!(pv.pe_size, 0);
+        assert_eq!(pv.pv_uuid, "");
+        assert_eq!((pv.major, pv.minor), (Some(8), Some(96)));
+    }
+
     #[test]
     fn test_parse_lvso() {
-        let s = "  lvbackup,vg03_backups,1073741824000B,-wi-ao----,linear,MlVT0F-L2mW-XCcY-UbJF-QUZJ-MzYe-cQYS9x,1,1,,/dev/sde2:0-255999
-  lvpub,vg04_1tbdisks,536875106304B,rwi-aor---,raid5,0iPPdB-17pl-7SKc-3rwU-EiBd-10fZ-WheGSZ,4,3,,[lvpub_rimage_0]:0-42666,[lvpub_rimage_1]:0-42666,[lvpub_rimage_2]:0-42666,[lvpub_rimage_3]:0-42666
-  [lvpub_rimage_0],vg04_1tbdisks,178958368768B,iwi-aor---,linear,pIfgYg-TSAx-zinr-EyUh-AO8D-VezQ-FUDRGR,1,1,lvpub,/dev/sdc1:2561-45227
-  [lvpub_rimage_1],vg04_1tbdisks,178958368768B,iwi-aor---,linear,CfoQsY-v1Py-SaN4-KoGF-JmDk-1aNe-Q82Np9,1,1,lvpub,/dev/sdd1:1-42667
-  [lvpub_rimage_2],vg04_1tbdisks,178958368768B,iwi-aor---,linear,Z4fbfS-DEJy-SBaU-qo89-XIH0-oRFW-kpOgBj,1,1,lvpub,/dev/sde1:1-42667
-  [lvpub_rimage_3],vg04_1tbdisks,178958368768B,iwi-aor---,linear,XFDDI5-0pL2-SO6Y-NS8P-TJJv-jSGk-KRv1gz,1,1,lvpub,/dev/sdb1:1-42667
-  [lvpub_rmeta_0],vg04_1tbdisks,4194304B,ewi-aor---,linear,Rv8iwp-YEGJ-b9V4-ekfe-blD0-5FdB-7pIrhZ,1,1,lvpub,/dev/sdc1:2560-2560
-  [lvpub_rmeta_1],vg04_1tbdisks,4194304B,ewi-aor---,linear,wgwTeO-cW8E-xHuL-Zt0j-xwAa-F5tj-WnJqm9,1,1,lvpub,/dev/sdd1:0-0
-  [lvpub_rmeta_2],vg04_1tbdisks,4194304B,ewi-aor---,linear,WGltv5-UiaK-n0IT-tLeO-HDyj-jZnx-w0TIrM,1,1,lvpub,/dev/sde1:0-0
-  [lvpub_rmeta_3],vg04_1tbdisks,4194304B,ewi-aor---,linear,qhuhv2-Kdro-dySw-L8d4-uSLJ-8ReD-rgYbD9,1,1,lvpub,/dev/sdb1:0-0";
+        let s = r#"{
+      "report": [
+          {
+              "lv": [
+                  {"lv_name":"lvbackup", "vg_name":"vg03_backups", "lv_size":1073741824000, "lv_attr":"-wi-ao----", "segtype":"linear", "lv_uuid":"MlVT0F-L2mW-XCcY-UbJF-QUZJ-MzYe-cQYS9x", "stripes":1, "data_stripes":1, "lv_parent":"", "seg_le_ranges":["/dev/sde2:0-255999"]},
+                  {"lv_name":"lvpub", "vg_name":"vg04_1tbdisks", "lv_size":536875106304, "lv_attr":"rwi-aor---", "segtype":"raid5", "lv_uuid":"0iPPdB-17pl-7SKc-3rwU-EiBd-10fZ-WheGSZ", "stripes":4, "data_stripes":3, "lv_parent":"", "seg_le_ranges":["[lvpub_rimage_0]:0-42666", "[lvpub_rimage_1]:0-42666", "[lvpub_rimage_2]:0-42666", "[lvpub_rimage_3]:0-42666"]},
+                  {"lv_name":"[lvpub_rimage_0]", "vg_name":"vg04_1tbdisks", "lv_size":178958368768, "lv_attr":"iwi-aor---", "segtype":"linear", "lv_uuid":"pIfgYg-TSAx-zinr-EyUh-AO8D-VezQ-FUDRGR", "stripes":1, "data_stripes":1, "lv_parent":"lvpub", "seg_le_ranges":["/dev/sdc1:2561-45227"]},
+                  {"lv_name":"[lvpub_rimage_1]", "vg_name":"vg04_1tbdisks", "lv_size":178958368768, "lv_attr":"iwi-aor---", "segtype":"linear", "lv_uuid":"CfoQsY-v1Py-SaN4-KoGF-JmDk-1aNe-Q82Np9", "stripes":1, "data_stripes":1, "lv_parent":"lvpub", "seg_le_ranges":["/dev/sdd1:1-42667"]},
+                  {"lv_name":"[lvpub_rimage_2]", "vg_name":"vg04_1tbdisks", "lv_size":178958368768, "lv_attr":"iwi-aor---", "segtype":"linear", "lv_uuid":"Z4fbfS-DEJy-SBaU-qo89-XIH0-oRFW-kpOgBj", "stripes":1, "data_stripes":1, "lv_parent":"lvpub", "seg_le_ranges":["/dev/sde1:1-42667"]},
+                  {"lv_name":"[lvpub_rimage_3]", "vg_name":"vg04_1tbdisks", "lv_size":178958368768, "lv_attr":"iwi-aor---", "segtype":"linear", "lv_uuid":"XFDDI5-0pL2-SO6Y-NS8P-TJJv-jSGk-KRv1gz", "stripes":1, "data_stripes":1, "lv_parent":"lvpub", "seg_le_ranges":["/dev/sdb1:1-42667"]},
+                  {"lv_name":"[lvpub_rmeta_0]", "vg_name":"vg04_1tbdisks", "lv_size":4194304, "lv_attr":"ewi-aor---", "segtype":"linear", "lv_uuid":"Rv8iwp-YEGJ-b9V4-ekfe-blD0-5FdB-7pIrhZ", "stripes":1, "data_stripes":1, "lv_parent":"lvpub", "seg_le_ranges":["/dev/sdc1:2560-2560"]},
+                  {"lv_name":"[lvpub_rmeta_1]", "vg_name":"vg04_1tbdisks", "lv_size":4194304, "lv_attr":"ewi-aor---", "segtype":"linear", "lv_uuid":"wgwTeO-cW8E-xHuL-Zt0j-xwAa-F5tj-WnJqm9", "stripes":1, "data_stripes":1, "lv_parent":"lvpub", "seg_le_ranges":["/dev/sdd1:0-0"]},
+                  {"lv_name":"[lvpub_rmeta_2]", "vg_name":"vg04_1tbdisks", "lv_size":4194304, "lv_attr":"ewi-aor---", "segtype":"linear", "lv_uuid":"WGltv5-UiaK-n0IT-tLeO-HDyj-jZnx-w0TIrM", "stripes":1, "data_stripes":1, "lv_parent":"lvpub", "seg_le_ranges":["/dev/sde1:0-0"]},
+                  {"lv_name":"[lvpub_rmeta_3]", "vg_name":"vg04_1tbdisks", "lv_size":4194304, "lv_attr":"ewi-aor---", "segtype":"linear", "lv_uuid":"qhuhv2-Kdro-dySw-L8d4-uSLJ-8ReD-rgYbD9", "stripes":1, "data_stripes":1, "lv_parent":"lvpub", "seg_le_ranges":["/dev/sdb1:0-0"]}
+              ]
+          }
+      ]
+  }"#;
+
+        let lvm_lvs = parse_lvso(s).expect("error");
+        assert_eq!(lvm_lvs.len(), 10);
 
-        let s: std::borrow::Cow<'_, str> = std::borrow::Cow::Borrowed(s);
+        let lvpub = lvm_lvs.iter().find(|lv| lv.lv_name == "lvpub").unwrap();
+        assert_eq!(lvpub.segtype, "raid5");
+        assert_eq!(lvpub.stripes, 4);
+        assert_eq!(lvpub.data_stripes, 3);
+        assert_eq!(lvpub.lv_segs.len(), 4);
+        assert_eq!(lvpub.lv_segs[0].pvdev, "[lvpub_rimage_0]");
+        assert_eq!(lvpub.lv_segs[0].size_pe, 42667);
+
+        let rmeta = lvm_lvs
+            .iter()
+            .find(|lv| lv.lv_name == "[lvpub_rmeta_0]")
+            .unwrap();
+        assert_eq!(rmeta.parent_lv, "lvpub");
+        assert_eq!(rmeta.lv_segs[0].pvdev, "/dev/sdc1");
+        assert_eq!(rmeta.lv_segs[0].pv_start_pe, 2560);
+        assert_eq!(rmeta.lv_segs[0].size_pe, 1);
+    }
 
-        let lvm_lvs = parse_lvso(&s).expect("error");
+    #[test]
+    fn test_parse_lvso_multiseg() {
+        // Linear lv extended twice, one row per segment.
+        let s = r#"{"report": [{"seg": [
+            {"lv_name":"lvdata", "vg_name":"vg01", "lv_size":3221225472, "lv_attr":"-wi-a-----", "segtype":"linear", "lv_uuid":"aaaaaa", "stripes":1, "data_stripes":1, "lv_parent":"", "seg_le_ranges":["/dev/sda1:0-255"]},
+            {"lv_name":"lvdata", "vg_name":"vg01", "lv_size":3221225472, "lv_attr":"-wi-a-----", "segtype":"linear", "lv_uuid":"aaaaaa", "stripes":1, "data_stripes":1, "lv_parent":"", "seg_le_ranges":["/dev/sdb1:100-355"]},
+            {"lv_name":"lvdata", "vg_name":"vg01", "lv_size":3221225472, "lv_attr":"-wi-a-----", "segtype":"striped", "lv_uuid":"aaaaaa", "stripes":2, "data_stripes":2, "lv_parent":"", "seg_le_ranges":["/dev/sda1:256-319", "/dev/sdb1:0-63"]},
+            {"lv_name":"lvdata", "vg_name":"vg02", "lv_size":1073741824, "lv_attr":"-wi-a-----", "segtype":"linear", "lv_uuid":"bbbbbb", "stripes":1, "data_stripes":1, "lv_parent":"", "seg_le_ranges":"/dev/sdc1:0-255"}
+        ]}]}"#;
+
+        let lvm_lvs = parse_lvso(s).expect("error");
+        assert_eq!(lvm_lvs.len(), 2);
+
+        let lvdata = &lvm_lvs[0];
+        assert_eq!(lvdata.vg_name, "vg01");
+        assert_eq!(lvdata.lv_segs.len(), 4);
+        assert_eq!(lvdata.lv_segs[1].pvdev, "/dev/sdb1");
+        assert_eq!(lvdata.lv_segs[1].pv_start_pe, 100);
+        assert_eq!(lvdata.lv_segs[1].size_pe, 256);
+        assert_eq!(lvdata.lv_segs[3].size_pe, 64);
+
+        // Same name in other vg is another lv, plain json string list
+        let lvdata = &lvm_lvs[1];
+        assert_eq!(lvdata.vg_name, "vg02");
+        assert_eq!(lvdata.lv_segs.len(), 1);
+        assert_eq!(lvdata.lv_segs[0].pvdev, "/dev/sdc1");
+
+        // Empty output
+        let lvm_lvs = parse_lvso("").expect("error");
+        assert_eq!(lvm_lvs.len(), 0);
+    }
 
-        assert_eq!(lvm_lvs.len(), 10);
+    #[test]
+    fn test_parse_lvso_details() {
+        let s = r#"{"report": [{"lv": [
+            {"lv_name":"lvpub", "vg_name":"vg04_1tbdisks", "lv_size":536875106304, "lv_attr":"rwi-aor---", "segtype":"raid5", "lv_uuid":"0iPPdB", "stripes":4, "data_stripes":3, "lv_parent":"", "seg_le_ranges":[], "lv_path":"/dev/vg04_1tbdisks/lvpub", "lv_dm_path":"/dev/mapper/vg04_1tbdisks-lvpub", "lv_kernel_major":253, "lv_kernel_minor":9, "sync_percent":42.50, "lv_health_status":"", "raid_sync_action":"check", "raid_mismatch_count":16, "lv_tags":["backup","nas"], "lv_time":"2024-03-01 12:00:00 +0100", "lv_host":"nas01"},
+            {"lv_name":"lvoff", "vg_name":"vg01", "lv_size":"1073741824", "lv_attr":"-wi-------", "segtype":"linear", "lv_uuid":"x", "lv_path":"/dev/vg01/lvoff", "lv_kernel_major":"-1", "lv_kernel_minor":"-1", "sync_percent":"", "lv_health_status":"partial", "lv_tags":"a,b", "lv_time":"2024-03-01 12:00:00 +0100", "lv_host":"nas01", "origin":"lvdata", "snap_percent":"12.50"},
+            {"lv_name":"pool0", "vg_name":"vg01", "lv_size":"1073741824", "lv_attr":"twi-aotz--", "segtype":"thin-pool", "lv_uuid":"y", "data_percent":"61.20", "metadata_percent":"10.05"},
+            {"lv_name":"thin1", "vg_name":"vg01", "lv_size":"2147483648", "lv_attr":"Vwi-a-tz--", "segtype":"thin", "lv_uuid":"z", "pool_lv":"pool0", "data_percent":"30.60"},
+            {"lv_name":"lvhdd", "vg_name":"vg01", "lv_size":"2147483648", "lv_attr":"Cwi-aoC---", "segtype":"cache", "lv_uuid":"c", "pool_lv":"[fast_cpool]", "cache_mode":"writeback", "cache_policy":"smq", "cache_total_blocks":16384, "cache_used_blocks":8192, "cache_dirty_blocks":12, "cache_read_hits":900, "cache_read_misses":100, "cache_write_hits":"50", "cache_write_misses":"5", "writecache_total_blocks":"", "writecache_free_blocks":null},
+            {"lv_name":"lvwc", "vg_name":"vg01", "lv_size":"2147483648", "lv_attr":"Cwi-a-C---", "segtype":"writecache", "lv_uuid":"w", "pool_lv":"[fastwc_cvol]", "cache_total_blocks":-1, "writecache_total_blocks":262144, "writecache_free_blocks":262000, "writecache_writeback_blocks":0}
+        ]}]}"#;
+
+        let lvm_lvs = parse_lvso(s).expect("error");
+        let lv = &lvm_lvs[0];
+        assert_eq!(lv.path, "/dev/vg04_1tbdisks/lvpub");
+        assert_eq!(lv.dm_path, "/dev/mapper/vg04_1tbdisks-lvpub");
+        assert_eq!((lv.kernel_major, lv.kernel_minor), (Some(253), Some(9)));
+        assert_eq!(lv.sync_percent, Some(42.5));
+        assert_eq!(lv.raid_sync_action, "check");
+        assert_eq!(lv.raid_mismatch_count, Some(16));
+        assert_eq!(lv.tags, vec!["backup", "nas"]);
+        assert_eq!(lv.time, "2024-03-01 12:00:00 +0100");
+        assert_eq!(lv.host, "nas01");
+
+        // inactive, plain json
+        let lv = &lvm_lvs[1];
+        assert_eq!((lv.kernel_major, lv.kernel_minor), (None, None));
+        assert_eq!(lv.sync_percent, None);
+        assert_eq!(lv.health_status, "partial");
+        assert_eq!(lv.raid_sync_action, "");
+        assert_eq!(lv.raid_mismatch_count, None);
+        assert_eq!(lv.tags, vec!["a", "b"]);
+        assert_eq!(lv.origin, "lvdata");
+        assert_eq!(lv.snap_percent, Some(12.5));
+        assert_eq!(lvm_lvs[0].origin, "");
+
+        // thin pool and a thin lv in it
+        let pool = &lvm_lvs[2];
+        assert_eq!(pool.data_percent, Some(61.2));
+        assert_eq!(pool.metadata_percent, Some(10.05));
+        assert_eq!(pool.pool_lv, "");
+        let thin = &lvm_lvs[3];
+        assert_eq!(thin.pool_lv, "pool0");
+        assert_eq!(thin.data_percent, Some(30.6));
+        assert_eq!(thin.metadata_percent, None);
+        assert_eq!(thin_provisioning(pool, &lvm_lvs), Some(2.0));
+        assert_eq!(lvm_lvs[0].data_percent, None);
+
+        // dm-cache and dm-writecache
+        let cached = &lvm_lvs[4];
+        assert_eq!(cached.pool_lv, "[fast_cpool]");
+        assert_eq!(cached.cache_mode, "writeback");
+        assert_eq!(cached.cache_policy, "smq");
+        assert_eq!(cached.cache_total_blocks, Some(16384));
+        assert_eq!(cached.cache_dirty_blocks, Some(12));
+        assert_eq!(cached.cache_read_hits, Some(900));
+        assert_eq!(cached.cache_write_misses, Some(5));
+        assert_eq!(cached.writecache_total_blocks, None);
+        assert_eq!(cached.writecache_free_blocks, None);
+        let wc = &lvm_lvs[5];
+        assert_eq!(wc.cache_total_blocks, None);
+        assert_eq!(wc.cache_mode, "");
+        assert_eq!(wc.writecache_total_blocks, Some(262144));
+        assert_eq!(wc.writecache_writeback_blocks, Some(0));
+        assert_eq!(lvm_lvs[0].cache_read_hits, None);
+    }
+
+    #[test]
+    fn test_cache_args() {
+        let mut cache = LvmCache {
+            name: "lvhdd_cache".to_string(),
+            cache_type: LvmCacheType::CachePool,
+            fast_pv: "/dev/nvme1n1".to_string(),
+            size: 10,
+            size_unit: "G".to_string(),
+            cache_mode: "writeback".to_string(),
+            cache_policy: "smq".to_string(),
+        };
+        assert_eq!(
+            cache_create_args("vg01", &cache).join(" "),
+            "--type cache-pool -L 10G -n lvhdd_cache vg01 /dev/nvme1n1"
+        );
+        assert_eq!(
+            cache_attach_args("vg01", "lvhdd", &cache).join(" "),
+            "-y --type cache --cachepool vg01/lvhdd_cache --cachemode writeback \
+             --cachepolicy smq vg01/lvhdd"
+        );
+
+        cache.cache_type = LvmCacheType::CacheVol;
+        cache.cache_policy.clear();
+        assert_eq!(
+            cache_create_args("vg01", &cache).join(" "),
+            "-L 10G -n lvhdd_cache vg01 /dev/nvme1n1"
+        );
+        assert_eq!(
+            cache_attach_args("vg01", "lvhdd", &cache).join(" "),
+            "-y --type cache --cachevol vg01/lvhdd_cache --cachemode writeback vg01/lvhdd"
+        );
+
+        // No mode or policy for writecache
+        cache.cache_type = LvmCacheType::Writecache;
+        assert_eq!(
+            cache_attach_args("vg01", "lvhdd", &cache).join(" "),
+            "-y --type writecache --cachevol vg01/lvhdd_cache vg01/lvhdd"
+        );
+
+        assert_eq!(
+            cache_detach_args("vg01", "lvhdd", true).join(" "),
+            "-y --splitcache vg01/lvhdd"
+        );
+        assert_eq!(
+            cache_detach_args("vg01", "lvhdd", false).join(" "),
+            "-y --uncache vg01/lvhdd"
+        );
+    }
+
+    #[test]
+    fn test_raid_args() {
+        assert_eq!(
+            raid_syncaction_args("vg04", "lvpub", LvmSyncAction::Check).join(" "),
+            "--syncaction check vg04/lvpub"
+        );
+        assert_eq!(
+            raid_syncaction_args("vg04", "lvpub", LvmSyncAction::Repair).join(" "),
+            "--syncaction repair vg04/lvpub"
+        );
+        assert_eq!(
+            raid_repair_args("vg04", "lvpub", &[]).join(" "),
+            "-y --repair vg04/lvpub"
+        );
+        let pvl = ["/dev/sdf1".to_string()];
+        assert_eq!(
+            raid_repair_args("vg04", "lvpub", &pvl).join(" "),
+            "-y --repair vg04/lvpub /dev/sdf1"
+        );
+        assert_eq!(
+            raid_replace_args("vg04", "lvpub", "/dev/sdb1", &pvl).join(" "),
+            "-y --replace /dev/sdb1 vg04/lvpub /dev/sdf1"
+        );
+    }
+
+    #[test]
+    fn test_convert() {
+        let raid1 = LvmConvert {
+            segtype: "raid1".to_string(),
+            mirrors: Some(2),
+            stripes: None,
+        };
+        let pvl = ["/dev/sdd1".to_string(), "/dev/sde1".to_string()];
+        assert_eq!(
+            convert_lv_args("vg04", "lvvirt", &raid1, &pvl).join(" "),
+            "-y --type raid1 -m 2 vg04/lvvirt /dev/sdd1 /dev/sde1"
+        );
+        let raid6 = LvmConvert {
+            segtype: "raid6".to_string(),
+            mirrors: None,
+            stripes: Some(3),
+        };
+        assert_eq!(
+            convert_lv_args("vg04", "lvpub", &raid6, &[]).join(" "),
+            "-y --type raid6 --stripes 3 vg04/lvpub"
+        );
+
+        let mut lv = LvmLvData {
+            segtype: "linear".to_string(),
+            stripes: 1,
+            data_stripes: 1,
+            ..Default::default()
+        };
+        assert_eq!(convert_targets(&lv), ["raid1", "mirror"]);
+        assert_eq!(
+            LvmLayout::of_lv(&lv),
+            LvmLayout {
+                images: 0,
+                data_stripes: 1,
+                meta: false
+            }
+        );
+        lv.segtype = "raid1".to_string();
+        lv.stripes = 2;
+        assert_eq!(convert_targets(&lv), ["linear", "raid1", "mirror", "raid5"]);
+        lv.stripes = 3;
+        assert!(!convert_targets(&lv).contains(&"raid5"));
+        lv.segtype = "thin".to_string();
+        assert!(convert_targets(&lv).is_empty());
+
+        // raid5 with 3 data stripes as in the demo, to raid6 adds an image.
+        let raid5 = LvmLayout::new("raid5", Some(3), None).unwrap();
+        assert_eq!((raid5.images, raid5.data_stripes), (4, 3));
+        let raid6 = LvmLayout::new("raid6", Some(3), None).unwrap();
+        assert_eq!((raid6.images, raid6.data_stripes), (5, 3));
+        let raid10 = LvmLayout::new("raid10", None, Some(1)).unwrap();
+        assert_eq!((raid10.images, raid10.data_stripes), (4, 2));
+        assert!(LvmLayout::new("thin", None, None).is_none());
+    }
+
+    #[test]
+    fn test_vg_create_args() {
+        assert_eq!(exact_size_arg(4 * 1024 * 1024), "4M");
+        assert_eq!(exact_size_arg(1536 * 1024), "1536K");
+        assert_eq!(exact_size_arg(2 * 1024 * 1024 * 1024), "2G");
+        assert_eq!(exact_size_arg(1000), "1000B");
+
+        let mut pv = LvmPvCreate::default();
+        assert_eq!(pvcreate_args("/dev/sdh", &pv).join(" "), "/dev/sdh");
+        pv.metadata_copies = Some(2);
+        pv.data_alignment = Some(1024 * 1024);
+        assert_eq!(
+            pvcreate_args("/dev/sdh", &pv).join(" "),
+            "--pvmetadatacopies 2 --dataalignment 1M /dev/sdh"
+        );
+
+        let pvl = ["/dev/sdh".to_string(), "/dev/sdi".to_string()];
+        let mut opts = LvmVgCreate {
+            extent_size: None,
+            alloc: AllocPolicy::Unset,
+        };
+        assert_eq!(
+            vgcreate_args("vg05", &pvl, &opts).join(" "),
+            "vg05 /dev/sdh /dev/sdi"
+        );
+        opts.extent_size = Some(32 * 1024 * 1024);
+        opts.alloc = AllocPolicy::Cling;
+        assert_eq!(
+            vgcreate_args("vg05", &pvl, &opts).join(" "),
+            "-s 32M --alloc cling vg05 /dev/sdh /dev/sdi"
+        );
+        assert_eq!(AllocPolicy::Cling.to_char(), 'l');
+        assert_eq!(AllocPolicy::Unset.to_char(), '-');
+    }
+
+    #[test]
+    fn test_pvmove_args() {
+        let mut mv = LvmPvMove {
+            src_pv: "/dev/sdb1".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(pvmove_args(&mv).join(" "), "-b /dev/sdb1");
+        mv.pe_range = Some((0, 2559));
+        mv.lv_name = "lvdata".to_string();
+        mv.dest_pvs = vec!["/dev/sdc1".to_string(), "/dev/sdd1".to_string()];
+        assert_eq!(
+            pvmove_args(&mv).join(" "),
+            "-b -n lvdata /dev/sdb1:0-2559 /dev/sdc1 /dev/sdd1"
+        );
+
+        let json = r#"{"report": [{"lv": [
+            {"lv_name":"[pvmove0]", "vg_name":"vg01", "lv_size":"4294967296", "lv_attr":"p-C-aom---", "segtype":"mirror", "lv_uuid":"p", "seg_le_ranges":["/dev/sdb1:0-1023"], "move_pv":"/dev/sdb1", "copy_percent":"37.50"},
+            {"lv_name":"lvdata", "vg_name":"vg01", "lv_size":"4294967296", "lv_attr":"-wI-ao----", "segtype":"linear", "lv_uuid":"d", "seg_le_ranges":["[pvmove0]:0-1023"], "move_pv":"", "copy_percent":""}
+        ]}]}"#;
+        let lvs = parse_lvso(json).unwrap();
+        let moves = pvmoves("vg01", &lvs);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].move_pv, "/dev/sdb1");
+        assert_eq!(moves[0].copy_percent, Some(37.5));
+        assert_eq!(lvs[1].copy_percent, None);
+        assert!(pvmoves("vg02", &lvs).is_empty());
+    }
+
+    #[test]
+    fn test_create_lv_args() {
+        let thinpool = [LvmExtraArg {
+            opt: "--thinpool".to_string(),
+            value: "pool0".to_string(),
+        }];
+        assert_eq!(
+            create_lv_args("thin1", "vg01", 20, "G", "thin", &[], &thinpool).join(" "),
+            "--type thin --thinpool pool0 -V 20G -n thin1 vg01"
+        );
+        let pvs = ["/dev/sdd1".to_string()];
+        assert_eq!(
+            create_lv_args("pool0", "vg01", 10, "G", "thin-pool", &pvs, &[]).join(" "),
+            "--type thin-pool -L 10G -n pool0 vg01 /dev/sdd1"
+        );
+        // Extents for sizes relative to the vg
+        assert_eq!(
+            create_lv_args("lvdata", "vg01", 50, "%FREE", "linear", &[], &[]).join(" "),
+            "--type linear -l 50%FREE -n lvdata vg01"
+        );
+        assert_eq!(
+            create_lv_args("pool0", "vg01", 80, "%VG", "thin-pool", &[], &[]).join(" "),
+            "--type thin-pool -l 80%VG -n pool0 vg01"
+        );
+
+        assert_eq!(parse_size_arg("64", "M"), Some(64 * 1024 * 1024));
+        assert_eq!(parse_size_arg("512k", "M"), Some(512 * 1024));
+        assert_eq!(parse_size_arg("1G", "K"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse_size_arg("", "M"), None);
+        assert_eq!(parse_size_arg("64X", "M"), None);
+        assert_eq!(parse_size_arg("6.4M", "M"), None);
+    }
+
+    #[test]
+    fn test_resize() {
+        let gb = 1024 * 1024 * 1024;
+        let extent = 4 * 1024 * 1024;
+        let vg = LvmVgData {
+            size: 100 * gb,
+            free: 40 * gb,
+            ..Default::default()
+        };
+        let resize = |sign: Option<char>, size: u64, unit: &str| LvmResize {
+            sign,
+            size,
+            size_unit: unit.to_string(),
+            resizefs: false,
+        };
+
+        assert_eq!(
+            resize(None, 20, "G")
+                .new_size(10 * gb, &vg, extent)
+                .unwrap(),
+            20 * gb
+        );
+        assert_eq!(
+            resize(Some('+'), 5, "G")
+                .new_size(10 * gb, &vg, extent)
+                .unwrap(),
+            15 * gb
+        );
+        assert_eq!(
+            resize(Some('-'), 5, "G")
+                .new_size(10 * gb, &vg, extent)
+                .unwrap(),
+            5 * gb
+        );
+        assert_eq!(
+            resize(Some('+'), 50, "%FREE")
+                .new_size(10 * gb, &vg, extent)
+                .unwrap(),
+            30 * gb
+        );
+        assert_eq!(
+            resize(None, 10, "%VG").new_size(gb, &vg, extent).unwrap(),
+            10 * gb
+        );
+        // Rounded up to an extent
+        assert_eq!(
+            resize(Some('+'), 1, "M")
+                .new_size(10 * gb, &vg, extent)
+                .unwrap(),
+            10 * gb + extent
+        );
+        assert!(
+            resize(Some('-'), 20, "G")
+                .new_size(10 * gb, &vg, extent)
+                .is_err()
+        );
+        assert!(
+            resize(Some('-'), 10, "G")
+                .new_size(10 * gb, &vg, extent)
+                .is_err()
+        );
+        assert!(resize(None, 1, "X").new_size(10 * gb, &vg, extent).is_err());
+        // Too large for u64, not wrapped around
+        let e = resize(None, 99999999999, "T")
+            .new_size(10 * gb, &vg, extent)
+            .unwrap_err();
+        assert!(matches!(e, LvmError::Usage(ref msg) if msg.contains("too large")));
+        assert!(
+            resize(Some('+'), 2, "G")
+                .new_size(u64::MAX - gb, &vg, extent)
+                .is_err()
+        );
+
+        let mut r = resize(Some('+'), 100, "%FREE");
+        r.resizefs = true;
+        assert_eq!(
+            resize_lv_args("vg01", "lvdata", &r),
+            vec!["-y", "--resizefs", "-l", "+100%FREE", "vg01/lvdata"]
+        );
+        assert_eq!(
+            resize_lv_args("vg01", "lvdata", &resize(Some('-'), 2, "G")),
+            vec!["-y", "-L", "-2G", "vg01/lvdata"]
+        );
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let gb = 1024 * 1024 * 1024;
+        let extent = 4 * 1024 * 1024;
+        let vg = LvmVgData {
+            name: "vg01".to_string(),
+            free: 100 * gb,
+            size: 200 * gb,
+            ..Default::default()
+        };
+        let snap = |size: u64, unit: &str| LvmSnapshot {
+            name: "lvdata_snap".to_string(),
+            size,
+            size_unit: unit.to_string(),
+        };
+
+        assert_eq!(
+            snap(20, "%ORIGIN").cow_size(10 * gb, &vg, extent).unwrap(),
+            (10 * gb / 100 * 20).div_ceil(extent) * extent
+        );
+        assert_eq!(
+            snap(10, "%FREE").cow_size(10 * gb, &vg, extent).unwrap(),
+            10 * gb
+        );
+        assert_eq!(snap(1, "G").cow_size(10 * gb, &vg, extent).unwrap(), gb);
+        let e = snap(0, "G").cow_size(10 * gb, &vg, extent).unwrap_err();
+        assert!(matches!(e, LvmError::Usage(_)));
+        assert!(snap(1, "%X").cow_size(10 * gb, &vg, extent).is_err());
+        assert!(
+            snap(99999999999, "T")
+                .cow_size(10 * gb, &vg, extent)
+                .is_err()
+        );
+        assert!(
+            snap(u64::MAX, "%ORIGIN")
+                .cow_size(10 * gb, &vg, extent)
+                .is_err()
+        );
+
+        assert_eq!(
+            snapshot_args("vg01", "lvdata", &snap(20, "%ORIGIN")),
+            vec!["-s", "-l", "20%ORIGIN", "-n", "lvdata_snap", "vg01/lvdata"]
+        );
+        assert_eq!(
+            snapshot_args("vg01", "lvdata", &snap(5, "G")),
+            vec!["-s", "-L", "5G", "-n", "lvdata_snap", "vg01/lvdata"]
+        );
+    }
+
+    #[test]
+    fn test_names() {
+        assert!(check_vg_name("vg01_ssd-fast").is_ok());
+        assert!(check_vg_name("").is_err());
+        assert!(check_vg_name("-vg").is_err());
+        assert!(check_vg_name("vg 01").is_err());
+        assert!(check_vg_name(&"v".repeat(128)).is_err());
+
+        assert!(check_lv_name("lvdata").is_ok());
+        assert!(check_lv_name("data_rimage").is_err());
+        assert!(check_lv_name("pool_tdata_0").is_err());
+        assert!(check_lv_name("snapshot1").is_err());
+        assert!(check_lv_name("pvmove0").is_err());
+        assert!(check_lv_name("my_snapshot").is_ok());
+        assert!(check_lv_name("lv/x").is_err());
+    }
+
+    #[test]
+    fn test_attr_decode() {
+        let attr = LvAttr::decode("rwi-aor-r-");
+        assert_eq!(attr.volume_type, LvVolumeType::Raid);
+        assert_eq!(attr.permissions, LvPermissions::Writeable);
+        assert_eq!(attr.allocation, AllocPolicy::Inherited);
+        assert_eq!(attr.state, LvState::Active);
+        assert_eq!(attr.open, Some(true));
+        assert_eq!(attr.target_type, LvTargetType::Raid);
+        assert_eq!(attr.health, LvHealth::RefreshNeeded);
+        assert!(attr.is_active());
+        let texts: Vec<&str> = attr.flags().iter().map(|f| f.text).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "raid",
+                "writeable",
+                "inherited",
+                "active",
+                "open",
+                "raid target",
+                "refresh needed"
+            ]
+        );
+
+        // Thin, locked cling allocation, inactive, skip activation
+        let attr = LvAttr::decode("VwL------k");
+        assert_eq!(attr.volume_type, LvVolumeType::Thin);
+        assert_eq!(attr.allocation, AllocPolicy::Cling);
+        assert!(attr.allocation_locked);
+        assert!(!attr.is_active());
+        assert!(attr.skip_activation);
+        assert!(attr.flags().iter().any(|f| f.text == "inactive"));
+
+        // Short or garbage attr does not panic
+        let attr = LvAttr::decode("Q");
+        assert_eq!(attr.volume_type, LvVolumeType::Other('Q'));
+        assert_eq!(attr.state, LvState::Unset);
+
+        let attr = VgAttr::decode("wz--n-");
+        assert!(attr.writeable && attr.resizeable && !attr.partial);
+        assert_eq!(attr.allocation, AllocPolicy::Normal);
+        let attr = VgAttr::decode("rz-pns");
+        assert!(!attr.writeable && attr.partial && attr.shared);
+        assert!(
+            attr.flags()
+                .iter()
+                .any(|f| f.text == "partial" && f.level == AttrLevel::Warning)
+        );
+
+        let attr = PvAttr::decode("a--");
+        assert!(attr.allocatable && !attr.missing);
+        let attr = PvAttr::decode("-xm");
+        assert!(!attr.allocatable && attr.exported && attr.missing);
+    }
+
+    #[test]
+    fn test_lvm_error() {
+        let e = run_cmd("/nonexistent/lvs", &[]).unwrap_err();
+        assert!(matches!(e, LvmError::BinaryNotFound(ref bin) if bin == "/nonexistent/lvs"));
+        // Warnings on stderr are shown with the output, not parsed as report
+        let sh = [
+            "-c",
+            "echo '  Logical volume \"lv\" created.'; echo '  WARNING: x' >&2",
+        ];
+        assert_eq!(
+            run_cmd("sh", &sh).unwrap(),
+            "  Logical volume \"lv\" created.\n  WARNING: x\n"
+        );
+        assert_eq!(
+            run_report("sh", &sh).unwrap(),
+            "  Logical volume \"lv\" created.\n"
+        );
+        assert_eq!(
+            cmd_output(run_cmd("true", &[]).unwrap(), "Created lv"),
+            "Created lv"
+        );
+
+        let e = LvmError::from_failed_cmd(
+            "/usr/sbin/lvs",
+            Some(5),
+            "  WARNING: Running as a non-root user. Functionality may be unavailable.\n  /run/lock/lvm/P_global:aux: open failed: Permission denied".to_string(),
+        );
+        assert!(matches!(e, LvmError::PermissionDenied(_)));
+
+        let e = LvmError::from_failed_cmd(
+            "/usr/sbin/lvcreate",
+            Some(5),
+            "  Giving up waiting for lock.\n  Can't get lock for vg01.".to_string(),
+        );
+        assert!(matches!(e, LvmError::LockContention(_)));
+
+        let e = LvmError::from_failed_cmd(
+            "/usr/sbin/lvcreate",
+            Some(5),
+            "  Volume group \"vg01\" has insufficient free space (10 extents): 256 required.\n"
+                .to_string(),
+        );
+        assert!(matches!(e, LvmError::CommandFailed { code: Some(5), .. }));
+        assert_eq!(
+            e.to_string(),
+            "/usr/sbin/lvcreate failed (5): Volume group \"vg01\" has insufficient free space (10 extents): 256 required."
+        );
+
+        // Parse error points out the line
+        let s = "{\n \"report\": [\n {\"vg\": [{\"vg_name\": 12}]}\n ]\n}";
+        match parse_vgso(s).unwrap_err() {
+            LvmError::Parse { line, .. } => assert_eq!(line, " {\"vg\": [{\"vg_name\": 12}]}"),
+            e => panic!("unexpected error {e}"),
+        }
     }
 }
@@ -0,0 +1,358 @@
+//
+// A bundle of the local lvm state for support (capture): the vgs, pvs and lvs
+// reports exactly as the tui reads them, the metadata of all vgs
+// (vgcfgbackup), dm tables and status, lsblk and the lvm.conf settings that
+// matter, in one tar with a manifest.
+//
+// The reports are at the top of the tar, so it can be browsed elsewhere with
+//
+//   tar xf capture.tar -C case && lvm-tui --from-dir case
+//
+// Without the reports the bundle is of no use and capture fails. Anything
+// else that can not be read is noted in the manifest and left out.
+//
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::lvm::{
+    DMSETUP_BIN, LSBLK_BIN, LVM_BIN, LVMCONFIG_BIN, LVS_BIN, LVS_FIELDS, LvmError, PVS_BIN,
+    PVS_FIELDS, VGCFGBACKUP_BIN, VGDISPLAY_BIN, VGS_FIELDS, report_args, run_report,
+};
+
+pub const MANIFEST: &str = "manifest.json";
+// vgcfgbackup -f writes a file per vg, %s is the vg name.
+const BACKUP_DIR: &str = "backup";
+
+// Runs a command, returns its stdout. run_report, or a stand-in in tests.
+pub type Runner<'a> = &'a dyn Fn(&str, &[&str]) -> Result<String, LvmError>;
+
+#[derive(Serialize)]
+pub struct ManifestEntry {
+    // Name in the tar, a dir for the vg metadata.
+    pub name: String,
+    pub command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Manifest {
+    pub lvm_tui_version: &'static str,
+    pub host: String,
+    // Seconds since the epoch.
+    pub created: u64,
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn failed(&self) -> impl Iterator<Item = &ManifestEntry> {
+        self.entries.iter().filter(|e| e.error.is_some())
+    }
+}
+
+struct Bundle {
+    files: Vec<(String, Vec<u8>)>,
+    entries: Vec<ManifestEntry>,
+}
+
+impl Bundle {
+    fn add(&mut self, name: &str, command: String, result: Result<String, LvmError>) {
+        match result {
+            Ok(out) => {
+                self.entries.push(ManifestEntry {
+                    name: name.to_string(),
+                    command,
+                    bytes: Some(out.len() as u64),
+                    error: None,
+                });
+                self.files.push((name.to_string(), out.into_bytes()));
+            }
+            Err(e) => self.entries.push(ManifestEntry {
+                name: name.to_string(),
+                command,
+                bytes: None,
+                error: Some(e.to_string()),
+            }),
+        }
+    }
+}
+
+fn cmd_line(bin: &str, args: &[&str]) -> String {
+    [&[bin], args].concat().join(" ")
+}
+
+//
+// Capture with the lvm commands of this host.
+//
+pub fn capture(path: &Path) -> Result<Manifest, LvmError> {
+    capture_with(path, &run_report)
+}
+
+pub fn capture_with(path: &Path, run: Runner) -> Result<Manifest, LvmError> {
+    let mut bundle = Bundle {
+        files: Vec::new(),
+        entries: Vec::new(),
+    };
+
+    for (name, bin, fields) in [
+        ("vgs.json", VGDISPLAY_BIN, VGS_FIELDS),
+        ("pvs.json", PVS_BIN, PVS_FIELDS),
+        ("lvs.json", LVS_BIN, LVS_FIELDS),
+    ] {
+        let args = report_args(fields, &[]);
+        let out = run(bin, &args)?;
+        bundle.add(name, cmd_line(bin, &args), Ok(out));
+    }
+
+    let optional: [(&str, &str, &[&str]); 6] = [
+        ("lvm-version.txt", LVM_BIN, &["version"]),
+        ("dmsetup-table.txt", DMSETUP_BIN, &["table"]),
+        ("dmsetup-status.txt", DMSETUP_BIN, &["status"]),
+        (
+            "lsblk.json",
+            LSBLK_BIN,
+            &["--json", "--bytes", "--output-all"],
+        ),
+        // Settings changed from the defaults, and all of devices (filters).
+        ("lvmconfig-diff.txt", LVMCONFIG_BIN, &["--type", "diff"]),
+        (
+            "lvmconfig-devices.txt",
+            LVMCONFIG_BIN,
+            &["--type", "full", "devices"],
+        ),
+    ];
+    for (name, bin, args) in optional {
+        bundle.add(name, cmd_line(bin, args), run(bin, args));
+    }
+
+    capture_backups(&mut bundle, run);
+
+    let manifest = Manifest {
+        lvm_tui_version: env!("CARGO_PKG_VERSION"),
+        host: std::fs::read_to_string("/proc/sys/kernel/hostname")
+            .map(|h| h.trim().to_string())
+            .unwrap_or_default(),
+        created: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        entries: bundle.entries,
+    };
+    write_tar(path, &manifest, &bundle.files)?;
+    Ok(manifest)
+}
+
+//
+// A new dir only we can get at, for what vgcfgbackup writes as root. Its name
+// is random and it must not exist yet, so nobody can have put a dir or link
+// there first.
+//
+fn private_temp_dir() -> Result<PathBuf, LvmError> {
+    let mut tries = 0;
+    loop {
+        let suffix = RandomState::new().build_hasher().finish();
+        let dir = std::env::temp_dir().join(format!("lvm-tui-capture-{suffix:016x}"));
+        match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && tries < 8 => tries += 1,
+            Err(e) => return Err(LvmError::Io(e)),
+        }
+    }
+}
+
+// Metadata of all vgs, written by vgcfgbackup to a temp dir and taken from there.
+fn capture_backups(bundle: &mut Bundle, run: Runner) {
+    let tmp = match private_temp_dir() {
+        Ok(tmp) => tmp,
+        Err(e) => {
+            bundle.entries.push(ManifestEntry {
+                name: format!("{BACKUP_DIR}/"),
+                command: cmd_line(VGCFGBACKUP_BIN, &["-f"]),
+                bytes: None,
+                error: Some(e.to_string()),
+            });
+            return;
+        }
+    };
+    let pattern = tmp.join("%s").to_string_lossy().to_string();
+    let args = ["-f", pattern.as_str()];
+    let command = cmd_line(VGCFGBACKUP_BIN, &args);
+
+    let result = run(VGCFGBACKUP_BIN, &args).and_then(|_| {
+        let mut backups = Vec::new();
+        for entry in std::fs::read_dir(&tmp).map_err(LvmError::Io)? {
+            let path = entry.map_err(LvmError::Io)?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let data = std::fs::read(&path).map_err(LvmError::Io)?;
+            backups.push((format!("{BACKUP_DIR}/{name}"), data));
+        }
+        backups.sort();
+        Ok(backups)
+    });
+    let _ = std::fs::remove_dir_all(&tmp);
+
+    match result {
+        Ok(backups) => {
+            bundle.entries.push(ManifestEntry {
+                name: format!("{BACKUP_DIR}/"),
+                command,
+                bytes: Some(backups.iter().map(|(_, data)| data.len() as u64).sum()),
+                error: None,
+            });
+            bundle.files.extend(backups);
+        }
+        Err(e) => bundle.entries.push(ManifestEntry {
+            name: format!("{BACKUP_DIR}/"),
+            command,
+            bytes: None,
+            error: Some(e.to_string()),
+        }),
+    }
+}
+
+fn write_tar(
+    path: &Path,
+    manifest: &Manifest,
+    files: &[(String, Vec<u8>)],
+) -> Result<(), LvmError> {
+    let manifest = serde_json::to_vec_pretty(manifest).map_err(|e| LvmError::Parse {
+        msg: e.to_string(),
+        line: String::new(),
+    })?;
+    let file = std::fs::File::create(path).map_err(LvmError::Io)?;
+    let mut tar = tar::Builder::new(file);
+    let mtime = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    for (name, data) in std::iter::once((MANIFEST, &manifest))
+        .chain(files.iter().map(|(name, data)| (name.as_str(), data)))
+    {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        header.set_cksum();
+        tar.append_data(&mut header, name, data.as_slice())
+            .map_err(LvmError::Io)?;
+    }
+    tar.into_inner()
+        .and_then(|mut file| std::io::Write::flush(&mut file))
+        .map_err(LvmError::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::os::unix::fs::PermissionsExt;
+
+    use crate::lvm::capture::{MANIFEST, capture_with};
+    use crate::lvm::replay::ReplayBackend;
+    use crate::lvm::{LvmBackend, LvmError};
+
+    fn run(cmd: &str, args: &[&str]) -> Result<String, LvmError> {
+        let out = match cmd.rsplit('/').next().unwrap() {
+            "vgs" => {
+                r#"{"report": [{"vg": [{"vg_name":"vg01", "vg_size":10733223936, "vg_free":8585740288, "pv_count":1, "vg_attr":"wz--n-", "vg_uuid":"u0"}]}]}"#
+            }
+            "pvs" => {
+                r#"{"report": [{"pv": [{"pv_name":"/dev/sda", "vg_name":"vg01", "pv_size":10733223936, "pv_free":8585740288, "vg_extent_size":4194304, "pv_attr":"a--"}]}]}"#
+            }
+            "lvs" => {
+                r#"{"report": [{"lv": [{"lv_name":"lvdata", "vg_name":"vg01", "lv_size":2147483648, "lv_attr":"-wi-a-----", "segtype":"linear", "seg_le_ranges":["/dev/sda:0-511"]}]}]}"#
+            }
+            "vgcfgbackup" => {
+                // Written to a dir of our own
+                let dir = std::path::Path::new(args[1]).parent().unwrap();
+                let mode = std::fs::metadata(dir).unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o700);
+                let path = args[1].replace("%s", "vg01");
+                std::fs::write(path, "vg01 {\n\tid = \"u0\"\n}\n").unwrap();
+                ""
+            }
+            "dmsetup" if args == ["table"] => "vg01-lvdata: 0 4194304 linear 8:0 2048\n",
+            _ => {
+                return Err(LvmError::CommandFailed {
+                    cmd: cmd.to_string(),
+                    code: Some(1),
+                    stderr: "not here".to_string(),
+                });
+            }
+        };
+        Ok(out.to_string())
+    }
+
+    #[test]
+    fn test_capture() {
+        let dir = std::env::temp_dir().join(format!("lvm-tui-capture-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("capture.tar");
+        let manifest = capture_with(&path, &run).unwrap();
+        let failed: Vec<&str> = manifest.failed().map(|e| e.name.as_str()).collect();
+        assert_eq!(
+            failed,
+            vec![
+                "lvm-version.txt",
+                "dmsetup-status.txt",
+                "lsblk.json",
+                "lvmconfig-diff.txt",
+                "lvmconfig-devices.txt"
+            ]
+        );
+        assert!(manifest.entries[2].command.starts_with(
+            "/usr/sbin/lvs --reportformat json_std --units b --nosuffix -a -o lv_name,"
+        ));
+
+        let mut archive = tar::Archive::new(std::fs::File::open(&path).unwrap());
+        let mut names = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let name = entry.path().unwrap().to_string_lossy().to_string();
+            if name == MANIFEST {
+                let mut json = String::new();
+                entry.read_to_string(&mut json).unwrap();
+                let manifest: serde_json::Value = serde_json::from_str(&json).unwrap();
+                assert_eq!(manifest["entries"][9]["name"], "backup/");
+                assert_eq!(
+                    manifest["entries"][6]["error"],
+                    "/usr/bin/lsblk failed (1): not here"
+                );
+            }
+            names.push(name);
+        }
+        assert_eq!(
+            names,
+            vec![
+                "manifest.json",
+                "vgs.json",
+                "pvs.json",
+                "lvs.json",
+                "dmsetup-table.txt",
+                "backup/vg01"
+            ]
+        );
+
+        // Browsable as it is unpacked
+        let case = dir.join("case");
+        tar::Archive::new(std::fs::File::open(&path).unwrap())
+            .unpack(&case)
+            .unwrap();
+        let replay = ReplayBackend::open(&case).unwrap();
+        assert_eq!(replay.get_lvs().unwrap()[0].lv_name, "lvdata");
+
+        // No reports, no capture
+        let none = |cmd: &str, _: &[&str]| -> Result<String, LvmError> {
+            Err(LvmError::BinaryNotFound(cmd.to_string()))
+        };
+        assert!(capture_with(&dir.join("none.tar"), &none).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,2362 @@
+//
+// Backend talking to lvmdbusd (com.redhat.lvmdbus1) on the system bus.
+//
+// Everything lvmdbusd knows is read in one go with ObjectManager.GetManagedObjects
+// and mapped to the same data model as the lvs/pvs/vgs reports. Mutations are
+// methods on the Vg and Lv objects, they return (result, job) or just job. If the operation did not
+// finish within JOB_TMO seconds job is a com.redhat.lvmdbus1.Job object that is
+// polled until complete, progress is kept in 'jobs' and shown in the job list.
+//
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::{Serialize, de::DeserializeOwned};
+use zbus::blocking::Connection;
+use zbus::fdo::ManagedObjects;
+use zbus::zvariant::{DynamicType, OwnedObjectPath, OwnedValue, Value};
+
+use crate::lvm::{
+    AllocPolicy, LvmBackend, LvmBackendJob, LvmCache, LvmCacheType, LvmConvert, LvmError,
+    LvmExtraArg, LvmLvData, LvmPVData, LvmPvCreate, LvmPvMove, LvmResize, LvmSnapshot,
+    LvmSyncAction, LvmVgCreate, LvmVgData, LvmlvSegData,
+    devinfo::{self, BlockDev},
+    exact_size_arg, size_unit_bytes,
+};
+
+const DEST: &str = "com.redhat.lvmdbus1";
+const ROOT_PATH: &str = "/com/redhat/lvmdbus1";
+const MANAGER_PATH: &str = "/com/redhat/lvmdbus1/Manager";
+const MANAGER_IFACE: &str = "com.redhat.lvmdbus1.Manager";
+const VG_IFACE: &str = "com.redhat.lvmdbus1.Vg";
+const PV_IFACE: &str = "com.redhat.lvmdbus1.Pv";
+const LV_IFACE: &str = "com.redhat.lvmdbus1.LvCommon";
+// Methods of non hidden lvs, Remove, Rename, ...
+const LV_OPS_IFACE: &str = "com.redhat.lvmdbus1.Lv";
+const SNAPSHOT_IFACE: &str = "com.redhat.lvmdbus1.Snapshot";
+const THINPOOL_IFACE: &str = "com.redhat.lvmdbus1.ThinPool";
+const CACHEPOOL_IFACE: &str = "com.redhat.lvmdbus1.CachePool";
+const CACHEDLV_IFACE: &str = "com.redhat.lvmdbus1.CachedLv";
+const JOB_IFACE: &str = "com.redhat.lvmdbus1.Job";
+const OBJMGR_IFACE: &str = "org.freedesktop.DBus.ObjectManager";
+const PROPS_IFACE: &str = "org.freedesktop.DBus.Properties";
+
+// Seconds lvmdbusd waits for an operation before handing back a job.
+const JOB_TMO: i32 = 15;
+// Seconds per Job.Wait call, progress is updated in between.
+const JOB_WAIT_TMO: i32 = 1;
+// pvmoves run in the background, the job is handed back right away.
+const MOVE_TMO: i32 = 0;
+// Smallest cache pool metadata lv, lvm picks about 1/1000 of the data.
+const CACHE_META_MIN_MB: u64 = 8;
+
+type Props = HashMap<String, OwnedValue>;
+// LvCommon.Devices, a(oa(tts)): pv and its (start pe, end pe, segtype) ranges.
+type Devices = Vec<(OwnedObjectPath, Vec<(u64, u64, String)>)>;
+
+pub struct DbusBackend {
+    conn: Connection,
+    jobs: Mutex<Vec<LvmBackendJob>>,
+}
+
+//
+// What lvmdbusd knows, mapped to our data model, plus object paths needed for calls.
+//
+#[derive(Default)]
+struct DbusModel {
+    vgs: Vec<(String, LvmVgData)>,
+    pvs: Vec<(String, LvmPVData)>, // path, pv
+    lvs: Vec<LvmLvData>,
+    lv_paths: HashMap<(String, String), String>, // (vg, lv), path
+}
+
+impl DbusBackend {
+    //
+    // Connect to lvmdbusd on the system bus, fails if it is not running.
+    //
+    pub fn system() -> Result<Self, LvmError> {
+        let conn = Connection::system().map_err(dbus_err)?;
+        let backend = Self::with_connection(conn);
+        backend.version()?;
+        Ok(backend)
+    }
+
+    pub fn with_connection(conn: Connection) -> Self {
+        Self {
+            conn,
+            jobs: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn version(&self) -> Result<String, LvmError> {
+        self.property(MANAGER_PATH, MANAGER_IFACE, "Version")
+    }
+
+    fn call<B, R>(&self, path: &str, iface: &str, method: &str, body: &B) -> Result<R, LvmError>
+    where
+        B: Serialize + DynamicType,
+        R: DeserializeOwned + zbus::zvariant::Type,
+    {
+        let reply = self
+            .conn
+            .call_method(Some(DEST), path, Some(iface), method, body)
+            .map_err(dbus_err)?;
+        reply.body().deserialize::<R>().map_err(dbus_err)
+    }
+
+    fn property<R>(&self, path: &str, iface: &str, name: &str) -> Result<R, LvmError>
+    where
+        R: TryFrom<OwnedValue>,
+        R::Error: std::fmt::Display,
+    {
+        let v: OwnedValue = self.call(path, PROPS_IFACE, "Get", &(iface, name))?;
+        R::try_from(v).map_err(|e| LvmError::Dbus(format!("property {name}: {e}")))
+    }
+
+    fn model(&self) -> Result<DbusModel, LvmError> {
+        let objects: ManagedObjects =
+            self.call(ROOT_PATH, OBJMGR_IFACE, "GetManagedObjects", &())?;
+        Ok(model_from_objects(&objects))
+    }
+
+    //
+    // Poll job until complete, result is taken from the GetError property.
+    //
+    fn wait_job(&self, job: &str) -> Result<(), LvmError> {
+        self.jobs.lock().unwrap().push(LvmBackendJob {
+            path: job.to_string(),
+            percent: 0.0,
+            complete: false,
+        });
+
+        let res = loop {
+            let done: bool = match self.call(job, JOB_IFACE, "Wait", &(JOB_WAIT_TMO,)) {
+                Ok(done) => done,
+                Err(e) => break Err(e),
+            };
+            let percent: f64 = self.property(job, JOB_IFACE, "Percent").unwrap_or(0.0);
+            if let Some(j) = self.jobs.lock().unwrap().iter_mut().find(|j| j.path == job) {
+                j.percent = percent;
+                j.complete = done;
+            }
+            if done {
+                let (code, msg): (i32, String) =
+                    match self.property::<(i32, String)>(job, JOB_IFACE, "GetError") {
+                        Ok(v) => v,
+                        Err(e) => break Err(e),
+                    };
+                let removed = self.call::<_, ()>(job, JOB_IFACE, "Remove", &());
+                if code != 0 {
+                    break Err(LvmError::from_failed_cmd("lvmdbusd", Some(code), msg));
+                }
+                break removed;
+            }
+        };
+
+        self.jobs.lock().unwrap().retain(|j| j.path != job);
+        res
+    }
+
+    //
+    // Turn the new fast lv into a cache pool or writecache of vg/lv.
+    //
+    fn cache_convert(
+        &self,
+        vg: &str,
+        lv: &str,
+        meta_name: &str,
+        cache: &LvmCache,
+    ) -> Result<String, LvmError> {
+        let mut options = HashMap::<&str, Value>::new();
+        let (fast_path, iface, method) = if cache.cache_type == LvmCacheType::Writecache {
+            let model = self.model()?;
+            (
+                model.lv_path(vg, &cache.name)?.clone(),
+                LV_OPS_IFACE,
+                "WriteCacheLv",
+            )
+        } else {
+            let unit_bytes = size_unit_bytes(&cache.size_unit)
+                .ok_or_else(|| LvmError::Unsupported(format!("size unit {}", cache.size_unit)))?;
+            let size_bytes = cache.size.checked_mul(unit_bytes).ok_or_else(|| {
+                LvmError::Usage(format!(
+                    "Size {}{} is too large.",
+                    cache.size, cache.size_unit
+                ))
+            })?;
+            let meta_mb = (size_bytes / 1000)
+                .div_ceil(1024 * 1024)
+                .max(CACHE_META_MIN_MB);
+            let pvl = std::slice::from_ref(&cache.fast_pv);
+            self.create_lv(meta_name, vg, meta_mb, "M", "linear", pvl, &[])?;
+
+            let model = self.model()?;
+            let meta_path = OwnedObjectPath::try_from(model.lv_path(vg, meta_name)?.as_str())
+                .map_err(|e| LvmError::Dbus(e.to_string()))?;
+            let data_path = OwnedObjectPath::try_from(model.lv_path(vg, &cache.name)?.as_str())
+                .map_err(|e| LvmError::Dbus(e.to_string()))?;
+            let (pool_path, job): (OwnedObjectPath, OwnedObjectPath) = self.call(
+                model.vg_path(vg)?,
+                VG_IFACE,
+                "CreateCachePool",
+                &(meta_path, data_path, JOB_TMO, HashMap::<&str, Value>::new()),
+            )?;
+            if job.as_str() != "/" {
+                self.wait_job(job.as_str())?;
+            }
+            // Passed on as --cachemode/--cachepolicy to lvconvert.
+            for (key, value) in [
+                ("cachemode", &cache.cache_mode),
+                ("cachepolicy", &cache.cache_policy),
+            ] {
+                if !value.is_empty() {
+                    options.insert(key, Value::from(value.clone()));
+                }
+            }
+            (pool_path.to_string(), CACHEPOOL_IFACE, "CacheLv")
+        };
+
+        let model = self.model()?;
+        let lv_path = OwnedObjectPath::try_from(model.lv_path(vg, lv)?.as_str())
+            .map_err(|e| LvmError::Dbus(e.to_string()))?;
+        let (_, job): (OwnedObjectPath, OwnedObjectPath) =
+            self.call(&fast_path, iface, method, &(lv_path, JOB_TMO, options))?;
+        if job.as_str() != "/" {
+            self.wait_job(job.as_str())?;
+        }
+        Ok("Attached cache".to_string())
+    }
+}
+
+fn dbus_err<E: Into<zbus::Error>>(e: E) -> LvmError {
+    match e.into() {
+        zbus::Error::MethodError(name, msg, _) => {
+            let msg = msg.unwrap_or_default();
+            match name.as_str() {
+                "org.freedesktop.DBus.Error.AccessDenied" => LvmError::PermissionDenied(msg),
+                "org.freedesktop.DBus.Error.ServiceUnknown" => {
+                    LvmError::Dbus(format!("{DEST} not available, is lvmdbusd running? {msg}"))
+                }
+                n if n.starts_with(DEST) => LvmError::from_failed_cmd("lvmdbusd", None, msg),
+                n => LvmError::Dbus(format!("{n}: {msg}")),
+            }
+        }
+        zbus::Error::FDO(e) => match *e {
+            zbus::fdo::Error::AccessDenied(msg) => LvmError::PermissionDenied(msg),
+            e => LvmError::Dbus(e.to_string()),
+        },
+        e => LvmError::Dbus(e.to_string()),
+    }
+}
+
+fn prop<T>(props: &Props, name: &str) -> Option<T>
+where
+    T: TryFrom<OwnedValue>,
+{
+    props
+        .get(name)
+        .and_then(|v| v.try_clone().ok())
+        .and_then(|v| T::try_from(v).ok())
+}
+
+fn prop_str(props: &Props, name: &str) -> String {
+    prop::<String>(props, name).unwrap_or_default()
+}
+
+fn prop_path(props: &Props, name: &str) -> String {
+    prop::<OwnedObjectPath>(props, name)
+        .map(|p| p.to_string())
+        .unwrap_or_default()
+}
+
+//
+// lvmdbusd has no vg_attr, build it from the bool properties.
+//
+fn vg_attr(props: &Props) -> String {
+    let flag = |name: &str, c: char| {
+        if prop::<bool>(props, name).unwrap_or(false) {
+            c
+        } else {
+            '-'
+        }
+    };
+    let alloc = [
+        ("AllocContiguous", 'c'),
+        ("AllocCling", 'l'),
+        ("AllocNormal", 'n'),
+        ("AllocAnywhere", 'a'),
+    ]
+    .into_iter()
+    .find(|(name, _)| prop::<bool>(props, name).unwrap_or(false))
+    .map(|(_, c)| c)
+    .unwrap_or('-');
+    let writeable = if prop::<bool>(props, "Writeable").unwrap_or(true) {
+        'w'
+    } else {
+        'r'
+    };
+
+    [
+        writeable,
+        flag("Resizeable", 'z'),
+        flag("Exportable", 'x'),
+        flag("Partial", 'p'),
+        alloc,
+        flag("Clustered", 'c'),
+    ]
+    .iter()
+    .collect()
+}
+
+fn data_stripes(segtype: &str, images: u16) -> u16 {
+    match segtype {
+        "raid1" | "mirror" => 1,
+        "raid4" | "raid5" => images.saturating_sub(1).max(1),
+        "raid6" => images.saturating_sub(2).max(1),
+        "raid10" => (images / 2).max(1),
+        _ => images.max(1),
+    }
+}
+
+fn model_from_objects(objects: &ManagedObjects) -> DbusModel {
+    let mut model = DbusModel::default();
+    let mut vg_names = HashMap::<String, String>::new();
+    let mut pv_names = HashMap::<String, String>::new();
+    let mut extent_sizes = HashMap::<String, u64>::new();
+
+    for (path, ifaces) in objects {
+        if let Some(props) = ifaces.get(VG_IFACE) {
+            let name = prop_str(props, "Name");
+            vg_names.insert(path.to_string(), name.clone());
+            extent_sizes.insert(
+                path.to_string(),
+                prop(props, "ExtentSizeBytes").unwrap_or(0),
+            );
+            model.vgs.push((
+                path.to_string(),
+                LvmVgData {
+                    name,
+                    free: prop(props, "FreeBytes").unwrap_or(0),
+                    size: prop(props, "SizeBytes").unwrap_or(0),
+                    pv_count: prop(props, "PvCount").unwrap_or(0),
+                    attr: vg_attr(props),
+                    uuid: prop_str(props, "Uuid"),
+                },
+            ));
+        }
+    }
+    for (path, ifaces) in objects {
+        if let Some(props) = ifaces.get(PV_IFACE) {
+            let pv_name = prop_str(props, "Name");
+            pv_names.insert(path.to_string(), pv_name.clone());
+            // "/" if pv not in a vg
+            let vg_path = prop_path(props, "Vg");
+            let flag = |name: &str, c: char| {
+                if prop::<bool>(props, name).unwrap_or(false) {
+                    c
+                } else {
+                    '-'
+                }
+            };
+            model.pvs.push((
+                path.to_string(),
+                LvmPVData {
+                    pv_name,
+                    vg_name: vg_names.get(&vg_path).cloned().unwrap_or_default(),
+                    pv_size: prop(props, "SizeBytes").unwrap_or(0),
+                    pv_free: prop(props, "FreeBytes").unwrap_or(0),
+                    pe_start: prop(props, "PeStart").unwrap_or(0),
+                    pe_size: extent_sizes.get(&vg_path).copied().unwrap_or(0),
+                    pe_count: prop(props, "PeCount").unwrap_or(0),
+                    pe_alloc_count: prop(props, "PeAllocCount").unwrap_or(0),
+                    pv_attr: [
+                        flag("Allocatable", 'a'),
+                        flag("Exportable", 'x'),
+                        flag("Missing", 'm'),
+                    ]
+                    .iter()
+                    .collect(),
+                    pv_uuid: prop_str(props, "Uuid"),
+                    // Not known by lvmdbusd.
+                    major: None,
+                    minor: None,
+                },
+            ));
+        }
+    }
+
+    // Hidden lvs (_rimage, _rmeta) are listed by their parent.
+    let mut parents = HashMap::<String, String>::new();
+    let mut lv_names = HashMap::<String, String>::new();
+    for (path, ifaces) in objects {
+        if let Some(props) = ifaces.get(LV_IFACE) {
+            let name = prop_str(props, "Name");
+            lv_names.insert(path.to_string(), name.clone());
+            for hidden in prop::<Vec<OwnedObjectPath>>(props, "HiddenLvs").unwrap_or_default() {
+                parents.insert(hidden.to_string(), name.clone());
+            }
+        }
+    }
+
+    for (path, ifaces) in objects {
+        let Some(props) = ifaces.get(LV_IFACE) else {
+            continue;
+        };
+        let segtype = prop::<Vec<String>>(props, "SegType")
+            .and_then(|s| s.into_iter().next())
+            .unwrap_or_default();
+        let devices: Devices = prop(props, "Devices").unwrap_or_default();
+        let is_raid = segtype.starts_with("raid") || segtype == "mirror";
+        let lv_segs = devices
+            .iter()
+            .flat_map(|(pv_path, ranges)| {
+                let pvdev = pv_names
+                    .get(pv_path.as_str())
+                    .cloned()
+                    .unwrap_or_else(|| pv_path.to_string());
+                ranges.iter().map(move |(start, end, _)| LvmlvSegData {
+                    pvdev: pvdev.clone(),
+                    pv_start_pe: *start,
+                    size_pe: end.saturating_sub(*start) + 1,
+                })
+            })
+            .collect();
+
+        let lv_name = prop_str(props, "Name");
+        let vg_name = vg_names
+            .get(&prop_path(props, "Vg"))
+            .cloned()
+            .unwrap_or_default();
+        // "/" if not a snapshot
+        let origin = lv_names
+            .get(&prop_path(props, "OriginLv"))
+            .cloned()
+            .unwrap_or_default();
+        // "/" if not a thin lv
+        let pool_lv = lv_names
+            .get(&prop_path(props, "PoolLv"))
+            .cloned()
+            .unwrap_or_default();
+        // "/" if not a pvmove
+        let move_pv = pv_names
+            .get(&prop_path(props, "MovePv"))
+            .cloned()
+            .unwrap_or_default();
+        let is_thin = segtype == "thin" || segtype == "thin-pool";
+        let is_pool = segtype == "thin-pool";
+        model
+            .lv_paths
+            .insert((vg_name.clone(), lv_name.clone()), path.to_string());
+        model.lvs.push(LvmLvData {
+            lv_name,
+            vg_name,
+            size: prop(props, "SizeBytes").unwrap_or(0),
+            attr: prop_str(props, "Attr"),
+            segtype,
+            uuid: prop_str(props, "Uuid"),
+            lv_segs,
+            stripes: 1,
+            data_stripes: 1,
+            parent_lv: parents.get(path.as_str()).cloned().unwrap_or_default(),
+            path: prop_str(props, "Path"),
+            // Only raid/mirror lvs have a sync percent.
+            sync_percent: prop::<u32>(props, "SyncPercent")
+                .filter(|_| is_raid)
+                .map(f64::from),
+            // (attr char, description), '-' is healthy.
+            health_status: prop::<(String, String)>(props, "Health")
+                .filter(|(code, _)| code != "-")
+                .map(|(_, desc)| desc)
+                .unwrap_or_default(),
+            tags: prop::<Vec<String>>(props, "Tags").unwrap_or_default(),
+            snap_percent: prop::<u32>(props, "SnapPercent")
+                .filter(|_| !origin.is_empty())
+                .map(f64::from),
+            origin,
+            pool_lv,
+            data_percent: prop::<u32>(props, "DataPercent")
+                .filter(|_| is_thin)
+                .map(f64::from),
+            metadata_percent: prop::<u32>(props, "MetaDataPercent")
+                .filter(|_| is_pool)
+                .map(f64::from),
+            copy_percent: prop::<u32>(props, "CopyPercent")
+                .filter(|_| !move_pv.is_empty())
+                .map(f64::from),
+            move_pv,
+            ..Default::default()
+        });
+    }
+
+    // raid lvs are made of the _rimage sub lvs, like in seg_le_ranges.
+    for i in 0..model.lvs.len() {
+        let name = model.lvs[i].lv_name.clone();
+        let mut images: Vec<LvmlvSegData> = model
+            .lvs
+            .iter()
+            .filter(|lv| lv.parent_lv == name && lv.lv_name.contains("_rimage_"))
+            .map(|lv| LvmlvSegData {
+                pvdev: lv.lv_name.clone(),
+                pv_start_pe: 0,
+                size_pe: lv.lv_segs.iter().map(|seg| seg.size_pe).sum(),
+            })
+            .collect();
+        if images.is_empty() {
+            continue;
+        }
+        images.sort_by(|a, b| a.pvdev.cmp(&b.pvdev));
+        let lv = &mut model.lvs[i];
+        lv.stripes = images.len() as u16;
+        lv.data_stripes = data_stripes(&lv.segtype, lv.stripes);
+        if lv.lv_segs.is_empty() {
+            lv.lv_segs = images;
+        }
+    }
+
+    // Keep a stable order, objects come from a hash map.
+    model.vgs.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+    model.pvs.sort_by(|a, b| a.1.pv_name.cmp(&b.1.pv_name));
+    model
+        .lvs
+        .sort_by(|a, b| (&a.vg_name, &a.lv_name).cmp(&(&b.vg_name, &b.lv_name)));
+    model
+}
+
+impl DbusModel {
+    fn lv_path(&self, vg: &str, lv: &str) -> Result<&String, LvmError> {
+        self.lv_paths
+            .get(&(vg.to_string(), lv.to_string()))
+            .ok_or_else(|| LvmError::Dbus(format!("Logical volume \"{vg}/{lv}\" not found")))
+    }
+
+    fn vg_path(&self, vg: &str) -> Result<&String, LvmError> {
+        self.vgs
+            .iter()
+            .find(|(_, data)| data.name == vg)
+            .map(|(path, _)| path)
+            .ok_or_else(|| LvmError::Dbus(format!("Volume group \"{vg}\" not found")))
+    }
+
+    fn pv_paths(&self, pvl: &[String]) -> Result<Vec<OwnedObjectPath>, LvmError> {
+        pvl.iter()
+            .map(|name| {
+                let (path, _) = self
+                    .pvs
+                    .iter()
+                    .find(|(_, pv)| pv.pv_name == *name)
+                    .ok_or_else(|| {
+                        LvmError::Dbus(format!("Physical volume \"{name}\" not found"))
+                    })?;
+                OwnedObjectPath::try_from(path.as_str()).map_err(|e| LvmError::Dbus(e.to_string()))
+            })
+            .collect()
+    }
+}
+
+fn extra_u32(extra: &[LvmExtraArg], opt: &str) -> u32 {
+    extra
+        .iter()
+        .find(|e| e.opt == opt)
+        .and_then(|e| e.value.parse::<u32>().ok())
+        .unwrap_or(0)
+}
+
+impl LvmBackend for DbusBackend {
+    fn get_vgs(&self) -> Result<Vec<String>, LvmError> {
+        Ok(self
+            .model()?
+            .vgs
+            .into_iter()
+            .map(|(_, vg)| vg.name)
+            .collect())
+    }
+
+    fn get_vg_info(&self, vg_name: &str) -> Result<LvmVgData, LvmError> {
+        self.model()?
+            .vgs
+            .into_iter()
+            .map(|(_, vg)| vg)
+            .find(|vg| vg.name == vg_name)
+            .ok_or_else(|| LvmError::Dbus(format!("Volume group \"{vg_name}\" not found")))
+    }
+
+    fn get_pvs(&self) -> Result<Vec<LvmPVData>, LvmError> {
+        Ok(self.model()?.pvs.into_iter().map(|(_, pv)| pv).collect())
+    }
+
+    fn get_lvs(&self) -> Result<Vec<LvmLvData>, LvmError> {
+        Ok(self.model()?.lvs)
+    }
+
+    fn create_lv(
+        &self,
+        lv: &str,
+        vg: &str,
+        size: u64,
+        size_unit: &str,
+        segtype: &str,
+        pvl: &[String],
+        extra: &[LvmExtraArg],
+    ) -> Result<String, LvmError> {
+        let model = self.model()?;
+        let (vg_path, vg_data) = model
+            .vgs
+            .iter()
+            .find(|(_, data)| data.name == vg)
+            .ok_or_else(|| LvmError::Dbus(format!("Volume group \"{vg}\" not found")))?;
+        let size_bytes = match size_unit {
+            "%FREE" => (vg_data.free / 100).checked_mul(size),
+            "%VG" => (vg_data.size / 100).checked_mul(size),
+            unit => size.checked_mul(
+                size_unit_bytes(unit)
+                    .ok_or_else(|| LvmError::Unsupported(format!("size unit {unit}")))?,
+            ),
+        }
+        .ok_or_else(|| LvmError::Usage(format!("Size {size}{size_unit} is too large.")))?;
+
+        // Passed on as --<key> <value> to lvcreate by lvmdbusd.
+        let mut options = HashMap::<&str, Value>::new();
+        let mirrors = extra_u32(extra, "--mirrors");
+        if mirrors > 0 {
+            options.insert("mirrors", Value::from(mirrors.to_string()));
+        }
+        for opt in ["--poolmetadatasize", "--chunksize", "--zero", "--discards"] {
+            if let Some(e) = extra.iter().find(|e| e.opt == opt) {
+                options.insert(&opt[2..], Value::from(e.value.clone()));
+            }
+        }
+
+        let (_lv_path, job): (OwnedObjectPath, OwnedObjectPath) = match segtype {
+            "linear" | "thin-pool" if pvl.is_empty() => self.call(
+                vg_path,
+                VG_IFACE,
+                "LvCreateLinear",
+                &(lv, size_bytes, segtype == "thin-pool", JOB_TMO, options),
+            )?,
+            "thin-pool" => {
+                return Err(LvmError::Unsupported(
+                    "selecting PVs for thin pools via lvmdbusd".to_string(),
+                ));
+            }
+            "thin" => {
+                let pool = extra
+                    .iter()
+                    .find(|e| e.opt == "--thinpool")
+                    .map(|e| e.value.as_str())
+                    .unwrap_or_default();
+                let pool_path = model.lv_path(vg, pool)?;
+                self.call(
+                    pool_path,
+                    THINPOOL_IFACE,
+                    "LvCreate",
+                    &(lv, size_bytes, JOB_TMO, options),
+                )?
+            }
+            "linear" => {
+                // Allow whole of each selected pv, lvm picks the extents.
+                let ranges: Vec<(OwnedObjectPath, u64, u64)> = model
+                    .pvs
+                    .iter()
+                    .filter(|(_, pv)| pvl.contains(&pv.pv_name))
+                    .map(|(path, pv)| {
+                        OwnedObjectPath::try_from(path.as_str())
+                            .map(|p| (p, 0, pv.pe_count.saturating_sub(1)))
+                            .map_err(|e| LvmError::Dbus(e.to_string()))
+                    })
+                    .collect::<Result<_, _>>()?;
+                self.call(
+                    vg_path,
+                    VG_IFACE,
+                    "LvCreate",
+                    &(lv, size_bytes, ranges, JOB_TMO, options),
+                )?
+            }
+            raid if raid.starts_with("raid") => {
+                if !pvl.is_empty() {
+                    return Err(LvmError::Unsupported(
+                        "selecting PVs for raid lvs via lvmdbusd".to_string(),
+                    ));
+                }
+                self.call(
+                    vg_path,
+                    VG_IFACE,
+                    "LvCreateRaid",
+                    &(
+                        lv,
+                        raid,
+                        size_bytes,
+                        extra_u32(extra, "--stripes"),
+                        extra_u32(extra, "--stripesize"),
+                        JOB_TMO,
+                        options,
+                    ),
+                )?
+            }
+            other => {
+                return Err(LvmError::Unsupported(format!(
+                    "segtype {other} via lvmdbusd"
+                )));
+            }
+        };
+
+        if job.as_str() != "/" {
+            self.wait_job(job.as_str())?;
+        }
+        Ok("Created lv".to_string())
+    }
+
+    fn remove_lv(&self, vg: &str, lv: &str) -> Result<String, LvmError> {
+        let model = self.model()?;
+        let lv_path = model.lv_path(vg, lv)?;
+
+        let options = HashMap::<&str, Value>::new();
+        let job: OwnedObjectPath =
+            self.call(lv_path, LV_OPS_IFACE, "Remove", &(JOB_TMO, options))?;
+        if job.as_str() != "/" {
+            self.wait_job(job.as_str())?;
+        }
+        Ok("Removed lv".to_string())
+    }
+
+    //
+    // lvmdbusd takes the new size in bytes, computed here like lvresize would.
+    //
+    fn resize_lv(&self, vg: &str, lv: &str, resize: &LvmResize) -> Result<String, LvmError> {
+        let model = self.model()?;
+        let lv_path = model.lv_path(vg, lv)?;
+        let lv_data = model
+            .lvs
+            .iter()
+            .find(|item| item.vg_name == vg && item.lv_name == lv)
+            .ok_or_else(|| LvmError::Dbus(format!("Logical volume \"{vg}/{lv}\" not found")))?;
+        let (_, vg_data) = model
+            .vgs
+            .iter()
+            .find(|(_, data)| data.name == vg)
+            .ok_or_else(|| LvmError::Dbus(format!("Volume group \"{vg}\" not found")))?;
+        let extent_size = model
+            .pvs
+            .iter()
+            .find(|(_, pv)| pv.vg_name == vg)
+            .map_or(0, |(_, pv)| pv.pe_size);
+        let new_size = resize.new_size(lv_data.size, vg_data, extent_size)?;
+
+        // Passed on as --resizefs to lvresize.
+        let mut options = HashMap::<&str, Value>::new();
+        if resize.resizefs {
+            options.insert("resizefs", Value::from(""));
+        }
+        let pv_dests: Vec<(OwnedObjectPath, u64, u64)> = Vec::new();
+        let job: OwnedObjectPath = self.call(
+            lv_path,
+            LV_OPS_IFACE,
+            "Resize",
+            &(new_size, pv_dests, JOB_TMO, options),
+        )?;
+        if job.as_str() != "/" {
+            self.wait_job(job.as_str())?;
+        }
+        Ok("Resized lv".to_string())
+    }
+
+    fn rename_lv(&self, vg: &str, lv: &str, new_name: &str) -> Result<String, LvmError> {
+        let model = self.model()?;
+        let lv_path = model.lv_path(vg, lv)?;
+
+        let options = HashMap::<&str, Value>::new();
+        let job: OwnedObjectPath = self.call(
+            lv_path,
+            LV_OPS_IFACE,
+            "Rename",
+            &(new_name, JOB_TMO, options),
+        )?;
+        if job.as_str() != "/" {
+            self.wait_job(job.as_str())?;
+        }
+        Ok("Renamed lv".to_string())
+    }
+
+    fn rename_vg(&self, vg: &str, new_name: &str) -> Result<String, LvmError> {
+        let model = self.model()?;
+        let vg_path = model.vg_path(vg)?;
+
+        let options = HashMap::<&str, Value>::new();
+        let job: OwnedObjectPath =
+            self.call(vg_path, VG_IFACE, "Rename", &(new_name, JOB_TMO, options))?;
+        if job.as_str() != "/" {
+            self.wait_job(job.as_str())?;
+        }
+        Ok("Renamed vg".to_string())
+    }
+
+    //
+    // Like resize, lvmdbusd takes the cow size in bytes.
+    //
+    fn create_snapshot(
+        &self,
+        vg: &str,
+        origin: &str,
+        snap: &LvmSnapshot,
+    ) -> Result<String, LvmError> {
+        let model = self.model()?;
+        let lv_path = model.lv_path(vg, origin)?;
+        let origin_size = model
+            .lvs
+            .iter()
+            .find(|item| item.vg_name == vg && item.lv_name == origin)
+            .map_or(0, |item| item.size);
+        let (_, vg_data) = model
+            .vgs
+            .iter()
+            .find(|(_, data)| data.name == vg)
+            .ok_or_else(|| LvmError::Dbus(format!("Volume group \"{vg}\" not found")))?;
+        let extent_size = model
+            .pvs
+            .iter()
+            .find(|(_, pv)| pv.vg_name == vg)
+            .map_or(0, |(_, pv)| pv.pe_size);
+        let size = snap.cow_size(origin_size, vg_data, extent_size)?;
+
+        let options = HashMap::<&str, Value>::new();
+        let (_, job): (OwnedObjectPath, OwnedObjectPath) = self.call(
+            lv_path,
+            LV_OPS_IFACE,
+            "Snapshot",
+            &(snap.name.as_str(), size, JOB_TMO, options),
+        )?;
+        if job.as_str() != "/" {
+            self.wait_job(job.as_str())?;
+        }
+        Ok("Created snapshot".to_string())
+    }
+
+    fn merge_snapshot(&self, vg: &str, snap: &str) -> Result<String, LvmError> {
+        let model = self.model()?;
+        let lv_path = model.lv_path(vg, snap)?;
+
+        let options = HashMap::<&str, Value>::new();
+        let job: OwnedObjectPath =
+            self.call(lv_path, SNAPSHOT_IFACE, "Merge", &(JOB_TMO, options))?;
+        if job.as_str() != "/" {
+            self.wait_job(job.as_str())?;
+        }
+        Ok("Merged snapshot".to_string())
+    }
+
+    //
+    // The fast lv is created on the pv first, a cache pool from a data and a
+    // metadata lv. lvmdbusd has no dm-cache with a cachevol.
+    //
+    fn attach_cache(&self, vg: &str, lv: &str, cache: &LvmCache) -> Result<String, LvmError> {
+        if cache.cache_type == LvmCacheType::CacheVol {
+            return Err(LvmError::Unsupported(
+                "dm-cache with a cachevol via lvmdbusd".to_string(),
+            ));
+        }
+        let pvl = std::slice::from_ref(&cache.fast_pv);
+        self.create_lv(
+            &cache.name,
+            vg,
+            cache.size,
+            &cache.size_unit,
+            "linear",
+            pvl,
+            &[],
+        )?;
+        let meta_name = format!("{}_meta", cache.name);
+        let attached = self.cache_convert(vg, lv, &meta_name, cache);
+        if attached.is_err() {
+            // Converted lvs are gone, leftovers not.
+            let _ = self.remove_lv(vg, &cache.name);
+            let _ = self.remove_lv(vg, &meta_name);
+        }
+        attached
+    }
+
+    fn detach_cache(&self, vg: &str, lv: &str, keep: bool) -> Result<String, LvmError> {
+        let model = self.model()?;
+        let lv_path = model.lv_path(vg, lv)?;
+
+        let options = HashMap::<&str, Value>::new();
+        let (_, job): (OwnedObjectPath, OwnedObjectPath) = self.call(
+            lv_path,
+            CACHEDLV_IFACE,
+            "DetachCachePool",
+            &(!keep, JOB_TMO, options),
+        )?;
+        if job.as_str() != "/" {
+            self.wait_job(job.as_str())?;
+        }
+        Ok(if keep { "Split cache" } else { "Removed cache" }.to_string())
+    }
+
+    //
+    // lvmdbusd has no raid scrubbing, repair or image replace, and does not
+    // report sync action or mismatches. Use the cmd backend for these.
+    //
+    fn raid_sync_action(
+        &self,
+        _vg: &str,
+        _lv: &str,
+        action: LvmSyncAction,
+    ) -> Result<String, LvmError> {
+        Err(LvmError::Unsupported(format!(
+            "raid {} via lvmdbusd",
+            action.text()
+        )))
+    }
+
+    fn repair_raid(&self, _vg: &str, _lv: &str, _pvl: &[String]) -> Result<String, LvmError> {
+        Err(LvmError::Unsupported(
+            "raid repair via lvmdbusd".to_string(),
+        ))
+    }
+
+    fn replace_raid_pv(
+        &self,
+        _vg: &str,
+        _lv: &str,
+        _old_pv: &str,
+        _pvl: &[String],
+    ) -> Result<String, LvmError> {
+        Err(LvmError::Unsupported(
+            "raid image replace via lvmdbusd".to_string(),
+        ))
+    }
+
+    //
+    // lvmdbusd has no lvconvert --type, only cache attach and detach.
+    //
+    fn convert_lv(
+        &self,
+        _vg: &str,
+        _lv: &str,
+        convert: &LvmConvert,
+        _pvl: &[String],
+    ) -> Result<String, LvmError> {
+        Err(LvmError::Unsupported(format!(
+            "converting to {} via lvmdbusd",
+            convert.segtype
+        )))
+    }
+
+    //
+    // lvmdbusd does not know devices that are not pvs, they are read here
+    // like for the cmd backend.
+    //
+    fn get_block_devs(&self) -> Result<Vec<BlockDev>, LvmError> {
+        Ok(devinfo::block_devs())
+    }
+
+    fn create_pv(&self, dev: &str, pv: &LvmPvCreate) -> Result<String, LvmError> {
+        // Passed on as --<key> <value> to pvcreate.
+        let mut options = HashMap::<&str, Value>::new();
+        if let Some(copies) = pv.metadata_copies {
+            options.insert("pvmetadatacopies", Value::from(copies.to_string()));
+        }
+        if let Some(alignment) = pv.data_alignment {
+            options.insert("dataalignment", Value::from(exact_size_arg(alignment)));
+        }
+        let (_, job): (OwnedObjectPath, OwnedObjectPath) = self.call(
+            MANAGER_PATH,
+            MANAGER_IFACE,
+            "PvCreate",
+            &(dev, JOB_TMO, options),
+        )?;
+        if job.as_str() != "/" {
+            self.wait_job(job.as_str())?;
+        }
+        Ok("Created pv".to_string())
+    }
+
+    fn create_vg(&self, vg: &str, pvl: &[String], opts: &LvmVgCreate) -> Result<String, LvmError> {
+        let pv_paths = self.model()?.pv_paths(pvl)?;
+        // Passed on as --<key> <value> to vgcreate.
+        let mut options = HashMap::<&str, Value>::new();
+        if let Some(extent_size) = opts.extent_size {
+            options.insert(
+                "physicalextentsize",
+                Value::from(exact_size_arg(extent_size)),
+            );
+        }
+        if opts.alloc != AllocPolicy::Unset {
+            options.insert("alloc", Value::from(opts.alloc.text()));
+        }
+        let (_, job): (OwnedObjectPath, OwnedObjectPath) = self.call(
+            MANAGER_PATH,
+            MANAGER_IFACE,
+            "VgCreate",
+            &(vg, pv_paths, JOB_TMO, options),
+        )?;
+        if job.as_str() != "/" {
+            self.wait_job(job.as_str())?;
+        }
+        Ok("Created vg".to_string())
+    }
+
+    fn extend_vg(&self, vg: &str, pvl: &[String]) -> Result<String, LvmError> {
+        let model = self.model()?;
+        let pv_paths = model.pv_paths(pvl)?;
+
+        let options = HashMap::<&str, Value>::new();
+        let job: OwnedObjectPath = self.call(
+            model.vg_path(vg)?,
+            VG_IFACE,
+            "Extend",
+            &(pv_paths, JOB_TMO, options),
+        )?;
+        if job.as_str() != "/" {
+            self.wait_job(job.as_str())?;
+        }
+        Ok("Extended vg".to_string())
+    }
+
+    //
+    // Reduce takes 'missing' first, to drop all missing pvs instead of pvl.
+    //
+    fn reduce_vg(&self, vg: &str, pvl: &[String]) -> Result<String, LvmError> {
+        let model = self.model()?;
+        let pv_paths = model.pv_paths(pvl)?;
+
+        let options = HashMap::<&str, Value>::new();
+        let job: OwnedObjectPath = self.call(
+            model.vg_path(vg)?,
+            VG_IFACE,
+            "Reduce",
+            &(false, pv_paths, JOB_TMO, options),
+        )?;
+        if job.as_str() != "/" {
+            self.wait_job(job.as_str())?;
+        }
+        Ok("Reduced vg".to_string())
+    }
+
+    //
+    // Pv.Move, or Lv.Move for the extents of one lv. The job is not waited
+    // for, the pvmove lv shows the progress like with the cmd backend.
+    //
+    fn move_pv(&self, vg: &str, mv: &LvmPvMove) -> Result<String, LvmError> {
+        let model = self.model()?;
+        let src = model.pv_paths(std::slice::from_ref(&mv.src_pv))?.remove(0);
+        // (0, 0) is all of the pv, for the source and each destination.
+        let range = mv.pe_range.unwrap_or((0, 0));
+        let dests: Vec<(OwnedObjectPath, u64, u64)> = model
+            .pv_paths(&mv.dest_pvs)?
+            .into_iter()
+            .map(|path| (path, 0, 0))
+            .collect();
+
+        let options = HashMap::<&str, Value>::new();
+        let _job: OwnedObjectPath = if mv.lv_name.is_empty() {
+            self.call(
+                src.as_str(),
+                PV_IFACE,
+                "Move",
+                &(range, dests, MOVE_TMO, options),
+            )?
+        } else {
+            self.call(
+                model.lv_path(vg, &mv.lv_name)?,
+                LV_OPS_IFACE,
+                "Move",
+                &(src, range, dests, MOVE_TMO, options),
+            )?
+        };
+        Ok("Started pvmove".to_string())
+    }
+
+    fn abort_pvmove(&self) -> Result<String, LvmError> {
+        Err(LvmError::Unsupported(
+            "aborting pvmove via lvmdbusd".to_string(),
+        ))
+    }
+
+    fn pending_jobs(&self) -> Vec<LvmBackendJob> {
+        self.jobs.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::os::unix::net::UnixStream;
+    use std::sync::{Arc, Mutex};
+
+    use zbus::blocking::connection::Builder;
+    use zbus::zvariant::OwnedObjectPath;
+    use zbus::{ObjectServer, fdo, interface};
+
+    use crate::lvm::dbus::{DbusBackend, Devices, ROOT_PATH};
+    use crate::lvm::{
+        AllocPolicy, LvmBackend, LvmCache, LvmCacheType, LvmConvert, LvmError, LvmExtraArg,
+        LvmLvData, LvmPvCreate, LvmPvMove, LvmResize, LvmSnapshot, LvmSyncAction, LvmVgCreate,
+        pvmoves,
+    };
+
+    //
+    // Stand-in for the part of lvmdbusd we use. One vg with one pv and a
+    // raid1 lv, new lvs are published as objects, raid creation returns a job.
+    // New pvs and vgs are objects too, options given are kept in 'options'.
+    //
+    struct Manager {
+        options: Arc<Mutex<Vec<String>>>,
+        seq: u32,
+    }
+
+    type Options = std::collections::HashMap<String, zbus::zvariant::OwnedValue>;
+
+    fn keep_options(kept: &Mutex<Vec<String>>, options: &Options) {
+        let mut kept = kept.lock().unwrap();
+        for (key, value) in options {
+            let value = String::try_from(value.try_clone().unwrap()).unwrap();
+            kept.push(format!("{key}={value}"));
+        }
+        kept.sort();
+    }
+
+    async fn set_pv_vg(server: &ObjectServer, pv: &OwnedObjectPath, vg: OwnedObjectPath) {
+        let pv = server.interface::<_, Pv>(pv).await.unwrap();
+        let mut pv = pv.get_mut().await;
+        pv.pe_count = if vg.as_str() == "/" { 0 } else { 2560 };
+        pv.vg = vg;
+    }
+
+    #[interface(name = "com.redhat.lvmdbus1.Manager")]
+    impl Manager {
+        #[zbus(property)]
+        fn version(&self) -> String {
+            "1.1.0".to_string()
+        }
+
+        async fn pv_create(
+            &mut self,
+            device: String,
+            _tmo: i32,
+            options: Options,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+            if device == "/dev/sdx" {
+                return Err(fdo::Error::Failed(
+                    "Exit code 5, stderr = Cannot use /dev/sdx: device is partitioned".to_string(),
+                ));
+            }
+            keep_options(&self.options, &options);
+            self.seq += 1;
+            let pv_path = path(&format!("{ROOT_PATH}/Pv/{}", self.seq));
+            server
+                .at(
+                    &pv_path,
+                    Pv {
+                        name: device,
+                        vg: path("/"),
+                        pe_count: 0,
+                        pe_alloc_count: 0,
+                    },
+                )
+                .await?;
+            Ok((pv_path, path("/")))
+        }
+
+        async fn vg_create(
+            &mut self,
+            name: String,
+            pvs: Vec<OwnedObjectPath>,
+            _tmo: i32,
+            options: Options,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+            keep_options(&self.options, &options);
+            self.seq += 1;
+            let vg_path = path(&format!("{ROOT_PATH}/Vg/{}", self.seq));
+            let state = Arc::new(Mutex::new(StandinState {
+                vg_name: name,
+                free: 0,
+                seq: 100 * self.seq,
+            }));
+            server
+                .at(
+                    &vg_path,
+                    Vg {
+                        path: vg_path.clone(),
+                        state,
+                    },
+                )
+                .await?;
+            for pv in pvs {
+                set_pv_vg(server, &pv, vg_path.clone()).await;
+            }
+            Ok((vg_path, path("/")))
+        }
+    }
+
+    #[derive(Default)]
+    struct StandinState {
+        vg_name: String,
+        free: u64,
+        seq: u32,
+    }
+
+    struct Vg {
+        path: OwnedObjectPath,
+        state: Arc<Mutex<StandinState>>,
+    }
+
+    fn path(p: &str) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(p).unwrap()
+    }
+
+    impl Vg {
+        async fn add_lv(
+            &self,
+            server: &ObjectServer,
+            name: &str,
+            size: u64,
+            segtype: &str,
+        ) -> OwnedObjectPath {
+            let seq = {
+                let mut state = self.state.lock().unwrap();
+                state.free -= size;
+                state.seq += 1;
+                state.seq
+            };
+            let lv_path = path(&format!("{ROOT_PATH}/Lv/{seq}"));
+            server
+                .at(
+                    &lv_path,
+                    Lv {
+                        name: name.to_string(),
+                        size,
+                        segtype: segtype.to_string(),
+                        devices: vec![(
+                            path(&format!("{ROOT_PATH}/Pv/0")),
+                            vec![(0, 9, "linear".to_string())],
+                        )],
+                        hidden: vec![],
+                        origin: path("/"),
+                        pool: path("/"),
+                        move_pv: path("/"),
+                    },
+                )
+                .await
+                .unwrap();
+            server
+                .at(
+                    &lv_path,
+                    LvOps {
+                        state: self.state.clone(),
+                        size,
+                    },
+                )
+                .await
+                .unwrap();
+            lv_path
+        }
+    }
+
+    #[interface(name = "com.redhat.lvmdbus1.Vg")]
+    impl Vg {
+        #[zbus(property)]
+        fn name(&self) -> String {
+            self.state.lock().unwrap().vg_name.clone()
+        }
+        #[zbus(property)]
+        fn uuid(&self) -> String {
+            "VGUUID-0000".to_string()
+        }
+        #[zbus(property)]
+        fn size_bytes(&self) -> u64 {
+            10 * 1024 * 1024 * 1024
+        }
+        #[zbus(property)]
+        fn free_bytes(&self) -> u64 {
+            self.state.lock().unwrap().free
+        }
+        #[zbus(property)]
+        fn pv_count(&self) -> u64 {
+            1
+        }
+        #[zbus(property)]
+        fn writeable(&self) -> bool {
+            true
+        }
+        #[zbus(property)]
+        fn resizeable(&self) -> bool {
+            true
+        }
+        #[zbus(property)]
+        fn alloc_normal(&self) -> bool {
+            true
+        }
+        #[zbus(property)]
+        fn extent_size_bytes(&self) -> u64 {
+            4 * 1024 * 1024
+        }
+
+        async fn lv_create_linear(
+            &self,
+            name: String,
+            size_bytes: u64,
+            thin_pool: bool,
+            _tmo: i32,
+            _options: std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+            if name == "bad" {
+                return Err(fdo::Error::Failed(
+                    "Exit code 5, stderr = invalid name".to_string(),
+                ));
+            }
+            let segtype = if thin_pool { "thin-pool" } else { "linear" };
+            let lv_path = self.add_lv(server, &name, size_bytes, segtype).await;
+            if thin_pool {
+                server.at(&lv_path, ThinPoolOps).await?;
+            }
+            Ok((lv_path, path("/")))
+        }
+
+        async fn lv_create(
+            &self,
+            name: String,
+            size_bytes: u64,
+            _pv_dests: Vec<(OwnedObjectPath, u64, u64)>,
+            _tmo: i32,
+            _options: std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+            let lv_path = self.add_lv(server, &name, size_bytes, "linear").await;
+            Ok((lv_path, path("/")))
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        async fn lv_create_raid(
+            &self,
+            name: String,
+            _raid_type: String,
+            size_bytes: u64,
+            _num_stripes: u32,
+            _stripe_size_kb: u32,
+            _tmo: i32,
+            _options: std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+            self.add_lv(server, &name, size_bytes * 2, "linear").await;
+            let job_path = path(&format!("{ROOT_PATH}/Job/0"));
+            server
+                .at(
+                    &job_path,
+                    Job {
+                        percent: 0.0,
+                        complete: false,
+                        stuck: name == "lvstuck",
+                    },
+                )
+                .await
+                .unwrap();
+            // Result is not known until job is done.
+            Ok((path("/"), job_path))
+        }
+
+        fn rename(
+            &self,
+            name: String,
+            _tmo: i32,
+            _options: std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+        ) -> OwnedObjectPath {
+            self.state.lock().unwrap().vg_name = name;
+            path("/")
+        }
+
+        async fn extend(
+            &self,
+            pvs: Vec<OwnedObjectPath>,
+            _tmo: i32,
+            _options: Options,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> OwnedObjectPath {
+            for pv in pvs {
+                set_pv_vg(server, &pv, self.path.clone()).await;
+            }
+            path("/")
+        }
+
+        async fn reduce(
+            &self,
+            missing: bool,
+            pvs: Vec<OwnedObjectPath>,
+            _tmo: i32,
+            _options: Options,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<OwnedObjectPath> {
+            assert!(!missing);
+            for pv in pvs {
+                let used = server
+                    .interface::<_, Pv>(&pv)
+                    .await?
+                    .get()
+                    .await
+                    .pe_alloc_count;
+                if used > 0 {
+                    return Err(fdo::Error::Failed(format!(
+                        "Exit code 5, stderr = Physical volume {pv} still in use"
+                    )));
+                }
+                set_pv_vg(server, &pv, path("/")).await;
+            }
+            Ok(path("/"))
+        }
+
+        // The data lv becomes the pool, the metadata lv is hidden in it.
+        async fn create_cache_pool(
+            &self,
+            meta: OwnedObjectPath,
+            data: OwnedObjectPath,
+            _tmo: i32,
+            _options: std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+            server.remove::<Lv, _>(&meta).await?;
+            server.remove::<LvOps, _>(&meta).await?;
+            let lv = server.interface::<_, Lv>(&data).await?;
+            lv.get_mut().await.segtype = "cache-pool".to_string();
+            server.at(&data, CachePoolOps).await?;
+            Ok((data, path("/")))
+        }
+    }
+
+    struct Pv {
+        name: String,
+        vg: OwnedObjectPath,
+        pe_count: u64,
+        pe_alloc_count: u64,
+    }
+
+    #[interface(name = "com.redhat.lvmdbus1.Pv")]
+    impl Pv {
+        #[zbus(property)]
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+        #[zbus(property)]
+        fn vg(&self) -> OwnedObjectPath {
+            self.vg.clone()
+        }
+        #[zbus(property)]
+        fn pe_count(&self) -> u64 {
+            self.pe_count
+        }
+        #[zbus(property)]
+        fn pe_alloc_count(&self) -> u64 {
+            self.pe_alloc_count
+        }
+        #[zbus(property)]
+        fn allocatable(&self) -> bool {
+            true
+        }
+
+        #[zbus(name = "Move")]
+        async fn move_extents(
+            &self,
+            _range: (u64, u64),
+            _dests: Vec<(OwnedObjectPath, u64, u64)>,
+            _tmo: i32,
+            _options: Options,
+            #[zbus(header)] header: zbus::message::Header<'_>,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<OwnedObjectPath> {
+            let src = OwnedObjectPath::from(header.path().unwrap().to_owned());
+            start_pvmove(server, src).await
+        }
+    }
+
+    // Shows up as a hidden [pvmove0] lv, half copied, until the test ends.
+    async fn start_pvmove(
+        server: &ObjectServer,
+        src: OwnedObjectPath,
+    ) -> fdo::Result<OwnedObjectPath> {
+        let mv = Lv {
+            name: "[pvmove0]".to_string(),
+            size: 1024 * 1024 * 1024,
+            segtype: "mirror".to_string(),
+            devices: vec![],
+            hidden: vec![],
+            origin: path("/"),
+            pool: path("/"),
+            move_pv: src,
+        };
+        if !server.at(format!("{ROOT_PATH}/HiddenLv/9"), mv).await? {
+            return Err(fdo::Error::Failed(
+                "Exit code 5, stderr = Detected pvmove in progress".to_string(),
+            ));
+        }
+        Ok(path("/"))
+    }
+
+    struct Lv {
+        name: String,
+        size: u64,
+        segtype: String,
+        devices: Devices,
+        hidden: Vec<OwnedObjectPath>,
+        origin: OwnedObjectPath,
+        pool: OwnedObjectPath,
+        move_pv: OwnedObjectPath,
+    }
+
+    #[interface(name = "com.redhat.lvmdbus1.LvCommon")]
+    impl Lv {
+        #[zbus(property)]
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+        #[zbus(property)]
+        fn uuid(&self) -> String {
+            format!("UUID-{}", self.name)
+        }
+        #[zbus(property)]
+        fn size_bytes(&self) -> u64 {
+            self.size
+        }
+        #[zbus(property)]
+        fn attr(&self) -> String {
+            "-wi-a-----".to_string()
+        }
+        #[zbus(property)]
+        fn path(&self) -> String {
+            format!("/dev/vg01/{}", self.name)
+        }
+        #[zbus(property)]
+        fn sync_percent(&self) -> u32 {
+            100
+        }
+        #[zbus(property)]
+        fn health(&self) -> (String, String) {
+            ("-".to_string(), "Unspecified".to_string())
+        }
+        #[zbus(property)]
+        fn seg_type(&self) -> Vec<String> {
+            vec![self.segtype.clone()]
+        }
+        #[zbus(property)]
+        fn vg(&self) -> OwnedObjectPath {
+            path(&format!("{ROOT_PATH}/Vg/0"))
+        }
+        #[zbus(property)]
+        fn devices(&self) -> Devices {
+            self.devices.clone()
+        }
+        #[zbus(property)]
+        fn hidden_lvs(&self) -> Vec<OwnedObjectPath> {
+            self.hidden.clone()
+        }
+        #[zbus(property)]
+        fn origin_lv(&self) -> OwnedObjectPath {
+            self.origin.clone()
+        }
+        #[zbus(property)]
+        fn snap_percent(&self) -> u32 {
+            if self.origin.as_str() == "/" { 0 } else { 5 }
+        }
+        #[zbus(property)]
+        fn pool_lv(&self) -> OwnedObjectPath {
+            self.pool.clone()
+        }
+        #[zbus(property)]
+        fn data_percent(&self) -> u32 {
+            if self.segtype.starts_with("thin") {
+                42
+            } else {
+                0
+            }
+        }
+        #[zbus(property)]
+        fn meta_data_percent(&self) -> u32 {
+            if self.segtype == "thin-pool" { 7 } else { 0 }
+        }
+        #[zbus(property)]
+        fn move_pv(&self) -> OwnedObjectPath {
+            self.move_pv.clone()
+        }
+        #[zbus(property)]
+        fn copy_percent(&self) -> u32 {
+            if self.move_pv.as_str() == "/" { 0 } else { 50 }
+        }
+    }
+
+    // The lvs created by the tests can be removed, resized, renamed and snapshotted.
+    struct LvOps {
+        state: Arc<Mutex<StandinState>>,
+        size: u64,
+    }
+
+    #[interface(name = "com.redhat.lvmdbus1.Lv")]
+    impl LvOps {
+        async fn remove(
+            &self,
+            _tmo: i32,
+            _options: std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+            #[zbus(header)] header: zbus::message::Header<'_>,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<OwnedObjectPath> {
+            let lv_path = header.path().unwrap().to_owned();
+            server.remove::<Lv, _>(&lv_path).await?;
+            server.remove::<LvOps, _>(&lv_path).await?;
+            self.state.lock().unwrap().free += self.size;
+            Ok(path("/"))
+        }
+
+        async fn resize(
+            &mut self,
+            new_size_bytes: u64,
+            _pv_dests: Vec<(OwnedObjectPath, u64, u64)>,
+            _tmo: i32,
+            options: std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+            #[zbus(header)] header: zbus::message::Header<'_>,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<OwnedObjectPath> {
+            if !options.contains_key("resizefs") {
+                return Err(fdo::Error::Failed("expected resizefs".to_string()));
+            }
+            let lv_path = header.path().unwrap().to_owned();
+            let lv = server.interface::<_, Lv>(&lv_path).await?;
+            lv.get_mut().await.size = new_size_bytes;
+            let mut state = self.state.lock().unwrap();
+            state.free = state.free + self.size - new_size_bytes;
+            self.size = new_size_bytes;
+            Ok(path("/"))
+        }
+
+        async fn rename(
+            &self,
+            name: String,
+            _tmo: i32,
+            _options: std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+            #[zbus(header)] header: zbus::message::Header<'_>,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<OwnedObjectPath> {
+            let lv_path = header.path().unwrap().to_owned();
+            let lv = server.interface::<_, Lv>(&lv_path).await?;
+            lv.get_mut().await.name = name;
+            Ok(path("/"))
+        }
+
+        async fn snapshot(
+            &self,
+            name: String,
+            optional_size: u64,
+            _tmo: i32,
+            _options: std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+            #[zbus(header)] header: zbus::message::Header<'_>,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+            let origin = OwnedObjectPath::from(header.path().unwrap().to_owned());
+            let seq = {
+                let mut state = self.state.lock().unwrap();
+                state.free -= optional_size;
+                state.seq += 1;
+                state.seq
+            };
+            let snap_path = path(&format!("{ROOT_PATH}/Lv/{seq}"));
+            let snap = Lv {
+                name,
+                size: optional_size,
+                segtype: "linear".to_string(),
+                devices: vec![],
+                hidden: vec![],
+                origin,
+                pool: path("/"),
+                move_pv: path("/"),
+            };
+            server.at(&snap_path, snap).await?;
+            let ops = SnapshotOps {
+                state: self.state.clone(),
+                size: optional_size,
+            };
+            server.at(&snap_path, ops).await?;
+            Ok((snap_path, path("/")))
+        }
+
+        async fn write_cache_lv(
+            &self,
+            lv_object: OwnedObjectPath,
+            _tmo: i32,
+            _options: std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+            #[zbus(header)] header: zbus::message::Header<'_>,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+            let fast = OwnedObjectPath::from(header.path().unwrap().to_owned());
+            cache_lv(server, &lv_object, fast, "writecache").await?;
+            Ok((lv_object, path("/")))
+        }
+
+        #[zbus(name = "Move")]
+        async fn move_extents(
+            &self,
+            pv_src: OwnedObjectPath,
+            _range: (u64, u64),
+            _dests: Vec<(OwnedObjectPath, u64, u64)>,
+            _tmo: i32,
+            _options: Options,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<OwnedObjectPath> {
+            start_pvmove(server, pv_src).await
+        }
+    }
+
+    // Cached lv gets the fast lv as pool.
+    async fn cache_lv(
+        server: &ObjectServer,
+        lv_object: &OwnedObjectPath,
+        fast: OwnedObjectPath,
+        segtype: &str,
+    ) -> fdo::Result<()> {
+        let lv = server.interface::<_, Lv>(lv_object).await?;
+        let mut lv = lv.get_mut().await;
+        lv.segtype = segtype.to_string();
+        lv.pool = fast;
+        server.at(lv_object, CachedLvOps).await?;
+        Ok(())
+    }
+
+    struct CachePoolOps;
+
+    #[interface(name = "com.redhat.lvmdbus1.CachePool")]
+    impl CachePoolOps {
+        async fn cache_lv(
+            &self,
+            lv_object: OwnedObjectPath,
+            _tmo: i32,
+            options: std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+            #[zbus(header)] header: zbus::message::Header<'_>,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+            if !options.contains_key("cachemode") {
+                return Err(fdo::Error::Failed("expected cachemode".to_string()));
+            }
+            let pool = OwnedObjectPath::from(header.path().unwrap().to_owned());
+            cache_lv(server, &lv_object, pool, "cache").await?;
+            Ok((lv_object, path("/")))
+        }
+    }
+
+    struct CachedLvOps;
+
+    #[interface(name = "com.redhat.lvmdbus1.CachedLv")]
+    impl CachedLvOps {
+        async fn detach_cache_pool(
+            &self,
+            destroy_cache: bool,
+            _tmo: i32,
+            _options: std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+            #[zbus(header)] header: zbus::message::Header<'_>,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+            let lv_path = header.path().unwrap().to_owned();
+            let lv = server.interface::<_, Lv>(&lv_path).await?;
+            let fast = {
+                let mut lv = lv.get_mut().await;
+                lv.segtype = "linear".to_string();
+                std::mem::replace(&mut lv.pool, path("/"))
+            };
+            server.remove::<CachedLvOps, _>(&lv_path).await?;
+            if !destroy_cache {
+                return Ok((fast, path("/")));
+            }
+            // Not all of them exist on each kind of fast lv.
+            let _ = server.remove::<Lv, _>(&fast).await;
+            let _ = server.remove::<LvOps, _>(&fast).await;
+            let _ = server.remove::<CachePoolOps, _>(&fast).await;
+            Ok((path("/"), path("/")))
+        }
+    }
+
+    // Thin lvs in a pool take no space from the vg.
+    struct ThinPoolOps;
+
+    #[interface(name = "com.redhat.lvmdbus1.ThinPool")]
+    impl ThinPoolOps {
+        async fn lv_create(
+            &self,
+            name: String,
+            size_bytes: u64,
+            _tmo: i32,
+            _options: std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+            #[zbus(header)] header: zbus::message::Header<'_>,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+            let pool = OwnedObjectPath::from(header.path().unwrap().to_owned());
+            let thin_path = path(&format!("{ROOT_PATH}/Lv/{name}"));
+            let thin = Lv {
+                name,
+                size: size_bytes,
+                segtype: "thin".to_string(),
+                devices: vec![],
+                hidden: vec![],
+                origin: path("/"),
+                pool,
+                move_pv: path("/"),
+            };
+            server.at(&thin_path, thin).await?;
+            Ok((thin_path, path("/")))
+        }
+    }
+
+    struct SnapshotOps {
+        state: Arc<Mutex<StandinState>>,
+        size: u64,
+    }
+
+    #[interface(name = "com.redhat.lvmdbus1.Snapshot")]
+    impl SnapshotOps {
+        async fn merge(
+            &self,
+            _tmo: i32,
+            _options: std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+            #[zbus(header)] header: zbus::message::Header<'_>,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<OwnedObjectPath> {
+            let snap_path = header.path().unwrap().to_owned();
+            server.remove::<Lv, _>(&snap_path).await?;
+            server.remove::<SnapshotOps, _>(&snap_path).await?;
+            self.state.lock().unwrap().free += self.size;
+            Ok(path("/"))
+        }
+    }
+
+    // A stuck job can not be removed once done.
+    struct Job {
+        percent: f64,
+        complete: bool,
+        stuck: bool,
+    }
+
+    #[interface(name = "com.redhat.lvmdbus1.Job")]
+    impl Job {
+        #[zbus(property)]
+        fn percent(&self) -> f64 {
+            self.percent
+        }
+        #[zbus(property)]
+        fn complete(&self) -> bool {
+            self.complete
+        }
+        #[zbus(property)]
+        fn get_error(&self) -> (i32, String) {
+            (0, String::new())
+        }
+
+        // Takes two rounds to finish.
+        fn wait(&mut self, _timeout: i32) -> bool {
+            if self.percent < 50.0 {
+                self.percent = 50.0;
+            } else {
+                self.percent = 100.0;
+                self.complete = true;
+            }
+            self.complete
+        }
+
+        async fn remove(&self, #[zbus(object_server)] server: &ObjectServer) -> fdo::Result<()> {
+            if self.stuck {
+                return Err(fdo::Error::Failed("job is busy".to_string()));
+            }
+            server
+                .remove::<Job, _>(format!("{ROOT_PATH}/Job/0"))
+                .await?;
+            Ok(())
+        }
+    }
+
+    fn standin() -> (
+        DbusBackend,
+        zbus::blocking::Connection,
+        Arc<Mutex<Vec<String>>>,
+    ) {
+        let (server_sock, client_sock) = UnixStream::pair().unwrap();
+        let state = Arc::new(Mutex::new(StandinState {
+            vg_name: "vg01".to_string(),
+            free: 8 * 1024 * 1024 * 1024,
+            seq: 0,
+        }));
+
+        let options = Arc::new(Mutex::new(Vec::new()));
+        let manager = Manager {
+            options: options.clone(),
+            seq: 0,
+        };
+
+        let server = std::thread::spawn(move || {
+            let rimage = |i: u64| Lv {
+                name: format!("[lvr1_rimage_{i}]"),
+                size: 1024 * 1024 * 1024,
+                segtype: "linear".to_string(),
+                devices: vec![(
+                    path(&format!("{ROOT_PATH}/Pv/0")),
+                    vec![(256 * i + 1, 256 * i + 256, "linear".to_string())],
+                )],
+                hidden: vec![],
+                origin: path("/"),
+                pool: path("/"),
+                move_pv: path("/"),
+            };
+            Builder::async_io_unix_stream(server_sock)
+                .server(zbus::Guid::generate())
+                .unwrap()
+                .p2p()
+                .serve_at(ROOT_PATH, fdo::ObjectManager)
+                .unwrap()
+                .serve_at(format!("{ROOT_PATH}/Manager"), manager)
+                .unwrap()
+                .serve_at(
+                    format!("{ROOT_PATH}/Vg/0"),
+                    Vg {
+                        path: path(&format!("{ROOT_PATH}/Vg/0")),
+                        state,
+                    },
+                )
+                .unwrap()
+                .serve_at(
+                    format!("{ROOT_PATH}/Pv/0"),
+                    Pv {
+                        name: "/dev/sda1".to_string(),
+                        vg: path(&format!("{ROOT_PATH}/Vg/0")),
+                        pe_count: 2560,
+                        pe_alloc_count: 512,
+                    },
+                )
+                .unwrap()
+                .serve_at(
+                    format!("{ROOT_PATH}/Lv/0"),
+                    Lv {
+                        name: "lvr1".to_string(),
+                        size: 1024 * 1024 * 1024,
+                        segtype: "raid1".to_string(),
+                        devices: vec![],
+                        hidden: vec![
+                            path(&format!("{ROOT_PATH}/HiddenLv/0")),
+                            path(&format!("{ROOT_PATH}/HiddenLv/1")),
+                        ],
+                        origin: path("/"),
+                        pool: path("/"),
+                        move_pv: path("/"),
+                    },
+                )
+                .unwrap()
+                .serve_at(format!("{ROOT_PATH}/HiddenLv/0"), rimage(0))
+                .unwrap()
+                .serve_at(format!("{ROOT_PATH}/HiddenLv/1"), rimage(1))
+                .unwrap()
+                .build()
+                .unwrap()
+        });
+
+        let client = Builder::async_io_unix_stream(client_sock)
+            .p2p()
+            .build()
+            .unwrap();
+        let server = server.join().unwrap();
+        (DbusBackend::with_connection(client), server, options)
+    }
+
+    fn lv<'a>(lvs: &'a [LvmLvData], name: &str) -> &'a LvmLvData {
+        lvs.iter().find(|lv| lv.lv_name == name).unwrap()
+    }
+
+    fn has_lv(backend: &DbusBackend, name: &str) -> bool {
+        backend
+            .get_lvs()
+            .unwrap()
+            .iter()
+            .any(|lv| lv.lv_name == name)
+    }
+
+    fn mirrors() -> [LvmExtraArg; 1] {
+        [LvmExtraArg {
+            opt: "--mirrors".to_string(),
+            value: "1".to_string(),
+        }]
+    }
+
+    // Stand-in with a 1G linear lv 'lvbig' and a raid1 'lvraid' next to lvr1.
+    fn standin_with_lvs() -> (DbusBackend, zbus::blocking::Connection) {
+        let (backend, server, _) = standin();
+        backend
+            .create_lv("lvbig", "vg01", 1, "G", "linear", &[], &[])
+            .unwrap();
+        backend
+            .create_lv("lvraid", "vg01", 1, "G", "raid1", &[], &mirrors())
+            .unwrap();
+        (backend, server)
+    }
+
+    #[test]
+    fn test_dbus_read() {
+        let (backend, _server, _) = standin();
+        assert_eq!(backend.version().unwrap(), "1.1.0");
+
+        assert_eq!(backend.get_vgs().unwrap(), vec!["vg01"]);
+        let vg = backend.get_vg_info("vg01").unwrap();
+        assert_eq!(vg.size, 10 * 1024 * 1024 * 1024);
+        assert_eq!(vg.free, 8 * 1024 * 1024 * 1024);
+        assert_eq!(vg.attr, "wz--n-");
+        assert!(backend.get_vg_info("vg02").is_err());
+
+        let pvs = backend.get_pvs().unwrap();
+        assert_eq!(pvs.len(), 1);
+        assert_eq!(pvs[0].pv_name, "/dev/sda1");
+        assert_eq!(pvs[0].vg_name, "vg01");
+        assert_eq!(pvs[0].pe_size, 4 * 1024 * 1024);
+        assert_eq!(pvs[0].pe_alloc_count, 512);
+        assert_eq!(pvs[0].pv_attr, "a--");
+
+        // raid1 with its two images as segments
+        let lvs = backend.get_lvs().unwrap();
+        assert_eq!(lvs.len(), 3);
+        let lvr1 = lv(&lvs, "lvr1");
+        assert_eq!(lvr1.segtype, "raid1");
+        assert_eq!(lvr1.stripes, 2);
+        assert_eq!(lvr1.data_stripes, 1);
+        assert_eq!(lvr1.lv_segs.len(), 2);
+        assert_eq!(lvr1.lv_segs[0].pvdev, "[lvr1_rimage_0]");
+        assert_eq!(lvr1.lv_segs[0].size_pe, 256);
+        assert_eq!(lvr1.path, "/dev/vg01/lvr1");
+        assert_eq!(lvr1.sync_percent, Some(100.0));
+        assert_eq!(lvr1.health_status, "");
+        let rimage = lv(&lvs, "[lvr1_rimage_1]");
+        assert_eq!(rimage.parent_lv, "lvr1");
+        assert_eq!(rimage.lv_segs[0].pvdev, "/dev/sda1");
+        assert_eq!(rimage.lv_segs[0].pv_start_pe, 257);
+        assert_eq!(rimage.lv_segs[0].size_pe, 256);
+    }
+
+    #[test]
+    fn test_dbus_create_remove_lv() {
+        let (backend, _server, _) = standin();
+
+        // Created right away
+        backend
+            .create_lv("lvnew", "vg01", 1, "G", "linear", &[], &[])
+            .unwrap();
+        assert!(has_lv(&backend, "lvnew"));
+        assert_eq!(
+            backend.get_vg_info("vg01").unwrap().free,
+            7 * 1024 * 1024 * 1024
+        );
+
+        // Created via job
+        backend
+            .create_lv("lvraid", "vg01", 1, "G", "raid1", &[], &mirrors())
+            .unwrap();
+        assert!(has_lv(&backend, "lvraid"));
+        assert!(backend.pending_jobs().is_empty());
+
+        // Removed, space is back
+        backend.remove_lv("vg01", "lvnew").unwrap();
+        assert!(!has_lv(&backend, "lvnew"));
+        assert_eq!(
+            backend.get_vg_info("vg01").unwrap().free,
+            6 * 1024 * 1024 * 1024
+        );
+        assert!(backend.remove_lv("vg01", "lvnew").is_err());
+
+        // Too large for u64, nothing sent
+        let e = backend
+            .create_lv("lvhuge", "vg01", 99999999999, "T", "linear", &[], &[])
+            .unwrap_err();
+        assert!(matches!(e, LvmError::Usage(_)));
+
+        // Errors from lvmdbusd
+        let e = backend
+            .create_lv("bad", "vg01", 1, "G", "linear", &[], &[])
+            .unwrap_err();
+        assert!(matches!(e, LvmError::Dbus(_)));
+        let e = backend
+            .create_lv(
+                "lvraid2",
+                "vg01",
+                1,
+                "G",
+                "raid1",
+                &["/dev/sda1".to_string()],
+                &mirrors(),
+            )
+            .unwrap_err();
+        assert!(matches!(e, LvmError::Unsupported(_)));
+    }
+
+    #[test]
+    fn test_dbus_job_not_removed() {
+        let (backend, _server, _) = standin();
+
+        // Failing to remove the finished job is an error, it is not kept
+        let e = backend
+            .create_lv("lvstuck", "vg01", 1, "G", "raid1", &[], &mirrors())
+            .unwrap_err();
+        assert!(matches!(e, LvmError::Dbus(_)));
+        assert!(backend.pending_jobs().is_empty());
+    }
+
+    #[test]
+    fn test_dbus_resize_lv() {
+        let (backend, _server) = standin_with_lvs();
+
+        // Resized to whole extents
+        let free = backend.get_vg_info("vg01").unwrap().free;
+        let resize = LvmResize {
+            sign: Some('+'),
+            size: 3,
+            size_unit: "M".to_string(),
+            resizefs: true,
+        };
+        backend.resize_lv("vg01", "lvbig", &resize).unwrap();
+        let lvs = backend.get_lvs().unwrap();
+        assert_eq!(lv(&lvs, "lvbig").size, 1024 * 1024 * 1024 + 4 * 1024 * 1024);
+        assert_eq!(
+            backend.get_vg_info("vg01").unwrap().free,
+            free - 4 * 1024 * 1024
+        );
+    }
+
+    #[test]
+    fn test_dbus_rename() {
+        let (backend, _server) = standin_with_lvs();
+
+        backend.rename_lv("vg01", "lvbig", "lvlarge").unwrap();
+        assert!(has_lv(&backend, "lvlarge"));
+        assert!(!has_lv(&backend, "lvbig"));
+        assert!(backend.rename_lv("vg01", "lvbig", "lvx").is_err());
+        backend.rename_vg("vg01", "vgdata").unwrap();
+        assert_eq!(backend.get_vgs().unwrap(), vec!["vgdata"]);
+        assert!(backend.rename_vg("vg01", "vgx").is_err());
+    }
+
+    #[test]
+    fn test_dbus_snapshot() {
+        let (backend, _server) = standin_with_lvs();
+
+        // Snapshot of 25% of the 1G lv, merged back
+        let free = backend.get_vg_info("vg01").unwrap().free;
+        let snap = LvmSnapshot {
+            name: "lvbig_snap".to_string(),
+            size: 25,
+            size_unit: "%ORIGIN".to_string(),
+        };
+        backend.create_snapshot("vg01", "lvbig", &snap).unwrap();
+        let lvs = backend.get_lvs().unwrap();
+        let lvbig_snap = lv(&lvs, "lvbig_snap");
+        assert_eq!(lvbig_snap.origin, "lvbig");
+        assert_eq!(lvbig_snap.snap_percent, Some(5.0));
+        assert_eq!(
+            backend.get_vg_info("vg01").unwrap().free,
+            free - lvbig_snap.size
+        );
+        assert_eq!(lv(&lvs, "lvbig").snap_percent, None);
+        backend.merge_snapshot("vg01", "lvbig_snap").unwrap();
+        assert!(!has_lv(&backend, "lvbig_snap"));
+        assert!(backend.merge_snapshot("vg01", "lvbig").is_err());
+    }
+
+    #[test]
+    fn test_dbus_thin() {
+        let (backend, _server, _) = standin();
+
+        // Thin pool and a thin lv in it, only the pool takes space
+        let free = backend.get_vg_info("vg01").unwrap().free;
+        backend
+            .create_lv("pool0", "vg01", 1, "G", "thin-pool", &[], &[])
+            .unwrap();
+        let thinpool = [LvmExtraArg {
+            opt: "--thinpool".to_string(),
+            value: "pool0".to_string(),
+        }];
+        backend
+            .create_lv("thin1", "vg01", 2, "G", "thin", &[], &thinpool)
+            .unwrap();
+        let lvs = backend.get_lvs().unwrap();
+        let pool = lv(&lvs, "pool0");
+        assert_eq!(pool.segtype, "thin-pool");
+        assert_eq!(pool.data_percent, Some(42.0));
+        assert_eq!(pool.metadata_percent, Some(7.0));
+        let thin1 = lv(&lvs, "thin1");
+        assert_eq!(thin1.pool_lv, "pool0");
+        assert_eq!(thin1.data_percent, Some(42.0));
+        assert_eq!(thin1.metadata_percent, None);
+        assert_eq!(
+            backend.get_vg_info("vg01").unwrap().free,
+            free - 1024 * 1024 * 1024
+        );
+        assert!(
+            backend
+                .create_lv("thin2", "vg01", 2, "G", "thin", &[], &[])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_dbus_cache() {
+        let (backend, _server) = standin_with_lvs();
+
+        // Cache pool made of data and metadata lvs, then split off again
+        let mut cache = LvmCache {
+            name: "fast".to_string(),
+            cache_type: LvmCacheType::CachePool,
+            fast_pv: "/dev/sda1".to_string(),
+            size: 100,
+            size_unit: "M".to_string(),
+            cache_mode: "writeback".to_string(),
+            cache_policy: "smq".to_string(),
+        };
+        backend.attach_cache("vg01", "lvbig", &cache).unwrap();
+        let lvs = backend.get_lvs().unwrap();
+        let lvbig = lv(&lvs, "lvbig");
+        assert_eq!(lvbig.segtype, "cache");
+        assert_eq!(lvbig.pool_lv, "fast");
+        assert!(lvs.iter().all(|lv| lv.lv_name != "fast_meta"));
+        backend.detach_cache("vg01", "lvbig", true).unwrap();
+        let lvs = backend.get_lvs().unwrap();
+        assert_eq!(lv(&lvs, "fast").segtype, "cache-pool");
+        let lvbig = lv(&lvs, "lvbig");
+        assert_eq!(lvbig.segtype, "linear");
+        assert_eq!(lvbig.pool_lv, "");
+        assert!(backend.detach_cache("vg01", "lvbig", true).is_err());
+
+        // Writecache, removed with the cache
+        cache.name = "wc".to_string();
+        cache.cache_type = LvmCacheType::Writecache;
+        backend.attach_cache("vg01", "lvraid", &cache).unwrap();
+        let lvs = backend.get_lvs().unwrap();
+        let lvraid = lv(&lvs, "lvraid");
+        assert_eq!(lvraid.segtype, "writecache");
+        assert_eq!(lvraid.pool_lv, "wc");
+        backend.detach_cache("vg01", "lvraid", false).unwrap();
+        assert!(!has_lv(&backend, "wc"));
+
+        cache.cache_type = LvmCacheType::CacheVol;
+        let e = backend.attach_cache("vg01", "lvbig", &cache).unwrap_err();
+        assert!(matches!(e, LvmError::Unsupported(_)));
+
+        // Too large for u64
+        cache.cache_type = LvmCacheType::CachePool;
+        cache.size = u64::MAX;
+        let e = backend.attach_cache("vg01", "lvbig", &cache).unwrap_err();
+        assert!(matches!(e, LvmError::Usage(_)));
+    }
+
+    #[test]
+    fn test_dbus_raid() {
+        let (backend, _server) = standin_with_lvs();
+
+        // No raid scrub or repair in lvmdbusd
+        let lvs = backend.get_lvs().unwrap();
+        assert_eq!(lv(&lvs, "lvraid").raid_sync_action, "");
+        let e = backend
+            .raid_sync_action("vg01", "lvraid", LvmSyncAction::Check)
+            .unwrap_err();
+        assert!(matches!(e, LvmError::Unsupported(_)));
+        let e = backend.repair_raid("vg01", "lvraid", &[]).unwrap_err();
+        assert!(matches!(e, LvmError::Unsupported(_)));
+        let e = backend
+            .replace_raid_pv("vg01", "lvraid", "/dev/sda1", &[])
+            .unwrap_err();
+        assert!(matches!(e, LvmError::Unsupported(_)));
+        let linear = LvmConvert {
+            segtype: "linear".to_string(),
+            mirrors: None,
+            stripes: None,
+        };
+        let e = backend
+            .convert_lv("vg01", "lvraid", &linear, &[])
+            .unwrap_err();
+        assert!(matches!(e, LvmError::Unsupported(_)));
+    }
+
+    #[test]
+    fn test_dbus_pv_vg() {
+        let (backend, _server, options) = standin();
+
+        // New pvs, a vg on one of them, extended by the other
+        let pv = LvmPvCreate {
+            metadata_copies: Some(2),
+            data_alignment: Some(1024 * 1024),
+        };
+        backend.create_pv("/dev/sdh", &pv).unwrap();
+        backend
+            .create_pv("/dev/sdi", &LvmPvCreate::default())
+            .unwrap();
+        let e = backend
+            .create_pv("/dev/sdx", &LvmPvCreate::default())
+            .unwrap_err();
+        assert!(matches!(e, LvmError::Dbus(_)));
+        let pvs = backend.get_pvs().unwrap();
+        assert_eq!(pvs.len(), 3);
+        assert!(
+            pvs.iter()
+                .all(|pv| pv.pv_name == "/dev/sda1" || pv.vg_name.is_empty())
+        );
+
+        let opts = LvmVgCreate {
+            extent_size: Some(32 * 1024 * 1024),
+            alloc: AllocPolicy::Cling,
+        };
+        backend
+            .create_vg("vg05", &["/dev/sdh".to_string()], &opts)
+            .unwrap();
+        assert_eq!(
+            *options.lock().unwrap(),
+            [
+                "alloc=cling",
+                "dataalignment=1M",
+                "physicalextentsize=32M",
+                "pvmetadatacopies=2"
+            ]
+        );
+        assert_eq!(backend.get_vgs().unwrap(), vec!["vg01", "vg05"]);
+        let sdi = ["/dev/sdi".to_string()];
+        backend.extend_vg("vg05", &sdi).unwrap();
+        let in_vg05 = |backend: &DbusBackend| {
+            let mut pvs: Vec<String> = backend
+                .get_pvs()
+                .unwrap()
+                .into_iter()
+                .filter(|pv| pv.vg_name == "vg05")
+                .map(|pv| pv.pv_name)
+                .collect();
+            pvs.sort();
+            pvs
+        };
+        assert_eq!(in_vg05(&backend), ["/dev/sdh", "/dev/sdi"]);
+        backend.reduce_vg("vg05", &sdi).unwrap();
+        assert_eq!(in_vg05(&backend), ["/dev/sdh"]);
+        let e = backend
+            .reduce_vg("vg01", &["/dev/sda1".to_string()])
+            .unwrap_err();
+        assert!(matches!(e, LvmError::Dbus(_)));
+        assert!(
+            backend
+                .extend_vg("vg05", &["/dev/sdz".to_string()])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_dbus_pvmove() {
+        let (backend, _server) = standin_with_lvs();
+
+        // pvmove of all of sda1 runs in the background, one at a time
+        let mut mv = LvmPvMove {
+            src_pv: "/dev/sda1".to_string(),
+            ..Default::default()
+        };
+        backend.move_pv("vg01", &mv).unwrap();
+        let lvs = backend.get_lvs().unwrap();
+        let moves = pvmoves("vg01", &lvs);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].lv_name, "[pvmove0]");
+        assert_eq!(moves[0].move_pv, "/dev/sda1");
+        assert_eq!(moves[0].copy_percent, Some(50.0));
+        mv.lv_name = "lvraid".to_string();
+        let e = backend.move_pv("vg01", &mv).unwrap_err();
+        assert!(matches!(e, LvmError::Dbus(_)));
+        let e = backend.abort_pvmove().unwrap_err();
+        assert!(matches!(e, LvmError::Unsupported(_)));
+    }
+}
@@ -0,0 +1,466 @@
+//
+// What the kernel knows about an lv device: where it is mounted, who holds it
+// (dm-crypt, md, another lv) and what signature is on it. Read straight from
+// /proc, /sys and the device, nothing here needs lvm.
+//
+// Used to tell the user what they are about to destroy. Anything not readable
+// (not root, lv inactive) is reported as not known rather than as an error.
+//
+// Block devices for new pvs are found the same way, in /sys/class/block.
+//
+
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::lvm::{LvAttr, LvmLvData};
+
+const PROC_MOUNTS: &str = "/proc/mounts";
+const PROC_SWAPS: &str = "/proc/swaps";
+const ETC_FSTAB: &str = "/etc/fstab";
+const SYS_DEV_BLOCK: &str = "/sys/dev/block";
+const SYS_BLOCK: &str = "/sys/block";
+const SYS_CLASS_BLOCK: &str = "/sys/class/block";
+const DEV_DIR: &str = "/dev";
+// Not disks: ram disks, lvs and other dm devices, cd drives, floppies.
+const NOT_PV_PREFIXES: [&str; 5] = ["ram", "zram", "dm-", "sr", "fd"];
+// sysfs sizes are in 512 byte sectors, whatever the device's sector size.
+const SECTOR_SIZE: u64 = 512;
+
+// Enough to see the btrfs superblock at 64k.
+const PROBE_LEN: usize = 0x10040 + 8;
+
+#[derive(Debug, Default, Clone)]
+pub struct LvUsage {
+    // Device open according to lv_attr.
+    pub open: bool,
+    pub mounts: Vec<String>,
+    // Devices stacked on the lv, e.g. a dm-crypt mapping.
+    pub holders: Vec<String>,
+    // Signature found on the lv, blkid style name. None if none or not readable.
+    pub fs_type: Option<String>,
+    // Snapshots with this lv as origin, removed with it.
+    pub snapshots: Vec<String>,
+}
+
+impl LvUsage {
+    pub fn in_use(&self) -> bool {
+        self.open || !self.mounts.is_empty() || !self.holders.is_empty()
+    }
+
+    pub fn has_data(&self) -> bool {
+        self.fs_type.is_some() || !self.snapshots.is_empty()
+    }
+}
+
+//
+// A disk or partition that could become a pv.
+//
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockDev {
+    // Device node, e.g. /dev/sdh.
+    pub name: String,
+    pub size: u64,
+    // Of the disk, "" for partitions and if not known.
+    pub model: String,
+    // Why pvcreate would refuse or destroy something, None if free.
+    pub used_by: Option<String>,
+}
+
+//
+// Usage of 'lv', 'lvs' are all lvs of its vg (for the snapshots).
+//
+pub fn lv_usage(lv: &LvmLvData, lvs: &[LvmLvData]) -> LvUsage {
+    let mut devs = vec![lv.path.clone(), lv.dm_path.clone()];
+    if let Ok(real) = fs::canonicalize(&lv.dm_path) {
+        devs.push(real.to_string_lossy().into_owned());
+    }
+    devs.retain(|dev| !dev.is_empty());
+
+    let mounts = fs::read_to_string(PROC_MOUNTS)
+        .map(|s| parse_mounts(&s, &devs))
+        .unwrap_or_default();
+    let holders = match (lv.kernel_major, lv.kernel_minor) {
+        (Some(major), Some(minor)) => holders(Path::new(SYS_DEV_BLOCK), major, minor),
+        _ => Vec::new(),
+    };
+    let fs_type = devs
+        .iter()
+        .find_map(|dev| fs_type(Path::new(dev)))
+        .map(str::to_string);
+    let snapshots = lvs
+        .iter()
+        .filter(|snap| snap.vg_name == lv.vg_name && snap.origin == lv.lv_name)
+        .map(|snap| snap.lv_name.clone())
+        .collect();
+
+    LvUsage {
+        open: LvAttr::decode(&lv.attr).open == Some(true),
+        mounts,
+        holders,
+        fs_type,
+        snapshots,
+    }
+}
+
+//
+// Mount points of any of 'devs' in /proc/mounts format, 'dev mnt fstype opts 0 0'.
+// Spaces etc in mount points are octal escaped, e.g. '\040'.
+//
+fn parse_mounts(s: &str, devs: &[String]) -> Vec<String> {
+    s.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let dev = fields.next()?;
+            let mnt = fields.next()?;
+            devs.iter().any(|d| d == dev).then(|| unescape_octal(mnt))
+        })
+        .collect()
+}
+
+//
+// /etc/fstab entries naming a device of 'vg' (of 'lv' only if given), as
+// (device, mount point). These break when the vg or lv is renamed, entries by
+// UUID= or LABEL= do not.
+//
+pub fn fstab_entries(vg: &str, lv: Option<&str>) -> Vec<(String, String)> {
+    fs::read_to_string(ETC_FSTAB)
+        .map(|s| parse_fstab(&s, vg, lv))
+        .unwrap_or_default()
+}
+
+fn parse_fstab(s: &str, vg: &str, lv: Option<&str>) -> Vec<(String, String)> {
+    let mapper_vg = vg.replace('-', "--");
+    let names_lv = |dev: &str| match lv {
+        Some(lv) => {
+            dev == format!("/dev/{vg}/{lv}")
+                || dev == format!("/dev/mapper/{mapper_vg}-{}", lv.replace('-', "--"))
+        }
+        None => {
+            // vg-lv, a '-' in the vg itself is doubled.
+            dev.starts_with(&format!("/dev/{vg}/"))
+                || dev
+                    .strip_prefix("/dev/mapper/")
+                    .and_then(|name| name.strip_prefix(&mapper_vg))
+                    .is_some_and(|rest| rest.starts_with('-') && !rest.starts_with("--"))
+        }
+    };
+    s.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let dev = fields.next()?;
+            let mnt = fields.next()?;
+            names_lv(dev).then(|| (dev.to_string(), unescape_octal(mnt)))
+        })
+        .collect()
+}
+
+fn unescape_octal(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && let Some(oct) = s.get(i + 1..i + 4)
+            && let Ok(b) = u8::from_str_radix(oct, 8)
+        {
+            out.push(b);
+            i += 4;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+//
+// Holders of block device major:minor, dm devices by their dm name.
+// <sys>/<major>:<minor>/holders/dm-3 -> /sys/block/dm-3/dm/name
+//
+fn holders(sys_dev_block: &Path, major: u32, minor: u32) -> Vec<String> {
+    holder_names(
+        &sys_dev_block
+            .join(format!("{major}:{minor}"))
+            .join("holders"),
+    )
+}
+
+fn holder_names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let kname = entry.file_name().to_string_lossy().into_owned();
+            fs::read_to_string(Path::new(SYS_BLOCK).join(&kname).join("dm/name"))
+                .map(|name| name.trim().to_string())
+                .unwrap_or(kname)
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+//
+// Block devices of the host, sorted by name.
+//
+pub fn block_devs() -> Vec<BlockDev> {
+    let mounts = fs::read_to_string(PROC_MOUNTS).unwrap_or_default();
+    let swaps = fs::read_to_string(PROC_SWAPS).unwrap_or_default();
+    scan_block_devs(
+        Path::new(SYS_CLASS_BLOCK),
+        Path::new(DEV_DIR),
+        &mounts,
+        &swaps,
+    )
+}
+
+//
+// <sys>/sda/{size,ro,device/model,holders/}, partitions have a 'partition'
+// file and are listed both at top level and under their disk.
+//
+fn scan_block_devs(sys: &Path, dev_dir: &Path, mounts: &str, swaps: &str) -> Vec<BlockDev> {
+    let Ok(entries) = fs::read_dir(sys) else {
+        return Vec::new();
+    };
+    let mut kernel_names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|kname| !NOT_PV_PREFIXES.iter().any(|p| kname.starts_with(p)))
+        .collect();
+    kernel_names.sort();
+
+    let read = |path: std::path::PathBuf| {
+        fs::read_to_string(path)
+            .map(|s| s.trim().to_string())
+            .unwrap_or_default()
+    };
+    // Swap files are paths too, they just never match a device.
+    let swap_devs: Vec<&str> = swaps
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().next())
+        .collect();
+
+    kernel_names
+        .into_iter()
+        .filter_map(|kname| {
+            let dir = sys.join(&kname);
+            let size = read(dir.join("size")).parse::<u64>().ok()? * SECTOR_SIZE;
+            // Empty loop devices, card readers without a card.
+            if size == 0 {
+                return None;
+            }
+            let name = dev_dir.join(&kname).to_string_lossy().into_owned();
+            let is_partition = dir.join("partition").exists();
+            let has_partitions = !is_partition
+                && fs::read_dir(&dir).is_ok_and(|mut entries| {
+                    entries.any(|entry| {
+                        entry.is_ok_and(|entry| {
+                            entry.file_name().to_string_lossy().starts_with(&kname)
+                                && entry.path().join("partition").exists()
+                        })
+                    })
+                });
+            let holders = holder_names(&dir.join("holders"));
+            let mounted = parse_mounts(mounts, std::slice::from_ref(&name));
+
+            let used_by = if read(dir.join("ro")) == "1" {
+                Some("read-only".to_string())
+            } else if has_partitions {
+                Some("has partitions".to_string())
+            } else if !holders.is_empty() {
+                Some(format!("held by {}", holders.join(", ")))
+            } else if !mounted.is_empty() {
+                Some(format!("mounted on {}", mounted.join(", ")))
+            } else if swap_devs.contains(&name.as_str()) {
+                Some("swap in use".to_string())
+            } else {
+                fs_type(Path::new(&name)).map(|fs| format!("{fs} signature"))
+            };
+            let model = if is_partition {
+                String::new()
+            } else {
+                read(dir.join("device/model"))
+            };
+            Some(BlockDev {
+                name,
+                size,
+                model,
+                used_by,
+            })
+        })
+        .collect()
+}
+
+//
+// Signature at the start of the device, None if none known or not readable.
+//
+pub fn fs_type(dev: &Path) -> Option<&'static str> {
+    let mut buf = Vec::with_capacity(PROBE_LEN);
+    fs::File::open(dev)
+        .ok()?
+        .take(PROBE_LEN as u64)
+        .read_to_end(&mut buf)
+        .ok()?;
+    fs_signature(&buf)
+}
+
+//
+// Known magics, names as blkid prints them in TYPE.
+//
+fn fs_signature(buf: &[u8]) -> Option<&'static str> {
+    let at = |off: usize, magic: &[u8]| buf.get(off..off + magic.len()) == Some(magic);
+    let le32 = |off: usize| {
+        buf.get(off..off + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .unwrap_or(0)
+    };
+
+    if at(0, b"XFSB") {
+        Some("xfs")
+    } else if at(0, b"LUKS\xba\xbe") {
+        Some("crypto_LUKS")
+    } else if at(0x200, b"LABELONE") {
+        Some("LVM2_member")
+    } else if at(0x438, &[0x53, 0xef]) {
+        // has_journal in compat, extents in incompat
+        if le32(0x460) & 0x40 != 0 {
+            Some("ext4")
+        } else if le32(0x45c) & 0x4 != 0 {
+            Some("ext3")
+        } else {
+            Some("ext2")
+        }
+    } else if at(0x10040, b"_BHRfS_M") {
+        Some("btrfs")
+    } else if at(4096 - 10, b"SWAPSPACE2") || at(4096 - 10, b"SWAP-SPACE") {
+        Some("swap")
+    } else if at(0x52, b"FAT32   ") || at(0x36, b"FAT16   ") || at(0x36, b"FAT12   ") {
+        Some("vfat")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::fs;
+
+    use crate::lvm::devinfo::{fs_signature, holders, parse_fstab, parse_mounts, scan_block_devs};
+
+    #[test]
+    fn test_devinfo() {
+        let mounts = "/dev/mapper/vgroot-root / ext4 rw,relatime 0 0\n\
+                      proc /proc proc rw 0 0\n\
+                      /dev/mapper/vg01-lvdata /mnt/my\\040data xfs rw 0 0\n\
+                      /dev/dm-7 /srv btrfs rw 0 0\n";
+        let devs = vec![
+            "/dev/vg01/lvdata".to_string(),
+            "/dev/mapper/vg01-lvdata".to_string(),
+            "/dev/dm-7".to_string(),
+        ];
+        assert_eq!(parse_mounts(mounts, &devs), vec!["/mnt/my data", "/srv"]);
+        assert!(parse_mounts(mounts, &["/dev/sda1".to_string()]).is_empty());
+
+        let fstab = "# /dev/vgroot/old / ext4 defaults 0 1\n\
+                     /dev/vgroot/root / ext4 defaults 0 1\n\
+                     /dev/mapper/vgroot-swap none swap sw 0 0\n\
+                     /dev/mapper/vgroot--x-home /home xfs defaults 0 2\n\
+                     UUID=1234 /boot ext4 defaults 0 2\n";
+        let root = ("/dev/vgroot/root".to_string(), "/".to_string());
+        assert_eq!(
+            parse_fstab(fstab, "vgroot", Some("root")),
+            vec![root.clone()]
+        );
+        assert_eq!(parse_fstab(fstab, "vgroot", None).len(), 2);
+        assert_eq!(parse_fstab(fstab, "vgroot-x", None).len(), 1);
+        assert!(parse_fstab(fstab, "vgroot", Some("home")).is_empty());
+
+        let mut buf = vec![0u8; 0x10048];
+        assert_eq!(fs_signature(&buf), None);
+        buf[0x438] = 0x53;
+        buf[0x439] = 0xef;
+        assert_eq!(fs_signature(&buf), Some("ext2"));
+        buf[0x460] = 0x40;
+        assert_eq!(fs_signature(&buf), Some("ext4"));
+        buf[..4].copy_from_slice(b"XFSB");
+        assert_eq!(fs_signature(&buf), Some("xfs"));
+        // Short read does not panic
+        assert_eq!(fs_signature(b"XFS"), None);
+
+        let sys = std::env::temp_dir().join(format!("lvm-tui-devinfo-{}", std::process::id()));
+        fs::create_dir_all(sys.join("253:4/holders/dm-9")).unwrap();
+        fs::create_dir_all(sys.join("253:5/holders")).unwrap();
+        assert_eq!(holders(&sys, 253, 4).len(), 1);
+        assert!(holders(&sys, 253, 5).is_empty());
+        assert!(holders(&sys, 253, 6).is_empty());
+        fs::remove_dir_all(&sys).unwrap();
+    }
+
+    #[test]
+    fn test_block_devs() {
+        let root = std::env::temp_dir().join(format!("lvm-tui-blockdevs-{}", std::process::id()));
+        let (sys, dev) = (root.join("sys"), root.join("dev"));
+        let add = |kname: &str, sectors: u64, files: &[&str]| {
+            let dir = sys.join(kname);
+            fs::create_dir_all(dir.join("holders")).unwrap();
+            fs::write(dir.join("size"), format!("{sectors}\n")).unwrap();
+            for file in files {
+                fs::write(dir.join(file), "1\n").unwrap();
+            }
+            fs::write(dev.join(kname), [0u8; 16]).unwrap();
+        };
+        fs::create_dir_all(&dev).unwrap();
+        add("sda", 2048, &[]);
+        add("sda1", 1024, &["partition"]);
+        add("sda2", 1024, &["partition"]);
+        fs::create_dir_all(sys.join("sda/sda1")).unwrap();
+        fs::write(sys.join("sda/sda1/partition"), "1").unwrap();
+        add("sdb", 4096, &[]);
+        fs::create_dir_all(sys.join("sdb/device")).unwrap();
+        fs::write(sys.join("sdb/device/model"), "WDC WD20EFRX  \n").unwrap();
+        add("sdc", 4096, &["ro"]);
+        add("sdd", 4096, &[]);
+        fs::create_dir_all(sys.join("sdd/holders/md0")).unwrap();
+        add("loop0", 0, &[]);
+        add("dm-0", 4096, &[]);
+        let mut xfs = vec![0u8; 512];
+        xfs[..4].copy_from_slice(b"XFSB");
+        add("sde", 4096, &[]);
+        fs::write(dev.join("sde"), xfs).unwrap();
+
+        let d = |name: &str| dev.join(name).to_string_lossy().into_owned();
+        let mounts = format!("{} /boot ext4 rw 0 0\n", d("sda1"));
+        let swaps = format!(
+            "Filename Type Size Used Priority\n{} partition 524284 0 -2\n",
+            d("sda2")
+        );
+        let devs = scan_block_devs(&sys, &dev, &mounts, &swaps);
+        let used: Vec<(&str, Option<&str>)> = devs
+            .iter()
+            .map(|bd| (bd.name.rsplit('/').next().unwrap(), bd.used_by.as_deref()))
+            .collect();
+        assert_eq!(
+            used,
+            [
+                ("sda", Some("has partitions")),
+                ("sda1", Some("mounted on /boot")),
+                ("sda2", Some("swap in use")),
+                ("sdb", None),
+                ("sdc", Some("read-only")),
+                ("sdd", Some("held by md0")),
+                ("sde", Some("xfs signature")),
+            ]
+        );
+        let sdb = &devs[3];
+        assert_eq!(sdb.size, 4096 * 512);
+        assert_eq!(sdb.model, "WDC WD20EFRX");
+        assert!(devs[1].model.is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
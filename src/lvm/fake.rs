@@ -0,0 +1,3356 @@
+//
+// In-memory lvm, simulates VGs, PVs and LVs so the tui can run without root
+// and without real lvm, e.g. for tests and demo (--backend fake).
+//
+// Extents are allocated from PVs like lvm would (first free range first), raid
+// images get one PV each, raid1/4/5/6/10 images get a 1 extent rmeta on the same PV.
+// Thin pools are a _tdata and _tmeta sub lv, thin lvs take no extents. Cache
+// pools likewise have _cdata and _cmeta, a cached lv keeps its extents in a
+// hidden _corig (_wcorig for writecache).
+//
+// Block devices for new pvs are a plain list, pvcreate turns one into an
+// orphan pv that vgcreate or vgextend put into a vg.
+//
+// A pv can be failed, raid lvs on it are then partial until repaired. Scrubs
+// and resyncs advance SYNC_STEP each time lvs are read, like a refresh would
+// see them progress.
+//
+// A pvmove reserves its destination extents in a hidden [pvmove0] lv, which
+// progresses like a resync and, once done, moves the lv segments over.
+//
+
+use std::sync::Mutex;
+
+use crate::lvm::{
+    AllocPolicy, LvmBackend, LvmCache, LvmCacheType, LvmConvert, LvmError, LvmExtraArg, LvmLayout,
+    LvmLvData, LvmPVData, LvmPvCreate, LvmPvMove, LvmResize, LvmSnapshot, LvmSyncAction,
+    LvmVgCreate, LvmVgData, LvmlvSegData, check_lv_name, check_vg_name, convert_targets,
+    devinfo::BlockDev, parse_size_arg, size_unit_bytes,
+};
+
+const DEFAULT_EXTENT_SIZE: u64 = 4 * 1024 * 1024;
+// Where data starts on a pv, after label and metadata area.
+const PE_START: u64 = 1024 * 1024;
+const DM_MAJOR: u32 = 253;
+const FAKE_TIME: &str = "2025-09-18 19:42:06 +0200";
+const FAKE_HOST: &str = "fakehost";
+// dm-cache chunk and dm-writecache block size lvm defaults to.
+const CACHE_CHUNK: u64 = 64 * 1024;
+const WRITECACHE_BLOCK: u64 = 4096;
+const SYNC_STEP: f64 = 25.0;
+const PVMOVE_LV: &str = "[pvmove0]";
+
+struct FakeVg {
+    name: String,
+    uuid: String,
+    extent_size: u64,
+    alloc: AllocPolicy,
+}
+
+struct FakePv {
+    name: String,
+    vg_name: String,
+    size: u64,
+    // Extents of its vg, 0 for orphans.
+    pe_count: u64,
+    missing: bool,
+}
+
+// Extents being moved, (lv, start, count) on the source pv in lv order, and
+// the destination extents they go to.
+struct FakePvMove {
+    vg: String,
+    src: String,
+    moves: Vec<(String, u64, u64)>,
+    dest: Vec<LvmlvSegData>,
+}
+
+#[derive(Default)]
+struct FakeState {
+    vgs: Vec<FakeVg>,
+    pvs: Vec<FakePv>,
+    devs: Vec<BlockDev>,
+    lvs: Vec<LvmLvData>,
+    pvmove: Option<FakePvMove>,
+    uuid_seq: u64,
+    dm_minor_seq: u32,
+}
+
+pub struct FakeBackend {
+    state: Mutex<FakeState>,
+}
+
+impl Default for FakeBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeBackend {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(FakeState::default()),
+        }
+    }
+
+    //
+    // Some volume groups to look at, roughly the same as in the parser tests.
+    //
+    pub fn demo() -> Self {
+        let gb = 1024 * 1024 * 1024;
+        let fake = Self::new();
+        fake.add_vg("vgroot", &[("/dev/nvme0n1p3", 476 * gb)]);
+        fake.add_vg(
+            "vg04_1tbdisks",
+            &[
+                ("/dev/sdb1", 931 * gb),
+                ("/dev/sdc1", 931 * gb),
+                ("/dev/sdd1", 931 * gb),
+                ("/dev/sde1", 931 * gb),
+            ],
+        );
+        fake.add_vg(
+            "vg03_backups",
+            &[("/dev/sdf1", 1863 * gb), ("/dev/nvme1n1", 240 * gb)],
+        );
+        fake.add_pv("/dev/sdg", 240 * gb);
+        // Disks, two new ones for a vg.
+        fake.add_dev(
+            "/dev/nvme0n1",
+            476 * gb,
+            "Samsung SSD 970",
+            Some("has partitions"),
+        );
+        fake.add_dev("/dev/sdg", 240 * gb, "KINGSTON SA400", None);
+        fake.add_dev("/dev/sdh", 1863 * gb, "WDC WD20EFRX", None);
+        fake.add_dev("/dev/sdi", 1863 * gb, "WDC WD20EFRX", None);
+        fake.add_dev("/dev/sdj", 64 * gb, "USB DISK", Some("vfat signature"));
+
+        let thinpool = [LvmExtraArg {
+            opt: "--thinpool".to_string(),
+            value: "pool0".to_string(),
+        }];
+        let stripes = [LvmExtraArg {
+            opt: "--stripes".to_string(),
+            value: "3".to_string(),
+        }];
+        let mirrors = [LvmExtraArg {
+            opt: "--mirrors".to_string(),
+            value: "1".to_string(),
+        }];
+        let demo_lvs = [
+            fake.create_lv("root", "vgroot", 80, "G", "linear", &[], &[]),
+            fake.create_lv("swap", "vgroot", 16, "G", "linear", &[], &[]),
+            fake.create_lv("home", "vgroot", 200, "G", "linear", &[], &[]),
+            fake.create_lv("lvpub", "vg04_1tbdisks", 500, "G", "raid5", &[], &stripes),
+            fake.create_lv("lvvirt", "vg04_1tbdisks", 100, "G", "raid1", &[], &mirrors),
+            fake.create_lv("lvbackup", "vg03_backups", 1000, "G", "linear", &[], &[]),
+            fake.create_lv("pool0", "vg03_backups", 400, "G", "thin-pool", &[], &[]),
+            fake.create_lv("thin_web", "vg03_backups", 300, "G", "thin", &[], &thinpool),
+            fake.create_lv("thin_db", "vg03_backups", 250, "G", "thin", &[], &thinpool),
+        ];
+        for res in demo_lvs {
+            res.expect("demo lv");
+        }
+        // The system is running off vgroot.
+        for lv in ["root", "swap", "home"] {
+            fake.set_open("vgroot", lv, true);
+        }
+        // Over-provisioned and filling up.
+        fake.set_data_percent("vg03_backups", "pool0", 85.0, Some(12.5));
+        fake.set_data_percent("vg03_backups", "thin_web", 60.0, None);
+        fake.set_data_percent("vg03_backups", "thin_db", 65.6, None);
+        // Backups on the hdd, cached by the ssd.
+        let cache = LvmCache {
+            name: "lvbackup_cache".to_string(),
+            cache_type: LvmCacheType::CachePool,
+            fast_pv: "/dev/nvme1n1".to_string(),
+            size: 100,
+            size_unit: "G".to_string(),
+            cache_mode: "writeback".to_string(),
+            cache_policy: "smq".to_string(),
+        };
+        fake.attach_cache("vg03_backups", "lvbackup", &cache)
+            .expect("demo cache");
+        fake.set_cache_stats("vg03_backups", "lvbackup", 72.0, 91.5, 1523);
+        // Last monthly check found some.
+        fake.set_raid_mismatches("vg04_1tbdisks", "lvvirt", 64);
+
+        fake
+    }
+
+    //
+    // Add vg with given pvs (name, size in bytes).
+    //
+    pub fn add_vg(&self, vg_name: &str, pvs: &[(&str, u64)]) {
+        let mut state = self.state.lock().unwrap();
+        let uuid = state.next_uuid();
+        state.vgs.push(FakeVg {
+            name: vg_name.to_string(),
+            uuid,
+            extent_size: DEFAULT_EXTENT_SIZE,
+            alloc: AllocPolicy::Normal,
+        });
+        for (pv_name, size) in pvs {
+            state.pvs.push(FakePv {
+                name: pv_name.to_string(),
+                vg_name: vg_name.to_string(),
+                size: *size,
+                pe_count: size / DEFAULT_EXTENT_SIZE,
+                missing: false,
+            });
+        }
+    }
+
+    //
+    // Mark lv open (in use by mount, swap, ...) or not, the 'o' in lv_attr.
+    //
+    pub fn set_open(&self, vg_name: &str, lv_name: &str, open: bool) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(lv) = state
+            .lvs
+            .iter_mut()
+            .find(|lv| lv.vg_name == vg_name && lv.lv_name == lv_name)
+        {
+            set_attr_char(lv, 5, if open { 'o' } else { '-' });
+        }
+    }
+
+    //
+    // Fill a snapshot's cow space, at 100% it is invalid like lvm marks it.
+    //
+    pub fn set_snap_percent(&self, vg_name: &str, lv_name: &str, percent: f64) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(lv) = state
+            .lvs
+            .iter_mut()
+            .find(|lv| lv.vg_name == vg_name && lv.lv_name == lv_name && !lv.origin.is_empty())
+        {
+            lv.snap_percent = Some(percent.min(100.0));
+            if percent >= 100.0 {
+                set_attr_char(lv, 4, 'I');
+            }
+        }
+    }
+
+    //
+    // Usage of a thin pool or thin lv, a full pool is marked out of data space.
+    //
+    pub fn set_data_percent(&self, vg_name: &str, lv_name: &str, data: f64, metadata: Option<f64>) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(lv) = state
+            .lvs
+            .iter_mut()
+            .find(|lv| lv.vg_name == vg_name && lv.lv_name == lv_name && lv.data_percent.is_some())
+        {
+            lv.data_percent = Some(data.min(100.0));
+            if metadata.is_some() {
+                lv.metadata_percent = metadata;
+            }
+            if lv.segtype == "thin-pool" && data >= 100.0 {
+                set_attr_char(lv, 8, 'D');
+            }
+        }
+    }
+
+    //
+    // Usage, read hit rate and dirty (not yet written back) blocks of a cached
+    // lv. Counters are as if after 100000 reads and 40000 writes.
+    //
+    pub fn set_cache_stats(
+        &self,
+        vg_name: &str,
+        lv_name: &str,
+        used_percent: f64,
+        hit_percent: f64,
+        dirty: u64,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        let Some(lv) = state
+            .lvs
+            .iter_mut()
+            .find(|lv| lv.vg_name == vg_name && lv.lv_name == lv_name)
+        else {
+            return;
+        };
+        let part = |total: u64, percent: f64| (total as f64 * percent / 100.0) as u64;
+        if let Some(total) = lv.writecache_total_blocks {
+            lv.writecache_free_blocks = Some(total - part(total, used_percent));
+            lv.writecache_writeback_blocks = Some(dirty);
+        } else if let Some(total) = lv.cache_total_blocks {
+            lv.cache_used_blocks = Some(part(total, used_percent));
+            lv.cache_dirty_blocks = Some(dirty);
+            lv.cache_read_hits = Some(part(100000, hit_percent));
+            lv.cache_read_misses = Some(100000 - part(100000, hit_percent));
+            lv.cache_write_hits = Some(part(40000, hit_percent));
+            lv.cache_write_misses = Some(40000 - part(40000, hit_percent));
+        }
+    }
+
+    //
+    // The pv's device is gone. Lvs on it are partial, raid lvs keep running
+    // degraded.
+    //
+    pub fn fail_pv(&self, pv_name: &str) {
+        let mut state = self.state.lock().unwrap();
+        let Some(pv) = state.pvs.iter_mut().find(|pv| pv.name == pv_name) else {
+            return;
+        };
+        pv.missing = true;
+        let vg_name = pv.vg_name.clone();
+        let mut parents = Vec::new();
+        for lv in state.lvs.iter_mut().filter(|lv| lv.vg_name == vg_name) {
+            if lv.lv_segs.iter().any(|seg| seg.pvdev == pv_name) {
+                set_attr_char(lv, 8, 'p');
+                lv.health_status = "partial".to_string();
+                parents.push(lv.parent_lv.clone());
+            }
+        }
+        for lv in state.lvs.iter_mut().filter(|lv| lv.vg_name == vg_name) {
+            if parents.contains(&lv.lv_name) {
+                set_attr_char(lv, 8, 'p');
+                lv.health_status = "partial".to_string();
+            }
+        }
+    }
+
+    //
+    // Result of a check scrub finding 'count' mismatches between images.
+    //
+    pub fn set_raid_mismatches(&self, vg_name: &str, lv_name: &str, count: u64) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(lv) = state.lvs.iter_mut().find(|lv| {
+            lv.vg_name == vg_name && lv.lv_name == lv_name && lv.raid_mismatch_count.is_some()
+        }) {
+            lv.raid_mismatch_count = Some(count);
+            if count > 0 {
+                set_attr_char(lv, 8, 'm');
+                lv.health_status = "mismatches exist".to_string();
+            }
+        }
+    }
+
+    //
+    // Add pv not in any vg.
+    //
+    pub fn add_pv(&self, pv_name: &str, size: u64) {
+        self.state.lock().unwrap().pvs.push(FakePv {
+            name: pv_name.to_string(),
+            vg_name: String::new(),
+            size,
+            pe_count: 0,
+            missing: false,
+        });
+    }
+
+    //
+    // Add block device, free for pvcreate if 'used_by' is None.
+    //
+    pub fn add_dev(&self, name: &str, size: u64, model: &str, used_by: Option<&str>) {
+        self.state.lock().unwrap().devs.push(BlockDev {
+            name: name.to_string(),
+            size,
+            model: model.to_string(),
+            used_by: used_by.map(str::to_string),
+        });
+    }
+}
+
+impl FakeState {
+    fn next_uuid(&mut self) -> String {
+        self.uuid_seq += 1;
+        format!("FAKE00-0000-0000-0000-0000-0000-{:06}", self.uuid_seq)
+    }
+
+    fn vg(&self, vg_name: &str) -> Result<&FakeVg, LvmError> {
+        self.vgs
+            .iter()
+            .find(|vg| vg.name == vg_name)
+            .ok_or_else(|| failed("vgs", format!("Volume group \"{vg_name}\" not found")))
+    }
+
+    fn vg_data(&self, vg_name: &str) -> Result<LvmVgData, LvmError> {
+        let vg = self.vg(vg_name)?;
+        let (total, free) = self.vg_extents(vg_name);
+
+        Ok(LvmVgData {
+            name: vg.name.clone(),
+            size: total * vg.extent_size,
+            free: free * vg.extent_size,
+            pv_count: self.pvs_in_vg(vg_name).count() as u64,
+            attr: format!("wz--{}-", vg.alloc.to_char()),
+            uuid: vg.uuid.clone(),
+        })
+    }
+
+    //
+    // Index of each of pvl, they have to be pvs not in a vg.
+    //
+    fn orphan_pvs(&self, pvl: &[String], cmd: &str) -> Result<Vec<usize>, LvmError> {
+        if pvl.is_empty() {
+            return Err(failed(cmd, "Please enter physical volume name(s)"));
+        }
+        pvl.iter()
+            .map(|name| {
+                let i = self
+                    .pvs
+                    .iter()
+                    .position(|pv| pv.name == *name)
+                    .ok_or_else(|| failed(cmd, format!("No physical volume found for {name}.")))?;
+                match self.pvs[i].vg_name.as_str() {
+                    "" => Ok(i),
+                    vg => Err(failed(
+                        cmd,
+                        format!("Physical volume '{name}' is already in volume group '{vg}'"),
+                    )),
+                }
+            })
+            .collect()
+    }
+
+    fn join_vg(&mut self, pvs: &[usize], vg_name: &str) {
+        let extent_size = self
+            .vg(vg_name)
+            .map_or(DEFAULT_EXTENT_SIZE, |vg| vg.extent_size);
+        for i in pvs {
+            let pv = &mut self.pvs[*i];
+            pv.vg_name = vg_name.to_string();
+            pv.pe_count = pv.size / extent_size;
+        }
+    }
+
+    fn pvs_in_vg(&self, vg_name: &str) -> impl Iterator<Item = &FakePv> {
+        self.pvs.iter().filter(move |pv| pv.vg_name == vg_name)
+    }
+
+    // An origin without snapshots left is a plain lv again.
+    fn sync_origin(&mut self, vg_name: &str, origin: &str) {
+        let has_snaps = self
+            .lvs
+            .iter()
+            .any(|lv| lv.vg_name == vg_name && lv.origin == origin);
+        if let Some(lv) = self
+            .lvs
+            .iter_mut()
+            .find(|lv| lv.vg_name == vg_name && lv.lv_name == origin)
+        {
+            set_attr_char(lv, 0, if has_snaps { 'o' } else { '-' });
+        }
+    }
+
+    // Extents used on pv, sorted (start, count).
+    fn used_ranges(&self, pv_name: &str) -> Vec<(u64, u64)> {
+        let mut used: Vec<(u64, u64)> = self
+            .lvs
+            .iter()
+            .flat_map(|lv| lv.lv_segs.iter())
+            .filter(|seg| seg.pvdev == pv_name)
+            .map(|seg| (seg.pv_start_pe, seg.size_pe))
+            .collect();
+        used.sort();
+        used
+    }
+
+    // Free extents on pv, sorted (start, count).
+    fn free_ranges(&self, pv: &FakePv) -> Vec<(u64, u64)> {
+        let mut free = Vec::new();
+        let mut pos = 0;
+        for (start, count) in self.used_ranges(&pv.name) {
+            if start > pos {
+                free.push((pos, start - pos));
+            }
+            pos = pos.max(start + count);
+        }
+        if pos < pv.pe_count {
+            free.push((pos, pv.pe_count - pos));
+        }
+        free
+    }
+
+    fn free_extents(&self, pv: &FakePv) -> u64 {
+        self.free_ranges(pv).iter().map(|(_, count)| count).sum()
+    }
+
+    fn vg_extents(&self, vg_name: &str) -> (u64, u64) {
+        let total = self.pvs_in_vg(vg_name).map(|pv| pv.pe_count).sum();
+        let free = self
+            .pvs_in_vg(vg_name)
+            .map(|pv| self.free_extents(pv))
+            .sum();
+        (total, free)
+    }
+
+    //
+    // Thin lvs take no extents, only the pool has to exist.
+    //
+    fn create_thin(
+        &mut self,
+        lv: &str,
+        vg: &str,
+        size: u64,
+        size_unit: &str,
+        extra: &[LvmExtraArg],
+    ) -> Result<String, LvmError> {
+        let extent_size = self.vg(vg)?.extent_size;
+        let pool = extra
+            .iter()
+            .find(|e| e.opt == "--thinpool")
+            .map(|e| e.value.clone())
+            .unwrap_or_default();
+        if !self
+            .lvs
+            .iter()
+            .any(|item| item.vg_name == vg && item.lv_name == pool && item.segtype == "thin-pool")
+        {
+            return Err(failed(
+                "lvcreate",
+                format!("Thin pool volume with name \"{pool}\" not found in \"{vg}\"."),
+            ));
+        }
+        let mult = size_unit_bytes(size_unit).ok_or_else(|| {
+            failed(
+                "lvcreate",
+                format!("Invalid virtual size unit {size_unit}."),
+            )
+        })?;
+        let extents = (size * mult).div_ceil(extent_size);
+        if extents == 0 {
+            return Err(failed("lvcreate", "Size must be larger than 0."));
+        }
+
+        let uuid = self.next_uuid();
+        self.dm_minor_seq += 1;
+        self.lvs.push(LvmLvData {
+            lv_name: lv.to_string(),
+            vg_name: vg.to_string(),
+            size: extents * extent_size,
+            attr: "Vwi-a-tz--".to_string(),
+            segtype: "thin".to_string(),
+            uuid,
+            stripes: 1,
+            data_stripes: 1,
+            path: format!("/dev/{vg}/{lv}"),
+            dm_path: format!("/dev/mapper/{}", dm_name(vg, lv)),
+            kernel_major: Some(DM_MAJOR),
+            kernel_minor: Some(self.dm_minor_seq),
+            time: FAKE_TIME.to_string(),
+            host: FAKE_HOST.to_string(),
+            pool_lv: pool,
+            data_percent: Some(0.0),
+            ..Default::default()
+        });
+        Ok("Created lv".to_string())
+    }
+
+    //
+    // Index of a raid lv with redundancy, the ones that can be scrubbed and
+    // repaired.
+    //
+    fn raid_lv(&self, vg: &str, lv: &str, cmd: &str) -> Result<usize, LvmError> {
+        self.vg(vg)?;
+        let i = self
+            .lvs
+            .iter()
+            .position(|item| item.vg_name == vg && item.lv_name == lv)
+            .ok_or_else(|| failed(cmd, format!("Failed to find logical volume \"{vg}/{lv}\"")))?;
+        if self.lvs[i].raid_mismatch_count.is_none() {
+            return Err(failed(
+                cmd,
+                format!("{vg}/{lv} must be a RAID logical volume with redundancy."),
+            ));
+        }
+        Ok(i)
+    }
+
+    //
+    // Move the images of raid lv 'i' that are on 'bad' pvs to other pvs, one
+    // not holding an image of the lv each. Allocated before anything changes,
+    // the raid then resyncs the new images.
+    //
+    fn replace_images(
+        &mut self,
+        i: usize,
+        bad: &[String],
+        pvl: &[String],
+        cmd: &str,
+    ) -> Result<Vec<String>, LvmError> {
+        let (vg, lv) = (self.lvs[i].vg_name.clone(), self.lvs[i].lv_name.clone());
+        let image_pv = |item: &LvmLvData| {
+            item.lv_segs
+                .first()
+                .map(|seg| seg.pvdev.clone())
+                .unwrap_or_default()
+        };
+        let images: Vec<&LvmLvData> = self
+            .lvs
+            .iter()
+            .filter(|item| {
+                item.vg_name == vg && item.parent_lv == lv && item.lv_name.contains("_rimage_")
+            })
+            .collect();
+        let used: Vec<String> = images.iter().map(|item| image_pv(item)).collect();
+        let replaced: Vec<(String, u64)> = images
+            .iter()
+            .filter(|item| bad.contains(&image_pv(item)))
+            .map(|item| {
+                let size_pe = item.lv_segs.iter().map(|seg| seg.size_pe).sum();
+                (item.lv_name.replacen("_rimage_", "_rmeta_", 1), size_pe)
+            })
+            .collect();
+        let has_meta = |rmeta: &str| {
+            self.lvs
+                .iter()
+                .any(|item| item.vg_name == vg && item.lv_name == rmeta)
+        };
+
+        let mut candidates: Vec<&FakePv> = self
+            .pvs_in_vg(&vg)
+            .filter(|pv| !pv.missing && !used.contains(&pv.name))
+            .filter(|pv| pvl.is_empty() || pvl.contains(&pv.name))
+            .collect();
+        candidates.sort_by_key(|pv| std::cmp::Reverse(self.free_extents(pv)));
+        let mut moves = Vec::new();
+        for (rmeta, size_pe) in &replaced {
+            let need = size_pe + u64::from(has_meta(rmeta));
+            let pos = candidates
+                .iter()
+                .position(|pv| self.free_ranges(pv).iter().any(|(_, count)| *count >= need))
+                .ok_or_else(|| {
+                    failed(
+                        cmd,
+                        format!("Insufficient suitable allocatable extents for {vg}/{lv}."),
+                    )
+                })?;
+            let pv = candidates.remove(pos);
+            let (start, _) = self
+                .free_ranges(pv)
+                .into_iter()
+                .find(|(_, count)| *count >= need)
+                .unwrap_or_default();
+            moves.push((rmeta.clone(), *size_pe, pv.name.clone(), start));
+        }
+
+        let mut new_pvs = Vec::new();
+        for (rmeta, size_pe, pv_name, mut start) in moves {
+            let rimage = rmeta.replacen("_rmeta_", "_rimage_", 1);
+            for item in self.lvs.iter_mut().filter(|item| item.vg_name == vg) {
+                let size_pe = if item.lv_name == rmeta {
+                    1
+                } else if item.lv_name == rimage {
+                    size_pe
+                } else {
+                    continue;
+                };
+                item.lv_segs = vec![LvmlvSegData {
+                    pvdev: pv_name.clone(),
+                    pv_start_pe: start,
+                    size_pe,
+                }];
+                set_attr_char(item, 8, '-');
+                item.health_status.clear();
+                start += size_pe;
+            }
+            new_pvs.push(pv_name);
+        }
+        let item = &mut self.lvs[i];
+        set_attr_char(item, 8, '-');
+        item.health_status.clear();
+        item.raid_sync_action = "recover".to_string();
+        item.sync_percent = Some(0.0);
+        Ok(new_pvs)
+    }
+
+    //
+    // Allocate 'extents' from the given pvs in order, may span several pvs.
+    //
+    fn alloc(&self, pvs: &[&FakePv], extents: u64) -> Option<Vec<LvmlvSegData>> {
+        let mut segs = Vec::new();
+        let mut remaining = extents;
+        for pv in pvs {
+            for (start, count) in self.free_ranges(pv) {
+                if remaining == 0 {
+                    break;
+                }
+                let take = count.min(remaining);
+                segs.push(LvmlvSegData {
+                    pvdev: pv.name.clone(),
+                    pv_start_pe: start,
+                    size_pe: take,
+                });
+                remaining -= take;
+            }
+        }
+
+        if remaining > 0 { None } else { Some(segs) }
+    }
+
+    //
+    // Drop the [pvmove0] lv, and if the copy is done point the moved
+    // extents of the lvs at their destination.
+    //
+    fn end_pvmove(&mut self, done: bool) {
+        let Some(mv) = self.pvmove.take() else {
+            return;
+        };
+        self.lvs
+            .retain(|lv| !(lv.vg_name == mv.vg && lv.lv_name == PVMOVE_LV));
+        if !done {
+            return;
+        }
+        let mut dest = mv.dest;
+        for (lv_name, start, count) in mv.moves {
+            let (to, rest) = split_segs(dest, count);
+            dest = rest;
+            if let Some(lv) = self
+                .lvs
+                .iter_mut()
+                .find(|lv| lv.vg_name == mv.vg && lv.lv_name == lv_name)
+            {
+                let segs = std::mem::take(&mut lv.lv_segs);
+                lv.lv_segs = move_extents(segs, &mv.src, start, count, to);
+            }
+        }
+    }
+
+    //
+    // Add a hidden sub lv of 'parent', active as the parent is.
+    //
+    fn add_sub_lv(
+        &mut self,
+        vg: &str,
+        parent: &str,
+        name: &str,
+        attr: &str,
+        segs: Vec<LvmlvSegData>,
+    ) {
+        let extent_size = self.vg(vg).map_or(DEFAULT_EXTENT_SIZE, |vg| vg.extent_size);
+        let uuid = self.next_uuid();
+        self.dm_minor_seq += 1;
+        self.lvs.push(LvmLvData {
+            lv_name: name.to_string(),
+            vg_name: vg.to_string(),
+            size: segs.iter().map(|seg| seg.size_pe).sum::<u64>() * extent_size,
+            attr: attr.to_string(),
+            segtype: "linear".to_string(),
+            uuid,
+            lv_segs: segs,
+            stripes: 1,
+            data_stripes: 1,
+            parent_lv: parent.to_string(),
+            dm_path: format!("/dev/mapper/{}", dm_name(vg, name)),
+            kernel_major: Some(DM_MAJOR),
+            kernel_minor: Some(self.dm_minor_seq),
+            time: FAKE_TIME.to_string(),
+            host: FAKE_HOST.to_string(),
+            ..Default::default()
+        });
+    }
+}
+
+fn failed<S: Into<String>>(cmd: &str, stderr: S) -> LvmError {
+    LvmError::CommandFailed {
+        cmd: cmd.to_string(),
+        code: Some(5),
+        stderr: stderr.into(),
+    }
+}
+
+fn unbracket(lv: &str) -> &str {
+    lv.trim_start_matches('[').trim_end_matches(']')
+}
+
+//
+// Device mapper name, '-' in names doubled, brackets of hidden lvs dropped.
+//
+fn dm_name(vg: &str, lv: &str) -> String {
+    let lv = unbracket(lv);
+    format!("{}-{}", vg.replace('-', "--"), lv.replace('-', "--"))
+}
+
+// After a rename, hidden lvs have no /dev/vg/lv.
+fn set_attr_char(item: &mut LvmLvData, n: usize, c: char) {
+    let mut attr: Vec<char> = item.attr.chars().collect();
+    if let Some(old) = attr.get_mut(n) {
+        *old = c;
+    }
+    item.attr = attr.into_iter().collect();
+}
+
+fn set_paths(item: &mut LvmLvData) {
+    if !item.path.is_empty() {
+        item.path = format!("/dev/{}/{}", item.vg_name, item.lv_name);
+    }
+    item.dm_path = format!("/dev/mapper/{}", dm_name(&item.vg_name, &item.lv_name));
+}
+
+fn clear_cache(item: &mut LvmLvData) {
+    item.pool_lv.clear();
+    item.cache_mode.clear();
+    item.cache_policy.clear();
+    for count in [
+        &mut item.cache_total_blocks,
+        &mut item.cache_used_blocks,
+        &mut item.cache_dirty_blocks,
+        &mut item.cache_read_hits,
+        &mut item.cache_read_misses,
+        &mut item.cache_write_hits,
+        &mut item.cache_write_misses,
+        &mut item.writecache_total_blocks,
+        &mut item.writecache_free_blocks,
+        &mut item.writecache_writeback_blocks,
+    ] {
+        *count = None;
+    }
+}
+
+//
+// Rename a fast lv and its sub lvs, '[fast_cpool]' and '[fast_cpool_cdata]'
+// for 'fast' and '[fast_cdata]'. Hidden when it has a 'parent'.
+//
+fn rename_fast(lvs: &mut [LvmLvData], vg: &str, from: &str, to: &str, parent: &str) {
+    let (from_base, to_base) = (unbracket(from), unbracket(to));
+    let (from_prefix, to_prefix) = (format!("[{from_base}_"), format!("[{to_base}_"));
+    for item in lvs.iter_mut().filter(|item| item.vg_name == vg) {
+        if item.lv_name == from {
+            item.lv_name = to.to_string();
+            item.parent_lv = parent.to_string();
+            item.path = if parent.is_empty() {
+                format!("/dev/{vg}/{to}")
+            } else {
+                String::new()
+            };
+        } else if item.parent_lv == from_base {
+            item.lv_name = item.lv_name.replacen(&from_prefix, &to_prefix, 1);
+            item.parent_lv = to_base.to_string();
+        } else {
+            continue;
+        }
+        set_paths(item);
+        for seg in item.lv_segs.iter_mut() {
+            seg.pvdev = seg.pvdev.replacen(&from_prefix, &to_prefix, 1);
+        }
+    }
+}
+
+//
+// First 'extents' of the segments and the rest.
+//
+fn split_segs(segs: Vec<LvmlvSegData>, extents: u64) -> (Vec<LvmlvSegData>, Vec<LvmlvSegData>) {
+    let (mut head, mut tail) = (Vec::new(), Vec::new());
+    let mut remaining = extents;
+    for seg in segs {
+        if remaining >= seg.size_pe {
+            remaining -= seg.size_pe;
+            head.push(seg);
+        } else {
+            if remaining > 0 {
+                head.push(LvmlvSegData {
+                    size_pe: remaining,
+                    ..seg.clone()
+                });
+            }
+            tail.push(LvmlvSegData {
+                pv_start_pe: seg.pv_start_pe + remaining,
+                size_pe: seg.size_pe - remaining,
+                ..seg
+            });
+            remaining = 0;
+        }
+    }
+    (head, tail)
+}
+
+//
+// Replace extents start..start+count of 'pv' in the segments with 'to',
+// splitting the segment they are in.
+//
+fn move_extents(
+    segs: Vec<LvmlvSegData>,
+    pv: &str,
+    start: u64,
+    count: u64,
+    mut to: Vec<LvmlvSegData>,
+) -> Vec<LvmlvSegData> {
+    let end = start + count;
+    let mut moved = Vec::new();
+    for seg in segs {
+        let seg_end = seg.pv_start_pe + seg.size_pe;
+        if seg.pvdev != pv || seg_end <= start || seg.pv_start_pe >= end {
+            moved.push(seg);
+            continue;
+        }
+        if seg.pv_start_pe < start {
+            moved.push(LvmlvSegData {
+                size_pe: start - seg.pv_start_pe,
+                ..seg.clone()
+            });
+        }
+        moved.append(&mut to);
+        if seg_end > end {
+            moved.push(LvmlvSegData {
+                pv_start_pe: end,
+                size_pe: seg_end - end,
+                ..seg
+            });
+        }
+    }
+    moved
+}
+
+//
+// Pool metadata size, --poolmetadatasize or roughly what lvm picks, 1/1000 of
+// the data and at least 2M.
+//
+fn pool_meta_extents(
+    extents: u64,
+    extent_size: u64,
+    extra: &[LvmExtraArg],
+) -> Result<u64, LvmError> {
+    let bytes = match extra.iter().find(|e| e.opt == "--poolmetadatasize") {
+        Some(e) => parse_size_arg(&e.value, "M").ok_or_else(|| {
+            failed(
+                "lvcreate",
+                format!("Invalid pool metadata size {}.", e.value),
+            )
+        })?,
+        None => (extents * extent_size / 1000).max(2 * 1024 * 1024),
+    };
+    Ok(bytes.div_ceil(extent_size).max(1))
+}
+
+fn extra_value(extra: &[LvmExtraArg], opt: &str) -> Option<u64> {
+    extra
+        .iter()
+        .find(|e| e.opt == opt)
+        .and_then(|e| e.value.parse::<u64>().ok())
+}
+
+//
+// Pools are allocated like linear lvs, striped and mirror are not modeled.
+//
+fn raid_layout(
+    segtype: &str,
+    stripes: Option<u64>,
+    mirrors: Option<u64>,
+    cmd: &str,
+) -> Result<LvmLayout, LvmError> {
+    let layout = match segtype {
+        "thin-pool" | "cache-pool" => LvmLayout::new("linear", None, None),
+        "striped" | "mirror" => None,
+        _ => LvmLayout::new(segtype, stripes, mirrors),
+    }
+    .ok_or_else(|| {
+        failed(
+            cmd,
+            format!("Segment type {segtype} is not supported by the fake backend."),
+        )
+    })?;
+
+    if layout.data_stripes == 0 || layout.images == 1 {
+        return Err(failed(cmd, "Invalid number of stripes/mirrors."));
+    }
+    Ok(layout)
+}
+
+impl LvmBackend for FakeBackend {
+    fn get_vgs(&self) -> Result<Vec<String>, LvmError> {
+        let state = self.state.lock().unwrap();
+        Ok(state.vgs.iter().map(|vg| vg.name.clone()).collect())
+    }
+
+    fn get_vg_info(&self, vg_name: &str) -> Result<LvmVgData, LvmError> {
+        self.state.lock().unwrap().vg_data(vg_name)
+    }
+
+    fn get_pvs(&self) -> Result<Vec<LvmPVData>, LvmError> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .pvs
+            .iter()
+            .enumerate()
+            .map(|(i, pv)| {
+                // Orphan pvs have no extents, size is still known.
+                let (pe_size, pe_count, alloc, attr) = match state.vg(&pv.vg_name) {
+                    Ok(vg) => (
+                        vg.extent_size,
+                        pv.pe_count,
+                        pv.pe_count - state.free_extents(pv),
+                        if pv.missing { "a-m" } else { "a--" },
+                    ),
+                    Err(_) => (0, 0, 0, "---"),
+                };
+                LvmPVData {
+                    pv_name: pv.name.clone(),
+                    vg_name: pv.vg_name.clone(),
+                    pv_size: pv.size,
+                    pv_free: pv.size - alloc * pe_size,
+                    pe_start: PE_START,
+                    pe_size,
+                    pe_count,
+                    pe_alloc_count: alloc,
+                    pv_attr: attr.to_string(),
+                    pv_uuid: format!("FAKEPV-0000-0000-0000-0000-0000-{i:06}"),
+                    major: (!pv.missing).then_some(8),
+                    minor: (!pv.missing).then_some(16 * i as u32),
+                }
+            })
+            .collect())
+    }
+
+    fn get_lvs(&self) -> Result<Vec<LvmLvData>, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        for lv in state.lvs.iter_mut() {
+            if matches!(lv.raid_sync_action.as_str(), "" | "idle") {
+                continue;
+            }
+            let percent = (lv.sync_percent.unwrap_or(0.0) + SYNC_STEP).min(100.0);
+            lv.sync_percent = Some(percent);
+            if percent >= 100.0 {
+                if lv.raid_sync_action == "repair" {
+                    lv.raid_mismatch_count = Some(0);
+                    set_attr_char(lv, 8, '-');
+                    lv.health_status.clear();
+                }
+                lv.raid_sync_action = "idle".to_string();
+            }
+        }
+        let mut moved = false;
+        for lv in state.lvs.iter_mut().filter(|lv| lv.lv_name == PVMOVE_LV) {
+            let percent = (lv.copy_percent.unwrap_or(0.0) + SYNC_STEP).min(100.0);
+            lv.copy_percent = Some(percent);
+            moved = percent >= 100.0;
+        }
+        if moved {
+            state.end_pvmove(true);
+        }
+        Ok(state.lvs.clone())
+    }
+
+    fn create_lv(
+        &self,
+        lv: &str,
+        vg: &str,
+        size: u64,
+        size_unit: &str,
+        segtype: &str,
+        pvl: &[String],
+        extra: &[LvmExtraArg],
+    ) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        let extent_size = state.vg(vg)?.extent_size;
+        if lv.is_empty() {
+            return Err(failed("lvcreate", "Please specify a logical volume name."));
+        }
+        if state
+            .lvs
+            .iter()
+            .any(|item| item.vg_name == vg && item.lv_name == lv)
+        {
+            return Err(failed(
+                "lvcreate",
+                format!("Logical Volume \"{lv}\" already exists in volume group \"{vg}\""),
+            ));
+        }
+
+        if segtype == "thin" {
+            return state.create_thin(lv, vg, size, size_unit, extra);
+        }
+
+        let layout = raid_layout(
+            segtype,
+            extra_value(extra, "--stripes"),
+            extra_value(extra, "--mirrors"),
+            "lvcreate",
+        )?;
+        let factor = layout.images.max(1) / layout.data_stripes;
+
+        // Size in extents of the lv as seen by the user, multiple of data stripes.
+        let (total, free) = state.vg_extents(vg);
+        let extents = match size_unit {
+            "%FREE" => free * size / 100 / factor,
+            "%VG" => total * size / 100 / factor,
+            unit => {
+                let mult = size_unit_bytes(unit)
+                    .ok_or_else(|| failed("lvcreate", format!("Invalid size unit {unit}.")))?;
+                (size * mult).div_ceil(extent_size)
+            }
+        };
+        let extents = extents.div_ceil(layout.data_stripes) * layout.data_stripes;
+        if extents == 0 {
+            return Err(failed("lvcreate", "Size must be larger than 0."));
+        }
+
+        let pvs: Vec<&FakePv> = state
+            .pvs_in_vg(vg)
+            .filter(|pv| pvl.is_empty() || pvl.contains(&pv.name))
+            .collect();
+        let no_space = || {
+            failed(
+                "lvcreate",
+                format!(
+                    "Volume group \"{vg}\" has insufficient free space ({free} extents): {} required.",
+                    extents * factor
+                ),
+            )
+        };
+
+        // Sub lv suffixes of a pool's metadata and data.
+        let pool_subs = match segtype {
+            "thin-pool" => Some(("tmeta", "tdata", "Twi-ao----")),
+            "cache-pool" => Some(("cmeta", "cdata", "Cwi-ao----")),
+            _ => None,
+        };
+        let is_pool = pool_subs.is_some();
+        let is_thin_pool = segtype == "thin-pool";
+        let meta_extents = if is_pool {
+            pool_meta_extents(extents, extent_size, extra)?
+        } else {
+            0
+        };
+
+        let mut new_lvs = Vec::<LvmLvData>::new();
+        let top_segs = if layout.images == 0 {
+            state
+                .alloc(&pvs, extents + meta_extents)
+                .ok_or_else(no_space)?
+        } else {
+            // One pv per image, pick the ones with most free space.
+            let image_extents = extents / layout.data_stripes;
+            let meta_extents = if layout.meta { 1 } else { 0 };
+            let mut candidates: Vec<&FakePv> = pvs
+                .iter()
+                .copied()
+                .filter(|pv| {
+                    state
+                        .free_ranges(pv)
+                        .iter()
+                        .any(|(_, count)| *count >= image_extents + meta_extents)
+                })
+                .collect();
+            candidates.sort_by_key(|pv| std::cmp::Reverse(state.free_extents(pv)));
+            if (candidates.len() as u64) < layout.images {
+                return Err(failed(
+                    "lvcreate",
+                    format!(
+                        "Insufficient suitable allocatable extents for logical volume {lv}: {} PVs needed, {} available.",
+                        layout.images,
+                        candidates.len()
+                    ),
+                ));
+            }
+
+            let mut top_segs = Vec::new();
+            for (i, pv) in candidates.iter().take(layout.images as usize).enumerate() {
+                let range = state
+                    .free_ranges(pv)
+                    .into_iter()
+                    .find(|(_, count)| *count >= image_extents + meta_extents)
+                    .ok_or_else(no_space)?;
+                let mut start = range.0;
+                if layout.meta {
+                    new_lvs.push(LvmLvData {
+                        lv_name: format!("[{lv}_rmeta_{i}]"),
+                        vg_name: vg.to_string(),
+                        size: extent_size,
+                        attr: "ewi-a-r---".to_string(),
+                        segtype: "linear".to_string(),
+                        uuid: String::new(),
+                        lv_segs: vec![LvmlvSegData {
+                            pvdev: pv.name.clone(),
+                            pv_start_pe: start,
+                            size_pe: 1,
+                        }],
+                        stripes: 1,
+                        data_stripes: 1,
+                        parent_lv: lv.to_string(),
+                        ..Default::default()
+                    });
+                    start += 1;
+                }
+                let image_name = format!("[{lv}_rimage_{i}]");
+                new_lvs.push(LvmLvData {
+                    lv_name: image_name.clone(),
+                    vg_name: vg.to_string(),
+                    size: image_extents * extent_size,
+                    attr: "iwi-a-r---".to_string(),
+                    segtype: "linear".to_string(),
+                    uuid: String::new(),
+                    lv_segs: vec![LvmlvSegData {
+                        pvdev: pv.name.clone(),
+                        pv_start_pe: start,
+                        size_pe: image_extents,
+                    }],
+                    stripes: 1,
+                    data_stripes: 1,
+                    parent_lv: lv.to_string(),
+                    ..Default::default()
+                });
+                top_segs.push(LvmlvSegData {
+                    pvdev: image_name,
+                    pv_start_pe: 0,
+                    size_pe: image_extents,
+                });
+            }
+            top_segs
+        };
+
+        // Data and metadata of a pool are sub lvs, allocated in one go.
+        let top_segs = if let Some((meta, data, data_attr)) = pool_subs {
+            let (data_segs, meta_segs) = split_segs(top_segs, extents);
+            for (suffix, attr, size_pe, segs) in [
+                (meta, "ewi-ao----", meta_extents, meta_segs),
+                (data, data_attr, extents, data_segs),
+            ] {
+                new_lvs.push(LvmLvData {
+                    lv_name: format!("[{lv}_{suffix}]"),
+                    vg_name: vg.to_string(),
+                    size: size_pe * extent_size,
+                    attr: attr.to_string(),
+                    segtype: "linear".to_string(),
+                    lv_segs: segs,
+                    stripes: 1,
+                    data_stripes: 1,
+                    parent_lv: lv.to_string(),
+                    ..Default::default()
+                });
+            }
+            vec![LvmlvSegData {
+                pvdev: format!("[{lv}_{data}]"),
+                pv_start_pe: 0,
+                size_pe: extents,
+            }]
+        } else {
+            top_segs
+        };
+
+        let is_raid = layout.images > 0;
+        let attr = match (is_raid, is_pool) {
+            (true, _) => "rwi-a-r---",
+            (_, true) if is_thin_pool => "twi-a-tz--",
+            (_, true) => "Cwi---C---",
+            _ => "-wi-a-----",
+        };
+        new_lvs.insert(
+            0,
+            LvmLvData {
+                lv_name: lv.to_string(),
+                vg_name: vg.to_string(),
+                size: extents * extent_size,
+                attr: attr.to_string(),
+                segtype: segtype.to_string(),
+                uuid: String::new(),
+                lv_segs: top_segs,
+                stripes: layout.images.max(1) as u16,
+                data_stripes: layout.data_stripes as u16,
+                parent_lv: String::new(),
+                path: format!("/dev/{vg}/{lv}"),
+                sync_percent: is_raid.then_some(100.0),
+                raid_sync_action: if layout.meta { "idle" } else { "" }.to_string(),
+                raid_mismatch_count: layout.meta.then_some(0),
+                data_percent: is_thin_pool.then_some(0.0),
+                metadata_percent: is_thin_pool.then_some(0.0),
+                ..Default::default()
+            },
+        );
+        for new_lv in new_lvs.iter_mut() {
+            new_lv.uuid = state.next_uuid();
+            state.dm_minor_seq += 1;
+            new_lv.kernel_major = Some(DM_MAJOR);
+            new_lv.kernel_minor = Some(state.dm_minor_seq);
+            new_lv.dm_path = format!("/dev/mapper/{}", dm_name(vg, &new_lv.lv_name));
+            new_lv.time = FAKE_TIME.to_string();
+            new_lv.host = FAKE_HOST.to_string();
+        }
+        state.lvs.append(&mut new_lvs);
+
+        Ok("Created lv".to_string())
+    }
+
+    fn remove_lv(&self, vg: &str, lv: &str) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        state.vg(vg)?;
+        let item = state
+            .lvs
+            .iter()
+            .find(|item| item.vg_name == vg && item.lv_name == lv)
+            .ok_or_else(|| {
+                failed(
+                    "lvremove",
+                    format!("Failed to find logical volume \"{vg}/{lv}\""),
+                )
+            })?;
+        if !item.parent_lv.is_empty() {
+            return Err(failed(
+                "lvremove",
+                format!(
+                    "Can't remove logical volume {vg}/{lv} used by {}.",
+                    item.parent_lv
+                ),
+            ));
+        }
+        if item.attr.chars().nth(5) == Some('o') {
+            return Err(failed(
+                "lvremove",
+                format!("Logical volume {vg}/{lv} contains a filesystem in use."),
+            ));
+        }
+
+        let origin = item.origin.clone();
+
+        // The lv, its sub lvs and snapshots of it, and theirs.
+        let mut names = vec![lv.to_string()];
+        let mut i = 0;
+        while i < names.len() {
+            for item in state.lvs.iter().filter(|item| item.vg_name == vg) {
+                // Parents of sub lvs of hidden lvs ([fast_cpool]) have no brackets.
+                let below = item.parent_lv == unbracket(&names[i])
+                    || item.origin == names[i]
+                    || item.pool_lv == names[i];
+                if below && !names.contains(&item.lv_name) {
+                    names.push(item.lv_name.clone());
+                }
+            }
+            i += 1;
+        }
+        state
+            .lvs
+            .retain(|item| item.vg_name != vg || !names.contains(&item.lv_name));
+        if !origin.is_empty() {
+            state.sync_origin(vg, &origin);
+        }
+
+        Ok("Removed lv".to_string())
+    }
+
+    //
+    // Linear lvs only, extended at the end with the first free extents of the
+    // vg, reduced from the end.
+    //
+    fn resize_lv(&self, vg: &str, lv: &str, resize: &LvmResize) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        let vg_data = state.vg_data(vg)?;
+        let extent_size = state.vg(vg)?.extent_size;
+        let i = state
+            .lvs
+            .iter()
+            .position(|item| item.vg_name == vg && item.lv_name == lv)
+            .ok_or_else(|| {
+                failed(
+                    "lvresize",
+                    format!("Failed to find logical volume \"{vg}/{lv}\""),
+                )
+            })?;
+        if state.lvs[i].segtype != "linear" {
+            return Err(failed(
+                "lvresize",
+                format!(
+                    "Resizing {} lvs is not supported by the fake backend.",
+                    state.lvs[i].segtype
+                ),
+            ));
+        }
+
+        let cur_extents = state.lvs[i].size / extent_size;
+        let new_extents = resize.new_size(state.lvs[i].size, &vg_data, extent_size)? / extent_size;
+        if new_extents == cur_extents {
+            return Err(failed(
+                "lvresize",
+                format!(
+                    "New size ({new_extents} extents) matches existing size ({cur_extents} extents)."
+                ),
+            ));
+        }
+
+        let mut segs = state.lvs[i].lv_segs.clone();
+        if new_extents > cur_extents {
+            let pvs: Vec<&FakePv> = state.pvs_in_vg(vg).collect();
+            let free = vg_data.free / extent_size;
+            let more = state
+                .alloc(&pvs, new_extents - cur_extents)
+                .ok_or_else(|| {
+                    failed(
+                        "lvresize",
+                        format!(
+                            "Insufficient free space: {} extents needed, but only {free} available",
+                            new_extents - cur_extents
+                        ),
+                    )
+                })?;
+            segs.extend(more);
+        } else {
+            let mut remaining = new_extents;
+            segs.retain_mut(|seg| {
+                let keep = seg.size_pe.min(remaining);
+                seg.size_pe = keep;
+                remaining -= keep;
+                keep > 0
+            });
+        }
+
+        let item = &mut state.lvs[i];
+        item.lv_segs = segs;
+        item.size = new_extents * extent_size;
+        Ok("Resized lv".to_string())
+    }
+
+    //
+    // Sub lvs follow, [lv_rimage_0] becomes [new_rimage_0], as do references to
+    // the lv (parent, origin, segments on sub lvs).
+    //
+    fn rename_lv(&self, vg: &str, lv: &str, new_name: &str) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        state.vg(vg)?;
+        let item = state
+            .lvs
+            .iter()
+            .find(|item| item.vg_name == vg && item.lv_name == lv)
+            .ok_or_else(|| {
+                failed(
+                    "lvrename",
+                    format!("Existing logical volume \"{lv}\" not found in volume group \"{vg}\""),
+                )
+            })?;
+        if !item.parent_lv.is_empty() {
+            return Err(failed(
+                "lvrename",
+                format!("Cannot rename internal LV \"{lv}\"."),
+            ));
+        }
+        check_lv_name(new_name).map_err(|e| failed("lvrename", e))?;
+        if state
+            .lvs
+            .iter()
+            .any(|item| item.vg_name == vg && item.lv_name == new_name)
+        {
+            return Err(failed(
+                "lvrename",
+                format!("Logical Volume \"{new_name}\" already exists in volume group \"{vg}\""),
+            ));
+        }
+
+        let sub_prefix = format!("[{lv}_");
+        let renamed = |name: &str| {
+            if name == lv {
+                Some(new_name.to_string())
+            } else {
+                name.strip_prefix(&sub_prefix)
+                    .map(|rest| format!("[{new_name}_{rest}"))
+            }
+        };
+        for item in state.lvs.iter_mut().filter(|item| item.vg_name == vg) {
+            if let Some(name) = renamed(&item.lv_name) {
+                item.lv_name = name;
+                set_paths(item);
+            }
+            if let Some(name) = renamed(&item.parent_lv) {
+                item.parent_lv = name;
+            }
+            if let Some(name) = renamed(&item.origin) {
+                item.origin = name;
+            }
+            if let Some(name) = renamed(&item.pool_lv) {
+                item.pool_lv = name;
+            }
+            for seg in item.lv_segs.iter_mut() {
+                if let Some(name) = renamed(&seg.pvdev) {
+                    seg.pvdev = name;
+                }
+            }
+        }
+
+        Ok("Renamed lv".to_string())
+    }
+
+    fn rename_vg(&self, vg: &str, new_name: &str) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        state.vg(vg)?;
+        check_vg_name(new_name).map_err(|e| failed("vgrename", e))?;
+        if state.vg(new_name).is_ok() {
+            return Err(failed(
+                "vgrename",
+                format!("New volume group \"{new_name}\" already exists"),
+            ));
+        }
+
+        for item in state.vgs.iter_mut().filter(|item| item.name == vg) {
+            item.name = new_name.to_string();
+        }
+        for pv in state.pvs.iter_mut().filter(|pv| pv.vg_name == vg) {
+            pv.vg_name = new_name.to_string();
+        }
+        for item in state.lvs.iter_mut().filter(|item| item.vg_name == vg) {
+            item.vg_name = new_name.to_string();
+            set_paths(item);
+        }
+
+        Ok("Renamed vg".to_string())
+    }
+
+    //
+    // Cow space is allocated like for a linear lv, first free extents first.
+    //
+    fn create_snapshot(
+        &self,
+        vg: &str,
+        origin: &str,
+        snap: &LvmSnapshot,
+    ) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        let vg_data = state.vg_data(vg)?;
+        let extent_size = state.vg(vg)?.extent_size;
+        let origin_lv = state
+            .lvs
+            .iter()
+            .find(|item| item.vg_name == vg && item.lv_name == origin)
+            .ok_or_else(|| {
+                failed(
+                    "lvcreate",
+                    format!("Failed to find logical volume \"{vg}/{origin}\""),
+                )
+            })?;
+        if !origin_lv.parent_lv.is_empty() || !origin_lv.origin.is_empty() {
+            return Err(failed(
+                "lvcreate",
+                format!("Snapshots of {vg}/{origin} are not supported."),
+            ));
+        }
+        check_lv_name(&snap.name).map_err(|e| failed("lvcreate", e))?;
+        if state
+            .lvs
+            .iter()
+            .any(|item| item.vg_name == vg && item.lv_name == snap.name)
+        {
+            return Err(failed(
+                "lvcreate",
+                format!(
+                    "Logical Volume \"{}\" already exists in volume group \"{vg}\"",
+                    snap.name
+                ),
+            ));
+        }
+
+        let extents = snap.cow_size(origin_lv.size, &vg_data, extent_size)? / extent_size;
+        let pvs: Vec<&FakePv> = state.pvs_in_vg(vg).collect();
+        let segs = state.alloc(&pvs, extents).ok_or_else(|| {
+            failed(
+                "lvcreate",
+                format!(
+                    "Volume group \"{vg}\" has insufficient free space ({} extents): {extents} required.",
+                    vg_data.free / extent_size
+                ),
+            )
+        })?;
+
+        let uuid = state.next_uuid();
+        state.dm_minor_seq += 1;
+        let minor = state.dm_minor_seq;
+        let name = &snap.name;
+        state.lvs.push(LvmLvData {
+            lv_name: name.clone(),
+            vg_name: vg.to_string(),
+            size: extents * extent_size,
+            attr: "swi-a-s---".to_string(),
+            segtype: "linear".to_string(),
+            uuid,
+            lv_segs: segs,
+            stripes: 1,
+            data_stripes: 1,
+            path: format!("/dev/{vg}/{name}"),
+            dm_path: format!("/dev/mapper/{}", dm_name(vg, name)),
+            kernel_major: Some(DM_MAJOR),
+            kernel_minor: Some(minor),
+            time: FAKE_TIME.to_string(),
+            host: FAKE_HOST.to_string(),
+            origin: origin.to_string(),
+            snap_percent: Some(0.0),
+            ..Default::default()
+        });
+        state.sync_origin(vg, origin);
+
+        Ok("Created snapshot".to_string())
+    }
+
+    //
+    // Merged right away, unless the origin is open then both are marked merging.
+    //
+    fn merge_snapshot(&self, vg: &str, snap: &str) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        state.vg(vg)?;
+        let i = state
+            .lvs
+            .iter()
+            .position(|item| item.vg_name == vg && item.lv_name == snap)
+            .ok_or_else(|| {
+                failed(
+                    "lvconvert",
+                    format!("Failed to find logical volume \"{vg}/{snap}\""),
+                )
+            })?;
+        let origin = state.lvs[i].origin.clone();
+        if origin.is_empty() {
+            return Err(failed(
+                "lvconvert",
+                format!("{vg}/{snap} is not a snapshot."),
+            ));
+        }
+        if state.lvs[i].attr.chars().nth(4) == Some('I') {
+            return Err(failed(
+                "lvconvert",
+                format!("Unable to merge invalidated snapshot LV \"{snap}\"."),
+            ));
+        }
+
+        let origin_open = state.lvs.iter().any(|item| {
+            item.vg_name == vg && item.lv_name == origin && item.attr.chars().nth(5) == Some('o')
+        });
+        if origin_open {
+            for item in state.lvs.iter_mut().filter(|item| item.vg_name == vg) {
+                if item.lv_name == snap {
+                    set_attr_char(item, 0, 'S');
+                } else if item.lv_name == origin {
+                    set_attr_char(item, 0, 'O');
+                }
+            }
+            return Ok(format!(
+                "Delaying merge since origin is open. Merging of snapshot {vg}/{snap} will occur on next activation of {vg}/{origin}."
+            ));
+        }
+        state.lvs.remove(i);
+        state.sync_origin(vg, &origin);
+
+        Ok(format!("Merging of volume {vg}/{snap} started."))
+    }
+
+    //
+    // Linear lvs only. The fast lv is created like lvcreate would, then the
+    // lv's extents move to [lv_corig] and the fast lv becomes [fast_cpool] or
+    // [fast_cvol], both hidden below the lv.
+    //
+    fn attach_cache(&self, vg: &str, lv: &str, cache: &LvmCache) -> Result<String, LvmError> {
+        {
+            let state = self.state.lock().unwrap();
+            state.vg(vg)?;
+            let item = state
+                .lvs
+                .iter()
+                .find(|item| item.vg_name == vg && item.lv_name == lv)
+                .ok_or_else(|| {
+                    failed(
+                        "lvconvert",
+                        format!("Failed to find logical volume \"{vg}/{lv}\""),
+                    )
+                })?;
+            if item.segtype != "linear" || !item.parent_lv.is_empty() || !item.origin.is_empty() {
+                return Err(failed(
+                    "lvconvert",
+                    format!(
+                        "Caching {} lv {vg}/{lv} is not supported by the fake backend.",
+                        item.segtype
+                    ),
+                ));
+            }
+            if !["", "writethrough", "writeback"].contains(&cache.cache_mode.as_str()) {
+                return Err(failed(
+                    "lvconvert",
+                    format!("Unknown cache mode \"{}\".", cache.cache_mode),
+                ));
+            }
+            if !["", "smq", "mq"].contains(&cache.cache_policy.as_str()) {
+                return Err(failed(
+                    "lvconvert",
+                    format!("Unknown cache policy \"{}\".", cache.cache_policy),
+                ));
+            }
+        }
+        let segtype = match cache.cache_type {
+            LvmCacheType::CachePool => "cache-pool",
+            _ => "linear",
+        };
+        self.create_lv(
+            &cache.name,
+            vg,
+            cache.size,
+            &cache.size_unit,
+            segtype,
+            std::slice::from_ref(&cache.fast_pv),
+            &[],
+        )?;
+
+        let mut state = self.state.lock().unwrap();
+        let (fast_suffix, orig_suffix) = match cache.cache_type {
+            LvmCacheType::CachePool => ("cpool", "corig"),
+            LvmCacheType::CacheVol => ("cvol", "corig"),
+            LvmCacheType::Writecache => ("cvol", "wcorig"),
+        };
+        let fast_name = format!("[{}_{fast_suffix}]", cache.name);
+        let orig_name = format!("[{lv}_{orig_suffix}]");
+        let fast_size = state
+            .lvs
+            .iter()
+            .find(|item| item.vg_name == vg && item.lv_name == cache.name)
+            .map_or(0, |item| item.size);
+        rename_fast(&mut state.lvs, vg, &cache.name, &fast_name, lv);
+        if cache.cache_type != LvmCacheType::CachePool
+            && let Some(item) = state
+                .lvs
+                .iter_mut()
+                .find(|item| item.vg_name == vg && item.lv_name == fast_name)
+        {
+            item.attr = "Cwi-aoC---".to_string();
+        }
+
+        let uuid = state.next_uuid();
+        state.dm_minor_seq += 1;
+        let minor = state.dm_minor_seq;
+        let extent_size = state.vg(vg)?.extent_size;
+        let i = state
+            .lvs
+            .iter()
+            .position(|item| item.vg_name == vg && item.lv_name == lv)
+            .ok_or_else(|| failed("lvconvert", format!("Failed to find {vg}/{lv}.")))?;
+        let item = &mut state.lvs[i];
+        let orig = LvmLvData {
+            lv_name: orig_name.clone(),
+            vg_name: vg.to_string(),
+            size: item.size,
+            attr: "owi-aoC---".to_string(),
+            segtype: item.segtype.clone(),
+            uuid,
+            lv_segs: std::mem::take(&mut item.lv_segs),
+            stripes: 1,
+            data_stripes: 1,
+            parent_lv: lv.to_string(),
+            dm_path: format!("/dev/mapper/{}", dm_name(vg, &orig_name)),
+            kernel_major: Some(DM_MAJOR),
+            kernel_minor: Some(minor),
+            time: FAKE_TIME.to_string(),
+            host: FAKE_HOST.to_string(),
+            ..Default::default()
+        };
+        let open = item.attr.chars().nth(5) == Some('o');
+        item.attr = "Cwi-a-C---".to_string();
+        set_attr_char(item, 5, if open { 'o' } else { '-' });
+        item.segtype = cache.cache_type.segtype().to_string();
+        item.lv_segs = vec![LvmlvSegData {
+            pvdev: orig_name,
+            pv_start_pe: 0,
+            size_pe: item.size / extent_size,
+        }];
+        item.pool_lv = fast_name;
+        if cache.cache_type == LvmCacheType::Writecache {
+            item.writecache_total_blocks = Some(fast_size / WRITECACHE_BLOCK);
+            item.writecache_free_blocks = item.writecache_total_blocks;
+            item.writecache_writeback_blocks = Some(0);
+        } else {
+            item.cache_mode = match cache.cache_mode.as_str() {
+                "" => "writethrough".to_string(),
+                mode => mode.to_string(),
+            };
+            item.cache_policy = match cache.cache_policy.as_str() {
+                "" => "smq".to_string(),
+                policy => policy.to_string(),
+            };
+            item.cache_total_blocks = Some(fast_size / CACHE_CHUNK);
+            for count in [
+                &mut item.cache_used_blocks,
+                &mut item.cache_dirty_blocks,
+                &mut item.cache_read_hits,
+                &mut item.cache_read_misses,
+                &mut item.cache_write_hits,
+                &mut item.cache_write_misses,
+            ] {
+                *count = Some(0);
+            }
+        }
+        state.lvs.push(orig);
+
+        Ok("Attached cache".to_string())
+    }
+
+    //
+    // The lv gets the extents of [lv_corig] back, the fast lv is visible again
+    // or removed.
+    //
+    fn detach_cache(&self, vg: &str, lv: &str, keep: bool) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        state.vg(vg)?;
+        let i = state
+            .lvs
+            .iter()
+            .position(|item| item.vg_name == vg && item.lv_name == lv)
+            .ok_or_else(|| {
+                failed(
+                    "lvconvert",
+                    format!("Failed to find logical volume \"{vg}/{lv}\""),
+                )
+            })?;
+        if !matches!(state.lvs[i].segtype.as_str(), "cache" | "writecache") {
+            return Err(failed(
+                "lvconvert",
+                format!("Logical volume {vg}/{lv} is not cached."),
+            ));
+        }
+        let fast_name = state.lvs[i].pool_lv.clone();
+        let orig_name = state.lvs[i]
+            .lv_segs
+            .first()
+            .map(|seg| seg.pvdev.clone())
+            .unwrap_or_default();
+        let orig = state
+            .lvs
+            .iter()
+            .find(|item| item.vg_name == vg && item.lv_name == orig_name)
+            .cloned()
+            .ok_or_else(|| failed("lvconvert", format!("{vg}/{lv} has no cache origin.")))?;
+
+        let item = &mut state.lvs[i];
+        let open = item.attr.chars().nth(5) == Some('o');
+        item.attr = "-wi-a-----".to_string();
+        set_attr_char(item, 5, if open { 'o' } else { '-' });
+        item.segtype = orig.segtype;
+        item.lv_segs = orig.lv_segs;
+        clear_cache(item);
+        state
+            .lvs
+            .retain(|item| item.vg_name != vg || item.lv_name != orig_name);
+
+        let fast_base = unbracket(&fast_name);
+        if keep {
+            let (name, is_pool) = match fast_base.strip_suffix("_cpool") {
+                Some(name) => (name, true),
+                None => (fast_base.strip_suffix("_cvol").unwrap_or(fast_base), false),
+            };
+            rename_fast(&mut state.lvs, vg, &fast_name, name, "");
+            if let Some(item) = state
+                .lvs
+                .iter_mut()
+                .find(|item| item.vg_name == vg && item.lv_name == name)
+            {
+                item.attr = if is_pool { "Cwi---C---" } else { "-wi-a-----" }.to_string();
+            }
+            return Ok(format!(
+                "Logical volume {vg}/{lv} is not cached and {vg}/{name} is unused."
+            ));
+        }
+        state.lvs.retain(|item| {
+            item.vg_name != vg || (item.lv_name != fast_name && item.parent_lv != fast_base)
+        });
+        Ok(format!("Logical volume \"{vg}/{lv}\" is not cached."))
+    }
+
+    //
+    // Check resets the mismatch count, repair clears it when done.
+    //
+    fn raid_sync_action(
+        &self,
+        vg: &str,
+        lv: &str,
+        action: LvmSyncAction,
+    ) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        let i = state.raid_lv(vg, lv, "lvchange")?;
+        let item = &mut state.lvs[i];
+        if !item.health_status.is_empty() && item.health_status != "mismatches exist" {
+            return Err(failed(
+                "lvchange",
+                format!(
+                    "Unable to {} degraded {vg}/{lv}, repair it first.",
+                    action.text()
+                ),
+            ));
+        }
+        if item.raid_sync_action != "idle" {
+            return Err(failed(
+                "lvchange",
+                format!(
+                    "{vg}/{lv} state is currently \"{}\".  Unable to switch to \"{}\".",
+                    item.raid_sync_action,
+                    action.text()
+                ),
+            ));
+        }
+        item.raid_sync_action = action.text().to_string();
+        item.sync_percent = Some(0.0);
+        if action == LvmSyncAction::Check {
+            item.raid_mismatch_count = Some(0);
+            set_attr_char(item, 8, '-');
+            item.health_status.clear();
+        }
+        Ok(format!("Started {} of {vg}/{lv}", action.text()))
+    }
+
+    fn repair_raid(&self, vg: &str, lv: &str, pvl: &[String]) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        let i = state.raid_lv(vg, lv, "lvconvert")?;
+        let missing: Vec<String> = state
+            .pvs_in_vg(vg)
+            .filter(|pv| pv.missing)
+            .map(|pv| pv.name.clone())
+            .collect();
+        if state.lvs[i].attr.chars().nth(8) != Some('p') || missing.is_empty() {
+            return Err(failed(
+                "lvconvert",
+                format!("{vg}/{lv} has no failed images to repair."),
+            ));
+        }
+        let new_pvs = state.replace_images(i, &missing, pvl, "lvconvert")?;
+        Ok(format!(
+            "Faulty devices in {vg}/{lv} successfully replaced by {}.",
+            new_pvs.join(", ")
+        ))
+    }
+
+    fn replace_raid_pv(
+        &self,
+        vg: &str,
+        lv: &str,
+        old_pv: &str,
+        pvl: &[String],
+    ) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        let i = state.raid_lv(vg, lv, "lvconvert")?;
+        if state.lvs[i].raid_sync_action != "idle" {
+            return Err(failed(
+                "lvconvert",
+                format!("Unable to replace devices in {vg}/{lv} while it is not in-sync."),
+            ));
+        }
+        let on_pv = state.lvs.iter().any(|item| {
+            item.vg_name == vg
+                && item.parent_lv == lv
+                && item.lv_segs.iter().any(|seg| seg.pvdev == old_pv)
+        });
+        if !on_pv {
+            return Err(failed(
+                "lvconvert",
+                format!("{vg}/{lv} does not contain devices specified to replace."),
+            ));
+        }
+        let new_pvs = state.replace_images(i, &[old_pv.to_string()], pvl, "lvconvert")?;
+        Ok(format!(
+            "Replaced {old_pv} in {vg}/{lv} by {}.",
+            new_pvs.join(", ")
+        ))
+    }
+
+    //
+    // Images are added or dropped at the end, a linear lv becomes image 0 and
+    // back. Metadata sub lvs go on the pv of their image. Everything is
+    // allocated before anything changes, added images then resync. More
+    // stripes (reshape) adds images of the same size, the lv grows.
+    //
+    fn convert_lv(
+        &self,
+        vg: &str,
+        lv: &str,
+        convert: &LvmConvert,
+        pvl: &[String],
+    ) -> Result<String, LvmError> {
+        let cmd = "lvconvert";
+        let mut state = self.state.lock().unwrap();
+        let extent_size = state.vg(vg)?.extent_size;
+        let i = state
+            .lvs
+            .iter()
+            .position(|item| item.vg_name == vg && item.lv_name == lv)
+            .ok_or_else(|| failed(cmd, format!("Failed to find logical volume \"{vg}/{lv}\"")))?;
+        let item = &state.lvs[i];
+        if !convert_targets(item).contains(&convert.segtype.as_str()) {
+            return Err(failed(
+                cmd,
+                format!(
+                    "Unable to convert {vg}/{lv} from {} to {}.",
+                    item.segtype, convert.segtype
+                ),
+            ));
+        }
+        if !matches!(item.raid_sync_action.as_str(), "" | "idle") {
+            return Err(failed(
+                cmd,
+                format!("Unable to convert {vg}/{lv} while it is not in-sync."),
+            ));
+        }
+        let from = LvmLayout::of_lv(item);
+        let stripes = convert.stripes.unwrap_or(from.data_stripes);
+        let reshape = stripes != from.data_stripes;
+        if reshape && (convert.segtype != item.segtype || stripes < from.data_stripes) {
+            return Err(failed(
+                cmd,
+                format!(
+                    "Unable to change the stripes of {vg}/{lv} from {} to {stripes} converting to {}.",
+                    from.data_stripes, convert.segtype
+                ),
+            ));
+        }
+        // Copies are kept unless asked for.
+        let mirrors = convert
+            .mirrors
+            .or(matches!(item.segtype.as_str(), "raid1" | "raid10")
+                .then(|| from.images / from.data_stripes - 1));
+        let to = raid_layout(&convert.segtype, Some(stripes), mirrors, cmd)?;
+        let image_extents = item.size / extent_size / from.data_stripes;
+
+        let sub_name = |kind: &str, k: u64| format!("[{lv}_{kind}_{k}]");
+        let sub_segs = |name: &str| {
+            state
+                .lvs
+                .iter()
+                .find(|item| item.vg_name == vg && item.lv_name == name)
+                .map(|item| item.lv_segs.clone())
+        };
+        // A linear lv is its own image 0.
+        let image_segs = |k: u64| {
+            if from.images == 0 {
+                Some(item.lv_segs.clone())
+            } else {
+                sub_segs(&sub_name("rimage", k))
+            }
+        };
+        let lv_pvs: Vec<String> = state
+            .lvs
+            .iter()
+            .filter(|item| item.vg_name == vg && (item.lv_name == lv || item.parent_lv == lv))
+            .flat_map(|item| item.lv_segs.iter())
+            .filter(|seg| !seg.pvdev.starts_with('['))
+            .map(|seg| seg.pvdev.clone())
+            .collect();
+        let kept = to.images.min(from.images.max(1));
+        let meta = u64::from(to.meta);
+
+        // (name, pv, start, extents) of the sub lvs to create.
+        let mut allocs = Vec::new();
+        for k in 0..kept {
+            if !to.meta || sub_segs(&sub_name("rmeta", k)).is_some() {
+                continue;
+            }
+            let pv_name = image_segs(k)
+                .and_then(|segs| segs.first().map(|seg| seg.pvdev.clone()))
+                .unwrap_or_default();
+            let start = state
+                .pvs
+                .iter()
+                .find(|pv| pv.name == pv_name)
+                .and_then(|pv| state.free_ranges(pv).first().copied())
+                .map(|(start, _)| start)
+                .ok_or_else(|| {
+                    failed(
+                        cmd,
+                        format!("No free extents on {pv_name} for the metadata of {vg}/{lv}."),
+                    )
+                })?;
+            allocs.push((sub_name("rmeta", k), pv_name, start, 1));
+        }
+        let mut candidates: Vec<&FakePv> = state
+            .pvs_in_vg(vg)
+            .filter(|pv| !pv.missing && !lv_pvs.contains(&pv.name))
+            .filter(|pv| pvl.is_empty() || pvl.contains(&pv.name))
+            .collect();
+        candidates.sort_by_key(|pv| std::cmp::Reverse(state.free_extents(pv)));
+        for k in kept..to.images {
+            let need = image_extents + meta;
+            let pos = candidates
+                .iter()
+                .position(|pv| state.free_ranges(pv).iter().any(|(_, count)| *count >= need))
+                .ok_or_else(|| {
+                    failed(
+                        cmd,
+                        format!(
+                            "Insufficient suitable allocatable extents for {vg}/{lv}: {} more PVs needed.",
+                            to.images - k
+                        ),
+                    )
+                })?;
+            let pv = candidates.remove(pos);
+            let (start, _) = state
+                .free_ranges(pv)
+                .into_iter()
+                .find(|(_, count)| *count >= need)
+                .unwrap_or_default();
+            if to.meta {
+                allocs.push((sub_name("rmeta", k), pv.name.clone(), start, 1));
+            }
+            allocs.push((
+                sub_name("rimage", k),
+                pv.name.clone(),
+                start + meta,
+                image_extents,
+            ));
+        }
+        let linear_segs = image_segs(0).unwrap_or_default();
+        let was_linear = from.images == 0;
+        let old_segs = item.lv_segs.clone();
+
+        // Image 0 of a linear lv, dropped images and metadata.
+        if was_linear && to.images > 0 {
+            state.add_sub_lv(vg, lv, &sub_name("rimage", 0), "iwi-a-r---", old_segs);
+        }
+        state.lvs.retain(|item| {
+            if item.vg_name != vg || item.parent_lv != lv {
+                return true;
+            }
+            let index = |kind: &str| {
+                unbracket(&item.lv_name)
+                    .strip_prefix(&format!("{lv}_{kind}_"))
+                    .and_then(|k| k.parse::<u64>().ok())
+            };
+            match (index("rimage"), index("rmeta")) {
+                (Some(k), _) => k < to.images,
+                (_, Some(k)) => to.meta && k < to.images,
+                _ => true,
+            }
+        });
+        for (name, pv_name, start, size_pe) in allocs {
+            let attr = if name.contains("_rmeta_") {
+                "ewi-a-r---"
+            } else {
+                "iwi-a-r---"
+            };
+            let segs = vec![LvmlvSegData {
+                pvdev: pv_name,
+                pv_start_pe: start,
+                size_pe,
+            }];
+            state.add_sub_lv(vg, lv, &name, attr, segs);
+        }
+
+        let added = to.images > kept;
+        let i = state
+            .lvs
+            .iter()
+            .position(|item| item.vg_name == vg && item.lv_name == lv)
+            .unwrap_or(i);
+        let item = &mut state.lvs[i];
+        item.segtype = convert.segtype.clone();
+        item.size = image_extents * to.data_stripes * extent_size;
+        item.stripes = to.images.max(1) as u16;
+        item.data_stripes = to.data_stripes as u16;
+        let is_raid = to.images > 0;
+        if is_raid {
+            item.lv_segs = (0..to.images)
+                .map(|k| LvmlvSegData {
+                    pvdev: sub_name("rimage", k),
+                    pv_start_pe: 0,
+                    size_pe: image_extents,
+                })
+                .collect();
+        } else {
+            item.lv_segs = linear_segs;
+        }
+        if !item.attr.starts_with('o') {
+            set_attr_char(item, 0, if is_raid { 'r' } else { '-' });
+        }
+        set_attr_char(item, 6, if is_raid { 'r' } else { '-' });
+        item.sync_percent = match (is_raid, added) {
+            (false, _) => None,
+            (true, true) => Some(0.0),
+            (true, false) => Some(item.sync_percent.unwrap_or(100.0)),
+        };
+        item.raid_sync_action = match (to.meta, added) {
+            (false, _) => "",
+            (true, true) if reshape => "reshape",
+            (true, true) => "recover",
+            (true, false) => "idle",
+        }
+        .to_string();
+        item.raid_mismatch_count = to.meta.then(|| item.raid_mismatch_count.unwrap_or(0));
+        if !to.meta {
+            set_attr_char(item, 8, '-');
+            item.health_status.clear();
+        }
+        Ok(format!(
+            "Logical volume {vg}/{lv} successfully converted to {}.",
+            convert.segtype
+        ))
+    }
+
+    //
+    // Pvs are listed as taken, like blkid would see their label.
+    //
+    fn get_block_devs(&self) -> Result<Vec<BlockDev>, LvmError> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .devs
+            .iter()
+            .map(|dev| {
+                let mut dev = dev.clone();
+                if state.pvs.iter().any(|pv| pv.name == dev.name) {
+                    dev.used_by = Some("LVM2_member signature".to_string());
+                }
+                dev
+            })
+            .collect())
+    }
+
+    fn create_pv(&self, dev: &str, pv: &LvmPvCreate) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(existing) = state.pvs.iter().find(|item| item.name == dev) {
+            if existing.vg_name.is_empty() {
+                return Ok(format!("Physical volume \"{dev}\" successfully created."));
+            }
+            return Err(failed(
+                "pvcreate",
+                format!(
+                    "Can't initialize physical volume \"{dev}\" of volume group \"{}\" without -ff",
+                    existing.vg_name
+                ),
+            ));
+        }
+        let block_dev = state
+            .devs
+            .iter()
+            .find(|item| item.name == dev)
+            .ok_or_else(|| failed("pvcreate", format!("No device found for {dev}.")))?;
+        if let Some(used_by) = &block_dev.used_by {
+            return Err(failed("pvcreate", format!("Cannot use {dev}: {used_by}")));
+        }
+        if pv.metadata_copies.is_some_and(|n| n > 2) {
+            return Err(failed("pvcreate", "Metadatacopies may only be 0, 1 or 2"));
+        }
+        if pv
+            .data_alignment
+            .is_some_and(|n| !n.is_multiple_of(4096) || n >= block_dev.size)
+        {
+            return Err(failed(
+                "pvcreate",
+                "Data alignment must be a multiple of 4KiB and less than the device.",
+            ));
+        }
+        let size = block_dev.size;
+        state.pvs.push(FakePv {
+            name: dev.to_string(),
+            vg_name: String::new(),
+            size,
+            pe_count: 0,
+            missing: false,
+        });
+        Ok(format!("Physical volume \"{dev}\" successfully created."))
+    }
+
+    fn create_vg(&self, vg: &str, pvl: &[String], opts: &LvmVgCreate) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        check_vg_name(vg).map_err(|e| failed("vgcreate", e))?;
+        if state.vg(vg).is_ok() {
+            return Err(failed(
+                "vgcreate",
+                format!("A volume group called {vg} already exists."),
+            ));
+        }
+        let extent_size = opts.extent_size.unwrap_or(DEFAULT_EXTENT_SIZE);
+        if !extent_size.is_power_of_two() || extent_size < 1024 {
+            return Err(failed(
+                "vgcreate",
+                "Physical extent size must be a power of 2 of at least 1KiB.",
+            ));
+        }
+        let alloc = match opts.alloc {
+            AllocPolicy::Unset => AllocPolicy::Normal,
+            alloc if AllocPolicy::VG.contains(&alloc) => alloc,
+            alloc => {
+                return Err(failed(
+                    "vgcreate",
+                    format!("Allocation policy {} is not for vgs.", alloc.text()),
+                ));
+            }
+        };
+        let pvs = state.orphan_pvs(pvl, "vgcreate")?;
+        if pvs.iter().any(|i| state.pvs[*i].size < extent_size) {
+            return Err(failed(
+                "vgcreate",
+                "Physical extent size is larger than a pv.",
+            ));
+        }
+
+        let uuid = state.next_uuid();
+        state.vgs.push(FakeVg {
+            name: vg.to_string(),
+            uuid,
+            extent_size,
+            alloc,
+        });
+        state.join_vg(&pvs, vg);
+        Ok(format!("Volume group \"{vg}\" successfully created"))
+    }
+
+    fn extend_vg(&self, vg: &str, pvl: &[String]) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        state.vg(vg)?;
+        let pvs = state.orphan_pvs(pvl, "vgextend")?;
+        state.join_vg(&pvs, vg);
+        Ok(format!("Volume group \"{vg}\" successfully extended"))
+    }
+
+    fn reduce_vg(&self, vg: &str, pvl: &[String]) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        state.vg(vg)?;
+        if pvl.is_empty() {
+            return Err(failed("vgreduce", "Please enter physical volume name(s)"));
+        }
+        for name in pvl {
+            let Some(pv) = state
+                .pvs
+                .iter()
+                .find(|pv| pv.name == *name && pv.vg_name == vg)
+            else {
+                return Err(failed(
+                    "vgreduce",
+                    format!("Physical volume \"{name}\" not in volume group \"{vg}\"."),
+                ));
+            };
+            if state.free_extents(pv) < pv.pe_count {
+                return Err(failed(
+                    "vgreduce",
+                    format!("Physical volume \"{name}\" still in use"),
+                ));
+            }
+        }
+        if state.pvs_in_vg(vg).all(|pv| pvl.contains(&pv.name)) {
+            return Err(failed(
+                "vgreduce",
+                format!(
+                    "Can't remove final physical volume \"{}\" from volume group \"{vg}\"",
+                    pvl[0]
+                ),
+            ));
+        }
+
+        for pv in state.pvs.iter_mut().filter(|pv| pvl.contains(&pv.name)) {
+            pv.vg_name.clear();
+            pv.pe_count = 0;
+        }
+        Ok(format!(
+            "Removed \"{}\" from volume group \"{vg}\"",
+            pvl.join("\", \"")
+        ))
+    }
+
+    fn move_pv(&self, vg: &str, mv: &LvmPvMove) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        state.vg(vg)?;
+        let src = &mv.src_pv;
+        let Some(src_pv) = state
+            .pvs_in_vg(vg)
+            .find(|pv| pv.name == *src && !pv.missing)
+        else {
+            return Err(failed(
+                "pvmove",
+                format!("Physical Volume \"{src}\" not found in Volume Group \"{vg}\"."),
+            ));
+        };
+        if let Some(running) = &state.pvmove {
+            return Err(failed(
+                "pvmove",
+                format!("Detected pvmove in progress for {}", running.src),
+            ));
+        }
+        let (first, last) = mv
+            .pe_range
+            .unwrap_or((0, src_pv.pe_count.saturating_sub(1)));
+        if first > last || last >= src_pv.pe_count {
+            return Err(failed(
+                "pvmove",
+                format!("PE range error: start extent {first} to end extent {last}"),
+            ));
+        }
+        if !mv.lv_name.is_empty()
+            && !state
+                .lvs
+                .iter()
+                .any(|lv| lv.vg_name == vg && lv.lv_name == mv.lv_name)
+        {
+            return Err(failed(
+                "pvmove",
+                format!("Logical volume {} not found.", mv.lv_name),
+            ));
+        }
+
+        // The lv or its sub lvs, in the order their extents are on the pv.
+        let mut moves: Vec<(String, u64, u64)> = state
+            .lvs
+            .iter()
+            .filter(|lv| lv.vg_name == vg)
+            .filter(|lv| {
+                mv.lv_name.is_empty() || lv.lv_name == mv.lv_name || lv.parent_lv == mv.lv_name
+            })
+            .flat_map(|lv| {
+                lv.lv_segs
+                    .iter()
+                    .filter(|seg| seg.pvdev == *src)
+                    .filter_map(|seg| {
+                        let start = seg.pv_start_pe.max(first);
+                        let end = (seg.pv_start_pe + seg.size_pe).min(last + 1);
+                        (start < end).then(|| (lv.lv_name.clone(), start, end - start))
+                    })
+            })
+            .collect();
+        moves.sort_by_key(|(_, start, _)| *start);
+        if moves.is_empty() {
+            return Err(failed("pvmove", format!("No data to move for {vg}.")));
+        }
+
+        let dests: Vec<&FakePv> = if mv.dest_pvs.is_empty() {
+            state
+                .pvs_in_vg(vg)
+                .filter(|pv| pv.name != *src && !pv.missing)
+                .collect()
+        } else {
+            let mut dests = Vec::new();
+            for name in &mv.dest_pvs {
+                let Some(pv) = state
+                    .pvs_in_vg(vg)
+                    .find(|pv| pv.name == *name && !pv.missing)
+                else {
+                    return Err(failed(
+                        "pvmove",
+                        format!("Physical Volume \"{name}\" not found in Volume Group \"{vg}\"."),
+                    ));
+                };
+                if pv.name == *src {
+                    return Err(failed(
+                        "pvmove",
+                        format!("Source and destination physical volume {src} are the same."),
+                    ));
+                }
+                dests.push(pv);
+            }
+            dests
+        };
+        let extents: u64 = moves.iter().map(|(_, _, count)| count).sum();
+        let Some(dest) = state.alloc(&dests, extents) else {
+            let free: u64 = dests.iter().map(|pv| state.free_extents(pv)).sum();
+            return Err(failed(
+                "pvmove",
+                format!(
+                    "Insufficient free space: {extents} extents needed, but only {free} available"
+                ),
+            ));
+        };
+
+        // Keeps the destination extents while copying.
+        state.add_sub_lv(vg, "", PVMOVE_LV, "p-C-ao----", dest.clone());
+        let item = state.lvs.last_mut().unwrap();
+        item.segtype = "mirror".to_string();
+        item.move_pv = src.clone();
+        item.copy_percent = Some(0.0);
+        state.pvmove = Some(FakePvMove {
+            vg: vg.to_string(),
+            src: src.clone(),
+            moves,
+            dest,
+        });
+        Ok(format!("{src}: Moved: 0.00%"))
+    }
+
+    fn abort_pvmove(&self) -> Result<String, LvmError> {
+        let mut state = self.state.lock().unwrap();
+        state.end_pvmove(false);
+        Ok(String::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::lvm::{
+        AllocPolicy, LvmBackend, LvmCache, LvmCacheType, LvmConvert, LvmExtraArg, LvmPvCreate,
+        LvmPvMove, LvmResize, LvmSnapshot, LvmSyncAction, LvmVgCreate, fake::FakeBackend,
+    };
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    fn arg(opt: &str, value: &str) -> LvmExtraArg {
+        LvmExtraArg {
+            opt: opt.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_fake_linear() {
+        let fake = FakeBackend::new();
+        fake.add_vg("vg01", &[("/dev/sda1", 10 * GB), ("/dev/sdb1", 10 * GB)]);
+        fake.add_pv("/dev/sdc", 10 * GB);
+
+        assert_eq!(fake.get_vgs().unwrap(), vec!["vg01"]);
+        assert_eq!(fake.get_pvs().unwrap().len(), 3);
+        let vg = fake.get_vg_info("vg01").unwrap();
+        assert_eq!(vg.size, 20 * GB);
+        assert_eq!(vg.free, 20 * GB);
+        assert_eq!(vg.pv_count, 2);
+
+        // Spans both pvs
+        fake.create_lv("lvdata", "vg01", 15, "G", "linear", &[], &[])
+            .unwrap();
+        let vg = fake.get_vg_info("vg01").unwrap();
+        assert_eq!(vg.free, 5 * GB);
+        let lvs = fake.get_lvs().unwrap();
+        assert_eq!(lvs.len(), 1);
+        assert_eq!(lvs[0].size, 15 * GB);
+        assert_eq!(lvs[0].lv_segs.len(), 2);
+        assert_eq!(lvs[0].lv_segs[0].pvdev, "/dev/sda1");
+        assert_eq!(lvs[0].lv_segs[0].size_pe, 2560);
+        assert_eq!(lvs[0].lv_segs[1].pvdev, "/dev/sdb1");
+        assert_eq!(lvs[0].lv_segs[1].pv_start_pe, 0);
+        assert_eq!(lvs[0].lv_segs[1].size_pe, 1280);
+
+        // Rest of the vg in %
+        fake.create_lv("lvrest", "vg01", 100, "%FREE", "linear", &[], &[])
+            .unwrap();
+        assert_eq!(fake.get_vg_info("vg01").unwrap().free, 0);
+
+        // Full, duplicate name, unknown vg
+        assert!(
+            fake.create_lv("lvmore", "vg01", 1, "G", "linear", &[], &[])
+                .is_err()
+        );
+        assert!(
+            fake.create_lv("lvdata", "vg01", 0, "G", "linear", &[], &[])
+                .is_err()
+        );
+        assert!(
+            fake.create_lv("lvdata", "vg02", 1, "G", "linear", &[], &[])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_fake_resize() {
+        let fake = FakeBackend::new();
+        fake.add_vg("vg01", &[("/dev/sda1", 10 * GB), ("/dev/sdb1", 10 * GB)]);
+        fake.create_lv("lv1", "vg01", 8, "G", "linear", &[], &[])
+            .unwrap();
+        fake.create_lv("lv2", "vg01", 1, "G", "linear", &[], &[])
+            .unwrap();
+        let resize = |sign: Option<char>, size: u64, unit: &str| LvmResize {
+            sign,
+            size,
+            size_unit: unit.to_string(),
+            resizefs: false,
+        };
+
+        // After lv2, on both pvs
+        fake.resize_lv("vg01", "lv1", &resize(Some('+'), 4, "G"))
+            .unwrap();
+        let lvs = fake.get_lvs().unwrap();
+        assert_eq!(lvs[0].size, 12 * GB);
+        assert_eq!(lvs[0].lv_segs.len(), 3);
+        assert_eq!(lvs[0].lv_segs[1].pv_start_pe, 2304);
+        assert_eq!(fake.get_vg_info("vg01").unwrap().free, 7 * GB);
+
+        fake.resize_lv("vg01", "lv1", &resize(None, 6, "G"))
+            .unwrap();
+        let lvs = fake.get_lvs().unwrap();
+        assert_eq!(lvs[0].size, 6 * GB);
+        assert_eq!(lvs[0].lv_segs.len(), 1);
+        assert_eq!(lvs[0].lv_segs[0].size_pe, 1536);
+
+        fake.resize_lv("vg01", "lv2", &resize(Some('+'), 100, "%FREE"))
+            .unwrap();
+        assert_eq!(fake.get_vg_info("vg01").unwrap().free, 0);
+        assert!(
+            fake.resize_lv("vg01", "lv1", &resize(Some('+'), 1, "G"))
+                .is_err()
+        );
+        assert!(
+            fake.resize_lv("vg01", "lv1", &resize(None, 6, "G"))
+                .is_err()
+        );
+        assert!(
+            fake.resize_lv("vg01", "lv3", &resize(None, 1, "G"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_fake_raid() {
+        let fake = FakeBackend::new();
+        fake.add_vg(
+            "vg01",
+            &[
+                ("/dev/sda1", 10 * GB),
+                ("/dev/sdb1", 10 * GB),
+                ("/dev/sdc1", 10 * GB),
+            ],
+        );
+
+        // raid5, 2 data stripes + parity on 3 pvs
+        fake.create_lv(
+            "lvr5",
+            "vg01",
+            4,
+            "G",
+            "raid5",
+            &[],
+            &[arg("--stripes", "2")],
+        )
+        .unwrap();
+        let lvs = fake.get_lvs().unwrap();
+        assert_eq!(lvs.len(), 7);
+        let top = lvs.iter().find(|lv| lv.lv_name == "lvr5").unwrap();
+        assert_eq!(top.size, 4 * GB);
+        assert_eq!(top.stripes, 3);
+        assert_eq!(top.data_stripes, 2);
+        assert_eq!(top.lv_segs.len(), 3);
+        assert_eq!(top.lv_segs[0].pvdev, "[lvr5_rimage_0]");
+        let images: Vec<_> = lvs
+            .iter()
+            .filter(|lv| lv.lv_name.starts_with("[lvr5_rimage"))
+            .collect();
+        assert_eq!(images.len(), 3);
+        assert!(images.iter().all(|lv| lv.parent_lv == "lvr5"));
+        assert!(images.iter().all(|lv| lv.size == 2 * GB));
+        // each image on its own pv
+        let mut devs: Vec<&str> = images
+            .iter()
+            .map(|lv| lv.lv_segs[0].pvdev.as_str())
+            .collect();
+        devs.sort();
+        devs.dedup();
+        assert_eq!(devs.len(), 3);
+
+        // 3 * (2G + 1 extent meta)
+        let vg = fake.get_vg_info("vg01").unwrap();
+        assert_eq!(vg.free, 30 * GB - 3 * (2 * GB + 4 * 1024 * 1024));
+
+        // raid1 with 3 mirrors needs 4 pvs
+        assert!(
+            fake.create_lv(
+                "lvr1",
+                "vg01",
+                1,
+                "G",
+                "raid1",
+                &[],
+                &[arg("--mirrors", "3")]
+            )
+            .is_err()
+        );
+        // restricted to the given pvs
+        assert!(
+            fake.create_lv(
+                "lvr1",
+                "vg01",
+                1,
+                "G",
+                "raid1",
+                &["/dev/sda1".to_string()],
+                &[arg("--mirrors", "1")]
+            )
+            .is_err()
+        );
+        fake.create_lv(
+            "lvr1",
+            "vg01",
+            1,
+            "G",
+            "raid1",
+            &["/dev/sda1".to_string(), "/dev/sdc1".to_string()],
+            &[arg("--mirrors", "1")],
+        )
+        .unwrap();
+        let lvs = fake.get_lvs().unwrap();
+        let image_devs: Vec<&str> = lvs
+            .iter()
+            .filter(|lv| lv.parent_lv == "lvr1")
+            .map(|lv| lv.lv_segs[0].pvdev.as_str())
+            .collect();
+        assert_eq!(image_devs.len(), 4);
+        assert!(!image_devs.contains(&"/dev/sdb1"));
+
+        // Removed with its images, extents are free again
+        assert!(fake.remove_lv("vg01", "[lvr5_rimage_0]").is_err());
+        fake.remove_lv("vg01", "lvr5").unwrap();
+        assert!(fake.remove_lv("vg01", "lvr5").is_err());
+        let lvs = fake.get_lvs().unwrap();
+        assert_eq!(lvs.len(), 5);
+        assert!(lvs.iter().all(|lv| !lv.lv_name.contains("lvr5")));
+        let vg = fake.get_vg_info("vg01").unwrap();
+        assert_eq!(vg.free, 30 * GB - 2 * (GB + 4 * 1024 * 1024));
+
+        // Open lvs are refused
+        fake.set_open("vg01", "lvr1", true);
+        assert!(fake.remove_lv("vg01", "lvr1").is_err());
+        fake.set_open("vg01", "lvr1", false);
+        fake.remove_lv("vg01", "lvr1").unwrap();
+        assert!(fake.get_lvs().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fake_rename() {
+        let fake = FakeBackend::new();
+        fake.add_vg("vg01", &[("/dev/sda1", 10 * GB), ("/dev/sdb1", 10 * GB)]);
+        fake.add_vg("vg02", &[("/dev/sdc1", 10 * GB)]);
+        fake.create_lv(
+            "lvr1",
+            "vg01",
+            1,
+            "G",
+            "raid1",
+            &["/dev/sda1".to_string(), "/dev/sdb1".to_string()],
+            &[arg("--mirrors", "1")],
+        )
+        .unwrap();
+        fake.create_lv("lvdata", "vg01", 1, "G", "linear", &[], &[])
+            .unwrap();
+
+        // Conflicts, internal lvs and reserved names are refused
+        assert!(fake.rename_lv("vg01", "lvr1", "lvdata").is_err());
+        assert!(fake.rename_lv("vg01", "[lvr1_rimage_0]", "x").is_err());
+        assert!(fake.rename_lv("vg01", "lvr1", "x_rimage").is_err());
+        assert!(fake.rename_lv("vg01", "nolv", "x").is_err());
+
+        // Sub lvs follow
+        fake.rename_lv("vg01", "lvr1", "lvmirror").unwrap();
+        let lvs = fake.get_lvs().unwrap();
+        assert!(lvs.iter().all(|lv| !lv.lv_name.contains("lvr1")));
+        let top = lvs.iter().find(|lv| lv.lv_name == "lvmirror").unwrap();
+        assert_eq!(top.path, "/dev/vg01/lvmirror");
+        assert!(
+            top.lv_segs
+                .iter()
+                .all(|seg| seg.pvdev.starts_with("[lvmirror_"))
+        );
+        assert_eq!(
+            lvs.iter().filter(|lv| lv.parent_lv == "lvmirror").count(),
+            4
+        );
+
+        // vg, pvs and lvs
+        assert!(fake.rename_vg("vg01", "vg02").is_err());
+        assert!(fake.rename_vg("vg01", "-vg").is_err());
+        fake.rename_vg("vg01", "vg-fast").unwrap();
+        assert!(fake.get_vg_info("vg01").is_err());
+        assert_eq!(fake.get_vg_info("vg-fast").unwrap().pv_count, 2);
+        let lvs = fake.get_lvs().unwrap();
+        let lvdata = lvs.iter().find(|lv| lv.lv_name == "lvdata").unwrap();
+        assert_eq!(lvdata.vg_name, "vg-fast");
+        assert_eq!(lvdata.dm_path, "/dev/mapper/vg--fast-lvdata");
+    }
+
+    #[test]
+    fn test_fake_snapshot() {
+        let fake = FakeBackend::new();
+        fake.add_vg("vg01", &[("/dev/sda1", 10 * GB)]);
+        fake.create_lv("lvdata", "vg01", 4, "G", "linear", &[], &[])
+            .unwrap();
+        let snap = |name: &str, size: u64, unit: &str| LvmSnapshot {
+            name: name.to_string(),
+            size,
+            size_unit: unit.to_string(),
+        };
+        let lv = |fake: &FakeBackend, name: &str| {
+            fake.get_lvs()
+                .unwrap()
+                .into_iter()
+                .find(|lv| lv.lv_name == name)
+        };
+
+        fake.create_snapshot("vg01", "lvdata", &snap("snap1", 25, "%ORIGIN"))
+            .unwrap();
+        fake.create_snapshot("vg01", "lvdata", &snap("snap2", 1, "G"))
+            .unwrap();
+        let snap1 = lv(&fake, "snap1").unwrap();
+        assert_eq!(snap1.size, GB);
+        assert_eq!(snap1.origin, "lvdata");
+        assert_eq!(snap1.snap_percent, Some(0.0));
+        assert!(snap1.attr.starts_with('s'));
+        assert!(lv(&fake, "lvdata").unwrap().attr.starts_with('o'));
+        assert_eq!(fake.get_vg_info("vg01").unwrap().free, 4 * GB);
+
+        // No snapshots of snapshots, taken names or more than is free
+        assert!(
+            fake.create_snapshot("vg01", "snap1", &snap("s", 1, "G"))
+                .is_err()
+        );
+        assert!(
+            fake.create_snapshot("vg01", "lvdata", &snap("snap1", 1, "G"))
+                .is_err()
+        );
+        assert!(
+            fake.create_snapshot("vg01", "lvdata", &snap("big", 5, "G"))
+                .is_err()
+        );
+
+        // Invalid ones are not merged
+        fake.set_snap_percent("vg01", "snap2", 100.0);
+        assert!(lv(&fake, "snap2").unwrap().attr.chars().nth(4) == Some('I'));
+        assert!(fake.merge_snapshot("vg01", "snap2").is_err());
+        assert!(fake.merge_snapshot("vg01", "lvdata").is_err());
+
+        // Delayed while the origin is open
+        fake.set_open("vg01", "lvdata", true);
+        fake.merge_snapshot("vg01", "snap1").unwrap();
+        assert!(lv(&fake, "snap1").unwrap().attr.starts_with('S'));
+        assert!(lv(&fake, "lvdata").unwrap().attr.starts_with('O'));
+        fake.set_open("vg01", "lvdata", false);
+        fake.merge_snapshot("vg01", "snap1").unwrap();
+        assert!(lv(&fake, "snap1").is_none());
+
+        // Last snapshot gone, a plain lv again
+        fake.remove_lv("vg01", "snap2").unwrap();
+        assert!(lv(&fake, "lvdata").unwrap().attr.starts_with('-'));
+        assert_eq!(fake.get_vg_info("vg01").unwrap().free, 6 * GB);
+    }
+
+    #[test]
+    fn test_fake_thin() {
+        let fake = FakeBackend::new();
+        fake.add_vg("vg01", &[("/dev/sda1", 10 * GB)]);
+        let arg = |opt: &str, value: &str| LvmExtraArg {
+            opt: opt.to_string(),
+            value: value.to_string(),
+        };
+        let lv = |fake: &FakeBackend, name: &str| {
+            fake.get_lvs()
+                .unwrap()
+                .into_iter()
+                .find(|lv| lv.lv_name == name)
+        };
+
+        // 4G data and 8M metadata next to it
+        let meta = [arg("--poolmetadatasize", "8M")];
+        fake.create_lv("pool0", "vg01", 4, "G", "thin-pool", &[], &meta)
+            .unwrap();
+        let pool = lv(&fake, "pool0").unwrap();
+        assert_eq!(pool.size, 4 * GB);
+        assert!(pool.attr.starts_with('t'));
+        assert_eq!(pool.data_percent, Some(0.0));
+        assert_eq!(pool.lv_segs[0].pvdev, "[pool0_tdata]");
+        let tmeta = lv(&fake, "[pool0_tmeta]").unwrap();
+        assert_eq!(tmeta.size, 8 * 1024 * 1024);
+        assert_eq!(tmeta.parent_lv, "pool0");
+        assert_eq!(tmeta.lv_segs[0].pv_start_pe, 1024);
+        let free = 6 * GB - 8 * 1024 * 1024;
+        assert_eq!(fake.get_vg_info("vg01").unwrap().free, free);
+
+        // Thin lvs take no space, may be more than the pool
+        let thinpool = [arg("--thinpool", "pool0")];
+        fake.create_lv("thin1", "vg01", 3, "G", "thin", &[], &thinpool)
+            .unwrap();
+        fake.create_lv("thin2", "vg01", 3, "G", "thin", &[], &thinpool)
+            .unwrap();
+        let thin1 = lv(&fake, "thin1").unwrap();
+        assert_eq!(thin1.pool_lv, "pool0");
+        assert_eq!(thin1.segtype, "thin");
+        assert!(thin1.attr.starts_with('V'));
+        assert_eq!(fake.get_vg_info("vg01").unwrap().free, free);
+        let lvs = fake.get_lvs().unwrap();
+        assert_eq!(crate::lvm::thin_provisioning(&pool, &lvs), Some(1.5));
+
+        // Pool has to exist, virtual size is not a percentage
+        let nopool = [arg("--thinpool", "pool9")];
+        assert!(
+            fake.create_lv("thin3", "vg01", 1, "G", "thin", &[], &nopool)
+                .is_err()
+        );
+        assert!(
+            fake.create_lv("thin3", "vg01", 10, "%FREE", "thin", &[], &thinpool)
+                .is_err()
+        );
+
+        // Full pool
+        fake.set_data_percent("vg01", "pool0", 100.0, Some(40.0));
+        let pool = lv(&fake, "pool0").unwrap();
+        assert_eq!(pool.metadata_percent, Some(40.0));
+        assert_eq!(pool.attr.chars().nth(8), Some('D'));
+
+        // Renamed along, removed with the pool
+        fake.rename_lv("vg01", "pool0", "pool1").unwrap();
+        assert_eq!(lv(&fake, "thin2").unwrap().pool_lv, "pool1");
+        assert!(lv(&fake, "[pool1_tdata]").is_some());
+        fake.remove_lv("vg01", "pool1").unwrap();
+        assert!(fake.get_lvs().unwrap().is_empty());
+        assert_eq!(fake.get_vg_info("vg01").unwrap().free, 10 * GB);
+    }
+    #[test]
+    fn test_fake_cache() {
+        let fake = FakeBackend::new();
+        fake.add_vg("vg01", &[("/dev/sda1", 10 * GB), ("/dev/sdb1", 2 * GB)]);
+        fake.create_lv(
+            "lvhdd",
+            "vg01",
+            4,
+            "G",
+            "linear",
+            &["/dev/sda1".to_string()],
+            &[],
+        )
+        .unwrap();
+        let lv = |fake: &FakeBackend, name: &str| {
+            fake.get_lvs()
+                .unwrap()
+                .into_iter()
+                .find(|lv| lv.lv_name == name)
+        };
+        let mut cache = LvmCache {
+            name: "fast".to_string(),
+            cache_type: LvmCacheType::CachePool,
+            fast_pv: "/dev/sdb1".to_string(),
+            size: 1,
+            size_unit: "G".to_string(),
+            cache_mode: "writeback".to_string(),
+            cache_policy: String::new(),
+        };
+
+        // Extents move to [lvhdd_corig], the pool is hidden below lvhdd
+        fake.attach_cache("vg01", "lvhdd", &cache).unwrap();
+        let lvhdd = lv(&fake, "lvhdd").unwrap();
+        assert_eq!(lvhdd.segtype, "cache");
+        assert_eq!(lvhdd.pool_lv, "[fast_cpool]");
+        assert_eq!(lvhdd.cache_mode, "writeback");
+        assert_eq!(lvhdd.cache_policy, "smq");
+        assert_eq!(lvhdd.cache_total_blocks, Some(GB / (64 * 1024)));
+        assert_eq!(lvhdd.lv_segs[0].pvdev, "[lvhdd_corig]");
+        let corig = lv(&fake, "[lvhdd_corig]").unwrap();
+        assert_eq!(corig.parent_lv, "lvhdd");
+        assert_eq!(corig.lv_segs[0].pvdev, "/dev/sda1");
+        let cpool = lv(&fake, "[fast_cpool]").unwrap();
+        assert_eq!(cpool.parent_lv, "lvhdd");
+        assert_eq!(cpool.lv_segs[0].pvdev, "[fast_cpool_cdata]");
+        let cdata = lv(&fake, "[fast_cpool_cdata]").unwrap();
+        assert_eq!(cdata.parent_lv, "fast_cpool");
+        assert_eq!(cdata.lv_segs[0].pvdev, "/dev/sdb1");
+        assert!(lv(&fake, "fast").is_none());
+
+        fake.set_cache_stats("vg01", "lvhdd", 50.0, 90.0, 12);
+        let lvhdd = lv(&fake, "lvhdd").unwrap();
+        assert_eq!(lvhdd.cache_used_blocks, Some(GB / (64 * 1024) / 2));
+        assert_eq!(lvhdd.cache_read_hits, Some(90000));
+        assert_eq!(lvhdd.cache_dirty_blocks, Some(12));
+
+        // Only once, only linear lvs
+        assert!(fake.attach_cache("vg01", "lvhdd", &cache).is_err());
+        assert!(fake.attach_cache("vg01", "[lvhdd_corig]", &cache).is_err());
+
+        // Split, the pool is visible again
+        fake.detach_cache("vg01", "lvhdd", true).unwrap();
+        let lvhdd = lv(&fake, "lvhdd").unwrap();
+        assert_eq!(lvhdd.segtype, "linear");
+        assert_eq!(lvhdd.pool_lv, "");
+        assert_eq!(lvhdd.cache_total_blocks, None);
+        assert_eq!(lvhdd.lv_segs[0].pvdev, "/dev/sda1");
+        assert!(lv(&fake, "[lvhdd_corig]").is_none());
+        let fast = lv(&fake, "fast").unwrap();
+        assert_eq!(fast.segtype, "cache-pool");
+        assert_eq!(fast.path, "/dev/vg01/fast");
+        assert_eq!(fast.lv_segs[0].pvdev, "[fast_cdata]");
+        assert!(lv(&fake, "[fast_cdata]").is_some());
+        assert!(fake.detach_cache("vg01", "lvhdd", true).is_err());
+        fake.remove_lv("vg01", "fast").unwrap();
+
+        // Writecache, removed with uncache
+        cache.name = "wc".to_string();
+        cache.cache_type = LvmCacheType::Writecache;
+        fake.attach_cache("vg01", "lvhdd", &cache).unwrap();
+        let lvhdd = lv(&fake, "lvhdd").unwrap();
+        assert_eq!(lvhdd.segtype, "writecache");
+        assert_eq!(lvhdd.pool_lv, "[wc_cvol]");
+        assert_eq!(lvhdd.writecache_free_blocks, Some(GB / 4096));
+        assert_eq!(lvhdd.lv_segs[0].pvdev, "[lvhdd_wcorig]");
+        fake.detach_cache("vg01", "lvhdd", false).unwrap();
+        let lvs = fake.get_lvs().unwrap();
+        assert_eq!(lvs.len(), 1);
+        assert_eq!(fake.get_vg_info("vg01").unwrap().free, 8 * GB);
+    }
+
+    #[test]
+    fn test_fake_raid_health() {
+        let fake = FakeBackend::new();
+        fake.add_vg(
+            "vg01",
+            &[
+                ("/dev/sda1", 10 * GB),
+                ("/dev/sdb1", 10 * GB),
+                ("/dev/sdc1", 10 * GB),
+                ("/dev/sdd1", 10 * GB),
+            ],
+        );
+        let pvs = ["/dev/sda1".to_string(), "/dev/sdb1".to_string()];
+        let mirrors = [arg("--mirrors", "1")];
+        fake.create_lv("lvr1", "vg01", 1, "G", "raid1", &pvs, &mirrors)
+            .unwrap();
+        fake.create_lv("lvlin", "vg01", 1, "G", "linear", &[], &[])
+            .unwrap();
+        let lv = |fake: &FakeBackend, name: &str| {
+            fake.get_lvs()
+                .unwrap()
+                .into_iter()
+                .find(|lv| lv.lv_name == name)
+                .unwrap()
+        };
+        let image_pv = |fake: &FakeBackend, name: &str| lv(fake, name).lv_segs[0].pvdev.clone();
+        let lvr1 = lv(&fake, "lvr1");
+        assert_eq!(lvr1.raid_sync_action, "idle");
+        assert_eq!(lvr1.raid_mismatch_count, Some(0));
+        assert_eq!(lv(&fake, "lvlin").raid_mismatch_count, None);
+
+        // Check progresses each read and finds mismatches, repair fixes them
+        assert!(
+            fake.raid_sync_action("vg01", "lvlin", LvmSyncAction::Check)
+                .is_err()
+        );
+        fake.raid_sync_action("vg01", "lvr1", LvmSyncAction::Check)
+            .unwrap();
+        assert!(
+            fake.raid_sync_action("vg01", "lvr1", LvmSyncAction::Repair)
+                .is_err()
+        );
+        let lvr1 = lv(&fake, "lvr1");
+        assert_eq!(lvr1.raid_sync_action, "check");
+        assert_eq!(lvr1.sync_percent, Some(25.0));
+        for _ in 0..3 {
+            fake.get_lvs().unwrap();
+        }
+        assert_eq!(lv(&fake, "lvr1").raid_sync_action, "idle");
+        fake.set_raid_mismatches("vg01", "lvr1", 8);
+        let lvr1 = lv(&fake, "lvr1");
+        assert_eq!(lvr1.attr, "rwi-a-r-m-");
+        assert_eq!(lvr1.health_status, "mismatches exist");
+        fake.raid_sync_action("vg01", "lvr1", LvmSyncAction::Repair)
+            .unwrap();
+        for _ in 0..4 {
+            fake.get_lvs().unwrap();
+        }
+        let lvr1 = lv(&fake, "lvr1");
+        assert_eq!(lvr1.raid_sync_action, "idle");
+        assert_eq!(lvr1.raid_mismatch_count, Some(0));
+        assert_eq!(lvr1.health_status, "");
+
+        // Nothing to repair while healthy, replace moves one image
+        assert!(fake.repair_raid("vg01", "lvr1", &[]).is_err());
+        assert!(
+            fake.replace_raid_pv("vg01", "lvr1", "/dev/sdc1", &[])
+                .is_err()
+        );
+        fake.replace_raid_pv("vg01", "lvr1", "/dev/sdb1", &["/dev/sdc1".to_string()])
+            .unwrap();
+        // Not while resyncing, every read moves the resync on
+        assert_eq!(lv(&fake, "lvr1").raid_sync_action, "recover");
+        assert!(
+            fake.replace_raid_pv("vg01", "lvr1", "/dev/sda1", &[])
+                .is_err()
+        );
+        assert_eq!(image_pv(&fake, "[lvr1_rimage_1]"), "/dev/sdc1");
+        assert_eq!(image_pv(&fake, "[lvr1_rmeta_1]"), "/dev/sdc1");
+        assert_eq!(image_pv(&fake, "[lvr1_rimage_0]"), "/dev/sda1");
+        for _ in 0..4 {
+            fake.get_lvs().unwrap();
+        }
+
+        // A failed pv leaves the raid partial until repaired
+        fake.fail_pv("/dev/sda1");
+        let lvr1 = lv(&fake, "lvr1");
+        assert_eq!(lvr1.attr, "rwi-a-r-p-");
+        assert_eq!(lvr1.health_status, "partial");
+        assert_eq!(lv(&fake, "[lvr1_rimage_0]").health_status, "partial");
+        let pvs = fake.get_pvs().unwrap();
+        let sda1 = pvs.iter().find(|pv| pv.pv_name == "/dev/sda1").unwrap();
+        assert_eq!(sda1.pv_attr, "a-m");
+        assert_eq!(sda1.major, None);
+        assert!(
+            fake.raid_sync_action("vg01", "lvr1", LvmSyncAction::Check)
+                .is_err()
+        );
+        // Only sdc1 left, it holds the other image
+        assert!(
+            fake.repair_raid("vg01", "lvr1", &["/dev/sdc1".to_string()])
+                .is_err()
+        );
+        fake.repair_raid("vg01", "lvr1", &[]).unwrap();
+        let lvr1 = lv(&fake, "lvr1");
+        assert_eq!(lvr1.attr, "rwi-a-r---");
+        assert_eq!(lvr1.health_status, "");
+        assert_eq!(lvr1.raid_sync_action, "recover");
+        let new_pv = image_pv(&fake, "[lvr1_rimage_0]");
+        assert!(new_pv == "/dev/sdb1" || new_pv == "/dev/sdd1");
+        assert_eq!(lv(&fake, "[lvr1_rimage_0]").attr, "iwi-a-r---");
+    }
+
+    #[test]
+    fn test_fake_convert() {
+        let fake = FakeBackend::new();
+        fake.add_vg(
+            "vg01",
+            &[
+                ("/dev/sda1", 10 * GB),
+                ("/dev/sdb1", 10 * GB),
+                ("/dev/sdc1", 10 * GB),
+            ],
+        );
+        let sda1 = ["/dev/sda1".to_string()];
+        fake.create_lv("lvlin", "vg01", 2, "G", "linear", &sda1, &[])
+            .unwrap();
+        let lv = |name: &str| {
+            fake.get_lvs()
+                .unwrap()
+                .into_iter()
+                .find(|lv| lv.lv_name == name)
+        };
+        let convert = |segtype: &str, mirrors: Option<u64>| LvmConvert {
+            segtype: segtype.to_string(),
+            mirrors,
+            stripes: None,
+        };
+        let used = |fake: &FakeBackend| -> u64 {
+            fake.get_pvs()
+                .unwrap()
+                .iter()
+                .map(|pv| pv.pe_alloc_count)
+                .sum()
+        };
+        let extents = 2 * GB / (4 * 1024 * 1024);
+
+        // Not a takeover lvconvert knows, and no pv left for a third image.
+        assert!(
+            fake.convert_lv("vg01", "lvlin", &convert("raid5", None), &[])
+                .is_err()
+        );
+        let e = fake
+            .convert_lv("vg01", "lvlin", &convert("raid1", Some(3)), &[])
+            .unwrap_err();
+        assert!(e.to_string().contains("more PVs needed"));
+        assert!(lv("[lvlin_rimage_0]").is_none());
+
+        // Linear becomes image 0, the new image on sdc1 resyncs
+        let sdc1 = ["/dev/sdc1".to_string()];
+        fake.convert_lv("vg01", "lvlin", &convert("raid1", Some(1)), &sdc1)
+            .unwrap();
+        // Not while resyncing, every read moves the resync on
+        assert!(
+            fake.convert_lv("vg01", "lvlin", &convert("linear", None), &[])
+                .is_err()
+        );
+        assert_eq!(used(&fake), 2 * extents + 2);
+        let lvlin = lv("lvlin").unwrap();
+        assert_eq!(lvlin.segtype, "raid1");
+        assert_eq!(lvlin.attr, "rwi-a-r---");
+        assert_eq!(lvlin.stripes, 2);
+        assert_eq!(lvlin.lv_segs[1].pvdev, "[lvlin_rimage_1]");
+        assert_eq!(lvlin.raid_sync_action, "recover");
+        assert_eq!(
+            lv("[lvlin_rimage_0]").unwrap().lv_segs[0].pvdev,
+            "/dev/sda1"
+        );
+        assert_eq!(lv("[lvlin_rmeta_0]").unwrap().lv_segs[0].pvdev, "/dev/sda1");
+        assert_eq!(
+            lv("[lvlin_rimage_1]").unwrap().lv_segs[0].pvdev,
+            "/dev/sdc1"
+        );
+        for _ in 0..4 {
+            fake.get_lvs().unwrap();
+        }
+
+        // Two way raid1 to raid5 keeps the images
+        fake.convert_lv("vg01", "lvlin", &convert("raid5", None), &[])
+            .unwrap();
+        let lvlin = lv("lvlin").unwrap();
+        assert_eq!((lvlin.segtype.as_str(), lvlin.stripes), ("raid5", 2));
+        assert_eq!(lvlin.raid_sync_action, "idle");
+        fake.convert_lv("vg01", "lvlin", &convert("raid1", None), &[])
+            .unwrap();
+
+        // And back, image 0 is the linear lv again
+        fake.convert_lv("vg01", "lvlin", &convert("linear", None), &[])
+            .unwrap();
+        let lvlin = lv("lvlin").unwrap();
+        assert_eq!(lvlin.attr, "-wi-a-----");
+        assert_eq!(lvlin.lv_segs[0].pvdev, "/dev/sda1");
+        assert_eq!(lvlin.sync_percent, None);
+        assert_eq!(lvlin.raid_mismatch_count, None);
+        assert_eq!(used(&fake), extents);
+        assert!(
+            fake.get_lvs()
+                .unwrap()
+                .iter()
+                .all(|lv| lv.parent_lv.is_empty())
+        );
+
+        // raid5 of 2 stripes grows with a third stripe on the fourth pv
+        fake.add_vg(
+            "vg02",
+            &[
+                ("/dev/sde1", 10 * GB),
+                ("/dev/sdf1", 10 * GB),
+                ("/dev/sdg1", 10 * GB),
+                ("/dev/sdh1", 10 * GB),
+            ],
+        );
+        let stripes = [arg("--stripes", "2")];
+        fake.create_lv("lvr5", "vg02", 2, "G", "raid5", &[], &stripes)
+            .unwrap();
+        let mut reshape = convert("raid5", None);
+        reshape.stripes = Some(1);
+        assert!(fake.convert_lv("vg02", "lvr5", &reshape, &[]).is_err());
+        reshape.stripes = Some(3);
+        fake.convert_lv("vg02", "lvr5", &reshape, &[]).unwrap();
+        let lvr5 = lv("lvr5").unwrap();
+        assert_eq!((lvr5.stripes, lvr5.data_stripes), (4, 3));
+        assert_eq!(lvr5.size, 3 * GB);
+        assert_eq!(lvr5.raid_sync_action, "reshape");
+    }
+
+    #[test]
+    fn test_fake_vg_create() {
+        let fake = FakeBackend::new();
+        fake.add_vg("vg01", &[("/dev/sda1", 10 * GB)]);
+        fake.add_dev("/dev/sda1", 10 * GB, "", None);
+        fake.add_dev("/dev/sdh", 100 * GB, "WDC", None);
+        fake.add_dev("/dev/sdi", 100 * GB, "WDC", None);
+        fake.add_dev("/dev/sdj", 8 * GB, "USB", Some("vfat signature"));
+        let devs: Vec<(String, Option<String>)> = fake
+            .get_block_devs()
+            .unwrap()
+            .into_iter()
+            .map(|dev| (dev.name, dev.used_by))
+            .collect();
+        assert_eq!(devs[0].1.as_deref(), Some("LVM2_member signature"));
+        assert_eq!(devs[1], ("/dev/sdh".to_string(), None));
+
+        // pvcreate refuses used devices, pvs of a vg and bad options
+        let defaults = LvmPvCreate::default();
+        assert!(fake.create_pv("/dev/sdj", &defaults).is_err());
+        assert!(fake.create_pv("/dev/sda1", &defaults).is_err());
+        assert!(fake.create_pv("/dev/sdz", &defaults).is_err());
+        let copies = LvmPvCreate {
+            metadata_copies: Some(3),
+            data_alignment: None,
+        };
+        assert!(fake.create_pv("/dev/sdh", &copies).is_err());
+        let align = LvmPvCreate {
+            metadata_copies: None,
+            data_alignment: Some(1000),
+        };
+        assert!(fake.create_pv("/dev/sdh", &align).is_err());
+        fake.create_pv("/dev/sdh", &defaults).unwrap();
+        fake.create_pv("/dev/sdi", &defaults).unwrap();
+        let pvs = fake.get_pvs().unwrap();
+        assert_eq!(pvs.len(), 3);
+        assert!(pvs[1].vg_name.is_empty());
+        assert_eq!((pvs[1].pv_size, pvs[1].pe_count), (100 * GB, 0));
+
+        // vgcreate with cling and 32M extents
+        let mut opts = LvmVgCreate {
+            extent_size: Some(3 * 1024 * 1024),
+            alloc: AllocPolicy::Cling,
+        };
+        let sdh = ["/dev/sdh".to_string()];
+        assert!(fake.create_vg("vg02", &sdh, &opts).is_err());
+        opts.extent_size = Some(32 * 1024 * 1024);
+        assert!(fake.create_vg("vg01", &sdh, &opts).is_err());
+        assert!(fake.create_vg("vg02", &[], &opts).is_err());
+        assert!(
+            fake.create_vg("vg02", &["/dev/sda1".to_string()], &opts)
+                .is_err()
+        );
+        fake.create_vg("vg02", &sdh, &opts).unwrap();
+        let vg = fake.get_vg_info("vg02").unwrap();
+        assert_eq!(vg.attr, "wz--l-");
+        assert_eq!((vg.size, vg.free, vg.pv_count), (100 * GB, 100 * GB, 1));
+
+        // vgextend, then vgreduce of a used pv and of the final pv
+        let sdi = ["/dev/sdi".to_string()];
+        fake.extend_vg("vg02", &sdi).unwrap();
+        assert!(fake.extend_vg("vg02", &sdi).is_err());
+        assert_eq!(fake.get_vg_info("vg02").unwrap().size, 200 * GB);
+        fake.create_lv("lvdata", "vg02", 10, "G", "linear", &[], &[])
+            .unwrap();
+        assert!(fake.reduce_vg("vg02", &sdh).is_err());
+        assert!(fake.reduce_vg("vg01", &sdi).is_err());
+        fake.reduce_vg("vg02", &sdi).unwrap();
+        let vg = fake.get_vg_info("vg02").unwrap();
+        assert_eq!((vg.size, vg.pv_count), (100 * GB, 1));
+        fake.remove_lv("vg02", "lvdata").unwrap();
+        assert!(fake.reduce_vg("vg02", &sdh).is_err());
+    }
+
+    #[test]
+    fn test_fake_pvmove() {
+        let fake = FakeBackend::new();
+        fake.add_vg(
+            "vg01",
+            &[
+                ("/dev/sda", 10 * GB),
+                ("/dev/sdb", 10 * GB),
+                ("/dev/sdc", 10 * GB),
+            ],
+        );
+        fake.create_lv("lv1", "vg01", 12, "G", "linear", &[], &[])
+            .unwrap();
+        fake.create_lv("lv2", "vg01", 2, "G", "linear", &[], &[])
+            .unwrap();
+        let pvmove = |src: &str, pe_range, lv: &str, dests: &[&str]| LvmPvMove {
+            src_pv: src.to_string(),
+            pe_range,
+            lv_name: lv.to_string(),
+            dest_pvs: dests.iter().map(|d| d.to_string()).collect(),
+        };
+        let segs = |lv: &str| -> Vec<(String, u64, u64)> {
+            let lvs = fake.get_lvs().unwrap();
+            let lv = lvs.iter().find(|item| item.lv_name == lv).unwrap();
+            lv.lv_segs
+                .iter()
+                .map(|seg| (seg.pvdev.clone(), seg.pv_start_pe, seg.size_pe))
+                .collect()
+        };
+
+        // bad vg, pv, range, lv, destination and not enough space
+        assert!(
+            fake.move_pv("vg02", &pvmove("/dev/sda", None, "", &[]))
+                .is_err()
+        );
+        assert!(
+            fake.move_pv("vg01", &pvmove("/dev/sdz", None, "", &[]))
+                .is_err()
+        );
+        let range = Some((0, 5000));
+        assert!(
+            fake.move_pv("vg01", &pvmove("/dev/sda", range, "", &[]))
+                .is_err()
+        );
+        assert!(
+            fake.move_pv("vg01", &pvmove("/dev/sda", None, "lvx", &[]))
+                .is_err()
+        );
+        let same = pvmove("/dev/sda", None, "", &["/dev/sda"]);
+        assert!(fake.move_pv("vg01", &same).is_err());
+        let full = pvmove("/dev/sda", None, "", &["/dev/sdb"]);
+        assert!(fake.move_pv("vg01", &full).is_err());
+        let empty = pvmove("/dev/sdc", None, "", &[]);
+        assert!(fake.move_pv("vg01", &empty).is_err());
+
+        // 100 extents of lv1 to sdc, the copy reserves them until done
+        let part = pvmove("/dev/sda", Some((100, 199)), "lv1", &["/dev/sdc"]);
+        fake.move_pv("vg01", &part).unwrap();
+        assert!(fake.move_pv("vg01", &empty).is_err());
+        assert_eq!(
+            fake.get_vg_info("vg01").unwrap().free,
+            16 * GB - 100 * 4 * 1024 * 1024
+        );
+        let lvs = fake.get_lvs().unwrap();
+        let mv = lvs.iter().find(|lv| lv.lv_name == "[pvmove0]").unwrap();
+        assert_eq!(mv.move_pv, "/dev/sda");
+        assert_eq!(mv.copy_percent, Some(25.0));
+        fake.get_lvs().unwrap();
+        fake.get_lvs().unwrap();
+        assert_eq!(
+            segs("lv1"),
+            [
+                ("/dev/sda".to_string(), 0, 100),
+                ("/dev/sdc".to_string(), 0, 100),
+                ("/dev/sda".to_string(), 200, 2360),
+                ("/dev/sdb".to_string(), 0, 512),
+            ]
+        );
+        let lvs = fake.get_lvs().unwrap();
+        assert!(lvs.iter().all(|lv| lv.move_pv.is_empty()));
+        assert_eq!(fake.get_vg_info("vg01").unwrap().free, 16 * GB);
+
+        // lvm picks the destinations, abort leaves lv2 where it was
+        let lv2 = segs("lv2");
+        fake.move_pv("vg01", &pvmove("/dev/sdb", None, "lv2", &[]))
+            .unwrap();
+        fake.abort_pvmove().unwrap();
+        assert_eq!(segs("lv2"), lv2);
+        assert_eq!(fake.get_vg_info("vg01").unwrap().free, 16 * GB);
+        fake.abort_pvmove().unwrap();
+    }
+}
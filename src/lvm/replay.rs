@@ -0,0 +1,341 @@
+//
+// Browse lvm of another machine from its captured reports, e.g. attached to a
+// support case (--from-dir). Nothing is run and nothing needs root, changes are
+// refused.
+//
+// The directory holds one report each of vgs, pvs and lvs, named vgs, pvs and
+// lvs (.json or .txt optional), as
+//
+//   vgs --reportformat json_std --units b --nosuffix -a -o <VGS_FIELDS>
+//
+// writes them, likewise pvs and lvs. Plain --reportformat json and sizes in
+// other units are read too, fields missing from a report are left empty.
+// A capture bundle (capture) has them all.
+//
+// Reports that are not JSON are read as plain ones, the way lvm-tui read
+// them before json_std:
+//
+//   vgs --reportformat basic --headings none --separator , --units b -o <BASIC_VGS_FIELDS>
+//
+//
+// The files are read again on every refresh.
+//
+
+use std::path::{Path, PathBuf};
+
+use crate::lvm::{
+    BASIC_LVS_FIELDS, BASIC_PVS_FIELDS, BASIC_VGS_FIELDS, LvmBackend, LvmCache, LvmConvert,
+    LvmError, LvmExtraArg, LvmLvData, LvmPVData, LvmPvCreate, LvmPvMove, LvmResize, LvmSnapshot,
+    LvmSyncAction, LvmVgCreate, LvmVgData, basic_report_json, devinfo::BlockDev, parse_lvso,
+    parse_pvso, parse_vgso_full,
+};
+
+// Extensions the reports may have, in the order looked for.
+const EXTENSIONS: [&str; 3] = ["", ".json", ".txt"];
+
+pub struct ReplayBackend {
+    dir: PathBuf,
+}
+
+impl ReplayBackend {
+    //
+    // Fails if a report is missing or can not be parsed, rather than showing
+    // an empty lvm.
+    //
+    pub fn open(dir: &Path) -> Result<Self, LvmError> {
+        let replay = Self {
+            dir: dir.to_path_buf(),
+        };
+        replay.read_vgs()?;
+        replay.get_pvs()?;
+        replay.get_lvs()?;
+        Ok(replay)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn report(&self, name: &str) -> Result<String, LvmError> {
+        let path = EXTENSIONS
+            .iter()
+            .map(|ext| self.dir.join(format!("{name}{ext}")))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                LvmError::Io(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("no {name} report in {}", self.dir.display()),
+                ))
+            })?;
+        std::fs::read_to_string(&path).map_err(LvmError::Io)
+    }
+
+    // The report as json_std, 'section' and 'fields' of a plain one.
+    fn report_json(&self, name: &str, section: &str, fields: &str) -> Result<String, LvmError> {
+        let s = self.report(name)?;
+        if s.trim().is_empty() || s.trim_start().starts_with('{') {
+            return Ok(s);
+        }
+        basic_report_json(&s, section, fields)
+    }
+
+    fn read_vgs(&self) -> Result<Vec<LvmVgData>, LvmError> {
+        parse_vgso_full(&self.report_json("vgs", "vg", BASIC_VGS_FIELDS)?)
+    }
+
+    fn refused(&self) -> LvmError {
+        LvmError::Unsupported(format!(
+            "read only, replaying captured lvm output from {}",
+            self.dir.display()
+        ))
+    }
+}
+
+impl LvmBackend for ReplayBackend {
+    fn get_vgs(&self) -> Result<Vec<String>, LvmError> {
+        Ok(self.read_vgs()?.into_iter().map(|vg| vg.name).collect())
+    }
+
+    fn get_vg_info(&self, vg_name: &str) -> Result<LvmVgData, LvmError> {
+        self.read_vgs()?
+            .into_iter()
+            .find(|vg| vg.name == vg_name)
+            .ok_or_else(|| LvmError::CommandFailed {
+                cmd: "vgs".to_string(),
+                code: Some(5),
+                stderr: format!("Volume group \"{vg_name}\" not found"),
+            })
+    }
+
+    fn get_pvs(&self) -> Result<Vec<LvmPVData>, LvmError> {
+        parse_pvso(&self.report_json("pvs", "pv", BASIC_PVS_FIELDS)?)
+    }
+
+    fn get_lvs(&self) -> Result<Vec<LvmLvData>, LvmError> {
+        parse_lvso(&self.report_json("lvs", "lv", BASIC_LVS_FIELDS)?)
+    }
+
+    fn create_lv(
+        &self,
+        _lv: &str,
+        _vg: &str,
+        _size: u64,
+        _size_unit: &str,
+        _segtype: &str,
+        _pvl: &[String],
+        _extra: &[LvmExtraArg],
+    ) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn remove_lv(&self, _vg: &str, _lv: &str) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn resize_lv(&self, _vg: &str, _lv: &str, _resize: &LvmResize) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn rename_lv(&self, _vg: &str, _lv: &str, _new_name: &str) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn rename_vg(&self, _vg: &str, _new_name: &str) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn create_snapshot(
+        &self,
+        _vg: &str,
+        _origin: &str,
+        _snap: &LvmSnapshot,
+    ) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn merge_snapshot(&self, _vg: &str, _snap: &str) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn attach_cache(&self, _vg: &str, _lv: &str, _cache: &LvmCache) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn detach_cache(&self, _vg: &str, _lv: &str, _keep: bool) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn raid_sync_action(
+        &self,
+        _vg: &str,
+        _lv: &str,
+        _action: LvmSyncAction,
+    ) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn repair_raid(&self, _vg: &str, _lv: &str, _pvl: &[String]) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn replace_raid_pv(
+        &self,
+        _vg: &str,
+        _lv: &str,
+        _old_pv: &str,
+        _pvl: &[String],
+    ) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn convert_lv(
+        &self,
+        _vg: &str,
+        _lv: &str,
+        _convert: &LvmConvert,
+        _pvl: &[String],
+    ) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    // The block devices of this host have nothing to do with the capture.
+    fn get_block_devs(&self) -> Result<Vec<BlockDev>, LvmError> {
+        Ok(Vec::new())
+    }
+
+    fn create_pv(&self, _dev: &str, _pv: &LvmPvCreate) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn create_vg(
+        &self,
+        _vg: &str,
+        _pvl: &[String],
+        _opts: &LvmVgCreate,
+    ) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn extend_vg(&self, _vg: &str, _pvl: &[String]) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn reduce_vg(&self, _vg: &str, _pvl: &[String]) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn move_pv(&self, _vg: &str, _mv: &LvmPvMove) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn abort_pvmove(&self) -> Result<String, LvmError> {
+        Err(self.refused())
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lvm::replay::ReplayBackend;
+    use crate::lvm::{LvmBackend, LvmError};
+
+    const VGS: &str = r#"{"report": [{"vg": [
+        {"vg_name":"vgdata", "vg_size":21466447872, "vg_free":19318964224, "pv_count":2, "vg_attr":"wz--n-", "vg_uuid":"qX2a4P-Zk8b-3RJm-vF0d-Tq9w-Lb2E-nC7uYs"},
+        {"vg_name":"vgroot", "vg_size":"<99.00g", "vg_free":"0 ", "pv_count":"1", "vg_attr":"wz--n-", "vg_uuid":"Hc1x0s-2kQq-VJ5d-8rGm-ePa2-w3Zt-Ys6fBn"}
+    ]}]}"#;
+    const PVS: &str = r#"{"report": [{"pv": [
+        {"pv_name":"/dev/sdb", "vg_name":"vgdata", "pv_size":10733223936, "pv_free":8585740288, "vg_extent_size":4194304, "pv_pe_count":2559, "pv_pe_alloc_count":512, "pv_attr":"a--"},
+        {"pv_name":"/dev/sdc", "vg_name":"vgdata", "pv_size":10733223936, "pv_free":10733223936, "vg_extent_size":4194304, "pv_pe_count":2559, "pv_pe_alloc_count":0, "pv_attr":"a--"},
+        {"pv_name":"/dev/vda3", "vg_name":"vgroot", "pv_size":"<99.00g", "pv_free":"0 ", "pv_attr":"a--"}
+    ]}]}"#;
+    const LVS: &str = r#"{"report": [{"lv": [
+        {"lv_name":"lvweb", "vg_name":"vgdata", "lv_size":2147483648, "lv_attr":"-wi-ao----", "segtype":"linear", "lv_uuid":"u1", "stripes":1, "lv_parent":"", "seg_le_ranges":["/dev/sdb:0-511"], "lv_path":"/dev/vgdata/lvweb"},
+        {"lv_name":"root", "vg_name":"vgroot", "lv_size":"<99.00g", "lv_attr":"-wi-ao----", "segtype":"linear", "stripes":"1", "seg_le_ranges":"/dev/vda3:0-25343"}
+    ]}]}"#;
+
+    #[test]
+    fn test_replay() {
+        let dir = std::env::temp_dir().join(format!("lvm-tui-replay-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("vgs"), VGS).unwrap();
+        std::fs::write(dir.join("pvs.json"), PVS).unwrap();
+        assert!(ReplayBackend::open(&dir).is_err());
+        std::fs::write(dir.join("lvs.txt"), LVS).unwrap();
+        let replay = ReplayBackend::open(&dir).unwrap();
+        assert!(replay.read_only());
+
+        assert_eq!(replay.get_vgs().unwrap(), vec!["vgdata", "vgroot"]);
+        let vgroot = replay.get_vg_info("vgroot").unwrap();
+        assert_eq!(vgroot.size, 99 * 1024 * 1024 * 1024);
+        assert_eq!(vgroot.pv_count, 1);
+        assert!(replay.get_vg_info("vgnone").is_err());
+        let pvs = replay.get_pvs().unwrap();
+        assert_eq!(pvs.len(), 3);
+        assert_eq!(pvs[0].pe_size, 4 * 1024 * 1024);
+        let lvs = replay.get_lvs().unwrap();
+        assert_eq!(lvs[0].lv_segs[0].size_pe, 512);
+        assert_eq!(lvs[1].lv_segs[0].pvdev, "/dev/vda3");
+
+        // Nothing is changed
+        let e = replay.remove_lv("vgdata", "lvweb").unwrap_err();
+        assert!(matches!(e, LvmError::Unsupported(_)));
+        assert!(e.to_string().contains("read only"));
+        assert!(replay.get_block_devs().unwrap().is_empty());
+
+        // Broken reports are told about, on refresh too
+        std::fs::write(
+            dir.join("lvs.txt"),
+            "  LV   VG   Attr\n  root vgroot -wi-ao----\n",
+        )
+        .unwrap();
+        assert!(matches!(replay.get_lvs(), Err(LvmError::Parse { .. })));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_replay_basic() {
+        let dir = std::env::temp_dir().join(format!("lvm-tui-replay-basic-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("vgs.txt"),
+            "  vgdata,21466447872B,19318964224B,2,wz--n-,qX2a4P-Zk8b-3RJm-vF0d-Tq9w-Lb2E-nC7uYs\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("pvs.txt"),
+            "  /dev/sdb,vgdata\n  /dev/sdc,vgdata\n  /dev/sdd,\n  /dev/sdb2\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("lvs.txt"),
+            "  lvpub,vgdata,2147483648B,rwi-a-r---,raid1,u1,2,1,,[lvpub_rimage_0]:0-255,[lvpub_rimage_1]:0-255\n\
+             \x20 [lvpub_rimage_0],vgdata,1073741824B,iwi-aor---,linear,u2,1,1,lvpub,/dev/sdb:1-256\n",
+        )
+        .unwrap();
+        let replay = ReplayBackend::open(&dir).unwrap();
+
+        let vgdata = replay.get_vg_info("vgdata").unwrap();
+        assert_eq!(vgdata.free, 19318964224);
+        assert_eq!(vgdata.pv_count, 2);
+        let pvs = replay.get_pvs().unwrap();
+        assert_eq!(pvs.len(), 4);
+        assert_eq!(pvs[2].vg_name, "");
+        // No vg, no separator either
+        assert_eq!(pvs[3].pv_name, "/dev/sdb2");
+        assert_eq!(pvs[3].vg_name, "");
+        let lvs = replay.get_lvs().unwrap();
+        assert_eq!(lvs[0].size, 2 * 1024 * 1024 * 1024);
+        assert_eq!(lvs[0].lv_segs.len(), 2);
+        assert_eq!(lvs[0].lv_segs[1].pvdev, "[lvpub_rimage_1]");
+        assert_eq!(lvs[1].parent_lv, "lvpub");
+        assert_eq!(lvs[1].lv_segs[0].size_pe, 256);
+
+        // Columns, not ',' separated
+        std::fs::write(dir.join("vgs.txt"), "  VG     #PV #LV\n  vgdata   2   1\n").unwrap();
+        assert!(matches!(replay.get_vgs(), Err(LvmError::Parse { .. })));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,383 @@
+//
+// All of lvm as one document, for inventory tools that would otherwise scrape
+// lvs themselves: vgs with their pvs and lvs, lv segments, which lv an lv is
+// part of and attrs decoded. Sizes are in bytes.
+//
+// Written as JSON or YAML, the cli's export and 'x' in the tui.
+//
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::lvm::{AttrFlag, LvAttr, LvmBackend, LvmError, LvmLvData, LvmPVData, PvAttr, VgAttr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Yaml,
+}
+
+impl ExportFormat {
+    //
+    // YAML for .yaml and .yml, else JSON.
+    //
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => ExportFormat::Yaml,
+            _ => ExportFormat::Json,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Topology {
+    pub vgs: Vec<VgDoc>,
+    // Pvs in no vg.
+    pub orphan_pvs: Vec<PvDoc>,
+}
+
+#[derive(Serialize)]
+pub struct VgDoc {
+    pub name: String,
+    pub uuid: String,
+    pub size: u64,
+    pub free: u64,
+    pub extent_size: u64,
+    pub attr: String,
+    pub attr_decoded: VgAttrDoc,
+    pub pvs: Vec<PvDoc>,
+    pub lvs: Vec<LvDoc>,
+}
+
+#[derive(Serialize)]
+pub struct VgAttrDoc {
+    pub writeable: bool,
+    pub resizeable: bool,
+    pub exported: bool,
+    pub partial: bool,
+    pub allocation: Option<&'static str>,
+    pub clustered: bool,
+    pub shared: bool,
+}
+
+#[derive(Serialize)]
+pub struct PvDoc {
+    pub name: String,
+    pub uuid: String,
+    pub size: u64,
+    pub free: u64,
+    pub pe_start: u64,
+    pub pe_count: u64,
+    pub pe_alloc_count: u64,
+    pub attr: String,
+    pub attr_decoded: PvAttrDoc,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub major: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minor: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct PvAttrDoc {
+    pub allocatable: bool,
+    pub duplicate: bool,
+    pub used: bool,
+    pub exported: bool,
+    pub missing: bool,
+}
+
+#[derive(Serialize)]
+pub struct LvDoc {
+    pub name: String,
+    pub uuid: String,
+    pub size: u64,
+    pub segtype: String,
+    pub attr: String,
+    pub attr_decoded: LvAttrDoc,
+    // Not hidden, has a /dev path.
+    pub visible: bool,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub path: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub dm_path: String,
+    // The lv this one is part of, e.g. the raid of an image, and its parts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool: Option<String>,
+    pub stripes: u16,
+    pub segments: Vec<SegDoc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync_percent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snap_percent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_percent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_percent: Option<f64>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub health_status: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub cache_mode: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub time: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub host: String,
+}
+
+// Texts as the legend (F1) shows them, None where the attr char is '-'.
+#[derive(Serialize)]
+pub struct LvAttrDoc {
+    pub volume_type: Option<&'static str>,
+    pub permissions: Option<&'static str>,
+    pub allocation: Option<&'static str>,
+    pub allocation_locked: bool,
+    pub fixed_minor: bool,
+    pub state: Option<&'static str>,
+    // None if not known.
+    pub open: Option<bool>,
+    pub target_type: Option<&'static str>,
+    pub zero: bool,
+    pub health: Option<&'static str>,
+    pub skip_activation: bool,
+}
+
+#[derive(Serialize)]
+pub struct SegDoc {
+    pub pv: String,
+    pub start_pe: u64,
+    pub count_pe: u64,
+}
+
+fn text(flag: Option<AttrFlag>) -> Option<&'static str> {
+    flag.map(|f| f.text)
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    (!s.is_empty()).then(|| s.to_string())
+}
+
+impl From<&LvmPVData> for PvDoc {
+    fn from(pv: &LvmPVData) -> Self {
+        let attr = PvAttr::decode(&pv.pv_attr);
+        Self {
+            name: pv.pv_name.clone(),
+            uuid: pv.pv_uuid.clone(),
+            size: pv.pv_size,
+            free: pv.pv_free,
+            pe_start: pv.pe_start,
+            pe_count: pv.pe_count,
+            pe_alloc_count: pv.pe_alloc_count,
+            attr: pv.pv_attr.clone(),
+            attr_decoded: PvAttrDoc {
+                allocatable: attr.allocatable,
+                duplicate: attr.duplicate,
+                used: attr.used,
+                exported: attr.exported,
+                missing: attr.missing,
+            },
+            major: pv.major,
+            minor: pv.minor,
+        }
+    }
+}
+
+impl LvDoc {
+    // 'lvs' are the lvs of its vg, for its children.
+    fn new(lv: &LvmLvData, lvs: &[LvmLvData]) -> Self {
+        let attr = LvAttr::decode(&lv.attr);
+        let children = lvs
+            .iter()
+            .filter(|sub| sub.parent_lv == lv.lv_name && sub.lv_name != lv.lv_name)
+            .map(|sub| sub.lv_name.clone())
+            .collect();
+        Self {
+            name: lv.lv_name.clone(),
+            uuid: lv.uuid.clone(),
+            size: lv.size,
+            segtype: lv.segtype.clone(),
+            attr: lv.attr.clone(),
+            attr_decoded: LvAttrDoc {
+                volume_type: text(attr.volume_type.flag()),
+                permissions: text(attr.permissions.flag()),
+                allocation: text(attr.allocation.flag()),
+                allocation_locked: attr.allocation_locked,
+                fixed_minor: attr.fixed_minor,
+                state: text(attr.state.flag()),
+                open: attr.open,
+                target_type: text(attr.target_type.flag()),
+                zero: attr.zero,
+                health: text(attr.health.flag()),
+                skip_activation: attr.skip_activation,
+            },
+            visible: !lv.path.is_empty(),
+            path: lv.path.clone(),
+            dm_path: lv.dm_path.clone(),
+            parent: non_empty(&lv.parent_lv),
+            children,
+            origin: non_empty(&lv.origin),
+            pool: non_empty(&lv.pool_lv),
+            stripes: lv.stripes,
+            segments: lv
+                .lv_segs
+                .iter()
+                .map(|seg| SegDoc {
+                    pv: seg.pvdev.clone(),
+                    start_pe: seg.pv_start_pe,
+                    count_pe: seg.size_pe,
+                })
+                .collect(),
+            sync_percent: lv.sync_percent,
+            snap_percent: lv.snap_percent,
+            data_percent: lv.data_percent,
+            metadata_percent: lv.metadata_percent,
+            health_status: lv.health_status.clone(),
+            cache_mode: lv.cache_mode.clone(),
+            tags: lv.tags.clone(),
+            time: lv.time.clone(),
+            host: lv.host.clone(),
+        }
+    }
+}
+
+//
+// Read all of it from the backend.
+//
+pub fn topology(lvm: &dyn LvmBackend) -> Result<Topology, LvmError> {
+    let pvs = lvm.get_pvs()?;
+    let lvs = lvm.get_lvs()?;
+    let mut vgs = Vec::new();
+    for vg_name in lvm.get_vgs()? {
+        let vg = lvm.get_vg_info(&vg_name)?;
+        let attr = VgAttr::decode(&vg.attr);
+        let vg_pvs: Vec<&LvmPVData> = pvs.iter().filter(|pv| pv.vg_name == vg_name).collect();
+        let vg_lvs: Vec<LvmLvData> = lvs
+            .iter()
+            .filter(|lv| lv.vg_name == vg_name)
+            .cloned()
+            .collect();
+        vgs.push(VgDoc {
+            name: vg.name,
+            uuid: vg.uuid,
+            size: vg.size,
+            free: vg.free,
+            extent_size: vg_pvs.first().map(|pv| pv.pe_size).unwrap_or_default(),
+            attr: vg.attr,
+            attr_decoded: VgAttrDoc {
+                writeable: attr.writeable,
+                resizeable: attr.resizeable,
+                exported: attr.exported,
+                partial: attr.partial,
+                allocation: text(attr.allocation.flag()),
+                clustered: attr.clustered,
+                shared: attr.shared,
+            },
+            pvs: vg_pvs.into_iter().map(PvDoc::from).collect(),
+            lvs: vg_lvs.iter().map(|lv| LvDoc::new(lv, &vg_lvs)).collect(),
+        });
+    }
+    let orphan_pvs = pvs
+        .iter()
+        .filter(|pv| pv.vg_name.is_empty())
+        .map(PvDoc::from)
+        .collect();
+    Ok(Topology { vgs, orphan_pvs })
+}
+
+pub fn to_string(topology: &Topology, format: ExportFormat) -> Result<String, LvmError> {
+    let text = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(topology)
+            .map(|json| json + "\n")
+            .map_err(|e| e.to_string()),
+        ExportFormat::Yaml => serde_yaml::to_string(topology).map_err(|e| e.to_string()),
+    };
+    text.map_err(|msg| LvmError::Parse {
+        msg,
+        line: String::new(),
+    })
+}
+
+//
+// Read it and write it to 'path', in the format its extension says.
+//
+pub fn export_to(lvm: &dyn LvmBackend, path: &Path) -> Result<(), LvmError> {
+    let text = to_string(&topology(lvm)?, ExportFormat::from_path(path))?;
+    std::fs::write(path, text).map_err(LvmError::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::lvm::LvmBackend;
+    use crate::lvm::fake::FakeBackend;
+    use crate::lvm::topology::{ExportFormat, export_to, to_string, topology};
+
+    #[test]
+    fn test_topology() {
+        let fake = FakeBackend::demo();
+        let topology = topology(&fake).unwrap();
+        let vg = topology
+            .vgs
+            .iter()
+            .find(|vg| vg.name == "vg04_1tbdisks")
+            .unwrap();
+        assert_eq!(vg.pvs.len(), 4);
+        assert!(vg.attr_decoded.writeable);
+        let lvpub = vg.lvs.iter().find(|lv| lv.name == "lvpub").unwrap();
+        let lvpub_size = lvpub.size;
+        assert_eq!(lvpub.attr_decoded.volume_type, Some("raid"));
+        assert_eq!(lvpub.attr_decoded.state, Some("active"));
+        assert_eq!(lvpub.children.len(), 8);
+        assert!(lvpub.children.contains(&"[lvpub_rimage_3]".to_string()));
+        let image = vg
+            .lvs
+            .iter()
+            .find(|lv| lv.name == "[lvpub_rimage_0]")
+            .unwrap();
+        assert_eq!(image.parent.as_deref(), Some("lvpub"));
+        assert!(!image.visible);
+        assert_eq!(image.segments.len(), 1);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&to_string(&topology, ExportFormat::Json).unwrap()).unwrap();
+        let lvs = json["vgs"][1]["lvs"].as_array().unwrap();
+        assert_eq!(lvs[0]["name"], "lvpub");
+        assert_eq!(lvs[0]["size"], lvpub_size);
+        assert!(lvs[0].get("origin").is_none());
+        let yaml = to_string(&topology, ExportFormat::Yaml).unwrap();
+        assert!(yaml.starts_with("vgs:\n- name: vgroot\n"));
+        assert!(yaml.contains("  - name: lvpub\n"));
+    }
+
+    #[test]
+    fn test_export_to() {
+        let fake = FakeBackend::new();
+        fake.add_vg("vg01", &[("/dev/sda", 1024 * 1024 * 1024)]);
+        fake.create_lv("lvdata", "vg01", 512, "M", "linear", &[], &[])
+            .unwrap();
+        assert_eq!(
+            ExportFormat::from_path(Path::new("/tmp/lvm.yml")),
+            ExportFormat::Yaml
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("lvm")),
+            ExportFormat::Json
+        );
+
+        let path = std::env::temp_dir().join(format!("lvm-tui-export-{}.yaml", std::process::id()));
+        export_to(&fake, &path).unwrap();
+        let yaml = std::fs::read_to_string(&path).unwrap();
+        assert!(yaml.contains("- name: lvdata\n"));
+        assert!(yaml.contains("orphan_pvs: []\n"));
+        std::fs::remove_file(&path).unwrap();
+        assert!(export_to(&fake, Path::new("/nonexistent/dir/lvm.json")).is_err());
+    }
+}
@@ -0,0 +1,305 @@
+//
+// Notices lvm being changed from elsewhere, another shell, udev autoactivation
+// or a disk plugged in, so the app can read it again.
+//
+// Two sources, each read in a thread of its own:
+//  - kernel uevents of block devices, on a netlink socket,
+//  - inotify on /etc/lvm/backup (written after every metadata change) and
+//    /run/lvm (pvs/vgs online, set by pvscan when autoactivating).
+//
+// Changes come in bursts, an lvcreate alone gives a handful. They are
+// debounced, a refresh is due once it has been quiet for a while.
+//
+
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
+use nix::sys::socket::{
+    AddressFamily, MsgFlags, NetlinkAddr, SockFlag, SockProtocol, SockType, bind, recv, socket,
+};
+
+use crate::lvm::LvmError;
+
+pub const WATCH_DIRS: [&str; 4] = [
+    "/etc/lvm/backup",
+    "/run/lvm",
+    "/run/lvm/pvs_online",
+    "/run/lvm/vgs_online",
+];
+// Rewritten by lvm commands themselves, reading lvs included.
+const IGNORED_NAMES: [&str; 2] = ["hints", "newhints"];
+// Kernel uevents, not the ones udevd sends on after its rules ran.
+const UEVENT_GROUP: u32 = 1;
+const UEVENT_BUFSIZE: usize = 8192;
+// Quiet time before a refresh, and the longest a burst can put it off.
+const DEBOUNCE_QUIET: Duration = Duration::from_millis(500);
+const DEBOUNCE_MAX: Duration = Duration::from_secs(3);
+
+//
+// Due once no event came for 'quiet', or 'max' after the first of a burst
+// that does not stop.
+//
+pub struct Debounce {
+    quiet: Duration,
+    max: Duration,
+    first: Option<Instant>,
+    last: Option<Instant>,
+}
+
+impl Debounce {
+    pub fn new(quiet: Duration, max: Duration) -> Self {
+        Self {
+            quiet,
+            max,
+            first: None,
+            last: None,
+        }
+    }
+
+    pub fn event(&mut self, at: Instant) {
+        self.first.get_or_insert(at);
+        self.last = Some(at);
+    }
+
+    //
+    // True once per burst.
+    //
+    pub fn due(&mut self, now: Instant) -> bool {
+        let (Some(first), Some(last)) = (self.first, self.last) else {
+            return false;
+        };
+        if now.duration_since(last) >= self.quiet || now.duration_since(first) >= self.max {
+            self.first = None;
+            self.last = None;
+            return true;
+        }
+        false
+    }
+}
+
+//
+// "add@/devices/.../block/sdc\0ACTION=add\0SUBSYSTEM=block\0DEVNAME=sdc\0..."
+// to "add sdc", None if not about a block device.
+//
+pub fn parse_uevent(msg: &[u8]) -> Option<String> {
+    let msg = String::from_utf8_lossy(msg);
+    let mut action = None;
+    let mut devname = None;
+    let mut block = false;
+    for field in msg.split('\0') {
+        match field.split_once('=') {
+            Some(("ACTION", v)) => action = Some(v.to_string()),
+            Some(("DEVNAME", v)) => devname = Some(v.to_string()),
+            Some(("SUBSYSTEM", v)) => block = v == "block",
+            _ => {}
+        }
+    }
+    match (block, action, devname) {
+        (true, Some(action), Some(devname)) => Some(format!("{action} {devname}")),
+        _ => None,
+    }
+}
+
+//
+// What changed, debounced. Sources that can not be set up (not root, no
+// /run/lvm) are left out, start fails only if there is none.
+//
+pub struct LvmWatcher {
+    rx: Receiver<String>,
+    debounce: Debounce,
+    // Latest change of the burst, told to the user.
+    last: String,
+}
+
+impl LvmWatcher {
+    pub fn start() -> Result<Self, LvmError> {
+        Self::start_on(&WATCH_DIRS, true)
+    }
+
+    pub fn start_on(dirs: &[&str], uevents: bool) -> Result<Self, LvmError> {
+        let (tx, rx) = mpsc::channel();
+        let mut started = false;
+        let mut error = None;
+        match watch_dirs(dirs, tx.clone()) {
+            Ok(()) => started = true,
+            Err(e) => error = Some(e),
+        }
+        if uevents {
+            match watch_uevents(tx) {
+                Ok(()) => started = true,
+                Err(e) => error = Some(e),
+            }
+        }
+        if !started {
+            return Err(
+                error.unwrap_or_else(|| LvmError::Unsupported("nothing to watch".to_string()))
+            );
+        }
+        Ok(Self {
+            rx,
+            debounce: Debounce::new(DEBOUNCE_QUIET, DEBOUNCE_MAX),
+            last: String::new(),
+        })
+    }
+
+    //
+    // Some(latest change) when it is time to refresh, called from the main
+    // loop.
+    //
+    pub fn poll(&mut self) -> Option<String> {
+        let now = Instant::now();
+        for what in self.rx.try_iter() {
+            self.debounce.event(now);
+            self.last = what;
+        }
+        self.debounce
+            .due(now)
+            .then(|| std::mem::take(&mut self.last))
+    }
+}
+
+fn nix_error(what: &str, e: nix::Error) -> LvmError {
+    LvmError::Io(std::io::Error::other(format!("{what}: {e}")))
+}
+
+// Directories that do not exist (yet) are skipped.
+fn watch_dirs(dirs: &[&str], tx: Sender<String>) -> Result<(), LvmError> {
+    let inotify = Inotify::init(InitFlags::IN_CLOEXEC).map_err(|e| nix_error("inotify", e))?;
+    let flags = AddWatchFlags::IN_CLOSE_WRITE
+        | AddWatchFlags::IN_CREATE
+        | AddWatchFlags::IN_DELETE
+        | AddWatchFlags::IN_MOVED_TO;
+    let mut watched = Vec::new();
+    for dir in dirs {
+        if let Ok(wd) = inotify.add_watch(*dir, flags) {
+            watched.push((wd, dir.to_string()));
+        }
+    }
+    if watched.is_empty() {
+        return Err(LvmError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("none of {} to watch", dirs.join(", ")),
+        )));
+    }
+
+    thread::spawn(move || {
+        while let Ok(events) = inotify.read_events() {
+            for event in events {
+                let Some(name) = event.name else {
+                    continue;
+                };
+                let name = name.to_string_lossy();
+                if IGNORED_NAMES.contains(&name.as_ref()) {
+                    continue;
+                }
+                let dir = watched
+                    .iter()
+                    .find(|(wd, _)| *wd == event.wd)
+                    .map(|(_, dir)| dir.as_str())
+                    .unwrap_or_default();
+                let path = Path::new(dir).join(name.as_ref());
+                if tx.send(path.display().to_string()).is_err() {
+                    // Nobody listening anymore.
+                    return;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+fn watch_uevents(tx: Sender<String>) -> Result<(), LvmError> {
+    let sock = socket(
+        AddressFamily::Netlink,
+        SockType::Datagram,
+        SockFlag::SOCK_CLOEXEC,
+        SockProtocol::NetlinkKObjectUEvent,
+    )
+    .map_err(|e| nix_error("uevent socket", e))?;
+    bind(sock.as_raw_fd(), &NetlinkAddr::new(0, UEVENT_GROUP))
+        .map_err(|e| nix_error("uevent bind", e))?;
+
+    thread::spawn(move || {
+        let mut buf = vec![0u8; UEVENT_BUFSIZE];
+        while let Ok(n) = recv(sock.as_raw_fd(), &mut buf, MsgFlags::empty()) {
+            if let Some(what) = parse_uevent(&buf[..n])
+                && tx.send(what).is_err()
+            {
+                return;
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::lvm::watch::{Debounce, LvmWatcher, parse_uevent};
+
+    #[test]
+    fn test_debounce() {
+        let ms = Duration::from_millis;
+        let t0 = Instant::now();
+        let mut debounce = Debounce::new(ms(500), ms(3000));
+        assert!(!debounce.due(t0));
+
+        // A burst is due once, after it went quiet
+        debounce.event(t0);
+        debounce.event(t0 + ms(200));
+        assert!(!debounce.due(t0 + ms(600)));
+        assert!(debounce.due(t0 + ms(700)));
+        assert!(!debounce.due(t0 + ms(2000)));
+
+        // One that does not stop is due anyway
+        let t1 = t0 + ms(10000);
+        for i in 0..20 {
+            debounce.event(t1 + ms(i * 200));
+        }
+        assert!(debounce.due(t1 + ms(3000)));
+    }
+
+    #[test]
+    fn test_parse_uevent() {
+        let add =
+            b"add@/devices/pci0000:00/0000:00:1f.2/ata3/host2/target2:0:0/2:0:0:0/block/sdc\0\
+            ACTION=add\0DEVPATH=/devices/pci0000:00/block/sdc\0SUBSYSTEM=block\0\
+            MAJOR=8\0MINOR=32\0DEVNAME=sdc\0DEVTYPE=disk\0SEQNUM=4711\0";
+        assert_eq!(parse_uevent(add).unwrap(), "add sdc");
+        let dm =
+            b"change@/devices/virtual/block/dm-3\0ACTION=change\0SUBSYSTEM=block\0DEVNAME=dm-3\0";
+        assert_eq!(parse_uevent(dm).unwrap(), "change dm-3");
+        let usb = b"add@/devices/pci0000:00/usb1/1-1\0ACTION=add\0SUBSYSTEM=usb\0DEVNAME=bus/usb/001/004\0";
+        assert!(parse_uevent(usb).is_none());
+        assert!(parse_uevent(b"").is_none());
+    }
+
+    #[test]
+    fn test_watch_dir() {
+        let dir = std::env::temp_dir().join(format!("lvm-tui-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir_name = dir.to_string_lossy().to_string();
+        assert!(LvmWatcher::start_on(&["/nonexistent/lvm"], false).is_err());
+        let mut watcher = LvmWatcher::start_on(&[&dir_name, "/nonexistent/lvm"], false).unwrap();
+        assert!(watcher.poll().is_none());
+
+        // Hints are lvm's own, the backup is a change
+        std::fs::write(dir.join("hints"), "").unwrap();
+        std::fs::write(dir.join("vg01"), "vg01 {\n}\n").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let what = loop {
+            if let Some(what) = watcher.poll() {
+                break what;
+            }
+            assert!(Instant::now() < deadline);
+            std::thread::sleep(Duration::from_millis(50));
+        };
+        assert_eq!(what, dir.join("vg01").display().to_string());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,13 +1,30 @@
+pub mod attrbadge;
+pub mod cacheview;
+pub mod convertview;
+pub mod exportview;
+pub mod extentmap;
+pub mod jobs;
+pub mod lvinfoview;
+pub mod lvremove;
+pub mod lvresize;
 pub mod lvview;
 pub mod popup;
+pub mod pvmoveview;
+pub mod pvview;
+pub mod raidview;
+pub mod rename;
 pub mod res;
+pub mod snapview;
 pub mod statusbar;
+pub mod thinpool;
+pub mod vgcreateview;
 pub mod vgview;
 
 use core::time;
 use crossterm::event::KeyEvent;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::thread::{self};
+use std::time::Instant;
 
 use Constraint::{Length, Max, Min};
 use ratatui::style::Stylize;
@@ -19,21 +36,40 @@ use ratatui::{
     style::{Modifier, Style},
     text::{Line, Text},
     widgets::{
-        Block, Borders, Cell, HighlightSpacing, Paragraph, Row, Scrollbar, ScrollbarOrientation,
-        ScrollbarState, Table, TableState,
+        Block, Borders, Cell, HighlightSpacing, LineGauge, Paragraph, Row, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Table, TableState,
     },
 };
 
 use unicode_width::UnicodeWidthStr;
 
+use crate::lvmapp::cacheview::{CacheAttachView, CacheDetachPopup};
+use crate::lvmapp::convertview::LvConvertView;
+use crate::lvmapp::exportview::{DEFAULT_EXPORT_PATH, ExportPopup};
+use crate::lvmapp::jobs::{JobListView, Jobs, LvmJob};
+use crate::lvmapp::lvinfoview::LvInfoView;
+use crate::lvmapp::lvremove::LvRemovePopup;
+use crate::lvmapp::lvresize::LvResizeView;
 use crate::lvmapp::lvview::LvNewView;
+use crate::lvmapp::popup::ConfPopup;
+use crate::lvmapp::pvmoveview::{PvMoveAbortPopup, PvMoveView};
+use crate::lvmapp::pvview::PvInfoView;
+use crate::lvmapp::raidview::RaidActionPopup;
+use crate::lvmapp::rename::RenameEdit;
+use crate::lvmapp::snapview::{SnapMergePopup, SnapNewView};
 use crate::lvmapp::statusbar::StatusBar;
+use crate::lvmapp::thinpool::ThinThresholds;
+use crate::lvmapp::vgcreateview::{VgCreateView, VgReducePopup};
 use crate::{
-    lvm::{self},
+    lvm::{self, LvmBackend, LvmLvData, watch::LvmWatcher},
     lvmapp::{res::Colors, vgview::VgInfoView},
 };
 
 const STATUS_RESET_INTERVAL: u64 = 30;
+// How often lvs are read while a raid scrub, resync or pvmove runs, for its progress.
+const SYNC_POLL_INTERVAL: u64 = 1;
+// How long to wait for a key before looking at the jobs, in ms.
+const EVENT_POLL_INTERVAL: u64 = 250;
 
 struct Status {
     last_result: Option<String>,
@@ -82,11 +118,20 @@ impl VgTableData {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum ViewType {
     VgOverview,
     VgInfo,
     LvNew,
+    LvResize,
+    SnapNew,
+    CacheAttach,
+    LvConvert,
+    VgCreate,
+    PvMove,
+    PvInfo,
+    LvInfo,
+    Jobs,
 }
 
 pub struct LvmApp<'a> {
@@ -100,8 +145,53 @@ pub struct LvmApp<'a> {
     sel_vg_name: String,
     title: String,
     vg_info_view: Option<VgInfoView>,
+    pv_info_view: Option<PvInfoView>,
+    lv_info_view: Option<LvInfoView>,
+    // Where to go back to from lv info, overview or vg info.
+    lv_info_back: ViewType,
     lv_new_view: Option<LvNewView<'a>>,
+    lv_resize_view: Option<LvResizeView>,
+    snap_new_view: Option<SnapNewView>,
+    cache_attach_view: Option<CacheAttachView>,
+    lv_convert_view: Option<LvConvertView>,
+    // New vg (F7) or more pvs for one (F4), from the overview.
+    vg_create_view: Option<VgCreateView>,
+    // Extents off a pv (F12), from the overview or pv info.
+    pv_move_view: Option<PvMoveView>,
+    pv_move_back: ViewType,
+    // pvmoves running in any vg, their progress is in the footer.
+    pvmoves: Vec<LvmLvData>,
+    // Lvm changes running in the background, listed with j.
+    jobs: Jobs,
+    job_list_view: Option<JobListView>,
+    job_list_back: ViewType,
     refresh_lvm_data: bool,
+    error_popup: Option<String>,
+    // lv_attr explained, F1 in vg info and lv info.
+    legend_popup: Option<String>,
+    // lvremove confirmation, F8 in vg info.
+    lv_remove_popup: Option<LvRemovePopup>,
+    // Name edited in place, F2 in overview and vg info.
+    rename_edit: Option<RenameEdit>,
+    // lvconvert --merge confirmation, F5 in vg info.
+    snap_merge_popup: Option<SnapMergePopup>,
+    // lvconvert --splitcache/--uncache confirmation, F9 in vg info.
+    cache_detach_popup: Option<CacheDetachPopup>,
+    // Scrub, repair or image replace of a raid, F10 in vg info.
+    raid_action_popup: Option<RaidActionPopup>,
+    // vgreduce confirmation, F8 on a pv in the overview.
+    vg_reduce_popup: Option<VgReducePopup>,
+    // pvmove --abort confirmation, F12 on a pv with a move running in its vg.
+    pv_move_abort_popup: Option<PvMoveAbortPopup>,
+    // Topology to a file, x from the info views. The path is kept for the
+    // next export.
+    export_popup: Option<ExportPopup>,
+    export_path: String,
+    // data% and metadata% a thin pool is warned about from.
+    thin_thresholds: ThinThresholds,
+    // Changes made outside, refreshed on when given (--watch).
+    watcher: Option<LvmWatcher>,
+    lvm: Arc<dyn LvmBackend>,
 }
 
 impl View for LvmApp<'_> {
@@ -109,14 +199,33 @@ impl View for LvmApp<'_> {
     // Main view and vgview.
     fn refresh_data(&mut self) {
         let mut vgs = Vec::<VgTableData>::new();
-        fetch_data(&mut vgs);
+        if let Err(e) = fetch_data(self.lvm.as_ref(), &mut vgs) {
+            // Keep showing what we had, just tell.
+            STATUS
+                .lock()
+                .unwrap()
+                .set_status(&format!("Refresh failed: {e}"));
+            return;
+        }
         self.vgd_longest_item_lens = constraint_len_calculator(&vgs);
+        // Stay on the same row, rows before it may have come or gone.
+        let row = same_row(&self.items, self.state.selected().unwrap_or(0), &vgs);
+        self.scroll_state =
+            ScrollbarState::new(scroll_len(vgs.len())).position(row * res::ITEM_HEIGHT);
+        self.state.select(Some(row));
         self.items = vgs;
+        STATUS.lock().unwrap().set_status("Refreshed lvm info.");
 
-        if self.vg_info_view.is_some() {
-            self.vg_info_view.as_mut().unwrap().refresh_data();
+        if let Some(vg_info_view) = self.vg_info_view.as_mut() {
+            vg_info_view.refresh_data();
         }
-        STATUS.lock().unwrap().set_status("Refreshed lvm info.");
+        if let Some(pv_info_view) = self.pv_info_view.as_mut() {
+            pv_info_view.refresh_data();
+        }
+        if let Some(lv_info_view) = self.lv_info_view.as_mut() {
+            lv_info_view.refresh_data();
+        }
+        self.refresh_pvmoves();
     }
 
     fn view_type(&self) -> ViewType {
@@ -124,16 +233,219 @@ impl View for LvmApp<'_> {
             ViewType::VgOverview => ViewType::VgOverview,
             ViewType::VgInfo => ViewType::VgInfo,
             ViewType::LvNew => ViewType::LvNew,
+            ViewType::LvResize => ViewType::LvResize,
+            ViewType::SnapNew => ViewType::SnapNew,
+            ViewType::CacheAttach => ViewType::CacheAttach,
+            ViewType::LvConvert => ViewType::LvConvert,
+            ViewType::VgCreate => ViewType::VgCreate,
+            ViewType::PvMove => ViewType::PvMove,
+            ViewType::PvInfo => ViewType::PvInfo,
+            ViewType::LvInfo => ViewType::LvInfo,
+            ViewType::Jobs => ViewType::Jobs,
         }
     }
 
     fn handle_events(&mut self, key: &KeyEvent) -> core::result::Result<bool, &'static str> {
+        if self.error_popup.is_some() {
+            // Error popup is modal, just ack it.
+            if key.kind == KeyEventKind::Press && matches!(key.code, KeyCode::Enter | KeyCode::Esc)
+            {
+                self.error_popup = None;
+            }
+            return Ok(false);
+        }
+        if self.legend_popup.is_some() {
+            if key.kind == KeyEventKind::Press && matches!(key.code, KeyCode::Enter | KeyCode::Esc)
+            {
+                self.legend_popup = None;
+            }
+            return Ok(false);
+        }
+        if let Some(lv_remove_popup) = self.lv_remove_popup.as_mut() {
+            match lv_remove_popup.handle_events(key) {
+                Ok(true) => {
+                    if let Some(job) = lv_remove_popup.take_job() {
+                        self.jobs.spawn(job);
+                    }
+                    self.lv_remove_popup = None;
+                }
+                Ok(false) => (),
+                Err(e) => {
+                    self.lv_remove_popup = None;
+                    self.error_popup = Some(e.to_string());
+                }
+            }
+            return Ok(false);
+        }
+        if let Some(rename_edit) = self.rename_edit.as_mut() {
+            match rename_edit.handle_events(key) {
+                Ok(true) => {
+                    if let Some(job) = rename_edit.take_job() {
+                        self.jobs.spawn(job);
+                    }
+                    self.rename_edit = None;
+                }
+                Ok(false) => (),
+                Err(e) => {
+                    self.rename_edit = None;
+                    self.error_popup = Some(e.to_string());
+                }
+            }
+            return Ok(false);
+        }
+        if let Some(snap_merge_popup) = self.snap_merge_popup.as_mut() {
+            match snap_merge_popup.handle_events(key) {
+                Ok(true) => {
+                    if let Some(job) = snap_merge_popup.take_job() {
+                        self.jobs.spawn(job);
+                    }
+                    self.snap_merge_popup = None;
+                }
+                Ok(false) => (),
+                Err(e) => {
+                    self.snap_merge_popup = None;
+                    self.error_popup = Some(e.to_string());
+                }
+            }
+            return Ok(false);
+        }
+        if let Some(cache_detach_popup) = self.cache_detach_popup.as_mut() {
+            match cache_detach_popup.handle_events(key) {
+                Ok(true) => {
+                    if let Some(job) = cache_detach_popup.take_job() {
+                        self.jobs.spawn(job);
+                    }
+                    self.cache_detach_popup = None;
+                }
+                Ok(false) => (),
+                Err(e) => {
+                    self.cache_detach_popup = None;
+                    self.error_popup = Some(e.to_string());
+                }
+            }
+            return Ok(false);
+        }
+        if let Some(raid_action_popup) = self.raid_action_popup.as_mut() {
+            match raid_action_popup.handle_events(key) {
+                Ok(true) => {
+                    if let Some(job) = raid_action_popup.take_job() {
+                        self.jobs.spawn(job);
+                    }
+                    self.raid_action_popup = None;
+                }
+                Ok(false) => (),
+                Err(e) => {
+                    self.raid_action_popup = None;
+                    self.error_popup = Some(e.to_string());
+                }
+            }
+            return Ok(false);
+        }
+        if let Some(vg_reduce_popup) = self.vg_reduce_popup.as_mut() {
+            match vg_reduce_popup.handle_events(key) {
+                Ok(true) => {
+                    if vg_reduce_popup.lvm_changed() {
+                        self.trigger_lvm_refresh();
+                    }
+                    self.vg_reduce_popup = None;
+                }
+                Ok(false) => (),
+                Err(e) => {
+                    self.vg_reduce_popup = None;
+                    self.error_popup = Some(e.to_string());
+                }
+            }
+            return Ok(false);
+        }
+        if let Some(pv_move_abort_popup) = self.pv_move_abort_popup.as_mut() {
+            match pv_move_abort_popup.handle_events(key) {
+                Ok(true) => {
+                    if let Some(job) = pv_move_abort_popup.take_job() {
+                        self.jobs.spawn(job);
+                    }
+                    self.pv_move_abort_popup = None;
+                }
+                Ok(false) => (),
+                Err(e) => {
+                    self.pv_move_abort_popup = None;
+                    self.error_popup = Some(e.to_string());
+                }
+            }
+            return Ok(false);
+        }
+        if let Some(export_popup) = self.export_popup.as_mut() {
+            match export_popup.handle_events(key) {
+                Ok(true) => {
+                    self.export_path = export_popup.path().to_string();
+                    self.export_popup = None;
+                }
+                Ok(false) => (),
+                Err(e) => {
+                    self.export_path = export_popup.path().to_string();
+                    self.export_popup = None;
+                    self.error_popup = Some(format!("Export failed: {e}"));
+                }
+            }
+            return Ok(false);
+        }
+        // The job list and export are there from any of the info views.
+        if key.kind == KeyEventKind::Press
+            && matches!(
+                self.view_type,
+                ViewType::VgOverview | ViewType::VgInfo | ViewType::PvInfo | ViewType::LvInfo
+            )
+        {
+            match key.code {
+                KeyCode::Char('j') => {
+                    self.job_list_back = self.view_type;
+                    self.job_list_view =
+                        Some(JobListView::new(self.jobs.clone(), self.lvm.clone()));
+                    self.view_type = ViewType::Jobs;
+                    return Ok(false);
+                }
+                KeyCode::Char('x') => {
+                    self.export_popup = Some(ExportPopup::new(
+                        &self.export_path,
+                        self.colors.clone(),
+                        self.lvm.clone(),
+                    ));
+                    return Ok(false);
+                }
+                _ => {}
+            }
+        }
+        // Captured output (--from-dir) is only looked at, the keys that would
+        // change something are not taken.
+        if self.lvm.read_only()
+            && key.kind == KeyEventKind::Press
+            && matches!(key.code, KeyCode::F(2..=5) | KeyCode::F(7..=12))
+            && matches!(
+                self.view_type,
+                ViewType::VgOverview | ViewType::VgInfo | ViewType::PvInfo | ViewType::LvInfo
+            )
+        {
+            STATUS
+                .lock()
+                .unwrap()
+                .set_status("Read only, nothing can be changed.");
+            return Ok(false);
+        }
+
         if self.view_type == ViewType::VgOverview {
             if key.kind == KeyEventKind::Press {
                 match key.code {
                     KeyCode::Enter => self.acton_cell(),
                     KeyCode::Esc => {
-                        // if in main window, quit
+                        // if in main window, quit, unless something is still
+                        // being changed.
+                        let running = self.jobs.running();
+                        if running > 0 {
+                            STATUS.lock().unwrap().set_status(&format!(
+                                "{running} job{} running, see the job list (j)",
+                                if running == 1 { "" } else { "s" }
+                            ));
+                            return Ok(false);
+                        }
                         self.view_type = ViewType::VgOverview;
                         return Ok(true);
                     }
@@ -141,6 +453,11 @@ impl View for LvmApp<'_> {
                     KeyCode::Up => self.previous_row(),
                     KeyCode::Right => self.next_column(),
                     KeyCode::Left => self.previous_column(),
+                    KeyCode::F(2) => self.rename_cell(),
+                    KeyCode::F(7) => self.open_vg_create(None),
+                    KeyCode::F(4) => self.extend_cell(),
+                    KeyCode::F(8) => self.reduce_cell(),
+                    KeyCode::F(12) => self.move_cell(),
                     _ => {}
                 }
             }
@@ -155,84 +472,527 @@ impl View for LvmApp<'_> {
                     }
                     KeyCode::Down => vg_info_view.next_lvrow(),
                     KeyCode::Up => vg_info_view.previous_lvrow(),
+                    KeyCode::F(1) => {
+                        self.legend_popup = vg_info_view.selected_lv().map(|lv| lv.attr.clone());
+                    }
+                    KeyCode::Enter => {
+                        if let Some(lv) = vg_info_view.selected_lv() {
+                            let (vg_name, lv_name) = (lv.vg_name.clone(), lv.lv_name.clone());
+                            self.open_lv_info(&vg_name, &lv_name, ViewType::VgInfo);
+                        }
+                    }
+                    KeyCode::F(8) => {
+                        if let Some(lv) = vg_info_view.selected_lv() {
+                            if lv.parent_lv.is_empty() {
+                                self.lv_remove_popup = Some(LvRemovePopup::new(
+                                    lv,
+                                    vg_info_view.lvs(),
+                                    self.colors.clone(),
+                                    self.lvm.clone(),
+                                ));
+                            } else {
+                                // lvremove refuses these, no need to ask first.
+                                self.error_popup = Some(format!(
+                                    "{} is part of {}, remove {} instead.",
+                                    lv.lv_name, lv.parent_lv, lv.parent_lv
+                                ));
+                            }
+                        }
+                    }
+                    KeyCode::F(2) => {
+                        if let Some(lv) = vg_info_view.selected_lv() {
+                            if lv.parent_lv.is_empty() {
+                                self.rename_edit = Some(RenameEdit::lv(
+                                    lv,
+                                    vg_info_view.lvs(),
+                                    self.colors.clone(),
+                                    self.lvm.clone(),
+                                ));
+                            } else {
+                                self.error_popup = Some(format!(
+                                    "{} is part of {}, it is renamed with it.",
+                                    lv.lv_name, lv.parent_lv
+                                ));
+                            }
+                        }
+                    }
+                    KeyCode::F(4) => {
+                        if let Some(lv) = vg_info_view.selected_lv() {
+                            if lv.parent_lv.is_empty() {
+                                let mut lv_resize_view =
+                                    LvResizeView::new(&lv.vg_name, &lv.lv_name, self.lvm.clone());
+                                match lv_resize_view.fetch_data() {
+                                    Ok(()) => {
+                                        self.view_type = ViewType::LvResize;
+                                        self.lv_resize_view = Some(lv_resize_view);
+                                    }
+                                    Err(e) => self.error_popup = Some(e.to_string()),
+                                }
+                            } else {
+                                self.error_popup = Some(format!(
+                                    "{} is part of {}, resize {} instead.",
+                                    lv.lv_name, lv.parent_lv, lv.parent_lv
+                                ));
+                            }
+                        }
+                    }
+                    KeyCode::F(3) => {
+                        if let Some(lv) = vg_info_view.selected_lv() {
+                            if !lv.parent_lv.is_empty() {
+                                self.error_popup = Some(format!(
+                                    "{} is part of {}, snapshot {} instead.",
+                                    lv.lv_name, lv.parent_lv, lv.parent_lv
+                                ));
+                            } else if !lv.origin.is_empty() {
+                                self.error_popup =
+                                    Some(format!("{} is a snapshot already.", lv.lv_name));
+                            } else {
+                                let mut snap_new_view =
+                                    SnapNewView::new(&lv.vg_name, &lv.lv_name, self.lvm.clone());
+                                match snap_new_view.fetch_data() {
+                                    Ok(()) => {
+                                        self.view_type = ViewType::SnapNew;
+                                        self.snap_new_view = Some(snap_new_view);
+                                    }
+                                    Err(e) => self.error_popup = Some(e.to_string()),
+                                }
+                            }
+                        }
+                    }
+                    KeyCode::F(5) => {
+                        if let Some(lv) = vg_info_view.selected_lv() {
+                            if lv.origin.is_empty() {
+                                self.error_popup =
+                                    Some(format!("{} is not a snapshot.", lv.lv_name));
+                            } else if snapview::is_invalid(lv) {
+                                self.error_popup = Some(format!(
+                                    "{} is invalid, it can only be removed.",
+                                    lv.lv_name
+                                ));
+                            } else {
+                                self.snap_merge_popup = Some(SnapMergePopup::new(
+                                    lv,
+                                    vg_info_view.lvs(),
+                                    self.colors.clone(),
+                                    self.lvm.clone(),
+                                ));
+                            }
+                        }
+                    }
+                    KeyCode::F(9) => {
+                        if let Some(lv) = vg_info_view.selected_lv() {
+                            if !lv.parent_lv.is_empty() {
+                                self.error_popup = Some(format!(
+                                    "{} is part of {}, cache {} instead.",
+                                    lv.lv_name, lv.parent_lv, lv.parent_lv
+                                ));
+                            } else if cacheview::is_cached(lv) {
+                                self.cache_detach_popup = Some(CacheDetachPopup::new(
+                                    lv,
+                                    self.colors.clone(),
+                                    self.lvm.clone(),
+                                ));
+                            } else if !lv.origin.is_empty()
+                                || matches!(lv.segtype.as_str(), "thin" | "cache-pool")
+                            {
+                                self.error_popup = Some(format!(
+                                    "{} ({}) can not be cached.",
+                                    lv.lv_name, lv.segtype
+                                ));
+                            } else {
+                                let mut cache_attach_view = CacheAttachView::new(
+                                    &lv.vg_name,
+                                    &lv.lv_name,
+                                    self.lvm.clone(),
+                                );
+                                match cache_attach_view.fetch_data() {
+                                    Ok(()) => {
+                                        self.view_type = ViewType::CacheAttach;
+                                        self.cache_attach_view = Some(cache_attach_view);
+                                    }
+                                    Err(e) => self.error_popup = Some(e.to_string()),
+                                }
+                            }
+                        }
+                    }
+                    KeyCode::F(10) => {
+                        if let Some(lv) = vg_info_view.selected_lv() {
+                            if !lv.parent_lv.is_empty() {
+                                self.error_popup = Some(format!(
+                                    "{} is part of {}, select {} instead.",
+                                    lv.lv_name, lv.parent_lv, lv.parent_lv
+                                ));
+                            } else if !raidview::is_raid(lv) {
+                                self.error_popup = Some(format!(
+                                    "{} ({}) is not a raid with redundancy.",
+                                    lv.lv_name, lv.segtype
+                                ));
+                            } else if raidview::is_syncing(lv) {
+                                self.error_popup = Some(format!(
+                                    "{} is busy ({} {:.0}%), wait for it to finish.",
+                                    lv.lv_name,
+                                    lv.raid_sync_action,
+                                    lv.sync_percent.unwrap_or(0.0)
+                                ));
+                            } else {
+                                self.raid_action_popup = Some(RaidActionPopup::new(
+                                    lv,
+                                    vg_info_view.lvs(),
+                                    vg_info_view.pvs(),
+                                    self.colors.clone(),
+                                    self.lvm.clone(),
+                                ));
+                            }
+                        }
+                    }
+                    KeyCode::F(11) => {
+                        if let Some(lv) = vg_info_view.selected_lv() {
+                            if !lv.parent_lv.is_empty() {
+                                self.error_popup = Some(format!(
+                                    "{} is part of {}, convert {} instead.",
+                                    lv.lv_name, lv.parent_lv, lv.parent_lv
+                                ));
+                            } else if lvm::convert_targets(lv).is_empty() {
+                                self.error_popup = Some(format!(
+                                    "{} ({}) can not be converted.",
+                                    lv.lv_name, lv.segtype
+                                ));
+                            } else if raidview::is_syncing(lv) {
+                                self.error_popup = Some(format!(
+                                    "{} is busy ({} {:.0}%), wait for it to finish.",
+                                    lv.lv_name,
+                                    lv.raid_sync_action,
+                                    lv.sync_percent.unwrap_or(0.0)
+                                ));
+                            } else {
+                                let mut lv_convert_view =
+                                    LvConvertView::new(&lv.vg_name, &lv.lv_name, self.lvm.clone());
+                                match lv_convert_view.fetch_data() {
+                                    Ok(()) => {
+                                        self.view_type = ViewType::LvConvert;
+                                        self.lv_convert_view = Some(lv_convert_view);
+                                    }
+                                    Err(e) => self.error_popup = Some(e.to_string()),
+                                }
+                            }
+                        }
+                    }
                     KeyCode::F(7) => {
                         self.view_type = ViewType::LvNew;
                         self.lv_new_view = Some(lvview::LvNewView::new(
                             &self.sel_vg_name,
                             vg_info_view.pvdev_list.as_ref().unwrap(),
+                            self.lvm.clone(),
                         ));
                     }
                     _ => {}
                 }
             }
-        } else if self.view_type == ViewType::LvNew {
-            let lv_new_view = self.lv_new_view.as_mut().unwrap();
+        } else if self.view_type == ViewType::PvInfo {
+            let pv_info_view = self.pv_info_view.as_mut().unwrap();
             if key.kind == KeyEventKind::Press {
                 match key.code {
-                    _ => {
-                        match lv_new_view.handle_events(&key) {
-                            Ok(true) => {
-                                // done,
-                                self.view_type = ViewType::VgInfo; // "back"
-                                if lv_new_view.lvm_changed() {
-                                    self.trigger_lvm_refresh();
-                                }
-                                self.lv_new_view = None;
-                            }
-                            Ok(false) => (),
-                            Err(e) => {
-                                // Error handled in view, panic if for some reason get here
-                                panic!("{e}");
-                            }
-                        }
+                    KeyCode::Esc => {
+                        self.view_type = ViewType::VgOverview;
+                        self.pv_info_view = None;
+                        return Ok(false);
+                    }
+                    KeyCode::Down => pv_info_view.next_segrow(),
+                    KeyCode::Up => pv_info_view.previous_segrow(),
+                    KeyCode::F(12) => {
+                        let pv_name = pv_info_view.pv_name().to_string();
+                        let range = pv_info_view.selected_range();
+                        self.open_pv_move(&pv_name, range, ViewType::PvInfo);
                     }
+                    _ => {}
                 }
             }
+        } else if self.view_type == ViewType::LvInfo {
+            let lv_info_view = self.lv_info_view.as_mut().unwrap();
+            if key.kind == KeyEventKind::Press {
+                match key.code {
+                    KeyCode::Esc => {
+                        self.view_type = self.lv_info_back;
+                        self.lv_info_view = None;
+                        return Ok(false);
+                    }
+                    KeyCode::F(1) => self.legend_popup = lv_info_view.lv_attr(),
+                    _ => {}
+                }
+            }
+        } else if self.view_type == ViewType::LvNew {
+            let lv_new_view = self.lv_new_view.as_mut().unwrap();
+            if form_done(
+                lv_new_view,
+                key,
+                LvNewView::take_job,
+                &self.jobs,
+                &mut self.error_popup,
+            ) {
+                self.view_type = ViewType::VgInfo;
+                self.lv_new_view = None;
+            }
+        } else if self.view_type == ViewType::LvResize {
+            let lv_resize_view = self.lv_resize_view.as_mut().unwrap();
+            if form_done(
+                lv_resize_view,
+                key,
+                LvResizeView::take_job,
+                &self.jobs,
+                &mut self.error_popup,
+            ) {
+                self.view_type = ViewType::VgInfo;
+                self.lv_resize_view = None;
+            }
+        } else if self.view_type == ViewType::SnapNew {
+            let snap_new_view = self.snap_new_view.as_mut().unwrap();
+            if form_done(
+                snap_new_view,
+                key,
+                SnapNewView::take_job,
+                &self.jobs,
+                &mut self.error_popup,
+            ) {
+                self.view_type = ViewType::VgInfo;
+                self.snap_new_view = None;
+            }
+        } else if self.view_type == ViewType::CacheAttach {
+            let cache_attach_view = self.cache_attach_view.as_mut().unwrap();
+            if form_done(
+                cache_attach_view,
+                key,
+                CacheAttachView::take_job,
+                &self.jobs,
+                &mut self.error_popup,
+            ) {
+                self.view_type = ViewType::VgInfo;
+                self.cache_attach_view = None;
+            }
+        } else if self.view_type == ViewType::LvConvert {
+            let lv_convert_view = self.lv_convert_view.as_mut().unwrap();
+            if form_done(
+                lv_convert_view,
+                key,
+                LvConvertView::take_job,
+                &self.jobs,
+                &mut self.error_popup,
+            ) {
+                self.view_type = ViewType::VgInfo;
+                self.lv_convert_view = None;
+            }
+        } else if self.view_type == ViewType::VgCreate {
+            let vg_create_view = self.vg_create_view.as_mut().unwrap();
+            if form_done(
+                vg_create_view,
+                key,
+                VgCreateView::take_job,
+                &self.jobs,
+                &mut self.error_popup,
+            ) {
+                self.view_type = ViewType::VgOverview;
+                self.vg_create_view = None;
+            }
+        } else if self.view_type == ViewType::PvMove {
+            let pv_move_view = self.pv_move_view.as_mut().unwrap();
+            if form_done(
+                pv_move_view,
+                key,
+                PvMoveView::take_job,
+                &self.jobs,
+                &mut self.error_popup,
+            ) {
+                self.view_type = self.pv_move_back;
+                self.pv_move_view = None;
+            }
+        } else if self.view_type == ViewType::Jobs {
+            let job_list_view = self.job_list_view.as_mut().unwrap();
+            if let Ok(true) = job_list_view.handle_events(key) {
+                self.view_type = self.job_list_back;
+                self.job_list_view = None;
+            }
         }
 
-        return Ok(false);
+        Ok(false)
     }
 }
 
 impl LvmApp<'_> {
-    pub fn new() -> Self {
+    pub fn new(lvm: Arc<dyn LvmBackend>) -> Self {
         let mut vgs = Vec::<VgTableData>::new();
-        fetch_data(&mut vgs);
-
-        let initial_cnt_len = match vgs.len() {
-            // dont * with 0
-            0 => res::ITEM_HEIGHT,
-            1 => res::ITEM_HEIGHT,
-            _ => (vgs.len() - 1) * res::ITEM_HEIGHT,
-        };
+        let error_popup = fetch_data(lvm.as_ref(), &mut vgs)
+            .err()
+            .map(|e| e.to_string());
+        let pvmoves = lvm
+            .get_lvs()
+            .map(|lvs| {
+                lvs.into_iter()
+                    .filter(|lv| !lv.move_pv.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
 
         Self {
             state: TableState::default()
                 .with_selected(0)
                 .with_selected_cell((0, 0)),
             vgd_longest_item_lens: constraint_len_calculator(&vgs),
-            scroll_state: ScrollbarState::new(initial_cnt_len),
+            scroll_state: ScrollbarState::new(scroll_len(vgs.len())),
             colors: Colors::new(&res::PALETTES[0]),
             color_index: 0,
             items: vgs,
             view_type: ViewType::VgOverview,
             sel_vg_name: String::new(),
-            title: String::from(res::TITLE),
+            title: if lvm.read_only() {
+                format!("{}(read only) ", res::TITLE)
+            } else {
+                String::from(res::TITLE)
+            },
             vg_info_view: None,
+            pv_info_view: None,
+            lv_info_view: None,
+            lv_info_back: ViewType::VgOverview,
             lv_new_view: None,
+            lv_resize_view: None,
+            snap_new_view: None,
+            cache_attach_view: None,
+            lv_convert_view: None,
+            vg_create_view: None,
+            pv_move_view: None,
+            pv_move_back: ViewType::VgOverview,
+            pvmoves,
+            jobs: Jobs::new(),
+            job_list_view: None,
+            job_list_back: ViewType::VgOverview,
             refresh_lvm_data: true,
+            error_popup,
+            legend_popup: None,
+            lv_remove_popup: None,
+            rename_edit: None,
+            snap_merge_popup: None,
+            cache_detach_popup: None,
+            raid_action_popup: None,
+            vg_reduce_popup: None,
+            pv_move_abort_popup: None,
+            export_popup: None,
+            export_path: DEFAULT_EXPORT_PATH.to_string(),
+            thin_thresholds: ThinThresholds::default(),
+            watcher: None,
+            lvm,
         }
     }
 
+    pub fn thin_thresholds(mut self, thin_thresholds: ThinThresholds) -> Self {
+        self.thin_thresholds = thin_thresholds;
+        self
+    }
+
+    pub fn watcher(mut self, watcher: LvmWatcher) -> Self {
+        self.watcher = Some(watcher);
+        self
+    }
+
+    //
+    // Read all again when something changed outside, where the user is
+    // stays selected.
+    //
+    fn poll_watcher(&mut self) {
+        let Some(what) = self.watcher.as_mut().and_then(|w| w.poll()) else {
+            return;
+        };
+        self.refresh_data();
+        STATUS
+            .lock()
+            .unwrap()
+            .set_status(&format!("Refreshed, changed: {what}"));
+    }
+
     fn trigger_lvm_refresh(&mut self) {
         self.refresh_lvm_data = true;
     }
 
+    //
+    // Tell about jobs that finished, ok in the status line and failed in
+    // the error popup, and read lvm again if any did.
+    //
+    fn poll_jobs(&mut self) {
+        let done = self.jobs.take_finished();
+        for job in &done {
+            match &job.error {
+                None => STATUS
+                    .lock()
+                    .unwrap()
+                    .set_status(&format!("Done: {}", job.title)),
+                Some(e) => self.error_popup = Some(format!("{}: {e}", job.title)),
+            }
+        }
+        if !done.is_empty() {
+            self.trigger_lvm_refresh();
+        }
+    }
+
+    //
+    // Let the running jobs finish, then as poll_jobs.
+    //
+    pub fn wait_jobs(&mut self) {
+        self.jobs.wait();
+        self.poll_jobs();
+    }
+
     fn clear_flags(&mut self) {
         self.refresh_lvm_data = false;
     }
 
+    //
+    // A raid scrub or resync is running in the vg or on the lv looked at.
+    //
+    fn raid_syncing(&self) -> bool {
+        let vg_lvs = self.vg_info_view.as_ref().map(|view| view.lvs());
+        let lv = self.lv_info_view.as_ref().and_then(|view| view.lv());
+        vg_lvs
+            .unwrap_or_default()
+            .iter()
+            .chain(lv)
+            .any(raidview::is_syncing)
+    }
+
+    //
+    // Progress of a scrub or resync, the views showing it are read again
+    // without touching the status line.
+    //
+    fn refresh_sync_progress(&mut self) {
+        if let Some(vg_info_view) = self.vg_info_view.as_mut() {
+            vg_info_view.refresh_data();
+        }
+        if let Some(lv_info_view) = self.lv_info_view.as_mut() {
+            lv_info_view.refresh_data();
+        }
+        // Extents are somewhere else once a pvmove is done, read all again.
+        if self.refresh_pvmoves() {
+            self.refresh_data();
+            STATUS.lock().unwrap().set_status("pvmove done.");
+        }
+    }
+
+    //
+    // Running pvmoves, for the footer. True if one ended since last read,
+    // on error the ones known are kept.
+    //
+    fn refresh_pvmoves(&mut self) -> bool {
+        let Ok(lvs) = self.lvm.get_lvs() else {
+            return false;
+        };
+        let running = self.pvmoves.len();
+        self.pvmoves = lvs
+            .into_iter()
+            .filter(|lv| !lv.move_pv.is_empty())
+            .collect();
+        self.pvmoves.len() < running
+    }
+
     pub fn next_row(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i >= self.items.len() - 1 {
@@ -248,6 +1008,9 @@ impl LvmApp<'_> {
     }
 
     pub fn previous_row(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -276,36 +1039,238 @@ impl LvmApp<'_> {
 
     pub fn acton_cell(&mut self) {
         if self.view_type == ViewType::VgOverview {
-            let ic: (usize, usize) = match self.state.selected_cell() {
-                Some(ic) => ic,
-                None => (0, 0),
-            };
+            let ic: (usize, usize) = self.state.selected_cell().unwrap_or_default();
 
-            let item: &VgTableData = self.items.get(ic.0).expect("Unexpected error");
+            let Some(item) = self.items.get(ic.0) else {
+                return;
+            };
             match ic.1 {
                 0 => {
-                    if !item.vg_name.eq("") {
+                    if !item.vg_name.is_empty() {
                         // if cell is "", nothing to act on
-                        self.view_type = ViewType::VgInfo;
-                        self.sel_vg_name = item.vg_name.clone();
-                        self.vg_info_view = Some(VgInfoView::new(&self.sel_vg_name));
-                        self.vg_info_view.as_mut().unwrap().refresh_data();
+                        let mut vg_info_view = VgInfoView::new(&item.vg_name, self.lvm.clone())
+                            .thin_thresholds(self.thin_thresholds);
+                        match vg_info_view.fetch_data() {
+                            Ok(()) => {
+                                self.view_type = ViewType::VgInfo;
+                                self.sel_vg_name = item.vg_name.clone();
+                                self.vg_info_view = Some(vg_info_view);
+                            }
+                            Err(e) => self.error_popup = Some(e.to_string()),
+                        }
                     }
                     item.vg_name.clone()
                 }
                 1 => {
-                    self.view_type = ViewType::VgOverview;
+                    if !item.pv_name.is_empty() {
+                        let mut pv_info_view = PvInfoView::new(&item.pv_name, self.lvm.clone());
+                        match pv_info_view.fetch_data() {
+                            Ok(()) => {
+                                self.view_type = ViewType::PvInfo;
+                                self.pv_info_view = Some(pv_info_view);
+                            }
+                            Err(e) => self.error_popup = Some(e.to_string()),
+                        }
+                    }
                     item.pv_name.clone()
                 }
                 2 => {
-                    self.view_type = ViewType::VgOverview;
-                    item.lv_name.clone()
+                    let (vg_name, lv_name) = (item.vg_name.clone(), item.lv_name.clone());
+                    if !lv_name.is_empty() {
+                        self.open_lv_info(&vg_name, &lv_name, ViewType::VgOverview);
+                    }
+                    lv_name
                 }
                 _ => "".to_string(),
             };
         }
     }
 
+    //
+    // Show lv info, error popup if lv could not be fetched. Esc goes back to 'back'.
+    //
+    fn open_lv_info(&mut self, vg_name: &str, lv_name: &str, back: ViewType) {
+        let mut lv_info_view = LvInfoView::new(vg_name, lv_name, self.lvm.clone());
+        match lv_info_view.fetch_data() {
+            Ok(()) => {
+                self.view_type = ViewType::LvInfo;
+                self.lv_info_back = back;
+                self.lv_info_view = Some(lv_info_view);
+            }
+            Err(e) => self.error_popup = Some(e.to_string()),
+        }
+    }
+
+    //
+    // Start editing the vg or lv name in the selected overview cell.
+    //
+    fn rename_cell(&mut self) {
+        let (row, col) = self.state.selected_cell().unwrap_or_default();
+        let Some(item) = self.items.get(row) else {
+            return;
+        };
+        match col {
+            0 if !item.vg_name.is_empty() => {
+                let vgs: Vec<String> = self.items.iter().map(|i| i.vg_name.clone()).collect();
+                self.rename_edit = Some(RenameEdit::vg(
+                    &item.vg_name,
+                    &vgs,
+                    self.colors.clone(),
+                    self.lvm.clone(),
+                ));
+            }
+            2 if !item.lv_name.is_empty() => match self.lvm.get_lvs() {
+                Ok(lvs) => {
+                    let lvs = lvm::get_lvinfo_by_vg(&item.vg_name, &lvs);
+                    match lvs.iter().find(|lv| lv.lv_name == item.lv_name) {
+                        Some(lv) if !lv.parent_lv.is_empty() => {
+                            self.error_popup = Some(format!(
+                                "{} is part of {}, it is renamed with it.",
+                                lv.lv_name, lv.parent_lv
+                            ));
+                        }
+                        Some(lv) => {
+                            self.rename_edit = Some(RenameEdit::lv(
+                                lv,
+                                &lvs,
+                                self.colors.clone(),
+                                self.lvm.clone(),
+                            ));
+                        }
+                        None => (),
+                    }
+                }
+                Err(e) => self.error_popup = Some(e.to_string()),
+            },
+            _ => STATUS
+                .lock()
+                .unwrap()
+                .set_status("Select a vg or lv name to rename."),
+        }
+    }
+
+    //
+    // Form for a new vg, or for more pvs of 'extend_vg'.
+    //
+    fn open_vg_create(&mut self, extend_vg: Option<&str>) {
+        let mut vg_create_view = VgCreateView::new(self.lvm.clone());
+        if let Some(vg_name) = extend_vg {
+            vg_create_view = vg_create_view.extend(vg_name);
+        }
+        match vg_create_view.fetch_data() {
+            Ok(()) => {
+                self.view_type = ViewType::VgCreate;
+                self.vg_create_view = Some(vg_create_view);
+            }
+            Err(e) => self.error_popup = Some(e.to_string()),
+        }
+    }
+
+    fn extend_cell(&mut self) {
+        let (row, col) = self.state.selected_cell().unwrap_or_default();
+        match self.items.get(row) {
+            Some(item) if col == 0 && !item.vg_name.is_empty() => {
+                let vg_name = item.vg_name.clone();
+                self.open_vg_create(Some(&vg_name));
+            }
+            _ => STATUS
+                .lock()
+                .unwrap()
+                .set_status("Select a vg name to extend."),
+        }
+    }
+
+    //
+    // vgreduce the pv in the selected cell, asked only if it is empty and not
+    // the last one.
+    //
+    fn reduce_cell(&mut self) {
+        let (row, col) = self.state.selected_cell().unwrap_or_default();
+        let pv_name = match self.items.get(row) {
+            Some(item) if col == 1 && !item.pv_name.is_empty() => item.pv_name.clone(),
+            _ => {
+                STATUS
+                    .lock()
+                    .unwrap()
+                    .set_status("Select a pv name to remove from its vg.");
+                return;
+            }
+        };
+        let pvs = match self.lvm.get_pvs() {
+            Ok(pvs) => pvs,
+            Err(e) => {
+                self.error_popup = Some(e.to_string());
+                return;
+            }
+        };
+        let Some(pv) = pvs.iter().find(|pv| pv.pv_name == pv_name) else {
+            return;
+        };
+        let in_vg = pvs.iter().filter(|p| p.vg_name == pv.vg_name).count();
+        if pv.vg_name.is_empty() {
+            self.error_popup = Some(format!("{pv_name} is in no vg."));
+        } else if pv.pe_alloc_count > 0 {
+            self.error_popup = Some(format!(
+                "{pv_name} has {} extents in use, move them off first (F12).",
+                pv.pe_alloc_count
+            ));
+        } else if in_vg == 1 {
+            self.error_popup = Some(format!(
+                "{pv_name} is the last pv of {}, remove the vg instead.",
+                pv.vg_name
+            ));
+        } else {
+            self.vg_reduce_popup = Some(VgReducePopup::new(
+                pv,
+                self.colors.clone(),
+                self.lvm.clone(),
+            ));
+        }
+    }
+
+    //
+    // pvmove off 'pv_name', 'range' picks a segment. With a move running in
+    // its vg asks to abort that instead.
+    //
+    fn open_pv_move(&mut self, pv_name: &str, range: Option<(u64, u64)>, back: ViewType) {
+        let mut pv_move_view = PvMoveView::new(pv_name, self.lvm.clone());
+        if let Some((first, last)) = range {
+            pv_move_view = pv_move_view.range(first, last);
+        }
+        if let Err(e) = pv_move_view.fetch_data() {
+            self.error_popup = Some(e.to_string());
+            return;
+        }
+        match pv_move_view.running() {
+            Some(mv) => {
+                self.pv_move_abort_popup = Some(PvMoveAbortPopup::new(
+                    mv,
+                    self.colors.clone(),
+                    self.lvm.clone(),
+                ));
+            }
+            None => {
+                self.view_type = ViewType::PvMove;
+                self.pv_move_back = back;
+                self.pv_move_view = Some(pv_move_view);
+            }
+        }
+    }
+
+    fn move_cell(&mut self) {
+        let (row, col) = self.state.selected_cell().unwrap_or_default();
+        match self.items.get(row) {
+            Some(item) if col == 1 && !item.pv_name.is_empty() => {
+                let pv_name = item.pv_name.clone();
+                self.open_pv_move(&pv_name, None, ViewType::VgOverview);
+            }
+            _ => STATUS
+                .lock()
+                .unwrap()
+                .set_status("Select a pv name to move extents off."),
+        }
+    }
+
     // Handle events for the whole app. Also responsible for init of 'views'.
     pub fn run(mut self, mut terminal: DefaultTerminal) -> color_eyre::Result<()> {
         // "clear" status bar every STATUS_RESET_INTERVALs (15).
@@ -320,18 +1285,30 @@ impl LvmApp<'_> {
             }
         });
 
+        let mut last_sync = Instant::now();
         loop {
             terminal.draw(|frame| self.draw(frame))?;
             self.clear_flags();
 
-            if let Event::Key(key) = event::read()? {
+            // Never block on a key, jobs finish and a raid sync or pvmove
+            // moves on meanwhile. Progress is read every SYNC_POLL_INTERVAL.
+            if event::poll(time::Duration::from_millis(EVENT_POLL_INTERVAL))?
+                && let Event::Key(key) = event::read()?
+            {
                 match self.handle_events(&key) {
                     Ok(true) => return color_eyre::eyre::Ok(()),
                     Ok(false) => (), // keep going
                     Err(_) => todo!("throw back error..."),
                 }
             }
+            let syncing = self.raid_syncing() || !self.pvmoves.is_empty();
+            if syncing && last_sync.elapsed().as_secs() >= SYNC_POLL_INTERVAL {
+                self.refresh_sync_progress();
+                last_sync = Instant::now();
+            }
 
+            self.poll_jobs();
+            self.poll_watcher();
             if self.refresh_lvm_data {
                 self.refresh_data();
             }
@@ -366,18 +1343,119 @@ impl LvmApp<'_> {
             self.render_scrollbar(frame, table_area);
         } else if self.view_type == ViewType::VgInfo {
             // inner layout to hold vginfo
-            let inner_layout = &Layout::vertical([Length(8), Min(15), Max(10)]).margin(1);
+            let inner_layout = &Layout::vertical([Length(8), Min(6), Max(10)]).margin(1);
             let vg_info_layout: [Rect; 3] = inner_layout.areas(outer_layout[0]);
             frame.render_widget(table_block, outer_layout[0]);
             let vg_view = self.vg_info_view.as_mut().unwrap();
-            vg_view.render(frame, &vg_info_layout);
+            vg_view.render(frame, &vg_info_layout, self.rename_edit.as_ref());
+        } else if self.view_type == ViewType::PvInfo {
+            // inner layout to hold pvinfo and segments on it
+            let inner_layout = &Layout::vertical([Length(12), Min(5)]).margin(1);
+            let pv_info_layout: [Rect; 2] = inner_layout.areas(outer_layout[0]);
+            frame.render_widget(table_block, outer_layout[0]);
+            let pv_view = self.pv_info_view.as_mut().unwrap();
+            pv_view.render(frame, &pv_info_layout);
+        } else if self.view_type == ViewType::LvInfo {
+            // inner layout to hold lvinfo and its segments
+            let inner_layout = &Layout::vertical([Length(16), Min(3)]).margin(1);
+            let lv_info_layout: [Rect; 2] = inner_layout.areas(outer_layout[0]);
+            frame.render_widget(table_block, outer_layout[0]);
+            let lv_view = self.lv_info_view.as_mut().unwrap();
+            lv_view.render(frame, &lv_info_layout);
         } else if self.view_type == ViewType::LvNew {
             let lv_new_view = self.lv_new_view.as_mut().unwrap();
             frame.render_widget(table_block, outer_layout[0]);
             lv_new_view.render(frame, &outer_layout[0]);
+        } else if self.view_type == ViewType::LvResize {
+            let lv_resize_view = self.lv_resize_view.as_mut().unwrap();
+            frame.render_widget(table_block, outer_layout[0]);
+            lv_resize_view.render(frame, &outer_layout[0]);
+        } else if self.view_type == ViewType::SnapNew {
+            let snap_new_view = self.snap_new_view.as_mut().unwrap();
+            frame.render_widget(table_block, outer_layout[0]);
+            snap_new_view.render(frame, &outer_layout[0]);
+        } else if self.view_type == ViewType::CacheAttach {
+            let cache_attach_view = self.cache_attach_view.as_mut().unwrap();
+            frame.render_widget(table_block, outer_layout[0]);
+            cache_attach_view.render(frame, &outer_layout[0]);
+        } else if self.view_type == ViewType::LvConvert {
+            let lv_convert_view = self.lv_convert_view.as_mut().unwrap();
+            frame.render_widget(table_block, outer_layout[0]);
+            lv_convert_view.render(frame, &outer_layout[0]);
+        } else if self.view_type == ViewType::VgCreate {
+            let vg_create_view = self.vg_create_view.as_mut().unwrap();
+            frame.render_widget(table_block, outer_layout[0]);
+            vg_create_view.render(frame, &outer_layout[0]);
+        } else if self.view_type == ViewType::PvMove {
+            let pv_move_view = self.pv_move_view.as_mut().unwrap();
+            frame.render_widget(table_block, outer_layout[0]);
+            pv_move_view.render(frame, &outer_layout[0]);
+        } else if self.view_type == ViewType::Jobs {
+            let job_list_view = self.job_list_view.as_mut().unwrap();
+            frame.render_widget(table_block, outer_layout[0]);
+            job_list_view.render(frame, &outer_layout[0]);
         }
 
         self.render_footer(frame, outer_layout[1]);
+
+        if let Some(lv_remove_popup) = self.lv_remove_popup.as_ref() {
+            lv_remove_popup.render(frame);
+        }
+        if let Some(rename_edit) = self.rename_edit.as_ref() {
+            rename_edit.render(frame);
+        }
+        if let Some(snap_merge_popup) = self.snap_merge_popup.as_ref() {
+            snap_merge_popup.render(frame);
+        }
+        if let Some(cache_detach_popup) = self.cache_detach_popup.as_ref() {
+            cache_detach_popup.render(frame);
+        }
+        if let Some(raid_action_popup) = self.raid_action_popup.as_ref() {
+            raid_action_popup.render(frame);
+        }
+        if let Some(vg_reduce_popup) = self.vg_reduce_popup.as_ref() {
+            vg_reduce_popup.render(frame);
+        }
+        if let Some(pv_move_abort_popup) = self.pv_move_abort_popup.as_ref() {
+            pv_move_abort_popup.render(frame);
+        }
+        if let Some(export_popup) = self.export_popup.as_ref() {
+            export_popup.render(frame);
+        }
+        if let Some(attr) = self.legend_popup.as_ref() {
+            self.render_legend_popup(frame, attr);
+        }
+        if let Some(msg) = self.error_popup.as_ref() {
+            self.render_error_popup(frame, msg);
+        }
+    }
+
+    fn render_legend_popup(&self, frame: &mut Frame, attr: &str) {
+        let area = frame.area();
+        let popup_area = Rect {
+            x: area.width / 6,
+            y: area.height / 6,
+            width: area.width * 2 / 3,
+            height: 18.min(area.height),
+        };
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(attrbadge::lv_attr_legend(attr))
+            .title(format!(" lv_attr {attr} "));
+        frame.render_widget(popup, popup_area);
+    }
+
+    fn render_error_popup(&self, frame: &mut Frame, msg: &str) {
+        let area = frame.area();
+        let popup_area = Rect {
+            x: area.width / 4,
+            y: area.height / 3,
+            width: area.width / 2,
+            height: area.height / 2,
+        };
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(Text::from(msg.to_string()))
+            .title(" Error ");
+        frame.render_widget(popup, popup_area);
     }
 
     fn render_table(&mut self, sb: Block, frame: &mut Frame, area: Rect) {
@@ -398,6 +1476,8 @@ impl LvmApp<'_> {
             .collect::<Row>()
             .style(header_style)
             .height(1);
+        // Name being renamed is edited in its cell.
+        let editing = self.rename_edit.as_ref().zip(self.state.selected_cell());
         let rows = self.items.iter().enumerate().map(|(i, data)| {
             let color = match i % 2 {
                 0 => self.colors.normal_row_color,
@@ -405,7 +1485,11 @@ impl LvmApp<'_> {
             };
             let item = data.ref_array();
             item.into_iter()
-                .map(|content| Cell::from(Text::from(format!("{content}"))))
+                .enumerate()
+                .map(|(col, content)| match editing {
+                    Some((rename, cell)) if cell == (i, col) => Cell::from(rename.input_line()),
+                    _ => Cell::from(Text::from(content.to_string())),
+                })
                 .collect::<Row>()
                 .style(Style::new().fg(self.colors.row_fg).bg(color))
                 .height(1)
@@ -474,10 +1558,38 @@ impl LvmApp<'_> {
         let msec = Span::from("Mark/sel").style(s2);
         let f6 = Span::from(" F6").style(s1);
         let save = Span::from("Save").style(s2);
+        let f2 = Span::from(" F2").style(s1);
+        let rename = Span::from("Rename").style(s2);
+        let f3 = Span::from(" F3").style(s1);
+        let snap = Span::from("Snap").style(s2);
+        let f4 = Span::from(" F4").style(s1);
+        let resize = Span::from("Resize").style(s2);
+        let f5 = Span::from(" F5").style(s1);
+        let merge = Span::from("Merge").style(s2);
         let f7 = Span::from(" F7").style(s1);
         let new = Span::from("New").style(s2);
+        let f8 = Span::from(" F8").style(s1);
+        let remove = Span::from("Remove").style(s2);
+        let f9 = Span::from(" F9").style(s1);
+        let cache = Span::from("Cache").style(s2);
+        let f10 = Span::from(" F10").style(s1);
+        let raid = Span::from("Raid").style(s2);
+        let f11 = Span::from(" F11").style(s1);
+        let convert = Span::from("Convert").style(s2);
+        let f12 = Span::from(" F12").style(s1);
+        let pvmove = Span::from("Move").style(s2);
+        let j = Span::from(" j").style(s1);
+        let jobs = Span::from("Jobs").style(s2);
+        let x = Span::from(" x").style(s1);
+        let export = Span::from("Export").style(s2);
+        let f1 = Span::from(" F1").style(s1);
+        let attr = Span::from("Attr").style(s2);
 
-        let line = Line::from(vec![esq, quit, tab, tabtxt, spc, msec, f6, save, f7, new]);
+        let line = Line::from(vec![
+            esq, quit, tab, tabtxt, spc, msec, f6, save, f2, rename, f3, snap, f4, resize, f5,
+            merge, f7, new, f8, remove, f9, cache, f10, raid, f11, convert, f12, pvmove, f1, attr,
+            j, jobs, x, export,
+        ]);
         let w = line.width() as u16;
 
         let info_footer = Paragraph::new(line)
@@ -493,20 +1605,121 @@ impl LvmApp<'_> {
             .horizontal_margin(1)
             .spacing(2);
 
-        let [action_area, status_area] = layout.areas(area);
+        let [keys_area, moves_area] = Layout::vertical([Length(1), Length(1)]).areas(area);
+        let [action_area, status_area] = layout.areas(keys_area);
 
         frame.render_widget(info_footer, action_area);
 
         let status = STATUS.lock().unwrap().status().clone();
         let sb = StatusBar::new(self.colors.clone()).content(status);
         frame.render_widget(sb, status_area);
+
+        // Running jobs first, then the pvmove bars.
+        let running = self.jobs.running();
+        let moves_area = if running > 0 {
+            let text = format!(
+                "{running} job{} running",
+                if running == 1 { "" } else { "s" }
+            );
+            let [jobs_area, moves_area] = Layout::horizontal([Length(text.width() as u16), Min(0)])
+                .horizontal_margin(1)
+                .areas(moves_area);
+            frame.render_widget(
+                Span::from(text).style(Style::new().fg(self.colors.selected_row_style_fg)),
+                jobs_area,
+            );
+            moves_area
+        } else {
+            moves_area
+        };
+        self.render_pvmoves(frame, moves_area);
+    }
+
+    //
+    // A bar per running pvmove, under the keys whatever view is shown.
+    //
+    fn render_pvmoves(&self, frame: &mut Frame, area: Rect) {
+        let n = self.pvmoves.len() as u32;
+        if n == 0 {
+            return;
+        }
+        let layout = Layout::horizontal(vec![Constraint::Ratio(1, n); n as usize])
+            .horizontal_margin(1)
+            .spacing(2);
+        for (mv, mv_area) in self.pvmoves.iter().zip(layout.split(area).iter()) {
+            let percent = mv.copy_percent.unwrap_or(0.0);
+            let gauge = LineGauge::default()
+                .label(format!(
+                    "pvmove {} ({}) {percent:.1}%",
+                    mv.move_pv, mv.vg_name
+                ))
+                .style(Style::new().fg(self.colors.row_fg))
+                .filled_style(Style::new().fg(self.colors.selected_row_style_fg))
+                .unfilled_style(Style::new().fg(self.colors.alt_row_color))
+                .ratio((percent / 100.0).clamp(0.0, 1.0));
+            frame.render_widget(gauge, *mv_area);
+        }
     }
 }
 
-fn fetch_data(vgs: &mut Vec<VgTableData>) {
-    let vg_list = lvm::get_vgs();
-    let pv_list = lvm::get_pvs();
-    let lv_list = lvm::get_lvs();
+fn scroll_len(rows: usize) -> usize {
+    match rows {
+        // dont * with 0
+        0 | 1 => res::ITEM_HEIGHT,
+        _ => (rows - 1) * res::ITEM_HEIGHT,
+    }
+}
+
+//
+// Where row 'i' of the old rows is in the new ones: the same vg, pv and lv,
+// else the first row of its vg, else as near as there is.
+//
+fn same_row(old: &[VgTableData], i: usize, rows: &[VgTableData]) -> usize {
+    let near = i.min(rows.len().saturating_sub(1));
+    let Some(row) = old.get(i) else {
+        return near;
+    };
+    rows.iter()
+        .position(|r| r.ref_array() == row.ref_array())
+        .or_else(|| rows.iter().position(|r| r.vg_name == row.vg_name))
+        .unwrap_or(near)
+}
+
+//
+// Keys to a form (new lv, resize, ...), true when it is done and can be
+// dropped. The job it leaves is spawned. A form failing is told about in the
+// error popup and left, the tui goes on.
+//
+fn form_done<V: View>(
+    form: &mut V,
+    key: &KeyEvent,
+    take_job: fn(&mut V) -> Option<LvmJob>,
+    jobs: &Jobs,
+    error_popup: &mut Option<String>,
+) -> bool {
+    if key.kind != KeyEventKind::Press {
+        return false;
+    }
+    match form.handle_events(key) {
+        Ok(false) => false,
+        Ok(true) => {
+            if let Some(job) = take_job(form) {
+                jobs.spawn(job);
+            }
+            true
+        }
+        Err(e) => {
+            STATUS.lock().unwrap().set_status(e);
+            *error_popup = Some(e.to_string());
+            true
+        }
+    }
+}
+
+fn fetch_data(lvm: &dyn LvmBackend, vgs: &mut Vec<VgTableData>) -> Result<(), lvm::LvmError> {
+    let vg_list = lvm.get_vgs()?;
+    let pv_list = lvm.get_pvs()?;
+    let lv_list = lvm.get_lvs()?;
 
     for vg_name in vg_list {
         let pvs_in_vg: Vec<String> = lvm::find_pvs_by_vg(&vg_name, &pv_list);
@@ -525,7 +1738,7 @@ fn fetch_data(vgs: &mut Vec<VgTableData>) {
         for lv_name in lvs_in_vg {
             // Go though existing rows, if find space i.e. "", update row,
             // if no empty lv_names remaining, add new row.
-            if rows.last().is_none() || !rows.last().unwrap().lv_name.eq("") {
+            if rows.last().is_none() || !rows.last().unwrap().lv_name.is_empty() {
                 // Add new
                 let row: VgTableData = VgTableData {
                     vg_name: vg_name.clone(),
@@ -536,7 +1749,7 @@ fn fetch_data(vgs: &mut Vec<VgTableData>) {
             } else {
                 // Update existing
                 for row in rows.iter_mut() {
-                    if row.lv_name.eq("") {
+                    if row.lv_name.is_empty() {
                         row.lv_name = lv_name.clone();
                         break;
                     }
@@ -545,7 +1758,7 @@ fn fetch_data(vgs: &mut Vec<VgTableData>) {
         }
 
         // If no match, put row with vgname only
-        if rows.len() < 1 {
+        if rows.is_empty() {
             let row: VgTableData = VgTableData {
                 vg_name: vg_name.clone(),
                 pv_name: String::from(""),
@@ -556,6 +1769,8 @@ fn fetch_data(vgs: &mut Vec<VgTableData>) {
             vgs.append(&mut rows);
         }
     }
+
+    Ok(())
 }
 
 fn constraint_len_calculator(items: &[VgTableData]) -> (u16, u16, u16) {
@@ -592,8 +1807,1080 @@ fn constraint_len_calculator(items: &[VgTableData]) -> (u16, u16, u16) {
 #[cfg(test)]
 mod tests {
 
+    use std::sync::{Arc, mpsc};
+    use std::time::{Duration, Instant};
+
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::{Terminal, backend::TestBackend};
+
+    use crate::lvm::{
+        LvmBackend, LvmError, LvmExtraArg, fake::FakeBackend, replay::ReplayBackend,
+        watch::LvmWatcher,
+    };
+    use crate::lvmapp::exportview::DEFAULT_EXPORT_PATH;
+    use crate::lvmapp::jobs::{Jobs, LvmJob};
+    use crate::lvmapp::thinpool::ThinThresholds;
+    use crate::lvmapp::{LvmApp, View, ViewType, form_done};
+
+    fn press(app: &mut LvmApp, code: KeyCode) -> bool {
+        app.handle_events(&KeyEvent::new(code, KeyModifiers::NONE))
+            .unwrap()
+    }
+
     #[test]
     fn something() {
         ////////////////////
     }
+
+    #[test]
+    fn test_create_lv_on_fake() {
+        let fake = Arc::new(FakeBackend::new());
+        fake.add_vg("vg01", &[("/dev/sda1", 10 * 1024 * 1024 * 1024)]);
+        let lvm: Arc<dyn LvmBackend> = fake.clone();
+        let mut app = LvmApp::new(lvm);
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        assert!(app.error_popup.is_none());
+
+        // Into vg view, then new lv
+        press(&mut app, KeyCode::Enter);
+        assert!(app.view_type == ViewType::VgInfo);
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        press(&mut app, KeyCode::F(7));
+        assert!(app.view_type == ViewType::LvNew);
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        for c in "lvtest".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Char('2'));
+        press(&mut app, KeyCode::F(6));
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        press(&mut app, KeyCode::Enter);
+        app.wait_jobs();
+        assert!(app.view_type == ViewType::VgInfo);
+        app.refresh_data();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        let lvs = fake.get_lvs().unwrap();
+        assert_eq!(lvs.len(), 1);
+        assert_eq!(lvs[0].lv_name, "lvtest");
+        assert_eq!(lvs[0].size, 2 * 1024 * 1024 * 1024);
+        assert_eq!(app.items[0].lv_name, "lvtest");
+
+        // Back to overview and quit
+        assert!(!press(&mut app, KeyCode::Esc));
+        assert!(press(&mut app, KeyCode::Esc));
+    }
+
+    #[test]
+    fn test_pv_view_on_fake() {
+        let lvm: Arc<dyn LvmBackend> = Arc::new(FakeBackend::demo());
+        let mut app = LvmApp::new(lvm);
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        // pv column of first row
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.view_type == ViewType::PvInfo);
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        press(&mut app, KeyCode::Down);
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains(&app.items[0].pv_name));
+        assert!(screen.contains("allocatable"));
+
+        assert!(!press(&mut app, KeyCode::Esc));
+        assert!(app.view_type == ViewType::VgOverview);
+        assert!(app.pv_info_view.is_none());
+    }
+
+    #[test]
+    fn test_lv_view_on_fake() {
+        let lvm: Arc<dyn LvmBackend> = Arc::new(FakeBackend::demo());
+        let mut app = LvmApp::new(lvm);
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+
+        // From overview, lv column of first row
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.view_type == ViewType::LvInfo);
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains(&format!("/dev/{}/", app.items[0].vg_name)));
+
+        // attr legend, Esc closes it only
+        press(&mut app, KeyCode::F(1));
+        assert!(app.legend_popup.is_some());
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("volume type"));
+        assert!(!press(&mut app, KeyCode::Esc));
+        assert!(app.legend_popup.is_none());
+        assert!(app.view_type == ViewType::LvInfo);
+
+        assert!(!press(&mut app, KeyCode::Esc));
+        assert!(app.view_type == ViewType::VgOverview);
+
+        // From vg view, back to vg view
+        press(&mut app, KeyCode::Left);
+        press(&mut app, KeyCode::Left);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.view_type == ViewType::VgInfo);
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        press(&mut app, KeyCode::Enter);
+        assert!(app.view_type == ViewType::LvInfo);
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        assert!(!press(&mut app, KeyCode::Esc));
+        assert!(app.view_type == ViewType::VgInfo);
+        assert!(app.lv_info_view.is_none());
+    }
+    #[test]
+    fn test_remove_lv_on_fake() {
+        let fake = Arc::new(FakeBackend::demo());
+        let lvm: Arc<dyn LvmBackend> = fake.clone();
+        let mut app = LvmApp::new(lvm);
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        let vgroot_lvs = |fake: &FakeBackend| {
+            fake.get_lvs()
+                .unwrap()
+                .into_iter()
+                .filter(|lv| lv.vg_name == "vgroot")
+                .count()
+        };
+
+        // vgroot, first lv is home, open
+        press(&mut app, KeyCode::Enter);
+        assert!(app.view_type == ViewType::VgInfo);
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        press(&mut app, KeyCode::F(8));
+        assert!(app.lv_remove_popup.is_some());
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("type its name"));
+
+        // Enter does nothing until the name is typed, then lvremove refuses
+        press(&mut app, KeyCode::Enter);
+        assert!(app.lv_remove_popup.is_some());
+        for c in "home".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        app.wait_jobs();
+        assert!(app.lv_remove_popup.is_none());
+        assert!(app.error_popup.as_ref().unwrap().contains("in use"));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(vgroot_lvs(&fake), 3);
+
+        // Cancelled
+        fake.set_open("vgroot", "home", false);
+        app.refresh_data();
+        press(&mut app, KeyCode::F(8));
+        press(&mut app, KeyCode::Esc);
+        assert!(app.lv_remove_popup.is_none());
+        assert_eq!(vgroot_lvs(&fake), 3);
+
+        // Not in use, no data, Enter removes
+        press(&mut app, KeyCode::F(8));
+        press(&mut app, KeyCode::Enter);
+        app.wait_jobs();
+        assert!(app.lv_remove_popup.is_none());
+        assert!(app.error_popup.is_none());
+        assert!(app.refresh_lvm_data);
+        app.refresh_data();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        assert_eq!(vgroot_lvs(&fake), 2);
+        assert_eq!(app.vg_info_view.as_ref().unwrap().lvs().len(), 2);
+    }
+
+    #[test]
+    fn test_resize_lv_on_fake() {
+        let fake = Arc::new(FakeBackend::demo());
+        let lvm: Arc<dyn LvmBackend> = fake.clone();
+        let mut app = LvmApp::new(lvm);
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        let gb = 1024 * 1024 * 1024;
+        let home_size = |fake: &FakeBackend| {
+            fake.get_lvs()
+                .unwrap()
+                .into_iter()
+                .find(|lv| lv.vg_name == "vgroot" && lv.lv_name == "home")
+                .unwrap()
+                .size
+        };
+        let before = home_size(&fake);
+
+        // vgroot, first lv is home
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::F(4));
+        assert!(app.view_type == ViewType::LvResize);
+
+        // More than the vg has, no confirmation
+        for c in "+900".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("lvresize -y -L +900G vgroot/home"));
+        assert!(screen.contains("Not enough free space"));
+        press(&mut app, KeyCode::F(6));
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(!screen.contains("You are about to resize"));
+
+        // +10G
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Char('1'));
+        press(&mut app, KeyCode::Char('0'));
+        press(&mut app, KeyCode::F(6));
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("You are about to resize"));
+        press(&mut app, KeyCode::Enter);
+        app.wait_jobs();
+        assert!(app.view_type == ViewType::VgInfo);
+        assert!(app.lv_resize_view.is_none());
+        assert!(app.refresh_lvm_data);
+        assert_eq!(home_size(&fake), before + 10 * gb);
+    }
+
+    #[test]
+    fn test_rename_on_fake() {
+        let fake = Arc::new(FakeBackend::demo());
+        let lvm: Arc<dyn LvmBackend> = fake.clone();
+        let mut app = LvmApp::new(lvm);
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        let vgroot_lvs = |fake: &FakeBackend| {
+            let mut names: Vec<String> = fake
+                .get_lvs()
+                .unwrap()
+                .into_iter()
+                .filter(|lv| lv.vg_name == "vgroot")
+                .map(|lv| lv.lv_name)
+                .collect();
+            names.sort();
+            names
+        };
+
+        // vgroot, first lv is home, edited in place
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::F(2));
+        assert!(app.rename_edit.is_some());
+        for _ in 0.."home".len() {
+            press(&mut app, KeyCode::Backspace);
+        }
+        // Taken, stays in edit
+        for c in "root".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert!(app.rename_edit.is_some());
+        // Not allowed chars are not taken
+        press(&mut app, KeyCode::Char('/'));
+        press(&mut app, KeyCode::Char('2'));
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("root2"));
+        press(&mut app, KeyCode::Enter);
+        assert!(app.rename_edit.is_none());
+        app.wait_jobs();
+        assert!(app.refresh_lvm_data);
+        assert_eq!(vgroot_lvs(&fake), vec!["root", "root2", "swap"]);
+
+        // Cancelled
+        press(&mut app, KeyCode::F(2));
+        press(&mut app, KeyCode::Char('x'));
+        press(&mut app, KeyCode::Esc);
+        assert!(app.rename_edit.is_none());
+        assert!(app.view_type == ViewType::VgInfo);
+        assert_eq!(vgroot_lvs(&fake), vec!["root", "root2", "swap"]);
+
+        // vg from the overview, first cell is vgroot
+        press(&mut app, KeyCode::Esc);
+        assert!(app.view_type == ViewType::VgOverview);
+        press(&mut app, KeyCode::F(2));
+        for c in "-sys".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        app.wait_jobs();
+        assert!(app.error_popup.is_none());
+        assert!(fake.get_vgs().unwrap().contains(&"vgroot-sys".to_string()));
+    }
+
+    #[test]
+    fn test_snapshot_on_fake() {
+        let fake = Arc::new(FakeBackend::demo());
+        let lvm: Arc<dyn LvmBackend> = fake.clone();
+        let mut app = LvmApp::new(lvm);
+        let mut terminal = Terminal::new(TestBackend::new(160, 30)).unwrap();
+        let vgroot_lv = |fake: &FakeBackend, name: &str| {
+            fake.get_lvs()
+                .unwrap()
+                .into_iter()
+                .find(|lv| lv.vg_name == "vgroot" && lv.lv_name == name)
+        };
+
+        // vgroot, first lv is home
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::F(3));
+        assert!(app.view_type == ViewType::SnapNew);
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("lvcreate -s -l 20%ORIGIN -n home_snap vgroot/home"));
+        press(&mut app, KeyCode::F(6));
+        press(&mut app, KeyCode::Enter);
+        app.wait_jobs();
+        assert!(app.view_type == ViewType::VgInfo);
+        assert!(app.refresh_lvm_data);
+        let snap = vgroot_lv(&fake, "home_snap").unwrap();
+        assert_eq!(snap.origin, "home");
+        app.refresh_data();
+
+        // Second one gets a free name, listed below its origin
+        press(&mut app, KeyCode::F(3));
+        press(&mut app, KeyCode::F(6));
+        press(&mut app, KeyCode::Enter);
+        app.wait_jobs();
+        assert!(vgroot_lv(&fake, "home_snap1").is_some());
+        fake.set_snap_percent("vgroot", "home_snap1", 100.0);
+        app.refresh_data();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("2 snapshots"));
+        assert!(screen.contains("└ home_snap1"));
+        assert!(screen.contains("INVALID"));
+        let names: Vec<&str> = app
+            .vg_info_view
+            .as_ref()
+            .unwrap()
+            .lvs()
+            .iter()
+            .map(|lv| lv.lv_name.as_str())
+            .collect();
+        assert_eq!(names, ["home", "home_snap", "home_snap1", "root", "swap"]);
+
+        // Not a snapshot, or invalid, no merge
+        press(&mut app, KeyCode::F(5));
+        assert!(app.error_popup.is_some());
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::F(5));
+        assert!(app.snap_merge_popup.is_none());
+        assert!(app.error_popup.is_some());
+        press(&mut app, KeyCode::Esc);
+
+        // home is open, merge waits for the next activation
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::F(5));
+        assert!(app.snap_merge_popup.is_some());
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("merge starts on its next activation"));
+        press(&mut app, KeyCode::Enter);
+        app.wait_jobs();
+        assert!(app.snap_merge_popup.is_none());
+        assert!(app.error_popup.is_none());
+        let snap = vgroot_lv(&fake, "home_snap").unwrap();
+        assert!(snap.attr.starts_with('S'));
+    }
+
+    #[test]
+    fn test_thin_on_fake() {
+        let fake = Arc::new(FakeBackend::new());
+        fake.add_vg("vg01", &[("/dev/sda1", 10 * 1024 * 1024 * 1024)]);
+        let lvm: Arc<dyn LvmBackend> = fake.clone();
+        let mut app = LvmApp::new(lvm).thin_thresholds(ThinThresholds {
+            data: 90.0,
+            metadata: 80.0,
+        });
+        let mut terminal = Terminal::new(TestBackend::new(160, 40)).unwrap();
+
+        // No pool yet, thin is refused before the confirmation
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::F(7));
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        press(&mut app, KeyCode::Char('t'));
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Char('1'));
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Tab);
+        for _ in 0..7 {
+            press(&mut app, KeyCode::Down);
+        }
+        press(&mut app, KeyCode::F(6));
+        press(&mut app, KeyCode::Enter);
+        assert!(app.view_type == ViewType::LvNew);
+        assert!(fake.get_lvs().unwrap().is_empty());
+        press(&mut app, KeyCode::Esc);
+        assert!(app.view_type == ViewType::VgInfo);
+
+        let mut create = |app: &mut LvmApp, name: &str, size: char, segtype_downs| {
+            press(app, KeyCode::F(7));
+            assert!(app.view_type == ViewType::LvNew);
+            terminal.draw(|frame| app.draw(frame)).unwrap();
+            for c in name.chars() {
+                press(app, KeyCode::Char(c));
+            }
+            press(app, KeyCode::Tab);
+            press(app, KeyCode::Char(size));
+            press(app, KeyCode::Tab);
+            press(app, KeyCode::Tab);
+            for _ in 0..segtype_downs {
+                press(app, KeyCode::Down);
+            }
+            terminal.draw(|frame| app.draw(frame)).unwrap();
+            press(app, KeyCode::F(6));
+            press(app, KeyCode::Enter);
+            app.wait_jobs();
+            assert!(app.view_type == ViewType::VgInfo);
+            app.refresh_data();
+            format!("{:?}", terminal.backend().buffer())
+        };
+
+        let screen = create(&mut app, "pool0", '2', 6);
+        assert!(screen.contains("discards:"));
+        let screen = create(&mut app, "thin1", '5', 7);
+        assert!(screen.contains("pool0"));
+        let lvs = fake.get_lvs().unwrap();
+        let thin1 = lvs.iter().find(|lv| lv.lv_name == "thin1").unwrap();
+        assert_eq!(thin1.pool_lv, "pool0");
+        assert_eq!(thin1.size, 5 * 1024 * 1024 * 1024);
+
+        // Over provisioned 2.5x, thin lv under its pool, warned from 90%
+        fake.set_data_percent("vg01", "pool0", 95.0, Some(10.0));
+        app.refresh_data();
+        // Below the hidden [pool0_tdata] and [pool0_tmeta]
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("2.50x"));
+        assert!(screen.contains("└ thin1"));
+        assert!(screen.contains("Data 95.0% over 90%"));
+        assert!(!screen.contains("Metadata 10%"));
+    }
+
+    #[test]
+    fn test_cache_on_fake() {
+        let gb = 1024 * 1024 * 1024;
+        let fake = Arc::new(FakeBackend::new());
+        fake.add_vg("vg01", &[("/dev/sda1", 10 * gb), ("/dev/sdb1", 2 * gb)]);
+        fake.create_lv("lvhdd", "vg01", 4, "G", "linear", &[], &[])
+            .unwrap();
+        let lvm: Arc<dyn LvmBackend> = fake.clone();
+        let mut app = LvmApp::new(lvm);
+        let mut terminal = Terminal::new(TestBackend::new(160, 40)).unwrap();
+        let lvhdd = |fake: &FakeBackend| {
+            fake.get_lvs()
+                .unwrap()
+                .into_iter()
+                .find(|lv| lv.lv_name == "lvhdd")
+                .unwrap()
+        };
+
+        // 1G writeback cache pool on the pv lvhdd is not on
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::F(9));
+        assert!(app.view_type == ViewType::CacheAttach);
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("lvcreate --type cache-pool -L 1G -n lvhdd_cache vg01 /dev/sdb1"));
+        for _ in 0..5 {
+            press(&mut app, KeyCode::Tab);
+        }
+        press(&mut app, KeyCode::Down);
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("--cachemode writeback"));
+        press(&mut app, KeyCode::F(6));
+        press(&mut app, KeyCode::Enter);
+        app.wait_jobs();
+        assert!(app.view_type == ViewType::VgInfo);
+        let lv = lvhdd(&fake);
+        assert_eq!(lv.segtype, "cache");
+        assert_eq!(lv.cache_mode, "writeback");
+        assert_eq!(lv.pool_lv, "[lvhdd_cache_cpool]");
+
+        // Stats in the table, lvhdd stays selected, hidden sub lvs sort first
+        fake.set_cache_stats("vg01", "lvhdd", 40.0, 75.0, 9);
+        app.refresh_data();
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::F(9));
+        assert!(app.error_popup.is_some());
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Down);
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("hit 75% dirty 9"));
+        assert!(screen.contains("75000 hit 25000 miss"));
+
+        // Uncache, dirty blocks are mentioned
+        press(&mut app, KeyCode::F(9));
+        assert!(app.cache_detach_popup.is_some());
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("9 dirty blocks are written back first"));
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Enter);
+        app.wait_jobs();
+        assert!(app.cache_detach_popup.is_none());
+        assert_eq!(lvhdd(&fake).segtype, "linear");
+        assert_eq!(fake.get_lvs().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_raid_on_fake() {
+        let gb = 1024 * 1024 * 1024;
+        let fake = Arc::new(FakeBackend::new());
+        fake.add_vg(
+            "vg01",
+            &[
+                ("/dev/sda1", 10 * gb),
+                ("/dev/sdb1", 10 * gb),
+                ("/dev/sdc1", 10 * gb),
+            ],
+        );
+        let mirrors = [LvmExtraArg {
+            opt: "--mirrors".to_string(),
+            value: "1".to_string(),
+        }];
+        let pvs = ["/dev/sda1".to_string(), "/dev/sdb1".to_string()];
+        fake.create_lv("lvr1", "vg01", 1, "G", "raid1", &pvs, &mirrors)
+            .unwrap();
+        let lvm: Arc<dyn LvmBackend> = fake.clone();
+        let mut app = LvmApp::new(lvm);
+        let mut terminal = Terminal::new(TestBackend::new(160, 40)).unwrap();
+        let screen = |app: &mut LvmApp, terminal: &mut Terminal<TestBackend>| {
+            terminal.draw(|frame| app.draw(frame)).unwrap();
+            format!("{:?}", terminal.backend().buffer())
+        };
+
+        // Hidden images sort first, lvr1 is last
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::F(10));
+        assert!(app.error_popup.is_some());
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Up);
+        assert!(screen(&mut app, &mut terminal).contains("in sync"));
+
+        // Check, progress is polled while it runs
+        press(&mut app, KeyCode::F(10));
+        assert!(app.raid_action_popup.is_some());
+        let s = screen(&mut app, &mut terminal);
+        assert!(s.contains("lvchange --syncaction check vg01/lvr1"));
+        assert!(s.contains("replace /dev/sdb1"));
+        press(&mut app, KeyCode::Enter);
+        assert!(app.raid_action_popup.is_none());
+        app.wait_jobs();
+        app.refresh_data();
+        assert!(app.raid_syncing());
+        assert!(screen(&mut app, &mut terminal).contains("check"));
+        press(&mut app, KeyCode::F(10));
+        assert!(app.error_popup.is_some());
+        press(&mut app, KeyCode::Esc);
+        while app.raid_syncing() {
+            app.refresh_sync_progress();
+        }
+        assert!(screen(&mut app, &mut terminal).contains("in sync"));
+
+        // A pv fails, the raid is repaired onto the free one
+        fake.fail_pv("/dev/sda1");
+        app.refresh_data();
+        let s = screen(&mut app, &mut terminal);
+        assert!(s.contains("DEGRADED"));
+        assert!(s.contains("/dev/sda1 MISSING"));
+        press(&mut app, KeyCode::F(10));
+        let s = screen(&mut app, &mut terminal);
+        assert!(s.contains("lvconvert -y --repair vg01/lvr1"));
+        assert!(!s.contains("lvchange"));
+        press(&mut app, KeyCode::Enter);
+        app.wait_jobs();
+        assert!(app.error_popup.is_none());
+        app.refresh_data();
+        assert!(app.raid_syncing());
+        let lvs = fake.get_lvs().unwrap();
+        let image = lvs
+            .iter()
+            .find(|lv| lv.lv_name == "[lvr1_rimage_0]")
+            .unwrap();
+        assert_eq!(image.lv_segs[0].pvdev, "/dev/sdc1");
+    }
+
+    #[test]
+    fn test_convert_on_fake() {
+        let gb = 1024 * 1024 * 1024;
+        let fake = Arc::new(FakeBackend::new());
+        fake.add_vg(
+            "vg01",
+            &[
+                ("/dev/sda1", 10 * gb),
+                ("/dev/sdb1", 10 * gb),
+                ("/dev/sdc1", 10 * gb),
+            ],
+        );
+        let sda1 = ["/dev/sda1".to_string()];
+        fake.create_lv("lvlin", "vg01", 2, "G", "linear", &sda1, &[])
+            .unwrap();
+        let lvm: Arc<dyn LvmBackend> = fake.clone();
+        let mut app = LvmApp::new(lvm);
+        let mut terminal = Terminal::new(TestBackend::new(160, 40)).unwrap();
+        let screen = |app: &mut LvmApp, terminal: &mut Terminal<TestBackend>| {
+            terminal.draw(|frame| app.draw(frame)).unwrap();
+            format!("{:?}", terminal.backend().buffer())
+        };
+
+        // Linear to raid1, the second image on the pv picked
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::F(11));
+        assert!(app.view_type == ViewType::LvConvert);
+        let s = screen(&mut app, &mut terminal);
+        assert!(s.contains("raid1, 2 copies, 2.00G per image"));
+        assert!(s.contains("lvconvert -y --type raid1 -m 1 vg01/lvlin"));
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char(' '));
+        let s = screen(&mut app, &mut terminal);
+        assert!(s.contains("lvconvert -y --type raid1 -m 1 vg01/lvlin /dev/sdc1"));
+        press(&mut app, KeyCode::F(6));
+        press(&mut app, KeyCode::Esc);
+        assert!(app.view_type == ViewType::LvConvert);
+        press(&mut app, KeyCode::F(6));
+        press(&mut app, KeyCode::Enter);
+        app.wait_jobs();
+        assert!(app.view_type == ViewType::VgInfo);
+        app.refresh_data();
+        assert!(app.raid_syncing());
+        let lvs = fake.get_lvs().unwrap();
+        let image = lvs
+            .iter()
+            .find(|lv| lv.lv_name == "[lvlin_rimage_1]")
+            .unwrap();
+        assert_eq!(image.lv_segs[0].pvdev, "/dev/sdc1");
+
+        // Images are not converted on their own, the raid not while syncing.
+        // Hidden images sort first, lvlin stays selected.
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::F(11));
+        assert!(app.error_popup.is_some());
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::F(11));
+        assert!(app.error_popup.is_some());
+        press(&mut app, KeyCode::Esc);
+        while app.raid_syncing() {
+            app.refresh_sync_progress();
+        }
+        assert!(screen(&mut app, &mut terminal).contains("in sync"));
+        press(&mut app, KeyCode::F(11));
+        assert!(app.view_type == ViewType::LvConvert);
+        assert!(screen(&mut app, &mut terminal).contains("drops 1 image, frees 2.01G"));
+    }
+
+    #[test]
+    fn test_vg_create_on_fake() {
+        let gb = 1024 * 1024 * 1024;
+        let fake = Arc::new(FakeBackend::new());
+        fake.add_vg("vg01", &[("/dev/sda1", 10 * gb)]);
+        fake.create_lv("lvdata", "vg01", 1, "G", "linear", &[], &[])
+            .unwrap();
+        fake.add_dev("/dev/sdh", 100 * gb, "WDC", None);
+        fake.add_dev("/dev/sdi", 100 * gb, "WDC", None);
+        fake.add_dev("/dev/sdj", 8 * gb, "USB", Some("vfat signature"));
+        let lvm: Arc<dyn LvmBackend> = fake.clone();
+        let mut app = LvmApp::new(lvm);
+        let mut terminal = Terminal::new(TestBackend::new(160, 40)).unwrap();
+        let screen = |app: &mut LvmApp, terminal: &mut Terminal<TestBackend>| {
+            terminal.draw(|frame| app.draw(frame)).unwrap();
+            format!("{:?}", terminal.backend().buffer())
+        };
+        let pvs_of = |fake: &FakeBackend, vg: &str| {
+            fake.get_pvs()
+                .unwrap()
+                .into_iter()
+                .filter(|pv| pv.vg_name == vg)
+                .count()
+        };
+
+        // New vg on sdh, default extent size and alloc
+        press(&mut app, KeyCode::F(7));
+        assert!(app.view_type == ViewType::VgCreate);
+        for c in "vg02".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        for _ in 0..5 {
+            press(&mut app, KeyCode::Tab);
+        }
+        assert!(screen(&mut app, &mut terminal).contains("Select the devices to use."));
+        press(&mut app, KeyCode::Char(' '));
+        let s = screen(&mut app, &mut terminal);
+        assert!(s.contains("pvcreate /dev/sdh"));
+        assert!(s.contains("vgcreate -s 4M --alloc normal vg02 /dev/sdh"));
+        assert!(s.contains("/dev/sdj 8.00G USB: vfat signature"));
+        press(&mut app, KeyCode::F(6));
+        press(&mut app, KeyCode::Enter);
+        app.wait_jobs();
+        assert!(app.view_type == ViewType::VgOverview);
+        assert!(app.refresh_lvm_data);
+        app.refresh_data();
+        assert_eq!(pvs_of(&fake, "vg02"), 1);
+
+        // Extended by sdi from the vg cell
+        let row = app.items.iter().position(|i| i.vg_name == "vg02").unwrap();
+        app.state.select_cell(Some((row, 0)));
+        press(&mut app, KeyCode::F(4));
+        assert!(app.view_type == ViewType::VgCreate);
+        assert!(screen(&mut app, &mut terminal).contains("EXTEND VOLUME GROUP vg02"));
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Tab);
+        screen(&mut app, &mut terminal);
+        press(&mut app, KeyCode::Char(' '));
+        assert!(screen(&mut app, &mut terminal).contains("vgextend vg02 /dev/sdi"));
+        press(&mut app, KeyCode::F(6));
+        press(&mut app, KeyCode::Enter);
+        app.wait_jobs();
+        app.refresh_data();
+        assert_eq!(pvs_of(&fake, "vg02"), 2);
+
+        // vgreduce of an empty pv, not of one in use or the last one
+        let row = app
+            .items
+            .iter()
+            .position(|i| i.pv_name == "/dev/sdi")
+            .unwrap();
+        app.state.select_cell(Some((row, 1)));
+        press(&mut app, KeyCode::F(8));
+        assert!(app.vg_reduce_popup.is_some());
+        press(&mut app, KeyCode::Enter);
+        assert!(app.vg_reduce_popup.is_none());
+        app.refresh_data();
+        assert_eq!(pvs_of(&fake, "vg02"), 1);
+        let row = app
+            .items
+            .iter()
+            .position(|i| i.pv_name == "/dev/sdh")
+            .unwrap();
+        app.state.select_cell(Some((row, 1)));
+        press(&mut app, KeyCode::F(8));
+        assert!(app.error_popup.as_ref().unwrap().contains("last pv"));
+        press(&mut app, KeyCode::Esc);
+        app.state.select_cell(Some((0, 1)));
+        press(&mut app, KeyCode::F(8));
+        assert!(app.error_popup.as_ref().unwrap().contains("in use"));
+    }
+
+    #[test]
+    fn test_pvmove_on_fake() {
+        let gb = 1024 * 1024 * 1024;
+        let fake = Arc::new(FakeBackend::new());
+        fake.add_vg("vg01", &[("/dev/sda", 10 * gb), ("/dev/sdb", 10 * gb)]);
+        fake.create_lv("lvdata", "vg01", 4, "G", "linear", &[], &[])
+            .unwrap();
+        let lvm: Arc<dyn LvmBackend> = fake.clone();
+        let mut app = LvmApp::new(lvm);
+        let mut terminal = Terminal::new(TestBackend::new(160, 40)).unwrap();
+        let screen = |app: &mut LvmApp, terminal: &mut Terminal<TestBackend>| {
+            terminal.draw(|frame| app.draw(frame)).unwrap();
+            format!("{:?}", terminal.backend().buffer())
+        };
+        let pv_of = |fake: &FakeBackend| {
+            let lvs = fake.get_lvs().unwrap();
+            let lv = lvs.iter().find(|lv| lv.lv_name == "lvdata").unwrap();
+            lv.lv_segs[0].pvdev.clone()
+        };
+
+        // All of sda, lvm picks sdb
+        let row = app
+            .items
+            .iter()
+            .position(|i| i.pv_name == "/dev/sda")
+            .unwrap();
+        app.state.select_cell(Some((row, 1)));
+        press(&mut app, KeyCode::F(12));
+        assert!(app.view_type == ViewType::PvMove);
+        assert!(screen(&mut app, &mut terminal).contains("pvmove -b /dev/sda"));
+        press(&mut app, KeyCode::F(6));
+        press(&mut app, KeyCode::Enter);
+        assert!(app.view_type == ViewType::VgOverview);
+        // Started as a job, it is in the job list
+        app.jobs.wait();
+        let done = app.jobs.take_finished();
+        assert_eq!(done[0].title, "Move 1024 extents off /dev/sda");
+        app.refresh_data();
+        assert_eq!(app.pvmoves.len(), 1);
+        assert!(screen(&mut app, &mut terminal).contains("pvmove /dev/sda (vg01)"));
+        for _ in 0..4 {
+            app.refresh_sync_progress();
+        }
+        assert!(app.pvmoves.is_empty());
+        assert!(!screen(&mut app, &mut terminal).contains("pvmove /dev/sda (vg01)"));
+        assert_eq!(pv_of(&fake), "/dev/sdb");
+
+        // The segment selected in pv info, then aborted
+        let row = app
+            .items
+            .iter()
+            .position(|i| i.pv_name == "/dev/sdb")
+            .unwrap();
+        app.state.select_cell(Some((row, 1)));
+        press(&mut app, KeyCode::Enter);
+        assert!(app.view_type == ViewType::PvInfo);
+        screen(&mut app, &mut terminal);
+        press(&mut app, KeyCode::F(12));
+        assert!(app.view_type == ViewType::PvMove);
+        assert!(screen(&mut app, &mut terminal).contains("pvmove -b /dev/sdb:0-1023"));
+        press(&mut app, KeyCode::F(6));
+        press(&mut app, KeyCode::Enter);
+        assert!(app.view_type == ViewType::PvInfo);
+        app.wait_jobs();
+        // Every read of the fake advances the copy, abort while it runs
+        press(&mut app, KeyCode::F(12));
+        assert!(app.pv_move_abort_popup.is_some());
+        assert!(screen(&mut app, &mut terminal).contains("Abort pvmove in vg01"));
+        press(&mut app, KeyCode::Enter);
+        assert!(app.pv_move_abort_popup.is_none());
+        app.wait_jobs();
+        assert!(app.error_popup.is_none());
+        app.refresh_data();
+        assert!(app.pvmoves.is_empty());
+        assert_eq!(pv_of(&fake), "/dev/sdb");
+    }
+    #[test]
+    fn test_jobs_on_fake() {
+        let fake = Arc::new(FakeBackend::new());
+        fake.add_vg("vg01", &[("/dev/sda", 1024 * 1024 * 1024)]);
+        let lvm: Arc<dyn LvmBackend> = fake.clone();
+        let mut app = LvmApp::new(lvm);
+        let mut terminal = Terminal::new(TestBackend::new(160, 40)).unwrap();
+        let screen = |app: &mut LvmApp, terminal: &mut Terminal<TestBackend>| {
+            terminal.draw(|frame| app.draw(frame)).unwrap();
+            format!("{:?}", terminal.backend().buffer())
+        };
+
+        // A job that runs until told to fail
+        let (tx, rx) = mpsc::channel::<()>();
+        app.jobs
+            .spawn(LvmJob::new("Resize LV vg01/lvslow", move || {
+                rx.recv().unwrap();
+                Err(LvmError::CommandFailed {
+                    cmd: "lvresize".to_string(),
+                    code: Some(5),
+                    stderr: "fsadm: resize2fs failed".to_string(),
+                })
+            }));
+        assert!(screen(&mut app, &mut terminal).contains("1 job running"));
+        // Not quitting while it runs
+        assert!(!press(&mut app, KeyCode::Esc));
+        app.refresh_lvm_data = false;
+
+        press(&mut app, KeyCode::Char('j'));
+        assert!(app.view_type == ViewType::Jobs);
+        let s = screen(&mut app, &mut terminal);
+        assert!(s.contains("Resize LV vg01/lvslow"));
+        assert!(s.contains("running"));
+
+        tx.send(()).unwrap();
+        app.wait_jobs();
+        assert!(app.refresh_lvm_data);
+        assert!(app.error_popup.as_ref().unwrap().contains("lvslow"));
+        press(&mut app, KeyCode::Esc);
+        let s = screen(&mut app, &mut terminal);
+        assert!(s.contains("failed"));
+        assert!(s.contains("resize2fs failed"));
+        assert!(!s.contains("job running"));
+
+        // Cleared, back to the overview and quit
+        press(&mut app, KeyCode::Char('c'));
+        assert!(!screen(&mut app, &mut terminal).contains("lvslow"));
+        press(&mut app, KeyCode::Esc);
+        assert!(app.view_type == ViewType::VgOverview);
+        assert!(press(&mut app, KeyCode::Esc));
+    }
+
+    #[test]
+    fn test_watch_keeps_selection() {
+        let gb = 1024 * 1024 * 1024;
+        let fake = Arc::new(FakeBackend::new());
+        fake.add_vg("vg01", &[("/dev/sda", 10 * gb)]);
+        fake.add_vg("vg02", &[("/dev/sdb", 10 * gb)]);
+        fake.create_lv("lvz", "vg02", 1, "G", "linear", &[], &[])
+            .unwrap();
+        let dir = std::env::temp_dir().join(format!("lvm-tui-app-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let watcher = LvmWatcher::start_on(&[&dir.to_string_lossy()], false).unwrap();
+        let lvm: Arc<dyn LvmBackend> = fake.clone();
+        let mut app = LvmApp::new(lvm).watcher(watcher);
+        let selected = |app: &LvmApp| {
+            let row = &app.items[app.state.selected().unwrap()];
+            (row.vg_name.clone(), row.pv_name.clone())
+        };
+        let changed_outside = |app: &mut LvmApp, name: &str| {
+            std::fs::write(dir.join(name), "").unwrap();
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while Instant::now() < deadline {
+                app.poll_watcher();
+                if app.items.len() != 2 {
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            panic!("no refresh on {name}");
+        };
+
+        // vg01 gets rows before sdb, sdb stays selected
+        press(&mut app, KeyCode::Down);
+        assert_eq!(selected(&app), ("vg02".to_string(), "/dev/sdb".to_string()));
+        for lv in ["lv1", "lv2"] {
+            fake.create_lv(lv, "vg01", 1, "G", "linear", &[], &[])
+                .unwrap();
+        }
+        changed_outside(&mut app, "vg01");
+        assert_eq!(app.items.len(), 3);
+        assert_eq!(app.state.selected(), Some(2));
+        assert_eq!(selected(&app), ("vg02".to_string(), "/dev/sdb".to_string()));
+
+        // So does lvz in vg info when lva comes first
+        press(&mut app, KeyCode::Enter);
+        assert!(app.view_type == ViewType::VgInfo);
+        fake.create_lv("lva", "vg02", 1, "G", "linear", &[], &[])
+            .unwrap();
+        app.refresh_data();
+        let vg_info_view = app.vg_info_view.as_ref().unwrap();
+        assert_eq!(vg_info_view.selected_lv().unwrap().lv_name, "lvz");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_export_on_fake() {
+        let fake = Arc::new(FakeBackend::new());
+        fake.add_vg("vg01", &[("/dev/sda", 1024 * 1024 * 1024)]);
+        let lvm: Arc<dyn LvmBackend> = fake.clone();
+        let mut app = LvmApp::new(lvm);
+        let dir = std::env::temp_dir().join(format!("lvm-tui-app-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("lvm.yaml").to_string_lossy().to_string();
+        let mut terminal = Terminal::new(TestBackend::new(160, 40)).unwrap();
+
+        // Path typed over the default, YAML from its extension
+        press(&mut app, KeyCode::Char('x'));
+        assert!(app.export_popup.is_some());
+        for _ in 0..DEFAULT_EXPORT_PATH.len() {
+            press(&mut app, KeyCode::Backspace);
+        }
+        for c in path.chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        assert!(format!("{:?}", terminal.backend().buffer()).contains("as YAML"));
+        press(&mut app, KeyCode::Enter);
+        assert!(app.export_popup.is_none());
+        assert!(app.error_popup.is_none());
+        let yaml = std::fs::read_to_string(&path).unwrap();
+        assert!(yaml.starts_with("vgs:\n- name: vg01\n"));
+
+        // The path is kept, the file there is only overwritten when confirmed
+        press(&mut app, KeyCode::Char('x'));
+        assert_eq!(app.export_popup.as_ref().unwrap().path(), path);
+        std::fs::write(&path, "mine").unwrap();
+        press(&mut app, KeyCode::Enter);
+        assert!(app.export_popup.is_some());
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        assert!(format!("{:?}", terminal.backend().buffer()).contains("Enter again overwrites"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "mine");
+        press(&mut app, KeyCode::Enter);
+        assert!(app.export_popup.is_none());
+        assert!(std::fs::read_to_string(&path).unwrap().starts_with("vgs:"));
+
+        // A failed write is told
+        press(&mut app, KeyCode::Char('x'));
+        std::fs::remove_dir_all(&dir).unwrap();
+        press(&mut app, KeyCode::Enter);
+        assert!(app.export_popup.is_none());
+        assert!(
+            app.error_popup
+                .as_ref()
+                .unwrap()
+                .starts_with("Export failed")
+        );
+    }
+
+    #[test]
+    fn test_read_only() {
+        let dir = std::env::temp_dir().join(format!("lvm-tui-app-replay-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("vgs"),
+            r#"{"report": [{"vg": [{"vg_name":"vg01", "vg_size":"10.00g", "vg_free":"8.00g", "pv_count":"1", "vg_attr":"wz--n-"}]}]}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("pvs"),
+            r#"{"report": [{"pv": [{"pv_name":"/dev/sda", "vg_name":"vg01", "pv_size":"10.00g", "pv_free":"8.00g", "vg_extent_size":"4.00m", "pv_attr":"a--"}]}]}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("lvs"),
+            r#"{"report": [{"lv": [{"lv_name":"lvdata", "vg_name":"vg01", "lv_size":"2.00g", "lv_attr":"-wi-a-----", "segtype":"linear", "seg_le_ranges":"/dev/sda:0-511"}]}]}"#,
+        )
+        .unwrap();
+        let lvm: Arc<dyn LvmBackend> = Arc::new(ReplayBackend::open(&dir).unwrap());
+        let mut app = LvmApp::new(lvm);
+        let mut terminal = Terminal::new(TestBackend::new(160, 40)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        assert!(format!("{:?}", terminal.backend().buffer()).contains("LVM-TUI (read only)"));
+
+        // Browsing works, changing is not offered
+        press(&mut app, KeyCode::F(7));
+        assert!(app.view_type == ViewType::VgOverview);
+        assert!(app.vg_create_view.is_none());
+        press(&mut app, KeyCode::Enter);
+        assert!(app.view_type == ViewType::VgInfo);
+        for key in [KeyCode::F(7), KeyCode::F(8), KeyCode::F(2)] {
+            press(&mut app, key);
+        }
+        assert!(app.view_type == ViewType::VgInfo);
+        assert!(app.lv_remove_popup.is_none());
+        assert!(app.rename_edit.is_none());
+        assert!(app.lv_new_view.is_none());
+        let vg_info_view = app.vg_info_view.as_ref().unwrap();
+        assert_eq!(vg_info_view.selected_lv().unwrap().lv_name, "lvdata");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    struct BrokenForm;
+
+    impl View for BrokenForm {
+        fn view_type(&self) -> ViewType {
+            ViewType::LvNew
+        }
+        fn refresh_data(&mut self) {}
+        fn handle_events(&mut self, _key: &KeyEvent) -> Result<bool, &'static str> {
+            Err("form is broken")
+        }
+    }
+
+    #[test]
+    fn test_form_error() {
+        // Told about and left, no panic
+        let jobs = Jobs::new();
+        let mut error_popup = None;
+        let key = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        assert!(form_done(
+            &mut BrokenForm,
+            &key,
+            |_| None,
+            &jobs,
+            &mut error_popup
+        ));
+        assert_eq!(error_popup.as_deref(), Some("form is broken"));
+        assert_eq!(jobs.running(), 0);
+    }
 }
@@ -0,0 +1,142 @@
+//
+// Decoded lvm attrs as colored badges, and a legend of lv_attr positions.
+//
+
+use ratatui::{
+    style::{Modifier, Style, palette::tailwind},
+    text::{Line, Span, Text},
+};
+
+use crate::lvm::{AttrFlag, AttrLevel, LvAttr};
+use crate::lvmapp::res::Colors;
+
+fn badge_style(level: AttrLevel, colors: &Colors) -> Style {
+    match level {
+        AttrLevel::Normal => Style::new().fg(colors.header_fg).bg(colors.header_bg),
+        AttrLevel::Info => Style::new()
+            .fg(tailwind::SLATE.c950)
+            .bg(tailwind::AMBER.c400),
+        AttrLevel::Warning => Style::new()
+            .fg(tailwind::SLATE.c50)
+            .bg(tailwind::RED.c700)
+            .add_modifier(Modifier::BOLD),
+    }
+}
+
+fn char_style(level: AttrLevel) -> Style {
+    match level {
+        AttrLevel::Normal => Style::new(),
+        AttrLevel::Info => Style::new().fg(tailwind::AMBER.c400),
+        AttrLevel::Warning => Style::new()
+            .fg(tailwind::RED.c400)
+            .add_modifier(Modifier::BOLD),
+    }
+}
+
+//
+// ' raid ' ' writeable ' ..., a space between badges.
+//
+pub fn badges(flags: &[AttrFlag], colors: &Colors) -> Line<'static> {
+    let mut spans = Vec::new();
+    for flag in flags {
+        spans.push(Span::styled(
+            format!(" {} ", flag.text),
+            badge_style(flag.level, colors),
+        ));
+        spans.push(Span::raw(" "));
+    }
+    spans.pop();
+    Line::from(spans)
+}
+
+//
+// Raw lv_attr with warning/info chars colored.
+//
+pub fn lv_attr_spans(attr: &str) -> Line<'static> {
+    let levels = LvAttr::decode(attr).levels();
+    Line::from(
+        attr.chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let level = levels.get(i).copied().unwrap_or(AttrLevel::Normal);
+                Span::styled(c.to_string(), char_style(level))
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+//
+// One line per lv_attr position: position, name, char and what it means.
+//
+pub fn lv_attr_legend(attr: &str) -> Text<'static> {
+    let decoded = LvAttr::decode(attr);
+    let yes_no = |set: bool, text: &'static str| if set { text } else { "-" };
+    let open = match decoded.open {
+        Some(true) => "open",
+        Some(false) => "-",
+        None => "unknown",
+    };
+    let positions: [(&str, &str); 10] = [
+        ("volume type", decoded.volume_type.text()),
+        ("permissions", decoded.permissions.text()),
+        ("allocation", decoded.allocation.text()),
+        ("fixed minor", yes_no(decoded.fixed_minor, "fixed minor")),
+        ("state", decoded.state.text()),
+        ("device open", open),
+        ("target type", decoded.target_type.text()),
+        ("zero", yes_no(decoded.zero, "zero")),
+        ("health", decoded.health.text()),
+        (
+            "skip activation",
+            yes_no(decoded.skip_activation, "skip activation"),
+        ),
+    ];
+
+    let levels = decoded.levels();
+    let lines: Vec<Line> = positions
+        .iter()
+        .enumerate()
+        .map(|(i, (name, text))| {
+            let c = attr.chars().nth(i).unwrap_or('-');
+            Line::from(format!("{:>2} {:<16} {} {:<32}", i + 1, name, c, text))
+                .style(char_style(levels[i]))
+        })
+        .collect();
+    Text::from(lines)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::lvm::{AttrLevel, LvAttr, PvAttr, VgAttr};
+    use crate::lvmapp::attrbadge::{badges, lv_attr_legend, lv_attr_spans};
+    use crate::lvmapp::res::{self, Colors};
+
+    #[test]
+    fn test_badges() {
+        let colors = Colors::new(&res::PALETTES[0]);
+        let line = badges(&VgAttr::decode("wz-pn-").flags(), &colors);
+        assert_eq!(
+            line.to_string(),
+            " writeable   resizeable   partial   normal "
+        );
+
+        // refresh needed shows in red
+        let line = lv_attr_spans("rwi-aor-r-");
+        assert_eq!(line.to_string(), "rwi-aor-r-");
+        assert_ne!(line.spans[8].style, line.spans[7].style);
+        assert_eq!(LvAttr::decode("rwi-aor-r-").levels()[8], AttrLevel::Warning);
+
+        let legend = lv_attr_legend("Vwi-aotz--");
+        assert_eq!(legend.lines.len(), 10);
+        assert!(legend.lines[0].to_string().contains("thin"));
+        assert!(legend.lines[7].to_string().contains("zero"));
+
+        assert!(
+            PvAttr::decode("a-m")
+                .flags()
+                .iter()
+                .any(|f| f.text == "missing")
+        );
+    }
+}
@@ -0,0 +1,929 @@
+//
+// dm-cache and dm-writecache. A fast lv on an ssd/nvme pv is created and
+// attached to a slow lv (lvcreate, then lvconvert --type cache|writecache).
+// Hit rates and dirty blocks tell how well the cache works and how much is
+// not yet on the slow lv.
+//
+
+use std::sync::Arc;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Modifier, Style, Stylize, palette::tailwind},
+    text::{Line, Span, Text},
+    widgets::{Block, Padding, Paragraph},
+};
+use tui_widget_list::ListState;
+
+use Constraint::{Length, Max, Min};
+
+use crate::{
+    lvm::{
+        self, LvmBackend, LvmCache, LvmCacheType, LvmError, LvmLvData, LvmPVData, fmt_size,
+        size_unit_bytes,
+    },
+    lvmapp::{
+        STATUS, View, ViewType,
+        jobs::LvmJob,
+        lvview::{InputField, render_dropdown},
+        popup::ConfPopup,
+        res::{self, Colors},
+        snapview,
+    },
+};
+
+const SIZE_OPTS: [&str; 3] = ["G", "M", "T"];
+const MODE_OPTS: [&str; 2] = ["writethrough", "writeback"];
+const POLICY_OPTS: [&str; 2] = ["smq", "mq"];
+// Cache usage from where the bar turns amber, a full cache is normal.
+const CACHE_WARN_PERCENT: f64 = 95.0;
+
+pub fn is_cached(lv: &LvmLvData) -> bool {
+    matches!(lv.segtype.as_str(), "cache" | "writecache")
+}
+
+fn percent(part: u64, total: u64) -> Option<f64> {
+    (total > 0).then(|| part as f64 * 100.0 / total as f64)
+}
+
+//
+// Blocks of the fast lv in use, None if not reported.
+//
+pub fn cache_used_percent(lv: &LvmLvData) -> Option<f64> {
+    if lv.segtype == "writecache" {
+        let total = lv.writecache_total_blocks?;
+        percent(total - lv.writecache_free_blocks?.min(total), total)
+    } else {
+        percent(lv.cache_used_blocks?, lv.cache_total_blocks?)
+    }
+}
+
+pub fn read_hit_percent(lv: &LvmLvData) -> Option<f64> {
+    let hits = lv.cache_read_hits?;
+    percent(hits, hits + lv.cache_read_misses?)
+}
+
+//
+// Blocks written to the fast lv only.
+//
+pub fn dirty_blocks(lv: &LvmLvData) -> Option<u64> {
+    if lv.segtype == "writecache" {
+        lv.writecache_writeback_blocks
+    } else {
+        lv.cache_dirty_blocks
+    }
+}
+
+fn dirty_style(dirty: u64) -> Style {
+    if dirty > 0 {
+        Style::new().fg(tailwind::AMBER.c400)
+    } else {
+        Style::new()
+    }
+}
+
+//
+// 'hit 92% dirty 1523' for the lvs table, writecache has no hit counters.
+//
+pub fn cache_usage_line(lv: &LvmLvData) -> Line<'static> {
+    let dirty = dirty_blocks(lv).unwrap_or(0);
+    let first = match lv.segtype.as_str() {
+        "writecache" => format!("used {:.0}% ", cache_used_percent(lv).unwrap_or(0.0)),
+        _ => format!("hit {:.0}% ", read_hit_percent(lv).unwrap_or(0.0)),
+    };
+    Line::from(vec![
+        Span::raw(first),
+        Span::styled(format!("dirty {dirty}"), dirty_style(dirty)),
+    ])
+}
+
+//
+// Cache lv, mode, usage and counters of a cached lv, for the vg and lv views.
+//
+pub fn cache_detail_lines(lv: &LvmLvData) -> Vec<Line<'static>> {
+    let label = |s: &str| Span::raw(format!("{s:<10}"));
+    let mut lines = vec![Line::from(vec![
+        label("cache"),
+        Span::raw(format!("{} {}", lv.segtype, lv.pool_lv)),
+    ])];
+    if lv.segtype == "cache" {
+        lines.push(Line::from(vec![
+            label("mode"),
+            Span::raw(format!("{} / {}", lv.cache_mode, lv.cache_policy)),
+        ]));
+    }
+    let mut usage = Line::from(label("usage"));
+    usage.spans.extend(
+        snapview::usage_bar(cache_used_percent(lv).unwrap_or(0.0), CACHE_WARN_PERCENT).spans,
+    );
+    lines.push(usage);
+    if lv.segtype == "cache" {
+        let counters = |name: &str, hits: Option<u64>, misses: Option<u64>| {
+            let (hits, misses) = (hits.unwrap_or(0), misses.unwrap_or(0));
+            let ratio = percent(hits, hits + misses).unwrap_or(0.0);
+            Line::from(vec![
+                label(name),
+                Span::raw(format!("{hits} hit {misses} miss ({ratio:.1}%)")),
+            ])
+        };
+        lines.push(counters("reads", lv.cache_read_hits, lv.cache_read_misses));
+        lines.push(counters(
+            "writes",
+            lv.cache_write_hits,
+            lv.cache_write_misses,
+        ));
+    }
+    let dirty = dirty_blocks(lv).unwrap_or(0);
+    lines.push(Line::from(vec![
+        label("dirty"),
+        Span::styled(format!("{dirty} blocks"), dirty_style(dirty)),
+    ]));
+    lines
+}
+
+//
+// Name the fast lv gets back when split off, '[fast_cpool]' -> 'fast'.
+//
+pub fn split_name(pool_lv: &str) -> &str {
+    let name = pool_lv.trim_matches(['[', ']']);
+    name.strip_suffix("_cpool")
+        .or_else(|| name.strip_suffix("_cvol"))
+        .unwrap_or(name)
+}
+
+#[derive(PartialEq)]
+enum Focus {
+    Type,
+    Pv,
+    Name,
+    Size,
+    SizeOpt,
+    Mode,
+    Policy,
+}
+
+//
+// Create a cache pool, cachevol or writecache on a fast pv and attach it to
+// an lv.
+//
+pub struct CacheAttachView {
+    focus: Focus,
+    popup_save: bool,
+    vg_name: String,
+    lv_name: String,
+    lv: Option<LvmLvData>,
+    // pvs of the vg with free extents.
+    pvs: Vec<LvmPVData>,
+    // lv names of the vg, a new name can not take them.
+    taken: Vec<String>,
+    type_state: ListState,
+    pv_state: ListState,
+    name: InputField,
+    size: InputField,
+    size_opt_state: ListState,
+    mode_state: ListState,
+    policy_state: ListState,
+    colors: Colors,
+    job: Option<LvmJob>,
+    lvm: Arc<dyn LvmBackend>,
+}
+
+impl View for CacheAttachView {
+    fn refresh_data(&mut self) {
+        // noop
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::CacheAttach
+    }
+
+    //
+    // handle events related to this view. If done here return true, e.g if "back" or "save".
+    //
+    fn handle_events(&mut self, key: &KeyEvent) -> core::result::Result<bool, &'static str> {
+        if key.kind == KeyEventKind::Press {
+            match key.code {
+                KeyCode::Tab => self.next_focus(),
+                KeyCode::BackTab => self.prev_focus(),
+                KeyCode::Backspace => {
+                    if let Some(input) = self.input_mut()
+                        && input.pos > 0
+                    {
+                        input.value.remove(input.pos - 1);
+                        input.pos -= 1;
+                    }
+                }
+                KeyCode::Char(c) => self.insert(c),
+                KeyCode::Left => {
+                    if let Some(input) = self.input_mut()
+                        && input.pos > 0
+                    {
+                        input.pos -= 1;
+                    }
+                }
+                KeyCode::Right => {
+                    if let Some(input) = self.input_mut()
+                        && input.pos < input.value.len()
+                    {
+                        input.pos += 1;
+                    }
+                }
+                KeyCode::Down => {
+                    if let Some(state) = self.dropdown_mut() {
+                        state.next();
+                    }
+                }
+                KeyCode::Up => {
+                    if let Some(state) = self.dropdown_mut() {
+                        state.previous();
+                    }
+                }
+                KeyCode::F(6) => match self.check() {
+                    Ok(_) => self.popup_save = true,
+                    Err(e) => STATUS.lock().unwrap().set_status(&e),
+                },
+                KeyCode::Esc => {
+                    if self.popup_save {
+                        STATUS.lock().unwrap().set_status("Cache attach cancelled.");
+                        self.popup_save = false;
+                    } else {
+                        return Ok(true);
+                    }
+                }
+                KeyCode::Enter if self.popup_save => {
+                    self.popup_save = false;
+                    let Some(cache) = self.cache() else {
+                        return Ok(false);
+                    };
+                    let (lvm, vg_name, lv_name) =
+                        (self.lvm.clone(), self.vg_name.clone(), self.lv_name.clone());
+                    let title = format!(
+                        "Attach {} {} to {vg_name}/{lv_name}",
+                        cache.cache_type.text(),
+                        cache.name
+                    );
+                    STATUS
+                        .lock()
+                        .unwrap()
+                        .set_status(&format!("Started: {title}"));
+                    self.job = Some(LvmJob::new(&title, move || {
+                        lvm.attach_cache(&vg_name, &lv_name, &cache)
+                    }));
+                    return Ok(true);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+impl CacheAttachView {
+    pub fn new(vg_name: &str, lv_name: &str, lvm: Arc<dyn LvmBackend>) -> Self {
+        let select_first = || {
+            let mut state = ListState::default();
+            state.select(Some(0));
+            state
+        };
+        Self {
+            focus: Focus::Type,
+            popup_save: false,
+            vg_name: vg_name.to_string(),
+            lv_name: lv_name.to_string(),
+            lv: None,
+            pvs: Vec::new(),
+            taken: Vec::new(),
+            type_state: select_first(),
+            pv_state: select_first(),
+            name: InputField {
+                len_max: 25,
+                value: String::new(),
+                pos: 0,
+            },
+            size: InputField {
+                len_max: 6,
+                value: String::new(),
+                pos: 0,
+            },
+            size_opt_state: select_first(),
+            mode_state: select_first(),
+            policy_state: select_first(),
+            colors: Colors::new(&res::PALETTES[0]),
+            job: None,
+            lvm,
+        }
+    }
+
+    //
+    // The lv, pvs with free space and the names in use. Name defaults to
+    // <lv>_cache, size to a tenth of the lv.
+    //
+    pub fn fetch_data(&mut self) -> Result<(), LvmError> {
+        let lvs = lvm::get_lvinfo_by_vg(&self.vg_name, &self.lvm.get_lvs()?);
+        let lv = lvs
+            .iter()
+            .find(|lv| lv.lv_name == self.lv_name)
+            .cloned()
+            .ok_or_else(|| LvmError::CommandFailed {
+                cmd: "lvs".to_string(),
+                code: None,
+                stderr: format!(
+                    "Failed to find logical volume \"{}/{}\"",
+                    self.vg_name, self.lv_name
+                ),
+            })?;
+        let mut pvs: Vec<LvmPVData> = self
+            .lvm
+            .get_pvs()?
+            .into_iter()
+            .filter(|pv| pv.vg_name == self.vg_name && pv.pe_alloc_count < pv.pe_count)
+            .collect();
+        // Pvs the lv is not on first, those are the candidates.
+        pvs.sort_by_key(|pv| lv.lv_segs.iter().any(|seg| seg.pvdev == pv.pv_name));
+        self.taken = lvs
+            .iter()
+            .map(|lv| lv.lv_name.trim_matches(['[', ']']).to_string())
+            .collect();
+
+        if self.name.value.is_empty() {
+            let base = format!("{}_cache", self.lv_name);
+            let name = (1..)
+                .map(|i| format!("{base}{i}"))
+                .find(|name| !self.taken.contains(name))
+                .filter(|_| self.taken.contains(&base))
+                .unwrap_or(base);
+            self.name.pos = name.len();
+            self.name.value = name;
+        }
+        if self.size.value.is_empty() {
+            let gib = 1024 * 1024 * 1024;
+            let size = (lv.size / 10 / gib).max(1).to_string();
+            self.size.pos = size.len();
+            self.size.value = size;
+        }
+        self.lv = Some(lv);
+        self.pvs = pvs;
+        Ok(())
+    }
+
+    //
+    // The cache attach to run, once confirmed.
+    //
+    pub fn take_job(&mut self) -> Option<LvmJob> {
+        self.job.take()
+    }
+
+    fn cache_type(&self) -> LvmCacheType {
+        LvmCacheType::ALL[self.type_state.selected.unwrap_or(0)]
+    }
+
+    fn is_writecache(&self) -> bool {
+        self.cache_type() == LvmCacheType::Writecache
+    }
+
+    fn focus_order(&self) -> Vec<Focus> {
+        let mut order = vec![
+            Focus::Type,
+            Focus::Pv,
+            Focus::Name,
+            Focus::Size,
+            Focus::SizeOpt,
+        ];
+        // writecache has no mode or policy.
+        if !self.is_writecache() {
+            order.push(Focus::Mode);
+            order.push(Focus::Policy);
+        }
+        order
+    }
+
+    fn next_focus(&mut self) {
+        let mut order = self.focus_order();
+        let i = order.iter().position(|f| *f == self.focus).unwrap_or(0);
+        let next = (i + 1) % order.len();
+        self.focus = order.swap_remove(next);
+    }
+
+    fn prev_focus(&mut self) {
+        let mut order = self.focus_order();
+        let i = order.iter().position(|f| *f == self.focus).unwrap_or(0);
+        let prev = (i + order.len() - 1) % order.len();
+        self.focus = order.swap_remove(prev);
+    }
+
+    fn input_mut(&mut self) -> Option<&mut InputField> {
+        match self.focus {
+            Focus::Name => Some(&mut self.name),
+            Focus::Size => Some(&mut self.size),
+            _ => None,
+        }
+    }
+
+    fn dropdown_mut(&mut self) -> Option<&mut ListState> {
+        match self.focus {
+            Focus::Type => Some(&mut self.type_state),
+            Focus::Pv => Some(&mut self.pv_state),
+            Focus::SizeOpt => Some(&mut self.size_opt_state),
+            Focus::Mode => Some(&mut self.mode_state),
+            Focus::Policy => Some(&mut self.policy_state),
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, c: char) {
+        let valid = match self.focus {
+            Focus::Name => lvm::is_name_char(c),
+            Focus::Size => c.is_ascii_digit(),
+            _ => false,
+        };
+        if let Some(input) = self.input_mut()
+            && valid
+            && input.value.len() < input.len_max
+        {
+            input.value.insert(input.pos, c);
+            input.pos += 1;
+        }
+    }
+
+    fn pv(&self) -> Option<&LvmPVData> {
+        self.pvs.get(self.pv_state.selected.unwrap_or(0))
+    }
+
+    fn cache(&self) -> Option<LvmCache> {
+        let (mode, policy) = if self.is_writecache() {
+            (String::new(), String::new())
+        } else {
+            (
+                MODE_OPTS[self.mode_state.selected.unwrap_or(0)].to_string(),
+                POLICY_OPTS[self.policy_state.selected.unwrap_or(0)].to_string(),
+            )
+        };
+        Some(LvmCache {
+            name: self.name.value.clone(),
+            cache_type: self.cache_type(),
+            fast_pv: self.pv()?.pv_name.clone(),
+            size: self.size.value.parse::<u64>().ok()?,
+            size_unit: SIZE_OPTS[self.size_opt_state.selected.unwrap_or(0)].to_string(),
+            cache_mode: mode,
+            cache_policy: policy,
+        })
+    }
+
+    //
+    // Size of the fast lv in bytes, or why it can not be attached.
+    //
+    fn check(&self) -> Result<u64, String> {
+        let Some(lv) = self.lv.as_ref() else {
+            return Err("No data".to_string());
+        };
+        let pv = self
+            .pv()
+            .ok_or_else(|| format!("No free space on any pv of {}.", self.vg_name))?;
+        let name = &self.name.value;
+        lvm::check_lv_name(name)?;
+        if self.taken.contains(name) {
+            return Err(format!("{name} already exists."));
+        }
+        let cache = self
+            .cache()
+            .ok_or_else(|| "Enter a size, e.g. 10 (G)".to_string())?;
+        let size = size_unit_bytes(&cache.size_unit)
+            .map_or(Some(0), |b| cache.size.checked_mul(b))
+            .ok_or_else(|| format!("Size {}{} is too large.", cache.size, cache.size_unit))?;
+        if size == 0 {
+            return Err("Size must be more than 0.".to_string());
+        }
+        if lv.lv_segs.iter().any(|seg| seg.pvdev == pv.pv_name) {
+            return Err(format!(
+                "{} is on {} already, pick a faster pv.",
+                self.lv_name, pv.pv_name
+            ));
+        }
+        let free = (pv.pe_count - pv.pe_alloc_count) * pv.pe_size;
+        if size > free {
+            return Err(format!(
+                "Not enough free space on {}, {} free.",
+                pv.pv_name,
+                fmt_size(free)
+            ));
+        }
+        Ok(size)
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, rect: &Rect) {
+        let inner_layout = &Layout::vertical([
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Min(7),
+        ])
+        .margin(2);
+        let [
+            header_area,
+            lv_area,
+            type_area,
+            pv_area,
+            name_area,
+            size_area,
+            mode_area,
+            policy_area,
+            _,
+            _,
+            info_area,
+        ] = inner_layout.areas(*rect);
+
+        let para_heading = Paragraph::new("ATTACH CACHE")
+            .alignment(ratatui::layout::Alignment::Left)
+            .style(Style::new().fg(self.colors.block_border));
+        frame.render_widget(para_heading, header_area);
+
+        let label_style = Style::new().fg(self.colors.row_fg);
+        let input_style = Style::new()
+            .fg(self.colors.header_bg)
+            .underline_color(self.colors.header_bg)
+            .add_modifier(Modifier::UNDERLINED);
+        let value_style = Style::new().fg(self.colors.header_bg);
+
+        let h_layout = &Layout::horizontal([Length(10), Max(40)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [label_area, val_area] = h_layout.areas(lv_area);
+        frame.render_widget(Paragraph::new("lv:").style(label_style), label_area);
+        let lv_size = self.lv.as_ref().map_or(0, |lv| lv.size);
+        frame.render_widget(
+            Paragraph::new(format!(
+                "{}/{} ({})",
+                self.vg_name,
+                self.lv_name,
+                fmt_size(lv_size)
+            ))
+            .style(value_style),
+            val_area,
+        );
+
+        let h_layout = &Layout::horizontal([Length(10), Length(16)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [label_area, opt_area] = h_layout.areas(type_area);
+        frame.render_widget(Paragraph::new("type:").style(label_style), label_area);
+        let types = LvmCacheType::ALL.map(|t| t.text());
+        render_dropdown(
+            frame,
+            opt_area,
+            &types,
+            &mut self.type_state,
+            self.focus == Focus::Type,
+            &self.colors,
+        );
+
+        let h_layout = &Layout::horizontal([Length(10), Length(34)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [label_area, opt_area] = h_layout.areas(pv_area);
+        frame.render_widget(Paragraph::new("fast pv:").style(label_style), label_area);
+        if self.pvs.is_empty() {
+            frame.render_widget(
+                Paragraph::new("no pv with free space").style(value_style),
+                opt_area,
+            );
+        } else {
+            let pvs: Vec<String> = self
+                .pvs
+                .iter()
+                .map(|pv| {
+                    let free = (pv.pe_count - pv.pe_alloc_count) * pv.pe_size;
+                    format!("{} ({} free)", pv.pv_name, fmt_size(free))
+                })
+                .collect();
+            let pvs: Vec<&str> = pvs.iter().map(String::as_str).collect();
+            render_dropdown(
+                frame,
+                opt_area,
+                &pvs,
+                &mut self.pv_state,
+                self.focus == Focus::Pv,
+                &self.colors,
+            );
+        }
+
+        let h_layout = &Layout::horizontal([Length(10), Max(26)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [label_area, input_area] = h_layout.areas(name_area);
+        frame.render_widget(Paragraph::new("name:").style(label_style), label_area);
+        frame.render_widget(
+            Paragraph::new(
+                Text::from(self.name.value.clone()).fg(self.colors.selected_column_style_fg),
+            )
+            .style(input_style),
+            input_area,
+        );
+        if self.focus == Focus::Name {
+            frame.set_cursor_position(Position::new(
+                input_area.x + (self.name.pos as u16),
+                input_area.y,
+            ));
+        }
+
+        let h_layout = &Layout::horizontal([Length(10), Max(7), Length(7)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [label_area, input_area, option_area] = h_layout.areas(size_area);
+        frame.render_widget(Paragraph::new("size:").style(label_style), label_area);
+        frame.render_widget(
+            Paragraph::new(
+                Text::from(self.size.value.clone()).fg(self.colors.selected_column_style_fg),
+            )
+            .style(input_style),
+            input_area,
+        );
+        render_dropdown(
+            frame,
+            option_area,
+            &SIZE_OPTS,
+            &mut self.size_opt_state,
+            self.focus == Focus::SizeOpt,
+            &self.colors,
+        );
+        if self.focus == Focus::Size {
+            frame.set_cursor_position(Position::new(
+                input_area.x + (self.size.pos as u16),
+                input_area.y,
+            ));
+        }
+
+        let h_layout = &Layout::horizontal([Length(10), Length(16)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [mode_label, mode_opt] = h_layout.areas(mode_area);
+        let [policy_label, policy_opt] = h_layout.areas(policy_area);
+        frame.render_widget(Paragraph::new("mode:").style(label_style), mode_label);
+        frame.render_widget(Paragraph::new("policy:").style(label_style), policy_label);
+        if self.is_writecache() {
+            for area in [mode_opt, policy_opt] {
+                frame.render_widget(Paragraph::new("-").style(value_style), area);
+            }
+        } else {
+            render_dropdown(
+                frame,
+                mode_opt,
+                &MODE_OPTS,
+                &mut self.mode_state,
+                self.focus == Focus::Mode,
+                &self.colors,
+            );
+            render_dropdown(
+                frame,
+                policy_opt,
+                &POLICY_OPTS,
+                &mut self.policy_state,
+                self.focus == Focus::Policy,
+                &self.colors,
+            );
+        }
+
+        self.render_info(frame, info_area);
+
+        if self.popup_save {
+            self.render_popup_save(frame, rect);
+        }
+    }
+
+    //
+    // The lvcreate and lvconvert commands and why they would be refused.
+    //
+    fn render_info(&self, frame: &mut Frame, area: Rect) {
+        let label = |s: &str| Span::styled(format!("{s:<16}"), Style::new().fg(self.colors.row_fg));
+        let value = |s: String| Span::styled(s, Style::new().fg(self.colors.header_fg));
+
+        let mut lines = Vec::new();
+        if let Some(cache) = self.cache() {
+            lines.push(Line::from(vec![
+                label("create"),
+                value(format!(
+                    "lvcreate {}",
+                    lvm::cache_create_args(&self.vg_name, &cache).join(" ")
+                )),
+            ]));
+            lines.push(Line::from(vec![
+                label("attach"),
+                value(format!(
+                    "lvconvert {}",
+                    lvm::cache_attach_args(&self.vg_name, &self.lv_name, &cache).join(" ")
+                )),
+            ]));
+            let note = match (cache.cache_type, cache.cache_mode.as_str()) {
+                (LvmCacheType::Writecache, _) => Some(
+                    "writecache only caches writes, they are on the fast pv until written back.",
+                ),
+                (_, "writeback") => Some(
+                    "writeback: writes are on the fast pv only until written back, losing it loses data.",
+                ),
+                _ => None,
+            };
+            if let Some(note) = note {
+                lines.push(Line::styled(note, Style::new().fg(tailwind::AMBER.c400)));
+            }
+        }
+        if let Err(e) = self.check() {
+            lines.push(Line::styled(e, Style::new().fg(tailwind::RED.c400).bold()));
+        }
+
+        let para = Paragraph::new(lines).block(Block::default().padding(Padding::horizontal(1)));
+        frame.render_widget(para, area);
+    }
+
+    fn render_popup_save(&self, frame: &mut Frame, rect: &Rect) {
+        let popup_area = Rect {
+            x: rect.width / 4,
+            y: rect.height / 3,
+            width: rect.width / 2,
+            height: rect.height / 2,
+        };
+        let s1 = Style::new().white().bold();
+        let size = self.check().unwrap_or(0);
+        let pv_name = self.pv().map(|pv| pv.pv_name.clone()).unwrap_or_default();
+
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(Text::from(Line::from(vec![
+                Span::from(format!(
+                    "You are about to create {} '",
+                    self.cache_type().text()
+                )),
+                Span::from(self.name.value.clone()).style(s1),
+                Span::from("' of "),
+                Span::from(fmt_size(size)).style(s1),
+                Span::from(" on "),
+                Span::from(pv_name).style(s1),
+                Span::from(" and attach it to '"),
+                Span::from(self.lv_name.clone()).style(s1),
+                Span::from("'."),
+            ])))
+            .title(format!("Cache {}", self.lv_name));
+        frame.render_widget(popup, popup_area);
+    }
+}
+
+//
+// Confirm detaching the cache of an lv, lvconvert --splitcache keeps the fast
+// lv, --uncache removes it. Tab toggles.
+//
+pub struct CacheDetachPopup {
+    vg_name: String,
+    lv_name: String,
+    pool_lv: String,
+    dirty: u64,
+    keep: bool,
+    colors: Colors,
+    lvm: Arc<dyn LvmBackend>,
+    job: Option<LvmJob>,
+}
+
+impl CacheDetachPopup {
+    pub fn new(lv: &LvmLvData, colors: Colors, lvm: Arc<dyn LvmBackend>) -> Self {
+        Self {
+            vg_name: lv.vg_name.clone(),
+            lv_name: lv.lv_name.clone(),
+            pool_lv: lv.pool_lv.clone(),
+            dirty: dirty_blocks(lv).unwrap_or(0),
+            keep: true,
+            colors,
+            lvm,
+            job: None,
+        }
+    }
+
+    //
+    // The cache detach to run, once confirmed.
+    //
+    pub fn take_job(&mut self) -> Option<LvmJob> {
+        self.job.take()
+    }
+
+    //
+    // Done (true) when detached or cancelled, lvconvert errors are passed on.
+    //
+    pub fn handle_events(&mut self, key: &KeyEvent) -> Result<bool, LvmError> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Tab => {
+                self.keep = !self.keep;
+                Ok(false)
+            }
+            KeyCode::Esc => {
+                STATUS.lock().unwrap().set_status("Cache detach cancelled.");
+                Ok(true)
+            }
+            KeyCode::Enter => {
+                // Dirty blocks are flushed first, that may take long.
+                let (lvm, vg_name, lv_name, keep) = (
+                    self.lvm.clone(),
+                    self.vg_name.clone(),
+                    self.lv_name.clone(),
+                    self.keep,
+                );
+                let title = format!("Detach cache of {vg_name}/{lv_name}");
+                STATUS
+                    .lock()
+                    .unwrap()
+                    .set_status(&format!("Started: {title}"));
+                self.job = Some(LvmJob::new(&title, move || {
+                    lvm.detach_cache(&vg_name, &lv_name, keep)
+                }));
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let popup_area = Rect {
+            x: area.width / 6,
+            y: area.height / 4,
+            width: area.width * 2 / 3,
+            height: 16.min(area.height),
+        };
+        let s1 = Style::new().white().bold();
+        let check = |on: bool| if on { "(x) " } else { "( ) " };
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::from("Detach cache '"),
+                Span::styled(self.pool_lv.clone(), s1),
+                Span::from("' from '"),
+                Span::styled(self.lv_name.clone(), s1),
+                Span::from("'?"),
+            ]),
+            Line::from(""),
+            Line::from(format!(
+                "{}keep it as '{}' (--splitcache)",
+                check(self.keep),
+                split_name(&self.pool_lv)
+            )),
+            Line::from(format!("{}remove it (--uncache)", check(!self.keep))),
+            Line::from(""),
+        ];
+        if self.dirty > 0 {
+            lines.push(Line::styled(
+                format!(
+                    "{} dirty blocks are written back first, this can take a while.",
+                    self.dirty
+                ),
+                Style::new().fg(tailwind::AMBER.c400).bold(),
+            ));
+        }
+        lines.push(Line::from("Tab toggles keep/remove."));
+
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(Text::from(lines))
+            .title(format!(" Detach cache {}/{} ", self.vg_name, self.lv_name));
+        frame.render_widget(popup, popup_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::lvm::LvmLvData;
+    use crate::lvmapp::cacheview::{cache_usage_line, cache_used_percent, split_name};
+
+    #[test]
+    fn test_cache_usage() {
+        let mut lv = LvmLvData {
+            segtype: "cache".to_string(),
+            pool_lv: "[fast_cpool]".to_string(),
+            cache_total_blocks: Some(1000),
+            cache_used_blocks: Some(250),
+            cache_dirty_blocks: Some(12),
+            cache_read_hits: Some(920),
+            cache_read_misses: Some(80),
+            ..Default::default()
+        };
+        assert_eq!(cache_used_percent(&lv), Some(25.0));
+        assert_eq!(cache_usage_line(&lv).to_string(), "hit 92% dirty 12");
+
+        lv.segtype = "writecache".to_string();
+        lv.writecache_total_blocks = Some(100);
+        lv.writecache_free_blocks = Some(40);
+        lv.writecache_writeback_blocks = Some(0);
+        assert_eq!(cache_used_percent(&lv), Some(60.0));
+        assert_eq!(cache_usage_line(&lv).to_string(), "used 60% dirty 0");
+
+        assert_eq!(split_name("[fast_cpool]"), "fast");
+        assert_eq!(split_name("[wc_cvol]"), "wc");
+    }
+}
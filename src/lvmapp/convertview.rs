@@ -0,0 +1,780 @@
+//
+// lvconvert --type, takes an lv over to another segtype, e.g. linear to raid1
+// or raid5 to raid6. Added images go on the selected pvs and then resync, the
+// vg view shows the progress.
+//
+
+use std::sync::Arc;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Modifier, Style, Stylize, palette::tailwind},
+    text::{Line, Span, Text},
+    widgets::{Block, Padding, Paragraph},
+};
+use tui_widget_list::ListState;
+
+use Constraint::{Length, Max, Min};
+
+use crate::{
+    lvm::{
+        self, LvmBackend, LvmConvert, LvmError, LvmLayout, LvmLvData, LvmPVData, PvAttr, fmt_size,
+    },
+    lvmapp::{
+        STATUS, View, ViewType,
+        jobs::LvmJob,
+        lvview::{InputField, PvList, PvSelect, render_dropdown},
+        popup::ConfPopup,
+        raidview,
+        res::{self, Colors},
+    },
+};
+
+//
+// 'raid5, 3 data + 1 parity' for the layout lines.
+//
+pub fn layout_text(segtype: &str, layout: &LvmLayout) -> String {
+    let ds = layout.data_stripes;
+    let detail = match segtype {
+        "linear" => return segtype.to_string(),
+        "striped" | "raid0" => format!("{ds} stripes"),
+        "raid1" | "mirror" => format!("{} copies", layout.images),
+        "raid10" => format!("{ds} stripes x {} copies", layout.images / ds),
+        _ => format!("{ds} data + {} parity", layout.images.saturating_sub(ds)),
+    };
+    format!("{segtype}, {detail}")
+}
+
+fn images(n: u64) -> String {
+    format!("{n} image{}", if n == 1 { "" } else { "s" })
+}
+
+fn has_mirrors(segtype: &str) -> bool {
+    matches!(segtype, "raid1" | "mirror" | "raid10")
+}
+
+//
+// What a conversion does to the lv, sizes in bytes.
+//
+#[derive(Debug, PartialEq)]
+struct ConvertPlan {
+    layout: LvmLayout,
+    image_size: u64,
+    added: u64,
+    dropped: u64,
+    needed: u64,
+    freed: u64,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Focus {
+    Target,
+    Mirrors,
+    Stripes,
+    PvAvail,
+    PvSelected,
+}
+
+pub struct LvConvertView {
+    focus: Focus,
+    popup_save: bool,
+    vg_name: String,
+    lv_name: String,
+    lv: Option<LvmLvData>,
+    // pvs of the vg, for the extent size and free space.
+    pvs: Vec<LvmPVData>,
+    // pvs the lv and its images are on, new images go elsewhere.
+    lv_pvs: Vec<String>,
+    targets: Vec<&'static str>,
+    target_state: ListState,
+    mirrors: InputField,
+    stripes: InputField,
+    pv_select: PvSelect,
+    colors: Colors,
+    job: Option<LvmJob>,
+    lvm: Arc<dyn LvmBackend>,
+}
+
+impl View for LvConvertView {
+    fn refresh_data(&mut self) {
+        // noop
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::LvConvert
+    }
+
+    //
+    // handle events related to this view. If done here return true, e.g if "back" or "save".
+    //
+    fn handle_events(&mut self, key: &KeyEvent) -> core::result::Result<bool, &'static str> {
+        if key.kind == KeyEventKind::Press {
+            match key.code {
+                KeyCode::Tab => self.next_focus(),
+                KeyCode::BackTab => self.prev_focus(),
+                KeyCode::Backspace => {
+                    if let Some(input) = self.input_mut()
+                        && input.pos > 0
+                    {
+                        input.value.remove(input.pos - 1);
+                        input.pos -= 1;
+                    }
+                }
+                KeyCode::Char(' ') => self.toggle_pv(),
+                KeyCode::Char(c) => self.insert(c),
+                KeyCode::Left => {
+                    if let Some(input) = self.input_mut()
+                        && input.pos > 0
+                    {
+                        input.pos -= 1;
+                    }
+                }
+                KeyCode::Right => {
+                    if let Some(input) = self.input_mut()
+                        && input.pos < input.value.len()
+                    {
+                        input.pos += 1;
+                    }
+                }
+                KeyCode::Down => match self.focus {
+                    Focus::Target => self.target_state.next(),
+                    Focus::PvAvail => self.pv_select.next(PvList::Avail),
+                    Focus::PvSelected => self.pv_select.next(PvList::Selected),
+                    _ => {}
+                },
+                KeyCode::Up => match self.focus {
+                    Focus::Target => self.target_state.previous(),
+                    Focus::PvAvail => self.pv_select.previous(PvList::Avail),
+                    Focus::PvSelected => self.pv_select.previous(PvList::Selected),
+                    _ => {}
+                },
+                KeyCode::F(6) => match self.check() {
+                    Ok(_) => self.popup_save = true,
+                    Err(e) => STATUS.lock().unwrap().set_status(&e),
+                },
+                KeyCode::Esc => {
+                    if self.popup_save {
+                        STATUS
+                            .lock()
+                            .unwrap()
+                            .set_status("Lv conversion cancelled.");
+                        self.popup_save = false;
+                    } else {
+                        return Ok(true);
+                    }
+                }
+                KeyCode::Enter if self.popup_save => {
+                    self.popup_save = false;
+                    let (Some(convert), Ok(plan)) = (self.convert(), self.check()) else {
+                        return Ok(false);
+                    };
+                    let (lvm, vg_name, lv_name, pvl) = (
+                        self.lvm.clone(),
+                        self.vg_name.clone(),
+                        self.lv_name.clone(),
+                        self.pv_select.selected.clone(),
+                    );
+                    let syncing = if plan.added > 0 { ", then syncs" } else { "" };
+                    let title = format!("Convert {vg_name}/{lv_name} to {}", convert.segtype);
+                    STATUS
+                        .lock()
+                        .unwrap()
+                        .set_status(&format!("Started: {title}{syncing}"));
+                    self.job = Some(LvmJob::new(&title, move || {
+                        lvm.convert_lv(&vg_name, &lv_name, &convert, &pvl)
+                    }));
+                    return Ok(true);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+impl LvConvertView {
+    pub fn new(vg_name: &str, lv_name: &str, lvm: Arc<dyn LvmBackend>) -> Self {
+        let mut target_state = ListState::default();
+        target_state.select(Some(0));
+        Self {
+            focus: Focus::Target,
+            popup_save: false,
+            vg_name: vg_name.to_string(),
+            lv_name: lv_name.to_string(),
+            lv: None,
+            pvs: Vec::new(),
+            lv_pvs: Vec::new(),
+            targets: Vec::new(),
+            target_state,
+            mirrors: InputField {
+                len_max: 2,
+                value: String::new(),
+                pos: 0,
+            },
+            stripes: InputField {
+                len_max: 2,
+                value: String::new(),
+                pos: 0,
+            },
+            pv_select: PvSelect::new(&[]),
+            colors: Colors::new(&res::PALETTES[0]),
+            job: None,
+            lvm,
+        }
+    }
+
+    //
+    // The lv, its segtype's targets and the pvs new images can go to. Mirrors
+    // and stripes default to what the lv has.
+    //
+    pub fn fetch_data(&mut self) -> Result<(), LvmError> {
+        let lvs = lvm::get_lvinfo_by_vg(&self.vg_name, &self.lvm.get_lvs()?);
+        let lv = lvs
+            .iter()
+            .find(|lv| lv.lv_name == self.lv_name)
+            .cloned()
+            .ok_or_else(|| LvmError::CommandFailed {
+                cmd: "lvs".to_string(),
+                code: None,
+                stderr: format!(
+                    "Failed to find logical volume \"{}/{}\"",
+                    self.vg_name, self.lv_name
+                ),
+            })?;
+        self.lv_pvs = lvs
+            .iter()
+            .filter(|item| item.lv_name == lv.lv_name || item.parent_lv == lv.lv_name)
+            .flat_map(|item| item.lv_segs.iter())
+            .filter(|seg| !seg.pvdev.starts_with('['))
+            .map(|seg| seg.pvdev.clone())
+            .collect();
+        self.pvs = self
+            .lvm
+            .get_pvs()?
+            .into_iter()
+            .filter(|pv| pv.vg_name == self.vg_name)
+            .collect();
+        let avail: Vec<String> = self
+            .pvs
+            .iter()
+            .filter(|pv| pv.pe_alloc_count < pv.pe_count && !self.lv_pvs.contains(&pv.pv_name))
+            .map(|pv| pv.pv_name.clone())
+            .collect();
+        self.pv_select = PvSelect::new(&avail);
+        self.targets = lvm::convert_targets(&lv);
+
+        let layout = LvmLayout::of_lv(&lv);
+        let mirrors = if has_mirrors(&lv.segtype) {
+            layout.images / layout.data_stripes - 1
+        } else {
+            1
+        };
+        for (input, n) in [
+            (&mut self.mirrors, mirrors),
+            (&mut self.stripes, layout.data_stripes),
+        ] {
+            input.value = n.to_string();
+            input.pos = input.value.len();
+        }
+        self.lv = Some(lv);
+        Ok(())
+    }
+
+    //
+    // The lvconvert to run, once confirmed.
+    //
+    pub fn take_job(&mut self) -> Option<LvmJob> {
+        self.job.take()
+    }
+
+    fn target(&self) -> Option<&'static str> {
+        self.targets
+            .get(self.target_state.selected.unwrap_or(0))
+            .copied()
+    }
+
+    // Stripes change on their own, converting to the lv's segtype (reshape).
+    fn reshaping(&self) -> bool {
+        let segtype = self.lv.as_ref().map(|lv| lv.segtype.as_str());
+        self.target()
+            .is_some_and(|t| Some(t) == segtype && t != "raid1")
+    }
+
+    fn focus_order(&self) -> Vec<Focus> {
+        let mut order = vec![Focus::Target];
+        if self.target().is_some_and(has_mirrors) {
+            order.push(Focus::Mirrors);
+        }
+        if self.reshaping() {
+            order.push(Focus::Stripes);
+        }
+        if !self.pv_select.avail.is_empty() {
+            order.push(Focus::PvAvail);
+        }
+        if !self.pv_select.selected.is_empty() {
+            order.push(Focus::PvSelected);
+        }
+        order
+    }
+
+    fn next_focus(&mut self) {
+        let mut order = self.focus_order();
+        let i = order.iter().position(|f| *f == self.focus).unwrap_or(0);
+        let next = (i + 1) % order.len();
+        self.focus = order.swap_remove(next);
+    }
+
+    fn prev_focus(&mut self) {
+        let mut order = self.focus_order();
+        let i = order.iter().position(|f| *f == self.focus).unwrap_or(0);
+        let prev = (i + order.len() - 1) % order.len();
+        self.focus = order.swap_remove(prev);
+    }
+
+    fn toggle_pv(&mut self) {
+        let list = match self.focus {
+            Focus::PvAvail => PvList::Avail,
+            Focus::PvSelected => PvList::Selected,
+            _ => return,
+        };
+        self.pv_select.toggle(list);
+        // Don't stay on a list that ran empty.
+        if !self.focus_order().contains(&self.focus) {
+            self.next_focus();
+        }
+    }
+
+    fn input_mut(&mut self) -> Option<&mut InputField> {
+        match self.focus {
+            Focus::Mirrors => Some(&mut self.mirrors),
+            Focus::Stripes => Some(&mut self.stripes),
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, c: char) {
+        if let Some(input) = self.input_mut()
+            && c.is_ascii_digit()
+            && input.value.len() < input.len_max
+        {
+            input.value.insert(input.pos, c);
+            input.pos += 1;
+        }
+    }
+
+    fn convert(&self) -> Option<LvmConvert> {
+        let lv = self.lv.as_ref()?;
+        let target = self.target()?;
+        let mirrors = if has_mirrors(target) {
+            Some(self.mirrors.value.parse::<u64>().ok()?)
+        } else {
+            None
+        };
+        let stripes = if self.reshaping() {
+            let stripes = self.stripes.value.parse::<u64>().ok()?;
+            (stripes != LvmLayout::of_lv(lv).data_stripes).then_some(stripes)
+        } else {
+            None
+        };
+        Some(LvmConvert {
+            segtype: target.to_string(),
+            mirrors,
+            stripes,
+        })
+    }
+
+    //
+    // Images kept, added and dropped. A linear lv is image 0 of a raid1 and
+    // what is left of it again. Kept images get metadata if they had none.
+    //
+    fn plan(&self) -> Option<ConvertPlan> {
+        let lv = self.lv.as_ref()?;
+        let convert = self.convert()?;
+        let from = LvmLayout::of_lv(lv);
+        let stripes = convert.stripes.unwrap_or(from.data_stripes);
+        let layout = LvmLayout::new(&convert.segtype, Some(stripes), convert.mirrors)?;
+        let pe_size = self.pvs.first().map_or(0, |pv| pv.pe_size);
+        let image_size = lv.size / from.data_stripes;
+        let kept = if layout.images == 0 {
+            0
+        } else {
+            layout.images.min(from.images.max(1))
+        };
+        let added = layout.images - kept;
+        let dropped = from.images.saturating_sub(layout.images.max(1));
+        let meta = |has: bool| if has { pe_size } else { 0 };
+        let mut needed = added * (image_size + meta(layout.meta));
+        let mut freed = dropped * (image_size + meta(from.meta));
+        if layout.meta && !from.meta {
+            needed += kept * pe_size;
+        } else if from.meta && !layout.meta {
+            freed += from.images.min(layout.images.max(1)) * pe_size;
+        }
+        Some(ConvertPlan {
+            layout,
+            image_size,
+            added,
+            dropped,
+            needed,
+            freed,
+        })
+    }
+
+    //
+    // Pvs a new image fits on, each takes one.
+    //
+    fn image_pvs(&self, plan: &ConvertPlan) -> Vec<&LvmPVData> {
+        let meta = if plan.layout.meta {
+            self.pvs.first().map_or(0, |pv| pv.pe_size)
+        } else {
+            0
+        };
+        let selected = &self.pv_select.selected;
+        self.pvs
+            .iter()
+            .filter(|pv| !self.lv_pvs.contains(&pv.pv_name) && !PvAttr::decode(&pv.pv_attr).missing)
+            .filter(|pv| selected.is_empty() || selected.contains(&pv.pv_name))
+            .filter(|pv| (pv.pe_count - pv.pe_alloc_count) * pv.pe_size >= plan.image_size + meta)
+            .collect()
+    }
+
+    //
+    // What the conversion does, or why lvconvert would refuse it.
+    //
+    fn check(&self) -> Result<ConvertPlan, String> {
+        let Some(lv) = self.lv.as_ref() else {
+            return Err("No data".to_string());
+        };
+        let Some(target) = self.target() else {
+            return Err(format!(
+                "{} ({}) can not be converted.",
+                lv.lv_name, lv.segtype
+            ));
+        };
+        if raidview::is_syncing(lv) {
+            return Err(format!(
+                "{} is busy ({} {:.0}%), wait for it to finish.",
+                lv.lv_name,
+                lv.raid_sync_action,
+                lv.sync_percent.unwrap_or(0.0)
+            ));
+        }
+        let convert = self
+            .convert()
+            .ok_or_else(|| "Enter the number of mirrors and stripes.".to_string())?;
+        if convert.mirrors == Some(0) {
+            return Err(format!(
+                "{target} needs at least 1 mirror, convert to linear instead."
+            ));
+        }
+        let from = LvmLayout::of_lv(lv);
+        if let Some(stripes) = convert.stripes
+            && stripes < from.data_stripes
+        {
+            return Err(format!(
+                "Fewer stripes needs {} shrunk first, this only adds stripes.",
+                lv.lv_name
+            ));
+        }
+        let plan = self
+            .plan()
+            .ok_or_else(|| format!("Can not lay out {target}."))?;
+        if target == lv.segtype && plan.layout == from {
+            return Err("Nothing to change, pick another type or count.".to_string());
+        }
+        let pvs = self.image_pvs(&plan);
+        if (pvs.len() as u64) < plan.added {
+            return Err(format!(
+                "{} with {} free needed for the new images, {} available.",
+                if plan.added == 1 {
+                    "1 pv".to_string()
+                } else {
+                    format!("{} pvs", plan.added)
+                },
+                fmt_size(plan.image_size),
+                pvs.len()
+            ));
+        }
+        Ok(plan)
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, rect: &Rect) {
+        let inner_layout = &Layout::vertical([
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(8),
+            Min(6),
+        ])
+        .margin(2);
+        let [
+            header_area,
+            lv_area,
+            now_area,
+            type_area,
+            mirrors_area,
+            stripes_area,
+            _,
+            pv_sel_label,
+            pv_sel_area,
+            info_area,
+        ] = inner_layout.areas(*rect);
+
+        let para_heading = Paragraph::new("CONVERT LOGICAL VOLUME")
+            .alignment(ratatui::layout::Alignment::Left)
+            .style(Style::new().fg(self.colors.block_border));
+        frame.render_widget(para_heading, header_area);
+
+        let label_style = Style::new().fg(self.colors.row_fg);
+        let input_style = Style::new()
+            .fg(self.colors.header_bg)
+            .underline_color(self.colors.header_bg)
+            .add_modifier(Modifier::UNDERLINED);
+        let value_style = Style::new().fg(self.colors.header_bg);
+
+        let h_layout = &Layout::horizontal([Length(10), Max(60)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [label_area, val_area] = h_layout.areas(lv_area);
+        frame.render_widget(Paragraph::new("lv:").style(label_style), label_area);
+        let lv_size = self.lv.as_ref().map_or(0, |lv| lv.size);
+        frame.render_widget(
+            Paragraph::new(format!(
+                "{}/{} ({})",
+                self.vg_name,
+                self.lv_name,
+                fmt_size(lv_size)
+            ))
+            .style(value_style),
+            val_area,
+        );
+        let [label_area, val_area] = h_layout.areas(now_area);
+        frame.render_widget(Paragraph::new("now:").style(label_style), label_area);
+        if let Some(lv) = self.lv.as_ref() {
+            frame.render_widget(
+                Paragraph::new(layout_text(&lv.segtype, &LvmLayout::of_lv(lv))).style(value_style),
+                val_area,
+            );
+        }
+
+        let h_layout = &Layout::horizontal([Length(10), Length(12)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [label_area, opt_area] = h_layout.areas(type_area);
+        frame.render_widget(Paragraph::new("to type:").style(label_style), label_area);
+        if self.targets.is_empty() {
+            frame.render_widget(Paragraph::new("none").style(value_style), opt_area);
+        } else {
+            render_dropdown(
+                frame,
+                opt_area,
+                &self.targets,
+                &mut self.target_state,
+                self.focus == Focus::Target,
+                &self.colors,
+            );
+        }
+
+        let h_layout = &Layout::horizontal([Length(10), Max(4)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let editable = [self.target().is_some_and(has_mirrors), self.reshaping()];
+        for ((area, label, input, focus), editable) in [
+            (mirrors_area, "mirrors:", &self.mirrors, Focus::Mirrors),
+            (stripes_area, "stripes:", &self.stripes, Focus::Stripes),
+        ]
+        .into_iter()
+        .zip(editable)
+        {
+            let [label_area, input_area] = h_layout.areas(area);
+            frame.render_widget(Paragraph::new(label).style(label_style), label_area);
+            if !editable {
+                frame.render_widget(Paragraph::new("-").style(value_style), input_area);
+                continue;
+            }
+            frame.render_widget(
+                Paragraph::new(
+                    Text::from(input.value.clone()).fg(self.colors.selected_column_style_fg),
+                )
+                .style(input_style),
+                input_area,
+            );
+            if self.focus == focus {
+                frame.set_cursor_position(Position::new(
+                    input_area.x + (input.pos as u16),
+                    input_area.y,
+                ));
+            }
+        }
+
+        frame.render_widget(
+            Paragraph::new("Select PVs for new images (Optional):")
+                .style(Style::new().fg(self.colors.block_border)),
+            pv_sel_label,
+        );
+        let pv_focus = match self.focus {
+            Focus::PvAvail => Some(PvList::Avail),
+            Focus::PvSelected => Some(PvList::Selected),
+            _ => None,
+        };
+        self.pv_select
+            .render(frame, pv_sel_area, pv_focus, &self.colors);
+
+        self.render_info(frame, info_area);
+
+        if self.popup_save {
+            self.render_popup_save(frame, rect);
+        }
+    }
+
+    //
+    // Resulting layout, space it takes and the lvconvert command.
+    //
+    fn render_info(&self, frame: &mut Frame, area: Rect) {
+        let label = |s: &str| Span::styled(format!("{s:<16}"), Style::new().fg(self.colors.row_fg));
+        let value = |s: String| Span::styled(s, Style::new().fg(self.colors.header_fg));
+
+        let mut lines = Vec::new();
+        if let (Some(plan), Some(convert)) = (self.plan(), self.convert()) {
+            let mut layout = layout_text(&convert.segtype, &plan.layout);
+            if plan.layout.images > 0 {
+                layout.push_str(&format!(", {} per image", fmt_size(plan.image_size)));
+            }
+            lines.push(Line::from(vec![label("after"), value(layout)]));
+            let space = match (plan.added, plan.dropped) {
+                (0, 0) if plan.needed > 0 => format!("{} for metadata", fmt_size(plan.needed)),
+                (0, 0) if plan.freed > 0 => format!("frees {} of metadata", fmt_size(plan.freed)),
+                (0, 0) => "nothing, the images stay".to_string(),
+                (0, dropped) => {
+                    format!("drops {}, frees {}", images(dropped), fmt_size(plan.freed))
+                }
+                (added, _) => format!(
+                    "{} on other pvs, {} needed",
+                    images(added),
+                    fmt_size(plan.needed)
+                ),
+            };
+            lines.push(Line::from(vec![label("space"), value(space)]));
+            lines.push(Line::from(vec![
+                label("command"),
+                value(format!(
+                    "lvconvert {}",
+                    lvm::convert_lv_args(
+                        &self.vg_name,
+                        &self.lv_name,
+                        &convert,
+                        &self.pv_select.selected
+                    )
+                    .join(" ")
+                )),
+            ]));
+            let note = if convert.stripes.is_some() {
+                Some("More stripes grow the lv, the filesystem on it is not resized.")
+            } else if plan.added > 0 {
+                Some("New images resync, the vg view shows the progress.")
+            } else if plan.dropped > 0 {
+                Some("Dropped images lose their copy of the data.")
+            } else {
+                None
+            };
+            if let Some(note) = note {
+                lines.push(Line::styled(note, Style::new().fg(tailwind::AMBER.c400)));
+            }
+        }
+        if let Err(e) = self.check() {
+            lines.push(Line::styled(e, Style::new().fg(tailwind::RED.c400).bold()));
+        }
+
+        let para = Paragraph::new(lines).block(Block::default().padding(Padding::horizontal(1)));
+        frame.render_widget(para, area);
+    }
+
+    fn render_popup_save(&self, frame: &mut Frame, rect: &Rect) {
+        let popup_area = Rect {
+            x: rect.width / 4,
+            y: rect.height / 3,
+            width: rect.width / 2,
+            height: rect.height / 2,
+        };
+        let s1 = Style::new().white().bold();
+        let segtype = self.lv.as_ref().map(|lv| lv.segtype.clone());
+        let target = self.target().unwrap_or_default();
+        let layout = self
+            .plan()
+            .map(|plan| layout_text(target, &plan.layout))
+            .unwrap_or_default();
+
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(Text::from(Line::from(vec![
+                Span::from("You are about to convert '"),
+                Span::from(self.lv_name.clone()).style(s1),
+                Span::from("' from "),
+                Span::from(segtype.unwrap_or_default()).style(s1),
+                Span::from(" to "),
+                Span::from(layout).style(s1),
+                Span::from("."),
+            ])))
+            .title(format!("Convert {}", self.lv_name));
+        frame.render_widget(popup, popup_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::lvm::{LvmBackend, LvmLayout, fake::FakeBackend};
+    use crate::lvmapp::convertview::{LvConvertView, layout_text};
+
+    #[test]
+    fn test_convert_plan() {
+        let layout = |segtype: &str, stripes, mirrors| {
+            let layout = LvmLayout::new(segtype, stripes, mirrors).unwrap();
+            layout_text(segtype, &layout)
+        };
+        assert_eq!(layout("linear", None, None), "linear");
+        assert_eq!(layout("raid1", None, Some(2)), "raid1, 3 copies");
+        assert_eq!(layout("raid5", Some(3), None), "raid5, 3 data + 1 parity");
+        assert_eq!(layout("raid10", None, None), "raid10, 2 stripes x 2 copies");
+
+        // lvvirt, raid1 on 2 of 4 pvs in the demo
+        let lvm: Arc<dyn LvmBackend> = Arc::new(FakeBackend::demo());
+        let mut view = LvConvertView::new("vg04_1tbdisks", "lvvirt", lvm);
+        view.fetch_data().unwrap();
+        assert_eq!(view.targets, ["linear", "raid1", "mirror", "raid5"]);
+        assert_eq!(view.mirrors.value, "1");
+        assert_eq!(view.pv_select.avail.len(), 2);
+
+        // Back to linear frees the second image and the metadata
+        let gb = 1024 * 1024 * 1024;
+        let plan = view.check().unwrap();
+        assert_eq!((plan.added, plan.dropped), (0, 1));
+        assert_eq!(plan.freed, 100 * gb + 2 * 4 * 1024 * 1024);
+
+        // A third copy on one of the free pvs
+        view.target_state.select(Some(1));
+        assert_eq!(
+            view.check().unwrap_err(),
+            "Nothing to change, pick another type or count."
+        );
+        view.mirrors.value = "0".to_string();
+        assert!(view.check().unwrap_err().contains("convert to linear"));
+        view.mirrors.value = "2".to_string();
+        let plan = view.check().unwrap();
+        assert_eq!((plan.added, plan.image_size), (1, 100 * gb));
+        assert_eq!(plan.needed, 100 * gb + 4 * 1024 * 1024);
+        view.mirrors.value = "3".to_string();
+        assert!(view.check().is_ok());
+        view.mirrors.value = "4".to_string();
+        assert!(view.check().unwrap_err().contains("2 available"));
+    }
+}
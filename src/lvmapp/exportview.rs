@@ -0,0 +1,123 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEventKind},
+    layout::Rect,
+    style::{Style, Stylize, palette::tailwind},
+    text::{Line, Span, Text},
+};
+
+use crate::{
+    lvm::{
+        LvmBackend, LvmError,
+        topology::{self, ExportFormat},
+    },
+    lvmapp::{STATUS, popup::ConfPopup, res::Colors},
+};
+
+pub const DEFAULT_EXPORT_PATH: &str = "lvm-topology.json";
+
+//
+// Asks where to write the topology (x), JSON or YAML by the extension. A file
+// that is there is only overwritten after a second Enter.
+//
+pub struct ExportPopup {
+    path: String,
+    overwrite: bool,
+    colors: Colors,
+    lvm: Arc<dyn LvmBackend>,
+}
+
+impl ExportPopup {
+    pub fn new(path: &str, colors: Colors, lvm: Arc<dyn LvmBackend>) -> Self {
+        Self {
+            path: path.to_string(),
+            overwrite: false,
+            colors,
+            lvm,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    //
+    // Done (true) when written or cancelled, errors writing are passed on.
+    //
+    pub fn handle_events(&mut self, key: &KeyEvent) -> Result<bool, LvmError> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Esc => {
+                STATUS.lock().unwrap().set_status("Export cancelled.");
+                return Ok(true);
+            }
+            KeyCode::Enter if !self.overwrite && Path::new(&self.path).exists() => {
+                self.overwrite = true;
+                STATUS
+                    .lock()
+                    .unwrap()
+                    .set_status(&format!("{} exists, Enter overwrites it.", self.path));
+            }
+            KeyCode::Enter if !self.path.is_empty() => {
+                topology::export_to(self.lvm.as_ref(), Path::new(&self.path))?;
+                STATUS
+                    .lock()
+                    .unwrap()
+                    .set_status(&format!("Exported to {}", self.path));
+                return Ok(true);
+            }
+            KeyCode::Backspace => {
+                self.path.pop();
+                self.overwrite = false;
+            }
+            KeyCode::Char(c) if self.path.len() < 4096 => {
+                self.path.push(c);
+                self.overwrite = false;
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let popup_area = Rect {
+            x: area.width / 6,
+            y: area.height / 4,
+            width: area.width * 2 / 3,
+            height: 12.min(area.height),
+        };
+
+        let format = match ExportFormat::from_path(Path::new(&self.path)) {
+            ExportFormat::Json => "JSON",
+            ExportFormat::Yaml => "YAML",
+        };
+        let input_style = Style::new().fg(self.colors.header_fg);
+        let mut lines = vec![
+            Line::from("Write all vgs, pvs and lvs to:"),
+            Line::from(vec![
+                Span::styled(self.path.clone(), input_style.underlined()),
+                Span::styled("_", input_style),
+            ]),
+            Line::from(""),
+            Line::from(format!("as {format}, YAML if it ends in .yaml or .yml.")),
+        ];
+        if self.overwrite {
+            lines.push(Line::styled(
+                "The file exists, Enter again overwrites it.",
+                Style::new().fg(tailwind::AMBER.c400).bold(),
+            ));
+        }
+
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(Text::from(lines))
+            .title(" Export topology ");
+        frame.render_widget(popup, popup_area);
+    }
+}
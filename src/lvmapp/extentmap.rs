@@ -0,0 +1,326 @@
+use derive_setters::Setters;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style, palette::tailwind},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph, Widget},
+};
+
+use crate::lvm::{LvmLvData, LvmPVData};
+use crate::lvmapp::res::Colors;
+
+// Colors for lvs in the map, picked in order of the top level lv.
+const LV_PALETTE: [tailwind::Palette; 8] = [
+    tailwind::BLUE,
+    tailwind::AMBER,
+    tailwind::GREEN,
+    tailwind::ROSE,
+    tailwind::VIOLET,
+    tailwind::TEAL,
+    tailwind::ORANGE,
+    tailwind::LIME,
+];
+
+const USED: &str = "█";
+const FREE: &str = "·";
+
+//
+// An lv segment on a pv, 'owner' is the top level lv e.g. lvpub for [lvpub_rimage_0].
+//
+#[derive(Debug, Clone)]
+pub struct ExtentMapSeg {
+    pub lv_name: String,
+    pub owner: String,
+    pub pv_start_pe: u64,
+    pub size_pe: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExtentMapPv {
+    pub pv_name: String,
+    pub pe_count: u64,
+    pub segs: Vec<ExtentMapSeg>,
+}
+
+//
+// One bar per pv, each cell is a run of extents colored by the lv owning most of them.
+// Free extents are dots, highlighted lvs are drawn bright and bold.
+//
+#[derive(Debug, Default, Setters)]
+pub struct ExtentMap<'a> {
+    #[setters(into)]
+    title: Line<'a>,
+    pvs: Vec<ExtentMapPv>,
+    // lv names to highlight, e.g. selected lv and its sub lvs.
+    highlight: Vec<String>,
+    // top level lvs in color order.
+    #[setters(skip)]
+    owners: Vec<String>,
+    colors: Colors,
+}
+
+impl ExtentMap<'_> {
+    pub fn new(colors: Colors, pvs: Vec<ExtentMapPv>) -> Self {
+        let mut owners: Vec<String> = pvs
+            .iter()
+            .flat_map(|pv| pv.segs.iter().map(|seg| seg.owner.clone()))
+            .collect();
+        owners.sort();
+        owners.dedup();
+        Self {
+            colors,
+            pvs,
+            owners,
+            ..Default::default()
+        }
+    }
+
+    fn lv_color(&self, owner: &str, highlight: bool) -> Color {
+        let i = self.owners.iter().position(|o| o == owner).unwrap_or(0);
+        let palette = &LV_PALETTE[i % LV_PALETTE.len()];
+        if self.highlight.is_empty() || highlight {
+            palette.c400
+        } else {
+            palette.c800
+        }
+    }
+
+    //
+    // What to draw for extents [start, end) of pv, lv with most extents in the
+    // cell wins. A highlighted lv always shows, even if it has few extents in it.
+    //
+    fn cell<'b>(&self, pv: &'b ExtentMapPv, start: u64, end: u64) -> Option<&'b ExtentMapSeg> {
+        let mut best: Option<(&ExtentMapSeg, u64)> = None;
+        let mut used = 0;
+        for seg in &pv.segs {
+            let seg_end = seg.pv_start_pe + seg.size_pe;
+            let overlap = seg_end.min(end).saturating_sub(seg.pv_start_pe.max(start));
+            if overlap == 0 {
+                continue;
+            }
+            used += overlap;
+            if self.highlight.contains(&seg.lv_name) {
+                return Some(seg);
+            }
+            if best.is_none_or(|(_, n)| overlap > n) {
+                best = Some((seg, overlap));
+            }
+        }
+        // Mostly free
+        if used * 2 < end - start {
+            return None;
+        }
+        best.map(|(seg, _)| seg)
+    }
+
+    fn bar_line(&self, pv: &ExtentMapPv, label_width: usize, bar_width: u64) -> Line<'_> {
+        let label: String = pv.pv_name.chars().take(label_width).collect();
+        let mut spans = vec![Span::styled(
+            format!("{:<w$} ", label, w = label_width),
+            Style::new().fg(self.colors.header_fg),
+        )];
+        for i in 0..bar_width {
+            let start = pv.pe_count * i / bar_width;
+            let end = (pv.pe_count * (i + 1) / bar_width).max(start + 1);
+            let span = match self.cell(pv, start, end) {
+                Some(seg) => {
+                    let highlight = self.highlight.contains(&seg.lv_name);
+                    let mut style = Style::new().fg(self.lv_color(&seg.owner, highlight));
+                    if highlight {
+                        style = style.add_modifier(Modifier::BOLD);
+                    }
+                    Span::styled(USED, style)
+                }
+                None => Span::styled(FREE, Style::new().fg(self.colors.block_border)),
+            };
+            spans.push(span);
+        }
+        Line::from(spans)
+    }
+
+    fn legend_line(&self) -> Line<'_> {
+        let mut spans = Vec::new();
+        for owner in &self.owners {
+            let highlight = self.highlight.contains(owner);
+            spans.push(Span::styled(
+                USED,
+                Style::new().fg(self.lv_color(owner, highlight)),
+            ));
+            spans.push(Span::styled(
+                format!("{owner} "),
+                Style::new().fg(self.colors.row_fg),
+            ));
+        }
+        spans.push(Span::styled(
+            FREE,
+            Style::new().fg(self.colors.block_border),
+        ));
+        spans.push(Span::styled("free", Style::new().fg(self.colors.row_fg)));
+        Line::from(spans)
+    }
+}
+
+impl Widget for ExtentMap<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .title(self.title.clone())
+            .border_style(Style::new().fg(self.colors.block_border))
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL);
+        let inner = block.inner(area);
+        block.render(area, buf);
+        if inner.width < 4 || inner.height == 0 {
+            return;
+        }
+
+        let label_width = self
+            .pvs
+            .iter()
+            .map(|pv| pv.pv_name.len())
+            .max()
+            .unwrap_or(0)
+            .min(inner.width as usize / 3);
+        let bar_width = inner.width as u64 - label_width as u64 - 1;
+
+        let mut lines: Vec<Line> = self
+            .pvs
+            .iter()
+            .filter(|pv| pv.pe_count > 0)
+            .map(|pv| self.bar_line(pv, label_width, bar_width))
+            .collect();
+        if lines.len() < inner.height as usize {
+            lines.push(self.legend_line());
+        }
+
+        Paragraph::new(lines)
+            .style(Style::new().bg(self.colors.buffer_bg))
+            .render(inner, buf);
+    }
+}
+
+//
+// Top level lv of 'lv_name', following parent_lv.
+//
+fn owner_of(lv_name: &str, lvs: &[LvmLvData]) -> String {
+    let mut name = lv_name;
+    // Bounded, in case of a parent loop. Parents of sub lvs of hidden lvs
+    // ([fast_cpool]) have no brackets.
+    for _ in 0..lvs.len() {
+        let unhidden = name.trim_matches(['[', ']']);
+        match lvs
+            .iter()
+            .find(|lv| lv.lv_name == name || lv.lv_name.trim_matches(['[', ']']) == unhidden)
+        {
+            Some(lv) if !lv.parent_lv.is_empty() => name = &lv.parent_lv,
+            _ => break,
+        }
+    }
+    name.to_string()
+}
+
+//
+// Pvs with the lv segments on them, in the order of 'pvs'.
+//
+pub fn extent_map_pvs(pvs: &[LvmPVData], lvs: &[LvmLvData]) -> Vec<ExtentMapPv> {
+    pvs.iter()
+        .map(|pv| {
+            let mut segs: Vec<ExtentMapSeg> = lvs
+                .iter()
+                .filter(|lv| lv.vg_name == pv.vg_name)
+                .flat_map(|lv| {
+                    lv.lv_segs
+                        .iter()
+                        .filter(|seg| seg.pvdev == pv.pv_name)
+                        .map(|seg| ExtentMapSeg {
+                            lv_name: lv.lv_name.clone(),
+                            owner: owner_of(&lv.lv_name, lvs),
+                            pv_start_pe: seg.pv_start_pe,
+                            size_pe: seg.size_pe,
+                        })
+                })
+                .collect();
+            segs.sort_by_key(|seg| seg.pv_start_pe);
+            ExtentMapPv {
+                pv_name: pv.pv_name.clone(),
+                pe_count: pv.pe_count,
+                segs,
+            }
+        })
+        .collect()
+}
+
+//
+// 'lv_name' and all lvs below it, e.g. lvpub, [lvpub_rimage_0], [lvpub_rmeta_0], ...
+//
+pub fn lv_and_sub_lvs(lv_name: &str, lvs: &[LvmLvData]) -> Vec<String> {
+    let mut names = vec![lv_name.to_string()];
+    let mut i = 0;
+    while i < names.len() {
+        // Parents of sub lvs of hidden lvs ([fast_cpool]) have no brackets.
+        let parent = names[i].trim_matches(['[', ']']).to_string();
+        for lv in lvs {
+            if lv.parent_lv == parent && !names.contains(&lv.lv_name) {
+                names.push(lv.lv_name.clone());
+            }
+        }
+        i += 1;
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+
+    use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+
+    use crate::lvm::{LvmBackend, fake::FakeBackend};
+    use crate::lvmapp::extentmap::{ExtentMap, extent_map_pvs, lv_and_sub_lvs};
+    use crate::lvmapp::res::{self, Colors};
+
+    #[test]
+    fn test_extent_map() {
+        let fake = FakeBackend::new();
+        fake.add_vg(
+            "vg01",
+            &[
+                ("/dev/sda1", 400 * 1024 * 1024),
+                ("/dev/sdb1", 400 * 1024 * 1024),
+            ],
+        );
+        // 100 extents per pv, raid1 puts one image (and rmeta) on each
+        fake.create_lv("lv1", "vg01", 100, "M", "linear", &[], &[])
+            .unwrap();
+        let mirrors = [crate::lvm::LvmExtraArg {
+            opt: "--mirrors".to_string(),
+            value: "1".to_string(),
+        }];
+        fake.create_lv("lvr", "vg01", 200, "M", "raid1", &[], &mirrors)
+            .unwrap();
+        let lvs = fake.get_lvs().unwrap();
+        let pvs = extent_map_pvs(&fake.get_pvs().unwrap(), &lvs);
+
+        assert_eq!(pvs.len(), 2);
+        assert_eq!(pvs[0].segs[0].lv_name, "lv1");
+        assert!(pvs[1].segs.iter().all(|seg| seg.owner == "lvr"));
+
+        let sub_lvs = lv_and_sub_lvs("lvr", &lvs);
+        assert_eq!(sub_lvs.len(), 5);
+        assert!(sub_lvs.contains(&"[lvr_rimage_1]".to_string()));
+
+        // label 9 + space, bar 20 cells of 5 extents
+        let area = Rect::new(0, 0, 32, 5);
+        let mut buf = Buffer::empty(area);
+        ExtentMap::new(Colors::new(&res::PALETTES[0]), pvs)
+            .highlight(sub_lvs)
+            .render(area, &mut buf);
+        let row =
+            |y: u16| -> String { (1..31).map(|x| buf[(x, y)].symbol().to_string()).collect() };
+        // lv1 25 extents, then rmeta/rimage 1+50, rest free. The last extent
+        // of the highlighted rimage gets a cell of its own.
+        assert_eq!(row(1), "/dev/sda1 ████████████████····");
+        assert_eq!(row(2), "/dev/sdb1 ███████████·········");
+        assert!(row(3).contains("lv1"));
+        assert!(row(3).contains("lvr"));
+    }
+}
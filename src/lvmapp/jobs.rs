@@ -0,0 +1,425 @@
+//
+// Lvm commands that change things run as jobs, each in a thread of its own, so
+// a slow lvconvert or lvresize --resizefs does not freeze the ui. Jobs are kept
+// after they finish, the job list (j) shows them with their output or error.
+//
+
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style, Stylize, palette::tailwind},
+    text::{Line, Text},
+    widgets::{
+        Block, BorderType, Borders, Cell, HighlightSpacing, Paragraph, Row, Table, TableState, Wrap,
+    },
+};
+
+use Constraint::{Length, Min};
+
+use crate::{
+    lvm::{LvmBackend, LvmError},
+    lvmapp::{
+        View, ViewType,
+        res::{self, Colors},
+    },
+};
+
+type JobFn = Box<dyn FnOnce() -> Result<String, LvmError> + Send>;
+
+//
+// What a view wants done, handed to the app which spawns it.
+//
+pub struct LvmJob {
+    title: String,
+    op: JobFn,
+}
+
+impl LvmJob {
+    pub fn new<F>(title: &str, op: F) -> Self
+    where
+        F: FnOnce() -> Result<String, LvmError> + Send + 'static,
+    {
+        Self {
+            title: title.to_string(),
+            op: Box::new(op),
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    //
+    // Run it here and now, what the job thread does.
+    //
+    pub fn run(self) -> Result<String, LvmError> {
+        (self.op)()
+    }
+}
+
+struct Job {
+    id: u32,
+    title: String,
+    started: Instant,
+    // How long it took, None while running.
+    took: Option<Duration>,
+    result: Option<Result<String, LvmError>>,
+    // Told about in the status line or an error popup.
+    reported: bool,
+}
+
+impl Job {
+    fn elapsed(&self) -> Duration {
+        self.took.unwrap_or_else(|| self.started.elapsed())
+    }
+
+    fn state(&self) -> &'static str {
+        match &self.result {
+            None => "running",
+            Some(Ok(_)) => "done",
+            Some(Err(_)) => "failed",
+        }
+    }
+
+    // 0 when done, the command's code when it failed, "-" if there is none.
+    fn exit_code(&self) -> String {
+        match &self.result {
+            Some(Ok(_)) => "0".to_string(),
+            Some(Err(LvmError::CommandFailed {
+                code: Some(code), ..
+            })) => code.to_string(),
+            _ => "-".to_string(),
+        }
+    }
+
+    // What lvm printed when done, stderr (or what went wrong) when failed.
+    fn output(&self) -> String {
+        match &self.result {
+            None => String::new(),
+            Some(Ok(stdout)) => stdout.clone(),
+            Some(Err(LvmError::CommandFailed { stderr, .. })) => stderr.clone(),
+            Some(Err(e)) => e.to_string(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct JobList {
+    seq: u32,
+    jobs: Vec<Job>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+// A job that finished since last asked, error is None if it went fine.
+pub struct JobDone {
+    pub title: String,
+    pub error: Option<String>,
+}
+
+//
+// The jobs of the app, clones share them.
+//
+#[derive(Clone, Default)]
+pub struct Jobs {
+    list: Arc<Mutex<JobList>>,
+}
+
+impl Jobs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn(&self, job: LvmJob) -> u32 {
+        let mut list = self.list.lock().unwrap();
+        list.seq += 1;
+        let id = list.seq;
+        list.jobs.push(Job {
+            id,
+            title: job.title.clone(),
+            started: Instant::now(),
+            took: None,
+            result: None,
+            reported: false,
+        });
+
+        let shared = self.list.clone();
+        let handle = thread::spawn(move || {
+            let result = job.run();
+            let mut list = shared.lock().unwrap();
+            if let Some(done) = list.jobs.iter_mut().find(|j| j.id == id) {
+                done.took = Some(done.started.elapsed());
+                done.result = Some(result);
+            }
+        });
+        list.threads.retain(|t| !t.is_finished());
+        list.threads.push(handle);
+        id
+    }
+
+    pub fn running(&self) -> usize {
+        let list = self.list.lock().unwrap();
+        list.jobs.iter().filter(|j| j.result.is_none()).count()
+    }
+
+    //
+    // Jobs finished since last called, each is returned once.
+    //
+    pub fn take_finished(&self) -> Vec<JobDone> {
+        let mut list = self.list.lock().unwrap();
+        list.jobs
+            .iter_mut()
+            .filter(|j| j.result.is_some() && !j.reported)
+            .map(|j| {
+                j.reported = true;
+                JobDone {
+                    title: j.title.clone(),
+                    error: j
+                        .result
+                        .as_ref()
+                        .unwrap()
+                        .as_ref()
+                        .err()
+                        .map(|e| e.to_string()),
+                }
+            })
+            .collect()
+    }
+
+    pub fn clear_finished(&self) {
+        let mut list = self.list.lock().unwrap();
+        list.jobs.retain(|j| j.result.is_none() || !j.reported);
+    }
+
+    //
+    // Block until all jobs are done, for tests and quitting.
+    //
+    pub fn wait(&self) {
+        let threads = std::mem::take(&mut self.list.lock().unwrap().threads);
+        for thread in threads {
+            let _ = thread.join();
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.list.lock().unwrap().jobs.len()
+    }
+}
+
+fn fmt_elapsed(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs < 60 {
+        format!("{:.1}s", d.as_secs_f64())
+    } else {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
+
+//
+// Jobs, newest last, with the output of the selected one below.
+//
+pub struct JobListView {
+    state: TableState,
+    jobs: Jobs,
+    lvm: Arc<dyn LvmBackend>,
+    colors: Colors,
+}
+
+impl View for JobListView {
+    fn refresh_data(&mut self) {
+        // noop, jobs are read when drawn
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::Jobs
+    }
+
+    //
+    // Done (true) on Esc, 'c' forgets the finished jobs.
+    //
+    fn handle_events(&mut self, key: &KeyEvent) -> core::result::Result<bool, &'static str> {
+        if key.kind == KeyEventKind::Press {
+            match key.code {
+                KeyCode::Esc => return Ok(true),
+                KeyCode::Down => self.next_row(),
+                KeyCode::Up => self.previous_row(),
+                KeyCode::Char('c') => {
+                    self.jobs.clear_finished();
+                    self.state.select(self.jobs.len().checked_sub(1));
+                }
+                _ => {}
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl JobListView {
+    pub fn new(jobs: Jobs, lvm: Arc<dyn LvmBackend>) -> Self {
+        let last = jobs.len().checked_sub(1);
+        Self {
+            state: TableState::default().with_selected(last),
+            jobs,
+            lvm,
+            colors: Colors::new(&res::PALETTES[0]),
+        }
+    }
+
+    fn next_row(&mut self) {
+        let len = self.jobs.len();
+        if len == 0 {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) if i < len - 1 => i + 1,
+            _ => 0,
+        };
+        self.state.select(Some(i));
+    }
+
+    fn previous_row(&mut self) {
+        let len = self.jobs.len();
+        if len == 0 {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(0) | None => len - 1,
+            Some(i) => i - 1,
+        };
+        self.state.select(Some(i));
+    }
+
+    // What a running job waits for, progress of the backend's own jobs if any.
+    fn running(&self) -> String {
+        let mut text = "Running...\n".to_string();
+        for job in self.lvm.pending_jobs() {
+            text += &format!("{} {:.0}%\n", job.path, job.percent);
+        }
+        text
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, rect: &Rect) {
+        let [table_area, output_area] = Layout::vertical([Min(6), Length(10)])
+            .margin(1)
+            .areas(*rect);
+        let list = self.jobs.list.lock().unwrap();
+
+        let header_style = Style::default()
+            .fg(self.colors.header_fg)
+            .bg(self.colors.header_bg);
+        let selected_row_style = Style::default()
+            .add_modifier(Modifier::REVERSED)
+            .fg(self.colors.selected_row_style_fg);
+        let header = ["#", "job", "state", "elapsed", "exit"]
+            .into_iter()
+            .map(Cell::from)
+            .collect::<Row>()
+            .style(header_style)
+            .height(1);
+        let rows = list.jobs.iter().enumerate().map(|(i, job)| {
+            let color = match i % 2 {
+                0 => self.colors.normal_row_color,
+                _ => self.colors.alt_row_color,
+            };
+            let state_style = match job.state() {
+                "failed" => Style::new().fg(tailwind::RED.c400).bold(),
+                "running" => Style::new().fg(tailwind::AMBER.c400),
+                _ => Style::new(),
+            };
+            Row::new([
+                Cell::from(job.id.to_string()),
+                Cell::from(job.title.clone()),
+                Cell::from(job.state()).style(state_style),
+                Cell::from(fmt_elapsed(job.elapsed())),
+                Cell::from(job.exit_code()),
+            ])
+            .style(Style::new().fg(self.colors.row_fg).bg(color))
+            .height(1)
+        });
+        let block = Block::default()
+            .title(" Jobs (c clears finished) ")
+            .border_style(Style::new().fg(self.colors.block_border))
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL);
+        let table = Table::new(rows, [Length(4), Min(30), Length(8), Length(8), Length(5)])
+            .header(header)
+            .row_highlight_style(selected_row_style)
+            .highlight_symbol(Text::from(" █ "))
+            .bg(self.colors.buffer_bg)
+            .block(block)
+            .highlight_spacing(HighlightSpacing::Always);
+        frame.render_stateful_widget(table, table_area, &mut self.state);
+
+        let selected = self.state.selected().and_then(|i| list.jobs.get(i));
+        let (title, output) = match selected {
+            Some(job) if job.result.is_none() => (format!(" #{} ", job.id), self.running()),
+            Some(job) => {
+                let stream = if job.state() == "done" {
+                    "stdout"
+                } else {
+                    "stderr"
+                };
+                (format!(" #{} {stream} ", job.id), job.output())
+            }
+            None => (" Output ".to_string(), String::new()),
+        };
+        let para = Paragraph::new(Text::from(
+            output.lines().map(Line::raw).collect::<Vec<_>>(),
+        ))
+        .style(Style::new().fg(self.colors.row_fg))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .title(title)
+                .border_style(Style::new().fg(self.colors.block_border))
+                .border_type(BorderType::Rounded)
+                .borders(Borders::ALL),
+        );
+        frame.render_widget(para, output_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lvm::LvmError;
+    use crate::lvmapp::jobs::{Jobs, LvmJob};
+
+    #[test]
+    fn test_jobs() {
+        let jobs = Jobs::new();
+        jobs.spawn(LvmJob::new("ok", || {
+            Ok("  Logical volume created.".to_string())
+        }));
+        jobs.spawn(LvmJob::new("fails", || {
+            Err(LvmError::CommandFailed {
+                cmd: "lvcreate".to_string(),
+                code: Some(5),
+                stderr: "Volume group not found".to_string(),
+            })
+        }));
+        jobs.wait();
+        assert_eq!(jobs.running(), 0);
+
+        let done = jobs.take_finished();
+        assert_eq!(done.len(), 2);
+        assert_eq!(done[0].title, "ok");
+        assert!(done[0].error.is_none());
+        assert!(done[1].error.as_ref().unwrap().contains("not found"));
+        assert!(jobs.take_finished().is_empty());
+        {
+            let list = jobs.list.lock().unwrap();
+            assert_eq!(list.jobs[1].exit_code(), "5");
+            assert_eq!(list.jobs[1].output(), "Volume group not found");
+        }
+
+        jobs.clear_finished();
+        assert_eq!(jobs.len(), 0);
+        assert_eq!(jobs.spawn(LvmJob::new("next", || Ok(String::new()))), 3);
+        jobs.wait();
+    }
+}
@@ -0,0 +1,387 @@
+use std::sync::Arc;
+
+use Constraint::{Length, Min};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Borders, Gauge, Paragraph},
+};
+
+use crate::{
+    lvm::{LvAttr, LvmBackend, LvmError, LvmLvData},
+    lvmapp::{
+        STATUS, View, ViewType, attrbadge, cacheview,
+        res::{self, Colors},
+        snapview,
+    },
+};
+
+pub struct LvInfoView {
+    vg_name: String,
+    lv_name: String,
+    lv_item: Option<LvmLvData>,
+    // All lvs of the vg, for the sub lv tree.
+    vg_lvs: Option<Vec<LvmLvData>>,
+    colors: Colors,
+    lvm: Arc<dyn LvmBackend>,
+}
+
+impl View for LvInfoView {
+    fn refresh_data(&mut self) {
+        if let Err(e) = self.fetch_data() {
+            STATUS
+                .lock()
+                .unwrap()
+                .set_status(&format!("Refresh of {} failed: {e}", self.lv_name));
+        }
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::LvInfo
+    }
+
+    fn handle_events(&mut self, _key: &crossterm::event::KeyEvent) -> Result<bool, &'static str> {
+        // Noop,
+        Ok(true)
+    }
+}
+
+impl LvInfoView {
+    pub fn new(vg_name: &str, lv_name: &str, lvm: Arc<dyn LvmBackend>) -> Self {
+        Self {
+            vg_name: vg_name.to_string(),
+            lv_name: lv_name.to_string(),
+            colors: Colors::new(&res::PALETTES[0]),
+            lv_item: None,
+            vg_lvs: None,
+            lvm,
+        }
+    }
+
+    //
+    // Fetch all first, on error previous data (if any) is left as is.
+    //
+    pub fn fetch_data(&mut self) -> Result<(), LvmError> {
+        let vg_lvs: Vec<LvmLvData> = self
+            .lvm
+            .get_lvs()?
+            .into_iter()
+            .filter(|lv| lv.vg_name == self.vg_name)
+            .collect();
+        let lv_item = vg_lvs
+            .iter()
+            .find(|lv| lv.lv_name == self.lv_name)
+            .cloned()
+            .ok_or_else(|| LvmError::CommandFailed {
+                cmd: "lvs".to_string(),
+                code: None,
+                stderr: format!(
+                    "Failed to find logical volume \"{}/{}\"",
+                    self.vg_name, self.lv_name
+                ),
+            })?;
+
+        self.lv_item = Some(lv_item);
+        self.vg_lvs = Some(vg_lvs);
+        Ok(())
+    }
+
+    pub fn lv_attr(&self) -> Option<String> {
+        self.lv_item.as_ref().map(|lv| lv.attr.clone())
+    }
+
+    pub fn lv(&self) -> Option<&LvmLvData> {
+        self.lv_item.as_ref()
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, inner_layout: &[Rect; 2]) {
+        let lv_info_layout = Layout::horizontal([Length(56), Min(0)]).horizontal_margin(1);
+        let [lv_info_area, right_area] = lv_info_layout.areas(inner_layout[0]);
+        self.render_lvinfo(frame, lv_info_area);
+
+        let lv = self.lv_item.as_ref().unwrap();
+        // Raid/mirror sync, cow usage of a snapshot or thin pool/lv data usage.
+        let gauge = match (lv.sync_percent, lv.snap_percent) {
+            (Some(sync), _) => {
+                // raid scrub or resync, and what the last check found.
+                let label = match lv.raid_mismatch_count {
+                    Some(n) => format!("{sync:.2}% {} ({n} mismatches)", lv.raid_sync_action),
+                    None => format!("{sync:.2}%"),
+                };
+                Some((" Sync ", sync, label))
+            }
+            (None, Some(_)) if snapview::is_invalid(lv) => {
+                Some((" Snapshot ", 100.0, "INVALID".to_string()))
+            }
+            (None, Some(snap)) => Some((" Snapshot ", snap, format!("{snap:.2}%"))),
+            (None, None) => lv.data_percent.map(|data| {
+                let label = match lv.metadata_percent {
+                    Some(meta) => format!("{data:.2}% (metadata {meta:.2}%)"),
+                    None => format!("{data:.2}%"),
+                };
+                (" Thin data ", data, label)
+            }),
+        };
+        if cacheview::is_cached(lv) {
+            // Cache usage and hit rates instead of a gauge.
+            let lines = cacheview::cache_detail_lines(lv);
+            let [cache_area, tree_area] =
+                Layout::vertical([Length(lines.len() as u16 + 2), Min(0)]).areas(right_area);
+            self.render_cache(frame, cache_area, lines);
+            self.render_tree(frame, tree_area);
+        } else if let Some((title, percent, label)) = gauge {
+            let [gbar_area, tree_area] = Layout::vertical([Length(5), Min(0)]).areas(right_area);
+            self.render_percent_bar(frame, gbar_area, title, percent, label);
+            self.render_tree(frame, tree_area);
+        } else {
+            self.render_tree(frame, right_area);
+        }
+
+        self.render_segs(frame, inner_layout[1]);
+    }
+
+    fn render_lvinfo(&mut self, frame: &mut Frame, area: Rect) {
+        let lv = self.lv_item.as_ref().unwrap();
+        let header_str = format!("{:<12} {:<42}", "Name", "Value");
+
+        let mut lines = vec![
+            Line::raw(header_str)
+                .fg(self.colors.header_fg)
+                .bg(self.colors.header_bg),
+        ];
+
+        let or_dash = |s: &str| {
+            if s.is_empty() {
+                "-".to_string()
+            } else {
+                s.to_string()
+            }
+        };
+        let dev = match (lv.kernel_major, lv.kernel_minor) {
+            (Some(major), Some(minor)) => format!("{major}:{minor}"),
+            _ => "-".to_string(),
+        };
+        let dm_name = lv.dm_path.rsplit('/').next().unwrap_or_default();
+        let rows = [
+            ("LV", lv.lv_name.clone()),
+            ("VG", lv.vg_name.clone()),
+            ("parent", or_dash(&lv.parent_lv)),
+            ("origin", or_dash(&lv.origin)),
+            ("path", or_dash(&lv.path)),
+            ("dm name", or_dash(dm_name)),
+            ("major:minor", dev),
+            (
+                "size (g)",
+                format!("{:.2}", lv.size as f64 / 1000.0 / 1000.0 / 1000.0),
+            ),
+            ("segtype", lv.segtype.clone()),
+            ("health", or_dash(&lv.health_status)),
+            ("tags", or_dash(&lv.tags.join(","))),
+            ("created", format!("{} {}", or_dash(&lv.time), lv.host)),
+            ("uuid", lv.uuid.clone()),
+        ];
+        for (name, value) in rows {
+            let line = format!("{:<12} {:<42}", name, value);
+            lines.push(Line::raw(line).fg(self.colors.header_fg));
+        }
+        // F1 explains the attr chars.
+        let mut attr_line = Line::raw(format!("{:<12} ", "attr")).fg(self.colors.header_fg);
+        attr_line
+            .spans
+            .extend(attrbadge::lv_attr_spans(&lv.attr).spans);
+        attr_line.spans.push(" (F1)".into());
+        lines.push(attr_line);
+        let mut flags_line = Line::raw(format!("{:<12} ", "flags")).fg(self.colors.header_fg);
+        flags_line
+            .spans
+            .extend(attrbadge::badges(&LvAttr::decode(&lv.attr).flags(), &self.colors).spans);
+        lines.push(flags_line);
+
+        let para = Paragraph::new(lines).style(
+            Style::new()
+                .fg(self.colors.row_fg)
+                .bg(self.colors.buffer_bg),
+        );
+
+        frame.render_widget(para, area);
+    }
+
+    fn render_percent_bar(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        title: &str,
+        percent: f64,
+        label: String,
+    ) {
+        let bar = Gauge::default()
+            .block(
+                Block::default()
+                    .border_style(Style::new().fg(self.colors.block_border))
+                    .title(title)
+                    .border_type(BorderType::Rounded)
+                    .borders(Borders::ALL),
+            )
+            .gauge_style(
+                Style::new()
+                    .fg(self.colors.selected_row_style_fg)
+                    .on_black()
+                    .italic(),
+            )
+            .label(label)
+            .ratio((percent / 100.0).clamp(0.0, 1.0));
+        frame.render_widget(bar, area);
+    }
+
+    fn render_cache(&self, frame: &mut Frame, area: Rect, lines: Vec<Line<'static>>) {
+        let para = Paragraph::new(lines)
+            .style(
+                Style::new()
+                    .fg(self.colors.row_fg)
+                    .bg(self.colors.buffer_bg),
+            )
+            .block(
+                Block::default()
+                    .title(" Cache ")
+                    .border_style(Style::new().fg(self.colors.block_border))
+                    .border_type(BorderType::Rounded)
+                    .borders(Borders::ALL),
+            );
+        frame.render_widget(para, area);
+    }
+
+    //
+    // The lv and its sub lvs (_rimage, _rmeta, ...) found via parent_lv.
+    //
+    fn render_tree(&mut self, frame: &mut Frame, area: Rect) {
+        let lvs = self.vg_lvs.as_ref().unwrap();
+        let lines: Vec<Line> = lv_tree(&self.lv_name, lvs)
+            .into_iter()
+            .map(|line| Line::raw(line).fg(self.colors.row_fg))
+            .collect();
+
+        let para = Paragraph::new(lines)
+            .style(Style::new().bg(self.colors.buffer_bg))
+            .block(
+                Block::default()
+                    .title(" Sub LVs ")
+                    .border_style(Style::new().fg(self.colors.block_border))
+                    .border_type(BorderType::Rounded)
+                    .borders(Borders::ALL),
+            );
+        frame.render_widget(para, area);
+    }
+
+    fn render_segs(&mut self, frame: &mut Frame, area: Rect) {
+        let lv = self.lv_item.as_ref().unwrap();
+        let lines: Vec<Line> = lv
+            .lv_segs
+            .iter()
+            .map(|seg| {
+                let line = format!(
+                    "pvdev={:<20} start_seg={:<10} seg_size={:<10}",
+                    seg.pvdev, seg.pv_start_pe, seg.size_pe
+                );
+                Line::raw(line)
+                    .fg(self.colors.row_fg)
+                    .bg(self.colors.buffer_bg)
+            })
+            .collect();
+
+        let para = Paragraph::new(lines)
+            .style(
+                Style::new()
+                    .fg(self.colors.row_fg)
+                    .bg(self.colors.buffer_bg),
+            )
+            .block(
+                Block::default()
+                    .title(" Segments ")
+                    .border_style(Style::new().fg(self.colors.block_border))
+                    .border_type(BorderType::Rounded)
+                    .borders(Borders::ALL),
+            );
+        frame.render_widget(para, area);
+    }
+}
+
+//
+// Lines of a tree with 'lv_name' as root, children sorted by name.
+//
+fn lv_tree(lv_name: &str, lvs: &[LvmLvData]) -> Vec<String> {
+    fn walk(name: &str, lvs: &[LvmLvData], prefix: &str, depth: usize, lines: &mut Vec<String>) {
+        // Bounded, in case of a parent loop.
+        if depth > lvs.len() {
+            return;
+        }
+        // Parents of sub lvs of hidden lvs ([fast_cpool]) have no brackets.
+        let parent = name.trim_matches(['[', ']']);
+        let mut children: Vec<&LvmLvData> =
+            lvs.iter().filter(|lv| lv.parent_lv == parent).collect();
+        children.sort_by(|a, b| a.lv_name.cmp(&b.lv_name));
+        for (i, lv) in children.iter().enumerate() {
+            let last = i == children.len() - 1;
+            let (branch, indent) = if last {
+                ("└─", "  ")
+            } else {
+                ("├─", "│ ")
+            };
+            lines.push(format!("{prefix}{branch}{} ({})", lv.lv_name, lv.segtype));
+            walk(
+                &lv.lv_name,
+                lvs,
+                &format!("{prefix}{indent}"),
+                depth + 1,
+                lines,
+            );
+        }
+    }
+
+    let segtype = lvs
+        .iter()
+        .find(|lv| lv.lv_name == lv_name)
+        .map(|lv| lv.segtype.as_str())
+        .unwrap_or_default();
+    let mut lines = vec![format!("{lv_name} ({segtype})")];
+    walk(lv_name, lvs, "", 0, &mut lines);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::lvm::{LvmBackend, LvmExtraArg, fake::FakeBackend};
+    use crate::lvmapp::lvinfoview::lv_tree;
+
+    #[test]
+    fn test_lv_tree() {
+        let fake = FakeBackend::new();
+        fake.add_vg(
+            "vg01",
+            &[
+                ("/dev/sda1", 1024 * 1024 * 1024),
+                ("/dev/sdb1", 1024 * 1024 * 1024),
+            ],
+        );
+        let mirrors = [LvmExtraArg {
+            opt: "--mirrors".to_string(),
+            value: "1".to_string(),
+        }];
+        fake.create_lv("lvr", "vg01", 100, "M", "raid1", &[], &mirrors)
+            .unwrap();
+
+        let lines = lv_tree("lvr", &fake.get_lvs().unwrap());
+        assert_eq!(
+            lines,
+            vec![
+                "lvr (raid1)",
+                "├─[lvr_rimage_0] (linear)",
+                "├─[lvr_rimage_1] (linear)",
+                "├─[lvr_rmeta_0] (linear)",
+                "└─[lvr_rmeta_1] (linear)",
+            ]
+        );
+    }
+}
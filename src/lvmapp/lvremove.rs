@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEventKind},
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Span, Text},
+};
+
+use crate::{
+    lvm::{
+        LvmBackend, LvmError, LvmLvData,
+        devinfo::{self, LvUsage},
+    },
+    lvmapp::{STATUS, jobs::LvmJob, popup::ConfPopup, res::Colors},
+};
+
+//
+// Confirm removal of an lv. If it is in use or has data on it the lv name has
+// to be typed before Enter does anything.
+//
+pub struct LvRemovePopup {
+    vg_name: String,
+    lv_name: String,
+    usage: LvUsage,
+    typed: String,
+    colors: Colors,
+    lvm: Arc<dyn LvmBackend>,
+    job: Option<LvmJob>,
+}
+
+impl LvRemovePopup {
+    //
+    // 'lvs' are the lvs of the vg, to find snapshots of 'lv'.
+    //
+    pub fn new(
+        lv: &LvmLvData,
+        lvs: &[LvmLvData],
+        colors: Colors,
+        lvm: Arc<dyn LvmBackend>,
+    ) -> Self {
+        Self {
+            vg_name: lv.vg_name.clone(),
+            lv_name: lv.lv_name.clone(),
+            usage: devinfo::lv_usage(lv, lvs),
+            typed: String::new(),
+            colors,
+            lvm,
+            job: None,
+        }
+    }
+
+    //
+    // The lvremove to run, once confirmed.
+    //
+    pub fn take_job(&mut self) -> Option<LvmJob> {
+        self.job.take()
+    }
+
+    fn needs_name(&self) -> bool {
+        self.usage.in_use() || self.usage.has_data()
+    }
+
+    fn confirmed(&self) -> bool {
+        !self.needs_name() || self.typed == self.lv_name
+    }
+
+    //
+    // Done (true) when removed or cancelled, lvremove errors are passed on.
+    //
+    pub fn handle_events(&mut self, key: &KeyEvent) -> Result<bool, LvmError> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Esc => {
+                STATUS.lock().unwrap().set_status("Lv removal cancelled.");
+                return Ok(true);
+            }
+            KeyCode::Enter if self.confirmed() => {
+                let (lvm, vg_name, lv_name) =
+                    (self.lvm.clone(), self.vg_name.clone(), self.lv_name.clone());
+                let title = format!("Remove LV {vg_name}/{lv_name}");
+                STATUS
+                    .lock()
+                    .unwrap()
+                    .set_status(&format!("Started: {title}"));
+                self.job = Some(LvmJob::new(&title, move || {
+                    lvm.remove_lv(&vg_name, &lv_name)
+                }));
+                return Ok(true);
+            }
+            KeyCode::Backspace => {
+                self.typed.pop();
+            }
+            KeyCode::Char(c) if self.needs_name() && self.typed.len() < 128 => {
+                self.typed.push(c);
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let popup_area = Rect {
+            x: area.width / 6,
+            y: area.height / 6,
+            width: area.width * 2 / 3,
+            height: 16.min(area.height),
+        };
+
+        let s1 = Style::new().white().bold();
+        let warn = Style::new().light_red().bold();
+        let usage = &self.usage;
+        let or_none = |v: &[String]| {
+            if v.is_empty() {
+                "-".to_string()
+            } else {
+                v.join(", ")
+            }
+        };
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::from("Remove logical volume '"),
+                Span::styled(self.lv_name.clone(), s1),
+                Span::from("' from volume group '"),
+                Span::styled(self.vg_name.clone(), s1),
+                Span::from("'?"),
+            ]),
+            Line::from(""),
+            Line::from(format!(
+                "open: {}   mounted on: {}",
+                if usage.open { "yes" } else { "no" },
+                or_none(&usage.mounts)
+            )),
+            Line::from(format!(
+                "holders: {}   signature: {}",
+                or_none(&usage.holders),
+                usage.fs_type.as_deref().unwrap_or("none found")
+            )),
+        ];
+        if !usage.snapshots.is_empty() {
+            lines.push(Line::styled(
+                format!("snapshots removed too: {}", usage.snapshots.join(", ")),
+                warn,
+            ));
+        }
+        if self.needs_name() {
+            let state = if usage.in_use() {
+                "in use"
+            } else {
+                "not empty"
+            };
+            lines.push(Line::from(""));
+            lines.push(Line::styled(
+                format!("The LV is {state}, type its name to confirm:"),
+                warn,
+            ));
+            let input_style = if self.confirmed() {
+                Style::new().fg(self.colors.header_fg)
+            } else {
+                Style::new().fg(self.colors.row_fg)
+            };
+            lines.push(Line::from(vec![
+                Span::styled(self.typed.clone(), input_style.underlined()),
+                Span::styled("_", input_style),
+            ]));
+        }
+
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(Text::from(lines))
+            .title(format!(" Remove {}/{} ", self.vg_name, self.lv_name));
+        frame.render_widget(popup, popup_area);
+    }
+}
@@ -0,0 +1,574 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Modifier, Style, Stylize, palette::tailwind},
+    text::{Line, Span, Text},
+    widgets::{Block, Padding, Paragraph},
+};
+use tui_widget_list::{ListBuilder, ListState, ListView, ScrollAxis};
+
+use Constraint::{Length, Max, Min};
+
+use crate::{
+    lvm::{self, LvmBackend, LvmError, LvmLvData, LvmResize, LvmVgData, devinfo, fmt_size},
+    lvmapp::{
+        STATUS, View, ViewType,
+        jobs::LvmJob,
+        lvview::{InputField, ListItem},
+        popup::ConfPopup,
+        res::{self, Colors},
+    },
+};
+
+const SIZE_OPTS: [&str; 5] = ["M", "G", "%FREE", "%VG", "T"];
+
+#[derive(PartialEq)]
+enum Focus {
+    LvSize,
+    LvSizeOpt,
+    ResizeFs,
+}
+
+pub struct LvResizeView {
+    focus: Focus,
+    popup_save: bool,
+    vg_name: String,
+    lv_name: String,
+    lv_item: Option<LvmLvData>,
+    vg_item: Option<LvmVgData>,
+    extent_size: u64,
+    fs_type: Option<&'static str>,
+    lvsize: InputField,
+    lvsize_opt_state: ListState,
+    resizefs: bool,
+    colors: Colors,
+    job: Option<LvmJob>,
+    lvm: Arc<dyn LvmBackend>,
+}
+
+impl View for LvResizeView {
+    fn refresh_data(&mut self) {
+        // noop
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::LvResize
+    }
+
+    //
+    // handle events related to this view. If done here return true, e.g if "back" or "save".
+    //
+    fn handle_events(&mut self, key: &KeyEvent) -> core::result::Result<bool, &'static str> {
+        if key.kind == KeyEventKind::Press {
+            match key.code {
+                KeyCode::Tab => self.next_focus(),
+                KeyCode::BackTab => self.prev_focus(),
+                KeyCode::Backspace if self.focus == Focus::LvSize && self.lvsize.pos > 0 => {
+                    self.lvsize.value.remove(self.lvsize.pos - 1);
+                    self.lvsize.pos -= 1;
+                }
+                KeyCode::Char(' ') if self.focus == Focus::ResizeFs => {
+                    self.resizefs = !self.resizefs;
+                }
+                KeyCode::Char(c) if self.focus == Focus::LvSize => self.insert(c),
+                KeyCode::Left if self.focus == Focus::LvSize && self.lvsize.pos > 0 => {
+                    self.lvsize.pos -= 1;
+                }
+                KeyCode::Right
+                    if self.focus == Focus::LvSize && self.lvsize.pos < self.lvsize.value.len() =>
+                {
+                    self.lvsize.pos += 1;
+                }
+                KeyCode::Down if self.focus == Focus::LvSizeOpt => self.lvsize_opt_state.next(),
+                KeyCode::Up if self.focus == Focus::LvSizeOpt => self.lvsize_opt_state.previous(),
+                KeyCode::F(6) => match self.check() {
+                    Ok(_) => self.popup_save = true,
+                    Err(e) => STATUS.lock().unwrap().set_status(&e),
+                },
+                KeyCode::Esc => {
+                    if self.popup_save {
+                        STATUS.lock().unwrap().set_status("Lv resize cancelled.");
+                        self.popup_save = false;
+                    } else {
+                        return Ok(true);
+                    }
+                }
+                KeyCode::Enter if self.popup_save => {
+                    self.popup_save = false;
+                    let Some(resize) = self.resize() else {
+                        return Ok(false);
+                    };
+                    // A filesystem resize can take a while, run it as a job.
+                    let (lvm, vg_name, lv_name) =
+                        (self.lvm.clone(), self.vg_name.clone(), self.lv_name.clone());
+                    let title = format!("Resize LV {vg_name}/{lv_name}");
+                    STATUS
+                        .lock()
+                        .unwrap()
+                        .set_status(&format!("Started: {title}"));
+                    self.job = Some(LvmJob::new(&title, move || {
+                        lvm.resize_lv(&vg_name, &lv_name, &resize)
+                    }));
+                    return Ok(true);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+impl LvResizeView {
+    pub fn new(vg_name: &str, lv_name: &str, lvm: Arc<dyn LvmBackend>) -> Self {
+        let mut lvsize_opt_state = ListState::default();
+        // Default select G
+        lvsize_opt_state.select(Some(1));
+        Self {
+            focus: Focus::LvSize,
+            popup_save: false,
+            vg_name: vg_name.to_string(),
+            lv_name: lv_name.to_string(),
+            lv_item: None,
+            vg_item: None,
+            extent_size: 0,
+            fs_type: None,
+            lvsize: InputField {
+                len_max: 8,
+                value: String::new(),
+                pos: 0,
+            },
+            lvsize_opt_state,
+            resizefs: false,
+            colors: Colors::new(&res::PALETTES[0]),
+            job: None,
+            lvm,
+        }
+    }
+
+    //
+    // lv, its vg and what is on the lv. --resizefs is preset if lvresize can
+    // resize the filesystem found.
+    //
+    pub fn fetch_data(&mut self) -> Result<(), LvmError> {
+        let vg_item = self.lvm.get_vg_info(&self.vg_name)?;
+        let lv_item = self
+            .lvm
+            .get_lvs()?
+            .into_iter()
+            .find(|lv| lv.vg_name == self.vg_name && lv.lv_name == self.lv_name)
+            .ok_or_else(|| LvmError::CommandFailed {
+                cmd: "lvs".to_string(),
+                code: None,
+                stderr: format!(
+                    "Failed to find logical volume \"{}/{}\"",
+                    self.vg_name, self.lv_name
+                ),
+            })?;
+        self.extent_size = self
+            .lvm
+            .get_pvs()?
+            .iter()
+            .find(|pv| pv.vg_name == self.vg_name)
+            .map_or(0, |pv| pv.pe_size);
+        self.fs_type = [&lv_item.path, &lv_item.dm_path]
+            .iter()
+            .filter(|dev| !dev.is_empty())
+            .find_map(|dev| devinfo::fs_type(Path::new(dev)));
+        self.resizefs = self.fs_type.is_some_and(fs_resizable);
+
+        self.lv_item = Some(lv_item);
+        self.vg_item = Some(vg_item);
+        Ok(())
+    }
+
+    //
+    // The lvresize to run, once confirmed.
+    //
+    pub fn take_job(&mut self) -> Option<LvmJob> {
+        self.job.take()
+    }
+
+    fn next_focus(&mut self) {
+        self.focus = match self.focus {
+            Focus::LvSize => Focus::LvSizeOpt,
+            Focus::LvSizeOpt => Focus::ResizeFs,
+            Focus::ResizeFs => Focus::LvSize,
+        };
+    }
+
+    fn prev_focus(&mut self) {
+        self.focus = match self.focus {
+            Focus::LvSize => Focus::ResizeFs,
+            Focus::LvSizeOpt => Focus::LvSize,
+            Focus::ResizeFs => Focus::LvSizeOpt,
+        };
+    }
+
+    // Digits, '+' or '-' first for relative sizes.
+    fn insert(&mut self, c: char) {
+        let sign = (c == '+' || c == '-') && self.lvsize.pos == 0;
+        if (c.is_ascii_digit() || sign) && self.lvsize.value.len() < self.lvsize.len_max {
+            self.lvsize.value.insert(self.lvsize.pos, c);
+            self.lvsize.pos += 1;
+        }
+    }
+
+    fn size_opt(&self) -> &'static str {
+        SIZE_OPTS[self.lvsize_opt_state.selected.unwrap_or(1)]
+    }
+
+    fn resize(&self) -> Option<LvmResize> {
+        let value = &self.lvsize.value;
+        let (sign, digits) = match value.chars().next() {
+            Some(c @ ('+' | '-')) => (Some(c), &value[1..]),
+            _ => (None, value.as_str()),
+        };
+        let size = digits.parse::<u64>().ok()?;
+        Some(LvmResize {
+            sign,
+            size,
+            size_unit: self.size_opt().to_string(),
+            resizefs: self.resizefs,
+        })
+    }
+
+    // (new size, vg free after), what lvm will end up with.
+    fn outcome(&self) -> Result<(u64, u64), String> {
+        let (Some(lv), Some(vg)) = (self.lv_item.as_ref(), self.vg_item.as_ref()) else {
+            return Err("No data".to_string());
+        };
+        let resize = self
+            .resize()
+            .ok_or_else(|| "Enter a size, e.g. 20, +5 or -2".to_string())?;
+        let new_size = resize
+            .new_size(lv.size, vg, self.extent_size)
+            .map_err(|e| e.to_string())?;
+        // Images and parity take their share of the vg too.
+        let factor = lv.stripes.max(1) as f64 / lv.data_stripes.max(1) as f64;
+        let used = ((new_size as f64 - lv.size as f64) * factor) as i64;
+        let free_after = vg.free as i64 - used;
+        if free_after < 0 {
+            return Err(format!(
+                "Not enough free space in {}, {} more needed.",
+                self.vg_name,
+                fmt_size(free_after.unsigned_abs())
+            ));
+        }
+        Ok((new_size, free_after as u64))
+    }
+
+    //
+    // Ok with an optional warning, or why the resize is refused.
+    //
+    fn check(&self) -> Result<Option<String>, String> {
+        let (new_size, _) = self.outcome()?;
+        let cur_size = self.lv_item.as_ref().map_or(0, |lv| lv.size);
+        check_resize(self.fs_type, cur_size, new_size, self.resizefs)
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, rect: &Rect) {
+        let inner_layout = &Layout::vertical([
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Min(7),
+        ])
+        .margin(2);
+        let [
+            header_area,
+            lvname_area,
+            lvsize_area,
+            resizefs_area,
+            _,
+            _,
+            info_area,
+        ] = inner_layout.areas(*rect);
+
+        let para_heading = Paragraph::new("RESIZE LOGICAL VOLUME")
+            .alignment(ratatui::layout::Alignment::Left)
+            .style(Style::new().fg(self.colors.block_border));
+        frame.render_widget(para_heading, header_area);
+
+        let h_layout = &Layout::horizontal([Length(10), Max(40)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [label_area, val_area] = h_layout.areas(lvname_area);
+        frame.render_widget(
+            Paragraph::new("lv:").style(Style::new().fg(self.colors.row_fg)),
+            label_area,
+        );
+        frame.render_widget(
+            Paragraph::new(format!("{}/{}", self.vg_name, self.lv_name))
+                .style(Style::new().fg(self.colors.header_bg)),
+            val_area,
+        );
+
+        let h_layout = &Layout::horizontal([Length(10), Max(9), Length(7)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [label_area, input_area, mut option_area] = h_layout.areas(lvsize_area);
+        frame.render_widget(
+            Paragraph::new("size:").style(Style::new().fg(self.colors.row_fg)),
+            label_area,
+        );
+        let input_style = Style::new()
+            .fg(self.colors.header_bg)
+            .underline_color(self.colors.header_bg)
+            .add_modifier(Modifier::UNDERLINED);
+        frame.render_widget(
+            Paragraph::new(
+                Text::from(self.lvsize.value.clone()).fg(self.colors.selected_column_style_fg),
+            )
+            .style(input_style),
+            input_area,
+        );
+        self.render_size_opt(frame, &mut option_area);
+        if self.focus == Focus::LvSize {
+            frame.set_cursor_position(Position::new(
+                input_area.x + (self.lvsize.pos as u16),
+                input_area.y,
+            ));
+        }
+
+        let h_layout = &Layout::horizontal([Length(10), Max(30)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [label_area, val_area] = h_layout.areas(resizefs_area);
+        frame.render_widget(
+            Paragraph::new("resizefs:").style(Style::new().fg(self.colors.row_fg)),
+            label_area,
+        );
+        let check_style = if self.focus == Focus::ResizeFs {
+            Style::new()
+                .bg(self.colors.header_bg)
+                .fg(self.colors.selected_column_style_fg)
+        } else {
+            Style::new().fg(self.colors.selected_column_style_fg)
+        };
+        let check = if self.resizefs { "[x]" } else { "[ ]" };
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(check, check_style),
+                Span::styled(" SPACE toggles", Style::new().fg(self.colors.block_border)),
+            ])),
+            val_area,
+        );
+
+        self.render_info(frame, info_area);
+
+        if self.popup_save {
+            self.render_popup_save(frame, rect);
+        }
+    }
+
+    fn render_size_opt(&mut self, frame: &mut Frame, rect: &mut Rect) {
+        rect.height = 1;
+        let list_style = match self.focus {
+            Focus::LvSizeOpt => Style::new()
+                .bg(self.colors.header_bg)
+                .fg(self.colors.selected_column_style_fg),
+            _ => Style::new()
+                .bg(self.colors.alt_row_color)
+                .fg(self.colors.selected_column_style_fg),
+        };
+
+        let builder = ListBuilder::new(|context| {
+            let mut item = ListItem::new(SIZE_OPTS[context.index]);
+            if context.is_selected {
+                item.style = Style::new().fg(self.colors.selected_cell_style_fg);
+            }
+            (item, 1)
+        });
+
+        let block = Block::default().padding(Padding::horizontal(1));
+        let list = ListView::new(builder, SIZE_OPTS.len())
+            .scroll_axis(ScrollAxis::Vertical)
+            .block(block)
+            .infinite_scrolling(true)
+            .style(list_style);
+
+        frame.render_stateful_widget(list, *rect, &mut self.lvsize_opt_state);
+        frame.render_widget(Text::from("▾").style(list_style).right_aligned(), *rect);
+    }
+
+    //
+    // Sizes before and after, the lvresize command and why it would be refused.
+    //
+    fn render_info(&self, frame: &mut Frame, area: Rect) {
+        let (Some(lv), Some(vg)) = (self.lv_item.as_ref(), self.vg_item.as_ref()) else {
+            return;
+        };
+        let label = |s: &str| Span::styled(format!("{s:<16}"), Style::new().fg(self.colors.row_fg));
+        let value = |s: String| Span::styled(s, Style::new().fg(self.colors.header_fg));
+
+        let outcome = self.outcome();
+        let (new_size, free_after) = match &outcome {
+            Ok((new_size, free_after)) => (fmt_size(*new_size), fmt_size(*free_after)),
+            Err(_) => ("-".to_string(), "-".to_string()),
+        };
+        let mut lines = vec![
+            Line::from(vec![
+                label("size"),
+                value(format!("{} -> {new_size}", fmt_size(lv.size))),
+            ]),
+            Line::from(vec![
+                label("vg free"),
+                value(format!("{} -> {free_after}", fmt_size(vg.free))),
+            ]),
+            Line::from(vec![
+                label("filesystem"),
+                value(self.fs_type.unwrap_or("none found").to_string()),
+            ]),
+        ];
+        if let Some(resize) = self.resize() {
+            lines.push(Line::from(vec![
+                label("command"),
+                value(format!(
+                    "lvresize {}",
+                    lvm::resize_lv_args(&self.vg_name, &self.lv_name, &resize).join(" ")
+                )),
+            ]));
+        }
+        match self.check() {
+            Ok(None) => {}
+            Ok(Some(warning)) => {
+                lines.push(Line::styled(warning, Style::new().fg(tailwind::AMBER.c400)));
+            }
+            Err(e) if self.lvsize.value.is_empty() => {
+                lines.push(Line::styled(e, Style::new().fg(self.colors.block_border)));
+            }
+            Err(e) => {
+                lines.push(Line::styled(e, Style::new().fg(tailwind::RED.c400).bold()));
+            }
+        }
+
+        let para = Paragraph::new(lines).block(Block::default().padding(Padding::horizontal(1)));
+        frame.render_widget(para, area);
+    }
+
+    fn render_popup_save(&self, frame: &mut Frame, rect: &Rect) {
+        let popup_area = Rect {
+            x: rect.width / 4,
+            y: rect.height / 3,
+            width: rect.width / 2,
+            height: rect.height / 2,
+        };
+        let s1 = Style::new().white().bold();
+        let cur_size = self.lv_item.as_ref().map_or(0, |lv| lv.size);
+        let new_size = self.outcome().map_or(0, |(new_size, _)| new_size);
+
+        let mut lines = vec![Line::from(vec![
+            Span::from("You are about to resize logical volume '"),
+            Span::from(self.lv_name.clone()).style(s1),
+            Span::from("' from "),
+            Span::from(fmt_size(cur_size)).style(s1),
+            Span::from(" to "),
+            Span::from(fmt_size(new_size)).style(s1),
+            Span::from(if self.resizefs {
+                ", filesystem included."
+            } else {
+                "."
+            }),
+        ])];
+        if let Ok(Some(warning)) = self.check() {
+            lines.push(Line::from(""));
+            lines.push(Line::styled(warning, Style::new().fg(tailwind::AMBER.c400)));
+        }
+
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(Text::from(lines))
+            .title(format!("Resize {}", self.lv_name));
+        frame.render_widget(popup, popup_area);
+    }
+}
+
+// lvresize --resizefs handles these (fsadm or lvm's own fs support).
+fn fs_resizable(fs_type: &str) -> bool {
+    matches!(fs_type, "ext2" | "ext3" | "ext4" | "xfs")
+}
+
+//
+// Is changing the lv from 'cur' to 'new' bytes safe for what is on it. Ok with a
+// warning if lvm will do it but the user should know, Err if it is refused.
+//
+fn check_resize(
+    fs_type: Option<&str>,
+    cur: u64,
+    new: u64,
+    resizefs: bool,
+) -> Result<Option<String>, String> {
+    if new == cur {
+        return Err("New size is the current size.".to_string());
+    }
+    if resizefs && let Some(fs) = fs_type.filter(|fs| !fs_resizable(fs)) {
+        return Err(format!(
+            "--resizefs can not resize {fs}, resize it by hand."
+        ));
+    }
+    let shrink = new < cur;
+    match fs_type {
+        Some("xfs") if shrink => Err("xfs can not be shrunk.".to_string()),
+        Some(fs) if shrink && !resizefs => {
+            Err(format!("{fs} on the LV, shrinking it needs --resizefs."))
+        }
+        Some("btrfs") if shrink => {
+            Err("Shrink btrfs first with 'btrfs filesystem resize', then the LV.".to_string())
+        }
+        Some("btrfs") => Ok(Some(
+            "Grow btrfs afterwards with 'btrfs filesystem resize max'.".to_string(),
+        )),
+        Some(fs) if !resizefs => Ok(Some(format!(
+            "{fs} is not grown with the LV without --resizefs."
+        ))),
+        None if shrink => Ok(Some(
+            "No filesystem found, data at the end of the LV is lost.".to_string(),
+        )),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::lvmapp::lvresize::check_resize;
+
+    #[test]
+    fn test_check_resize() {
+        let gb = 1024 * 1024 * 1024;
+        // xfs grows, never shrinks
+        assert_eq!(check_resize(Some("xfs"), 10 * gb, 20 * gb, true), Ok(None));
+        assert!(check_resize(Some("xfs"), 10 * gb, 5 * gb, true).is_err());
+        // ext4 shrinks with the fs only
+        assert_eq!(check_resize(Some("ext4"), 10 * gb, 5 * gb, true), Ok(None));
+        assert!(check_resize(Some("ext4"), 10 * gb, 5 * gb, false).is_err());
+        assert!(
+            check_resize(Some("ext4"), 10 * gb, 20 * gb, false)
+                .unwrap()
+                .is_some()
+        );
+        // btrfs by hand
+        assert!(check_resize(Some("btrfs"), 10 * gb, 20 * gb, true).is_err());
+        assert!(check_resize(Some("btrfs"), 10 * gb, 5 * gb, false).is_err());
+        assert!(
+            check_resize(Some("btrfs"), 10 * gb, 20 * gb, false)
+                .unwrap()
+                .is_some()
+        );
+        // Nothing known on it
+        assert!(
+            check_resize(None, 10 * gb, 5 * gb, false)
+                .unwrap()
+                .is_some()
+        );
+        assert!(check_resize(None, 10 * gb, 10 * gb, false).is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use crossterm::event::KeyEvent;
 use ratatui::{
     Frame,
@@ -13,9 +15,10 @@ use tui_widget_list::{ListBuilder, ListState, ListView, ScrollAxis};
 use Constraint::{Length, Max};
 
 use crate::{
-    lvm::LvmExtraArg,
+    lvm::{LvmBackend, LvmExtraArg},
     lvmapp::{
         STATUS, View, ViewType,
+        jobs::LvmJob,
         popup::ConfPopup,
         res::{self, Colors},
     },
@@ -23,9 +26,11 @@ use crate::{
 
 use crate::lvm::{self};
 
+const POOL_DISCARDS: [&str; 3] = ["passdown", "nopassdown", "ignore"];
+
 pub struct ListItem {
     text: String,
-    style: Style,
+    pub style: Style,
 }
 
 impl ListItem {
@@ -49,7 +54,7 @@ impl View for LvNewView<'_> {
     }
 
     fn view_type(&self) -> ViewType {
-        return ViewType::LvNew;
+        ViewType::LvNew
     }
 
     //
@@ -57,61 +62,52 @@ impl View for LvNewView<'_> {
     //
     fn handle_events(&mut self, key: &KeyEvent) -> core::result::Result<bool, &'static str> {
         if key.kind == KeyEventKind::Press {
-            if key.kind == KeyEventKind::Press {
-                match key.code {
-                    KeyCode::Tab => self.next_focus(),
-                    KeyCode::BackTab => self.prev_focus(),
-                    KeyCode::Backspace => self.remove(),
-                    KeyCode::Char(' ') => match self.focus {
-                        Focus::LvPvAv => self.move_availpv(),
-                        Focus::LvPvSel => self.move_selpv(),
-                        _ => {}
-                    },
-                    KeyCode::Char(to_insert) => {
-                        let c = to_insert;
-                        self.handle_keycode_char(c);
-                    }
-                    KeyCode::Right => self.right(),
-                    KeyCode::Left => self.left(),
-                    KeyCode::Down => self.down(),
-                    KeyCode::Up => self.up(),
-                    KeyCode::F(6) => {
-                        self.save_popup();
-                    }
-                    KeyCode::Esc => {
-                        if self.popup_save {
-                            // do nothing e.g stay in this view, reset popup flag.
-                            STATUS.lock().unwrap().set_status("Lv creation cancelled.");
-                            self.popup_save = false;
-                        } else {
-                            return Ok(true); // Done in this view.
-                        }
-                    }
-                    KeyCode::Enter => {
-                        if self.popup_save {
-                            self.popup_save = false;
-                            // well save/create lv
-                            let lv_c_res = self.handle_create_lv();
-                            match lv_c_res {
-                                Ok(_) => {
-                                    self.lvm_changed_flag = true;
-                                    let str = String::from("Created LV: ") + &self.lvname.value;
-                                    STATUS.lock().unwrap().set_status(str.as_str());
-                                    return Ok(true); // Done here
-                                }
-                                Err(e) => {
-                                    STATUS.lock().unwrap().set_status(&e);
-                                    // Nothing to do but indicate error and press on...
-                                }
-                            }
-                        }
-                    }
+            match key.code {
+                KeyCode::Tab => self.next_focus(),
+                KeyCode::BackTab => self.prev_focus(),
+                KeyCode::Backspace => self.remove(),
+                KeyCode::Char(' ') => match self.focus {
+                    Focus::LvPvAv => self.pv_select.toggle(PvList::Avail),
+                    Focus::LvPvSel => self.pv_select.toggle(PvList::Selected),
+                    Focus::PoolZero => self.pool_zero = !self.pool_zero,
                     _ => {}
+                },
+                KeyCode::Char(to_insert) => {
+                    let c = to_insert;
+                    self.handle_keycode_char(c);
+                }
+                KeyCode::Right => self.right(),
+                KeyCode::Left => self.left(),
+                KeyCode::Down => self.down(),
+                KeyCode::Up => self.up(),
+                KeyCode::F(6) => {
+                    self.save_popup();
+                }
+                KeyCode::Esc => {
+                    if self.popup_save {
+                        // do nothing e.g stay in this view, reset popup flag.
+                        STATUS.lock().unwrap().set_status("Lv creation cancelled.");
+                        self.popup_save = false;
+                    } else {
+                        return Ok(true); // Done in this view.
+                    }
+                }
+                KeyCode::Enter if self.popup_save => {
+                    self.popup_save = false;
+                    // lvcreate runs as a job, the app tells how it went.
+                    let job = self.create_lv_job();
+                    STATUS
+                        .lock()
+                        .unwrap()
+                        .set_status(&format!("Started: {}", job.title()));
+                    self.job = Some(job);
+                    return Ok(true); // Done here
                 }
+                _ => {}
             }
         }
 
-        return Ok(false);
+        Ok(false)
     }
 }
 
@@ -124,15 +120,132 @@ enum Focus {
     SegTypeStripes,
     SegTypeMirrors,
     SegTypeSsize,
+    PoolMetaSize,
+    PoolChunkSize,
+    PoolZero,
+    PoolDiscards,
+    ThinPool,
     LvPvAv,
     LvPvSel,
 }
 
-struct InputField {
-    len_max: usize,
-    value: String,
-    pos: usize,
+pub struct InputField {
+    pub len_max: usize,
+    pub value: String,
+    pub pos: usize,
+}
+#[derive(Clone, Copy, PartialEq)]
+pub enum PvList {
+    Avail,
+    Selected,
+}
+
+//
+// Pvs to allocate on, moved between the available and the selected list with
+// space. None selected leaves it to lvm.
+//
+pub struct PvSelect {
+    pub avail: Vec<String>,
+    pub selected: Vec<String>,
+    avail_state: ListState,
+    sel_state: ListState,
 }
+
+impl PvSelect {
+    pub fn new(pv_names: &[String]) -> Self {
+        Self {
+            avail: pv_names.to_vec(),
+            selected: Vec::new(),
+            avail_state: ListState::default(),
+            sel_state: ListState::default(),
+        }
+    }
+
+    fn list_mut(&mut self, list: PvList) -> (&mut Vec<String>, &mut ListState) {
+        match list {
+            PvList::Avail => (&mut self.avail, &mut self.avail_state),
+            PvList::Selected => (&mut self.selected, &mut self.sel_state),
+        }
+    }
+
+    //
+    // Move the highlighted pv of 'list' to the other one.
+    //
+    pub fn toggle(&mut self, list: PvList) {
+        let (pvs, state) = self.list_mut(list);
+        let Some(index) = state.selected.filter(|i| *i < pvs.len()) else {
+            return;
+        };
+        let pv = pvs.remove(index);
+        if index >= pvs.len() && index > 0 {
+            state.select(Some(index - 1));
+        }
+        match list {
+            PvList::Avail => self.selected.push(pv),
+            PvList::Selected => self.avail.push(pv),
+        }
+    }
+
+    pub fn next(&mut self, list: PvList) {
+        self.list_mut(list).1.next();
+    }
+
+    pub fn previous(&mut self, list: PvList) {
+        self.list_mut(list).1.previous();
+    }
+
+    pub fn render(
+        &mut self,
+        frame: &mut Frame,
+        rect: Rect,
+        focus: Option<PvList>,
+        colors: &Colors,
+    ) {
+        let h_layout = &Layout::horizontal([Max(15), Max(15)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [avail_pv_area, sel_pv_area] = h_layout.areas(rect);
+
+        for (list, title, area) in [
+            (PvList::Avail, "available", avail_pv_area),
+            (PvList::Selected, "selected", sel_pv_area),
+        ] {
+            let focused = focus == Some(list);
+            let (pvs, state) = self.list_mut(list);
+            let builder = ListBuilder::new(|context| {
+                let mut item = ListItem::new(pvs[context.index].clone());
+                if context.is_selected && focused {
+                    item.style = Style::new()
+                        .bg(colors.header_bg)
+                        .fg(colors.selected_column_style_fg);
+                } else {
+                    item.style = Style::new().fg(colors.selected_column_style_fg);
+                }
+                (item, 1)
+            });
+            let border_style = if focused {
+                Style::new().fg(colors.block_border)
+            } else {
+                Style::new().fg(colors.header_bg)
+            };
+            let block = Block::bordered()
+                .padding(Padding::horizontal(1))
+                .border_type(BorderType::Plain)
+                .title(title)
+                .border_style(border_style);
+            let list = ListView::new(builder, pvs.len())
+                .scroll_axis(ScrollAxis::Vertical)
+                .block(block)
+                .infinite_scrolling(true);
+            if state.selected.is_none() {
+                state.select(Some(0));
+            }
+
+            frame.render_stateful_widget(list, area, state);
+        }
+    }
+}
+
 pub struct LvNewView<'a> {
     focus: Focus,
     popup_save: bool,
@@ -142,20 +255,33 @@ pub struct LvNewView<'a> {
     lvsize_opt_state: ListState,
     lvsize_opts: [&'a str; 5], // TODO Get rid of this, use constant, forces 'a...
     lvsegtype_state: ListState,
-    lvsegtype_opts: [&'a str; 6], // TODO Get rid of this, use constant, forces 'a...
+    lvsegtype_opts: [&'a str; 8], // TODO Get rid of this, use constant, forces 'a...
     mirrors: InputField,
     stripes: InputField,
     strips_size: InputField,
-    pv_devs_avail: Vec<String>,
-    pv_devs_selected: Vec<String>,
-    sel_list_state: ListState,
-    avail_list_state: ListState,
+    // thin-pool options, sizes empty for lvm's default.
+    pool_metasize: InputField,
+    pool_chunksize: InputField,
+    pool_zero: bool,
+    pool_discards_state: ListState,
+    // Pools in the vg a thin lv can go to.
+    thin_pools: Vec<String>,
+    thin_pool_state: ListState,
+    pv_select: PvSelect,
     colors: Colors,
-    lvm_changed_flag: bool,
+    job: Option<LvmJob>,
+    lvm: Arc<dyn LvmBackend>,
 }
 
 impl<'a> LvNewView<'a> {
-    pub fn new(vg_name: &String, pvdev_names: &Vec<String>) -> Self {
+    pub fn new(vg_name: &str, pvdev_names: &[String], lvm: Arc<dyn LvmBackend>) -> Self {
+        let thin_pools = lvm
+            .get_lvs()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|lv| lv.vg_name == vg_name && lv.segtype == "thin-pool")
+            .map(|lv| lv.lv_name)
+            .collect();
         Self {
             focus: Focus::LvName,
             popup_save: false,
@@ -172,13 +298,19 @@ impl<'a> LvNewView<'a> {
             },
             lvsize_opt_state: ListState::default(),
             lvsize_opts: ["M", "G", "%FREE", "%VG", "T"],
-            sel_list_state: ListState::default(),
-            avail_list_state: ListState::default(),
-            vg_name: vg_name.clone(),
+            vg_name: vg_name.to_string(),
             lvsegtype_state: ListState::default(),
-            pv_devs_avail: pvdev_names.to_vec(),
-            pv_devs_selected: Vec::new(),
-            lvsegtype_opts: ["linear", "raid0", "raid1", "raid10", "raid5", "raid6"],
+            pv_select: PvSelect::new(pvdev_names),
+            lvsegtype_opts: [
+                "linear",
+                "raid0",
+                "raid1",
+                "raid10",
+                "raid5",
+                "raid6",
+                "thin-pool",
+                "thin",
+            ],
             mirrors: InputField {
                 len_max: 2,
                 value: String::from(""),
@@ -194,17 +326,35 @@ impl<'a> LvNewView<'a> {
                 value: String::from(""),
                 pos: 0,
             },
-            lvm_changed_flag: false,
+            pool_metasize: InputField {
+                len_max: 6,
+                value: String::from(""),
+                pos: 0,
+            },
+            pool_chunksize: InputField {
+                len_max: 6,
+                value: String::from(""),
+                pos: 0,
+            },
+            pool_zero: true,
+            pool_discards_state: ListState::default(),
+            thin_pools,
+            thin_pool_state: ListState::default(),
+            job: None,
+            lvm,
         }
     }
 
-    pub fn lvm_changed(&mut self) -> bool {
-        self.lvm_changed_flag
+    //
+    // The lvcreate to run, once confirmed.
+    //
+    pub fn take_job(&mut self) -> Option<LvmJob> {
+        self.job.take()
     }
 
     fn handle_nfocus_pvsel(&mut self) {
         // if nothing selected, no point ...
-        if self.pv_devs_selected.len() < 1 {
+        if self.pv_select.selected.is_empty() {
             self.focus = Focus::LvName;
         } else {
             self.focus = Focus::LvPvSel;
@@ -212,9 +362,9 @@ impl<'a> LvNewView<'a> {
     }
 
     fn handle_next_pv_selection(&mut self) {
-        if self.pv_devs_avail.len() > 0 {
+        if !self.pv_select.avail.is_empty() {
             self.focus = Focus::LvPvAv;
-        } else if self.pv_devs_selected.len() > 0 {
+        } else if !self.pv_select.selected.is_empty() {
             self.focus = Focus::LvPvSel;
         } else {
             self.focus = Focus::LvName;
@@ -223,9 +373,9 @@ impl<'a> LvNewView<'a> {
 
     fn handle_prev_pv_selection(&mut self) {
         // if nothing select in PvSel no point
-        if self.pv_devs_selected.len() > 0 {
+        if !self.pv_select.selected.is_empty() {
             self.focus = Focus::LvPvSel;
-        } else if self.pv_devs_avail.len() > 0 {
+        } else if !self.pv_select.avail.is_empty() {
             self.focus = Focus::LvPvAv;
         } else {
             self.focus = Focus::LvSegType;
@@ -240,13 +390,27 @@ impl<'a> LvNewView<'a> {
             "raid0" | "raid5" | "raid6" => self.focus = Focus::SegTypeSsize,
             "raid10" => self.focus = Focus::SegTypeMirrors,
             "raid1" => self.focus = Focus::SegTypeMirrors,
+            "thin-pool" => self.focus = Focus::PoolDiscards,
             _ => self.focus = Focus::LvSegType,
         }
     }
 
+    fn segtype(&self) -> &'a str {
+        self.lvsegtype_opts[self.lvsegtype_state.selected.unwrap_or(0)]
+    }
+
+    // Thin lvs are placed by their pool, no pvs to pick.
+    fn next_thin_focus(&mut self) {
+        if self.thin_pools.is_empty() {
+            self.focus = Focus::LvName;
+        } else {
+            self.focus = Focus::ThinPool;
+        }
+    }
+
     fn handle_pfocus_pvsel(&mut self) {
         // if nothing to select in PvAl, no point
-        if self.pv_devs_avail.len() < 1 {
+        if self.pv_select.avail.is_empty() {
             self.prev_segtype_focus();
         } else {
             self.focus = Focus::LvPvAv;
@@ -264,6 +428,8 @@ impl<'a> LvNewView<'a> {
                     "linear" => self.handle_next_pv_selection(),
                     "raid0" | "raid10" | "raid5" | "raid6" => self.focus = Focus::SegTypeStripes,
                     "raid1" => self.focus = Focus::SegTypeMirrors,
+                    "thin-pool" => self.focus = Focus::PoolMetaSize,
+                    "thin" => self.next_thin_focus(),
                     _ => self.handle_next_pv_selection(),
                 }
             }
@@ -276,6 +442,11 @@ impl<'a> LvNewView<'a> {
                 }
             }
             Focus::SegTypeMirrors => self.handle_next_pv_selection(),
+            Focus::PoolMetaSize => self.focus = Focus::PoolChunkSize,
+            Focus::PoolChunkSize => self.focus = Focus::PoolZero,
+            Focus::PoolZero => self.focus = Focus::PoolDiscards,
+            Focus::PoolDiscards => self.handle_next_pv_selection(),
+            Focus::ThinPool => self.focus = Focus::LvName,
             Focus::LvPvAv => self.handle_nfocus_pvsel(),
             Focus::LvPvSel => self.focus = Focus::LvName,
         }
@@ -283,6 +454,13 @@ impl<'a> LvNewView<'a> {
 
     fn prev_focus(&mut self) {
         match self.focus {
+            Focus::LvName if self.segtype() == "thin" => {
+                if self.thin_pools.is_empty() {
+                    self.focus = Focus::LvSegType;
+                } else {
+                    self.focus = Focus::ThinPool;
+                }
+            }
             Focus::LvName => self.handle_prev_pv_selection(),
             Focus::LvSize => self.focus = Focus::LvName,
             Focus::LvSizeOpt => self.focus = Focus::LvSize,
@@ -296,6 +474,11 @@ impl<'a> LvNewView<'a> {
                     _ => self.focus = Focus::LvSegType,
                 }
             }
+            Focus::PoolMetaSize => self.focus = Focus::LvSegType,
+            Focus::PoolChunkSize => self.focus = Focus::PoolMetaSize,
+            Focus::PoolZero => self.focus = Focus::PoolChunkSize,
+            Focus::PoolDiscards => self.focus = Focus::PoolZero,
+            Focus::ThinPool => self.focus = Focus::LvSegType,
             Focus::LvPvAv => self.prev_segtype_focus(),
             Focus::LvPvSel => self.handle_pfocus_pvsel(),
         }
@@ -303,35 +486,39 @@ impl<'a> LvNewView<'a> {
 
     fn insert(&mut self, char: &char) {
         match self.focus {
-            Focus::LvName => {
-                if self.lvname.value.len() < self.lvname.len_max {
-                    self.lvname.value.insert(self.lvname.pos, *char);
-                    self.lvname.pos += 1;
-                }
+            Focus::LvName if self.lvname.value.len() < self.lvname.len_max => {
+                self.lvname.value.insert(self.lvname.pos, *char);
+                self.lvname.pos += 1;
             }
-            Focus::LvSize => {
-                if self.lvsize.value.len() < self.lvsize.len_max {
-                    self.lvsize.value.insert(self.lvsize.pos, *char);
-                    self.lvsize.pos += 1;
-                }
+            Focus::LvSize if self.lvsize.value.len() < self.lvsize.len_max => {
+                self.lvsize.value.insert(self.lvsize.pos, *char);
+                self.lvsize.pos += 1;
             }
-            Focus::SegTypeStripes => {
-                if self.stripes.value.len() < self.stripes.len_max {
-                    self.stripes.value.insert(self.stripes.pos, *char);
-                    self.stripes.pos += 1;
-                }
+            Focus::SegTypeStripes if self.stripes.value.len() < self.stripes.len_max => {
+                self.stripes.value.insert(self.stripes.pos, *char);
+                self.stripes.pos += 1;
             }
-            Focus::SegTypeMirrors => {
-                if self.mirrors.value.len() < self.mirrors.len_max {
-                    self.mirrors.value.insert(self.mirrors.pos, *char);
-                    self.mirrors.pos += 1;
-                }
+            Focus::SegTypeMirrors if self.mirrors.value.len() < self.mirrors.len_max => {
+                self.mirrors.value.insert(self.mirrors.pos, *char);
+                self.mirrors.pos += 1;
             }
-            Focus::SegTypeSsize => {
-                if self.strips_size.value.len() < self.strips_size.len_max {
-                    self.strips_size.value.insert(self.strips_size.pos, *char);
-                    self.strips_size.pos += 1;
-                }
+            Focus::SegTypeSsize if self.strips_size.value.len() < self.strips_size.len_max => {
+                self.strips_size.value.insert(self.strips_size.pos, *char);
+                self.strips_size.pos += 1;
+            }
+            Focus::PoolMetaSize if self.pool_metasize.value.len() < self.pool_metasize.len_max => {
+                self.pool_metasize
+                    .value
+                    .insert(self.pool_metasize.pos, *char);
+                self.pool_metasize.pos += 1;
+            }
+            Focus::PoolChunkSize
+                if self.pool_chunksize.value.len() < self.pool_chunksize.len_max =>
+            {
+                self.pool_chunksize
+                    .value
+                    .insert(self.pool_chunksize.pos, *char);
+                self.pool_chunksize.pos += 1;
             }
             _ => {}
         }
@@ -339,35 +526,35 @@ impl<'a> LvNewView<'a> {
 
     fn remove(&mut self) {
         match self.focus {
-            Focus::LvName => {
-                if self.lvname.pos > 0 {
-                    self.lvname.value.remove(self.lvname.pos - 1);
-                    self.lvname.pos -= 1;
-                }
+            Focus::LvName if self.lvname.pos > 0 => {
+                self.lvname.value.remove(self.lvname.pos - 1);
+                self.lvname.pos -= 1;
             }
-            Focus::LvSize => {
-                if self.lvsize.pos > 0 {
-                    self.lvsize.value.remove(self.lvsize.pos - 1);
-                    self.lvsize.pos -= 1;
-                }
+            Focus::LvSize if self.lvsize.pos > 0 => {
+                self.lvsize.value.remove(self.lvsize.pos - 1);
+                self.lvsize.pos -= 1;
             }
-            Focus::SegTypeStripes => {
-                if self.stripes.pos > 0 {
-                    self.stripes.value.remove(self.stripes.pos - 1);
-                    self.stripes.pos -= 1;
-                }
+            Focus::SegTypeStripes if self.stripes.pos > 0 => {
+                self.stripes.value.remove(self.stripes.pos - 1);
+                self.stripes.pos -= 1;
             }
-            Focus::SegTypeSsize => {
-                if self.strips_size.pos > 0 {
-                    self.strips_size.value.remove(self.strips_size.pos - 1);
-                    self.strips_size.pos -= 1;
-                }
+            Focus::SegTypeSsize if self.strips_size.pos > 0 => {
+                self.strips_size.value.remove(self.strips_size.pos - 1);
+                self.strips_size.pos -= 1;
             }
-            Focus::SegTypeMirrors => {
-                if self.mirrors.pos > 0 {
-                    self.mirrors.value.remove(self.mirrors.pos - 1);
-                    self.mirrors.pos -= 1;
-                }
+            Focus::SegTypeMirrors if self.mirrors.pos > 0 => {
+                self.mirrors.value.remove(self.mirrors.pos - 1);
+                self.mirrors.pos -= 1;
+            }
+            Focus::PoolMetaSize if self.pool_metasize.pos > 0 => {
+                self.pool_metasize.value.remove(self.pool_metasize.pos - 1);
+                self.pool_metasize.pos -= 1;
+            }
+            Focus::PoolChunkSize if self.pool_chunksize.pos > 0 => {
+                self.pool_chunksize
+                    .value
+                    .remove(self.pool_chunksize.pos - 1);
+                self.pool_chunksize.pos -= 1;
             }
             _ => {}
         }
@@ -375,30 +562,26 @@ impl<'a> LvNewView<'a> {
 
     fn left(&mut self) {
         match self.focus {
-            Focus::LvName => {
-                if self.lvname.pos > 0 {
-                    self.lvname.pos -= 1;
-                }
+            Focus::LvName if self.lvname.pos > 0 => {
+                self.lvname.pos -= 1;
             }
-            Focus::LvSize => {
-                if self.lvsize.pos > 0 {
-                    self.lvsize.pos -= 1;
-                }
+            Focus::LvSize if self.lvsize.pos > 0 => {
+                self.lvsize.pos -= 1;
             }
-            Focus::SegTypeStripes => {
-                if self.stripes.pos > 0 {
-                    self.stripes.pos -= 1;
-                }
+            Focus::SegTypeStripes if self.stripes.pos > 0 => {
+                self.stripes.pos -= 1;
             }
-            Focus::SegTypeMirrors => {
-                if self.mirrors.pos > 0 {
-                    self.mirrors.pos -= 1;
-                }
+            Focus::SegTypeMirrors if self.mirrors.pos > 0 => {
+                self.mirrors.pos -= 1;
             }
-            Focus::SegTypeSsize => {
-                if self.strips_size.pos > 0 {
-                    self.strips_size.pos -= 1;
-                }
+            Focus::SegTypeSsize if self.strips_size.pos > 0 => {
+                self.strips_size.pos -= 1;
+            }
+            Focus::PoolMetaSize if self.pool_metasize.pos > 0 => {
+                self.pool_metasize.pos -= 1;
+            }
+            Focus::PoolChunkSize if self.pool_chunksize.pos > 0 => {
+                self.pool_chunksize.pos -= 1;
             }
             _ => {}
         }
@@ -406,31 +589,33 @@ impl<'a> LvNewView<'a> {
 
     fn right(&mut self) {
         match self.focus {
-            Focus::LvName => {
-                if self.lvname.pos > 0 && self.lvname.pos < (self.lvname.value.len()) {
-                    self.lvname.pos += 1;
-                }
+            Focus::LvName if self.lvname.pos > 0 && self.lvname.pos < (self.lvname.value.len()) => {
+                self.lvname.pos += 1;
             }
-            Focus::LvSize => {
-                if self.lvsize.pos > 0 && self.lvsize.pos < (self.lvsize.value.len()) {
-                    self.lvsize.pos += 1;
-                }
+            Focus::LvSize if self.lvsize.pos > 0 && self.lvsize.pos < (self.lvsize.value.len()) => {
+                self.lvsize.pos += 1;
             }
-            Focus::SegTypeStripes => {
-                if self.stripes.pos > 0 && self.stripes.pos < (self.stripes.value.len()) {
-                    self.stripes.pos += 1;
-                }
+            Focus::SegTypeStripes
+                if self.stripes.pos > 0 && self.stripes.pos < (self.stripes.value.len()) =>
+            {
+                self.stripes.pos += 1;
             }
-            Focus::SegTypeMirrors => {
-                if self.mirrors.pos > 0 && self.mirrors.pos < (self.mirrors.value.len()) {
-                    self.mirrors.pos += 1;
-                }
+            Focus::SegTypeMirrors
+                if self.mirrors.pos > 0 && self.mirrors.pos < (self.mirrors.value.len()) =>
+            {
+                self.mirrors.pos += 1;
             }
-            Focus::SegTypeSsize => {
-                if self.strips_size.pos > 0 && self.strips_size.pos < (self.strips_size.value.len())
-                {
-                    self.strips_size.pos += 1;
-                }
+            Focus::SegTypeSsize
+                if self.strips_size.pos > 0
+                    && self.strips_size.pos < (self.strips_size.value.len()) =>
+            {
+                self.strips_size.pos += 1;
+            }
+            Focus::PoolMetaSize if self.pool_metasize.pos < self.pool_metasize.value.len() => {
+                self.pool_metasize.pos += 1;
+            }
+            Focus::PoolChunkSize if self.pool_chunksize.pos < self.pool_chunksize.value.len() => {
+                self.pool_chunksize.pos += 1;
             }
             _ => {}
         }
@@ -444,6 +629,14 @@ impl<'a> LvNewView<'a> {
             Focus::LvSegType => {
                 self.lvsegtype_state.previous();
             }
+            Focus::PoolDiscards => {
+                self.pool_discards_state.previous();
+            }
+            Focus::ThinPool => {
+                self.thin_pool_state.previous();
+            }
+            Focus::LvPvAv => self.pv_select.previous(PvList::Avail),
+            Focus::LvPvSel => self.pv_select.previous(PvList::Selected),
             _ => {}
         }
     }
@@ -456,33 +649,65 @@ impl<'a> LvNewView<'a> {
             Focus::LvSegType => {
                 self.lvsegtype_state.next();
             }
+            Focus::PoolDiscards => {
+                self.pool_discards_state.next();
+            }
+            Focus::ThinPool => {
+                self.thin_pool_state.next();
+            }
+            Focus::LvPvAv => self.pv_select.next(PvList::Avail),
+            Focus::LvPvSel => self.pv_select.next(PvList::Selected),
             _ => {}
         }
     }
 
     fn save_popup(&mut self) {
         // cause "popup" confim dialog
-        self.popup_save = true;
-    }
-
-    fn move_availpv(&mut self) {
-        if self.avail_list_state.selected.is_none() || self.pv_devs_avail.len() < 1 {
-            return;
+        match self.check() {
+            Ok(()) => self.popup_save = true,
+            Err(e) => STATUS.lock().unwrap().set_status(&e),
         }
-        let select_index = self.avail_list_state.selected.unwrap();
-        // pop/remove from avail list, push to selected.
-        let pv_item = self.pv_devs_avail.remove(select_index);
-        self.pv_devs_selected.push(pv_item);
     }
 
-    fn move_selpv(&mut self) {
-        if self.sel_list_state.selected.is_none() || self.pv_devs_selected.len() < 1 {
-            return;
+    //
+    // Err with what lvcreate would refuse, before asking to create.
+    //
+    fn check(&self) -> Result<(), String> {
+        if self.lvsize.value.parse::<u64>().is_err() {
+            return Err("Enter a size in whole units, e.g. 10 (G).".to_string());
+        }
+        let size_opt = self.lvsize_opts[self.lvsize_opt_state.selected.unwrap_or(1)];
+        match self.segtype() {
+            "thin" if self.thin_pools.is_empty() => Err(format!(
+                "No thin pool in {}, create a thin-pool first.",
+                self.vg_name
+            )),
+            "thin" if size_opt.starts_with('%') => {
+                Err("The virtual size of a thin lv can not be a percentage.".to_string())
+            }
+            "thin-pool" => {
+                let metasize = &self.pool_metasize.value;
+                if !metasize.is_empty() && lvm::parse_size_arg(metasize, "M").is_none() {
+                    return Err(format!("Invalid metadata size {metasize}."));
+                }
+                let chunksize = &self.pool_chunksize.value;
+                if !chunksize.is_empty() {
+                    // 64K to 1G, a multiple of 64K.
+                    let k64 = 64 * 1024;
+                    match lvm::parse_size_arg(chunksize, "K") {
+                        Some(bytes)
+                            if bytes % k64 == 0 && (k64..=1024 * 1024 * 1024).contains(&bytes) => {}
+                        _ => {
+                            return Err(format!(
+                                "Invalid chunk size {chunksize}, 64K to 1G in steps of 64K."
+                            ));
+                        }
+                    }
+                }
+                Ok(())
+            }
+            _ => Ok(()),
         }
-        let select_index = self.sel_list_state.selected.unwrap();
-        // pop/remove from avail list, push to selected.
-        let pv_item = self.pv_devs_selected.remove(select_index);
-        self.pv_devs_avail.push(pv_item);
     }
 
     fn style_input(&mut self) -> Style {
@@ -495,61 +720,69 @@ impl<'a> LvNewView<'a> {
     fn handle_keycode_char(&mut self, c: char) {
         match self.focus {
             Focus::LvSize => {
-                if c >= '0' && c <= '9' || c == '.' {
+                if c.is_ascii_digit() || c == '.' {
                     self.insert(&c);
                 }
             }
             Focus::SegTypeMirrors | Focus::SegTypeStripes | Focus::SegTypeSsize => {
-                if c >= '0' && c <= '9' {
+                if c.is_ascii_digit() {
+                    self.insert(&c);
+                }
+            }
+            Focus::PoolMetaSize | Focus::PoolChunkSize => {
+                if c.is_ascii_digit() || "kKmMgG".contains(c) {
                     self.insert(&c);
                 }
             }
             _ => {
-                if (c >= 'a' && c <= 'z')
-                    || (c >= 'A' && c <= 'Z')
-                    || c == '_'
-                    || c == '-'
-                    || (c >= '0' && c <= '9')
-                {
+                if lvm::is_name_char(c) {
                     self.insert(&c);
                 }
             }
         }
     }
 
-    fn handle_create_lv(&mut self) -> Result<String, String> {
+    fn create_lv_job(&self) -> LvmJob {
         let size = self.lvsize.value.parse::<u64>().unwrap();
-        let size_opt = self.lvsize_opts[self.lvsize_opt_state.selected.unwrap()];
-        let segtype = &self.lvsegtype_opts[self.lvsegtype_state.selected.unwrap()].to_string();
-        let lv_name = &self.lvname.value;
-        let vg_name = &self.vg_name;
+        let size_opt = self.lvsize_opts[self.lvsize_opt_state.selected.unwrap()].to_string();
+        let segtype = self.lvsegtype_opts[self.lvsegtype_state.selected.unwrap()].to_string();
+        let lv_name = self.lvname.value.clone();
+        let vg_name = self.vg_name.clone();
         let mut lvm_extra_args = Vec::<lvm::LvmExtraArg>::new();
-        self.populate_extra_opts(segtype, &mut lvm_extra_args);
+        self.populate_extra_opts(&segtype, &mut lvm_extra_args);
+        let pvl = if segtype == "thin" {
+            Vec::new()
+        } else {
+            self.pv_select.selected.clone()
+        };
 
-        return lvm::create_lv(
-            lv_name,
-            vg_name,
-            size,
-            &size_opt.to_string(),
-            segtype,
-            &self.pv_devs_selected,
-            &lvm_extra_args,
-        );
+        let lvm = self.lvm.clone();
+        LvmJob::new(&format!("Create LV {vg_name}/{lv_name}"), move || {
+            lvm.create_lv(
+                &lv_name,
+                &vg_name,
+                size,
+                &size_opt,
+                &segtype,
+                &pvl,
+                &lvm_extra_args,
+            )
+        })
     }
 
-    fn populate_extra_opts(&self, segtype: &String, extra_opts: &mut Vec<lvm::LvmExtraArg>) {
-        match segtype.as_str() {
+    fn populate_extra_opts(&self, segtype: &str, extra_opts: &mut Vec<lvm::LvmExtraArg>) {
+        match segtype {
             "linear" => (),
             "raid0" | "raid5" | "raid6" => {
                 // --stripes,  --stripesize
-                if self.stripes.value.len() > 0 {
+                if !self.stripes.value.is_empty() {
                     let stripes = LvmExtraArg {
                         opt: "--stripes".to_string(),
                         value: self.stripes.value.clone(),
                     };
                     extra_opts.push(stripes);
                 }
-                if self.strips_size.value.len() > 0 {
+                if !self.strips_size.value.is_empty() {
                     let ss = LvmExtraArg {
                         opt: "--stripesize".to_string(),
                         value: self.strips_size.value.clone(),
@@ -559,21 +792,21 @@ impl<'a> LvNewView<'a> {
             }
             "raid10" => {
                 // --stripes,  --stripesize , --mirrors
-                if self.mirrors.value.len() > 0 {
+                if !self.mirrors.value.is_empty() {
                     let mirrors = LvmExtraArg {
                         opt: "--mirrors".to_string(),
                         value: self.mirrors.value.clone(),
                     };
                     extra_opts.push(mirrors);
                 }
-                if self.strips_size.value.len() > 0 {
+                if !self.strips_size.value.is_empty() {
                     let ss = LvmExtraArg {
                         opt: "--stripesize".to_string(),
                         value: self.strips_size.value.clone(),
                     };
                     extra_opts.push(ss);
                 }
-                if self.stripes.value.len() > 0 {
+                if !self.stripes.value.is_empty() {
                     let stripes = LvmExtraArg {
                         opt: "--stripes".to_string(),
                         value: self.stripes.value.clone(),
@@ -581,15 +814,46 @@ impl<'a> LvNewView<'a> {
                     extra_opts.push(stripes);
                 }
             }
-            "raid1" => {
+            "raid1"
                 // mirrors,
-                if self.mirrors.value.len() > 0 {
+                if !self.mirrors.value.is_empty() => {
                     let mirrors = LvmExtraArg {
                         opt: "--mirrors".to_string(),
                         value: self.mirrors.value.clone(),
                     };
                     extra_opts.push(mirrors);
                 }
+            "thin-pool" => {
+                // --poolmetadatasize, --chunksize, --zero, --discards
+                let mut push = |opt: &str, value: String| {
+                    extra_opts.push(LvmExtraArg {
+                        opt: opt.to_string(),
+                        value,
+                    })
+                };
+                if !self.pool_metasize.value.is_empty() {
+                    push("--poolmetadatasize", self.pool_metasize.value.clone());
+                }
+                if !self.pool_chunksize.value.is_empty() {
+                    push("--chunksize", self.pool_chunksize.value.clone());
+                }
+                push("--zero", if self.pool_zero { "y" } else { "n" }.to_string());
+                let discards = POOL_DISCARDS[self.pool_discards_state.selected.unwrap_or(0)];
+                push("--discards", discards.to_string());
+            }
+            "thin" => {
+                // --thinpool
+                if let Some(pool) = self
+                    .thin_pool_state
+                    .selected
+                    .and_then(|i| self.thin_pools.get(i))
+                    .or(self.thin_pools.first())
+                {
+                    extra_opts.push(LvmExtraArg {
+                        opt: "--thinpool".to_string(),
+                        value: pool.clone(),
+                    });
+                }
             }
             _ => (),
         }
@@ -686,7 +950,7 @@ impl<'a> LvNewView<'a> {
             Length(1),
             Length(1),
             Length(1),
-            Length(3),
+            Length(4),
             Length(1),
             Length(10),
         ])
@@ -699,7 +963,7 @@ impl<'a> LvNewView<'a> {
             lvtype_area,
             mut lvtype_options_area,
             pv_sel_label,
-            mut pv_sel_area,
+            pv_sel_area,
         ] = inner_layout.areas(*rect);
         let para_heading = Paragraph::new("CREATE LOGICAL VOLUMNE")
             .alignment(ratatui::layout::Alignment::Left)
@@ -777,7 +1041,7 @@ impl<'a> LvNewView<'a> {
 
         // Volumne type, linear, raid etc.
         // Redefine layout for next input row
-        let h_layout = &Layout::horizontal([Length(8), Length(9)])
+        let h_layout = &Layout::horizontal([Length(8), Length(12)])
             .horizontal_margin(1)
             .spacing(1);
         let [mut label_area, mut option_area] = h_layout.areas(lvtype_area);
@@ -786,7 +1050,6 @@ impl<'a> LvNewView<'a> {
             .alignment(ratatui::layout::Alignment::Left)
             .style(Style::new().fg(self.colors.row_fg));
         label_area.height = 1;
-        input_area.height = 1;
         frame.render_widget(para_label, label_area);
         self.render_segtype_opt(frame, &mut option_area);
 
@@ -797,7 +1060,13 @@ impl<'a> LvNewView<'a> {
             .alignment(ratatui::layout::Alignment::Left)
             .style(Style::new().fg(self.colors.block_border));
         frame.render_widget(para_sel, pv_sel_label);
-        self.render_pvsel(frame, &mut pv_sel_area);
+        let pv_focus = match self.focus {
+            Focus::LvPvAv => Some(PvList::Avail),
+            Focus::LvPvSel => Some(PvList::Selected),
+            _ => None,
+        };
+        self.pv_select
+            .render(frame, pv_sel_area, pv_focus, &self.colors);
 
         if self.popup_save {
             self.render_popup_save(frame, rect);
@@ -834,7 +1103,7 @@ impl<'a> LvNewView<'a> {
             Span::from(segtype),
         ]);
 
-        if lvm_extra_args.len() > 0 {
+        if !lvm_extra_args.is_empty() {
             line.push_span(Span::from("'. Extra args: "));
             line.push_span(Span::from(extra_args_msg));
             line.push_span(Span::from(""));
@@ -1022,6 +1291,139 @@ impl<'a> LvNewView<'a> {
         }
     }
 
+    //
+    // Thin pool
+    // --poolmetadatasize Size
+    // --chunksize Size
+    // --zero y|n
+    // --discards passdown|nopassdown|ignore
+    //
+    fn render_segtype_thin_pool(&mut self, frame: &mut Frame, rect: &Rect) {
+        let v_layout = &Layout::vertical([Length(1), Length(1), Length(1), Length(1)]);
+        let [meta_area, chunk_area, zero_area, discards_area] = v_layout.areas(*rect);
+
+        let h_layout = &Layout::horizontal([
+            Length(("chunk size:".len() + 1).try_into().unwrap()),
+            Length(7),
+            Length(24),
+        ])
+        .horizontal_margin(1)
+        .spacing(1);
+        let hint_style = Style::new().fg(self.colors.block_border);
+        let input_style = self.style_input();
+
+        for (label, area, input, focus, hint) in [
+            (
+                "metadata:",
+                meta_area,
+                &self.pool_metasize,
+                Focus::PoolMetaSize,
+                "e.g. 64M, empty for auto",
+            ),
+            (
+                "chunk size:",
+                chunk_area,
+                &self.pool_chunksize,
+                Focus::PoolChunkSize,
+                "e.g. 64K, empty for auto",
+            ),
+        ] {
+            let [label_area, input_area, hint_area] = h_layout.areas(area);
+            let para_label = Paragraph::new(label)
+                .alignment(ratatui::layout::Alignment::Left)
+                .style(Style::new().fg(self.colors.row_fg));
+            let para_input =
+                Paragraph::new(input.value.clone().fg(self.colors.selected_column_style_fg))
+                    .alignment(ratatui::layout::Alignment::Left)
+                    .style(input_style);
+            frame.render_widget(para_label, label_area);
+            frame.render_widget(para_input, input_area);
+            frame.render_widget(Paragraph::new(hint).style(hint_style), hint_area);
+            if self.focus == focus {
+                frame.set_cursor_position(Position::new(
+                    input_area.x + (input.pos as u16),
+                    input_area.y,
+                ));
+            }
+        }
+
+        let [label_area, toggle_area, hint_area] = h_layout.areas(zero_area);
+        frame.render_widget(
+            Paragraph::new("zero:").style(Style::new().fg(self.colors.row_fg)),
+            label_area,
+        );
+        let toggle_style = if self.focus == Focus::PoolZero {
+            Style::new()
+                .bg(self.colors.header_bg)
+                .fg(self.colors.selected_column_style_fg)
+        } else {
+            Style::new().fg(self.colors.selected_column_style_fg)
+        };
+        let toggle = if self.pool_zero { "[x]" } else { "[ ]" };
+        frame.render_widget(Paragraph::new(toggle).style(toggle_style), toggle_area);
+        frame.render_widget(Paragraph::new("SPACE toggles").style(hint_style), hint_area);
+
+        let h_layout = &Layout::horizontal([
+            Length(("chunk size:".len() + 1).try_into().unwrap()),
+            Length(13),
+        ])
+        .horizontal_margin(1)
+        .spacing(1);
+        let [label_area, option_area] = h_layout.areas(discards_area);
+        frame.render_widget(
+            Paragraph::new("discards:").style(Style::new().fg(self.colors.row_fg)),
+            label_area,
+        );
+        render_dropdown(
+            frame,
+            option_area,
+            &POOL_DISCARDS,
+            &mut self.pool_discards_state,
+            self.focus == Focus::PoolDiscards,
+            &self.colors,
+        );
+    }
+
+    //
+    // Thin lv, --thinpool Pool, the size is the virtual size (-V).
+    //
+    fn render_segtype_thin(&mut self, frame: &mut Frame, rect: &Rect) {
+        let v_layout = &Layout::vertical([Length(1), Length(1)]);
+        let [pool_area, hint_area] = v_layout.areas(*rect);
+        let h_layout =
+            &Layout::horizontal([Length(("pool:".len() + 1).try_into().unwrap()), Max(28)])
+                .horizontal_margin(1)
+                .spacing(1);
+        let [label_area, option_area] = h_layout.areas(pool_area);
+        frame.render_widget(
+            Paragraph::new("pool:").style(Style::new().fg(self.colors.row_fg)),
+            label_area,
+        );
+
+        if self.thin_pools.is_empty() {
+            frame.render_widget(
+                Paragraph::new("no thin pool in vg").style(Style::new().light_red().bold()),
+                option_area,
+            );
+            return;
+        }
+        let pools: Vec<&str> = self.thin_pools.iter().map(String::as_str).collect();
+        render_dropdown(
+            frame,
+            option_area,
+            &pools,
+            &mut self.thin_pool_state,
+            self.focus == Focus::ThinPool,
+            &self.colors,
+        );
+        let [_, hint_area] = h_layout.areas(hint_area);
+        frame.render_widget(
+            Paragraph::new("size is the virtual size, may exceed the pool")
+                .style(Style::new().fg(self.colors.block_border)),
+            hint_area,
+        );
+    }
+
     fn draw_segtype_opts(&mut self, frame: &mut Frame, rect: &mut Rect) {
         let segtype = self.lvsegtype_opts[self.lvsegtype_state.selected.unwrap()];
 
@@ -1032,84 +1434,53 @@ impl<'a> LvNewView<'a> {
             "raid5" => self.render_segtype_raid0_5_6(frame, rect),
             "raid6" => self.render_segtype_raid0_5_6(frame, rect),
             "raid1" => self.render_segtype_mirror(frame, rect),
+            "thin-pool" => self.render_segtype_thin_pool(frame, rect),
+            "thin" => self.render_segtype_thin(frame, rect),
             _ => rect.height = 0,
         }
     }
+}
 
-    // List are rendered based on pv_devs_avail and self.pv_devs_avail.
-    fn render_pvsel(&mut self, frame: &mut Frame, rect: &Rect) {
-        let h_layout = &Layout::horizontal([Max(15), Max(15)])
-            .horizontal_margin(1)
-            .spacing(1);
-        let [avail_pv_area, sel_pv_area] = h_layout.areas(*rect);
-
-        // Render available list
-        let avail_builder = ListBuilder::new(|context| {
-            let mut item = ListItem::new(self.pv_devs_avail[context.index].clone());
-            let main_axis_size = 1;
-            if context.is_selected && self.focus == Focus::LvPvAv {
-                item.style = Style::new()
-                    .bg(self.colors.header_bg)
-                    .fg(self.colors.selected_column_style_fg);
-            } else {
-                item.style = Style::new().fg(self.colors.selected_column_style_fg);
-            }
-            (item, main_axis_size)
-        });
-
-        let border_style = match self.focus {
-            Focus::LvPvAv => Style::new().fg(self.colors.block_border),
-            _ => Style::new().fg(self.colors.header_bg),
-        };
-        let block = Block::bordered()
-            .padding(Padding::horizontal(1))
-            .border_type(BorderType::Plain)
-            .title("available")
-            .border_style(border_style);
-        let avail_count = self.pv_devs_avail.len();
-        let avail_list = ListView::new(avail_builder, avail_count)
-            .scroll_axis(ScrollAxis::Vertical)
-            .block(block)
-            .infinite_scrolling(true);
-        let avail_state = &mut self.avail_list_state;
-        if avail_state.selected.is_none() {
-            avail_state.select(Some(0));
-        }
-
-        frame.render_stateful_widget(avail_list, avail_pv_area, avail_state);
-
-        // Render selected pvs.
-        let sel_builder = ListBuilder::new(|context| {
-            let mut item = ListItem::new(self.pv_devs_selected[context.index].clone());
-            if context.is_selected && self.focus == Focus::LvPvSel {
-                item.style = Style::new()
-                    .bg(self.colors.header_bg)
-                    .fg(self.colors.selected_column_style_fg);
-            } else {
-                item.style = Style::new().fg(self.colors.selected_column_style_fg);
-            }
-            let main_axis_size = 1;
-            (item, main_axis_size)
-        });
-        let border_style = match self.focus {
-            Focus::LvPvSel => Style::new().fg(self.colors.block_border),
-            _ => Style::new().fg(self.colors.header_bg),
-        };
-        let block = Block::bordered()
-            .padding(Padding::horizontal(1))
-            .border_type(BorderType::Plain)
-            .title("selected")
-            .border_style(border_style);
-        let sel_count = self.pv_devs_selected.len();
-        let sel_list = ListView::new(sel_builder, sel_count)
-            .scroll_axis(ScrollAxis::Vertical)
-            .block(block)
-            .infinite_scrolling(true);
-        let sel_state = &mut self.sel_list_state;
-        if sel_state.selected.is_none() {
-            sel_state.select(Some(0));
+//
+// One line drop down list, Up/Down changes the selection while focused.
+//
+pub fn render_dropdown(
+    frame: &mut Frame,
+    mut rect: Rect,
+    opts: &[&str],
+    state: &mut ListState,
+    focused: bool,
+    colors: &Colors,
+) {
+    rect.height = 1;
+    let list_style = if focused {
+        Style::new()
+            .bg(colors.header_bg)
+            .fg(colors.selected_column_style_fg)
+    } else {
+        Style::new()
+            .bg(colors.alt_row_color)
+            .fg(colors.selected_column_style_fg)
+    };
+
+    let builder = ListBuilder::new(|context| {
+        let mut item = ListItem::new(opts[context.index]);
+        if context.is_selected {
+            item.style = Style::new().fg(colors.selected_cell_style_fg);
         }
-
-        frame.render_stateful_widget(sel_list, sel_pv_area, sel_state);
+        (item, 1)
+    });
+
+    let block = Block::default().padding(Padding::horizontal(1));
+    let list = ListView::new(builder, opts.len())
+        .scroll_axis(ScrollAxis::Vertical)
+        .block(block)
+        .infinite_scrolling(true)
+        .style(list_style);
+    if state.selected.is_none() {
+        state.select(Some(0));
     }
+
+    frame.render_stateful_widget(list, rect, state);
+    frame.render_widget(Text::from("▾").style(list_style).right_aligned(), rect);
 }
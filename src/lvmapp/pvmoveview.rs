@@ -0,0 +1,682 @@
+//
+// pvmove off a pv: all of it, one of its segments (a pe range) or only the
+// extents of one lv, to chosen pvs of the vg or wherever lvm finds room. The
+// move runs in the background (pvmove -b), the footer shows its progress.
+//
+
+use std::sync::Arc;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize, palette::tailwind},
+    text::{Line, Span, Text},
+    widgets::{Block, Padding, Paragraph},
+};
+use tui_widget_list::ListState;
+
+use Constraint::{Length, Min};
+
+use crate::{
+    lvm::{self, LvmBackend, LvmError, LvmLvData, LvmPVData, LvmPvMove, PvAttr, fmt_size},
+    lvmapp::{
+        STATUS, View, ViewType,
+        jobs::LvmJob,
+        lvview::{PvList, PvSelect, render_dropdown},
+        popup::ConfPopup,
+        res::{self, Colors},
+    },
+};
+
+fn n_extents(n: u64) -> String {
+    format!("{n} extent{}", if n == 1 { "" } else { "s" })
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Focus {
+    Range,
+    Lv,
+    DestAvail,
+    DestSelected,
+}
+
+// An lv segment on the pv being moved.
+struct MoveSeg {
+    lv_name: String,
+    pv_start_pe: u64,
+    size_pe: u64,
+}
+
+pub struct PvMoveView {
+    focus: Focus,
+    popup_save: bool,
+    pv_name: String,
+    pv: LvmPVData,
+    // Lvs of the vg, for the lv a sub lv belongs to and running pvmoves.
+    lvs: Vec<LvmLvData>,
+    // Sorted on start pe, the range dropdown is "all" then these.
+    segs: Vec<MoveSeg>,
+    // Range picked before fetch, e.g. the segment selected in pv info.
+    preset_range: Option<(u64, u64)>,
+    range_state: ListState,
+    // Lvs with extents on the pv, the lv dropdown is "all" then these.
+    lv_names: Vec<String>,
+    lv_state: ListState,
+    // Other pvs of the vg and their free extents.
+    dests: Vec<(String, u64)>,
+    dest_select: PvSelect,
+    colors: Colors,
+    job: Option<LvmJob>,
+    lvm: Arc<dyn LvmBackend>,
+}
+
+impl View for PvMoveView {
+    fn refresh_data(&mut self) {
+        // noop
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::PvMove
+    }
+
+    //
+    // handle events related to this view. If done here return true, e.g if "back" or "save".
+    //
+    fn handle_events(&mut self, key: &KeyEvent) -> core::result::Result<bool, &'static str> {
+        if key.kind == KeyEventKind::Press {
+            match key.code {
+                KeyCode::Tab => self.next_focus(),
+                KeyCode::BackTab => self.prev_focus(),
+                KeyCode::Char(' ') => self.toggle_dest(),
+                KeyCode::Down => match self.focus {
+                    Focus::Range => self.range_state.next(),
+                    Focus::Lv => self.lv_state.next(),
+                    Focus::DestAvail => self.dest_select.next(PvList::Avail),
+                    Focus::DestSelected => self.dest_select.next(PvList::Selected),
+                },
+                KeyCode::Up => match self.focus {
+                    Focus::Range => self.range_state.previous(),
+                    Focus::Lv => self.lv_state.previous(),
+                    Focus::DestAvail => self.dest_select.previous(PvList::Avail),
+                    Focus::DestSelected => self.dest_select.previous(PvList::Selected),
+                },
+                KeyCode::F(6) => match self.check() {
+                    Ok(_) => self.popup_save = true,
+                    Err(e) => STATUS.lock().unwrap().set_status(&e),
+                },
+                KeyCode::Esc => {
+                    if self.popup_save {
+                        STATUS.lock().unwrap().set_status("pvmove cancelled.");
+                        self.popup_save = false;
+                    } else {
+                        return Ok(true);
+                    }
+                }
+                KeyCode::Enter if self.popup_save => {
+                    self.popup_save = false;
+                    if self.check().is_err() {
+                        return Ok(false);
+                    }
+                    self.save();
+                    return Ok(true);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+impl PvMoveView {
+    pub fn new(pv_name: &str, lvm: Arc<dyn LvmBackend>) -> Self {
+        Self {
+            focus: Focus::Range,
+            popup_save: false,
+            pv_name: pv_name.to_string(),
+            pv: LvmPVData::default(),
+            lvs: Vec::new(),
+            segs: Vec::new(),
+            preset_range: None,
+            range_state: ListState::default(),
+            lv_names: Vec::new(),
+            lv_state: ListState::default(),
+            dests: Vec::new(),
+            dest_select: PvSelect::new(&[]),
+            colors: Colors::new(&res::PALETTES[0]),
+            job: None,
+            lvm,
+        }
+    }
+
+    //
+    // Start with the segment at 'first'..='last' picked, if there is one.
+    //
+    pub fn range(mut self, first: u64, last: u64) -> Self {
+        self.preset_range = Some((first, last));
+        self
+    }
+
+    //
+    // The pv, its segments and where they can go. Fails for pvs in no vg.
+    //
+    pub fn fetch_data(&mut self) -> Result<(), LvmError> {
+        let not_found = |stderr: String| LvmError::CommandFailed {
+            cmd: "pvmove".to_string(),
+            code: None,
+            stderr,
+        };
+        let pvs = self.lvm.get_pvs()?;
+        let pv = pvs
+            .iter()
+            .find(|pv| pv.pv_name == self.pv_name)
+            .ok_or_else(|| not_found(format!("Physical volume {} not found", self.pv_name)))?;
+        if pv.vg_name.is_empty() {
+            return Err(not_found(format!(
+                "Physical volume {} is in no vg, nothing to move",
+                self.pv_name
+            )));
+        }
+        self.lvs = lvm::get_lvinfo_by_vg(&pv.vg_name, &self.lvm.get_lvs()?);
+
+        // The pvmove lv only holds the destination extents.
+        self.segs = self
+            .lvs
+            .iter()
+            .filter(|lv| lv.move_pv.is_empty())
+            .flat_map(|lv| {
+                lv.lv_segs
+                    .iter()
+                    .filter(|seg| seg.pvdev == self.pv_name)
+                    .map(|seg| MoveSeg {
+                        lv_name: lv.lv_name.clone(),
+                        pv_start_pe: seg.pv_start_pe,
+                        size_pe: seg.size_pe,
+                    })
+            })
+            .collect();
+        self.segs.sort_by_key(|seg| seg.pv_start_pe);
+        let mut lv_names: Vec<String> = self
+            .segs
+            .iter()
+            .map(|seg| self.top_lv(&seg.lv_name).to_string())
+            .collect();
+        lv_names.sort();
+        lv_names.dedup();
+        self.lv_names = lv_names;
+
+        self.dests = pvs
+            .iter()
+            .filter(|p| p.vg_name == pv.vg_name && p.pv_name != pv.pv_name)
+            .filter(|p| !PvAttr::decode(&p.pv_attr).missing)
+            .map(|p| (p.pv_name.clone(), p.pe_count - p.pe_alloc_count))
+            .filter(|(_, free)| *free > 0)
+            .collect();
+        let avail: Vec<String> = self.dests.iter().map(|(name, _)| name.clone()).collect();
+        self.dest_select = PvSelect::new(&avail);
+        self.pv = pv.clone();
+
+        let preset = self.preset_range.and_then(|(first, last)| {
+            self.segs.iter().position(|seg| {
+                seg.pv_start_pe == first && seg.pv_start_pe + seg.size_pe == last + 1
+            })
+        });
+        self.range_state.select(Some(preset.map_or(0, |i| i + 1)));
+        self.lv_state.select(Some(0));
+        Ok(())
+    }
+
+    //
+    // The pvmove to run, once confirmed.
+    //
+    pub fn take_job(&mut self) -> Option<LvmJob> {
+        self.job.take()
+    }
+
+    //
+    // The pvmove running in the vg, only one at a time is started from here.
+    //
+    pub fn running(&self) -> Option<&LvmLvData> {
+        lvm::pvmoves(&self.pv.vg_name, &self.lvs).into_iter().next()
+    }
+
+    // Sub lvs are moved with (and named by) the lv they belong to.
+    fn top_lv<'a>(&'a self, lv_name: &'a str) -> &'a str {
+        let mut name = lv_name;
+        while let Some(lv) = self
+            .lvs
+            .iter()
+            .find(|lv| lv.lv_name == name && !lv.parent_lv.is_empty())
+        {
+            name = &lv.parent_lv;
+        }
+        name
+    }
+
+    fn selected_seg(&self) -> Option<&MoveSeg> {
+        match self.range_state.selected {
+            Some(i) if i > 0 => self.segs.get(i - 1),
+            _ => None,
+        }
+    }
+
+    fn selected_lv(&self) -> Option<&String> {
+        match self.lv_state.selected {
+            Some(i) if i > 0 => self.lv_names.get(i - 1),
+            _ => None,
+        }
+    }
+
+    fn pv_move(&self) -> LvmPvMove {
+        LvmPvMove {
+            src_pv: self.pv_name.clone(),
+            pe_range: self
+                .selected_seg()
+                .map(|seg| (seg.pv_start_pe, seg.pv_start_pe + seg.size_pe - 1)),
+            lv_name: self.selected_lv().cloned().unwrap_or_default(),
+            dest_pvs: self.dest_select.selected.clone(),
+        }
+    }
+
+    //
+    // Extents the range and lv picked cover.
+    //
+    fn extents(&self) -> u64 {
+        let seg = self.selected_seg();
+        let lv = self.selected_lv();
+        self.segs
+            .iter()
+            .filter(|s| seg.is_none_or(|seg| seg.pv_start_pe == s.pv_start_pe))
+            .filter(|s| lv.is_none_or(|lv| self.top_lv(&s.lv_name) == lv))
+            .map(|s| s.size_pe)
+            .sum()
+    }
+
+    // Free extents of the selected destinations, of all others if none are.
+    fn dest_free(&self) -> u64 {
+        let selected = &self.dest_select.selected;
+        self.dests
+            .iter()
+            .filter(|(name, _)| selected.is_empty() || selected.contains(name))
+            .map(|(_, free)| free)
+            .sum()
+    }
+
+    fn focus_order(&self) -> Vec<Focus> {
+        let mut order = vec![Focus::Range, Focus::Lv];
+        if !self.dest_select.avail.is_empty() {
+            order.push(Focus::DestAvail);
+        }
+        if !self.dest_select.selected.is_empty() {
+            order.push(Focus::DestSelected);
+        }
+        order
+    }
+
+    fn next_focus(&mut self) {
+        let mut order = self.focus_order();
+        let i = order.iter().position(|f| *f == self.focus).unwrap_or(0);
+        let next = (i + 1) % order.len();
+        self.focus = order.swap_remove(next);
+    }
+
+    fn prev_focus(&mut self) {
+        let mut order = self.focus_order();
+        let i = order.iter().position(|f| *f == self.focus).unwrap_or(0);
+        let prev = (i + order.len() - 1) % order.len();
+        self.focus = order.swap_remove(prev);
+    }
+
+    fn toggle_dest(&mut self) {
+        let list = match self.focus {
+            Focus::DestAvail => PvList::Avail,
+            Focus::DestSelected => PvList::Selected,
+            _ => return,
+        };
+        self.dest_select.toggle(list);
+        // Don't stay on a list that ran empty.
+        if !self.focus_order().contains(&self.focus) {
+            self.next_focus();
+        }
+    }
+
+    //
+    // What pvmove would refuse, checked before asking.
+    //
+    fn check(&self) -> Result<(), String> {
+        if let Some(mv) = self.running() {
+            return Err(format!(
+                "pvmove of {} is running in {}, wait for it or abort it.",
+                mv.move_pv, self.pv.vg_name
+            ));
+        }
+        let extents = self.extents();
+        if extents == 0 {
+            return Err("No extents to move in this selection.".to_string());
+        }
+        let free = self.dest_free();
+        if free < extents {
+            return Err(format!(
+                "{extents} extents to move, destinations have {free} free."
+            ));
+        }
+        Ok(())
+    }
+
+    //
+    // pvmove -b returns once the move started, the footer shows how far it
+    // got from then on.
+    //
+    fn save(&mut self) {
+        let (lvm, vg_name, mv) = (self.lvm.clone(), self.pv.vg_name.clone(), self.pv_move());
+        let title = format!("Move {} off {}", n_extents(self.extents()), self.pv_name);
+        STATUS
+            .lock()
+            .unwrap()
+            .set_status(&format!("Started: {title}, see the footer."));
+        self.job = Some(LvmJob::new(&title, move || lvm.move_pv(&vg_name, &mv)));
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, rect: &Rect) {
+        let inner_layout = &Layout::vertical([
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(8),
+            Min(6),
+        ])
+        .margin(2);
+        let [
+            header_area,
+            range_area,
+            lv_area,
+            _,
+            dest_sel_label,
+            _,
+            dest_sel_area,
+            info_area,
+        ] = inner_layout.areas(*rect);
+
+        let heading = format!("MOVE EXTENTS OFF {} ({})", self.pv_name, self.pv.vg_name);
+        let para_heading = Paragraph::new(heading)
+            .alignment(ratatui::layout::Alignment::Left)
+            .style(Style::new().fg(self.colors.block_border));
+        frame.render_widget(para_heading, header_area);
+
+        let label_style = Style::new().fg(self.colors.row_fg);
+        let h_layout = &Layout::horizontal([Length(16), Length(40)])
+            .horizontal_margin(1)
+            .spacing(1);
+
+        let ranges: Vec<String> = std::iter::once("all of the pv".to_string())
+            .chain(self.segs.iter().map(|seg| {
+                format!(
+                    "{} {}-{}",
+                    seg.lv_name,
+                    seg.pv_start_pe,
+                    seg.pv_start_pe + seg.size_pe - 1
+                )
+            }))
+            .collect();
+        let lvs: Vec<String> = std::iter::once("all lvs".to_string())
+            .chain(self.lv_names.iter().cloned())
+            .collect();
+        for (area, label, opts, state, focus) in [
+            (
+                range_area,
+                "extents:",
+                &ranges,
+                &mut self.range_state,
+                Focus::Range,
+            ),
+            (lv_area, "lv:", &lvs, &mut self.lv_state, Focus::Lv),
+        ] {
+            let [label_area, opt_area] = h_layout.areas(area);
+            frame.render_widget(Paragraph::new(label).style(label_style), label_area);
+            let opts: Vec<&str> = opts.iter().map(|s| s.as_str()).collect();
+            render_dropdown(
+                frame,
+                opt_area,
+                &opts,
+                state,
+                self.focus == focus,
+                &self.colors,
+            );
+        }
+
+        frame.render_widget(
+            Paragraph::new("Destination pvs, none selected lets lvm pick:")
+                .style(Style::new().fg(self.colors.block_border)),
+            dest_sel_label,
+        );
+        let dest_focus = match self.focus {
+            Focus::DestAvail => Some(PvList::Avail),
+            Focus::DestSelected => Some(PvList::Selected),
+            _ => None,
+        };
+        self.dest_select
+            .render(frame, dest_sel_area, dest_focus, &self.colors);
+
+        self.render_info(frame, info_area);
+
+        if self.popup_save {
+            self.render_popup_save(frame, rect);
+        }
+    }
+
+    //
+    // Extents moved, room for them and the command run.
+    //
+    fn render_info(&self, frame: &mut Frame, area: Rect) {
+        let label = |s: &str| Span::styled(format!("{s:<16}"), Style::new().fg(self.colors.row_fg));
+        let value = |s: String| Span::styled(s, Style::new().fg(self.colors.header_fg));
+
+        let extents = self.extents();
+        let mut lines = vec![
+            Line::from(vec![
+                label("to move"),
+                value(format!(
+                    "{}, {}",
+                    n_extents(extents),
+                    fmt_size(extents * self.pv.pe_size)
+                )),
+            ]),
+            Line::from(vec![
+                label("free at dest"),
+                value(format!(
+                    "{}, {}",
+                    n_extents(self.dest_free()),
+                    fmt_size(self.dest_free() * self.pv.pe_size)
+                )),
+            ]),
+            Line::from(vec![
+                label("command"),
+                value(format!(
+                    "pvmove {}",
+                    lvm::pvmove_args(&self.pv_move()).join(" ")
+                )),
+            ]),
+            Line::styled(
+                "Runs in the background, the footer shows progress. F12 on the pv aborts.",
+                Style::new().fg(tailwind::AMBER.c400),
+            ),
+        ];
+        if let Err(e) = self.check() {
+            lines.push(Line::styled(e, Style::new().fg(tailwind::RED.c400).bold()));
+        }
+
+        let para = Paragraph::new(lines).block(Block::default().padding(Padding::horizontal(1)));
+        frame.render_widget(para, area);
+    }
+
+    fn render_popup_save(&self, frame: &mut Frame, rect: &Rect) {
+        let popup_area = Rect {
+            x: rect.width / 4,
+            y: rect.height / 3,
+            width: rect.width / 2,
+            height: rect.height / 2,
+        };
+        let s1 = Style::new().white().bold();
+        let dests = if self.dest_select.selected.is_empty() {
+            "other pvs of the vg".to_string()
+        } else {
+            self.dest_select.selected.join(", ")
+        };
+
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(Text::from(Line::from(vec![
+                Span::from("You are about to move "),
+                Span::from(n_extents(self.extents())).style(s1),
+                Span::from(" off '"),
+                Span::from(self.pv_name.clone()).style(s1),
+                Span::from("' to "),
+                Span::from(dests).style(s1),
+                Span::from("."),
+            ])))
+            .title(format!("Move {}", self.pv_name));
+        frame.render_widget(popup, popup_area);
+    }
+}
+
+//
+// Confirm pvmove --abort. Extents copied so far go back to the source, the
+// move can be started again later.
+//
+pub struct PvMoveAbortPopup {
+    vg_name: String,
+    src_pv: String,
+    copy_percent: f64,
+    colors: Colors,
+    lvm: Arc<dyn LvmBackend>,
+    job: Option<LvmJob>,
+}
+
+impl PvMoveAbortPopup {
+    pub fn new(mv: &LvmLvData, colors: Colors, lvm: Arc<dyn LvmBackend>) -> Self {
+        Self {
+            vg_name: mv.vg_name.clone(),
+            src_pv: mv.move_pv.clone(),
+            copy_percent: mv.copy_percent.unwrap_or(0.0),
+            colors,
+            lvm,
+            job: None,
+        }
+    }
+
+    //
+    // The pvmove --abort to run, once confirmed.
+    //
+    pub fn take_job(&mut self) -> Option<LvmJob> {
+        self.job.take()
+    }
+
+    //
+    // Done (true) when confirmed or cancelled, the abort is left as a job.
+    //
+    pub fn handle_events(&mut self, key: &KeyEvent) -> Result<bool, LvmError> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Esc => {
+                STATUS.lock().unwrap().set_status("pvmove keeps running.");
+                return Ok(true);
+            }
+            KeyCode::Enter => {
+                let lvm = self.lvm.clone();
+                let title = format!("Abort pvmove of {}", self.src_pv);
+                STATUS
+                    .lock()
+                    .unwrap()
+                    .set_status(&format!("Started: {title}"));
+                self.job = Some(LvmJob::new(&title, move || lvm.abort_pvmove()));
+                return Ok(true);
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let popup_area = Rect {
+            x: area.width / 4,
+            y: area.height / 3,
+            width: area.width / 2,
+            height: area.height / 2,
+        };
+        let s1 = Style::new().white().bold();
+
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(Text::from(Line::from(vec![
+                Span::from("pvmove of '"),
+                Span::from(self.src_pv.clone()).style(s1),
+                Span::from(format!("' is {:.1}% done. Abort it? ", self.copy_percent)),
+                Span::from("Extents stay where they were."),
+            ])))
+            .title(format!(" Abort pvmove in {} ", self.vg_name));
+        frame.render_widget(popup, popup_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::lvm::{LvmBackend, fake::FakeBackend};
+    use crate::lvmapp::pvmoveview::PvMoveView;
+
+    #[test]
+    fn test_pv_move_check() {
+        let fake = Arc::new(FakeBackend::new());
+        let gb = 1024 * 1024 * 1024;
+        fake.add_vg(
+            "vg01",
+            &[
+                ("/dev/sda", 10 * gb),
+                ("/dev/sdb", 4 * gb),
+                ("/dev/sdc", 10 * gb),
+            ],
+        );
+        fake.create_lv("lv1", "vg01", 6, "G", "linear", &[], &[])
+            .unwrap();
+        fake.create_lv("lv2", "vg01", 2, "G", "linear", &[], &[])
+            .unwrap();
+        let lvm: Arc<dyn LvmBackend> = fake.clone();
+
+        // Segment of lv2 picked up from pv info
+        let mut view = PvMoveView::new("/dev/sda", lvm.clone()).range(1536, 2047);
+        view.fetch_data().unwrap();
+        assert_eq!(view.lv_names, ["lv1", "lv2"]);
+        assert_eq!(view.range_state.selected, Some(2));
+        assert_eq!(view.extents(), 512);
+        view.lv_state.select(Some(1));
+        assert_eq!(
+            view.check().unwrap_err(),
+            "No extents to move in this selection."
+        );
+
+        // All of sda does not fit on sdb alone
+        view.range_state.select(Some(0));
+        view.lv_state.select(Some(0));
+        assert_eq!(view.extents(), 2048);
+        view.dest_select.selected = vec!["/dev/sdb".to_string()];
+        assert!(view.check().unwrap_err().contains("1024 free"));
+        view.dest_select.selected.clear();
+        view.check().unwrap();
+        view.save();
+        view.take_job().unwrap().run().unwrap();
+
+        // One at a time
+        let mut view = PvMoveView::new("/dev/sdc", lvm.clone());
+        view.fetch_data().unwrap();
+        assert_eq!(view.running().unwrap().move_pv, "/dev/sda");
+        assert!(view.check().unwrap_err().contains("is running in vg01"));
+        assert!(PvMoveView::new("/dev/sdz", lvm).fetch_data().is_err());
+    }
+}
@@ -0,0 +1,361 @@
+use std::sync::Arc;
+
+use Constraint::{Length, Min};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Margin, Rect},
+    style::{Modifier, Style, Stylize},
+    text::{Line, Text},
+    widgets::{
+        Block, BorderType, Borders, Cell, Gauge, HighlightSpacing, Paragraph, Row, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Table, TableState,
+    },
+};
+
+use crate::{
+    lvm::{LvmBackend, LvmError, LvmLvData, LvmPVData, PvAttr},
+    lvmapp::{
+        STATUS, View, ViewType, attrbadge,
+        extentmap::{self, ExtentMap},
+        res::{self, Colors},
+    },
+};
+
+// An lv segment placed on the pv.
+struct PvSegData {
+    lv_name: String,
+    pv_start_pe: u64,
+    size_pe: u64,
+}
+
+// The pv and segments on it, sorted on start pe. lvs are those of the vg.
+struct PvData {
+    pv: LvmPVData,
+    segs: Vec<PvSegData>,
+    lvs: Vec<LvmLvData>,
+}
+
+pub struct PvInfoView {
+    state: TableState,
+    pv_name: String,
+    pv_item: Option<PvData>,
+    scroll_state: ScrollbarState,
+    colors: Colors,
+    lvm: Arc<dyn LvmBackend>,
+}
+
+impl View for PvInfoView {
+    fn refresh_data(&mut self) {
+        if let Err(e) = self.fetch_data() {
+            STATUS
+                .lock()
+                .unwrap()
+                .set_status(&format!("Refresh of {} failed: {e}", self.pv_name));
+        }
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::PvInfo
+    }
+
+    fn handle_events(&mut self, _key: &crossterm::event::KeyEvent) -> Result<bool, &'static str> {
+        // Noop,
+        Ok(true)
+    }
+}
+
+impl PvInfoView {
+    pub fn new(pv_name: &str, lvm: Arc<dyn LvmBackend>) -> Self {
+        Self {
+            pv_name: pv_name.to_string(),
+            state: TableState::default().with_selected(0),
+            scroll_state: ScrollbarState::new(15),
+            colors: Colors::new(&res::PALETTES[0]),
+            pv_item: None,
+            lvm,
+        }
+    }
+
+    //
+    // Fetch all first, on error previous data (if any) is left as is.
+    //
+    pub fn fetch_data(&mut self) -> Result<(), LvmError> {
+        let pv = self
+            .lvm
+            .get_pvs()?
+            .into_iter()
+            .find(|pv| pv.pv_name == self.pv_name)
+            .ok_or_else(|| LvmError::CommandFailed {
+                cmd: "pvs".to_string(),
+                code: None,
+                stderr: format!("Failed to find physical volume \"{}\".", self.pv_name),
+            })?;
+        let lvs: Vec<LvmLvData> = self
+            .lvm
+            .get_lvs()?
+            .into_iter()
+            .filter(|lv| lv.vg_name == pv.vg_name)
+            .collect();
+        let mut segs: Vec<PvSegData> = lvs
+            .iter()
+            .flat_map(|lv| {
+                lv.lv_segs
+                    .iter()
+                    .filter(|seg| seg.pvdev == self.pv_name)
+                    .map(|seg| PvSegData {
+                        lv_name: lv.lv_name.clone(),
+                        pv_start_pe: seg.pv_start_pe,
+                        size_pe: seg.size_pe,
+                    })
+            })
+            .collect();
+        segs.sort_by_key(|seg| seg.pv_start_pe);
+
+        self.pv_item = Some(PvData { pv, segs, lvs });
+        Ok(())
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, inner_layout: &[Rect; 2]) {
+        let pv_info_layout = Layout::horizontal([Length(36), Min(0)]).horizontal_margin(1);
+
+        let [pv_info_area, right_area] = pv_info_layout.areas(inner_layout[0]);
+        let [gbar_area, map_area] = Layout::vertical([Length(7), Min(0)]).areas(right_area);
+        self.render_pvinfo(frame, pv_info_area);
+        self.render_pvinfo_usagebar(frame, gbar_area);
+        self.render_extent_map(frame, map_area);
+
+        self.render_segs_table(frame, inner_layout[1]);
+        self.render_scrollbar(frame, inner_layout[1]);
+    }
+
+    fn render_scrollbar(&mut self, frame: &mut Frame, area: Rect) {
+        frame.render_stateful_widget(
+            Scrollbar::default()
+                .orientation(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None),
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 1,
+            }),
+            &mut self.scroll_state,
+        );
+    }
+
+    fn render_pvinfo_usagebar(&mut self, frame: &mut Frame, area: Rect) {
+        let pv = &self.pv_item.as_ref().unwrap().pv;
+        let used = used_percent(pv) as u16;
+
+        let layout = Layout::vertical([Length(5)]).vertical_margin(1);
+        let [gbar_area] = layout.areas(area);
+
+        let bar_title = format!(" PV: {} ", self.pv_name);
+        let bar = Gauge::default()
+            .block(
+                Block::default()
+                    .border_style(Style::new().fg(self.colors.block_border))
+                    .title(bar_title)
+                    .border_type(BorderType::Rounded)
+                    .borders(Borders::ALL),
+            )
+            .gauge_style(
+                Style::new()
+                    .fg(self.colors.selected_row_style_fg)
+                    .on_black()
+                    .italic(),
+            )
+            .percent(used);
+        frame.render_widget(bar, gbar_area);
+    }
+
+    //
+    // Extents of the pv, lv of the selected segment (and its images) highlighted.
+    //
+    fn render_extent_map(&mut self, frame: &mut Frame, area: Rect) {
+        let pv_data = self.pv_item.as_ref().unwrap();
+        let highlight = self
+            .state
+            .selected()
+            .and_then(|i| pv_data.segs.get(i))
+            .map(|seg| extentmap::lv_and_sub_lvs(&seg.lv_name, &pv_data.lvs))
+            .unwrap_or_default();
+        let pvs = extentmap::extent_map_pvs(std::slice::from_ref(&pv_data.pv), &pv_data.lvs);
+
+        let map = ExtentMap::new(self.colors.clone(), pvs)
+            .title(" Extents ")
+            .highlight(highlight);
+        frame.render_widget(map, area);
+    }
+
+    fn render_pvinfo(&mut self, frame: &mut Frame, area: Rect) {
+        let pv = &self.pv_item.as_ref().unwrap().pv;
+        let header_str = format!("{:<12} {:<22}", "Name", "Value");
+
+        let mut lines = vec![
+            Line::raw(header_str)
+                .fg(self.colors.header_fg)
+                .bg(self.colors.header_bg),
+        ];
+
+        let dev = match (pv.major, pv.minor) {
+            (Some(major), Some(minor)) => format!("{major}:{minor}"),
+            _ => "-".to_string(),
+        };
+        let vg_name = if pv.vg_name.is_empty() {
+            "-"
+        } else {
+            &pv.vg_name
+        };
+        let rows = [
+            ("PV", pv.pv_name.clone()),
+            ("VG", vg_name.to_string()),
+            ("size (g)", format!("{}", pv.pv_size / 1000 / 1000 / 1000)),
+            ("free (g)", format!("{}", pv.pv_free / 1000 / 1000 / 1000)),
+            ("%used", format!("{:.1}", used_percent(pv))),
+            ("PE size (m)", format!("{}", pv.pe_size / 1024 / 1024)),
+            ("PE start (k)", format!("{}", pv.pe_start / 1024)),
+            ("PE count", format!("{}", pv.pe_count)),
+            ("PE alloc", format!("{}", pv.pe_alloc_count)),
+            ("major:minor", dev),
+        ];
+        for (name, value) in rows {
+            let line = format!("{:<12} {:<22}", name, value);
+            lines.push(Line::raw(line).fg(self.colors.header_fg));
+        }
+        let mut attr_line = Line::raw(format!("{:<12} ", "attr")).fg(self.colors.header_fg);
+        attr_line
+            .spans
+            .extend(attrbadge::badges(&PvAttr::decode(&pv.pv_attr).flags(), &self.colors).spans);
+        lines.push(attr_line);
+
+        let para = Paragraph::new(lines).style(
+            Style::new()
+                .fg(self.colors.row_fg)
+                .bg(self.colors.buffer_bg),
+        );
+
+        frame.render_widget(para, area);
+    }
+
+    fn render_segs_table(&mut self, frame: &mut Frame, area: Rect) {
+        let pv_data = self.pv_item.as_ref().unwrap();
+
+        let sb = Block::default()
+            .title(" LV segments ")
+            .border_style(Style::new().fg(self.colors.block_border))
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL);
+        let header_style = Style::default()
+            .fg(self.colors.header_fg)
+            .bg(self.colors.header_bg);
+        let selected_row_style = Style::default()
+            .add_modifier(Modifier::REVERSED)
+            .fg(self.colors.selected_row_style_fg);
+
+        let header = ["LV", "start_pe", "end_pe", "extents", "size(g)"]
+            .into_iter()
+            .map(Cell::from)
+            .collect::<Row>()
+            .style(header_style)
+            .height(1);
+        let rows = pv_data.segs.iter().enumerate().map(|(i, seg)| {
+            let color = match i % 2 {
+                0 => self.colors.normal_row_color,
+                _ => self.colors.alt_row_color,
+            };
+            let gb_conv = 1000.0 * 1000.0 * 1000.0;
+            let size_gb = (seg.size_pe * pv_data.pv.pe_size) as f64 / gb_conv;
+            let item = [
+                seg.lv_name.clone(),
+                seg.pv_start_pe.to_string(),
+                (seg.pv_start_pe + seg.size_pe - 1).to_string(),
+                seg.size_pe.to_string(),
+                format!("{:.2}", size_gb),
+            ];
+            item.into_iter()
+                .map(|content| Cell::from(Text::from(content)))
+                .collect::<Row>()
+                .style(Style::new().fg(self.colors.row_fg).bg(color))
+                .height(1)
+        });
+        let bar = " █ ";
+
+        let t = Table::new(
+            rows,
+            [
+                Constraint::Min(20),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(10),
+            ],
+        )
+        .header(header)
+        .row_highlight_style(selected_row_style)
+        .highlight_symbol(Text::from(vec![
+            "".into(),
+            bar.into(),
+            bar.into(),
+            "".into(),
+        ]))
+        .bg(self.colors.buffer_bg)
+        .block(sb)
+        .highlight_spacing(HighlightSpacing::Always);
+
+        frame.render_stateful_widget(t, area, &mut self.state);
+    }
+
+    pub fn pv_name(&self) -> &str {
+        &self.pv_name
+    }
+
+    //
+    // First and last extent of the selected segment, what F12 moves.
+    //
+    pub fn selected_range(&self) -> Option<(u64, u64)> {
+        let pv_data = self.pv_item.as_ref()?;
+        let seg = pv_data.segs.get(self.state.selected()?)?;
+        Some((seg.pv_start_pe, seg.pv_start_pe + seg.size_pe - 1))
+    }
+
+    fn seg_count(&self) -> usize {
+        self.pv_item.as_ref().map_or(0, |data| data.segs.len())
+    }
+
+    pub fn next_segrow(&mut self) {
+        let len = self.seg_count();
+        if len == 0 {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) if i < len - 1 => i + 1,
+            _ => 0,
+        };
+        self.state.select(Some(i));
+        self.scroll_state = self.scroll_state.position(i * res::ITEM_HEIGHT);
+    }
+
+    pub fn previous_segrow(&mut self) {
+        let len = self.seg_count();
+        if len == 0 {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(0) | None => len - 1,
+            Some(i) => i - 1,
+        };
+        self.state.select(Some(i));
+        self.scroll_state = self.scroll_state.position(i * res::ITEM_HEIGHT);
+    }
+}
+
+// Orphan pvs have no extents, use bytes.
+fn used_percent(pv: &LvmPVData) -> f64 {
+    if pv.pe_count > 0 {
+        (pv.pe_alloc_count as f64) / (pv.pe_count as f64) * 100.0
+    } else if pv.pv_size > 0 {
+        ((pv.pv_size - pv.pv_free) as f64) / (pv.pv_size as f64) * 100.0
+    } else {
+        0.0
+    }
+}
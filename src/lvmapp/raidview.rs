@@ -0,0 +1,419 @@
+//
+// raid health. lvchange --syncaction check scrubs a raid and counts the
+// mismatches between its images, repair also rewrites them. An image on a
+// missing pv leaves the raid partial (degraded) until lvconvert --repair puts
+// a new image on another pv, lvconvert --replace moves a healthy one.
+//
+
+use std::sync::Arc;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEventKind},
+    layout::Rect,
+    style::{Style, Stylize, palette::tailwind},
+    text::{Line, Span, Text},
+};
+
+use crate::{
+    lvm::{
+        self, LvAttr, LvHealth, LvmBackend, LvmError, LvmLvData, LvmPVData, LvmSyncAction, PvAttr,
+    },
+    lvmapp::{STATUS, jobs::LvmJob, popup::ConfPopup, res::Colors, snapview},
+};
+
+// Cells of the sync bar in the lvs table, the usage column is 18 wide.
+const SYNC_BAR_LEN: usize = 5;
+
+//
+// raid with redundancy, raid0 has nothing to check or repair.
+//
+pub fn is_raid(lv: &LvmLvData) -> bool {
+    lv.segtype.starts_with("raid") && lv.segtype != "raid0"
+}
+
+pub fn is_partial(lv: &LvmLvData) -> bool {
+    LvAttr::decode(&lv.attr).health == LvHealth::Partial
+}
+
+//
+// A scrub or resync is running, 'frozen' is one paused.
+//
+pub fn is_syncing(lv: &LvmLvData) -> bool {
+    is_raid(lv) && !matches!(lv.raid_sync_action.as_str(), "" | "idle" | "frozen")
+}
+
+//
+// 'DEGRADED', 'check ██░░░ 40%', '16 mismatches' or 'in sync' for the lvs
+// table.
+//
+pub fn raid_status_line(lv: &LvmLvData) -> Line<'static> {
+    let mismatches = lv.raid_mismatch_count.unwrap_or(0);
+    if is_partial(lv) {
+        Line::styled("DEGRADED", Style::new().fg(tailwind::RED.c400).bold())
+    } else if is_syncing(lv) {
+        let percent = lv.sync_percent.unwrap_or(0.0).clamp(0.0, 100.0);
+        let filled = (percent * SYNC_BAR_LEN as f64 / 100.0).round() as usize;
+        Line::from(vec![
+            Span::raw(format!("{} ", lv.raid_sync_action)),
+            Span::styled("█".repeat(filled), Style::new().fg(tailwind::BLUE.c400)),
+            Span::styled(
+                "░".repeat(SYNC_BAR_LEN - filled),
+                Style::new().fg(tailwind::SLATE.c600),
+            ),
+            Span::raw(format!(" {percent:.0}%")),
+        ])
+    } else if mismatches > 0 {
+        Line::styled(
+            format!("{mismatches} mismatches"),
+            Style::new().fg(tailwind::AMBER.c400),
+        )
+    } else {
+        Line::from("in sync")
+    }
+}
+
+//
+// Images of a raid lv and the pv each is on, in image order.
+//
+fn raid_images(lv: &LvmLvData, lvs: &[LvmLvData]) -> Vec<(String, String)> {
+    let mut images: Vec<(String, String)> = lvs
+        .iter()
+        .filter(|item| item.parent_lv == lv.lv_name && item.lv_name.contains("_rimage_"))
+        .map(|item| {
+            let pv = item
+                .lv_segs
+                .first()
+                .map(|seg| seg.pvdev.clone())
+                .unwrap_or_default();
+            (item.lv_name.trim_matches(['[', ']']).to_string(), pv)
+        })
+        .collect();
+    images.sort();
+    images
+}
+
+fn pv_missing(pv_name: &str, pvs: &[LvmPVData]) -> bool {
+    pvs.iter()
+        .find(|pv| pv.pv_name == pv_name)
+        .is_none_or(|pv| PvAttr::decode(&pv.pv_attr).missing)
+}
+
+//
+// Health, sync, mismatches and the pv of each image, for the vg view.
+//
+pub fn raid_detail_lines(
+    lv: &LvmLvData,
+    lvs: &[LvmLvData],
+    pvs: &[LvmPVData],
+) -> Vec<Line<'static>> {
+    let label = |s: &str| Span::raw(format!("{s:<11}"));
+    let red = Style::new().fg(tailwind::RED.c400).bold();
+    let amber = Style::new().fg(tailwind::AMBER.c400);
+
+    let health = match lv.health_status.as_str() {
+        "" => Span::raw("ok"),
+        "partial" => Span::styled("partial, an image is missing (F10 repair)", red),
+        status => Span::styled(status.to_string(), amber),
+    };
+    let mut lines = vec![Line::from(vec![label("health"), health])];
+
+    let mut sync = Line::from(label("sync"));
+    sync.spans
+        .extend(snapview::usage_bar(lv.sync_percent.unwrap_or(0.0), 101.0).spans);
+    if !lv.raid_sync_action.is_empty() {
+        sync.spans
+            .push(Span::raw(format!(" {}", lv.raid_sync_action)));
+    }
+    lines.push(sync);
+
+    let mismatches = lv.raid_mismatch_count.unwrap_or(0);
+    lines.push(Line::from(vec![
+        label("mismatches"),
+        Span::styled(
+            mismatches.to_string(),
+            if mismatches > 0 { amber } else { Style::new() },
+        ),
+    ]));
+
+    for (image, pv) in raid_images(lv, lvs) {
+        let prefix = format!("{}_", lv.lv_name);
+        let name = image.strip_prefix(&prefix).unwrap_or(&image);
+        let mut line = Line::from(vec![label(name), Span::raw(pv.clone())]);
+        if pv_missing(&pv, pvs) {
+            line.spans.push(Span::styled(" MISSING", red));
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[derive(Clone, PartialEq)]
+enum RaidAction {
+    Sync(LvmSyncAction),
+    Repair,
+    // Move the image on the pv.
+    Replace(String),
+}
+
+impl RaidAction {
+    fn text(&self) -> String {
+        match self {
+            RaidAction::Sync(LvmSyncAction::Check) => {
+                "check - scrub, count mismatches between images".to_string()
+            }
+            RaidAction::Sync(LvmSyncAction::Repair) => {
+                "repair - scrub, rewrite mismatched blocks".to_string()
+            }
+            RaidAction::Repair => "repair degraded - new images for the missing ones".to_string(),
+            RaidAction::Replace(pv) => format!("replace {pv} - move its image to another pv"),
+        }
+    }
+}
+
+//
+// Pick a scrub, repair or image replace for a raid lv, F10 in vg info. A
+// degraded raid can only be repaired.
+//
+pub struct RaidActionPopup {
+    vg_name: String,
+    lv_name: String,
+    segtype: String,
+    actions: Vec<RaidAction>,
+    selected: usize,
+    // No pv has room for a new image.
+    no_room: bool,
+    colors: Colors,
+    lvm: Arc<dyn LvmBackend>,
+    job: Option<LvmJob>,
+}
+
+impl RaidActionPopup {
+    //
+    // 'lvs' and 'pvs' are those of the vg.
+    //
+    pub fn new(
+        lv: &LvmLvData,
+        lvs: &[LvmLvData],
+        pvs: &[LvmPVData],
+        colors: Colors,
+        lvm: Arc<dyn LvmBackend>,
+    ) -> Self {
+        let images = raid_images(lv, lvs);
+        let actions = if is_partial(lv) {
+            vec![RaidAction::Repair]
+        } else {
+            let mut actions = vec![
+                RaidAction::Sync(LvmSyncAction::Check),
+                RaidAction::Sync(LvmSyncAction::Repair),
+            ];
+            actions.extend(images.iter().map(|(_, pv)| RaidAction::Replace(pv.clone())));
+            actions
+        };
+        // Roughly what lvm needs, the image and its rmeta extent on a pv
+        // without another image.
+        let image_size = lvs
+            .iter()
+            .find(|item| item.parent_lv == lv.lv_name && item.lv_name.contains("_rimage_"))
+            .map_or(0, |item| item.size);
+        let no_room = !pvs.iter().any(|pv| {
+            !PvAttr::decode(&pv.pv_attr).missing
+                && !images.iter().any(|(_, image_pv)| *image_pv == pv.pv_name)
+                && (pv.pe_count - pv.pe_alloc_count) * pv.pe_size > image_size
+        });
+        Self {
+            vg_name: lv.vg_name.clone(),
+            lv_name: lv.lv_name.clone(),
+            segtype: lv.segtype.clone(),
+            actions,
+            selected: 0,
+            no_room,
+            colors,
+            lvm,
+            job: None,
+        }
+    }
+
+    //
+    // The lvchange/lvconvert to run, once picked.
+    //
+    pub fn take_job(&mut self) -> Option<LvmJob> {
+        self.job.take()
+    }
+
+    //
+    // The command the selected action runs, as shown before running it.
+    //
+    fn command(&self) -> String {
+        let (vg, lv) = (self.vg_name.as_str(), self.lv_name.as_str());
+        match &self.actions[self.selected] {
+            RaidAction::Sync(action) => format!(
+                "lvchange {}",
+                lvm::raid_syncaction_args(vg, lv, *action).join(" ")
+            ),
+            RaidAction::Repair => {
+                format!("lvconvert {}", lvm::raid_repair_args(vg, lv, &[]).join(" "))
+            }
+            RaidAction::Replace(pv) => format!(
+                "lvconvert {}",
+                lvm::raid_replace_args(vg, lv, pv, &[]).join(" ")
+            ),
+        }
+    }
+
+    //
+    // Done (true) when picked or cancelled, the pick is left as a job, a repair
+    // or replace copies a whole image.
+    //
+    pub fn handle_events(&mut self, key: &KeyEvent) -> Result<bool, LvmError> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Down => {
+                self.selected = (self.selected + 1) % self.actions.len();
+                Ok(false)
+            }
+            KeyCode::Up => {
+                self.selected = (self.selected + self.actions.len() - 1) % self.actions.len();
+                Ok(false)
+            }
+            KeyCode::Esc => {
+                STATUS.lock().unwrap().set_status("Raid action cancelled.");
+                Ok(true)
+            }
+            KeyCode::Enter => {
+                let (lvm, vg, lv) = (self.lvm.clone(), self.vg_name.clone(), self.lv_name.clone());
+                let action = self.actions[self.selected].clone();
+                let title = match &action {
+                    RaidAction::Sync(sync) => format!("Raid {} {vg}/{lv}", sync.text()),
+                    RaidAction::Repair => format!("Repair raid {vg}/{lv}"),
+                    RaidAction::Replace(pv) => format!("Replace {pv} of raid {vg}/{lv}"),
+                };
+                STATUS
+                    .lock()
+                    .unwrap()
+                    .set_status(&format!("Started: {title}"));
+                self.job = Some(LvmJob::new(&title, move || match action {
+                    RaidAction::Sync(sync) => lvm.raid_sync_action(&vg, &lv, sync),
+                    RaidAction::Repair => lvm.repair_raid(&vg, &lv, &[]),
+                    RaidAction::Replace(pv) => lvm.replace_raid_pv(&vg, &lv, &pv, &[]),
+                }));
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let s1 = Style::new().white().bold();
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::from("Raid '"),
+                Span::styled(self.lv_name.clone(), s1),
+                Span::from(format!("' ({}), pick an action:", self.segtype)),
+            ]),
+            Line::from(""),
+        ];
+        for (i, action) in self.actions.iter().enumerate() {
+            lines.push(if i == self.selected {
+                Line::styled(format!("> {} ", action.text()), s1.reversed())
+            } else {
+                Line::from(format!("  {} ", action.text()))
+            });
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::styled(
+            self.command(),
+            Style::new().fg(self.colors.header_fg),
+        ));
+        let moves_image = !matches!(self.actions[self.selected], RaidAction::Sync(_));
+        if moves_image && self.no_room {
+            lines.push(Line::styled(
+                "No pv without an image has room for one, add a pv to the vg first.",
+                Style::new().fg(tailwind::AMBER.c400).bold(),
+            ));
+        }
+
+        // Content plus ConfPopup's margins and buttons.
+        let popup_area = Rect {
+            x: area.width / 6,
+            y: area.height / 5,
+            width: area.width * 2 / 3,
+            height: (lines.len() as u16 + 8).min(area.height),
+        };
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(Text::from(lines))
+            .title(format!(" Raid {}/{} ", self.vg_name, self.lv_name));
+        frame.render_widget(popup, popup_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::lvm::{LvmLvData, LvmPVData, LvmlvSegData};
+    use crate::lvmapp::raidview::{is_syncing, raid_detail_lines, raid_status_line};
+
+    #[test]
+    fn test_raid_status() {
+        let mut lv = LvmLvData {
+            lv_name: "lvr1".to_string(),
+            attr: "rwi-a-r---".to_string(),
+            segtype: "raid1".to_string(),
+            sync_percent: Some(100.0),
+            raid_sync_action: "idle".to_string(),
+            raid_mismatch_count: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(raid_status_line(&lv).to_string(), "in sync");
+        assert!(!is_syncing(&lv));
+
+        lv.raid_sync_action = "check".to_string();
+        lv.sync_percent = Some(40.0);
+        assert!(is_syncing(&lv));
+        assert_eq!(raid_status_line(&lv).to_string(), "check ██░░░ 40%");
+
+        lv.raid_sync_action = "idle".to_string();
+        lv.raid_mismatch_count = Some(16);
+        assert_eq!(raid_status_line(&lv).to_string(), "16 mismatches");
+
+        lv.attr = "rwi-a-r-p-".to_string();
+        lv.health_status = "partial".to_string();
+        assert_eq!(raid_status_line(&lv).to_string(), "DEGRADED");
+
+        // Images in order, the one on the missing pv marked
+        let image = |i: usize, pv: &str| LvmLvData {
+            lv_name: format!("[lvr1_rimage_{i}]"),
+            parent_lv: "lvr1".to_string(),
+            lv_segs: vec![LvmlvSegData {
+                pvdev: pv.to_string(),
+                pv_start_pe: 1,
+                size_pe: 256,
+            }],
+            ..Default::default()
+        };
+        let lvs = [image(1, "/dev/sdb1"), image(0, "/dev/sda1")];
+        let pvs = ["a--", "a-m"].map(|attr| LvmPVData {
+            pv_name: if attr == "a--" {
+                "/dev/sda1"
+            } else {
+                "/dev/sdb1"
+            }
+            .to_string(),
+            pv_attr: attr.to_string(),
+            ..Default::default()
+        });
+        let lines: Vec<String> = raid_detail_lines(&lv, &lvs, &pvs)
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert!(lines[0].starts_with("health     partial"));
+        assert_eq!(lines[2], "mismatches 16");
+        assert_eq!(lines[3], "rimage_0   /dev/sda1");
+        assert_eq!(lines[4], "rimage_1   /dev/sdb1 MISSING");
+    }
+}
@@ -0,0 +1,272 @@
+use std::sync::Arc;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEventKind},
+    layout::Rect,
+    style::{Modifier, Style, Stylize, palette::tailwind},
+    text::{Line, Span, Text},
+};
+
+use crate::{
+    lvm::{self, LvmBackend, LvmError, LvmLvData, devinfo},
+    lvmapp::{STATUS, jobs::LvmJob, lvview::InputField, popup::ConfPopup, res::Colors},
+};
+
+enum RenameTarget {
+    Lv { vg_name: String, lv_name: String },
+    Vg { vg_name: String },
+}
+
+//
+// Inline edit of a vg or lv name in a table, the table renders 'input_line' in
+// place of the name. Enter renames, if /etc/fstab names the device it has to
+// be confirmed first.
+//
+pub struct RenameEdit {
+    target: RenameTarget,
+    input: InputField,
+    // Names the new name can not take.
+    taken: Vec<String>,
+    // (device, mount point) in /etc/fstab using the current name.
+    fstab: Vec<(String, String)>,
+    confirm: bool,
+    colors: Colors,
+    lvm: Arc<dyn LvmBackend>,
+    job: Option<LvmJob>,
+}
+
+impl RenameEdit {
+    //
+    // 'lvs' are the lvs of the vg, internal ones included as their names are
+    // taken too.
+    //
+    pub fn lv(lv: &LvmLvData, lvs: &[LvmLvData], colors: Colors, lvm: Arc<dyn LvmBackend>) -> Self {
+        let taken = lvs
+            .iter()
+            .filter(|item| item.vg_name == lv.vg_name)
+            .map(|item| item.lv_name.trim_matches(['[', ']']).to_string())
+            .collect();
+        Self::new(
+            RenameTarget::Lv {
+                vg_name: lv.vg_name.clone(),
+                lv_name: lv.lv_name.clone(),
+            },
+            &lv.lv_name,
+            taken,
+            devinfo::fstab_entries(&lv.vg_name, Some(&lv.lv_name)),
+            colors,
+            lvm,
+        )
+    }
+
+    pub fn vg(vg_name: &str, vgs: &[String], colors: Colors, lvm: Arc<dyn LvmBackend>) -> Self {
+        Self::new(
+            RenameTarget::Vg {
+                vg_name: vg_name.to_string(),
+            },
+            vg_name,
+            vgs.to_vec(),
+            devinfo::fstab_entries(vg_name, None),
+            colors,
+            lvm,
+        )
+    }
+
+    fn new(
+        target: RenameTarget,
+        name: &str,
+        taken: Vec<String>,
+        fstab: Vec<(String, String)>,
+        colors: Colors,
+        lvm: Arc<dyn LvmBackend>,
+    ) -> Self {
+        Self {
+            target,
+            input: InputField {
+                len_max: 127,
+                value: name.to_string(),
+                pos: name.len(),
+            },
+            taken,
+            fstab,
+            confirm: false,
+            colors,
+            lvm,
+            job: None,
+        }
+    }
+
+    //
+    // The lvrename/vgrename to run, once entered.
+    //
+    pub fn take_job(&mut self) -> Option<LvmJob> {
+        self.job.take()
+    }
+
+    fn old_name(&self) -> &str {
+        match &self.target {
+            RenameTarget::Lv { lv_name, .. } => lv_name,
+            RenameTarget::Vg { vg_name } => vg_name,
+        }
+    }
+
+    //
+    // Err with the reason the new name is not usable.
+    //
+    fn check(&self) -> Result<(), String> {
+        let name = &self.input.value;
+        match self.target {
+            RenameTarget::Lv { .. } => lvm::check_lv_name(name)?,
+            RenameTarget::Vg { .. } => lvm::check_vg_name(name)?,
+        }
+        if name != self.old_name() && self.taken.contains(name) {
+            return Err(format!("{name} already exists."));
+        }
+        Ok(())
+    }
+
+    //
+    // Done (true) when entered or cancelled, the rename is left as a job.
+    //
+    pub fn handle_events(&mut self, key: &KeyEvent) -> Result<bool, LvmError> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(false);
+        }
+        if self.confirm {
+            match key.code {
+                KeyCode::Enter => return self.rename(),
+                KeyCode::Esc => self.confirm = false,
+                _ => {}
+            }
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Esc => {
+                STATUS.lock().unwrap().set_status("Rename cancelled.");
+                return Ok(true);
+            }
+            KeyCode::Enter if self.input.value == self.old_name() => {
+                STATUS.lock().unwrap().set_status("Name unchanged.");
+                return Ok(true);
+            }
+            KeyCode::Enter => match self.check() {
+                Err(e) => STATUS.lock().unwrap().set_status(&e),
+                Ok(()) if self.fstab.is_empty() => return self.rename(),
+                Ok(()) => self.confirm = true,
+            },
+            KeyCode::Char(c)
+                if lvm::is_name_char(c) && self.input.value.len() < self.input.len_max =>
+            {
+                self.input.value.insert(self.input.pos, c);
+                self.input.pos += 1;
+            }
+            KeyCode::Backspace if self.input.pos > 0 => {
+                self.input.pos -= 1;
+                self.input.value.remove(self.input.pos);
+            }
+            KeyCode::Delete if self.input.pos < self.input.value.len() => {
+                self.input.value.remove(self.input.pos);
+            }
+            KeyCode::Left if self.input.pos > 0 => self.input.pos -= 1,
+            KeyCode::Right if self.input.pos < self.input.value.len() => self.input.pos += 1,
+            KeyCode::Home => self.input.pos = 0,
+            KeyCode::End => self.input.pos = self.input.value.len(),
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn rename(&mut self) -> Result<bool, LvmError> {
+        let (lvm, new_name) = (self.lvm.clone(), self.input.value.clone());
+        let what = match self.target {
+            RenameTarget::Lv { .. } => "LV",
+            RenameTarget::Vg { .. } => "VG",
+        };
+        let title = format!("Rename {what} {} -> {new_name}", self.old_name());
+        STATUS
+            .lock()
+            .unwrap()
+            .set_status(&format!("Started: {title}"));
+        self.job = Some(match &self.target {
+            RenameTarget::Lv { vg_name, lv_name } => {
+                let (vg_name, lv_name) = (vg_name.clone(), lv_name.clone());
+                LvmJob::new(&title, move || lvm.rename_lv(&vg_name, &lv_name, &new_name))
+            }
+            RenameTarget::Vg { vg_name } => {
+                let vg_name = vg_name.clone();
+                LvmJob::new(&title, move || lvm.rename_vg(&vg_name, &new_name))
+            }
+        });
+        Ok(true)
+    }
+
+    //
+    // The name being edited with the cursor shown, red while not usable.
+    //
+    pub fn input_line(&self) -> Line<'static> {
+        let fg = match self.check() {
+            Ok(()) => self.colors.selected_column_style_fg,
+            Err(_) => tailwind::RED.c400,
+        };
+        let style = Style::new().fg(fg).add_modifier(Modifier::UNDERLINED);
+        let (before, after) = self.input.value.split_at(self.input.pos);
+        let mut after = after.chars();
+        let cursor = after.next().map_or(" ".to_string(), String::from);
+        Line::from(vec![
+            Span::styled(before.to_string(), style),
+            Span::styled(cursor, style.add_modifier(Modifier::REVERSED)),
+            Span::styled(after.as_str().to_string(), style),
+        ])
+    }
+
+    //
+    // Confirmation when /etc/fstab has to be updated after the rename.
+    //
+    pub fn render(&self, frame: &mut Frame) {
+        if !self.confirm {
+            return;
+        }
+        let area = frame.area();
+        let popup_area = Rect {
+            x: area.width / 6,
+            y: area.height / 4,
+            width: area.width * 2 / 3,
+            height: (10 + self.fstab.len() as u16).min(area.height),
+        };
+        let s1 = Style::new().white().bold();
+        let warn = Style::new().light_red().bold();
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::from("Rename '"),
+                Span::styled(self.old_name().to_string(), s1),
+                Span::from("' to '"),
+                Span::styled(self.input.value.clone(), s1),
+                Span::from("'? /etc/fstab uses the current name:"),
+            ]),
+            Line::from(""),
+        ];
+        for (dev, mnt) in &self.fstab {
+            let line = if mnt == "/" {
+                Line::styled(format!("{dev} on / (root)"), warn)
+            } else {
+                Line::from(format!("{dev} on {mnt}"))
+            };
+            lines.push(line);
+        }
+        lines.push(Line::from(""));
+        let hint = if self.fstab.iter().any(|(_, mnt)| mnt == "/") {
+            "Update fstab, initramfs and boot loader before rebooting."
+        } else {
+            "Update fstab after renaming."
+        };
+        lines.push(Line::styled(hint, warn));
+
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(Text::from(lines))
+            .title(format!(" Rename {} ", self.old_name()));
+        frame.render_widget(popup, popup_area);
+    }
+}
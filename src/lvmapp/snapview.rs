@@ -0,0 +1,691 @@
+use std::sync::Arc;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Modifier, Style, Stylize, palette::tailwind},
+    text::{Line, Span, Text},
+    widgets::{Block, Padding, Paragraph},
+};
+use tui_widget_list::{ListBuilder, ListState, ListView, ScrollAxis};
+
+use Constraint::{Length, Max, Min};
+
+use crate::{
+    lvm::{
+        self, LvAttr, LvState, LvmBackend, LvmError, LvmLvData, LvmSnapshot, LvmVgData, fmt_size,
+    },
+    lvmapp::{
+        STATUS, View, ViewType,
+        jobs::LvmJob,
+        lvview::{InputField, ListItem},
+        popup::ConfPopup,
+        res::{self, Colors},
+    },
+};
+
+const SIZE_OPTS: [&str; 5] = ["%ORIGIN", "M", "G", "T", "%FREE"];
+// Cow usage from where a snapshot is about to become invalid.
+const SNAP_WARN_PERCENT: f64 = 80.0;
+
+pub fn is_invalid(lv: &LvmLvData) -> bool {
+    matches!(
+        LvAttr::decode(&lv.attr).state,
+        LvState::InvalidSnapshot | LvState::InvalidSuspendedSnapshot
+    )
+}
+
+//
+// Cow usage of a snapshot as a bar, '███░░░░░░░ 31.0%', or INVALID when it
+// ran full. Empty for other lvs.
+//
+pub fn snap_usage_line(lv: &LvmLvData) -> Line<'static> {
+    if lv.origin.is_empty() {
+        return Line::default();
+    }
+    if is_invalid(lv) {
+        return Line::styled(
+            "INVALID",
+            Style::new()
+                .fg(tailwind::RED.c400)
+                .add_modifier(Modifier::BOLD),
+        );
+    }
+    usage_bar(lv.snap_percent.unwrap_or(0.0), SNAP_WARN_PERCENT)
+}
+
+//
+// '███░░░░░░░ 31.0%', amber from 'warn' on.
+//
+pub fn usage_bar(percent: f64, warn: f64) -> Line<'static> {
+    let percent = percent.clamp(0.0, 100.0);
+    let filled = (percent / 10.0).round() as usize;
+    let color = if percent >= warn {
+        tailwind::AMBER.c400
+    } else {
+        tailwind::GREEN.c400
+    };
+    Line::from(vec![
+        Span::styled("█".repeat(filled), Style::new().fg(color)),
+        Span::styled(
+            "░".repeat(10 - filled),
+            Style::new().fg(tailwind::SLATE.c600),
+        ),
+        Span::raw(format!(" {percent:.1}%")),
+    ])
+}
+
+#[derive(PartialEq)]
+enum Focus {
+    Name,
+    Size,
+    SizeOpt,
+}
+
+//
+// lvcreate -s of an lv, name and cow size as a size or a percentage of the
+// origin.
+//
+pub struct SnapNewView {
+    focus: Focus,
+    popup_save: bool,
+    vg_name: String,
+    origin_name: String,
+    origin: Option<LvmLvData>,
+    vg_item: Option<LvmVgData>,
+    extent_size: u64,
+    // lv names of the vg, a new name can not take them.
+    taken: Vec<String>,
+    snapname: InputField,
+    snapsize: InputField,
+    snapsize_opt_state: ListState,
+    colors: Colors,
+    job: Option<LvmJob>,
+    lvm: Arc<dyn LvmBackend>,
+}
+
+impl View for SnapNewView {
+    fn refresh_data(&mut self) {
+        // noop
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::SnapNew
+    }
+
+    //
+    // handle events related to this view. If done here return true, e.g if "back" or "save".
+    //
+    fn handle_events(&mut self, key: &KeyEvent) -> core::result::Result<bool, &'static str> {
+        if key.kind == KeyEventKind::Press {
+            match key.code {
+                KeyCode::Tab => self.next_focus(),
+                KeyCode::BackTab => self.prev_focus(),
+                KeyCode::Backspace => {
+                    if let Some(input) = self.input_mut()
+                        && input.pos > 0
+                    {
+                        input.value.remove(input.pos - 1);
+                        input.pos -= 1;
+                    }
+                }
+                KeyCode::Char(c) => self.insert(c),
+                KeyCode::Left => {
+                    if let Some(input) = self.input_mut()
+                        && input.pos > 0
+                    {
+                        input.pos -= 1;
+                    }
+                }
+                KeyCode::Right => {
+                    if let Some(input) = self.input_mut()
+                        && input.pos < input.value.len()
+                    {
+                        input.pos += 1;
+                    }
+                }
+                KeyCode::Down if self.focus == Focus::SizeOpt => self.snapsize_opt_state.next(),
+                KeyCode::Up if self.focus == Focus::SizeOpt => self.snapsize_opt_state.previous(),
+                KeyCode::F(6) => match self.check() {
+                    Ok(_) => self.popup_save = true,
+                    Err(e) => STATUS.lock().unwrap().set_status(&e),
+                },
+                KeyCode::Esc => {
+                    if self.popup_save {
+                        STATUS
+                            .lock()
+                            .unwrap()
+                            .set_status("Snapshot creation cancelled.");
+                        self.popup_save = false;
+                    } else {
+                        return Ok(true);
+                    }
+                }
+                KeyCode::Enter if self.popup_save => {
+                    self.popup_save = false;
+                    let Some(snap) = self.snapshot() else {
+                        return Ok(false);
+                    };
+                    let (lvm, vg_name, origin_name) = (
+                        self.lvm.clone(),
+                        self.vg_name.clone(),
+                        self.origin_name.clone(),
+                    );
+                    let title = format!("Snapshot {vg_name}/{origin_name} as {}", snap.name);
+                    STATUS
+                        .lock()
+                        .unwrap()
+                        .set_status(&format!("Started: {title}"));
+                    self.job = Some(LvmJob::new(&title, move || {
+                        lvm.create_snapshot(&vg_name, &origin_name, &snap)
+                    }));
+                    return Ok(true);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+impl SnapNewView {
+    pub fn new(vg_name: &str, origin_name: &str, lvm: Arc<dyn LvmBackend>) -> Self {
+        let mut snapsize_opt_state = ListState::default();
+        // Default 20%ORIGIN
+        snapsize_opt_state.select(Some(0));
+        Self {
+            focus: Focus::Name,
+            popup_save: false,
+            vg_name: vg_name.to_string(),
+            origin_name: origin_name.to_string(),
+            origin: None,
+            vg_item: None,
+            extent_size: 0,
+            taken: Vec::new(),
+            snapname: InputField {
+                len_max: 25,
+                value: String::new(),
+                pos: 0,
+            },
+            snapsize: InputField {
+                len_max: 5,
+                value: String::from("20"),
+                pos: 2,
+            },
+            snapsize_opt_state,
+            colors: Colors::new(&res::PALETTES[0]),
+            job: None,
+            lvm,
+        }
+    }
+
+    //
+    // Origin, its vg and the names in use. Name defaults to <origin>_snap, with
+    // a number if that is taken.
+    //
+    pub fn fetch_data(&mut self) -> Result<(), LvmError> {
+        let vg_item = self.lvm.get_vg_info(&self.vg_name)?;
+        let lvs = lvm::get_lvinfo_by_vg(&self.vg_name, &self.lvm.get_lvs()?);
+        let origin = lvs
+            .iter()
+            .find(|lv| lv.lv_name == self.origin_name)
+            .cloned()
+            .ok_or_else(|| LvmError::CommandFailed {
+                cmd: "lvs".to_string(),
+                code: None,
+                stderr: format!(
+                    "Failed to find logical volume \"{}/{}\"",
+                    self.vg_name, self.origin_name
+                ),
+            })?;
+        self.extent_size = self
+            .lvm
+            .get_pvs()?
+            .iter()
+            .find(|pv| pv.vg_name == self.vg_name)
+            .map_or(0, |pv| pv.pe_size);
+        self.taken = lvs
+            .iter()
+            .map(|lv| lv.lv_name.trim_matches(['[', ']']).to_string())
+            .collect();
+
+        if self.snapname.value.is_empty() {
+            let base = format!("{}_snap", self.origin_name);
+            let name = (1..)
+                .map(|i| format!("{base}{i}"))
+                .find(|name| !self.taken.contains(name))
+                .filter(|_| self.taken.contains(&base))
+                .unwrap_or(base);
+            self.snapname.pos = name.len();
+            self.snapname.value = name;
+        }
+        self.origin = Some(origin);
+        self.vg_item = Some(vg_item);
+        Ok(())
+    }
+
+    //
+    // The snapshot lvcreate to run, once confirmed.
+    //
+    pub fn take_job(&mut self) -> Option<LvmJob> {
+        self.job.take()
+    }
+
+    fn next_focus(&mut self) {
+        self.focus = match self.focus {
+            Focus::Name => Focus::Size,
+            Focus::Size => Focus::SizeOpt,
+            Focus::SizeOpt => Focus::Name,
+        };
+    }
+
+    fn prev_focus(&mut self) {
+        self.focus = match self.focus {
+            Focus::Name => Focus::SizeOpt,
+            Focus::Size => Focus::Name,
+            Focus::SizeOpt => Focus::Size,
+        };
+    }
+
+    fn input_mut(&mut self) -> Option<&mut InputField> {
+        match self.focus {
+            Focus::Name => Some(&mut self.snapname),
+            Focus::Size => Some(&mut self.snapsize),
+            Focus::SizeOpt => None,
+        }
+    }
+
+    fn insert(&mut self, c: char) {
+        let valid = match self.focus {
+            Focus::Name => lvm::is_name_char(c),
+            Focus::Size => c.is_ascii_digit(),
+            Focus::SizeOpt => false,
+        };
+        if let Some(input) = self.input_mut()
+            && valid
+            && input.value.len() < input.len_max
+        {
+            input.value.insert(input.pos, c);
+            input.pos += 1;
+        }
+    }
+
+    fn size_opt(&self) -> &'static str {
+        SIZE_OPTS[self.snapsize_opt_state.selected.unwrap_or(0)]
+    }
+
+    fn snapshot(&self) -> Option<LvmSnapshot> {
+        Some(LvmSnapshot {
+            name: self.snapname.value.clone(),
+            size: self.snapsize.value.parse::<u64>().ok()?,
+            size_unit: self.size_opt().to_string(),
+        })
+    }
+
+    //
+    // Cow size in bytes, or why the snapshot can not be created.
+    //
+    fn check(&self) -> Result<u64, String> {
+        let (Some(origin), Some(vg)) = (self.origin.as_ref(), self.vg_item.as_ref()) else {
+            return Err("No data".to_string());
+        };
+        let name = &self.snapname.value;
+        lvm::check_lv_name(name)?;
+        if self.taken.contains(name) {
+            return Err(format!("{name} already exists."));
+        }
+        let snap = self
+            .snapshot()
+            .ok_or_else(|| "Enter a size, e.g. 20 (%ORIGIN) or 5 (G)".to_string())?;
+        let cow_size = snap
+            .cow_size(origin.size, vg, self.extent_size)
+            .map_err(|e| e.to_string())?;
+        if cow_size > vg.free {
+            return Err(format!(
+                "Not enough free space in {}, {} free.",
+                self.vg_name,
+                fmt_size(vg.free)
+            ));
+        }
+        Ok(cow_size)
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, rect: &Rect) {
+        let inner_layout = &Layout::vertical([
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Min(7),
+        ])
+        .margin(2);
+        let [
+            header_area,
+            origin_area,
+            name_area,
+            size_area,
+            _,
+            _,
+            info_area,
+        ] = inner_layout.areas(*rect);
+
+        let para_heading = Paragraph::new("CREATE SNAPSHOT")
+            .alignment(ratatui::layout::Alignment::Left)
+            .style(Style::new().fg(self.colors.block_border));
+        frame.render_widget(para_heading, header_area);
+
+        let label_style = Style::new().fg(self.colors.row_fg);
+        let input_style = Style::new()
+            .fg(self.colors.header_bg)
+            .underline_color(self.colors.header_bg)
+            .add_modifier(Modifier::UNDERLINED);
+
+        let h_layout = &Layout::horizontal([Length(10), Max(40)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [label_area, val_area] = h_layout.areas(origin_area);
+        frame.render_widget(Paragraph::new("origin:").style(label_style), label_area);
+        frame.render_widget(
+            Paragraph::new(format!("{}/{}", self.vg_name, self.origin_name))
+                .style(Style::new().fg(self.colors.header_bg)),
+            val_area,
+        );
+
+        let h_layout = &Layout::horizontal([Length(10), Max(26)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [label_area, input_area] = h_layout.areas(name_area);
+        frame.render_widget(Paragraph::new("name:").style(label_style), label_area);
+        frame.render_widget(
+            Paragraph::new(
+                Text::from(self.snapname.value.clone()).fg(self.colors.selected_column_style_fg),
+            )
+            .style(input_style),
+            input_area,
+        );
+        if self.focus == Focus::Name {
+            frame.set_cursor_position(Position::new(
+                input_area.x + (self.snapname.pos as u16),
+                input_area.y,
+            ));
+        }
+
+        let h_layout = &Layout::horizontal([Length(10), Max(6), Length(11)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [label_area, input_area, mut option_area] = h_layout.areas(size_area);
+        frame.render_widget(Paragraph::new("size:").style(label_style), label_area);
+        frame.render_widget(
+            Paragraph::new(
+                Text::from(self.snapsize.value.clone()).fg(self.colors.selected_column_style_fg),
+            )
+            .style(input_style),
+            input_area,
+        );
+        self.render_size_opt(frame, &mut option_area);
+        if self.focus == Focus::Size {
+            frame.set_cursor_position(Position::new(
+                input_area.x + (self.snapsize.pos as u16),
+                input_area.y,
+            ));
+        }
+
+        self.render_info(frame, info_area);
+
+        if self.popup_save {
+            self.render_popup_save(frame, rect);
+        }
+    }
+
+    fn render_size_opt(&mut self, frame: &mut Frame, rect: &mut Rect) {
+        rect.height = 1;
+        let list_style = match self.focus {
+            Focus::SizeOpt => Style::new()
+                .bg(self.colors.header_bg)
+                .fg(self.colors.selected_column_style_fg),
+            _ => Style::new()
+                .bg(self.colors.alt_row_color)
+                .fg(self.colors.selected_column_style_fg),
+        };
+
+        let builder = ListBuilder::new(|context| {
+            let mut item = ListItem::new(SIZE_OPTS[context.index]);
+            if context.is_selected {
+                item.style = Style::new().fg(self.colors.selected_cell_style_fg);
+            }
+            (item, 1)
+        });
+
+        let block = Block::default().padding(Padding::horizontal(1));
+        let list = ListView::new(builder, SIZE_OPTS.len())
+            .scroll_axis(ScrollAxis::Vertical)
+            .block(block)
+            .infinite_scrolling(true)
+            .style(list_style);
+
+        frame.render_stateful_widget(list, *rect, &mut self.snapsize_opt_state);
+        frame.render_widget(Text::from("▾").style(list_style).right_aligned(), *rect);
+    }
+
+    //
+    // Sizes, the lvcreate command and why it would be refused.
+    //
+    fn render_info(&self, frame: &mut Frame, area: Rect) {
+        let (Some(origin), Some(vg)) = (self.origin.as_ref(), self.vg_item.as_ref()) else {
+            return;
+        };
+        let label = |s: &str| Span::styled(format!("{s:<16}"), Style::new().fg(self.colors.row_fg));
+        let value = |s: String| Span::styled(s, Style::new().fg(self.colors.header_fg));
+
+        let check = self.check();
+        let (cow_size, free_after) = match &check {
+            Ok(cow_size) => (fmt_size(*cow_size), fmt_size(vg.free - cow_size)),
+            Err(_) => ("-".to_string(), "-".to_string()),
+        };
+        let mut lines = vec![
+            Line::from(vec![label("origin size"), value(fmt_size(origin.size))]),
+            Line::from(vec![label("cow size"), value(cow_size)]),
+            Line::from(vec![
+                label("vg free"),
+                value(format!("{} -> {free_after}", fmt_size(vg.free))),
+            ]),
+        ];
+        if let Some(snap) = self.snapshot() {
+            lines.push(Line::from(vec![
+                label("command"),
+                value(format!(
+                    "lvcreate {}",
+                    lvm::snapshot_args(&self.vg_name, &self.origin_name, &snap).join(" ")
+                )),
+            ]));
+        }
+        match check {
+            Ok(cow_size) => {
+                lines.push(Line::styled(
+                    format!(
+                        "The snapshot is invalid once {} of the origin or snapshot has changed.",
+                        fmt_size(cow_size)
+                    ),
+                    Style::new().fg(self.colors.block_border),
+                ));
+            }
+            Err(e) => {
+                lines.push(Line::styled(e, Style::new().fg(tailwind::RED.c400).bold()));
+            }
+        }
+
+        let para = Paragraph::new(lines).block(Block::default().padding(Padding::horizontal(1)));
+        frame.render_widget(para, area);
+    }
+
+    fn render_popup_save(&self, frame: &mut Frame, rect: &Rect) {
+        let popup_area = Rect {
+            x: rect.width / 4,
+            y: rect.height / 3,
+            width: rect.width / 2,
+            height: rect.height / 2,
+        };
+        let s1 = Style::new().white().bold();
+        let cow_size = self.check().unwrap_or(0);
+
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(Text::from(Line::from(vec![
+                Span::from("You are about to create snapshot '"),
+                Span::from(self.snapname.value.clone()).style(s1),
+                Span::from("' of '"),
+                Span::from(self.origin_name.clone()).style(s1),
+                Span::from("' with "),
+                Span::from(fmt_size(cow_size)).style(s1),
+                Span::from(" for changes."),
+            ])))
+            .title(format!("Snapshot {}", self.origin_name));
+        frame.render_widget(popup, popup_area);
+    }
+}
+
+//
+// Confirm lvconvert --merge of a snapshot into its origin.
+//
+pub struct SnapMergePopup {
+    vg_name: String,
+    snap_name: String,
+    origin_name: String,
+    origin_open: bool,
+    colors: Colors,
+    lvm: Arc<dyn LvmBackend>,
+    job: Option<LvmJob>,
+}
+
+impl SnapMergePopup {
+    //
+    // 'lvs' are the lvs of the vg, to see if the origin is open.
+    //
+    pub fn new(
+        snap: &LvmLvData,
+        lvs: &[LvmLvData],
+        colors: Colors,
+        lvm: Arc<dyn LvmBackend>,
+    ) -> Self {
+        let origin_open = lvs
+            .iter()
+            .find(|lv| lv.lv_name == snap.origin)
+            .is_some_and(|lv| LvAttr::decode(&lv.attr).open == Some(true));
+        Self {
+            vg_name: snap.vg_name.clone(),
+            snap_name: snap.lv_name.clone(),
+            origin_name: snap.origin.clone(),
+            origin_open,
+            colors,
+            lvm,
+            job: None,
+        }
+    }
+
+    //
+    // The lvconvert --merge to run, once confirmed.
+    //
+    pub fn take_job(&mut self) -> Option<LvmJob> {
+        self.job.take()
+    }
+
+    //
+    // Done (true) when merged or cancelled, lvconvert errors are passed on.
+    //
+    pub fn handle_events(&mut self, key: &KeyEvent) -> Result<bool, LvmError> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Esc => {
+                STATUS
+                    .lock()
+                    .unwrap()
+                    .set_status("Snapshot merge cancelled.");
+                Ok(true)
+            }
+            KeyCode::Enter => {
+                // The merge itself goes on in the kernel, lvconvert returns
+                // when it started (or at next activation if the origin is open).
+                let (lvm, vg_name, snap_name) = (
+                    self.lvm.clone(),
+                    self.vg_name.clone(),
+                    self.snap_name.clone(),
+                );
+                let title = format!("Merge snapshot {vg_name}/{snap_name}");
+                STATUS
+                    .lock()
+                    .unwrap()
+                    .set_status(&format!("Started: {title}"));
+                self.job = Some(LvmJob::new(&title, move || {
+                    lvm.merge_snapshot(&vg_name, &snap_name)
+                }));
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let popup_area = Rect {
+            x: area.width / 6,
+            y: area.height / 4,
+            width: area.width * 2 / 3,
+            height: 12.min(area.height),
+        };
+        let s1 = Style::new().white().bold();
+        let warn = Style::new().light_red().bold();
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::from("Merge snapshot '"),
+                Span::styled(self.snap_name.clone(), s1),
+                Span::from("' into '"),
+                Span::styled(self.origin_name.clone(), s1),
+                Span::from("'?"),
+            ]),
+            Line::from(""),
+            Line::styled(
+                "The origin goes back to the snapshot, changes made since are lost.",
+                warn,
+            ),
+        ];
+        if self.origin_open {
+            lines.push(Line::from(
+                "The origin is open, the merge starts on its next activation.",
+            ));
+        }
+
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(Text::from(lines))
+            .title(format!(" Merge {}/{} ", self.vg_name, self.snap_name));
+        frame.render_widget(popup, popup_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::lvm::LvmLvData;
+    use crate::lvmapp::snapview::snap_usage_line;
+
+    #[test]
+    fn test_snap_usage_line() {
+        let mut lv = LvmLvData {
+            attr: "swi-a-s---".to_string(),
+            origin: "lvdata".to_string(),
+            snap_percent: Some(31.0),
+            ..Default::default()
+        };
+        assert_eq!(snap_usage_line(&lv).to_string(), "███░░░░░░░ 31.0%");
+        lv.attr = "swi-I-s---".to_string();
+        assert_eq!(snap_usage_line(&lv).to_string(), "INVALID");
+        lv.origin.clear();
+        assert_eq!(snap_usage_line(&lv).to_string(), "");
+    }
+}
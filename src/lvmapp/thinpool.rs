@@ -0,0 +1,172 @@
+//
+// Thin pool usage. Writes to a full pool fail or queue, so data and metadata
+// usage is shown against warning thresholds (--thin-data-warn, --thin-meta-warn).
+//
+
+use ratatui::{
+    style::{Modifier, Style, palette::tailwind},
+    text::{Line, Span},
+};
+
+use crate::{
+    lvm::{self, LvmLvData, fmt_size},
+    lvmapp::snapview,
+};
+
+//
+// Usage in percent from where a pool is shown as filling up.
+//
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThinThresholds {
+    pub data: f64,
+    pub metadata: f64,
+}
+
+impl Default for ThinThresholds {
+    fn default() -> Self {
+        Self {
+            data: 80.0,
+            metadata: 80.0,
+        }
+    }
+}
+
+fn percent_span(label: &str, percent: f64, warn: f64) -> Span<'static> {
+    let style = if percent >= warn {
+        Style::new()
+            .fg(tailwind::RED.c400)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::new()
+    };
+    Span::styled(format!("{label} {percent:.0}% "), style)
+}
+
+//
+// 'D 85% M 13% 1.38x' for the lvs table, data, metadata and provisioning.
+//
+pub fn pool_usage_line(pool: &LvmLvData, lvs: &[LvmLvData], t: &ThinThresholds) -> Line<'static> {
+    let mut spans = vec![
+        percent_span("D", pool.data_percent.unwrap_or(0.0), t.data),
+        percent_span("M", pool.metadata_percent.unwrap_or(0.0), t.metadata),
+    ];
+    if let Some(ratio) = lvm::thin_provisioning(pool, lvs) {
+        let style = if ratio > 1.0 {
+            Style::new().fg(tailwind::AMBER.c400)
+        } else {
+            Style::new()
+        };
+        spans.push(Span::styled(format!("{ratio:.2}x"), style));
+    }
+    Line::from(spans)
+}
+
+//
+// What needs attention in a pool, empty if all is well.
+//
+pub fn pool_warnings(pool: &LvmLvData, t: &ThinThresholds) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let data = pool.data_percent.unwrap_or(0.0);
+    if data >= t.data {
+        warnings.push(format!(
+            "Data {data:.1}% over {:.0}%, extend the pool or free thin lvs.",
+            t.data
+        ));
+    }
+    let metadata = pool.metadata_percent.unwrap_or(0.0);
+    if metadata >= t.metadata {
+        warnings.push(format!(
+            "Metadata {metadata:.1}% over {:.0}%, extend with --poolmetadatasize.",
+            t.metadata
+        ));
+    }
+    warnings
+}
+
+//
+// Gauges and provisioning of the selected pool, above its segments.
+//
+pub fn pool_detail_lines(
+    pool: &LvmLvData,
+    lvs: &[LvmLvData],
+    t: &ThinThresholds,
+) -> Vec<Line<'static>> {
+    let gauge = |label: &str, percent: Option<f64>, warn: f64| {
+        let mut line = Line::raw(format!("{label:<10}"));
+        line.spans
+            .extend(snapview::usage_bar(percent.unwrap_or(0.0), warn).spans);
+        line
+    };
+    let virtual_size: u64 = lvs
+        .iter()
+        .filter(|lv| lv.vg_name == pool.vg_name && lv.pool_lv == pool.lv_name)
+        .map(|lv| lv.size)
+        .sum();
+    let ratio = lvm::thin_provisioning(pool, lvs).unwrap_or(0.0);
+
+    let mut lines = vec![
+        gauge("data", pool.data_percent, t.data),
+        gauge("metadata", pool.metadata_percent, t.metadata),
+        Line::raw(format!(
+            "{:<10}{} of {} ({ratio:.2}x)",
+            "thin lvs",
+            fmt_size(virtual_size),
+            fmt_size(pool.size)
+        )),
+    ];
+    for warning in pool_warnings(pool, t) {
+        lines.push(Line::styled(
+            warning,
+            Style::new()
+                .fg(tailwind::RED.c400)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::lvm::LvmLvData;
+    use crate::lvmapp::thinpool::{ThinThresholds, pool_usage_line, pool_warnings};
+
+    #[test]
+    fn test_pool_usage() {
+        let gb = 1024 * 1024 * 1024;
+        let pool = LvmLvData {
+            lv_name: "pool0".to_string(),
+            vg_name: "vg01".to_string(),
+            size: 4 * gb,
+            segtype: "thin-pool".to_string(),
+            data_percent: Some(85.0),
+            metadata_percent: Some(12.5),
+            ..Default::default()
+        };
+        let thin = |name: &str, size: u64| LvmLvData {
+            lv_name: name.to_string(),
+            vg_name: "vg01".to_string(),
+            size,
+            pool_lv: "pool0".to_string(),
+            ..Default::default()
+        };
+        let lvs = vec![pool.clone(), thin("thin1", 3 * gb), thin("thin2", 3 * gb)];
+        let t = ThinThresholds::default();
+
+        assert_eq!(
+            pool_usage_line(&pool, &lvs, &t).to_string(),
+            "D 85% M 12% 1.50x"
+        );
+        let warnings = pool_warnings(&pool, &t);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Data 85.0% over 80%"));
+
+        let t = ThinThresholds {
+            data: 90.0,
+            metadata: 10.0,
+        };
+        let warnings = pool_warnings(&pool, &t);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Metadata 12.5% over 10%"));
+    }
+}
@@ -0,0 +1,867 @@
+//
+// New vgs and more pvs for a vg. Free disks and partitions are found in sysfs
+// (what lsblk shows), pvcreate'd and then given to vgcreate or vgextend. Orphan
+// pvs, already initialized but in no vg, are offered as they are.
+//
+
+use std::sync::Arc;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Modifier, Style, Stylize, palette::tailwind},
+    text::{Line, Span, Text},
+    widgets::{Block, Padding, Paragraph},
+};
+use tui_widget_list::ListState;
+
+use Constraint::{Length, Max, Min};
+
+use crate::{
+    lvm::{
+        self, AllocPolicy, LvmBackend, LvmError, LvmPVData, LvmPvCreate, LvmVgCreate,
+        devinfo::BlockDev, fmt_size, parse_size_arg,
+    },
+    lvmapp::{
+        STATUS, View, ViewType,
+        jobs::LvmJob,
+        lvview::{InputField, PvList, PvSelect, render_dropdown},
+        popup::ConfPopup,
+        res::{self, Colors},
+    },
+};
+
+const EXTENT_SIZE_DEFAULT: &str = "4M";
+// pvcreate aligns data to 1MiB unless told, alignment must be of 4KiB.
+const ALIGNMENT_MIN: u64 = 4096;
+const METADATA_COPIES_MAX: u64 = 2;
+
+fn is_size_char(c: char) -> bool {
+    c.is_ascii_digit() || "kKmMgG".contains(c)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Focus {
+    Name,
+    ExtentSize,
+    Alloc,
+    Copies,
+    Alignment,
+    DevAvail,
+    DevSelected,
+}
+
+pub struct VgCreateView {
+    focus: Focus,
+    popup_save: bool,
+    // Vg to extend, None for a new vg.
+    extend_vg: Option<String>,
+    // Existing vgs, the new name must not be one of them.
+    vgs: Vec<String>,
+    devs: Vec<BlockDev>,
+    // Pvs in no vg, no pvcreate needed.
+    orphans: Vec<LvmPVData>,
+    name: InputField,
+    extent_size: InputField,
+    alloc_state: ListState,
+    copies: InputField,
+    alignment: InputField,
+    dev_select: PvSelect,
+    colors: Colors,
+    job: Option<LvmJob>,
+    lvm: Arc<dyn LvmBackend>,
+}
+
+impl View for VgCreateView {
+    fn refresh_data(&mut self) {
+        // noop
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::VgCreate
+    }
+
+    //
+    // handle events related to this view. If done here return true, e.g if "back" or "save".
+    //
+    fn handle_events(&mut self, key: &KeyEvent) -> core::result::Result<bool, &'static str> {
+        if key.kind == KeyEventKind::Press {
+            match key.code {
+                KeyCode::Tab => self.next_focus(),
+                KeyCode::BackTab => self.prev_focus(),
+                KeyCode::Backspace => {
+                    if let Some(input) = self.input_mut()
+                        && input.pos > 0
+                    {
+                        input.value.remove(input.pos - 1);
+                        input.pos -= 1;
+                    }
+                }
+                KeyCode::Char(' ') => self.toggle_dev(),
+                KeyCode::Char(c) => self.insert(c),
+                KeyCode::Left => {
+                    if let Some(input) = self.input_mut()
+                        && input.pos > 0
+                    {
+                        input.pos -= 1;
+                    }
+                }
+                KeyCode::Right => {
+                    if let Some(input) = self.input_mut()
+                        && input.pos < input.value.len()
+                    {
+                        input.pos += 1;
+                    }
+                }
+                KeyCode::Down => match self.focus {
+                    Focus::Alloc => self.alloc_state.next(),
+                    Focus::DevAvail => self.dev_select.next(PvList::Avail),
+                    Focus::DevSelected => self.dev_select.next(PvList::Selected),
+                    _ => {}
+                },
+                KeyCode::Up => match self.focus {
+                    Focus::Alloc => self.alloc_state.previous(),
+                    Focus::DevAvail => self.dev_select.previous(PvList::Avail),
+                    Focus::DevSelected => self.dev_select.previous(PvList::Selected),
+                    _ => {}
+                },
+                KeyCode::F(6) => match self.check() {
+                    Ok(_) => self.popup_save = true,
+                    Err(e) => STATUS.lock().unwrap().set_status(&e),
+                },
+                KeyCode::Esc => {
+                    if self.popup_save {
+                        let what = if self.extend_vg.is_some() {
+                            "Vg extend cancelled."
+                        } else {
+                            "Vg creation cancelled."
+                        };
+                        STATUS.lock().unwrap().set_status(what);
+                        self.popup_save = false;
+                    } else {
+                        return Ok(true);
+                    }
+                }
+                KeyCode::Enter if self.popup_save => {
+                    self.popup_save = false;
+                    if self.check().is_err() {
+                        return Ok(false);
+                    }
+                    let job = self.save_job();
+                    STATUS
+                        .lock()
+                        .unwrap()
+                        .set_status(&format!("Started: {}", job.title()));
+                    self.job = Some(job);
+                    return Ok(true);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+impl VgCreateView {
+    pub fn new(lvm: Arc<dyn LvmBackend>) -> Self {
+        let mut alloc_state = ListState::default();
+        alloc_state.select(Some(0));
+        Self {
+            focus: Focus::Name,
+            popup_save: false,
+            extend_vg: None,
+            vgs: Vec::new(),
+            devs: Vec::new(),
+            orphans: Vec::new(),
+            name: InputField {
+                len_max: 25,
+                value: String::new(),
+                pos: 0,
+            },
+            extent_size: InputField {
+                len_max: 6,
+                value: EXTENT_SIZE_DEFAULT.to_string(),
+                pos: EXTENT_SIZE_DEFAULT.len(),
+            },
+            alloc_state,
+            copies: InputField {
+                len_max: 1,
+                value: String::new(),
+                pos: 0,
+            },
+            alignment: InputField {
+                len_max: 6,
+                value: String::new(),
+                pos: 0,
+            },
+            dev_select: PvSelect::new(&[]),
+            colors: Colors::new(&res::PALETTES[0]),
+            job: None,
+            lvm,
+        }
+    }
+
+    //
+    // Add pvs to 'vg_name' instead, only the pvcreate options apply.
+    //
+    pub fn extend(mut self, vg_name: &str) -> Self {
+        self.extend_vg = Some(vg_name.to_string());
+        self.focus = Focus::Copies;
+        self
+    }
+
+    //
+    // Block devices, orphan pvs and the vg names taken.
+    //
+    pub fn fetch_data(&mut self) -> Result<(), LvmError> {
+        self.vgs = self.lvm.get_vgs()?;
+        self.orphans = self
+            .lvm
+            .get_pvs()?
+            .into_iter()
+            .filter(|pv| pv.vg_name.is_empty())
+            .collect();
+        self.devs = self.lvm.get_block_devs()?;
+        let mut avail: Vec<String> = self
+            .devs
+            .iter()
+            .filter(|dev| dev.used_by.is_none())
+            .map(|dev| dev.name.clone())
+            .chain(self.orphans.iter().map(|pv| pv.pv_name.clone()))
+            .collect();
+        avail.sort();
+        avail.dedup();
+        self.dev_select = PvSelect::new(&avail);
+        if let Some(vg_name) = &self.extend_vg
+            && !self.vgs.contains(vg_name)
+        {
+            return Err(LvmError::CommandFailed {
+                cmd: "vgs".to_string(),
+                code: None,
+                stderr: format!("Volume group \"{vg_name}\" not found"),
+            });
+        }
+        Ok(())
+    }
+
+    //
+    // The vgcreate to run, once confirmed.
+    //
+    pub fn take_job(&mut self) -> Option<LvmJob> {
+        self.job.take()
+    }
+
+    fn is_orphan(&self, name: &str) -> bool {
+        self.orphans.iter().any(|pv| pv.pv_name == name)
+    }
+
+    fn dev_size(&self, name: &str) -> u64 {
+        match self.orphans.iter().find(|pv| pv.pv_name == name) {
+            Some(pv) => pv.pv_size,
+            None => self
+                .devs
+                .iter()
+                .find(|dev| dev.name == name)
+                .map_or(0, |dev| dev.size),
+        }
+    }
+
+    // Block devices not offered and why, pvs are left out.
+    fn unavailable(&self) -> Vec<&BlockDev> {
+        self.devs
+            .iter()
+            .filter(|dev| dev.used_by.is_some() && !self.is_orphan(&dev.name))
+            .filter(|dev| !self.dev_select.avail.contains(&dev.name))
+            .collect()
+    }
+
+    // Selected devices pvcreate is run on.
+    fn new_pvs(&self) -> Vec<&String> {
+        self.dev_select
+            .selected
+            .iter()
+            .filter(|name| !self.is_orphan(name))
+            .collect()
+    }
+
+    fn focus_order(&self) -> Vec<Focus> {
+        let mut order = Vec::new();
+        if self.extend_vg.is_none() {
+            order.extend([Focus::Name, Focus::ExtentSize, Focus::Alloc]);
+        }
+        order.extend([Focus::Copies, Focus::Alignment]);
+        if !self.dev_select.avail.is_empty() {
+            order.push(Focus::DevAvail);
+        }
+        if !self.dev_select.selected.is_empty() {
+            order.push(Focus::DevSelected);
+        }
+        order
+    }
+
+    fn next_focus(&mut self) {
+        let mut order = self.focus_order();
+        let i = order.iter().position(|f| *f == self.focus).unwrap_or(0);
+        let next = (i + 1) % order.len();
+        self.focus = order.swap_remove(next);
+    }
+
+    fn prev_focus(&mut self) {
+        let mut order = self.focus_order();
+        let i = order.iter().position(|f| *f == self.focus).unwrap_or(0);
+        let prev = (i + order.len() - 1) % order.len();
+        self.focus = order.swap_remove(prev);
+    }
+
+    fn toggle_dev(&mut self) {
+        let list = match self.focus {
+            Focus::DevAvail => PvList::Avail,
+            Focus::DevSelected => PvList::Selected,
+            _ => return,
+        };
+        self.dev_select.toggle(list);
+        // Don't stay on a list that ran empty.
+        if !self.focus_order().contains(&self.focus) {
+            self.next_focus();
+        }
+    }
+
+    fn input_mut(&mut self) -> Option<&mut InputField> {
+        match self.focus {
+            Focus::Name => Some(&mut self.name),
+            Focus::ExtentSize => Some(&mut self.extent_size),
+            Focus::Copies => Some(&mut self.copies),
+            Focus::Alignment => Some(&mut self.alignment),
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, c: char) {
+        let valid = match self.focus {
+            Focus::Name => lvm::is_name_char(c),
+            Focus::ExtentSize | Focus::Alignment => is_size_char(c),
+            Focus::Copies => c.is_ascii_digit(),
+            _ => false,
+        };
+        if let Some(input) = self.input_mut()
+            && valid
+            && input.value.len() < input.len_max
+        {
+            input.value.insert(input.pos, c);
+            input.pos += 1;
+        }
+    }
+
+    fn alloc(&self) -> AllocPolicy {
+        AllocPolicy::VG[self.alloc_state.selected.unwrap_or(0)]
+    }
+
+    //
+    // pvcreate options, None if one does not parse. Empty fields are lvm's
+    // defaults.
+    //
+    fn pv_create(&self) -> Option<LvmPvCreate> {
+        let metadata_copies = match self.copies.value.as_str() {
+            "" => None,
+            n => Some(n.parse::<u64>().ok()?),
+        };
+        let data_alignment = match self.alignment.value.as_str() {
+            "" => None,
+            size => Some(parse_size_arg(size, "K")?),
+        };
+        Some(LvmPvCreate {
+            metadata_copies,
+            data_alignment,
+        })
+    }
+
+    fn vg_create(&self) -> Option<LvmVgCreate> {
+        let extent_size = match self.extent_size.value.as_str() {
+            "" => None,
+            size => Some(parse_size_arg(size, "M")?),
+        };
+        Some(LvmVgCreate {
+            extent_size,
+            alloc: self.alloc(),
+        })
+    }
+
+    //
+    // What vgcreate/vgextend would refuse, checked before asking.
+    //
+    fn check(&self) -> Result<(), String> {
+        if self.extend_vg.is_none() {
+            let name = &self.name.value;
+            lvm::check_vg_name(name)?;
+            if self.vgs.contains(name) {
+                return Err(format!("A vg named {name} exists already."));
+            }
+            let vg = self
+                .vg_create()
+                .ok_or_else(|| "Extent size is not a size, e.g. 4M.".to_string())?;
+            if let Some(size) = vg.extent_size
+                && (!size.is_power_of_two() || size < 1024)
+            {
+                return Err("Extent size must be a power of 2, at least 1K.".to_string());
+            }
+        }
+        let pv = self
+            .pv_create()
+            .ok_or_else(|| "Metadata copies or alignment do not parse.".to_string())?;
+        if pv.metadata_copies.is_some_and(|n| n > METADATA_COPIES_MAX) {
+            return Err(format!("Metadata copies are 0 to {METADATA_COPIES_MAX}."));
+        }
+        if pv
+            .data_alignment
+            .is_some_and(|n| n == 0 || !n.is_multiple_of(ALIGNMENT_MIN))
+        {
+            return Err("Data alignment must be a multiple of 4K.".to_string());
+        }
+        if self.dev_select.selected.is_empty() {
+            return Err("Select the devices to use.".to_string());
+        }
+        Ok(())
+    }
+
+    //
+    // pvcreate what is not a pv yet, then vgcreate or vgextend. Stops at the
+    // first error, pvs made before it are left as orphans.
+    //
+    fn save_job(&self) -> LvmJob {
+        let lvm = self.lvm.clone();
+        let pv = self.pv_create().unwrap_or_default();
+        let new_pvs: Vec<String> = self.new_pvs().into_iter().cloned().collect();
+        let pvl = self.dev_select.selected.clone();
+        let n = pvl.len();
+        let pvs = format!("{n} pv{}", if n == 1 { "" } else { "s" });
+        let (title, vg) = match &self.extend_vg {
+            Some(vg_name) => (format!("Extend {vg_name} by {pvs}"), None),
+            None => {
+                let vg = self.vg_create().unwrap_or(LvmVgCreate {
+                    extent_size: None,
+                    alloc: AllocPolicy::Unset,
+                });
+                (format!("Create vg {} on {pvs}", self.name.value), Some(vg))
+            }
+        };
+        let vg_name = self
+            .extend_vg
+            .clone()
+            .unwrap_or_else(|| self.name.value.clone());
+        LvmJob::new(&title, move || {
+            let mut out = String::new();
+            for dev in &new_pvs {
+                out += &lvm.create_pv(dev, &pv)?;
+            }
+            out += &match vg {
+                Some(vg) => lvm.create_vg(&vg_name, &pvl, &vg)?,
+                None => lvm.extend_vg(&vg_name, &pvl)?,
+            };
+            Ok(out)
+        })
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, rect: &Rect) {
+        let inner_layout = &Layout::vertical([
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(1),
+            Length(8),
+            Min(6),
+        ])
+        .margin(2);
+        let [
+            header_area,
+            name_area,
+            extent_area,
+            alloc_area,
+            copies_area,
+            align_area,
+            _,
+            dev_sel_label,
+            _,
+            dev_sel_area,
+            info_area,
+        ] = inner_layout.areas(*rect);
+
+        let heading = match &self.extend_vg {
+            Some(vg_name) => format!("EXTEND VOLUME GROUP {vg_name}"),
+            None => "CREATE VOLUME GROUP".to_string(),
+        };
+        let para_heading = Paragraph::new(heading)
+            .alignment(ratatui::layout::Alignment::Left)
+            .style(Style::new().fg(self.colors.block_border));
+        frame.render_widget(para_heading, header_area);
+
+        let label_style = Style::new().fg(self.colors.row_fg);
+        let input_style = Style::new()
+            .fg(self.colors.header_bg)
+            .underline_color(self.colors.header_bg)
+            .add_modifier(Modifier::UNDERLINED);
+        let value_style = Style::new().fg(self.colors.header_bg);
+
+        let h_layout = &Layout::horizontal([Length(16), Max(26)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let creating = self.extend_vg.is_none();
+        for (area, label, input, focus, editable) in [
+            (name_area, "name:", &self.name, Focus::Name, creating),
+            (
+                extent_area,
+                "extent size:",
+                &self.extent_size,
+                Focus::ExtentSize,
+                creating,
+            ),
+            (
+                copies_area,
+                "metadata copies:",
+                &self.copies,
+                Focus::Copies,
+                true,
+            ),
+            (
+                align_area,
+                "data alignment:",
+                &self.alignment,
+                Focus::Alignment,
+                true,
+            ),
+        ] {
+            let [label_area, input_area] = h_layout.areas(area);
+            frame.render_widget(Paragraph::new(label).style(label_style), label_area);
+            if !editable {
+                frame.render_widget(Paragraph::new("-").style(value_style), input_area);
+                continue;
+            }
+            frame.render_widget(
+                Paragraph::new(
+                    Text::from(input.value.clone()).fg(self.colors.selected_column_style_fg),
+                )
+                .style(input_style),
+                input_area,
+            );
+            if self.focus == focus {
+                frame.set_cursor_position(Position::new(
+                    input_area.x + (input.pos as u16),
+                    input_area.y,
+                ));
+            }
+        }
+
+        let h_layout = &Layout::horizontal([Length(16), Length(12)])
+            .horizontal_margin(1)
+            .spacing(1);
+        let [label_area, opt_area] = h_layout.areas(alloc_area);
+        frame.render_widget(
+            Paragraph::new("alloc policy:").style(label_style),
+            label_area,
+        );
+        if creating {
+            let opts = AllocPolicy::VG.map(|alloc| alloc.text());
+            render_dropdown(
+                frame,
+                opt_area,
+                &opts,
+                &mut self.alloc_state,
+                self.focus == Focus::Alloc,
+                &self.colors,
+            );
+        } else {
+            frame.render_widget(Paragraph::new("-").style(value_style), opt_area);
+        }
+
+        frame.render_widget(
+            Paragraph::new("Select devices, new ones are initialized as pvs:")
+                .style(Style::new().fg(self.colors.block_border)),
+            dev_sel_label,
+        );
+        let dev_focus = match self.focus {
+            Focus::DevAvail => Some(PvList::Avail),
+            Focus::DevSelected => Some(PvList::Selected),
+            _ => None,
+        };
+        self.dev_select
+            .render(frame, dev_sel_area, dev_focus, &self.colors);
+
+        self.render_info(frame, info_area);
+
+        if self.popup_save {
+            self.render_popup_save(frame, rect);
+        }
+    }
+
+    //
+    // Selected devices, the commands run and devices left out with the reason.
+    //
+    fn render_info(&self, frame: &mut Frame, area: Rect) {
+        let label = |s: &str| Span::styled(format!("{s:<16}"), Style::new().fg(self.colors.row_fg));
+        let value = |s: String| Span::styled(s, Style::new().fg(self.colors.header_fg));
+        let dim = Style::new().fg(self.colors.row_fg);
+
+        let mut lines = Vec::new();
+        let selected = &self.dev_select.selected;
+        if !selected.is_empty() {
+            let total: u64 = selected.iter().map(|name| self.dev_size(name)).sum();
+            let n = selected.len();
+            lines.push(Line::from(vec![
+                label("size"),
+                value(format!(
+                    "{} on {n} device{}",
+                    fmt_size(total),
+                    if n == 1 { "" } else { "s" }
+                )),
+            ]));
+        }
+        let pv = self.pv_create().unwrap_or_default();
+        for dev in self.new_pvs() {
+            lines.push(Line::from(vec![
+                label("command"),
+                value(format!(
+                    "pvcreate {}",
+                    lvm::pvcreate_args(dev, &pv).join(" ")
+                )),
+            ]));
+        }
+        let command = match (&self.extend_vg, self.vg_create()) {
+            (Some(vg_name), _) => Some(format!("vgextend {vg_name} {}", selected.join(" "))),
+            (None, Some(vg)) => Some(format!(
+                "vgcreate {}",
+                lvm::vgcreate_args(&self.name.value, selected, &vg).join(" ")
+            )),
+            (None, None) => None,
+        };
+        if let Some(command) = command.filter(|_| !selected.is_empty()) {
+            lines.push(Line::from(vec![label("command"), value(command)]));
+        }
+        if !self.new_pvs().is_empty() {
+            lines.push(Line::styled(
+                "pvcreate wipes the start of the device.",
+                Style::new().fg(tailwind::AMBER.c400),
+            ));
+        }
+        for dev in self.unavailable() {
+            lines.push(Line::from(vec![
+                label("not offered"),
+                Span::styled(
+                    format!(
+                        "{} {} {}: {}",
+                        dev.name,
+                        fmt_size(dev.size),
+                        dev.model,
+                        dev.used_by.as_deref().unwrap_or_default()
+                    ),
+                    dim,
+                ),
+            ]));
+        }
+        if let Err(e) = self.check() {
+            lines.push(Line::styled(e, Style::new().fg(tailwind::RED.c400).bold()));
+        }
+
+        let para = Paragraph::new(lines).block(Block::default().padding(Padding::horizontal(1)));
+        frame.render_widget(para, area);
+    }
+
+    fn render_popup_save(&self, frame: &mut Frame, rect: &Rect) {
+        let popup_area = Rect {
+            x: rect.width / 4,
+            y: rect.height / 3,
+            width: rect.width / 2,
+            height: rect.height / 2,
+        };
+        let s1 = Style::new().white().bold();
+        let devs = self.dev_select.selected.join(", ");
+
+        let (title, mut spans) = match &self.extend_vg {
+            Some(vg_name) => (
+                format!("Extend {vg_name}"),
+                vec![
+                    Span::from("You are about to add "),
+                    Span::from(devs).style(s1),
+                    Span::from(" to '"),
+                    Span::from(vg_name.clone()).style(s1),
+                    Span::from("'."),
+                ],
+            ),
+            None => (
+                format!("Create {}", self.name.value),
+                vec![
+                    Span::from("You are about to create '"),
+                    Span::from(self.name.value.clone()).style(s1),
+                    Span::from("' on "),
+                    Span::from(devs).style(s1),
+                    Span::from("."),
+                ],
+            ),
+        };
+        let new_pvs = self.new_pvs();
+        if !new_pvs.is_empty() {
+            spans.push(Span::from(" Data on "));
+            spans.push(
+                Span::from(
+                    new_pvs
+                        .iter()
+                        .map(|s| s.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                )
+                .style(s1),
+            );
+            spans.push(Span::from(" is lost."));
+        }
+
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(Text::from(Line::from(spans)))
+            .title(title);
+        frame.render_widget(popup, popup_area);
+    }
+}
+
+//
+// Confirm vgreduce of a pv. Only asked for pvs without allocated extents, the
+// pv is an orphan after.
+//
+pub struct VgReducePopup {
+    vg_name: String,
+    pv_name: String,
+    pv_size: u64,
+    colors: Colors,
+    lvm: Arc<dyn LvmBackend>,
+    lvm_changed_flag: bool,
+}
+
+impl VgReducePopup {
+    pub fn new(pv: &LvmPVData, colors: Colors, lvm: Arc<dyn LvmBackend>) -> Self {
+        Self {
+            vg_name: pv.vg_name.clone(),
+            pv_name: pv.pv_name.clone(),
+            pv_size: pv.pv_size,
+            colors,
+            lvm,
+            lvm_changed_flag: false,
+        }
+    }
+
+    pub fn lvm_changed(&self) -> bool {
+        self.lvm_changed_flag
+    }
+
+    //
+    // Done (true) when reduced or cancelled, vgreduce errors are passed on.
+    //
+    pub fn handle_events(&mut self, key: &KeyEvent) -> Result<bool, LvmError> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Esc => {
+                STATUS.lock().unwrap().set_status("Vg reduce cancelled.");
+                return Ok(true);
+            }
+            KeyCode::Enter => {
+                self.lvm
+                    .reduce_vg(&self.vg_name, std::slice::from_ref(&self.pv_name))?;
+                self.lvm_changed_flag = true;
+                STATUS
+                    .lock()
+                    .unwrap()
+                    .set_status(&format!("Removed {} from {}", self.pv_name, self.vg_name));
+                return Ok(true);
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let popup_area = Rect {
+            x: area.width / 4,
+            y: area.height / 3,
+            width: area.width / 2,
+            height: area.height / 2,
+        };
+        let s1 = Style::new().white().bold();
+
+        let popup = ConfPopup::new(self.colors.clone())
+            .content(Text::from(Line::from(vec![
+                Span::from("You are about to remove pv '"),
+                Span::from(self.pv_name.clone()).style(s1),
+                Span::from(format!("' ({}) from '", fmt_size(self.pv_size))),
+                Span::from(self.vg_name.clone()).style(s1),
+                Span::from("'. It stays a pv, in no vg."),
+            ])))
+            .title(format!(" Reduce {} ", self.vg_name));
+        frame.render_widget(popup, popup_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::lvm::{LvmBackend, fake::FakeBackend};
+    use crate::lvmapp::vgcreateview::VgCreateView;
+
+    #[test]
+    fn test_vg_create_check() {
+        let lvm: Arc<dyn LvmBackend> = Arc::new(FakeBackend::demo());
+        let mut view = VgCreateView::new(lvm.clone());
+        view.fetch_data().unwrap();
+        // sdg is an orphan pv, nvme0n1 partitioned and sdj has a filesystem
+        assert_eq!(view.dev_select.avail, ["/dev/sdg", "/dev/sdh", "/dev/sdi"]);
+        let left_out: Vec<&str> = view
+            .unavailable()
+            .iter()
+            .map(|dev| dev.name.as_str())
+            .collect();
+        assert_eq!(left_out, ["/dev/nvme0n1", "/dev/sdj"]);
+
+        assert_eq!(view.check().unwrap_err(), "Name is empty.");
+        view.name.value = "vgroot".to_string();
+        assert!(view.check().unwrap_err().contains("exists already"));
+        view.name.value = "vgnew".to_string();
+        view.extent_size.value = "3M".to_string();
+        assert!(view.check().unwrap_err().contains("power of 2"));
+        view.extent_size.value = "32m".to_string();
+        view.copies.value = "3".to_string();
+        assert!(view.check().unwrap_err().contains("0 to 2"));
+        view.copies.value = "2".to_string();
+        view.alignment.value = "6".to_string();
+        assert!(view.check().unwrap_err().contains("4K"));
+        view.alignment.value = "1M".to_string();
+        assert_eq!(view.check().unwrap_err(), "Select the devices to use.");
+
+        // The orphan is used as it is, sdh gets pvcreate'd
+        view.dev_select.avail.retain(|dev| dev == "/dev/sdi");
+        view.dev_select.selected = vec!["/dev/sdg".to_string(), "/dev/sdh".to_string()];
+        view.check().unwrap();
+        assert_eq!(view.new_pvs(), ["/dev/sdh"]);
+        view.save_job().run().unwrap();
+        let vg = lvm.get_vg_info("vgnew").unwrap();
+        assert_eq!(vg.pv_count, 2);
+        assert_eq!(vg.attr, "wz--n-");
+
+        // Extend takes no name
+        let mut view = VgCreateView::new(lvm.clone()).extend("vgnew");
+        view.fetch_data().unwrap();
+        assert_eq!(view.dev_select.avail, ["/dev/sdi"]);
+        assert_eq!(view.check().unwrap_err(), "Select the devices to use.");
+        assert!(
+            VgCreateView::new(lvm)
+                .extend("vgnone")
+                .fetch_data()
+                .is_err()
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use Constraint::{Length, Min};
 use ratatui::{
     Frame,
@@ -11,10 +13,15 @@ use ratatui::{
 };
 
 use crate::{
-    lvm::{self, LvmLvData, LvmVgData},
+    lvm::{self, LvAttr, LvmBackend, LvmError, LvmLvData, LvmPVData, LvmVgData, VgAttr},
     lvmapp::{
-        View, ViewType,
+        STATUS, View, ViewType, attrbadge, cacheview,
+        extentmap::{self, ExtentMap},
+        raidview,
+        rename::RenameEdit,
         res::{self, Colors},
+        snapview,
+        thinpool::{self, ThinThresholds},
     },
 };
 
@@ -24,29 +31,37 @@ pub struct VgInfoView {
     vg_item: Option<LvmVgData>,
     lv_items: Option<Vec<LvmLvData>>,
     pub pvdev_list: Option<Vec<String>>,
+    pv_items: Option<Vec<LvmPVData>>,
     scroll_state: ScrollbarState,
     colors: Colors,
+    thin_thresholds: ThinThresholds,
+    lvm: Arc<dyn LvmBackend>,
 }
 
 impl View for VgInfoView {
     fn refresh_data(&mut self) {
-        self.fetch_data();
+        if let Err(e) = self.fetch_data() {
+            STATUS
+                .lock()
+                .unwrap()
+                .set_status(&format!("Refresh of {} failed: {e}", self.vg_name));
+        }
     }
 
     fn view_type(&self) -> ViewType {
-        return ViewType::VgInfo;
+        ViewType::VgInfo
     }
 
     fn handle_events(&mut self, _key: &crossterm::event::KeyEvent) -> Result<bool, &'static str> {
         // Noop,
-        return Ok(true);
+        Ok(true)
     }
 }
 
 impl VgInfoView {
-    pub fn new(vg_name: &String) -> Self {
+    pub fn new(vg_name: &str, lvm: Arc<dyn LvmBackend>) -> Self {
         Self {
-            vg_name: vg_name.clone(),
+            vg_name: vg_name.to_string(),
             state: TableState::default()
                 .with_selected(0)
                 .with_selected_cell((0, 0)),
@@ -55,23 +70,71 @@ impl VgInfoView {
             vg_item: None,
             lv_items: None,
             pvdev_list: None,
+            pv_items: None,
+            thin_thresholds: ThinThresholds::default(),
+            lvm,
         }
     }
 
-    pub fn fetch_data(&mut self) {
-        self.vg_item = Some(lvm::get_vg_info(&self.vg_name));
-        self.lv_items = Some(lvm::get_lvinfo_by_vg(&self.vg_name, &lvm::get_lvs()));
-        self.pvdev_list = Some(lvm::find_pvs_by_vg(&self.vg_name, &lvm::get_pvs()));
+    pub fn thin_thresholds(mut self, thin_thresholds: ThinThresholds) -> Self {
+        self.thin_thresholds = thin_thresholds;
+        self
+    }
+
+    //
+    // Fetch all first, on error previous data (if any) is left as is.
+    //
+    pub fn fetch_data(&mut self) -> Result<(), LvmError> {
+        let vg_item = self.lvm.get_vg_info(&self.vg_name)?;
+        let lv_items = lvm::get_lvinfo_by_vg(&self.vg_name, &self.lvm.get_lvs()?);
+        // sort lv data, selection is an index into it. Snapshots follow their
+        // origin, thin lvs their pool.
+        let paths: Vec<Vec<String>> = lv_items.iter().map(|lv| nest_path(lv, &lv_items)).collect();
+        let mut sorted: Vec<(Vec<String>, LvmLvData)> = paths.into_iter().zip(lv_items).collect();
+        sorted.sort_by(|a, b| a.0.cmp(&b.0));
+        let lv_items: Vec<LvmLvData> = sorted.into_iter().map(|(_, lv)| lv).collect();
+        let pv_items: Vec<LvmPVData> = self
+            .lvm
+            .get_pvs()?
+            .into_iter()
+            .filter(|pv| pv.vg_name == self.vg_name)
+            .collect();
+        let pvdev_list = pv_items.iter().map(|pv| pv.pv_name.clone()).collect();
+
+        // Stay on the selected lv, lvs before it may have come or gone. It
+        // may be gone itself, e.g. removed.
+        let selected = self.selected_lv().map(|lv| lv.lv_name.clone());
+        if let Some(i) = selected.and_then(|name| lv_items.iter().position(|lv| lv.lv_name == name))
+        {
+            self.state.select(Some(i));
+        } else if self.state.selected().is_some_and(|i| i >= lv_items.len()) {
+            self.state.select(Some(lv_items.len().saturating_sub(1)));
+        }
+        let i = self.state.selected().unwrap_or(0);
+        self.scroll_state = self.scroll_state.position(i * res::ITEM_HEIGHT);
+        self.vg_item = Some(vg_item);
+        self.lv_items = Some(lv_items);
+        self.pvdev_list = Some(pvdev_list);
+        self.pv_items = Some(pv_items);
+        Ok(())
     }
 
-    pub fn render(&mut self, frame: &mut Frame, inner_layout: &[Rect; 3]) {
+    //
+    // 'rename' is an lv name being edited, shown in place of the selected lv.
+    //
+    pub fn render(
+        &mut self,
+        frame: &mut Frame,
+        inner_layout: &[Rect; 3],
+        rename: Option<&RenameEdit>,
+    ) {
         let vg_info_layout = Layout::horizontal([Length(30), Min(0)]).horizontal_margin(1);
 
         let [vg_info_area, gbar_area] = vg_info_layout.areas(inner_layout[0]);
         self.render_vginfo(frame, vg_info_area);
         self.render_vginfo_usagebar(frame, gbar_area);
 
-        self.render_lvs_table(frame, inner_layout[1]);
+        self.render_lvs_table(frame, inner_layout[1], rename);
         self.render_scrollbar(frame, inner_layout[1]);
 
         self.render_lvs_pvs(frame, inner_layout[2]);
@@ -92,15 +155,44 @@ impl VgInfoView {
     }
 
     fn render_lvs_pvs(&mut self, frame: &mut Frame, area: Rect) {
+        let [segs_area, map_area] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(area);
+        self.render_extent_map(frame, map_area);
+
         // get the selected lv.
-        let i = match self.state.selected() {
-            Some(i) => i,
-            None => 0,
-        };
+        let i = self.state.selected().unwrap_or_default();
 
-        let sel_lv_item = self.lv_items.as_ref().unwrap().get(i).unwrap();
-        let mut lines = Vec::<Line>::new();
-        for seg in &sel_lv_item.lv_segs {
+        let Some(sel_lv_item) = self.lv_items.as_ref().unwrap().get(i) else {
+            // No lvs in vg
+            return;
+        };
+        let mut lines = vec![attrbadge::badges(
+            &LvAttr::decode(&sel_lv_item.attr).flags(),
+            &self.colors,
+        )];
+        if sel_lv_item.segtype == "thin-pool" {
+            lines.extend(thinpool::pool_detail_lines(
+                sel_lv_item,
+                self.lv_items.as_ref().unwrap(),
+                &self.thin_thresholds,
+            ));
+        }
+        if cacheview::is_cached(sel_lv_item) {
+            lines.extend(cacheview::cache_detail_lines(sel_lv_item));
+        }
+        // raid status in place of the image segments, images are listed with it.
+        let segs: &[lvm::LvmlvSegData] = if raidview::is_raid(sel_lv_item) {
+            lines.extend(raidview::raid_detail_lines(
+                sel_lv_item,
+                self.lv_items.as_ref().unwrap(),
+                self.pv_items.as_deref().unwrap_or_default(),
+            ));
+            &[]
+        } else {
+            &sel_lv_item.lv_segs
+        };
+        for seg in segs {
             let line = format!(
                 "pvdev={:<10} start_seg={:<10} seg_size={:<10}",
                 seg.pvdev, seg.pv_start_pe, seg.size_pe
@@ -126,7 +218,26 @@ impl VgInfoView {
                     .borders(Borders::ALL),
             );
 
-        frame.render_widget(para, area);
+        frame.render_widget(para, segs_area);
+    }
+
+    //
+    // Where lvs are on the pvs, selected lv (and its images) highlighted.
+    //
+    fn render_extent_map(&mut self, frame: &mut Frame, area: Rect) {
+        let lvs = self.lv_items.as_ref().unwrap();
+        let highlight = self
+            .state
+            .selected()
+            .and_then(|i| lvs.get(i))
+            .map(|lv| extentmap::lv_and_sub_lvs(&lv.lv_name, lvs))
+            .unwrap_or_default();
+        let pvs = extentmap::extent_map_pvs(self.pv_items.as_ref().unwrap(), lvs);
+
+        let map = ExtentMap::new(self.colors.clone(), pvs)
+            .title(" Extents ")
+            .highlight(highlight);
+        frame.render_widget(map, area);
     }
 
     fn render_vginfo_usagebar(&mut self, frame: &mut Frame, area: Rect) {
@@ -140,12 +251,14 @@ impl VgInfoView {
         let [gbar_area] = layout.areas(area);
 
         let bar_title = format!(" VG: {} ", self.vg_name);
+        let badges = attrbadge::badges(&VgAttr::decode(&lvm_vg_data.attr).flags(), &self.colors);
         // Render a paragraph
         let bar = Gauge::default()
             .block(
                 Block::default()
                     .border_style(Style::new().fg(self.colors.block_border))
                     .title(bar_title)
+                    .title_bottom(badges)
                     .border_type(BorderType::Rounded)
                     .borders(Borders::ALL),
             )
@@ -203,13 +316,7 @@ impl VgInfoView {
         frame.render_widget(para, area);
     }
 
-    fn render_lvs_table(&mut self, frame: &mut Frame, area: Rect) {
-        // sort lv data,
-        self.lv_items
-            .as_deref_mut()
-            .unwrap()
-            .sort_by_key(|item| (item.lv_name).clone());
-
+    fn render_lvs_table(&mut self, frame: &mut Frame, area: Rect, rename: Option<&RenameEdit>) {
         let sb = Block::default()
             .border_style(Style::new().fg(self.colors.block_border))
             .border_type(BorderType::Rounded)
@@ -221,12 +328,15 @@ impl VgInfoView {
             .add_modifier(Modifier::REVERSED)
             .fg(self.colors.selected_row_style_fg);
 
-        let header = ["LV", "size(g)", "attr", "segtype", "uuid"]
+        let header = ["LV", "size(g)", "attr", "segtype", "usage", "uuid"]
             .into_iter()
             .map(Cell::from)
             .collect::<Row>()
             .style(header_style)
             .height(1);
+        let selected = self.state.selected();
+        let lvs = self.lv_items.as_deref().unwrap_or_default();
+        let snap_count = |lv: &LvmLvData| lvs.iter().filter(|s| s.origin == lv.lv_name).count();
         let rows = self
             .lv_items
             .as_ref()
@@ -241,15 +351,43 @@ impl VgInfoView {
                 let gb_conv = 1000.0 * 1000.0 * 1000.0;
                 let size_gb = (data.size as f64) / gb_conv;
                 let size_gb = format!("{:.2}", size_gb);
-                let item: [&str; 5] = [
-                    &data.lv_name,
-                    &size_gb,
-                    &data.attr,
-                    &data.segtype,
-                    &data.uuid,
+                let name = match rename {
+                    Some(rename) if selected == Some(i) => Cell::from(rename.input_line()),
+                    _ if nested_under(data).is_some() => {
+                        let depth = nest_path(data, lvs).len() - 1;
+                        let indent = "  ".repeat(depth - 1);
+                        Cell::from(Text::from(format!("{indent} └ {}", data.lv_name)))
+                    }
+                    _ => Cell::from(Text::from(data.lv_name.clone())),
+                };
+                let usage = match snap_count(data) {
+                    _ if data.segtype == "thin-pool" => {
+                        Cell::from(thinpool::pool_usage_line(data, lvs, &self.thin_thresholds))
+                    }
+                    _ if cacheview::is_cached(data) => {
+                        Cell::from(cacheview::cache_usage_line(data))
+                    }
+                    _ if raidview::is_raid(data) => Cell::from(raidview::raid_status_line(data)),
+                    _ if !data.origin.is_empty() && data.pool_lv.is_empty() => {
+                        Cell::from(snapview::snap_usage_line(data))
+                    }
+                    _ if !data.pool_lv.is_empty() => Cell::from(snapview::usage_bar(
+                        data.data_percent.unwrap_or(0.0),
+                        self.thin_thresholds.data,
+                    )),
+                    0 => Cell::default(),
+                    1 => Cell::from("1 snapshot"),
+                    n => Cell::from(format!("{n} snapshots")),
+                };
+                let item = [
+                    name,
+                    Cell::from(Text::from(size_gb)),
+                    Cell::from(attrbadge::lv_attr_spans(&data.attr)),
+                    Cell::from(Text::from(data.segtype.clone())),
+                    usage,
+                    Cell::from(Text::from(data.uuid.clone())),
                 ];
                 item.into_iter()
-                    .map(|content| Cell::from(Text::from(format!("{content}"))))
                     .collect::<Row>()
                     .style(Style::new().fg(self.colors.row_fg).bg(color))
                     .height(1)
@@ -262,8 +400,9 @@ impl VgInfoView {
                 // + 1 is for padding.
                 Constraint::Min(20),
                 Constraint::Length(8),
-                Constraint::Length(8),
                 Constraint::Length(11),
+                Constraint::Length(11),
+                Constraint::Length(18),
                 Constraint::Min(40),
             ],
         )
@@ -282,7 +421,23 @@ impl VgInfoView {
         frame.render_stateful_widget(t, area, &mut self.state);
     }
 
+    pub fn lvs(&self) -> &[LvmLvData] {
+        self.lv_items.as_deref().unwrap_or_default()
+    }
+
+    pub fn pvs(&self) -> &[LvmPVData] {
+        self.pv_items.as_deref().unwrap_or_default()
+    }
+
+    pub fn selected_lv(&self) -> Option<&LvmLvData> {
+        let i = self.state.selected()?;
+        self.lv_items.as_ref()?.get(i)
+    }
+
     pub fn next_lvrow(&mut self) {
+        if self.lv_items.as_ref().unwrap().is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i >= self.lv_items.as_ref().unwrap().len() - 1 {
@@ -298,6 +453,9 @@ impl VgInfoView {
     }
 
     pub fn previous_lvrow(&mut self) {
+        if self.lv_items.as_ref().unwrap().is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -312,3 +470,38 @@ impl VgInfoView {
         self.scroll_state = self.scroll_state.position(i * res::ITEM_HEIGHT);
     }
 }
+
+//
+// Lv a snapshot or thin lv is listed under, its origin or pool. The cache
+// pool of a cached lv is one of its hidden sub lvs.
+//
+fn nested_under(lv: &LvmLvData) -> Option<&str> {
+    let pool = if lv.segtype == "thin" {
+        &lv.pool_lv
+    } else {
+        &lv.origin
+    };
+    [&lv.origin, pool]
+        .into_iter()
+        .find(|name| !name.is_empty())
+        .map(String::as_str)
+}
+
+//
+// Names from the top lv down to 'lv', e.g. [pool0, thin1, thin1_snap].
+//
+fn nest_path(lv: &LvmLvData, lvs: &[LvmLvData]) -> Vec<String> {
+    let mut path = vec![lv.lv_name.clone()];
+    let mut cur = lv;
+    // Bounded, in case of a loop.
+    while let Some(above) = nested_under(cur)
+        && path.len() < 8
+    {
+        path.insert(0, above.to_string());
+        match lvs.iter().find(|item| item.lv_name == above) {
+            Some(item) => cur = item,
+            None => break,
+        }
+    }
+    path
+}
@@ -2,10 +2,23 @@
 // Test out Rust and ratatui.
 //
 
+pub mod cli;
 pub mod lvm;
 pub mod lvmapp;
+pub mod spec;
 
+use std::path::PathBuf;
 use std::process::{ExitCode, Termination};
+use std::sync::Arc;
+
+use clap::{Parser, ValueEnum};
+
+use crate::cli::Command;
+use crate::lvm::{
+    CmdBackend, LvmBackend, dbus::DbusBackend, fake::FakeBackend, replay::ReplayBackend,
+    watch::LvmWatcher,
+};
+use crate::lvmapp::thinpool::ThinThresholds;
 
 pub enum LinuxExitCode {
     EOk,
@@ -21,16 +34,110 @@ impl Termination for LinuxExitCode {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum BackendKind {
+    // Run lvs, lvcreate etc.
+    Cmd,
+    // In-memory demo lvm, nothing on the system is touched.
+    Fake,
+    // lvmdbusd on the system bus, it does the access checks.
+    Dbus,
+}
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// How to talk to lvm
+    #[arg(long, global = true, value_enum, default_value_t = BackendKind::Cmd)]
+    backend: BackendKind,
+    /// Browse captured vgs, pvs and lvs reports in this directory, read only
+    #[arg(long, global = true, value_name = "PATH")]
+    from_dir: Option<PathBuf>,
+    /// Warn when a thin pool's data usage reaches this percentage
+    #[arg(long, global = true, default_value_t = 80.0)]
+    thin_data_warn: f64,
+    /// Warn when a thin pool's metadata usage reaches this percentage
+    #[arg(long, global = true, default_value_t = 80.0)]
+    thin_meta_warn: f64,
+    /// Refresh when lvm or block devices are changed outside, e.g. in another shell
+    #[arg(long)]
+    watch: bool,
+}
+
 fn main() -> LinuxExitCode {
-    unsafe {
-        let current_uid = nix::libc::geteuid();
-        if current_uid != 0 {
-            println!("Running as user with UID: {}", current_uid);
-            println!("Run as root/sudo! ...");
-            return LinuxExitCode::EErr(1);
+    let args = Args::parse();
+
+    // A capture is of this host's lvm, as its commands report it.
+    if matches!(args.command, Some(Command::Capture { .. }))
+        && (args.from_dir.is_some() || args.backend != BackendKind::Cmd)
+    {
+        eprintln!("Error: capture runs the lvm commands, not with --backend or --from-dir");
+        return LinuxExitCode::EErr(64);
+    }
+
+    let lvm: Arc<dyn LvmBackend> = match (&args.from_dir, args.backend) {
+        (Some(dir), _) => match ReplayBackend::open(dir) {
+            Ok(b) => Arc::new(b),
+            Err(e) => {
+                println!("Failed to read captured lvm output: {}", e);
+                return LinuxExitCode::EErr(1);
+            }
+        },
+        (None, BackendKind::Cmd) => {
+            unsafe {
+                let current_uid = nix::libc::geteuid();
+                if current_uid != 0 {
+                    println!("Running as user with UID: {}", current_uid);
+                    println!("Run as root/sudo! ...");
+                    return LinuxExitCode::EErr(1);
+                }
+            }
+            Arc::new(CmdBackend)
         }
+        (None, BackendKind::Fake) => Arc::new(FakeBackend::demo()),
+        (None, BackendKind::Dbus) => match DbusBackend::system() {
+            Ok(b) => Arc::new(b),
+            Err(e) => {
+                println!("Failed to connect to lvmdbusd: {}", e);
+                return LinuxExitCode::EErr(1);
+            }
+        },
+    };
+
+    if let Some(cmd) = args
+        .command
+        .as_ref()
+        .filter(|cmd| !matches!(cmd, Command::Tui))
+    {
+        return match cli::run(
+            cmd,
+            lvm.as_ref(),
+            &mut std::io::stdin().lock(),
+            &mut std::io::stdout(),
+        ) {
+            Ok(()) => LinuxExitCode::EOk,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                LinuxExitCode::EErr(cli::exit_code(&e))
+            }
+        };
     }
 
+    // Captured output is refreshed when its files are replaced.
+    let start_watcher = || match &args.from_dir {
+        Some(dir) => LvmWatcher::start_on(&[&dir.to_string_lossy()], false),
+        None => LvmWatcher::start(),
+    };
+    let watcher = match args.watch.then(start_watcher).transpose() {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            println!("Failed to watch for lvm changes: {}", e);
+            return LinuxExitCode::EErr(1);
+        }
+    };
+
     let terminal = ratatui::init();
     let result = terminal.size();
     match result {
@@ -46,13 +153,21 @@ fn main() -> LinuxExitCode {
         }
     }
 
-    let app_result = lvmapp::LvmApp::new().run(terminal);
+    let thin_thresholds = ThinThresholds {
+        data: args.thin_data_warn,
+        metadata: args.thin_meta_warn,
+    };
+    let mut app = lvmapp::LvmApp::new(lvm).thin_thresholds(thin_thresholds);
+    if let Some(watcher) = watcher {
+        app = app.watcher(watcher);
+    }
+    let app_result = app.run(terminal);
     ratatui::restore();
     match app_result {
-        Ok(_) => return LinuxExitCode::EOk,
+        Ok(_) => LinuxExitCode::EOk,
         Err(e) => {
             println!("Error: {:#}", e);
-            return LinuxExitCode::EErr(1);
+            LinuxExitCode::EErr(1)
         }
     }
 }
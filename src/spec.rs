@@ -0,0 +1,562 @@
+//
+// Vgs and lvs as they should be, in a YAML or TOML file, for provisioning
+// instead of scripts running lvcreate (plan, apply):
+//
+//   vgs:
+//     - name: vgdata
+//       pvs: [/dev/sdb, /dev/sdc]
+//       lvs:
+//         - name: lvweb
+//           size: 20G
+//           segtype: raid1
+//           mirrors: 1
+//         - name: pool0
+//           size: 50G
+//           segtype: thin-pool
+//         - name: thin_db
+//           size: 100G
+//           segtype: thin
+//           thinpool: pool0
+//
+// Lvs take what the new lv form (F7) takes. The plan only adds: devices
+// become pvs, missing vgs are created and others extended with the pvs not in
+// them yet, missing lvs are created and smaller ones extended. Nothing is
+// removed, shrunk or converted, such differences are noted in the plan.
+//
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::lvm::{
+    self, AllocPolicy, LvmBackend, LvmError, LvmExtraArg, LvmLvData, LvmPvCreate, LvmResize,
+    LvmVgCreate, fmt_size, size_unit_bytes,
+};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spec {
+    #[serde(default)]
+    pub vgs: Vec<VgSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VgSpec {
+    pub name: String,
+    // Pvs or devices to become pvs, needed for a vg to be created.
+    #[serde(default)]
+    pub pvs: Vec<String>,
+    #[serde(default)]
+    pub lvs: Vec<LvSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LvSpec {
+    pub name: String,
+    // '10G', '100%FREE', as for create-lv.
+    pub size: String,
+    #[serde(default = "linear")]
+    pub segtype: String,
+    pub stripes: Option<u32>,
+    pub stripesize: Option<String>,
+    pub mirrors: Option<u32>,
+    pub thinpool: Option<String>,
+    // Pvs to allocate from, any in the vg if none.
+    #[serde(default)]
+    pub pvs: Vec<String>,
+    // Grow the filesystem with the lv when extended.
+    #[serde(default)]
+    pub resizefs: bool,
+}
+
+fn linear() -> String {
+    "linear".to_string()
+}
+
+// What can't be read, the file or a size in it.
+fn invalid(msg: String, line: &str) -> LvmError {
+    LvmError::Parse {
+        msg,
+        line: line.to_string(),
+    }
+}
+
+// Read fine, but not something lvm can be brought to.
+fn unusable(msg: String) -> LvmError {
+    LvmError::Usage(msg)
+}
+
+impl Spec {
+    //
+    // TOML for .toml, else YAML.
+    //
+    pub fn load(path: &Path) -> Result<Self, LvmError> {
+        let text = std::fs::read_to_string(path).map_err(LvmError::Io)?;
+        let toml = path.extension().is_some_and(|ext| ext == "toml");
+        Self::parse(&text, toml)
+    }
+
+    pub fn parse(text: &str, toml: bool) -> Result<Self, LvmError> {
+        let spec = if toml {
+            toml::from_str(text).map_err(|e| invalid(e.message().to_string(), text))
+        } else {
+            serde_yaml::from_str(text).map_err(|e| invalid(e.to_string(), text))
+        }?;
+        Ok(spec)
+    }
+}
+
+//
+// One lvm command of a plan, in the order they are run.
+//
+pub enum Step {
+    PvCreate {
+        dev: String,
+    },
+    VgCreate {
+        vg: String,
+        pvs: Vec<String>,
+    },
+    VgExtend {
+        vg: String,
+        pvs: Vec<String>,
+    },
+    LvCreate {
+        vg: String,
+        lv: String,
+        size: u64,
+        unit: String,
+        segtype: String,
+        pvs: Vec<String>,
+        extra: Vec<LvmExtraArg>,
+    },
+    LvExtend {
+        vg: String,
+        lv: String,
+        resize: LvmResize,
+    },
+}
+
+impl Step {
+    //
+    // As run, e.g. 'vgextend vgdata /dev/sdd'.
+    //
+    pub fn command(&self) -> String {
+        let args = match self {
+            Step::PvCreate { dev } => [
+                vec!["pvcreate".to_string()],
+                lvm::pvcreate_args(dev, &pv_opts()),
+            ]
+            .concat(),
+            Step::VgCreate { vg, pvs } => [
+                vec!["vgcreate".to_string()],
+                lvm::vgcreate_args(vg, pvs, &vg_opts()),
+            ]
+            .concat(),
+            Step::VgExtend { vg, pvs } => {
+                [vec!["vgextend".to_string(), vg.clone()], pvs.clone()].concat()
+            }
+            Step::LvCreate {
+                vg,
+                lv,
+                size,
+                unit,
+                segtype,
+                pvs,
+                extra,
+            } => [
+                vec!["lvcreate".to_string()],
+                lvm::create_lv_args(lv, vg, *size, unit, segtype, pvs, extra),
+            ]
+            .concat(),
+            Step::LvExtend { vg, lv, resize } => [
+                vec!["lvresize".to_string()],
+                lvm::resize_lv_args(vg, lv, resize),
+            ]
+            .concat(),
+        };
+        args.join(" ")
+    }
+
+    pub fn apply(&self, lvm: &dyn LvmBackend) -> Result<String, LvmError> {
+        match self {
+            Step::PvCreate { dev } => lvm.create_pv(dev, &pv_opts()),
+            Step::VgCreate { vg, pvs } => lvm.create_vg(vg, pvs, &vg_opts()),
+            Step::VgExtend { vg, pvs } => lvm.extend_vg(vg, pvs),
+            Step::LvCreate {
+                vg,
+                lv,
+                size,
+                unit,
+                segtype,
+                pvs,
+                extra,
+            } => lvm.create_lv(lv, vg, *size, unit, segtype, pvs, extra),
+            Step::LvExtend { vg, lv, resize } => lvm.resize_lv(vg, lv, resize),
+        }
+    }
+}
+
+// lvm's defaults, a spec has no say in them.
+fn pv_opts() -> LvmPvCreate {
+    LvmPvCreate::default()
+}
+
+fn vg_opts() -> LvmVgCreate {
+    LvmVgCreate {
+        extent_size: None,
+        alloc: AllocPolicy::Unset,
+    }
+}
+
+//
+// What to run to get from lvm as it is to the spec, and what differs but is
+// left alone.
+//
+pub struct Plan {
+    pub steps: Vec<Step>,
+    pub notes: Vec<String>,
+}
+
+// Thin pools are created before the thin lvs in them.
+fn create_order(segtype: &str) -> u8 {
+    match segtype {
+        "thin-pool" => 0,
+        "thin" => 2,
+        _ => 1,
+    }
+}
+
+impl Plan {
+    pub fn new(spec: &Spec, lvm: &dyn LvmBackend) -> Result<Self, LvmError> {
+        let vgs = lvm.get_vgs()?;
+        let pvs = lvm.get_pvs()?;
+        let lvs = lvm.get_lvs()?;
+        let mut pv_creates = Vec::new();
+        let mut vg_steps = Vec::new();
+        let mut lv_creates = Vec::new();
+        let mut lv_extends = Vec::new();
+        let mut notes = Vec::new();
+
+        for vg_spec in &spec.vgs {
+            let vg = &vg_spec.name;
+            lvm::check_vg_name(vg).map_err(|e| unusable(format!("{vg}: {e}")))?;
+            let exists = vgs.contains(vg);
+
+            // Pvs the vg is to get, devices become pvs first.
+            let mut new_pvs = Vec::new();
+            for dev in &vg_spec.pvs {
+                match pvs.iter().find(|pv| &pv.pv_name == dev) {
+                    Some(pv) if &pv.vg_name == vg => continue,
+                    Some(pv) if !pv.vg_name.is_empty() => {
+                        return Err(unusable(format!(
+                            "{dev} is a pv of {}, not {vg}",
+                            pv.vg_name
+                        )));
+                    }
+                    Some(_) => (),
+                    None => pv_creates.push(Step::PvCreate { dev: dev.clone() }),
+                }
+                new_pvs.push(dev.clone());
+            }
+            if !exists && new_pvs.is_empty() {
+                return Err(unusable(format!("{vg} does not exist and has no pvs")));
+            }
+            if exists {
+                for pv in pvs.iter().filter(|pv| &pv.vg_name == vg) {
+                    if !vg_spec.pvs.is_empty() && !vg_spec.pvs.contains(&pv.pv_name) {
+                        notes.push(format!("{vg}: {} is not in the spec, left in", pv.pv_name));
+                    }
+                }
+            }
+            if !exists {
+                vg_steps.push(Step::VgCreate {
+                    vg: vg.clone(),
+                    pvs: new_pvs,
+                });
+            } else if !new_pvs.is_empty() {
+                vg_steps.push(Step::VgExtend {
+                    vg: vg.clone(),
+                    pvs: new_pvs,
+                });
+            }
+
+            for lv_spec in &vg_spec.lvs {
+                let name = format!("{vg}/{}", lv_spec.name);
+                lvm::check_lv_name(&lv_spec.name).map_err(|e| unusable(format!("{name}: {e}")))?;
+                let (size, unit) = lvm::split_size_arg(&lv_spec.size).ok_or_else(|| {
+                    invalid(
+                        format!(
+                            "{name}: size is a number and M, G, T, %FREE or %VG, not '{}'",
+                            lv_spec.size
+                        ),
+                        &lv_spec.size,
+                    )
+                })?;
+                if lv_spec.segtype == "thin" {
+                    check_thin(vg_spec, lv_spec, &unit, &lvs)
+                        .map_err(|e| unusable(format!("{name}: {e}")))?;
+                }
+                let bytes = size_unit_bytes(&unit)
+                    .map(|b| {
+                        b.checked_mul(size).ok_or_else(|| {
+                            invalid(
+                                format!("{name}: size '{}' is too large", lv_spec.size),
+                                &lv_spec.size,
+                            )
+                        })
+                    })
+                    .transpose()?;
+
+                let existing = lvs
+                    .iter()
+                    .find(|lv| &lv.vg_name == vg && lv.lv_name == lv_spec.name);
+                let Some(lv) = existing else {
+                    lv_creates.push(Step::LvCreate {
+                        vg: vg.clone(),
+                        lv: lv_spec.name.clone(),
+                        size,
+                        unit,
+                        segtype: lv_spec.segtype.clone(),
+                        pvs: lv_spec.pvs.clone(),
+                        extra: lv_spec.extra_args(),
+                    });
+                    continue;
+                };
+                if lv.segtype != lv_spec.segtype {
+                    notes.push(format!(
+                        "{name}: is {}, not {} as in the spec, not converted",
+                        lv.segtype, lv_spec.segtype
+                    ));
+                }
+                // Sizes relative to the vg are for creating only.
+                match bytes {
+                    Some(bytes) if bytes > lv.size => lv_extends.push(Step::LvExtend {
+                        vg: vg.clone(),
+                        lv: lv_spec.name.clone(),
+                        resize: LvmResize {
+                            sign: None,
+                            size,
+                            size_unit: unit,
+                            resizefs: lv_spec.resizefs,
+                        },
+                    }),
+                    Some(bytes) if bytes < lv.size => notes.push(format!(
+                        "{name}: is {}, larger than {} in the spec, not shrunk",
+                        fmt_size(lv.size),
+                        lv_spec.size
+                    )),
+                    _ => (),
+                }
+            }
+        }
+
+        lv_creates.sort_by_key(|step| match step {
+            Step::LvCreate { segtype, .. } => create_order(segtype),
+            _ => 1,
+        });
+        let mut steps = pv_creates;
+        steps.extend(vg_steps);
+        steps.extend(lv_creates);
+        steps.extend(lv_extends);
+        Ok(Self { steps, notes })
+    }
+
+    //
+    // The commands, then the notes as comments.
+    //
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for step in &self.steps {
+            text += &step.command();
+            text.push('\n');
+        }
+        for note in &self.notes {
+            text += &format!("# {note}\n");
+        }
+        if self.steps.is_empty() {
+            text += "# Nothing to do.\n";
+        }
+        text
+    }
+}
+
+//
+// A thin lv needs a fixed virtual size and a pool that is there, or is in the
+// spec and created before it.
+//
+fn check_thin(
+    vg_spec: &VgSpec,
+    lv_spec: &LvSpec,
+    unit: &str,
+    lvs: &[LvmLvData],
+) -> Result<(), String> {
+    if unit.starts_with('%') {
+        return Err("the virtual size of a thin lv can not be a percentage".to_string());
+    }
+    let Some(pool) = &lv_spec.thinpool else {
+        return Err("a thin lv needs a thinpool".to_string());
+    };
+    let exists = lvs
+        .iter()
+        .any(|lv| lv.vg_name == vg_spec.name && &lv.lv_name == pool && lv.segtype == "thin-pool");
+    let in_spec = vg_spec
+        .lvs
+        .iter()
+        .any(|lv| &lv.name == pool && lv.segtype == "thin-pool");
+    if !exists && !in_spec {
+        return Err(format!("thin pool {pool} is not in {}", vg_spec.name));
+    }
+    Ok(())
+}
+
+impl LvSpec {
+    fn extra_args(&self) -> Vec<LvmExtraArg> {
+        [
+            ("--stripes", self.stripes.map(|n| n.to_string())),
+            ("--stripesize", self.stripesize.clone()),
+            ("--mirrors", self.mirrors.map(|n| n.to_string())),
+            ("--thinpool", self.thinpool.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(opt, value)| {
+            value.map(|value| LvmExtraArg {
+                opt: opt.to_string(),
+                value,
+            })
+        })
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lvm::{LvmBackend, LvmError, fake::FakeBackend};
+    use crate::spec::{Plan, Spec};
+
+    const SPEC: &str = "
+vgs:
+  - name: vg01
+    pvs: [/dev/sda, /dev/sdc]
+    lvs:
+      - name: lvweb
+        size: 2G
+      - name: lvsmall
+        size: 1G
+        segtype: raid1
+  - name: vgnew
+    pvs: [/dev/sdd]
+    lvs:
+      - name: thin_db
+        size: 20G
+        segtype: thin
+        thinpool: pool0
+      - name: pool0
+        size: 1G
+        segtype: thin-pool
+";
+
+    #[test]
+    fn test_plan() {
+        let gb = 1024 * 1024 * 1024;
+        let fake = FakeBackend::new();
+        fake.add_vg("vg01", &[("/dev/sda", 10 * gb), ("/dev/sdb", 10 * gb)]);
+        fake.add_pv("/dev/sdc", 10 * gb);
+        fake.add_dev("/dev/sdd", 10 * gb, "NEW DISK", None);
+        fake.create_lv("lvweb", "vg01", 1, "G", "linear", &[], &[])
+            .unwrap();
+        fake.create_lv("lvsmall", "vg01", 2, "G", "linear", &[], &[])
+            .unwrap();
+
+        let spec = Spec::parse(SPEC, false).unwrap();
+        let plan = Plan::new(&spec, &fake).unwrap();
+        assert_eq!(
+            plan.text(),
+            "pvcreate /dev/sdd\n\
+             vgextend vg01 /dev/sdc\n\
+             vgcreate vgnew /dev/sdd\n\
+             lvcreate --type thin-pool -L 1G -n pool0 vgnew\n\
+             lvcreate --type thin --thinpool pool0 -V 20G -n thin_db vgnew\n\
+             lvresize -y -L 2G vg01/lvweb\n\
+             # vg01: /dev/sdb is not in the spec, left in\n\
+             # vg01/lvsmall: is linear, not raid1 as in the spec, not converted\n\
+             # vg01/lvsmall: is 2.00G, larger than 1G in the spec, not shrunk\n"
+        );
+
+        // Applied, there is nothing left to do
+        for step in &plan.steps {
+            step.apply(&fake).unwrap();
+        }
+        let plan = Plan::new(&spec, &fake).unwrap();
+        assert!(plan.steps.is_empty());
+        assert!(plan.text().contains("# Nothing to do.\n"));
+        let lvs = fake.get_lvs().unwrap();
+        let lvweb = lvs.iter().find(|lv| lv.lv_name == "lvweb").unwrap();
+        assert_eq!(lvweb.size, 2 * gb);
+        assert!(lvs.iter().any(|lv| lv.lv_name == "thin_db"));
+    }
+
+    #[test]
+    fn test_spec_errors() {
+        let gb = 1024 * 1024 * 1024;
+        let fake = FakeBackend::new();
+        fake.add_vg("vg01", &[("/dev/sda", 10 * gb)]);
+        let plan = |text: &str, toml: bool| {
+            Spec::parse(text, toml).and_then(|spec| Plan::new(&spec, &fake).map(|p| p.text()))
+        };
+
+        // Same in TOML
+        let toml = "[[vgs]]\nname = \"vg01\"\n[[vgs.lvs]]\nname = \"lvdata\"\nsize = \"50%FREE\"\n";
+        assert_eq!(
+            plan(toml, true).unwrap(),
+            "lvcreate --type linear -l 50%FREE -n lvdata vg01\n"
+        );
+
+        // Not read, or read but not doable
+        let usage = |e: LvmError| matches!(e, LvmError::Usage(_));
+        let e = plan("vgs:\n  - name: vg01\n    lvz: []\n", false).unwrap_err();
+        assert!(matches!(e, LvmError::Parse { .. }));
+        assert!(usage(plan("vgs:\n  - name: vg02\n", false).unwrap_err()));
+        let e = plan("vgs:\n  - name: vg02\n    pvs: [/dev/sda]\n", false).unwrap_err();
+        assert!(usage(e));
+        let e = plan(
+            "vgs:\n  - name: vg01\n    lvs:\n      - {name: lv, size: 10Q}\n",
+            false,
+        )
+        .unwrap_err();
+        assert!(e.to_string().contains("vg01/lv: size"));
+        let e = plan(
+            "vgs:\n  - name: vg01\n    lvs:\n      - {name: lv, size: 99999999999T}\n",
+            false,
+        )
+        .unwrap_err();
+        assert!(e.to_string().contains("is too large, at: '99999999999T'"));
+        let e = plan(
+            "vgs:\n  - name: vg01\n    lvs:\n      - {name: thin1, size: 100%FREE, segtype: thin, thinpool: pool0}\n",
+            false,
+        )
+        .unwrap_err();
+        assert!(usage(e));
+        let e = plan(
+            "vgs:\n  - name: vg01\n    lvs:\n      - {name: thin1, size: 1G, segtype: thin, thinpool: pool9}\n",
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(e.to_string(), "vg01/thin1: thin pool pool9 is not in vg01");
+        let e = plan(
+            "vgs:\n  - name: vg01\n    lvs:\n      - {name: thin1, size: 1G, segtype: thin}\n",
+            false,
+        )
+        .unwrap_err();
+        assert!(usage(e));
+        assert!(usage(
+            plan(
+                "vgs:\n  - name: vg01\n    lvs:\n      - {name: lv_rimage_0, size: 1G}\n",
+                false
+            )
+            .unwrap_err()
+        ));
+    }
+}